@@ -0,0 +1,193 @@
+//! Frame-cost benches for the systems that run every frame or scale with
+//! content, so a change that makes them an order of magnitude slower shows
+//! up before a playtest does:
+//!
+//!     cargo bench --bench hot_systems
+//!     cargo bench --bench hot_systems -- --otlp-endpoint localhost:4317
+//!
+//! Each bench builds a realistic world through the headless harness
+//! (`sregame::testing`) and times whole frames of the real game, not the
+//! system in isolation - what matters is what the player waits for. With an
+//! OTLP endpoint (flag or OTEL_EXPORTER_OTLP_ENDPOINT) every sample is also
+//! recorded as `game.bench.duration{bench=...}`, so runs can be trended.
+//!
+//! - `collision.is_walkable`: 100k random lookups on a 200x200 map,
+//!   out-of-bounds included (player.rs asks about the edges constantly).
+//! - `tilemap.spawn_200x200`: entering a 200x200 scene, tile loop and all.
+//! - `npc.proximity_1k`: a walking frame with 1000 NPCs on the map. Every
+//!   NPC's range is checked every frame (check_npc_proximity); this is the
+//!   number a spatial grid has to beat.
+//! - `dialogue.typewriter_5k`: a second of typing into a 5000-char line.
+
+use std::time::{Duration, Instant};
+
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Histogram, MeterProvider as _};
+use sregame::game_state::Scene;
+use sregame::input::GameAction;
+use sregame::rng::GameRng;
+use sregame::testing::{GeneratedTown, TestGame, scratch_dir};
+use sregame::tilemap::{CollisionMap, TileCollision};
+
+fn main() {
+    let recorder = Recorder::from_args();
+
+    collision_lookups(&recorder);
+    big_map_spawn(&recorder);
+    crowded_proximity(&recorder);
+    long_line_typewriter(&recorder);
+
+    recorder.shutdown();
+}
+
+fn collision_lookups(recorder: &Recorder) {
+    let mut rng = GameRng::new(1237);
+    let dice = rng.stream("bench");
+    let mut map = CollisionMap::new(200, 200);
+    for _ in 0..8000 {
+        map.set_tile(dice.below(200) as u32, dice.below(200) as u32, TileCollision::Blocked);
+    }
+    let probes: Vec<(i32, i32)> = (0..100_000)
+        .map(|_| (dice.below(210) as i32 - 5, dice.below(210) as i32 - 5))
+        .collect();
+
+    recorder.bench("collision.is_walkable", 50, || {
+        let walkable = probes.iter().filter(|&&(x, y)| map.is_walkable(x, y)).count();
+        std::hint::black_box(walkable);
+    });
+}
+
+fn big_map_spawn(recorder: &Recorder) {
+    let dir = scratch_dir("bench-spawn");
+    GeneratedTown::new(7, 5).write(&dir, "town_of_endgame");
+    GeneratedTown::new(200, 200).write(&dir, "team_marathon");
+    let mut game = TestGame::new(&dir);
+
+    // Back to the small town between samples, untimed.
+    let samples = (0..10)
+        .map(|_| {
+            game.enter_scene(Scene::TownOfEndgame);
+            time(|| game.enter_scene(Scene::TeamMarathon))
+        })
+        .collect();
+    recorder.record("tilemap.spawn_200x200", samples);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+fn crowded_proximity(recorder: &Recorder) {
+    let dir = GeneratedTown::new(101, 101).npcs(1000).write(scratch_dir("bench-npcs"), "town_of_endgame");
+    let mut game = TestGame::new(&dir);
+    game.step(10);
+
+    // Pace back and forth so ranges keep changing.
+    let mut frame = 0u32;
+    recorder.bench("npc.proximity_1k", 200, || {
+        let (on, off) = if (frame / 60).is_multiple_of(2) {
+            (GameAction::MoveRight, GameAction::MoveLeft)
+        } else {
+            (GameAction::MoveLeft, GameAction::MoveRight)
+        };
+        game.release(off);
+        game.press(on);
+        game.step(1);
+        frame += 1;
+    });
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+fn long_line_typewriter(recorder: &Recorder) {
+    let line = "Error budgets are a tool for balancing reliability and velocity. ".repeat(80);
+    let line: String = line.chars().take(5000).collect();
+    let dir = GeneratedTown::new(7, 5).greeter(line).write(scratch_dir("bench-typewriter"), "town_of_endgame");
+    let mut game = TestGame::new(&dir);
+    game.interact();
+    assert!(game.active_dialogue().is_some(), "the greeter's dialogue should be up");
+
+    recorder.bench("dialogue.typewriter_5k", 20, || game.step(60));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+/// Times benches, prints a summary line each, and exports samples when an
+/// OTLP endpoint was given.
+struct Recorder {
+    otlp: Option<Otlp>,
+}
+
+struct Otlp {
+    duration: Histogram<f64>,
+    meter_provider: opentelemetry_sdk::metrics::SdkMeterProvider,
+    // The exporters run on it; dropped last.
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl Recorder {
+    /// `--otlp-endpoint <url>` (after cargo bench's `--`) or
+    /// OTEL_EXPORTER_OTLP_ENDPOINT, like the game itself. Anything else on
+    /// the command line (cargo passes `--bench`) is ignored.
+    fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let endpoint = args
+            .iter()
+            .position(|arg| arg == "--otlp-endpoint")
+            .and_then(|i| args.get(i + 1).cloned())
+            .or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+            .map(|e| if e.starts_with("http://") || e.starts_with("https://") { e } else { format!("http://{e}") });
+
+        let otlp = endpoint.and_then(|endpoint| {
+            let runtime = tokio::runtime::Runtime::new().ok()?;
+            // The bench machine's profile tells one run's numbers from another's.
+            let profile = sregame::telemetry::SystemProfile::gather(&sregame::telemetry::Host, true, true);
+            match sregame::instrumentation::init_instrumentation(&runtime, &endpoint, None, &profile) {
+                Ok((_, _, _, meter_provider)) => {
+                    eprintln!("🔭 Recording bench results to {endpoint}");
+                    let duration = meter_provider
+                        .meter("sregame.bench")
+                        .f64_histogram("game.bench.duration")
+                        .with_description("Time per iteration of a hot-system bench (see benches/hot_systems.rs)")
+                        .with_unit("s")
+                        .build();
+                    Some(Otlp { duration, meter_provider, _runtime: runtime })
+                }
+                Err(e) => {
+                    eprintln!("⚠️  Not recording bench results: {e}");
+                    None
+                }
+            }
+        });
+        Self { otlp }
+    }
+
+    fn bench(&self, name: &str, iterations: u32, mut run: impl FnMut()) {
+        self.record(name, (0..iterations).map(|_| time(&mut run)).collect());
+    }
+
+    fn record(&self, name: &str, mut samples: Vec<Duration>) {
+        if let Some(otlp) = &self.otlp {
+            for sample in &samples {
+                otlp.duration.record(sample.as_secs_f64(), &[KeyValue::new("bench", name.to_string())]);
+            }
+        }
+        samples.sort();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        println!(
+            "{name:<24} median {:>10.3?}  p95 {:>10.3?}  ({} iterations)",
+            percentile(50),
+            percentile(95),
+            samples.len(),
+        );
+    }
+
+    fn shutdown(self) {
+        if let Some(otlp) = self.otlp
+            && let Err(e) = otlp.meter_provider.shutdown()
+        {
+            eprintln!("⚠️  Bench metrics export failed: {e}");
+        }
+    }
+}
+
+fn time(run: impl FnOnce()) -> Duration {
+    let started = Instant::now();
+    run();
+    started.elapsed()
+}
@@ -11,6 +11,14 @@ use std::path::Path;
 const MAPS_DIR: &str = "assets/data/maps";
 const CHARACTERS_DIR: &str = "assets/textures/characters";
 const TILESETS_DIR: &str = "assets/textures/tilesets";
+const TEXTURES_DIR: &str = "assets/textures";
+/// Optional: without it the game draws its own (shadow.rs).
+const SHADOW_TEXTURE: &str = "shadow.png";
+/// Optional: without it button prompts are text chips (glyphs.rs).
+const GLYPH_ATLAS: &str = "ui/input_glyphs.png";
+const FONTS_DIR: &str = "assets/fonts";
+/// Optional: without it bold dialogue markup is the regular face (dialogue.rs).
+const BOLD_FONT: &str = "dialogue_bold.ttf";
 
 /// Sorted file stems with the given extension. Sorted so the generated code
 /// (and thus the binary) is deterministic regardless of directory order.
@@ -65,6 +73,27 @@ fn main() {
         code.push_str("];\n\n");
     }
 
+    let shadow = Path::new(&manifest_dir).join(TEXTURES_DIR).join(SHADOW_TEXTURE);
+    if shadow.exists() {
+        writeln!(code, "pub static SHADOW_TEXTURE: Option<&str> = Some(\"textures/{SHADOW_TEXTURE}\");").unwrap();
+    } else {
+        code.push_str("pub static SHADOW_TEXTURE: Option<&str> = None;\n");
+    }
+
+    let glyphs = Path::new(&manifest_dir).join(TEXTURES_DIR).join(GLYPH_ATLAS);
+    if glyphs.exists() {
+        writeln!(code, "pub static GLYPH_ATLAS: Option<&str> = Some(\"textures/{GLYPH_ATLAS}\");").unwrap();
+    } else {
+        code.push_str("pub static GLYPH_ATLAS: Option<&str> = None;\n");
+    }
+
+    let bold = Path::new(&manifest_dir).join(FONTS_DIR).join(BOLD_FONT);
+    if bold.exists() {
+        writeln!(code, "pub static BOLD_FONT: Option<&str> = Some(\"fonts/{BOLD_FONT}\");").unwrap();
+    } else {
+        code.push_str("pub static BOLD_FONT: Option<&str> = None;\n");
+    }
+
     fs::write(Path::new(&out_dir).join("asset_manifest.rs"), code)
         .expect("write asset_manifest.rs");
 
@@ -73,4 +102,6 @@ fn main() {
     println!("cargo::rerun-if-changed={MAPS_DIR}");
     println!("cargo::rerun-if-changed={CHARACTERS_DIR}");
     println!("cargo::rerun-if-changed={TILESETS_DIR}");
+    println!("cargo::rerun-if-changed={TEXTURES_DIR}");
+    println!("cargo::rerun-if-changed={FONTS_DIR}");
 }
@@ -0,0 +1,97 @@
+//! A third-party plugin built only on the public hooks (`sregame::prelude`):
+//! workshop points for exploring and talking.
+//!
+//!     cargo run --example points_plugin
+//!
+//! In a real build the plugin goes on the game's App after
+//! `sregame::add_game`. Here the game runs headless through the test
+//! harness (`sregame::testing`) against the integration-test town, so the
+//! example plays a conversation by itself and prints the scoreboard.
+
+use bevy::prelude::*;
+use sregame::game_state::{Mode, Scene};
+use sregame::input::GameAction;
+use sregame::prelude::*;
+use sregame::testing::TestGame;
+use std::collections::HashSet;
+
+/// Points per thing done.
+const NEW_MAP: u32 = 20;
+const NEW_NPC: u32 = 10;
+/// Lines read at the typewriter's pace, not hurried with Space.
+const LINE_READ: u32 = 1;
+const CONVERSATION_FINISHED: u32 = 5;
+
+pub struct PointsPlugin;
+
+impl Plugin for PointsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Points>().add_systems(Update, award_points);
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Points {
+    pub total: u32,
+    pub log: Vec<String>,
+    maps: HashSet<Scene>,
+    npcs: HashSet<String>,
+}
+
+impl Points {
+    fn award(&mut self, points: u32, why: String) {
+        self.total += points;
+        self.log.push(format!("+{points:<3} {why}"));
+    }
+}
+
+fn award_points(
+    mut maps: MessageReader<MapChanged>,
+    mut talks: MessageReader<NpcInteracted>,
+    mut lines: MessageReader<DialogueLineShown>,
+    mut ended: MessageReader<DialogueEnded>,
+    mut points: ResMut<Points>,
+) {
+    for change in maps.read() {
+        if points.maps.insert(change.to) {
+            points.award(NEW_MAP, format!("found {:?}", change.to));
+        }
+    }
+    for talk in talks.read() {
+        if points.npcs.insert(talk.id.clone()) {
+            points.award(NEW_NPC, format!("met {}", talk.name));
+        }
+    }
+    for line in lines.read().filter(|line| !line.skipped) {
+        points.award(LINE_READ, format!("read line {} of {}", line.index, line.id));
+    }
+    for ended in ended.read().filter(|ended| ended.outcome == DialogueEndOutcome::Completed) {
+        points.award(CONVERSATION_FINISHED, format!("finished {}", ended.id));
+    }
+}
+
+fn main() {
+    let mut game = TestGame::with_plugins(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"), |app| {
+        sregame::add_game(app);
+        app.add_plugins(PointsPlugin);
+    });
+
+    // Isabella is one tile north of the spawn point: talk, let each line
+    // type out, and read to the end.
+    game.interact();
+    for _ in 0..20 {
+        if game.current_state().mode != Some(Mode::Dialogue) {
+            break;
+        }
+        game.step(90);
+        game.tap(GameAction::Advance);
+    }
+    game.step(2);
+    game.enter_scene(Scene::TeamMarathon);
+
+    let points = game.app_mut().world().resource::<Points>();
+    for entry in &points.log {
+        println!("{entry}");
+    }
+    println!("total: {}", points.total);
+}
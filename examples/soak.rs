@@ -0,0 +1,418 @@
+//! Soak test: the shipped game, headless, played through over and over to
+//! shake out what a playtest is too short to see - entity leaks, a
+//! conversation that never ends, an NPC nobody can reach, a panic on the
+//! fortieth scene load.
+//!
+//!     cargo run --release --example soak -- --iterations 50
+//!     cargo run --release --example soak -- --iterations 500 --otlp-endpoint localhost:4317
+//!
+//! Each iteration starts a new game (game_state::NewGameRequest, as the
+//! kiosk does) and visits every scene, arriving where a door would put the
+//! player. In each it walks up to every NPC - routed by
+//! `CollisionMap::find_path_to` around the other NPCs and the exits, driven
+//! by the same key presses a player would make - faces them, presses E and
+//! advances the conversation to its end. Then it resets and checks the
+//! entity census against the iteration's start (`LeakCheck`).
+//!
+//! Every iteration's wall-clock time is printed, then a summary. The first
+//! failure - a leak, a panic, an NPC out of reach or one that won't stop
+//! talking - ends the run with its iteration, scene and NPC, and a nonzero
+//! exit. With an OTLP endpoint (flag or OTEL_EXPORTER_OTLP_ENDPOINT) the
+//! game's own spans and metrics are exported, one `game_session` per
+//! iteration, which makes this a load generator for a collector.
+
+use std::collections::HashMap;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use bevy::prelude::*;
+use clap::Parser;
+use sregame::game_state::{Mode, NewGameRequest, Scene};
+use sregame::input::GameAction;
+use sregame::instrumentation::TelemetryProviders;
+use sregame::map_data::{MapData, scene_from_str, tile_to_world, world_to_tile};
+use sregame::npc::{InRange, Npc};
+use sregame::player::{Player, logical_position};
+use sregame::testing::{FRAME, LeakCheck, TestGame};
+use sregame::tilemap::{CollisionMap, MapExits, PendingArrival, scene_config};
+
+/// Every scene, in the order an iteration visits them.
+const SCENES: [Scene; 8] = [
+    Scene::TownOfEndgame,
+    Scene::TeamMarathon,
+    Scene::TeamMarathonRetro,
+    Scene::TeamDisco,
+    Scene::TeamInferno,
+    Scene::MahoganyRow,
+    Scene::Intro,
+    Scene::End,
+];
+
+/// How long walking up to one NPC may take, in frames, before they count
+/// as out of reach.
+const WALK_LIMIT: u32 = 60 * 60;
+
+/// Advance presses one conversation may take before it counts as stuck.
+const ADVANCE_LIMIT: u32 = 200;
+
+/// Frames of quiet after a reset, for toasts and emotes from the last
+/// iteration to expire before the census.
+const SETTLE_FRAMES: u32 = 10 * 60;
+
+/// How close to a waypoint, in pixels, counts as there on each axis.
+const SLACK: f32 = 2.0;
+
+#[derive(Parser, Debug)]
+#[command(about = "Play the whole game headless, repeatedly, checking for leaks and panics")]
+struct Args {
+    /// Times to play every scene through.
+    #[arg(long, default_value_t = 10)]
+    iterations: u32,
+
+    /// Export the game's telemetry here while soaking (host:port or URL;
+    /// default: OTEL_EXPORTER_OTLP_ENDPOINT).
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+}
+
+/// Where the soak was, for blaming a failure.
+#[derive(Debug, Default)]
+struct Progress {
+    scene: Option<Scene>,
+    npc: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    conversations: u32,
+    frames: u32,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let content = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/data");
+    let arrivals = arrivals(&content.join("maps"));
+
+    let endpoint = args
+        .otlp_endpoint
+        .or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+        .map(|e| if e.starts_with("http://") || e.starts_with("https://") { e } else { format!("http://{e}") });
+    let mut otlp = None;
+    let mut game = match endpoint.as_deref().map(start_otlp) {
+        Some(Ok((runtime, tracer, meter, providers))) => {
+            eprintln!("🔭 Exporting soak telemetry to {}", endpoint.as_deref().unwrap_or_default());
+            otlp = Some((runtime, providers.clone()));
+            TestGame::with_telemetry(&content, tracer, meter, providers)
+        }
+        Some(Err(e)) => {
+            eprintln!("❌ Couldn't start telemetry: {e}");
+            return ExitCode::FAILURE;
+        }
+        None => TestGame::new(&content),
+    };
+    new_game(&mut game);
+
+    let mut timings = Vec::new();
+    let mut failure = None;
+    for iteration in 1..=args.iterations {
+        let mut progress = Progress::default();
+        let mut tally = Tally::default();
+        let started = Instant::now();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let leaks = LeakCheck::start(&game);
+            play_through(&mut game, &arrivals, &mut progress, &mut tally)?;
+            progress = Progress::default();
+            new_game(&mut game);
+            leaks.assert_no_growth(&game);
+            Ok::<_, String>(())
+        }))
+        .unwrap_or_else(|panic| Err(panic_message(panic)));
+        let elapsed = started.elapsed();
+
+        if let Err(reason) = result {
+            failure = Some((iteration, progress, reason));
+            break;
+        }
+        println!(
+            "iteration {iteration:>4}  {elapsed:>10.3?}  {:>4} conversations  {:>6} frames ({:.0}x real time)",
+            tally.conversations,
+            tally.frames,
+            (FRAME * tally.frames).as_secs_f64() / elapsed.as_secs_f64(),
+        );
+        timings.push(elapsed);
+    }
+
+    if !timings.is_empty() {
+        let mut sorted = timings.clone();
+        sorted.sort();
+        let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+        println!(
+            "{} iterations: median {:.3?}  p95 {:.3?}  max {:.3?}",
+            timings.len(),
+            percentile(50),
+            percentile(95),
+            percentile(100),
+        );
+    }
+    if let Some((_runtime, providers)) = otlp {
+        if let Err(e) = providers.tracer.shutdown() {
+            eprintln!("⚠️  Span export failed: {e}");
+        }
+        if let Err(e) = providers.meter.shutdown() {
+            eprintln!("⚠️  Metric export failed: {e}");
+        }
+    }
+
+    match failure {
+        None => ExitCode::SUCCESS,
+        Some((iteration, progress, reason)) => {
+            let scene = progress.scene.map_or("-".to_string(), |scene| format!("{scene:?}"));
+            let npc = progress.npc.as_deref().unwrap_or("-");
+            eprintln!("❌ Soak failed in iteration {iteration} (scene {scene}, NPC {npc}): {reason}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn start_otlp(
+    endpoint: &str,
+) -> anyhow::Result<(
+    tokio::runtime::Runtime,
+    sregame::instrumentation::GameTracer,
+    sregame::instrumentation::GameMeter,
+    TelemetryProviders,
+)> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let profile = sregame::telemetry::SystemProfile::gather(&sregame::telemetry::Host, true, true);
+    let (tracer, meter, tracer_provider, meter_provider) =
+        sregame::instrumentation::init_instrumentation(&runtime, endpoint, None, &profile)?;
+    Ok((runtime, tracer, meter, TelemetryProviders { tracer: tracer_provider, meter: meter_provider }))
+}
+
+/// Where a door into each scene puts the player: the first exit in any
+/// map that leads there. Scenes nobody walks into keep the player where
+/// they stand.
+fn arrivals(maps: &Path) -> HashMap<Scene, (u32, u32)> {
+    let mut arrivals = HashMap::new();
+    for scene in SCENES {
+        let Ok(map) = MapData::load_from_dir(maps, scene_config(scene).map_file) else { continue };
+        for exit in &map.exits {
+            if let Some(target) = scene_from_str(&exit.target_scene) {
+                arrivals.entry(target).or_insert((exit.target_spawn_x, exit.target_spawn_y));
+            }
+        }
+    }
+    arrivals
+}
+
+/// A fresh game, settled.
+fn new_game(game: &mut TestGame) {
+    game.app_mut().world_mut().write_message(NewGameRequest { reason: "soak.iteration" });
+    game.step(3);
+    game.step(SETTLE_FRAMES);
+}
+
+fn play_through(
+    game: &mut TestGame,
+    arrivals: &HashMap<Scene, (u32, u32)>,
+    progress: &mut Progress,
+    tally: &mut Tally,
+) -> Result<(), String> {
+    for scene in SCENES {
+        progress.scene = Some(scene);
+        progress.npc = None;
+        enter(game, scene, arrivals, tally)?;
+
+        let mut npcs: Vec<(String, String)> = {
+            let world = game.app_mut().world_mut();
+            let mut query = world.query::<&Npc>();
+            query.iter(world).map(|npc| (npc.id.clone(), npc.name.clone())).collect()
+        };
+        npcs.sort();
+        for (id, name) in npcs {
+            progress.npc = Some(name);
+            // Someone's conversation may have sent the player on.
+            if game.current_state().scene != Some(scene) {
+                enter(game, scene, arrivals, tally)?;
+            }
+            if walk_up_to(game, &id, tally)? {
+                talk(game, &id, tally)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn enter(
+    game: &mut TestGame,
+    scene: Scene,
+    arrivals: &HashMap<Scene, (u32, u32)>,
+    tally: &mut Tally,
+) -> Result<(), String> {
+    if let Some(&(spawn_x, spawn_y)) = arrivals.get(&scene) {
+        game.app_mut().world_mut().insert_resource(PendingArrival { spawn_x, spawn_y });
+    }
+    game.enter_scene(scene);
+    tally.frames += 2;
+    // An arrival cutscene plays before the player can move.
+    finish_conversation(game, tally)?;
+    match game.current_state().scene {
+        Some(now) if now == scene => Ok(()),
+        now => Err(format!("entering {scene:?} ended up in {now:?}")),
+    }
+}
+
+/// The NPC's entity, position and whether they're in talking range.
+fn find_npc(game: &mut TestGame, id: &str) -> Option<(Entity, Vec2, bool)> {
+    let world = game.app_mut().world_mut();
+    let mut npcs = world.query::<(Entity, &Npc, &Transform, Has<InRange>)>();
+    npcs.iter(world)
+        .find(|(_, npc, ..)| npc.id == id)
+        .map(|(entity, _, transform, in_range)| (entity, transform.translation.truncate(), in_range))
+}
+
+/// Walk until the NPC can be talked to: in range, or across a counter
+/// from them. `false` if they've gone (an earlier conversation despawned
+/// them).
+fn walk_up_to(game: &mut TestGame, id: &str, tally: &mut Tally) -> Result<bool, String> {
+    let mut frames = 0;
+    let ready = loop {
+        let Some((entity, npc_pos, in_range)) = find_npc(game, id) else { break false };
+        if in_range {
+            break true;
+        }
+        if frames == WALK_LIMIT {
+            release_movement(game);
+            return Err(format!("couldn't reach them in {WALK_LIMIT} frames"));
+        }
+
+        let world = game.app_mut().world_mut();
+        let mut players = world.query_filtered::<&Transform, With<Player>>();
+        let Ok(here) = players.single(world).map(|player| logical_position(player.translation.truncate())) else {
+            return Err("no player".into());
+        };
+        let mut npcs = world.query::<(Entity, &Transform, &Npc)>();
+        let others: Vec<Vec2> = npcs
+            .iter(world)
+            .filter(|(other, ..)| *other != entity)
+            .map(|(_, transform, _)| transform.translation.truncate())
+            .collect();
+        let exits: Vec<(i32, i32)> = world
+            .get_resource::<MapExits>()
+            .map(|exits| exits.0.iter().map(|exit| (exit.trigger_x as i32, exit.trigger_y as i32)).collect())
+            .unwrap_or_default();
+        let Some(map) = world.get_resource::<CollisionMap>() else { return Err("no collision map".into()) };
+        let tile = |pos: Vec2| world_to_tile(pos, map.width, map.height);
+        let (from, npc_tile) = (tile(here), tile(npc_pos));
+        let others: Vec<(i32, i32)> = others.into_iter().map(tile).collect();
+
+        // Next to them, or a counter's width away with the counter between.
+        let beside = |(x, y): (i32, i32)| {
+            [(0, 1), (0, -1), (-1, 0), (1, 0)].into_iter().any(|(dx, dy)| {
+                (x + dx, y + dy) == npc_tile || ((x + 2 * dx, y + 2 * dy) == npc_tile && map.is_counter(x + dx, y + dy))
+            })
+        };
+        // Around everyone else if there's a way; through them (they may
+        // wander off) if not.
+        let path = map
+            .find_path_to(from, beside, |tile| others.contains(&tile) || exits.contains(&tile))
+            .or_else(|| map.find_path_to(from, beside, |tile| exits.contains(&tile)));
+        let Some(path) = path else {
+            release_movement(game);
+            return Err("no way to them".into());
+        };
+        let heading = match path.get(1) {
+            Some(&(x, y)) => tile_to_world(x as u32, y as u32, map.width, map.height) - here,
+            // Across a counter is as close as it gets.
+            None if npc_tile.0.abs_diff(from.0) + npc_tile.1.abs_diff(from.1) > 1 => break true,
+            None => npc_pos - here,
+        };
+        steer(game, heading);
+        game.step(1);
+        frames += 1;
+    };
+    release_movement(game);
+    tally.frames += frames;
+    Ok(ready)
+}
+
+/// Hold the movement keys that head along `offset`.
+fn steer(game: &mut TestGame, offset: Vec2) {
+    for (action, held) in [
+        (GameAction::MoveRight, offset.x > SLACK),
+        (GameAction::MoveLeft, offset.x < -SLACK),
+        (GameAction::MoveUp, offset.y > SLACK),
+        (GameAction::MoveDown, offset.y < -SLACK),
+    ] {
+        if held {
+            game.press(action);
+        } else {
+            game.release(action);
+        }
+    }
+}
+
+fn release_movement(game: &mut TestGame) {
+    steer(game, Vec2::ZERO);
+}
+
+/// Turn to the NPC, press E, and see the conversation through.
+fn talk(game: &mut TestGame, id: &str, tally: &mut Tally) -> Result<(), String> {
+    let Some((_, npc_pos, _)) = find_npc(game, id) else { return Ok(()) };
+    let Some(player_pos) = game.player_pos() else { return Err("no player".into()) };
+    let offset = npc_pos - player_pos;
+    let turn = if offset.x.abs() > offset.y.abs() {
+        if offset.x > 0.0 { GameAction::MoveRight } else { GameAction::MoveLeft }
+    } else if offset.y > 0.0 {
+        GameAction::MoveUp
+    } else {
+        GameAction::MoveDown
+    };
+    game.press(turn);
+    game.step(1);
+    game.release(turn);
+
+    game.press(GameAction::Interact);
+    game.step(1);
+    game.release(GameAction::Interact);
+    tally.frames += 2;
+    // A busy NPC finishes their step first (npc::BusyBehavior::Wait).
+    let mut waited = 0;
+    while game.current_state().mode != Some(Mode::Dialogue) {
+        if waited == 120 {
+            return Err("pressing E didn't start a conversation".into());
+        }
+        game.step(1);
+        waited += 1;
+    }
+    tally.frames += waited;
+    finish_conversation(game, tally)?;
+    tally.conversations += 1;
+    Ok(())
+}
+
+/// Advance whatever conversation is open to its end.
+fn finish_conversation(game: &mut TestGame, tally: &mut Tally) -> Result<(), String> {
+    let mut presses = 0;
+    while game.current_state().mode == Some(Mode::Dialogue) {
+        if presses == ADVANCE_LIMIT {
+            return Err(format!("conversation still going after {ADVANCE_LIMIT} presses"));
+        }
+        game.tap(GameAction::Advance);
+        presses += 1;
+    }
+    game.step(2);
+    tally.frames += presses * 2 + 2;
+    Ok(())
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".into());
+    format!("panicked: {message}")
+}
@@ -21,14 +21,15 @@ fn main() -> anyhow::Result<()> {
     }
 
     // Initialize telemetry (logs)
-    let Some((logger_provider, runtime)) = sregame::telemetry::init_telemetry(endpoint.clone())? else {
+    let profile = sregame::telemetry::SystemProfile::gather(&sregame::telemetry::Host, true, true);
+    let Some((logger_provider, runtime)) = sregame::telemetry::init_telemetry(endpoint.clone(), &profile, Default::default())? else {
         anyhow::bail!("Telemetry initialization returned None");
     };
 
     info!("🔭 OpenTelemetry initialized");
 
     // Initialize instrumentation (traces and metrics)
-    let (tracer, meter, tracer_provider, meter_provider) = sregame::instrumentation::init_instrumentation(&runtime, &endpoint.clone().unwrap(), None)?;
+    let (tracer, meter, tracer_provider, meter_provider) = sregame::instrumentation::init_instrumentation(&runtime, &endpoint.clone().unwrap(), None, &profile)?;
 
     info!("📊 Instrumentation initialized");
     info!("🎮 Test example started");
@@ -39,10 +40,13 @@ fn main() -> anyhow::Result<()> {
     error!("This is an error message");
 
     // Record some metrics
-    meter.dialogue_reading_speed.record(15.0, &[]);
-    meter.dialogue_reading_speed.record(22.5, &[]);
-    meter.interactions_total.add(3, &[KeyValue::new("type", "npc")]);
-    meter.dialogue_lines_read.add(12, &[]);
+    use sregame::instrumentation::MetricsBundle;
+    let dialogue = sregame::dialogue::DialogueMetrics::new(meter.meter());
+    let interactions = sregame::npc::InteractionMetrics::new(meter.meter());
+    dialogue.reading_speed.record(15.0, &[]);
+    dialogue.reading_speed.record(22.5, &[]);
+    interactions.total.add(3, &[KeyValue::new("type", "npc")]);
+    dialogue.lines_read.add(12, &[]);
 
     // Create a trace span to test traces
     use opentelemetry::trace::Tracer;
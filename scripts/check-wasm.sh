@@ -1,12 +1,29 @@
 #!/usr/bin/env bash
-# Compile gate for the browser build: keeps native-only dependencies (tokio,
+# Gate for the browser build: keeps native-only dependencies (tokio,
 # OTLP/tonic, BRP, std::fs, std::time::Instant) from creeping back into the
-# universal code paths. Run it like the tests - red means a regression.
+# universal code paths, and checks the logic the browser shares with native
+# behaves the same there. Run it like the tests - red means a regression.
 #
 #   ./scripts/check-wasm.sh
 #
-# Requires: rustup target add wasm32-unknown-unknown
+# Checks the game for wasm32, compiles the library's unit tests - the
+# headless logic (dialogue, maps, moods, timings, census) that has no
+# business depending on the platform - and runs tests/web.rs under
+# wasm-bindgen-test-runner (in Node). tests/ otherwise uses the native-only
+# `testing` harness and is skipped.
+#
+# Requires: rustup target add wasm32-unknown-unknown, Node, and
+# wasm-bindgen-cli at the version of wasm-bindgen in Cargo.lock:
+#   cargo install wasm-bindgen-cli --version "$(cargo pkgid wasm-bindgen | sed 's/.*@//')"
 set -euo pipefail
 cd "$(dirname "$0")/.."
 
-exec cargo check --target wasm32-unknown-unknown "$@"
+if ! command -v wasm-bindgen-test-runner >/dev/null; then
+    echo "check-wasm: wasm-bindgen-test-runner not found - install wasm-bindgen-cli (see this script)" >&2
+    exit 1
+fi
+
+cargo check --target wasm32-unknown-unknown --no-default-features "$@"
+cargo check --target wasm32-unknown-unknown --no-default-features --lib --profile test "$@"
+CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+    exec cargo test --target wasm32-unknown-unknown --no-default-features --test web "$@"
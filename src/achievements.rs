@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use crate::game_events::{GameEvent, GameEvents};
+use crate::toast::ShowToast;
+
+/// Achievements: named milestones ("talked_to_everyone"), each earned once.
+/// Earned by sending an `UnlockAchievement` - nothing in the content awards
+/// one yet, so for now that's the developer console's `give achievement`.
+/// A new one is toasted, logged and published to the gameplay event log
+/// (game_events.rs); one already earned is ignored.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Achievements>()
+            .add_message::<UnlockAchievement>()
+            .add_message::<ShowToast>()
+            .add_systems(Update, unlock_achievements);
+    }
+}
+
+/// Earn an achievement now.
+#[derive(Message, Debug, Clone)]
+pub struct UnlockAchievement {
+    pub id: String,
+}
+
+/// The achievements earned so far.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Achievements {
+    earned: BTreeSet<String>,
+}
+
+impl Achievements {
+    pub fn has(&self, id: &str) -> bool {
+        self.earned.contains(id)
+    }
+
+    /// Whether it was newly earned.
+    pub fn unlock(&mut self, id: impl Into<String>) -> bool {
+        self.earned.insert(id.into())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.earned.iter().map(String::as_str)
+    }
+}
+
+fn unlock_achievements(
+    mut requests: MessageReader<UnlockAchievement>,
+    mut achievements: ResMut<Achievements>,
+    mut toasts: MessageWriter<ShowToast>,
+    mut events: Option<ResMut<GameEvents>>,
+) {
+    for request in requests.read() {
+        // Only borrow mutably for a new one, as flags.rs does.
+        if achievements.has(&request.id) {
+            continue;
+        }
+        achievements.unlock(request.id.clone());
+        info!("🏆 Achievement unlocked: {}", request.id);
+        toasts.write(ShowToast::new(format!("Achievement unlocked: {}", request.id)));
+        if let Some(events) = events.as_deref_mut() {
+            events.publish(GameEvent::AchievementUnlocked { id: request.id.clone() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_achievement_is_earned_once() {
+        let mut achievements = Achievements::default();
+        assert!(achievements.unlock("first_steps"));
+        assert!(!achievements.unlock("first_steps"), "already earned");
+        assert!(achievements.has("first_steps"));
+        assert!(!achievements.has("talked_to_everyone"));
+        assert_eq!(achievements.iter().collect::<Vec<_>>(), ["first_steps"]);
+    }
+}
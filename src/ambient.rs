@@ -0,0 +1,251 @@
+use bevy::prelude::*;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::KeyValue;
+use std::time::Duration;
+use crate::assets::GameAssets;
+use crate::culling::Culled;
+use crate::game_state::{Mode, Scene};
+use crate::group_conversation::InGroupConversation;
+use crate::instrumentation::{MetricsBundle, init_metrics};
+use crate::npc::{Busy, PendingInteraction};
+use crate::player::Player;
+use crate::rng::{GameRng, RngStream};
+use crate::settings::GameSettings;
+
+/// Ambient chatter: NPCs with `ambient_lines` in their map entry now and
+/// then float one over their head in a small speech bubble while the
+/// player is within `CHATTER_RANGE` - the town talking to itself, no
+/// dialogue box, no input.
+///
+/// Each NPC keeps its own cooldown (`CHATTER_COOLDOWN`, jittered so a
+/// street doesn't speak in chorus), scaled by
+/// `GameSettings::ambient_chatter` (`--ambient-chatter`, 0 turns it off).
+/// Nothing new starts outside `Mode::Exploring`, and opening any dialogue
+/// clears the bubbles on screen, so chatter never talks over a
+/// conversation or a scripted scene. An NPC that is `Busy` (mid-step, or
+/// held by a scene), has a conversation queued behind it, or is culled
+/// off screen stays quiet, as does one talking in a group
+/// (group_conversation.rs).
+///
+/// Lines are checked against `MAX_AMBIENT_LINE_CHARS` at spawn (tilemap.rs);
+/// overlong ones are content errors and never shown. Every bubble bumps
+/// `game.npc.ambient_lines`, labelled by scene only - never by NPC or line,
+/// which would grow with the content.
+pub struct AmbientChatterPlugin;
+
+impl Plugin for AmbientChatterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>()
+            .init_resource::<GameRng>()
+            .add_systems(
+                Update,
+                (expire_chatter_bubbles, start_ambient_chatter)
+                    .chain()
+                    .run_if(in_state(Mode::Exploring)),
+            )
+            .add_systems(OnEnter(Mode::Dialogue), clear_chatter_bubbles);
+        init_metrics::<AmbientMetrics>(app);
+    }
+}
+
+/// `game.npc.ambient_lines`, by scene.
+#[derive(Resource)]
+pub struct AmbientMetrics {
+    pub lines: Counter<u64>,
+}
+
+impl MetricsBundle for AmbientMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            lines: meter
+                .u64_counter("game.npc.ambient_lines")
+                .with_description("Ambient NPC speech bubbles shown (see ambient.rs)")
+                .build(),
+        }
+    }
+}
+
+/// How close the player must be, in pixels, for an NPC to chatter.
+pub const CHATTER_RANGE: f32 = 300.0;
+
+/// Mean time between one NPC's lines at `ambient_chatter` 1.0.
+pub const CHATTER_COOLDOWN: Duration = Duration::from_secs(12);
+
+/// How long a bubble stays up.
+pub const BUBBLE_SECONDS: f32 = 3.5;
+
+/// An NPC's ambient lines and when it may speak next.
+#[derive(Component, Debug)]
+pub struct AmbientChatter {
+    lines: Vec<String>,
+    cooldown: Timer,
+    last_line: Option<usize>,
+    /// Whether the first cooldown has been drawn.
+    armed: bool,
+}
+
+impl AmbientChatter {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines,
+            cooldown: Timer::default(),
+            last_line: None,
+            armed: false,
+        }
+    }
+
+    /// Restart the cooldown at 0.5-1.5x the mean, divided by `frequency`.
+    fn rearm(&mut self, frequency: f32, rng: &mut RngStream) {
+        self.armed = true;
+        let jitter = 0.5 + rng.unit();
+        let seconds = CHATTER_COOLDOWN.as_secs_f32() * jitter / frequency;
+        self.cooldown = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+
+    /// A random line, never the same one twice in a row.
+    fn pick_line(&mut self, rng: &mut RngStream) -> &str {
+        let count = self.lines.len();
+        let mut index = rng.below(count as u64) as usize;
+        if count > 1 && Some(index) == self.last_line {
+            index = (index + 1) % count;
+        }
+        self.last_line = Some(index);
+        &self.lines[index]
+    }
+}
+
+/// A speech bubble over an NPC, child of that NPC.
+#[derive(Component)]
+pub struct ChatterBubble {
+    lifetime: Timer,
+}
+
+fn expire_chatter_bubbles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bubbles: Query<(Entity, &mut ChatterBubble)>,
+) {
+    for (entity, mut bubble) in &mut bubbles {
+        bubble.lifetime.tick(time.delta());
+        if bubble.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn start_ambient_chatter(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    mut rng: ResMut<GameRng>,
+    player: Query<&Transform, With<Player>>,
+    mut npcs: Query<
+        (Entity, &Transform, &mut AmbientChatter, Option<&Children>),
+        (Without<Busy>, Without<Culled>, Without<Player>, Without<InGroupConversation>),
+    >,
+    bubbles: Query<(), With<ChatterBubble>>,
+    pending: Option<Res<PendingInteraction>>,
+    scene: Option<Res<State<Scene>>>,
+    game_assets: Option<Res<GameAssets>>,
+    metrics: Option<Res<AmbientMetrics>>,
+) {
+    let frequency = settings.ambient_chatter;
+    if frequency <= 0.0 {
+        return;
+    }
+    let Ok(player) = player.single() else { return };
+    let player_pos = player.translation.truncate();
+    let rng = rng.stream("ambient");
+
+    for (entity, transform, mut chatter, children) in &mut npcs {
+        if !chatter.armed {
+            chatter.rearm(frequency, rng);
+        }
+        chatter.cooldown.tick(time.delta());
+        // A finished cooldown waits for the player, so walking up to a
+        // quiet NPC gets a line soon after.
+        if !chatter.cooldown.is_finished()
+            || transform.translation.truncate().distance(player_pos) > CHATTER_RANGE
+            || pending.as_ref().is_some_and(|pending| pending.npc == entity)
+            || children.is_some_and(|children| children.iter().any(|child| bubbles.contains(child)))
+        {
+            continue;
+        }
+
+        let line = chatter.pick_line(rng).to_string();
+        chatter.rearm(frequency, rng);
+        debug!("💬 Ambient line: {line}");
+        spawn_chatter_bubble(&mut commands, entity, line, game_assets.as_deref(), BUBBLE_SECONDS);
+
+        if let Some(metrics) = metrics.as_deref() {
+            let scene = scene.as_ref().map(|s| format!("{:?}", s.get())).unwrap_or_default();
+            metrics.lines.add(1, &[KeyValue::new("game.scene", scene)]);
+        }
+    }
+}
+
+/// Float `line` over `speaker` for `seconds`. Also how group
+/// conversations (group_conversation.rs) speak, so dialogue clears theirs
+/// too.
+pub(crate) fn spawn_chatter_bubble(
+    commands: &mut Commands,
+    speaker: Entity,
+    line: impl Into<String>,
+    game_assets: Option<&GameAssets>,
+    seconds: f32,
+) -> Entity {
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    let bubble = commands
+        .spawn((
+            ChatterBubble {
+                lifetime: Timer::from_seconds(seconds, TimerMode::Once),
+            },
+            Text2d::new(line),
+            TextFont {
+                font: font.into(),
+                font_size: FontSize::Px(14.0),
+                ..default()
+            },
+            TextColor(Color::srgb(0.1, 0.1, 0.15)),
+            TextBackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.85)),
+            // Above the head, higher than an emote (npc.rs), in front
+            // of everything on the NPC.
+            Transform::from_xyz(0.0, 44.0, 0.02),
+        ))
+        .id();
+    commands.entity(speaker).add_child(bubble);
+    bubble
+}
+
+fn clear_chatter_bubbles(mut commands: Commands, bubbles: Query<Entity, With<ChatterBubble>>) {
+    for entity in &bubbles {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_never_repeat_back_to_back_and_cooldowns_scale_with_frequency() {
+        let mut rng = GameRng::new(1);
+        let rng = rng.stream("ambient");
+        let mut chatter = AmbientChatter::new(vec!["wan!".into(), "*sniff*".into()]);
+        let mut previous = chatter.pick_line(rng).to_string();
+        for _ in 0..20 {
+            let line = chatter.pick_line(rng).to_string();
+            assert_ne!(line, previous);
+            previous = line;
+        }
+
+        let mean = CHATTER_COOLDOWN.as_secs_f32();
+        for _ in 0..20 {
+            chatter.rearm(1.0, rng);
+            let seconds = chatter.cooldown.duration().as_secs_f32();
+            assert!((mean * 0.5..=mean * 1.5).contains(&seconds), "{seconds}");
+            chatter.rearm(4.0, rng);
+            assert!(chatter.cooldown.duration().as_secs_f32() <= mean * 1.5 / 4.0);
+        }
+    }
+}
@@ -5,13 +5,18 @@
 //! `fs::read_to_string` map loading both fail there, and the failure mode is
 //! the game hanging in `GameState::Loading` forever. Instead, `build.rs`
 //! scans the asset directories at compile time and embeds map JSON
-//! (`include_str!`) plus sprite/tileset name lists. Native builds use the
+//! (`include_str!`) plus sprite/tileset name lists and whether optional
+//! textures (the drop shadow) shipped. Native builds use the
 //! same manifest so there is exactly one code path; the tests below keep the
 //! manifest honest against the real directories. "Drop a file in, it works"
 //! survives because build.rs re-runs when the asset directories change.
 //!
 //! PNGs themselves are NOT embedded - only their names. Pixel data still
 //! loads through the `AssetServer` (HTTP fetch on wasm, disk read natively).
+//! When each one loads is `assets/data/assets.json`'s call (`AssetPriority`).
+
+use serde::Deserialize;
+use std::collections::HashMap;
 
 include!(concat!(env!("OUT_DIR"), "/asset_manifest.rs"));
 
@@ -21,14 +26,64 @@ pub fn map_json(name: &str) -> Option<&'static str> {
     MAPS.iter().find(|(n, _)| *n == name).map(|(_, json)| *json)
 }
 
-/// Names (file stems) of every shipped map. Currently only the test suites
-/// enumerate maps (runtime lookups go through `map_json`); drop the cfg if a
-/// runtime consumer appears.
-#[cfg(test)]
+/// Names (file stems) of every shipped map - for the test suites and the
+/// story-flag count behind save progress (save.rs).
 pub fn map_names() -> impl Iterator<Item = &'static str> {
     MAPS.iter().map(|(name, _)| *name)
 }
 
+pub const ASSET_PRIORITIES_PATH: &str = "assets/data/assets.json";
+const ASSET_PRIORITIES_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/assets.json"));
+
+/// When an asset loads, from its `"priority"` in `assets/data/assets.json`.
+/// Assets with no entry are `Required`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetPriority {
+    /// Loaded before play starts: `GameState::Loading` waits for it.
+    #[default]
+    Required,
+    /// Starts loading with the rest, but play doesn't wait for it.
+    Background,
+    /// Not loaded until something first asks for it (assets.rs,
+    /// `GameAssets::npc_sprite` and `GameAssets::tileset`).
+    Lazy,
+}
+
+/// One `assets.json` entry.
+#[derive(Debug, Deserialize)]
+struct AssetEntry {
+    priority: AssetPriority,
+}
+
+/// Parse the asset priorities, keyed by path under `assets/`
+/// (`textures/characters/SF_Actor2.png`). Entries for paths that aren't
+/// in `known` are reported - a typo would leave the asset required without
+/// a word - and dropped.
+pub fn parse_asset_priorities(json: &str, known: &[String]) -> (HashMap<String, AssetPriority>, Vec<String>) {
+    let entries: HashMap<String, AssetEntry> = match serde_json::from_str(json) {
+        Ok(entries) => entries,
+        Err(e) => return (HashMap::new(), vec![format!("Failed to parse asset priorities: {e}")]),
+    };
+    let mut problems: Vec<String> = entries
+        .keys()
+        .filter(|path| !known.contains(path))
+        .map(|path| format!("priority for unknown asset {path:?}"))
+        .collect();
+    problems.sort();
+    let priorities = entries
+        .into_iter()
+        .filter(|(path, _)| known.contains(path))
+        .map(|(path, entry)| (path, entry.priority))
+        .collect();
+    (priorities, problems)
+}
+
+/// The shipped asset priorities (see `parse_asset_priorities`).
+pub fn load_asset_priorities(known: &[String]) -> (HashMap<String, AssetPriority>, Vec<String>) {
+    parse_asset_priorities(ASSET_PRIORITIES_JSON, known)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,6 +136,53 @@ mod tests {
         );
     }
 
+    /// The optional shadow texture is listed exactly when it's on disk;
+    /// otherwise assets.rs would wait forever on a file that isn't there
+    /// instead of generating the fallback.
+    #[test]
+    fn manifest_shadow_texture_matches_disk() {
+        let on_disk = std::path::Path::new("assets/textures/shadow.png").exists();
+        assert_eq!(SHADOW_TEXTURE.is_some(), on_disk);
+    }
+
+    /// Same for the button glyph atlas, whose fallback is text.
+    #[test]
+    fn manifest_glyph_atlas_matches_disk() {
+        let on_disk = std::path::Path::new("assets/textures/ui/input_glyphs.png").exists();
+        assert_eq!(GLYPH_ATLAS.is_some(), on_disk);
+    }
+
+    /// And the bold dialogue face, whose fallback is the regular one.
+    #[test]
+    fn manifest_bold_font_matches_disk() {
+        let on_disk = std::path::Path::new("assets/fonts/dialogue_bold.ttf").exists();
+        assert_eq!(BOLD_FONT.is_some(), on_disk);
+    }
+
+    #[test]
+    fn asset_priorities_mix_tiers_and_default_to_required() {
+        let known: Vec<String> = ["a.png", "b.png", "c.png", "d.png"].map(String::from).to_vec();
+        let (priorities, problems) = parse_asset_priorities(
+            r#"{
+                "a.png": { "priority": "required" },
+                "b.png": { "priority": "background" },
+                "c.png": { "priority": "lazy" },
+                "typo.png": { "priority": "lazy" }
+            }"#,
+            &known,
+        );
+        assert_eq!(problems, vec![r#"priority for unknown asset "typo.png""#.to_string()]);
+        assert_eq!(priorities.get("a.png"), Some(&AssetPriority::Required));
+        assert_eq!(priorities.get("b.png"), Some(&AssetPriority::Background));
+        assert_eq!(priorities.get("c.png"), Some(&AssetPriority::Lazy));
+        assert_eq!(priorities.get("d.png").copied().unwrap_or_default(), AssetPriority::Required);
+        assert!(!priorities.contains_key("typo.png"));
+
+        let (priorities, problems) = parse_asset_priorities(r#"{ "a.png": { "priority": "soon" } }"#, &known);
+        assert!(priorities.is_empty());
+        assert!(problems[0].contains("unknown variant"), "{problems:?}");
+    }
+
     /// Every embedded map must parse - a merge that breaks a map's JSON
     /// should fail here, not at scene-transition time in a release build.
     #[test]
@@ -1,41 +1,272 @@
+use bevy::asset::{AssetLoadFailedEvent, LoadState, RenderAssetUsages};
 use bevy::prelude::*;
-use crate::asset_manifest;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crate::asset_manifest::{self, AssetPriority};
+use crate::character_sheet::{
+    CharacterSprites, FRAME_SIZE, SHEET_COLUMNS, SHEET_ROWS, SPRITES_FILE, SPRITES_JSON, SheetOptions,
+    parse_character_sprites,
+};
+use crate::content_pack::{ContentPacks, data_file};
+use crate::content_errors::{ContentErrors, ContentMetrics};
 use crate::game_state::GameState;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub struct AssetsPlugin;
 
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GameAssets>()
+            .init_resource::<ContentErrors>()
             .add_systems(OnEnter(GameState::Loading), (
                 spawn_loading_screen,
                 start_asset_loading,
             ))
+            .add_message::<AssetLoadFailedEvent<Image>>()
             .add_systems(Update, check_asset_loading.run_if(in_state(GameState::Loading)))
+            // Background and lazy images can fail long after loading ends.
+            .add_systems(Update, stand_in_for_failed_images)
             .add_systems(OnExit(GameState::Loading), despawn_loading_screen);
+        crate::instrumentation::init_metrics::<ContentMetrics>(app);
     }
 }
 
+const CHARACTERS_DIR: &str = "textures/characters";
+pub const TILESETS_DIR: &str = "textures/tilesets";
+const PORTRAIT: &str = "textures/portraits/Nature.png";
+const DIALOGUE_FONT: &str = "fonts/dialogue.ttf";
+
+/// Every asset loads in one of three tiers, set per asset in
+/// `assets/data/assets.json` (`AssetPriority`): required assets hold
+/// `GameState::Loading` until they're in, background ones load alongside
+/// play, and lazy character sheets and tilesets wait until a scene first
+/// uses them. A background or lazy image that fails to load is stood in
+/// for by a blank one; a failed required asset keeps the loading screen up.
 #[derive(Resource, Default)]
 pub struct GameAssets {
     pub player_sprite: Handle<Image>,
-    /// Character sprite sheets, keyed by filename stem (e.g. "Nature" for
-    /// `textures/characters/Nature.png`). Names come from the compile-time
-    /// asset manifest so new characters need no Rust changes.
+    /// The sprite registry, `assets/data/sprites.json` (or a content pack's):
+    /// what each sprite name a map uses draws from, and how.
+    pub character_sprites: CharacterSprites,
+    /// Character sprite sheets, keyed by their `character_sprites` name
+    /// (e.g. "Nature" for `textures/characters/Nature.png`). Lazy sheets
+    /// aren't here: look sheets up with `npc_sprite`.
     pub npc_sprites: HashMap<String, Handle<Image>>,
     /// Tileset textures, keyed by filename stem (e.g. "town_tileset" for
     /// `textures/tilesets/town_tileset.png`). Scenes look these up by the
-    /// `tileset_key` in their `SceneConfig` (see `tilemap.rs`).
+    /// `tileset_key` in their `SceneConfig` (see `tilemap.rs`), through
+    /// `tileset`, which knows about lazy ones.
     pub tilesets: HashMap<String, Handle<Image>>,
     pub portrait_nature: Handle<Image>,
+    /// Drop shadow sprite: `textures/shadow.png` when it shipped, else an
+    /// ellipse drawn at startup (`shadow::generated_shadow_image`).
+    pub shadow: Handle<Image>,
+    /// Button glyph atlas (`textures/ui/input_glyphs.png`, laid out as in
+    /// glyphs.rs); None when it didn't ship, and prompts stay text.
+    pub input_glyphs: Option<Handle<Image>>,
     pub dialogue_font: Handle<Font>,
+    /// `{b}` markup in dialogue (dialogue.rs): `fonts/dialogue_bold.ttf`
+    /// when it shipped, else `dialogue_font` again.
+    pub dialogue_font_bold: Handle<Font>,
+    /// What `GameState::Loading` waits on.
+    pub required: Vec<UntypedHandle>,
+    /// Loading, but not waited on; counted on the loading screen.
+    pub background: Vec<UntypedHandle>,
+    /// Paths (under `assets/`) of the lazy images, not loaded yet.
+    pub lazy: HashSet<String>,
     pub loaded: bool,
 }
 
+impl GameAssets {
+    /// How the sprite `name` is drawn: its registry entry's options, the
+    /// defaults for a name the registry doesn't have.
+    pub fn sheet_options(&self, name: &str) -> SheetOptions {
+        self.character_sprites.get(name).map(|sprite| sprite.options).unwrap_or_default()
+    }
+
+    /// The sheet the registry gives the sprite `name`, or None if it has no
+    /// such name. A lazy sheet starts loading here.
+    pub fn npc_sprite(&self, name: &str, asset_server: Option<&AssetServer>) -> Option<Handle<Image>> {
+        let texture = &self.character_sprites.get(name)?.texture;
+        self.image(&self.npc_sprites, name, texture, asset_server)
+    }
+
+    /// Every sprite name `npc_sprite` knows, sorted - for saying what a
+    /// map could have asked for instead.
+    pub fn npc_sprite_names(&self) -> Vec<&str> {
+        self.character_sprites.names()
+    }
+
+    /// The tileset `name` (filename stem), as `npc_sprite`.
+    pub fn tileset(&self, name: &str, asset_server: Option<&AssetServer>) -> Option<Handle<Image>> {
+        self.image(&self.tilesets, name, &format!("{TILESETS_DIR}/{name}.png"), asset_server)
+    }
+
+    /// `name`'s image, at `path` when it's lazy. A lazy image is loaded on
+    /// every lookup: the asset server hands back the handle it already has
+    /// while anything still holds one, so only the first use (or the first
+    /// after a scene let go of it) reads it.
+    fn image(
+        &self,
+        loaded: &HashMap<String, Handle<Image>>,
+        name: &str,
+        path: &str,
+        asset_server: Option<&AssetServer>,
+    ) -> Option<Handle<Image>> {
+        if let Some(handle) = loaded.get(name) {
+            return Some(handle.clone());
+        }
+        if !self.lazy.contains(path) {
+            return None;
+        }
+        asset_server.map(|asset_server| asset_server.load(path.to_string()))
+    }
+
+    /// Required assets loaded, and out of how many.
+    pub fn required_progress(&self, asset_server: &AssetServer) -> (usize, usize) {
+        let done = self.required.iter().filter(|handle| asset_server.is_loaded_with_dependencies(*handle)).count();
+        (done, self.required.len())
+    }
+
+    /// Background assets loaded or given up on, and out of how many.
+    pub fn background_progress(&self, asset_server: &AssetServer) -> (usize, usize) {
+        let done = self
+            .background
+            .iter()
+            .filter(|handle| {
+                asset_server.is_loaded_with_dependencies(*handle)
+                    || matches!(asset_server.load_state(*handle), LoadState::Failed(_))
+            })
+            .count();
+        (done, self.background.len())
+    }
+}
+
+/// Sorts assets into `GameAssets`' tiers as they start loading.
+struct Tiers<'a> {
+    asset_server: &'a AssetServer,
+    priorities: HashMap<String, AssetPriority>,
+    required: Vec<UntypedHandle>,
+    background: Vec<UntypedHandle>,
+    lazy: HashSet<String>,
+    problems: Vec<String>,
+}
+
+impl<'a> Tiers<'a> {
+    fn new(asset_server: &'a AssetServer, priorities: HashMap<String, AssetPriority>) -> Self {
+        Self {
+            asset_server,
+            priorities,
+            required: Vec::new(),
+            background: Vec::new(),
+            lazy: HashSet::new(),
+            problems: Vec::new(),
+        }
+    }
+
+    fn priority(&self, path: &str) -> AssetPriority {
+        self.priorities.get(path).copied().unwrap_or_default()
+    }
+
+    /// Start loading the image at `path` in its tier, or - lazy, and
+    /// `can_wait` - leave it for its first use and return None. What
+    /// play needs from the start can't wait, and loads in the background.
+    fn image(&mut self, path: String, can_wait: bool) -> Option<Handle<Image>> {
+        let mut priority = self.priority(&path);
+        if priority == AssetPriority::Lazy && !can_wait {
+            self.problems
+                .push(format!("{path:?} is needed from the start and can't be lazy - loading it in the background"));
+            priority = AssetPriority::Background;
+        }
+        if priority == AssetPriority::Lazy {
+            self.lazy.insert(path);
+            return None;
+        }
+        let handle: Handle<Image> = self.asset_server.load(path);
+        let tier = match priority {
+            AssetPriority::Required => &mut self.required,
+            _ => &mut self.background,
+        };
+        let untyped = handle.clone().untyped();
+        if !tier.contains(&untyped) {
+            tier.push(untyped);
+        }
+        Some(handle)
+    }
+
+    /// Fonts are always required: text has nothing to stand in for them.
+    fn font(&mut self, path: &str) -> Handle<Font> {
+        if self.priority(path) != AssetPriority::Required {
+            self.problems.push(format!("{path:?} is a font, and fonts are always required"));
+        }
+        let handle: Handle<Font> = self.asset_server.load(path);
+        self.required.push(handle.clone().untyped());
+        handle
+    }
+
+    /// Load each of `stems` in `dir` in its tier, keyed by stem; lazy
+    /// ones are left out.
+    fn sheets(&mut self, dir: &str, stems: &[&str]) -> HashMap<String, Handle<Image>> {
+        stems
+            .iter()
+            .filter_map(|stem| {
+                let handle = self.image(format!("{dir}/{stem}.png"), true)?;
+                Some((stem.to_string(), handle))
+            })
+            .collect()
+    }
+
+    /// Load each registry sheet in its tier, keyed by sprite name; lazy
+    /// ones are left out.
+    fn character_sheets(&mut self, sprites: &CharacterSprites) -> HashMap<String, Handle<Image>> {
+        sprites
+            .0
+            .iter()
+            .filter_map(|(name, sprite)| Some((name.clone(), self.image(sprite.texture.clone(), true)?)))
+            .collect()
+    }
+}
+
+/// Every asset path (under `assets/`) the game loads - what `assets.json`
+/// may give a priority.
+fn shipped_paths() -> Vec<String> {
+    let sheets = asset_manifest::CHARACTER_SPRITES.iter().map(|stem| format!("{CHARACTERS_DIR}/{stem}.png"));
+    let tilesets = asset_manifest::TILESETS.iter().map(|stem| format!("{TILESETS_DIR}/{stem}.png"));
+    let optional = [asset_manifest::SHADOW_TEXTURE, asset_manifest::GLYPH_ATLAS, asset_manifest::BOLD_FONT];
+    sheets
+        .chain(tilesets)
+        .chain([PORTRAIT, DIALOGUE_FONT].map(String::from))
+        .chain(optional.into_iter().flatten().map(String::from))
+        .collect()
+}
+
+/// What stands in for a background or lazy image that failed to load: a
+/// blank character sheet. It draws nothing - a gap, like a missing tileset
+/// (tilemap.rs) - but whatever slices it into frames still can.
+pub fn placeholder_image() -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: SHEET_COLUMNS * FRAME_SIZE,
+            height: SHEET_ROWS * FRAME_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
 #[derive(Component)]
 struct LoadingScreen;
 
+/// The loading screen's count of required assets.
+#[derive(Component)]
+struct RequiredProgress;
+
+/// And of background ones, under it.
+#[derive(Component)]
+struct BackgroundProgress;
+
 fn spawn_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
     info!("Spawning loading screen");
 
@@ -64,6 +295,7 @@ fn spawn_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>)
         ));
 
         parent.spawn((
+            RequiredProgress,
             Text::new("Loading..."),
             TextFont {
                 font: asset_server.load("fonts/dialogue.ttf").into(),
@@ -72,86 +304,159 @@ fn spawn_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>)
             },
             TextColor(Color::srgb(0.7, 0.7, 0.7)),
         ));
-    });
-}
 
-/// Load every PNG named by the compile-time manifest through the asset
-/// server, keyed by filename stem. Discovery happens in build.rs (which
-/// scans the directory), not here at runtime, so this works identically on
-/// native and wasm; new art still needs no Rust changes, just a rebuild.
-fn load_manifest_pngs(
-    stems: &[&str],
-    asset_dir: &str,
-    asset_server: &AssetServer,
-) -> HashMap<String, Handle<Image>> {
-    stems
-        .iter()
-        .map(|stem| {
-            let asset_path = format!("{asset_dir}/{stem}.png");
-            (stem.to_string(), asset_server.load(asset_path))
-        })
-        .collect()
+        parent.spawn((
+            BackgroundProgress,
+            Text::new(""),
+            TextFont {
+                font: asset_server.load("fonts/dialogue.ttf").into(),
+                font_size: FontSize::Vh(16.0 / 10.8),
+                ..default()
+            },
+            TextColor(Color::srgb(0.5, 0.5, 0.5)),
+        ));
+    });
 }
 
 fn start_asset_loading(
     mut game_assets: ResMut<GameAssets>,
     asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut content_errors: ResMut<ContentErrors>,
+    content_metrics: Option<Res<ContentMetrics>>,
+    packs: Option<Res<ContentPacks>>,
 ) {
     info!("Starting asset loading...");
 
-    game_assets.player_sprite = asset_server.load("textures/characters/Amy-Walking.png");
+    let shipped = shipped_paths();
+    let (priorities, problems) = asset_manifest::load_asset_priorities(&shipped);
+    let mut tiers = Tiers::new(&asset_server, priorities);
+    tiers.problems.extend(problems);
 
-    game_assets.npc_sprites = load_manifest_pngs(
-        asset_manifest::CHARACTER_SPRITES,
-        "textures/characters",
-        &asset_server,
-    );
-    game_assets.tilesets = load_manifest_pngs(
-        asset_manifest::TILESETS,
-        "textures/tilesets",
-        &asset_server,
-    );
+    // Characters come from the sprite registry, checked against the
+    // textures build.rs found - and any a content pack brings along with
+    // its own registry; tilesets straight from build.rs's scan. Either way
+    // new art needs no Rust changes.
+    let (path, json) = data_file(packs.as_deref(), SPRITES_FILE, SPRITES_JSON);
+    let json = json.unwrap_or_else(|e| {
+        content_errors.record(&path, format!("{e:#}"), std::time::Duration::ZERO, content_metrics.as_deref());
+        SPRITES_JSON.into()
+    });
+    #[cfg(not(target_arch = "wasm32"))]
+    let textures: Vec<String> = shipped
+        .iter()
+        .cloned()
+        .chain(
+            packs
+                .iter()
+                .flat_map(|packs| packs.files().into_keys())
+                .map(|file| file.to_string_lossy().replace('\\', "/")),
+        )
+        .collect();
+    #[cfg(target_arch = "wasm32")]
+    let textures = shipped.clone();
+    let (character_sprites, problems) = parse_character_sprites(&json, &textures);
+    for problem in problems {
+        content_errors.record(&path, problem, std::time::Duration::ZERO, content_metrics.as_deref());
+    }
+    game_assets.npc_sprites = tiers.character_sheets(&character_sprites);
+    game_assets.tilesets = tiers.sheets(TILESETS_DIR, asset_manifest::TILESETS);
+
+    let player_sheet = format!("{CHARACTERS_DIR}/{}.png", crate::player::PLAYER_SHEET);
+    game_assets.player_sprite = tiers.image(player_sheet, false).unwrap_or_default();
 
+    game_assets.character_sprites = character_sprites;
+
+    game_assets.portrait_nature = tiers.image(PORTRAIT.to_string(), false).unwrap_or_default();
+    game_assets.dialogue_font = tiers.font(DIALOGUE_FONT);
+    game_assets.dialogue_font_bold = match asset_manifest::BOLD_FONT {
+        Some(path) => tiers.font(path),
+        None => game_assets.dialogue_font.clone(),
+    };
+    game_assets.shadow = match asset_manifest::SHADOW_TEXTURE {
+        Some(path) => tiers.image(path.to_string(), false).unwrap_or_default(),
+        None => {
+            info!("No shadow texture shipped - generating one");
+            images.add(crate::shadow::generated_shadow_image())
+        }
+    };
+
+    game_assets.input_glyphs = asset_manifest::GLYPH_ATLAS.and_then(|path| tiers.image(path.to_string(), false));
+
+    for problem in tiers.problems.drain(..) {
+        content_errors.record(
+            asset_manifest::ASSET_PRIORITIES_PATH,
+            problem,
+            std::time::Duration::ZERO,
+            content_metrics.as_deref(),
+        );
+    }
     info!(
-        "Discovered {} character sprites, {} tilesets",
-        game_assets.npc_sprites.len(),
-        game_assets.tilesets.len()
+        "Loading {} assets before play, {} in the background, {} on first use",
+        tiers.required.len(),
+        tiers.background.len(),
+        tiers.lazy.len()
     );
-
-    game_assets.portrait_nature = asset_server.load("textures/portraits/Nature.png");
-    game_assets.dialogue_font = asset_server.load("fonts/dialogue.ttf");
+    game_assets.required = tiers.required;
+    game_assets.background = tiers.background;
+    game_assets.lazy = tiers.lazy;
 
     game_assets.loaded = false;
 }
 
+/// Wait for the required tier only; a generated shadow is in `Assets`
+/// from the start and isn't in any tier.
 fn check_asset_loading(
     mut game_assets: ResMut<GameAssets>,
     asset_server: Res<AssetServer>,
+    mut required_text: Query<&mut Text, (With<RequiredProgress>, Without<BackgroundProgress>)>,
+    mut background_text: Query<&mut Text, (With<BackgroundProgress>, Without<RequiredProgress>)>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     if game_assets.loaded {
         return;
     }
 
-    let all_loaded = asset_server.is_loaded_with_dependencies(&game_assets.player_sprite)
-        && asset_server.is_loaded_with_dependencies(&game_assets.portrait_nature)
-        && asset_server.is_loaded_with_dependencies(&game_assets.dialogue_font)
-        && game_assets
-            .npc_sprites
-            .values()
-            .all(|handle| asset_server.is_loaded_with_dependencies(handle))
-        && game_assets
-            .tilesets
-            .values()
-            .all(|handle| asset_server.is_loaded_with_dependencies(handle));
+    let (required_done, required) = game_assets.required_progress(&asset_server);
+    let (background_done, background) = game_assets.background_progress(&asset_server);
+    for mut text in &mut required_text {
+        text.0 = format!("Loading... {required_done}/{required}");
+    }
+    for mut text in &mut background_text {
+        text.0 = if background == 0 {
+            String::new()
+        } else {
+            format!("In the background: {background_done}/{background}")
+        };
+    }
 
-    if all_loaded {
+    if required_done == required {
         game_assets.loaded = true;
-        info!("All assets loaded successfully!");
+        info!("Required assets loaded ({background_done}/{background} in the background so far)");
         next_state.set(GameState::Playing);
     }
 }
 
+/// A background or lazy image that failed to load becomes
+/// `placeholder_image` - the scene plays on without that art. A required
+/// one can't: `check_asset_loading` never sees it load.
+fn stand_in_for_failed_images(
+    mut failures: MessageReader<AssetLoadFailedEvent<Image>>,
+    game_assets: Res<GameAssets>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for failure in failures.read() {
+        if game_assets.required.iter().any(|handle| handle.id() == failure.id) {
+            error!("Required asset {} failed to load: {}", failure.path, failure.error);
+            continue;
+        }
+        warn!("{} failed to load ({}) - drawing a blank in its place", failure.path, failure.error);
+        if let Err(e) = images.insert(failure.id, placeholder_image()) {
+            warn!("Couldn't stand in for {}: {e}", failure.path);
+        }
+    }
+}
+
 fn despawn_loading_screen(
     mut commands: Commands,
     loading_screen: Query<Entity, With<LoadingScreen>>,
@@ -161,3 +466,66 @@ fn despawn_loading_screen(
     }
     info!("Loading screen despawned");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character_sheet::CharacterSprite;
+
+    #[test]
+    fn a_manifest_mixing_tiers_loads_each_in_its_own() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Font>();
+        let asset_server = app.world().resource::<AssetServer>().clone();
+
+        let priorities = HashMap::from([
+            ("sheets/Background.png".to_string(), AssetPriority::Background),
+            ("sheets/Lazy.png".to_string(), AssetPriority::Lazy),
+            ("portrait.png".to_string(), AssetPriority::Lazy),
+            ("font.ttf".to_string(), AssetPriority::Background),
+        ]);
+        let mut tiers = Tiers::new(&asset_server, priorities);
+        let registry = CharacterSprites(
+            ["Required", "Background", "Lazy"]
+                .into_iter()
+                .map(|name| {
+                    let sprite = CharacterSprite { texture: format!("sheets/{name}.png"), options: default() };
+                    (name.to_string(), sprite)
+                })
+                .collect(),
+        );
+        let sheets = tiers.character_sheets(&registry);
+        let portrait = tiers.image("portrait.png".to_string(), false);
+        let font = tiers.font("font.ttf");
+
+        let mut keys: Vec<&str> = sheets.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["Background", "Required"], "lazy sheets aren't loaded up front");
+        assert!(portrait.is_some(), "can't wait, so loads anyway");
+        assert_eq!(tiers.required, vec![sheets["Required"].clone().untyped(), font.untyped()]);
+        assert_eq!(
+            tiers.background,
+            vec![sheets["Background"].clone().untyped(), portrait.unwrap().untyped()]
+        );
+        assert_eq!(tiers.lazy, HashSet::from(["sheets/Lazy.png".to_string()]));
+        assert_eq!(tiers.problems.len(), 2, "{:?}", tiers.problems);
+
+        let game_assets = GameAssets {
+            character_sprites: registry,
+            npc_sprites: sheets,
+            required: tiers.required,
+            background: tiers.background,
+            lazy: tiers.lazy,
+            ..default()
+        };
+        let image = |name: &str| game_assets.npc_sprite(name, Some(&asset_server));
+        let lazy = image("Lazy").expect("loaded on first use");
+        assert_eq!(image("Lazy").map(|handle| handle.id()), Some(lazy.id()), "and the same one after");
+        assert!(image("Missing").is_none());
+        assert_eq!(game_assets.npc_sprite_names(), ["Background", "Lazy", "Required"]);
+        assert_eq!(game_assets.required_progress(&asset_server).1, 2);
+        assert_eq!(game_assets.background_progress(&asset_server).1, 2);
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use crate::game_state::GameState;
-use crate::player::Player;
+use crate::map_data::{CameraZoneData, world_to_tile};
+use crate::player::{Player, PlayerMovementSet};
 
 /// The game is designed around a 960x540 world-unit view - character/tile
 /// proportions matching the RPGMaker original (~20x11 tiles on screen).
@@ -18,19 +19,53 @@ pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (
-            camera_follow_player,
-        ).run_if(in_state(GameState::Playing)));
+        // The camera is ours rather than the entry point's, so anything
+        // that looks for it (tilemap.rs sets its bounds) finds it whatever
+        // else is in the app.
+        app.add_systems(Startup, spawn_camera)
+            .add_systems(Update, (
+                apply_camera_zones,
+                camera_follow_player,
+            ).chain()
+                // Follow where the player is this frame, not last frame.
+                .after(PlayerMovementSet)
+                .run_if(in_state(GameState::Playing)));
     }
 }
 
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        MainCamera,
+        CameraFollow::default(),
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: bevy::camera::ScalingMode::AutoMin {
+                min_width: VIEW_WIDTH,
+                min_height: VIEW_HEIGHT,
+            },
+            ..OrthographicProjection::default_2d()
+        }),
+        Transform::from_xyz(0.0, 0.0, 999.9),
+    ));
+}
+
 #[derive(Component)]
 pub struct MainCamera;
 
 #[derive(Component)]
 pub struct CameraFollow {
+    /// Exponential catch-up rate, per second: each second the camera closes
+    /// all but e^-smoothness of its remaining distance to the target (at 5.0
+    /// that's 99.3%). See `follow_factor`.
     pub smoothness: f32,
     pub bounds: Option<CameraBounds>,
+    /// Magnification the projection eases toward at the same rate as the
+    /// follow (2.0 shows half the design view). Set by camera zones.
+    pub zoom: f32,
+    /// Jump straight to the player next frame rather than easing there:
+    /// set when the player is put somewhere new (a map's spawn, an exit's
+    /// arrival), so the view doesn't pan across the whole map.
+    pub snap: bool,
 }
 
 impl Default for CameraFollow {
@@ -38,41 +73,108 @@ impl Default for CameraFollow {
         Self {
             smoothness: 5.0,
             bounds: None,
+            zoom: 1.0,
+            snap: false,
         }
     }
 }
 
-/// Half-extents of the current map, centered on the origin. The camera
-/// half-extents are NOT baked in here: the visible area varies with window
-/// size (AutoMin scaling), so clamping reads the projection's computed area
-/// each frame instead of assuming the 960x540 design view.
-#[derive(Clone, Copy)]
+/// The world rectangle the view has to stay inside: the current map, or a
+/// camera zone's bounds. The camera half-extents are NOT baked in here: the
+/// visible area varies with window size (AutoMin scaling), so clamping
+/// reads the projection's computed area each frame instead of assuming the
+/// 960x540 design view.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CameraBounds {
-    pub map_half_width: f32,
-    pub map_half_height: f32,
+    pub min: Vec2,
+    pub max: Vec2,
 }
 
 impl CameraBounds {
+    /// A whole map, centered on the origin.
     pub fn from_map_size(width: f32, height: f32) -> Self {
+        let half = Vec2::new(width, height) / 2.0;
+        Self { min: -half, max: half }
+    }
+
+    /// `[x, y, w, h]` in tiles (RPGMaker orientation, y = 0 at the top) on
+    /// a `map_width` x `map_height` map.
+    pub fn from_tiles([x, y, w, h]: [u32; 4], map_width: u32, map_height: u32) -> Self {
+        const TILE_SIZE: f32 = 48.0;
+        let left = (x as f32 - map_width as f32 / 2.0) * TILE_SIZE;
+        let top = (map_height as f32 / 2.0 - y as f32) * TILE_SIZE;
         Self {
-            map_half_width: width / 2.0,
-            map_half_height: height / 2.0,
+            min: Vec2::new(left, top - h as f32 * TILE_SIZE),
+            max: Vec2::new(left + w as f32 * TILE_SIZE, top),
         }
     }
 
-    /// Keep the view inside the map; if the view is larger than the map on
-    /// an axis, pin the camera to the map's center on that axis.
+    /// Keep the view inside the bounds; if the view is larger than them on
+    /// an axis, pin the camera to their center on that axis.
     fn clamp(&self, mut position: Vec3, camera_half_size: Vec2) -> Vec3 {
-        let max_x = (self.map_half_width - camera_half_size.x).max(0.0);
-        let max_y = (self.map_half_height - camera_half_size.y).max(0.0);
-        position.x = position.x.clamp(-max_x, max_x);
-        position.y = position.y.clamp(-max_y, max_y);
+        let center = (self.min + self.max) / 2.0;
+        let slack = ((self.max - self.min) / 2.0 - camera_half_size).max(Vec2::ZERO);
+        position.x = position.x.clamp(center.x - slack.x, center.x + slack.x);
+        position.y = position.y.clamp(center.y - slack.y, center.y + slack.y);
         position
     }
 }
 
+/// The current map's camera zones (the valid ones - see
+/// `CameraZoneData::problem`). Inserted by tilemap.rs::spawn_map and removed
+/// with the map; `apply_camera_zones` points the camera at whichever the
+/// player is standing in.
+#[derive(Resource, Debug, Clone)]
+pub struct CameraZones {
+    pub map_width: u32,
+    pub map_height: u32,
+    pub zones: Vec<CameraZoneData>,
+}
+
+impl CameraZones {
+    /// The zone holding a tile: the smallest, where zones overlap.
+    pub fn zone_at(&self, tile_x: i32, tile_y: i32) -> Option<&CameraZoneData> {
+        self.zones
+            .iter()
+            .filter(|zone| zone.contains(tile_x, tile_y))
+            .min_by_key(|zone| zone.area())
+    }
+
+    /// Bounds and zoom for a player standing on a tile: its zone's, or the
+    /// whole map at 1x.
+    pub fn view_at(&self, tile_x: i32, tile_y: i32) -> (CameraBounds, f32) {
+        match self.zone_at(tile_x, tile_y) {
+            Some(zone) => (CameraBounds::from_tiles(zone.bounds(), self.map_width, self.map_height), zone.zoom),
+            None => (
+                CameraBounds::from_map_size(self.map_width as f32 * 48.0, self.map_height as f32 * 48.0),
+                1.0,
+            ),
+        }
+    }
+}
+
+/// Swap bounds and zoom as the player crosses zone edges. The swap itself
+/// is instant; the camera eases into it through the follow (position) and
+/// `CameraFollow::zoom` (scale), so crossing an edge pans rather than cuts.
+fn apply_camera_zones(
+    zones: Option<Res<CameraZones>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut camera_query: Query<&mut CameraFollow, With<MainCamera>>,
+) {
+    let (Some(zones), Ok(player_transform), Ok(mut follow)) = (zones, player_query.single(), camera_query.single_mut()) else {
+        return;
+    };
+    let (tile_x, tile_y) = world_to_tile(player_transform.translation.truncate(), zones.map_width, zones.map_height);
+    let (bounds, zoom) = zones.view_at(tile_x, tile_y);
+    if follow.bounds != Some(bounds) || follow.zoom != zoom {
+        debug!("🎥 Camera bounds {:?}..{:?} at {zoom}x", bounds.min, bounds.max);
+        follow.bounds = Some(bounds);
+        follow.zoom = zoom;
+    }
+}
+
 fn camera_follow_player(
-    mut camera_query: Query<(&mut Transform, &CameraFollow, &Projection), (With<MainCamera>, Without<Player>)>,
+    mut camera_query: Query<(&mut Transform, &mut CameraFollow, &mut Projection), (With<MainCamera>, Without<Player>)>,
     player_query: Query<&Transform, With<Player>>,
     time: Res<Time>,
 ) {
@@ -80,24 +182,129 @@ fn camera_follow_player(
         return;
     };
 
-    let Ok((mut camera_transform, follow_config, projection)) = camera_query.single_mut() else {
+    let Ok((mut camera_transform, mut follow_config, mut projection)) = camera_query.single_mut() else {
         return;
     };
 
     let mut target = player_transform.translation;
     target.z = 999.9;
+    let factor = if follow_config.snap {
+        follow_config.snap = false;
+        1.0
+    } else {
+        follow_factor(follow_config.smoothness, time.delta_secs())
+    };
+
+    // Checked before writing so a settled zoom doesn't mark the projection
+    // changed every frame.
+    let target_scale = 1.0 / follow_config.zoom;
+    if let Projection::Orthographic(ortho) = &*projection
+        && ortho.scale != target_scale
+        && let Projection::Orthographic(ortho) = &mut *projection
+    {
+        ortho.scale = ease_scale(ortho.scale, target_scale, factor);
+    }
 
     if let Some(bounds) = follow_config.bounds {
-        let Projection::Orthographic(ortho) = projection else {
+        let Projection::Orthographic(ortho) = &*projection else {
             return;
         };
         target = bounds.clamp(target, ortho.area.half_size());
     }
 
-    let lerp_factor = follow_config.smoothness * time.delta_secs();
-    let lerp_factor = lerp_factor.clamp(0.0, 1.0);
-
-    camera_transform.translation = camera_transform.translation.lerp(target, lerp_factor);
+    camera_transform.translation = camera_transform.translation.lerp(target, factor);
 
     camera_transform.translation.z = 999.9;
 }
+
+/// Fraction of the remaining distance to close this frame. The old
+/// `smoothness * dt` (clamped to 1) is only the first-order term of this:
+/// applied per frame it converges faster at high frame rates, so the camera
+/// felt looser at 30 FPS than at 144. `1 - e^(-smoothness * dt)` composes
+/// exactly - two half-frames equal one full frame - so the follow is the
+/// same on every machine, and for small dt it matches the old factor, which
+/// keeps existing `smoothness` values meaning what they did at 60 FPS.
+pub fn follow_factor(smoothness: f32, dt: f32) -> f32 {
+    1.0 - (-smoothness * dt).exp()
+}
+
+/// One frame of easing toward a zone's zoom, snapping the last sliver so
+/// the projection settles.
+fn ease_scale(scale: f32, target: f32, factor: f32) -> f32 {
+    let eased = scale + (target - scale) * factor;
+    if (target - eased).abs() < 0.001 { target } else { eased }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chase a fixed target for one simulated second at `fps`.
+    fn follow_for_one_second(fps: u32) -> f32 {
+        let dt = 1.0 / fps as f32;
+        let mut position = 0.0_f32;
+        for _ in 0..fps {
+            position += (100.0 - position) * follow_factor(5.0, dt);
+        }
+        position
+    }
+
+    #[test]
+    fn follow_converges_the_same_at_any_frame_rate() {
+        let expected = 100.0 * (1.0 - (-5.0_f32).exp());
+        for fps in [30, 60, 240] {
+            let position = follow_for_one_second(fps);
+            assert!(
+                (position - expected).abs() < 0.01,
+                "{fps} FPS: camera at {position}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn follow_factor_matches_the_old_linear_factor_for_small_steps() {
+        let dt = 1.0 / 60.0;
+        assert!((follow_factor(5.0, dt) - 5.0 * dt).abs() < 0.005);
+        assert_eq!(follow_factor(5.0, 0.0), 0.0);
+        assert!(follow_factor(5.0, 10.0) <= 1.0);
+    }
+
+    #[test]
+    fn a_snapping_camera_lands_on_the_player_in_one_frame_then_eases_again() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(std::time::Duration::from_secs_f32(1.0 / 60.0));
+        world.insert_resource(time);
+        world.spawn((Player, Transform::from_xyz(2000.0, -1000.0, 1.0)));
+        let camera = world
+            .spawn((
+                MainCamera,
+                CameraFollow { snap: true, ..default() },
+                Projection::Orthographic(OrthographicProjection::default_2d()),
+                Transform::from_xyz(0.0, 0.0, 999.9),
+            ))
+            .id();
+
+        world.run_system_once(camera_follow_player).unwrap();
+        assert_eq!(world.get::<Transform>(camera).unwrap().translation, Vec3::new(2000.0, -1000.0, 999.9));
+        assert!(!world.get::<CameraFollow>(camera).unwrap().snap, "only the once");
+
+        world.entity_mut(camera).get_mut::<Transform>().unwrap().translation = Vec3::new(0.0, 0.0, 999.9);
+        world.run_system_once(camera_follow_player).unwrap();
+        assert!(world.get::<Transform>(camera).unwrap().translation.x < 2000.0, "eases");
+    }
+
+    #[test]
+    fn zone_bounds_clamp_off_center() {
+        // Columns 1-2, rows 1-3 of a 7x5 map: x -120..-24, y -72..72.
+        let room = CameraBounds::from_tiles([1, 1, 2, 3], 7, 5);
+        assert_eq!(room, CameraBounds { min: Vec2::new(-120.0, -72.0), max: Vec2::new(-24.0, 72.0) });
+
+        // A view wider than the room pins to its center; a shorter one
+        // slides within it.
+        let clamped = room.clamp(Vec3::new(100.0, 100.0, 0.0), Vec2::new(60.0, 30.0));
+        assert_eq!(clamped, Vec3::new(-72.0, 42.0, 0.0));
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::{KeyValue, trace::{Span as _, Tracer as _}};
+use crate::game_state::GameState;
+use crate::instrumentation::{GameTracer, MetricsBundle, PlayerSessionTrace, init_metrics};
+use crate::player::Player;
+
+/// Chaos scenarios for teaching: `--chaos <scenario>` perturbs the real
+/// telemetry pipeline in one controlled way, and tags what it touched
+/// with `chaos.scenario` so an instructor can reveal the cause after the
+/// class has hunted for it.
+///
+/// The scenarios are `ChaosScenario`; their knobs (`ChaosParams`) come from
+/// `assets/data/chaos.json`, or `--chaos-params <file>`:
+///
+/// - `frame_spikes`: every `period_secs`, a frame sleeps `amplitude` ms.
+///   frame_watchdog.rs reports it like any stall; the stall span and
+///   `game.frame.stalls` carry the tag.
+/// - `dialogue_latency`: every dialogue takes `amplitude` ms longer to
+///   open (dialogue.rs), tagged on its `dialogue.session` span.
+/// - `error_burst`: every `period_secs`, `amplitude` error logs at once,
+///   each with a `chaos.scenario` field, under a `chaos.error_burst` span.
+///
+/// Every injection also bumps `game.chaos.injections`, by scenario.
+pub struct ChaosPlugin;
+
+impl Plugin for ChaosPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (inject_frame_spikes, inject_error_bursts)
+                .run_if(in_state(GameState::Playing).and_then(resource_exists::<Chaos>)),
+        );
+        init_metrics::<ChaosMetrics>(app);
+    }
+}
+
+/// `game.chaos.injections`, by scenario.
+#[derive(Resource)]
+pub struct ChaosMetrics {
+    pub injections: Counter<u64>,
+}
+
+impl MetricsBundle for ChaosMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            injections: meter
+                .u64_counter("game.chaos.injections")
+                .with_description("Anomalies injected by a --chaos scenario, by scenario (see chaos.rs)")
+                .build(),
+        }
+    }
+}
+
+pub const CHAOS_PATH: &str = "assets/data/chaos.json";
+pub const CHAOS_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/chaos.json"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChaosScenario {
+    FrameSpikes,
+    DialogueLatency,
+    ErrorBurst,
+}
+
+impl ChaosScenario {
+    pub const ALL: [Self; 3] = [Self::FrameSpikes, Self::DialogueLatency, Self::ErrorBurst];
+
+    /// The `--chaos` name, also the `chaos.scenario` value.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FrameSpikes => "frame_spikes",
+            Self::DialogueLatency => "dialogue_latency",
+            Self::ErrorBurst => "error_burst",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scenario| scenario.name() == name)
+    }
+}
+
+/// A scenario's knobs. What `amplitude` means depends on the scenario (see
+/// `ChaosPlugin`); `period_secs` is ignored by `dialogue_latency`, which
+/// fires on every dialogue, and must be above zero for the others - a
+/// zero-length period would fire every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ChaosParams {
+    pub amplitude: f32,
+    #[serde(default = "default_period_secs")]
+    pub period_secs: f32,
+}
+
+/// `period_secs` when a chaos file leaves it out.
+pub const DEFAULT_PERIOD_SECS: f32 = 30.0;
+
+fn default_period_secs() -> f32 {
+    DEFAULT_PERIOD_SECS
+}
+
+fn valid_period(secs: f32) -> bool {
+    secs.is_finite() && secs > 0.0
+}
+
+/// Present while a `--chaos` scenario runs.
+#[derive(Resource, Debug)]
+pub struct Chaos {
+    pub scenario: ChaosScenario,
+    pub params: ChaosParams,
+    period: Timer,
+    /// A spike slept that the frame watchdog hasn't reported yet, and the
+    /// frames since. The stall shows in the next frame's delta; a spike
+    /// still unreported after that was under the threshold, and mustn't
+    /// tag some later stall it didn't cause.
+    unreported_spike: Option<u8>,
+}
+
+impl Chaos {
+    /// A `period_secs` that isn't above zero gets `DEFAULT_PERIOD_SECS`;
+    /// `from_json` refuses one outright.
+    pub fn new(scenario: ChaosScenario, params: ChaosParams) -> Self {
+        let period_secs = if valid_period(params.period_secs) { params.period_secs } else { DEFAULT_PERIOD_SECS };
+        Self {
+            scenario,
+            params,
+            period: Timer::from_seconds(period_secs, TimerMode::Repeating),
+            unreported_spike: None,
+        }
+    }
+
+    /// `name`'s scenario with its parameters from a chaos file.
+    pub fn from_json(name: &str, json: &str) -> anyhow::Result<Self> {
+        let Some(scenario) = ChaosScenario::from_name(name) else {
+            let known: Vec<&str> = ChaosScenario::ALL.iter().map(|scenario| scenario.name()).collect();
+            anyhow::bail!("no chaos scenario {name:?} (try one of: {})", known.join(", "));
+        };
+        let mut params: BTreeMap<String, ChaosParams> = serde_json::from_str(json)?;
+        let Some(params) = params.remove(name) else {
+            anyhow::bail!("no parameters for chaos scenario {name:?}");
+        };
+        if scenario != ChaosScenario::DialogueLatency && !valid_period(params.period_secs) {
+            anyhow::bail!("chaos scenario {name:?} needs a period_secs above 0, not {}", params.period_secs);
+        }
+        Ok(Self::new(scenario, params))
+    }
+
+    /// The tag for whatever this scenario touched.
+    pub fn attribute(&self) -> KeyValue {
+        KeyValue::new("chaos.scenario", self.scenario.name())
+    }
+
+    /// Whether the stall being reported is one `frame_spikes` caused - the
+    /// first stall after a spike. Called by the frame watchdog.
+    pub fn take_spike(&mut self) -> bool {
+        self.unreported_spike.take().is_some()
+    }
+
+    /// How much longer a dialogue takes to open under `dialogue_latency`,
+    /// `None` under any other scenario. Counts as an injection.
+    pub fn dialogue_delay(&self, metrics: Option<&ChaosMetrics>) -> Option<Duration> {
+        if self.scenario != ChaosScenario::DialogueLatency {
+            return None;
+        }
+        self.count_injection(metrics);
+        Some(Duration::from_secs_f32(self.params.amplitude.max(0.0) / 1000.0))
+    }
+
+    fn count_injection(&self, metrics: Option<&ChaosMetrics>) {
+        if let Some(metrics) = metrics {
+            metrics.injections.add(1, &[self.attribute()]);
+        }
+    }
+}
+
+fn inject_frame_spikes(time: Res<Time>, mut chaos: ResMut<Chaos>, metrics: Option<Res<ChaosMetrics>>) {
+    if chaos.scenario != ChaosScenario::FrameSpikes {
+        return;
+    }
+    // Last frame's spike has had its frame; one from before that never
+    // made a stall.
+    chaos.unreported_spike = match chaos.unreported_spike {
+        Some(0) => Some(1),
+        _ => None,
+    };
+    if !chaos.period.tick(time.delta()).just_finished() {
+        return;
+    }
+    let spike = Duration::from_secs_f32(chaos.params.amplitude.max(0.0) / 1000.0);
+    debug!("🐒 Chaos: {}ms frame spike", spike.as_millis());
+    std::thread::sleep(spike);
+    chaos.unreported_spike = Some(0);
+    chaos.count_injection(metrics.as_deref());
+}
+
+fn inject_error_bursts(
+    time: Res<Time>,
+    mut chaos: ResMut<Chaos>,
+    player: Query<&PlayerSessionTrace, With<Player>>,
+    tracer: Option<Res<GameTracer>>,
+    metrics: Option<Res<ChaosMetrics>>,
+) {
+    if chaos.scenario != ChaosScenario::ErrorBurst || !chaos.period.tick(time.delta()).just_finished() {
+        return;
+    }
+    let count = chaos.params.amplitude.max(0.0) as u32;
+    let mut span = tracer.map(|tracer| {
+        let context = player.single().map(PlayerSessionTrace::as_context).unwrap_or_default();
+        let mut span = tracer.tracer().start_with_context("chaos.error_burst", &context);
+        span.set_attribute(chaos.attribute());
+        span.set_attribute(KeyValue::new("chaos.errors", count as i64));
+        span
+    });
+    for i in 0..count {
+        error!(chaos.scenario = chaos.scenario.name(), "Synthetic error {} of {count}", i + 1);
+    }
+    if let Some(span) = span.as_mut() {
+        span.end();
+    }
+    chaos.count_injection(metrics.as_deref());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_scenario_has_shipped_parameters() {
+        for scenario in ChaosScenario::ALL {
+            let chaos = Chaos::from_json(scenario.name(), CHAOS_JSON)
+                .unwrap_or_else(|e| panic!("{CHAOS_PATH}: {e:#}"));
+            assert_eq!(chaos.scenario, scenario);
+            assert!(chaos.params.amplitude > 0.0, "{}", scenario.name());
+        }
+    }
+
+    #[test]
+    fn unknown_scenarios_list_the_known_ones() {
+        let error = Chaos::from_json("meteor", CHAOS_JSON).unwrap_err().to_string();
+        assert!(error.contains("frame_spikes, dialogue_latency, error_burst"), "{error}");
+    }
+
+    #[test]
+    fn periodic_scenarios_need_a_period() {
+        let json = r#"{ "frame_spikes": { "amplitude": 250, "period_secs": 0 }, "error_burst": { "amplitude": 25 } }"#;
+        let error = Chaos::from_json("frame_spikes", json).unwrap_err().to_string();
+        assert!(error.contains("period_secs above 0"), "{error}");
+        let chaos = Chaos::from_json("error_burst", json).unwrap();
+        assert_eq!(chaos.params.period_secs, DEFAULT_PERIOD_SECS);
+        let chaos = Chaos::new(ChaosScenario::FrameSpikes, ChaosParams { amplitude: 1.0, period_secs: -1.0 });
+        assert_eq!(chaos.period.duration(), Duration::from_secs_f32(DEFAULT_PERIOD_SECS));
+    }
+
+    #[test]
+    fn a_spike_only_tags_the_next_frames_stall() {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .insert_resource(Chaos::new(ChaosScenario::FrameSpikes, ChaosParams { amplitude: 0.0, period_secs: 60.0 }))
+            .add_systems(Update, inject_frame_spikes);
+        app.world_mut().resource_mut::<Chaos>().unreported_spike = Some(0);
+
+        app.update();
+        assert_eq!(app.world().resource::<Chaos>().unreported_spike, Some(1), "the watchdog's frame");
+        app.update();
+        assert!(!app.world_mut().resource_mut::<Chaos>().take_spike(), "too late to be this spike's stall");
+    }
+
+    #[test]
+    fn only_dialogue_latency_delays_dialogue() {
+        let params = ChaosParams { amplitude: 400.0, period_secs: 0.0 };
+        let delay = Chaos::new(ChaosScenario::DialogueLatency, params).dialogue_delay(None);
+        assert_eq!(delay, Some(Duration::from_millis(400)));
+        assert_eq!(Chaos::new(ChaosScenario::ErrorBurst, params).dialogue_delay(None), None);
+    }
+}
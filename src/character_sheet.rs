@@ -1,4 +1,6 @@
 use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 
 /// Geometry of a standard RPGMaker MZ character sheet as shipped in
 /// assets/textures/characters/*.png: 576x384 px holding a 4x2 grid of
@@ -15,8 +17,6 @@ pub const FRAME_SIZE: u32 = 48;
 pub const SHEET_COLUMNS: u32 = 12;
 pub const SHEET_ROWS: u32 = 8;
 
-const SLOT_GRID_COLUMNS: u32 = 4;
-const SLOTS_PER_SHEET: u32 = 8;
 const PATTERNS_PER_SLOT: u32 = 3;
 const FACINGS_PER_SLOT: u32 = 4;
 
@@ -42,6 +42,74 @@ pub fn sheet_layout_with_frame(frame_size: UVec2) -> TextureAtlasLayout {
     )
 }
 
+/// How a sheet's frames are laid out, in frames across and down: whole
+/// characters (3 patterns x 4 facings each), filled left to right, top to
+/// bottom. A standard sheet is the 12x8 `FULL` one; RPGMaker's
+/// single-character `$` sheets are 3x4. Set per sheet in
+/// `assets/data/sprites.json`.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "[u32; 2]")]
+pub struct SheetGrid {
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Default for SheetGrid {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl From<[u32; 2]> for SheetGrid {
+    fn from([columns, rows]: [u32; 2]) -> Self {
+        Self { columns, rows }
+    }
+}
+
+impl SheetGrid {
+    pub const FULL: Self = Self { columns: SHEET_COLUMNS, rows: SHEET_ROWS };
+
+    /// What's wrong with the grid, if it isn't whole characters.
+    pub fn problem(self) -> Option<String> {
+        let Self { columns, rows } = self;
+        (columns == 0 || rows == 0 || columns % PATTERNS_PER_SLOT != 0 || rows % FACINGS_PER_SLOT != 0).then(|| {
+            format!(
+                "grid {columns}x{rows} isn't whole characters \
+                 (columns a multiple of {PATTERNS_PER_SLOT}, rows of {FACINGS_PER_SLOT})"
+            )
+        })
+    }
+
+    /// How many characters the sheet holds.
+    pub fn slots(self) -> u32 {
+        (self.columns / PATTERNS_PER_SLOT) * (self.rows / FACINGS_PER_SLOT)
+    }
+
+    /// The atlas over the sheet, `frame_size` a frame.
+    pub fn layout(self, frame_size: UVec2) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(frame_size, self.columns, self.rows, None, None)
+    }
+
+    /// As `atlas_index`, on this grid.
+    pub fn index(self, slot: u32, facing_row: u32, pattern: u32) -> u32 {
+        let slots = self.slots();
+        assert!(slot < slots, "character slot {slot} out of range (sheets hold {slots} characters)");
+        assert!(
+            facing_row < FACINGS_PER_SLOT,
+            "facing row {facing_row} out of range (0=down, 1=left, 2=right, 3=up)"
+        );
+        assert!(
+            pattern < PATTERNS_PER_SLOT,
+            "animation pattern {pattern} out of range (slots have {PATTERNS_PER_SLOT} columns)"
+        );
+
+        let slot_columns = self.columns / PATTERNS_PER_SLOT;
+        let block_col = slot % slot_columns;
+        let block_row = slot / slot_columns;
+        (block_row * FACINGS_PER_SLOT + facing_row) * self.columns + block_col * PATTERNS_PER_SLOT + pattern
+    }
+}
+
 /// Atlas index of one 48x48 frame within `sheet_layout`. `facing_row` uses
 /// RPGMaker row order: 0=down, 1=left, 2=right, 3=up.
 ///
@@ -49,22 +117,112 @@ pub fn sheet_layout_with_frame(frame_size: UVec2) -> TextureAtlasLayout {
 /// exist is corrupt, and quietly rendering some other character's frames
 /// would be worse than failing loudly.
 pub fn atlas_index(slot: u32, facing_row: u32, pattern: u32) -> u32 {
-    assert!(
-        slot < SLOTS_PER_SHEET,
-        "character slot {slot} out of range (sheets hold {SLOTS_PER_SHEET} characters)"
-    );
-    assert!(
-        facing_row < FACINGS_PER_SLOT,
-        "facing row {facing_row} out of range (0=down, 1=left, 2=right, 3=up)"
-    );
-    assert!(
-        pattern < PATTERNS_PER_SLOT,
-        "animation pattern {pattern} out of range (slots have {PATTERNS_PER_SLOT} columns)"
-    );
-
-    let block_col = slot % SLOT_GRID_COLUMNS;
-    let block_row = slot / SLOT_GRID_COLUMNS;
-    (block_row * FACINGS_PER_SLOT + facing_row) * SHEET_COLUMNS + block_col * PATTERNS_PER_SLOT + pattern
+    SheetGrid::FULL.index(slot, facing_row, pattern)
+}
+
+/// The two side-facing rows, in RPGMaker row order.
+pub const LEFT_ROW: u32 = 1;
+pub const RIGHT_ROW: u32 = 2;
+
+/// How a sprite's sheet is drawn, from its `assets/data/sprites.json`
+/// entry (`CharacterSprite`). Left out, it's a full RPGMaker sheet and
+/// takes the defaults.
+#[derive(Component, Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[reflect(Component)]
+pub struct SheetOptions {
+    /// The sheet's left row isn't drawn (or is a placeholder): show the
+    /// right row mirrored instead.
+    #[serde(default)]
+    pub mirror_left_from_right: bool,
+    #[serde(default)]
+    pub grid: SheetGrid,
+}
+
+impl SheetOptions {
+    /// The frame to show for `facing_row` and `pattern`. Both the player
+    /// animator and the NPC/prop animators go through here, so a mirrored
+    /// sheet faces left the same way everywhere.
+    pub fn frame(self, slot: u32, facing_row: u32, pattern: u32) -> SheetFrame {
+        let flip_x = self.mirror_left_from_right && facing_row == LEFT_ROW;
+        let row = if flip_x { RIGHT_ROW } else { facing_row };
+        SheetFrame { index: self.grid.index(slot, row, pattern), flip_x }
+    }
+}
+
+/// One resolved frame: where it is in the atlas and whether to mirror it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetFrame {
+    pub index: u32,
+    pub flip_x: bool,
+}
+
+impl SheetFrame {
+    /// Show this frame. `flip_x` is always written, so turning from a
+    /// mirrored left back to any other facing un-mirrors the sprite.
+    pub fn apply(self, sprite: &mut Sprite) {
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = self.index as usize;
+        }
+        sprite.flip_x = self.flip_x;
+    }
+}
+
+/// The sprite registry, under `assets/` - a content pack may bring its own
+/// (content_pack.rs).
+pub const SPRITES_FILE: &str = "data/sprites.json";
+pub const SPRITES_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/sprites.json"));
+
+/// One `assets/data/sprites.json` entry: the sheet behind a sprite name, as
+/// maps give it in an NPC's, prop's or door's `sprite`, and how it's drawn.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CharacterSprite {
+    /// Under `assets/`.
+    pub texture: String,
+    #[serde(flatten)]
+    pub options: SheetOptions,
+}
+
+/// The sprite registry: every name a map can give a character, and its
+/// sheet. A new character is an entry here and a texture, no Rust.
+#[derive(Debug, Clone, Default)]
+pub struct CharacterSprites(pub BTreeMap<String, CharacterSprite>);
+
+impl CharacterSprites {
+    pub fn get(&self, name: &str) -> Option<&CharacterSprite> {
+        self.0.get(name)
+    }
+
+    /// Every name, sorted - for saying what a map could have asked for.
+    pub fn names(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
+
+/// Parse and validate the registry against the textures that `shipped`
+/// (paths under `assets/`). An entry whose grid isn't whole characters, or
+/// whose texture didn't ship, is reported and left out; the rest load.
+pub fn parse_character_sprites(json: &str, shipped: &[String]) -> (CharacterSprites, Vec<String>) {
+    let mut sprites: BTreeMap<String, CharacterSprite> = match serde_json::from_str(json) {
+        Ok(sprites) => sprites,
+        Err(e) => return (CharacterSprites::default(), vec![format!("Failed to parse the sprite registry: {e}")]),
+    };
+    let mut problems = Vec::new();
+    sprites.retain(|name, sprite| {
+        let problem = match sprite.options.grid.problem() {
+            Some(problem) => Some(problem),
+            None => (!shipped.contains(&sprite.texture)).then(|| format!("texture {:?} didn't ship", sprite.texture)),
+        };
+        if let Some(problem) = &problem {
+            problems.push(format!("sprite {name:?}: {problem}"));
+        }
+        problem.is_none()
+    });
+    (CharacterSprites(sprites), problems)
+}
+
+/// The shipped registry (see `parse_character_sprites`).
+pub fn load_character_sprites(shipped: &[String]) -> (CharacterSprites, Vec<String>) {
+    parse_character_sprites(SPRITES_JSON, shipped)
 }
 
 #[cfg(test)]
@@ -107,4 +265,69 @@ mod tests {
     fn slot_out_of_range_panics() {
         atlas_index(8, 0, 0);
     }
+
+    #[test]
+    fn every_facing_resolves_for_normal_and_mirrored_sheets() {
+        let normal = SheetOptions::default();
+        let mirrored = SheetOptions { mirror_left_from_right: true, ..default() };
+        let frame = |options: SheetOptions, row| options.frame(0, row, STANDING_PATTERN);
+        let at = |row| atlas_index(0, row, STANDING_PATTERN);
+
+        for row in [0, LEFT_ROW, RIGHT_ROW, 3] {
+            assert_eq!(frame(normal, row), SheetFrame { index: at(row), flip_x: false });
+        }
+        assert_eq!(frame(mirrored, 0), SheetFrame { index: at(0), flip_x: false });
+        assert_eq!(frame(mirrored, LEFT_ROW), SheetFrame { index: at(RIGHT_ROW), flip_x: true });
+        assert_eq!(frame(mirrored, RIGHT_ROW), SheetFrame { index: at(RIGHT_ROW), flip_x: false });
+        assert_eq!(frame(mirrored, 3), SheetFrame { index: at(3), flip_x: false });
+
+        // Turning away from a mirrored left clears the flip.
+        let mut sprite = Sprite {
+            texture_atlas: Some(TextureAtlas::default()),
+            ..default()
+        };
+        frame(mirrored, LEFT_ROW).apply(&mut sprite);
+        assert!(sprite.flip_x);
+        frame(mirrored, 3).apply(&mut sprite);
+        assert!(!sprite.flip_x);
+        assert_eq!(sprite.texture_atlas.unwrap().index, at(3) as usize);
+    }
+
+    #[test]
+    fn a_single_character_grid_indexes_its_one_slot() {
+        let single = SheetGrid { columns: 3, rows: 4 };
+        assert_eq!(single.slots(), 1);
+        assert_eq!(single.index(0, RIGHT_ROW, STANDING_PATTERN), 2 * 3 + 1);
+        assert_eq!(single.index(0, 3, 2), 11, "the last frame is the sheet's last cell");
+        assert_eq!(SheetGrid::FULL.index(6, 3, STANDING_PATTERN), atlas_index(6, 3, STANDING_PATTERN));
+    }
+
+    #[test]
+    fn registry_entries_with_a_bad_grid_or_a_missing_texture_are_reported_and_dropped() {
+        let json = r#"{
+            "Hero": { "texture": "textures/characters/Hero.png", "mirror_left_from_right": true },
+            "Solo": { "texture": "textures/characters/$Solo.png", "grid": [3, 4] },
+            "Lopsided": { "texture": "textures/characters/Hero.png", "grid": [10, 8] },
+            "Typo": { "texture": "textures/characters/Heor.png" }
+        }"#;
+        let shipped = ["textures/characters/Hero.png", "textures/characters/$Solo.png"].map(String::from);
+        let (sprites, problems) = parse_character_sprites(json, &shipped);
+        assert_eq!(sprites.names(), ["Hero", "Solo"]);
+        let hero = sprites.get("Hero").map(|hero| hero.options);
+        assert_eq!(hero, Some(SheetOptions { mirror_left_from_right: true, grid: SheetGrid::FULL }), "full sheets by default");
+        assert_eq!(sprites.get("Solo").map(|solo| solo.options.grid), Some(SheetGrid { columns: 3, rows: 4 }));
+        assert_eq!(
+            problems,
+            [
+                "sprite \"Lopsided\": grid 10x8 isn't whole characters (columns a multiple of 3, rows of 4)",
+                "sprite \"Typo\": texture \"textures/characters/Heor.png\" didn't ship",
+            ]
+        );
+
+        let shipped: Vec<String> =
+            crate::asset_manifest::CHARACTER_SPRITES.iter().map(|stem| format!("textures/characters/{stem}.png")).collect();
+        let (sprites, problems) = load_character_sprites(&shipped);
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(sprites.names().len(), shipped.len(), "every shipped sheet has a name");
+    }
 }
@@ -0,0 +1,195 @@
+use bevy::picking::hover::HoverMap;
+use bevy::picking::pointer::PointerId;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::MainCamera;
+use crate::character_sheet::FRAME_SIZE;
+use crate::dialogue::DialogueRequestSet;
+use crate::game_state::{GameStatePlugin, Mode};
+use crate::map_data::{tile_to_world, world_to_tile};
+use crate::npc::{InRange, Npc, NpcClicked};
+use crate::player::{AutoWalk, Player, logical_position};
+use crate::settings::GameSettings;
+use crate::tilemap::CollisionMap;
+use crate::toast::ShowToast;
+
+/// Click or tap an NPC to talk, for trackpad players who kept trying to.
+/// A click lands on whichever NPC's sprite is under the cursor (the one in
+/// front, where they overlap). One in interaction range is talked to as an
+/// E press would - `npc::NpcClicked`, so a busy NPC still queues it - with
+/// `input.source=mouse` on the `npc.interaction` span. One out of range is
+/// named in a toast and, with `GameSettings::click_to_walk`, walked to
+/// along `CollisionMap::find_path`, the conversation starting once the
+/// player is in range. A movement key on the way calls the walk off.
+///
+/// Clicks over the UI (a menu, the console, a toast) are the UI's, by the
+/// picking hover map. Map objects - terminals, action exits - still take E.
+pub struct ClickToTalkPlugin;
+
+impl Plugin for ClickToTalkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<WorldClick>()
+            .add_message::<NpcClicked>()
+            .add_message::<ShowToast>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<Touches>()
+            .init_resource::<GameSettings>()
+            .add_systems(
+                Update,
+                (read_pointer_clicks, click_npcs, walk_to_clicked_npc)
+                    .chain()
+                    .before(DialogueRequestSet)
+                    .run_if(in_state(Mode::Exploring)),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<GameStatePlugin>(app, "ClickToTalkPlugin");
+    }
+}
+
+/// A click or tap on the map, in world space.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct WorldClick {
+    pub position: Vec2,
+}
+
+/// The NPC the player is walking over to talk to.
+#[derive(Resource, Debug)]
+pub struct ClickWalk {
+    pub npc: Entity,
+}
+
+/// Which of `sprites` a click at `point` lands on: the frontmost whose
+/// character frame, as scaled, holds the point.
+pub fn sprite_under<T>(point: Vec2, sprites: impl IntoIterator<Item = (Transform, T)>) -> Option<T> {
+    let half = Vec2::splat(FRAME_SIZE as f32 / 2.0);
+    sprites
+        .into_iter()
+        .filter(|(transform, _)| {
+            let offset = point - transform.translation.truncate();
+            offset.abs().cmple(half * transform.scale.truncate().abs()).all()
+        })
+        .max_by(|(a, _), (b, _)| a.translation.z.total_cmp(&b.translation.z))
+        .map(|(_, sprite)| sprite)
+}
+
+fn read_pointer_clicks(
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    hover_map: Option<Res<HoverMap>>,
+    ui_nodes: Query<(), With<Node>>,
+    mut clicks: MessageWriter<WorldClick>,
+) {
+    let Ok((camera, camera_transform)) = camera.single() else { return };
+    let cursor = windows
+        .single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .filter(|_| mouse.just_pressed(MouseButton::Left))
+        .map(|position| (PointerId::Mouse, position));
+    let taps = touches.iter_just_pressed().map(|touch| (PointerId::Touch(touch.id()), touch.position()));
+    for (pointer, position) in cursor.into_iter().chain(taps) {
+        let over_ui = hover_map
+            .as_ref()
+            .and_then(|hover_map| hover_map.get(&pointer))
+            .is_some_and(|hits| hits.keys().any(|&entity| ui_nodes.contains(entity)));
+        if over_ui {
+            continue;
+        }
+        if let Ok(position) = camera.viewport_to_world_2d(camera_transform, position) {
+            clicks.write(WorldClick { position });
+        }
+    }
+}
+
+fn click_npcs(
+    mut commands: Commands,
+    mut clicks: MessageReader<WorldClick>,
+    npcs: Query<(Entity, &Transform, &Npc, Has<InRange>)>,
+    player: Query<(Entity, &Transform), With<Player>>,
+    collision_map: Option<Res<CollisionMap>>,
+    settings: Res<GameSettings>,
+    mut talk: MessageWriter<NpcClicked>,
+    mut toasts: MessageWriter<ShowToast>,
+) {
+    let Some(click) = clicks.read().last().copied() else { return };
+    let Some((npc, npc_transform, name, in_range)) = sprite_under(
+        click.position,
+        npcs.iter().map(|(entity, transform, npc, in_range)| (*transform, (entity, *transform, &npc.name, in_range))),
+    ) else {
+        return;
+    };
+    let Ok((player, player_transform)) = player.single() else { return };
+
+    // Whatever walk was under way, this click replaces it.
+    commands.entity(player).remove::<AutoWalk>();
+    commands.remove_resource::<ClickWalk>();
+    if in_range {
+        talk.write(NpcClicked { npc });
+        return;
+    }
+
+    toasts.write(ShowToast::new(name.clone()));
+    let Some(map) = collision_map.filter(|_| settings.click_to_walk) else { return };
+    let from = world_to_tile(logical_position(player_transform.translation.truncate()), map.width, map.height);
+    let to = world_to_tile(npc_transform.translation.truncate(), map.width, map.height);
+    let Some(path) = map.find_path(from, to) else {
+        info!("🖱️ No way over to {name}");
+        return;
+    };
+    // The walk starts by centering on the player's own tile, so the first
+    // step doesn't clip a corner. It ends on the NPC's, but reaching
+    // interaction range ends it first.
+    let waypoints = path
+        .into_iter()
+        .map(|(x, y)| tile_to_world(x as u32, y as u32, map.width, map.height))
+        .collect();
+    info!("🖱️ Walking over to {name}");
+    commands.entity(player).insert(AutoWalk { waypoints });
+    commands.insert_resource(ClickWalk { npc });
+}
+
+/// Talk once in range; give up if the walk ends short of that (a movement
+/// key, the route run out) or the NPC is gone.
+fn walk_to_clicked_npc(
+    mut commands: Commands,
+    walk: Option<Res<ClickWalk>>,
+    npcs: Query<Has<InRange>, With<Npc>>,
+    player: Query<(Entity, Has<AutoWalk>), With<Player>>,
+    mut talk: MessageWriter<NpcClicked>,
+) {
+    let Some(walk) = walk else { return };
+    let Ok((player, walking)) = player.single() else {
+        commands.remove_resource::<ClickWalk>();
+        return;
+    };
+    match npcs.get(walk.npc) {
+        Ok(false) if walking => return,
+        Ok(true) => {
+            talk.write(NpcClicked { npc: walk.npc });
+        }
+        _ => {}
+    }
+    commands.entity(player).remove::<AutoWalk>();
+    commands.remove_resource::<ClickWalk>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_click_lands_on_the_sprite_in_front() {
+        let behind = Transform::from_xyz(0.0, 0.0, 1.0);
+        let in_front = Transform::from_xyz(20.0, 0.0, 2.0);
+        let sprites = [(behind, "behind"), (in_front, "in front")];
+        assert_eq!(sprite_under(Vec2::new(-20.0, 0.0), sprites), Some("behind"));
+        assert_eq!(sprite_under(Vec2::new(10.0, 10.0), sprites), Some("in front"));
+        assert_eq!(sprite_under(Vec2::new(0.0, 30.0), sprites), None, "over their heads");
+        let scaled = Transform::from_xyz(0.0, 0.0, 1.0).with_scale(Vec3::splat(2.0));
+        assert_eq!(sprite_under(Vec2::new(0.0, 30.0), [(scaled, "tall")]), Some("tall"));
+    }
+}
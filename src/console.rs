@@ -0,0 +1,627 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use opentelemetry::KeyValue;
+use opentelemetry::trace::Span as _;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use crate::achievements::UnlockAchievement;
+use crate::assets::GameAssets;
+use crate::flags::{GameFlags, SetFlagEvent};
+use crate::game_state::{GameState, Mode};
+use crate::instrumentation::PlayerSessionTrace;
+use crate::map_data::{DialogueData, tile_to_world};
+use crate::player::Player;
+use crate::settings::GameSettings;
+use crate::tilemap::{CollisionMap, MapNpcs};
+
+/// The developer console: a quake-style panel dropped over the top of the
+/// screen with the backquote key (`~`), for debug builds or `--dev-console`
+/// (`GameSettings::dev_console`). A line typed there runs one of the
+/// commands in `ConsoleCommands` - `tp 10 4`, `flag set met_isabella`,
+/// `dialogue start isabella`, `state playing`, `give achievement
+/// first_steps`, `telemetry flush`; `help` lists them. Tab completes a command's name, Page Up/Down scroll the
+/// output, Escape or `~` again closes it.
+///
+/// Plugins add their own commands with `add_console_command`. A command's
+/// closure gets the whole `World`: the lines run in an exclusive system,
+/// so a command can do anything a system could. Every line run is logged
+/// and added to the session span as a `console.command` event.
+///
+/// The console is `Mode::Console`, opened from `Exploring` like the menus.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<KeyboardInput>()
+            .add_message::<SetFlagEvent>()
+            .add_message::<crate::dialogue::DialogueRequest>()
+            .add_message::<UnlockAchievement>()
+            .init_resource::<DevConsole>()
+            .init_resource::<ConsoleCommands>()
+            .add_systems(
+                Update,
+                (
+                    handle_console_keys,
+                    run_console_commands,
+                    sync_console.run_if(in_state(Mode::Console).and(resource_changed::<DevConsole>)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(Mode::Console), spawn_console)
+            .add_systems(OnExit(Mode::Console), despawn_console);
+        for command in built_in_commands() {
+            add_console_command(app, command);
+        }
+    }
+}
+
+/// Output lines kept for scrolling back through.
+pub const OUTPUT_LINES: usize = 200;
+/// Output lines on screen at once.
+const VISIBLE_LINES: usize = 12;
+
+/// What the console shows and what's been typed into it. Kept while it's
+/// closed, so reopening it finds the last session's output.
+#[derive(Resource, Debug, Default)]
+pub struct DevConsole {
+    /// The line being typed.
+    pub input: String,
+    output: VecDeque<String>,
+    /// Output lines scrolled back from the newest.
+    scroll: usize,
+    /// Submitted lines, for `run_console_commands` to run.
+    pending: Vec<String>,
+}
+
+impl DevConsole {
+    /// Run `line` as if typed and entered.
+    pub fn submit(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        if !line.trim().is_empty() {
+            self.pending.push(line);
+        }
+    }
+
+    /// Every output line kept, oldest first.
+    pub fn output(&self) -> impl Iterator<Item = &str> {
+        self.output.iter().map(String::as_str)
+    }
+
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            if self.output.len() == OUTPUT_LINES {
+                self.output.pop_front();
+            }
+            self.output.push_back(line.to_string());
+        }
+        self.scroll = 0;
+    }
+
+    /// Scroll back (positive) or forward through the output.
+    pub fn scroll_by(&mut self, lines: isize) {
+        let max = self.output.len().saturating_sub(VISIBLE_LINES);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    /// The output lines on screen, oldest first.
+    fn visible(&self) -> impl Iterator<Item = &str> {
+        let end = self.output.len() - self.scroll;
+        self.output.range(end.saturating_sub(VISIBLE_LINES)..end).map(String::as_str)
+    }
+
+    /// Tab: complete the command name being typed. Several candidates
+    /// complete as far as they agree and are listed.
+    fn complete(&mut self, commands: &ConsoleCommands) {
+        if self.input.contains(' ') {
+            return;
+        }
+        let candidates = commands.complete(&self.input);
+        match candidates.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{name} "),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.len(), |len, name| {
+                    first.chars().zip(name.chars()).take_while(|(a, b)| a == b).count().min(len)
+                });
+                self.input = first[..common].to_string();
+                self.print(&candidates.join("  "));
+            }
+        }
+    }
+}
+
+/// What a command does with its parsed arguments: what to print, or what
+/// went wrong.
+pub type CommandResult = Result<String, String>;
+
+type RunCommand = Box<dyn Fn(&[String], &mut World) -> CommandResult + Send + Sync>;
+
+/// One console command: `parse` turns the words after its name into
+/// arguments, `run` acts on them.
+pub struct ConsoleCommand {
+    pub name: &'static str,
+    /// Its arguments, for `help` and parse errors: `<x> <y>`.
+    pub usage: &'static str,
+    pub help: &'static str,
+    run: RunCommand,
+}
+
+impl ConsoleCommand {
+    pub fn new<A: 'static>(
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        parse: fn(&[String]) -> Result<A, String>,
+        run: impl Fn(A, &mut World) -> CommandResult + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name,
+            usage,
+            help,
+            run: Box::new(move |args, world| {
+                let args = parse(args).map_err(|e| format!("{e} (usage: {name} {usage})"))?;
+                run(args, world)
+            }),
+        }
+    }
+}
+
+/// Every command the console knows, by name.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<&'static str, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    /// A command of the same name is replaced.
+    pub fn add(&mut self, command: ConsoleCommand) {
+        self.commands.insert(command.name, command);
+    }
+
+    /// Command names starting with `prefix`, `help` included, in order.
+    pub fn complete(&self, prefix: &str) -> Vec<&'static str> {
+        std::iter::once("help")
+            .chain(self.commands.keys().copied())
+            .filter(|name| name.starts_with(prefix))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Run one typed line.
+    pub fn execute(&self, line: &str, world: &mut World) -> CommandResult {
+        let words = split_words(line)?;
+        let Some((name, args)) = words.split_first() else { return Ok(String::new()) };
+        if name == "help" {
+            return Ok(self.help());
+        }
+        let command = self.commands.get(name.as_str()).ok_or_else(|| format!("unknown command {name:?} - try help"))?;
+        (command.run)(args, world)
+    }
+
+    fn help(&self) -> String {
+        self.commands
+            .values()
+            .map(|command| format!("{} {} - {}", command.name, command.usage, command.help))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Register `command` with the console, `ConsolePlugin` or not (and before
+/// or after it).
+pub fn add_console_command(app: &mut App, command: ConsoleCommand) {
+    app.init_resource::<ConsoleCommands>();
+    app.world_mut().resource_mut::<ConsoleCommands>().add(command);
+}
+
+/// A typed line's words: split on whitespace, except inside double quotes
+/// (`flag set "two words"`).
+pub fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_default();
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".to_string());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn no_args(args: &[String]) -> Result<(), String> {
+    match args {
+        [] => Ok(()),
+        _ => Err("takes no arguments".to_string()),
+    }
+}
+
+fn parse_tile(args: &[String]) -> Result<(u32, u32), String> {
+    let [x, y] = args else { return Err("needs a tile x and y".to_string()) };
+    let coordinate = |word: &String| word.parse::<u32>().map_err(|_| format!("{word:?} isn't a tile coordinate"));
+    Ok((coordinate(x)?, coordinate(y)?))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FlagCommand {
+    Set(String),
+    Clear(String),
+}
+
+fn parse_flag(args: &[String]) -> Result<FlagCommand, String> {
+    match args {
+        [verb, flag] if verb == "set" => Ok(FlagCommand::Set(flag.clone())),
+        [verb, flag] if verb == "clear" => Ok(FlagCommand::Clear(flag.clone())),
+        _ => Err("set or clear one flag".to_string()),
+    }
+}
+
+fn parse_dialogue(args: &[String]) -> Result<String, String> {
+    match args {
+        [verb, id] if verb == "start" => Ok(id.clone()),
+        _ => Err("start one dialogue, by id".to_string()),
+    }
+}
+
+fn parse_state(args: &[String]) -> Result<GameState, String> {
+    match args {
+        [state] if state == "loading" => Ok(GameState::Loading),
+        [state] if state == "playing" => Ok(GameState::Playing),
+        _ => Err("loading or playing".to_string()),
+    }
+}
+
+fn parse_give(args: &[String]) -> Result<String, String> {
+    match args {
+        [what, id] if what == "achievement" => Ok(id.clone()),
+        _ => Err("give one achievement, by id".to_string()),
+    }
+}
+
+fn parse_telemetry(args: &[String]) -> Result<(), String> {
+    match args {
+        [verb] if verb == "flush" => Ok(()),
+        _ => Err("only flush".to_string()),
+    }
+}
+
+fn built_in_commands() -> Vec<ConsoleCommand> {
+    vec![
+        ConsoleCommand::new("tp", "<x> <y>", "move the player to a tile of this map", parse_tile, teleport),
+        ConsoleCommand::new("flag", "set|clear <flag>", "set or clear a story flag", parse_flag, |command, world| {
+            match command {
+                FlagCommand::Set(flag) => {
+                    world.write_message(SetFlagEvent { flag: flag.clone() });
+                    Ok(format!("set {flag}"))
+                }
+                FlagCommand::Clear(flag) => {
+                    let mut flags = world.get_resource_mut::<GameFlags>().ok_or("no story flags in this game")?;
+                    // Only borrow mutably for a real change (see flags.rs).
+                    if !flags.is_set(&flag) {
+                        return Ok(format!("{flag} wasn't set"));
+                    }
+                    flags.clear(&flag);
+                    Ok(format!("cleared {flag}"))
+                }
+            }
+        }),
+        ConsoleCommand::new(
+            "dialogue",
+            "start <id>",
+            "open one of this map's conversations, by id or map/id",
+            parse_dialogue,
+            start_dialogue,
+        ),
+        ConsoleCommand::new("state", "loading|playing", "switch the game state", parse_state, |state, world| {
+            world.get_resource_mut::<NextState<GameState>>().ok_or("no game state")?.set(state);
+            Ok(format!("switching to {state:?}"))
+        }),
+        ConsoleCommand::new("give", "achievement <id>", "unlock an achievement", parse_give, |id, world| {
+            world.write_message(UnlockAchievement { id: id.clone() });
+            Ok(format!("unlocking {id}"))
+        }),
+        ConsoleCommand::new(
+            "telemetry",
+            "flush",
+            "export the spans and metrics buffered so far",
+            parse_telemetry,
+            flush_telemetry,
+        ),
+    ]
+}
+
+fn teleport((x, y): (u32, u32), world: &mut World) -> CommandResult {
+    let map = world.get_resource::<CollisionMap>().ok_or("no map loaded")?;
+    if x >= map.width || y >= map.height {
+        return Err(format!("({x}, {y}) is off the {}x{} map", map.width, map.height));
+    }
+    let position = tile_to_world(x, y, map.width, map.height);
+    let mut players = world.query_filtered::<&mut Transform, With<Player>>();
+    let mut transform = players.single_mut(world).map_err(|_| "no player")?;
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    Ok(format!("teleported to ({x}, {y})"))
+}
+
+/// Open the conversation `id` names - inline in the map, or in a loaded
+/// dialogue file - as its NPC would, minus the NPC. The console closes for
+/// it: dialogue only opens while exploring.
+fn start_dialogue(id: String, world: &mut World) -> CommandResult {
+    let (qualified, data) = find_dialogue(world, &id).ok_or_else(|| format!("no dialogue {id:?} on this map"))?;
+    world.write_message(crate::dialogue::DialogueRequestBuilder::authored(Arc::new(data)).id(qualified.clone()).build());
+    world.resource_mut::<NextState<Mode>>().set(Mode::Exploring);
+    Ok(format!("starting {qualified}"))
+}
+
+/// The conversation with id (or qualified id, see
+/// `content::qualified_id`) `id` among the loaded map's NPCs, and its
+/// qualified id.
+fn find_dialogue(world: &World, id: &str) -> Option<(String, DialogueData)> {
+    let npcs = world.get_resource::<MapNpcs>()?;
+    let files = world.get_resource::<Assets<DialogueData>>();
+    let server = world.get_resource::<AssetServer>();
+    for npc in npcs.placed.iter().chain(&npcs.spawnable) {
+        let file = npc.dialogue_file.as_ref().and_then(|path| {
+            let handle = server?.get_handle::<DialogueData>(path.as_str())?;
+            Some((path.as_str(), files?.get(&handle)?))
+        });
+        let inline = std::iter::once(&npc.dialogue).chain(&npc.dialogues).map(|data| (npcs.path.as_str(), data));
+        for (source, data) in file.into_iter().chain(inline) {
+            let qualified = crate::content::qualified_id(source, &data.id());
+            if data.id() == id || qualified == id {
+                return Some((qualified, data.clone()));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn flush_telemetry((): (), world: &mut World) -> CommandResult {
+    let providers = world
+        .get_resource::<crate::instrumentation::TelemetryProviders>()
+        .ok_or("telemetry is off (no collector)")?;
+    providers.tracer.force_flush().map_err(|e| format!("flushing spans: {e}"))?;
+    providers.meter.force_flush().map_err(|e| format!("flushing metrics: {e}"))?;
+    Ok("flushed spans and metrics".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn flush_telemetry((): (), _world: &mut World) -> CommandResult {
+    Err("the browser build has no telemetry".to_string())
+}
+
+/// Every key press, as text: `KeyboardInput` rather than `GameInput`, for
+/// the characters typed and not just keys. One system for opening and
+/// typing, so the backquote that opens the console isn't also typed into
+/// it.
+fn handle_console_keys(
+    mut keys: MessageReader<KeyboardInput>,
+    settings: Option<Res<GameSettings>>,
+    mode: Res<State<Mode>>,
+    mut next_mode: ResMut<NextState<Mode>>,
+    mut console: ResMut<DevConsole>,
+    commands: Res<ConsoleCommands>,
+) {
+    let enabled = settings.map_or(GameSettings::default().dev_console, |settings| settings.dev_console);
+    let mut open = *mode.get() == Mode::Console;
+    for key in keys.read().filter(|key| key.state.is_pressed()) {
+        if !open {
+            if enabled && key.key_code == KeyCode::Backquote && *mode.get() == Mode::Exploring {
+                next_mode.set(Mode::Console);
+                open = true;
+            }
+            continue;
+        }
+        match key.key_code {
+            KeyCode::Backquote | KeyCode::Escape => {
+                next_mode.set(Mode::Exploring);
+                return;
+            }
+            KeyCode::Enter | KeyCode::NumpadEnter => {
+                let line = std::mem::take(&mut console.input);
+                console.submit(line);
+            }
+            KeyCode::Backspace => {
+                console.input.pop();
+            }
+            KeyCode::Tab => console.complete(&commands),
+            KeyCode::PageUp => console.scroll_by(VISIBLE_LINES as isize / 2),
+            KeyCode::PageDown => console.scroll_by(-(VISIBLE_LINES as isize / 2)),
+            _ => {
+                if let Some(text) = &key.text {
+                    console.input.extend(text.chars().filter(|c| !c.is_control()));
+                }
+            }
+        }
+    }
+}
+
+/// Run the lines submitted since last frame, each with the whole world.
+fn run_console_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<DevConsole>().pending);
+    if pending.is_empty() {
+        return;
+    }
+    world.resource_scope(|world, commands: Mut<ConsoleCommands>| {
+        for line in pending {
+            let result = commands.execute(&line, world);
+            match &result {
+                Ok(_) => info!("🖥️ Console: {line}"),
+                Err(e) => warn!("🖥️ Console: {line}: {e}"),
+            }
+            let mut sessions = world.query_filtered::<&mut PlayerSessionTrace, With<Player>>();
+            if let Ok(mut session) = sessions.single_mut(world) {
+                session.span.add_event(
+                    "console.command",
+                    vec![KeyValue::new("console.line", line.clone()), KeyValue::new("console.ok", result.is_ok())],
+                );
+            }
+            let mut console = world.resource_mut::<DevConsole>();
+            console.print(&format!("> {line}"));
+            match result {
+                Ok(text) => console.print(&text),
+                Err(e) => console.print(&format!("error: {e}")),
+            }
+        }
+    });
+}
+
+/// Same green as the telemetry terminal (dashboard.rs).
+const CONSOLE_TEXT: Color = Color::srgb(0.35, 1.0, 0.45);
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleOutput;
+
+#[derive(Component)]
+struct ConsoleInput;
+
+fn spawn_console(mut commands: Commands, game_assets: Option<Res<GameAssets>>, mut console: ResMut<DevConsole>) {
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    // 18px at 1080p, Vh like the dashboard.
+    let text = TextFont { font: font.into(), font_size: FontSize::Vh(18.0 / 10.8), ..default() };
+
+    commands
+        .spawn((
+            ConsoleRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::bottom(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            BorderColor::all(CONSOLE_TEXT),
+            // Over the debug overlay, which is over everything else.
+            GlobalZIndex(110),
+        ))
+        .with_children(|panel| {
+            panel.spawn((ConsoleOutput, Text::default(), text.clone(), TextColor(CONSOLE_TEXT.with_alpha(0.8))));
+            panel.spawn((ConsoleInput, Text::default(), text, TextColor(CONSOLE_TEXT)));
+        });
+    // For sync_console to fill the new panel in.
+    console.set_changed();
+}
+
+fn sync_console(
+    console: Res<DevConsole>,
+    mut output: Query<&mut Text, (With<ConsoleOutput>, Without<ConsoleInput>)>,
+    mut input: Query<&mut Text, With<ConsoleInput>>,
+) {
+    for mut text in &mut output {
+        text.0 = console.visible().collect::<Vec<_>>().join("\n");
+    }
+    for mut text in &mut input {
+        text.0 = format!("> {}_", console.input);
+    }
+}
+
+fn despawn_console(mut commands: Commands, roots: Query<Entity, With<ConsoleRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn lines_split_on_spaces_except_in_quotes() {
+        assert_eq!(split_words("  tp 10   4 "), Ok(words(&["tp", "10", "4"])));
+        assert_eq!(split_words(r#"flag set "two words""#), Ok(words(&["flag", "set", "two words"])));
+        assert_eq!(split_words(r#"say """#), Ok(words(&["say", ""])));
+        assert_eq!(split_words(""), Ok(vec![]));
+        assert_eq!(split_words(r#"flag set "open"#), Err("unterminated quote".to_string()));
+    }
+
+    #[test]
+    fn arguments_parse_or_say_why_not() {
+        assert_eq!(parse_tile(&words(&["10", "4"])), Ok((10, 4)));
+        assert!(parse_tile(&words(&["10"])).is_err());
+        assert_eq!(parse_tile(&words(&["-1", "4"])), Err(r#""-1" isn't a tile coordinate"#.to_string()));
+        assert_eq!(parse_flag(&words(&["set", "met_isabella"])), Ok(FlagCommand::Set("met_isabella".to_string())));
+        assert_eq!(parse_flag(&words(&["clear", "met_isabella"])), Ok(FlagCommand::Clear("met_isabella".to_string())));
+        assert!(parse_flag(&words(&["toggle", "met_isabella"])).is_err());
+        assert_eq!(parse_state(&words(&["playing"])), Ok(GameState::Playing));
+        assert_eq!(parse_dialogue(&words(&["start", "isabella"])), Ok("isabella".to_string()));
+        assert_eq!(parse_give(&words(&["achievement", "first_steps"])), Ok("first_steps".to_string()));
+        assert!(parse_give(&words(&["gold", "100"])).is_err());
+        assert!(parse_telemetry(&words(&["flush", "now"])).is_err());
+    }
+
+    #[test]
+    fn commands_run_by_name_and_report_bad_usage() {
+        let mut commands = ConsoleCommands::default();
+        commands.add(ConsoleCommand::new("add", "<a> <b>", "adds", parse_tile, |(a, b), _| Ok((a + b).to_string())));
+        let mut world = World::new();
+        assert_eq!(commands.execute("add 2 3", &mut world), Ok("5".to_string()));
+        assert_eq!(
+            commands.execute("add 2", &mut world),
+            Err("needs a tile x and y (usage: add <a> <b>)".to_string())
+        );
+        assert!(commands.execute("subtract 2 3", &mut world).unwrap_err().contains("unknown command"));
+        assert_eq!(commands.execute("help", &mut world), Ok("add <a> <b> - adds".to_string()));
+    }
+
+    #[test]
+    fn tab_completes_command_names() {
+        let mut commands = ConsoleCommands::default();
+        for command in built_in_commands() {
+            commands.add(command);
+        }
+        assert_eq!(commands.complete("t"), vec!["telemetry", "tp"]);
+
+        let mut console = DevConsole { input: "te".to_string(), ..default() };
+        console.complete(&commands);
+        assert_eq!(console.input, "telemetry ");
+
+        console.input = "t".to_string();
+        console.complete(&commands);
+        assert_eq!(console.input, "t");
+        assert_eq!(console.output().last(), Some("telemetry  tp"));
+
+        console.input = "he".to_string();
+        console.complete(&commands);
+        assert_eq!(console.input, "help ");
+    }
+
+    #[test]
+    fn output_scrolls_back_and_keeps_the_newest_lines() {
+        let mut console = DevConsole::default();
+        for n in 0..OUTPUT_LINES + 5 {
+            console.print(&n.to_string());
+        }
+        assert_eq!(console.output().next(), Some("5"));
+        assert_eq!(console.visible().last(), Some((OUTPUT_LINES + 4).to_string().as_str()));
+
+        console.scroll_by(10);
+        assert_eq!(console.visible().last(), Some((OUTPUT_LINES - 6).to_string().as_str()));
+        console.scroll_by(10_000);
+        assert_eq!(console.visible().next(), Some("5"), "no further back than the oldest");
+        console.scroll_by(-10_000);
+        assert_eq!(console.scroll, 0);
+    }
+}
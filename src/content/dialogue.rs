@@ -0,0 +1,531 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use super::{Issue, MapLoadError, ValidationOptions, authored_text, is_slug, normalize_newlines, slug};
+
+/// RPGMaker MZ face sheets are a 4-column x 2-row grid whatever the
+/// sheet's pixel size (see dialogue.rs's `FACE_SHEET_CELL_SIZE`), so a
+/// `face_index` is 0-7.
+pub const FACE_SHEET_COLUMNS: u32 = 4;
+pub const FACE_SHEET_ROWS: u32 = 2;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct DialogueData {
+    /// The conversation's id (see `id()`): what its traces, and "read it
+    /// already" in saves, are keyed on, qualified by the file it's in -
+    /// so renaming `speaker` changes neither. Unique per map, counting the
+    /// dialogue files its NPCs use. Derived from `speaker` when left out,
+    /// with a warning at load. A rewrite the player should read again
+    /// wants a new id.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Story flags this conversation needs, all of them (see
+    /// `DialogueCondition`): an NPC says the first of its `dialogues`
+    /// whose conditions hold (see map.rs's `NpcData::dialogues`). Defaults
+    /// to none - said whatever the flags.
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+    pub speaker: String,
+    pub portrait: String,
+    /// Which cell of `portrait`'s face sheet to display (RPGMaker MZ code-101
+    /// "Show Face" `faceIndex`, 0-7 in the standard 4-column x 2-row 144x144px
+    /// grid layout - see tools/convert_maps.py's
+    /// `extract_dialogue_from_commands`). Defaults to 0 (top-left cell) for
+    /// map JSON predating this field.
+    #[serde(default)]
+    pub face_index: u32,
+    /// One box each, in order - unless a line's `choices` or `end` say
+    /// otherwise (see `DialogueLine`).
+    pub lines: Vec<DialogueLine>,
+    /// Mood for the whole conversation (see mood.rs): tints the portrait
+    /// and speaker name. Defaults to none (neutral).
+    #[serde(default)]
+    pub mood: Option<String>,
+    /// How fast the lines type, relative to the player's setting (see
+    /// dialogue.rs's `DialogueSettings`): 0.5 for a slow, deliberate
+    /// character, 2.0 for an excitable one. Defaults to 1.0.
+    #[serde(default)]
+    pub text_speed: Option<f32>,
+    /// What reading this conversation to the end does (see
+    /// `DialogueOutcome`). Defaults to nothing.
+    #[serde(default)]
+    pub on_complete: Vec<DialogueOutcome>,
+    /// Hub characters (mentors): after `lines`, a menu of topics to ask
+    /// about, returned to after each one until the player says goodbye
+    /// (see dialogue.rs's `TopicMenu`). `on_complete` applies at goodbye.
+    /// Defaults to none - a plain, linear conversation.
+    #[serde(default)]
+    pub topics: Vec<DialogueTopic>,
+    /// Where the box sits for this conversation (see `DialogueBoxLayout`).
+    /// What it leaves out comes from the map's `box_position` and sizes.
+    #[serde(flatten)]
+    pub box_layout: DialogueBoxLayout,
+}
+
+/// Which edge of the window the dialogue box sits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoxPosition {
+    /// Out of the way of something happening at the bottom of the screen.
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// The box's size when nothing says otherwise, in percent of the window:
+/// the bottom third, full width.
+pub const BOX_HEIGHT_PERCENT: f32 = 33.3;
+pub const BOX_WIDTH_PERCENT: f32 = 100.0;
+
+/// The sizes a box may be given. Below a quarter of the window the box's
+/// three rows of text (dialogue.rs's `BOX_ROWS`) no longer fit; above half
+/// it hides the scene it's talking about.
+pub const BOX_HEIGHT_RANGE: std::ops::RangeInclusive<f32> = 25.0..=50.0;
+pub const BOX_WIDTH_RANGE: std::ops::RangeInclusive<f32> = 50.0..=100.0;
+
+/// Where the dialogue box sits and how big it is, as authored on a map, a
+/// conversation or a scripted scene's box:
+///
+/// ```json
+/// { "box_position": "top", "box_height": 25, "box_width": 80 }
+/// ```
+///
+/// Sizes are percent of the window; a narrower box is centered. Every
+/// field is optional - see `or`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct DialogueBoxLayout {
+    pub box_position: Option<BoxPosition>,
+    pub box_width: Option<f32>,
+    pub box_height: Option<f32>,
+}
+
+impl DialogueBoxLayout {
+    /// This layout, with what it leaves out taken from `fallback`.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            box_position: self.box_position.or(fallback.box_position),
+            box_width: self.box_width.or(fallback.box_width),
+            box_height: self.box_height.or(fallback.box_height),
+        }
+    }
+
+    pub fn position(&self) -> BoxPosition {
+        self.box_position.unwrap_or_default()
+    }
+
+    /// Percent of the window. One outside `BOX_WIDTH_RANGE` (see `problem`)
+    /// reads as the default.
+    pub fn width(&self) -> f32 {
+        self.box_width.filter(|width| BOX_WIDTH_RANGE.contains(width)).unwrap_or(BOX_WIDTH_PERCENT)
+    }
+
+    /// Percent of the window. One outside `BOX_HEIGHT_RANGE` reads as the
+    /// default.
+    pub fn height(&self) -> f32 {
+        self.box_height.filter(|height| BOX_HEIGHT_RANGE.contains(height)).unwrap_or(BOX_HEIGHT_PERCENT)
+    }
+
+    /// Why this layout can't be used as authored, if it can't: a width or
+    /// height outside `BOX_WIDTH_RANGE` / `BOX_HEIGHT_RANGE`.
+    pub fn problem(&self) -> Option<String> {
+        let sizes = [("box_width", self.box_width, BOX_WIDTH_RANGE), ("box_height", self.box_height, BOX_HEIGHT_RANGE)];
+        sizes.into_iter().find_map(|(name, size, range)| {
+            let size = size.filter(|size| !range.contains(size))?;
+            Some(format!("{name} {size} isn't between {} and {} percent", range.start(), range.end()))
+        })
+    }
+}
+
+/// One line of dialogue. Usually just its text (`"Welcome."`); a line
+/// that branches is an object:
+///
+/// ```json
+/// { "text": "Which team?", "choices": [
+///     { "label": "Disco", "goto": "disco" },
+///     { "label": "Never mind", "goto": 4 } ] }
+/// ```
+///
+/// Once the line has typed out, the player picks one of `choices` and the
+/// conversation goes on from its `goto`: a line index, or the `id` of a
+/// line. `"end": true` ends the conversation after the line - the last
+/// line of a branch. Without either, the next line follows.
+///
+/// `"portrait": "casey_angry"` changes the speaker's face for that line.
+/// `"speaker": "Amy"` gives the line to someone else, for a back-and-forth
+/// in one conversation - with the line's `portrait`, or none. `"actions"`
+/// make the line do something (see `LineAction`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "AuthoredLine")]
+pub struct DialogueLine {
+    /// May carry markup - `{red}...{/red}`, `{b}...{/b}`, `{pause:0.5}`
+    /// (see dialogue.rs's `parse_markup`).
+    pub text: String,
+    /// A name for choices to `goto`, unique within the conversation.
+    pub id: Option<String>,
+    pub choices: Vec<DialogueChoice>,
+    pub end: bool,
+    /// This line's portrait, by name like `DialogueData::portrait` (same
+    /// `face_index`). None: the conversation's.
+    pub portrait: Option<String>,
+    /// Who says this line. None: the conversation's `speaker`.
+    pub speaker: Option<String>,
+    /// What reading past this line does (see `LineAction`).
+    pub actions: Vec<LineAction>,
+}
+
+/// Something a line does as the player reads past it, once per
+/// conversation however often they come back to it:
+/// `"actions": [{"set_flag": "met_casey"}, {"change_scene": "TeamMarathon"}]`.
+/// A scene change waits for the conversation to close. Lines skipped over
+/// (Tab on a seen conversation) or never reached (Escape) do nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineAction {
+    /// Set a story flag (flags.rs).
+    SetFlag(String),
+    /// Go to a scene, by `game_state::Scene` variant name (see
+    /// `map::SCENE_NAMES`), arriving at its default spawn.
+    ChangeScene(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DialogueChoice {
+    pub label: String,
+    pub goto: LineTarget,
+}
+
+/// Where a choice leads: `3` or `"disco"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum LineTarget {
+    Index(usize),
+    Id(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AuthoredLine {
+    Text(String),
+    Node {
+        text: String,
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        choices: Vec<DialogueChoice>,
+        #[serde(default)]
+        end: bool,
+        #[serde(default)]
+        portrait: Option<String>,
+        #[serde(default)]
+        speaker: Option<String>,
+        #[serde(default)]
+        actions: Vec<LineAction>,
+    },
+}
+
+impl From<AuthoredLine> for DialogueLine {
+    fn from(line: AuthoredLine) -> Self {
+        match line {
+            AuthoredLine::Text(text) => text.into(),
+            AuthoredLine::Node { text, id, choices, end, portrait, speaker, actions } => {
+                Self { text, id, choices, end, portrait, speaker, actions }
+            }
+        }
+    }
+}
+
+impl From<String> for DialogueLine {
+    fn from(text: String) -> Self {
+        Self { text, id: None, choices: Vec::new(), end: false, portrait: None, speaker: None, actions: Vec::new() }
+    }
+}
+
+impl From<&str> for DialogueLine {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// One entry of a hub's topic menu: `{"id": "slos", "label": "SLOs",
+/// "lines": [...]}`, optionally `"requires_flag"` to offer it only once
+/// the flag is set.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DialogueTopic {
+    /// Stable name for telemetry (`game.dialogue.topic_selected`).
+    pub id: String,
+    pub label: String,
+    pub lines: Vec<String>,
+    #[serde(default)]
+    pub requires_flag: Option<String>,
+}
+
+/// Something a conversation does once it's over. Map JSON, on an NPC's
+/// dialogue: `"on_complete": [{"spawn_npc": "Vendor"}, {"set_flag": "x"}]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueOutcome {
+    /// Spawn the current map's `spawnable` NPC with this id - or name
+    /// (npc_spawning.rs).
+    SpawnNpc(String),
+    /// Set a story flag (flags.rs).
+    SetFlag(String),
+}
+
+/// A story flag (flags.rs) a conversation needs set or unset:
+/// `{"has_flag": "met_casey"}` or `{"not_flag": "quest_done"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueCondition {
+    HasFlag(String),
+    NotFlag(String),
+}
+
+impl DialogueCondition {
+    pub fn flag(&self) -> &str {
+        match self {
+            DialogueCondition::HasFlag(flag) | DialogueCondition::NotFlag(flag) => flag,
+        }
+    }
+
+    /// Whether it holds, given which flags are set.
+    pub fn holds(&self, is_set: impl Fn(&str) -> bool) -> bool {
+        match self {
+            DialogueCondition::HasFlag(flag) => is_set(flag),
+            DialogueCondition::NotFlag(flag) => !is_set(flag),
+        }
+    }
+}
+
+/// As traces show it: `has_flag:met_casey`.
+impl fmt::Display for DialogueCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialogueCondition::HasFlag(flag) => write!(f, "has_flag:{flag}"),
+            DialogueCondition::NotFlag(flag) => write!(f, "not_flag:{flag}"),
+        }
+    }
+}
+
+impl DialogueData {
+    /// `id` as authored, else `speaker`'s slug.
+    pub fn id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| slug(&self.speaker))
+    }
+
+    /// Why `id()` can't key anything, if it can't: an authored id that
+    /// isn't a slug, or a speaker with nothing to make one from.
+    pub fn id_problem(&self) -> Option<String> {
+        match &self.id {
+            Some(id) if !is_slug(id) => Some(format!("id {id:?} isn't lowercase letters, digits and underscores")),
+            None if slug(&self.speaker).is_empty() => {
+                Some(format!("has no id, and speaker {:?} doesn't make one", self.speaker))
+            }
+            _ => None,
+        }
+    }
+
+    /// Who says `line`: its own `speaker`, else the conversation's.
+    pub fn speaker_of<'a>(&'a self, line: &'a DialogueLine) -> &'a str {
+        line.speaker.as_deref().unwrap_or(&self.speaker)
+    }
+
+    /// The portrait `line` shows, by name: its own, else the
+    /// conversation's - unless someone else says it. Empty: none.
+    pub fn portrait_of<'a>(&'a self, line: &'a DialogueLine) -> &'a str {
+        match &line.portrait {
+            Some(portrait) => portrait,
+            None if self.speaker_of(line) != self.speaker => "",
+            None => &self.portrait,
+        }
+    }
+
+    /// Every scene a line's `change_scene` goes to, with the line's index.
+    pub fn scene_changes(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines.iter().enumerate().flat_map(|(index, line)| {
+            line.actions.iter().filter_map(move |action| match action {
+                LineAction::ChangeScene(scene) => Some((index, scene.as_str())),
+                LineAction::SetFlag(_) => None,
+            })
+        })
+    }
+
+    /// `change_scene` actions going to a scene that isn't one of
+    /// `options.known_scenes` (when it lists any).
+    pub fn unknown_scenes<'a>(&'a self, options: &'a ValidationOptions) -> impl Iterator<Item = String> + 'a {
+        self.scene_changes()
+            .filter(|(_, scene)| {
+                !options.known_scenes.is_empty() && !options.known_scenes.iter().any(|known| known.as_str() == *scene)
+            })
+            .map(|(index, scene)| format!("line {index} changes scene to {scene:?}, which isn't a scene"))
+    }
+
+    /// The line `target` leads to, if there is one.
+    pub fn resolve(&self, target: &LineTarget) -> Option<usize> {
+        match target {
+            LineTarget::Index(index) => (*index < self.lines.len()).then_some(*index),
+            LineTarget::Id(id) => self.lines.iter().position(|line| line.id.as_ref() == Some(id)),
+        }
+    }
+
+    /// Why the branching can't play as authored, if it can't: a choice
+    /// going nowhere, two lines with one id, or a line that both offers
+    /// choices and ends.
+    pub fn branch_problem(&self) -> Option<String> {
+        for (index, line) in self.lines.iter().enumerate() {
+            if let Some(id) = &line.id
+                && self.lines[..index].iter().any(|earlier| earlier.id.as_ref() == Some(id))
+            {
+                return Some(format!("line id {id:?} is used twice"));
+            }
+            if line.end && !line.choices.is_empty() {
+                return Some(format!("line {index} both ends the conversation and offers choices"));
+            }
+            if let Some(choice) = line.choices.iter().find(|choice| self.resolve(&choice.goto).is_none()) {
+                return Some(format!("choice {:?} on line {index} goes to no line ({:?})", choice.label, choice.goto));
+            }
+        }
+        None
+    }
+
+    /// Why this dialogue can't be shown as authored, if it can't: no lines
+    /// at all (the NPC would silently ignore E) or a topic without any,
+    /// branching that can't play (`branch_problem`), a face index off the
+    /// end of the 4x2 face sheet grid, a `text_speed` of zero or less, or a
+    /// box size out of range (`DialogueBoxLayout::problem`).
+    pub fn problem(&self) -> Option<String> {
+        if self.lines.iter().all(|line| line.text.trim().is_empty()) {
+            return Some("has no dialogue lines".to_string());
+        }
+        if let Some(topic) = self.topics.iter().find(|topic| topic.lines.iter().all(|line| line.trim().is_empty())) {
+            return Some(format!("topic {:?} has no lines", topic.id));
+        }
+        if let Some(problem) = self.branch_problem() {
+            return Some(problem);
+        }
+        let face_cells = FACE_SHEET_COLUMNS * FACE_SHEET_ROWS;
+        if !self.portrait.is_empty() && self.face_index >= face_cells {
+            return Some(format!("face_index {} is outside the {face_cells}-cell face sheet", self.face_index));
+        }
+        if let Some(speed) = self.text_speed.filter(|speed| !(*speed > 0.0 && speed.is_finite())) {
+            return Some(format!("text_speed {speed} isn't a positive number"));
+        }
+        self.box_layout.problem()
+    }
+
+    /// A `.dialogue.json` file's bytes, as leniently as `MapData::parse`.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MapLoadError> {
+        parse_dialogue(authored_text(bytes)?)
+    }
+
+    pub(super) fn normalize_newlines(&mut self) {
+        for line in &mut self.lines {
+            normalize_newlines(&mut line.text);
+            line.choices.iter_mut().for_each(|choice| normalize_newlines(&mut choice.label));
+        }
+        for topic in &mut self.topics {
+            topic.lines.iter_mut().for_each(normalize_newlines);
+        }
+    }
+}
+
+/// A `.dialogue.json` file's text. A BOM is tolerated and CRLFs become
+/// `\n`, as for maps.
+pub fn parse_dialogue(json: &str) -> Result<DialogueData, MapLoadError> {
+    let mut dialogue: DialogueData = serde_json::from_str(json.strip_prefix('\u{feff}').unwrap_or(json))?;
+    dialogue.normalize_newlines();
+    Ok(dialogue)
+}
+
+/// Everything wrong with a dialogue file (see `DialogueData::problem`),
+/// and - when `options` lists the scenes - lines changing to a scene that
+/// doesn't exist.
+pub fn validate_dialogue(dialogue: &DialogueData, options: &ValidationOptions) -> Vec<Issue> {
+    dialogue
+        .problem()
+        .into_iter()
+        .chain(dialogue.id_problem())
+        .chain(dialogue.unknown_scenes(options))
+        .map(|problem| Issue::new(format!("dialogue {problem}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_dialogue_file_is_judged_on_its_own() {
+        let dialogue =
+            parse_dialogue(r#"{ "speaker": "Casey", "portrait": "Casey", "face_index": 9, "lines": ["Hi."] }"#).unwrap();
+        let issues = validate_dialogue(&dialogue, &ValidationOptions::default());
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("face_index 9"), "{}", issues[0]);
+    }
+
+    #[test]
+    fn a_line_may_change_the_portrait() {
+        let dialogue = parse_dialogue(
+            r#"{ "speaker": "Casey", "portrait": "casey", "lines": [
+                "Hi.", { "text": "WHAT.", "portrait": "casey_angry" } ] }"#,
+        )
+        .unwrap();
+        assert_eq!(dialogue.lines[0].portrait, None);
+        assert_eq!(dialogue.lines[1].portrait.as_deref(), Some("casey_angry"));
+    }
+
+    #[test]
+    fn a_line_may_be_someone_elses() {
+        let dialogue = parse_dialogue(
+            r#"{ "speaker": "Casey", "portrait": "casey", "lines": [
+                "Hi.", { "speaker": "Amy", "text": "Hey." },
+                { "speaker": "Amy", "text": "Well?", "portrait": "Amy" }, { "speaker": "Casey", "text": "Well." } ] }"#,
+        )
+        .unwrap();
+        let said: Vec<_> =
+            dialogue.lines.iter().map(|line| (dialogue.speaker_of(line), dialogue.portrait_of(line))).collect();
+        assert_eq!(said, [("Casey", "casey"), ("Amy", ""), ("Amy", "Amy"), ("Casey", "casey")]);
+    }
+
+    #[test]
+    fn a_line_may_act_as_it_is_read() {
+        let dialogue = parse_dialogue(
+            r#"{ "speaker": "Casey", "portrait": "", "lines": [
+                "Hi.", { "text": "Off you go.", "actions": [{ "set_flag": "met_casey" }, { "change_scene": "Narnia" }] } ] }"#,
+        )
+        .unwrap();
+        assert!(dialogue.lines[0].actions.is_empty());
+        assert_eq!(
+            dialogue.lines[1].actions,
+            [LineAction::SetFlag("met_casey".into()), LineAction::ChangeScene("Narnia".into())]
+        );
+        assert!(validate_dialogue(&dialogue, &ValidationOptions::default()).is_empty());
+
+        let options =
+            ValidationOptions { known_scenes: crate::content::map::SCENE_NAMES.map(String::from).to_vec(), ..Default::default() };
+        let issues = validate_dialogue(&dialogue, &options);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("line 1 changes scene to \"Narnia\""), "{}", issues[0]);
+    }
+
+    #[test]
+    fn a_conversation_may_move_the_box_within_limits() {
+        let dialogue = parse_dialogue(
+            r#"{ "speaker": "Casey", "portrait": "", "lines": ["Up here."], "box_position": "top", "box_width": 80 }"#,
+        )
+        .unwrap();
+        assert_eq!(dialogue.problem(), None);
+        let map_default = DialogueBoxLayout { box_height: Some(25.0), box_width: Some(60.0), ..Default::default() };
+        let layout = dialogue.box_layout.or(map_default);
+        assert_eq!((layout.position(), layout.width(), layout.height()), (BoxPosition::Top, 80.0, 25.0));
+
+        let unset = DialogueBoxLayout::default();
+        assert_eq!((unset.position(), unset.width(), unset.height()), (BoxPosition::Bottom, 100.0, 33.3));
+
+        let squashed = DialogueBoxLayout { box_height: Some(5.0), ..Default::default() };
+        assert_eq!(squashed.height(), BOX_HEIGHT_PERCENT, "out of range reads as the default");
+        assert!(squashed.problem().is_some_and(|problem| problem.contains("box_height 5")));
+        assert!(parse_dialogue(r#"{ "speaker": "", "portrait": "", "lines": ["Hi."], "box_position": "middle" }"#).is_err());
+    }
+}
@@ -0,0 +1,1536 @@
+use serde::Deserialize;
+
+use super::dialogue::{DialogueBoxLayout, DialogueData, DialogueLine, DialogueOutcome, LineAction};
+use super::{Issue, MapLoadError, ValidationOptions, authored_text, is_slug, normalize_newlines, slug};
+
+#[derive(Debug, Deserialize)]
+pub struct MapData {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Interior map: no drop shadows (see shadow.rs). Baked by
+    /// tools/convert_maps.py from the tileset; defaults to false (outdoor)
+    /// for map JSON predating this field.
+    #[serde(default)]
+    pub indoor: bool,
+    /// Ground-layer atlas indices, one per cell (row-major, RPGMaker
+    /// orientation: row 0 is the TOP row of the map, matching the source
+    /// Map*.json data planes). Index 0 is a reserved fully-transparent tile.
+    /// The top-down -> bottom-up (+y up) conversion happens exactly once, at
+    /// the world boundary: `tile_to_world`/`world_to_tile` here and the
+    /// `TilePos` mapping in tilemap.rs::spawn_tile_layer. May be left out of a
+    /// map that lists its `layers` instead.
+    #[serde(default)]
+    pub tiles: Vec<u32>,
+    /// Upper-layer (drawn above the player/NPCs) atlas indices into the
+    /// *same* atlas as `tiles`, same shape as `tiles`. 0 means "no
+    /// upper-layer decoration on this cell". Produced by
+    /// tools/convert_maps.py from RPGMaker's per-tile 0x10 "higher" flag.
+    /// Defaults to empty for map JSON predating this field; an absent index
+    /// renders as tile 0 (blank), same as an explicit empty array.
+    #[serde(default)]
+    pub upper_tiles: Vec<u32>,
+    /// Tile layers, bottom to top, for maps wanting more than `tiles` and
+    /// `upper_tiles`: a path over the grass, canopies over the path (see
+    /// `TileLayerData`). When present, `tiles` and `upper_tiles` are
+    /// ignored. Defaults to empty: `tiles` as the one ground layer,
+    /// `upper_tiles` overhead (see `MapData::tile_layers`).
+    #[serde(default)]
+    pub layers: Vec<TileLayerData>,
+    /// Tiles that play as animations - water, torches - keyed by the atlas
+    /// index placed in the tile layers: `{"17": {"frames": [17, 18, 19],
+    /// "frame_seconds": 0.25}}`. Every cell holding that index steps
+    /// through `frames` together (see tile_animation.rs). Defaults to none.
+    #[serde(default)]
+    pub animated_tiles: std::collections::BTreeMap<u32, AnimatedTileData>,
+    /// Per-cell fully-blocked flag (row-major, same shape as `tiles`),
+    /// baked from RPGMaker tileset passability flags by
+    /// tools/convert_maps.py. See CollisionMap in tilemap.rs. Defaults to
+    /// empty for map JSON predating this field, which blocks only the map's
+    /// border; a layer shorter than the map reads its missing cells as
+    /// blocked (fail closed), not walkable - and fails validation.
+    /// Superseded by `passability` when present - kept as the coarse
+    /// fallback for older JSON.
+    #[serde(default)]
+    pub collision: Vec<bool>,
+    /// Per-cell 4-bit directional passability masks (row-major, same shape
+    /// as `tiles`), baked from RPGMaker's Game_Map.checkPassage semantics:
+    /// bit set = can move OUT of this cell in that direction. Bit values
+    /// match RPGMaker's flag nibble and tilemap.rs's PASS_* constants:
+    /// 1=down, 2=left, 4=right, 8=up (down = +y in RPGMaker orientation).
+    /// This is what represents shop counters, storefront edges, and wall
+    /// bands that are passable from some sides only. Defaults to empty for
+    /// map JSON predating this field (tilemap.rs then falls back to
+    /// `collision`).
+    #[serde(default)]
+    pub passability: Vec<u8>,
+    /// Sparse [x, y] list of counter cells (RPGMaker's Counter tile flag,
+    /// 0x80): the action button reaches ONE tile across a counter, which is
+    /// how shopkeepers standing behind counters are talkable. Baked by
+    /// tools/convert_maps.py; defaults to empty for map JSON predating this
+    /// field. See handle_interaction_input in npc.rs.
+    #[serde(default)]
+    pub counters: Vec<(u32, u32)>,
+    /// Sparse [x, y] list of tiles that pulse a soft "interact here"
+    /// highlight (the retro table's parchment map, the End fairies, ...).
+    /// Purely visual, decoupled from exit trigger tiles so the marker can
+    /// sit on the eye-catching graphic while the trigger stays on the
+    /// walkable tile(s). Baked by tools/convert_maps.py's synthesis passes;
+    /// defaults to empty for map JSON predating this field.
+    #[serde(default)]
+    pub indicators: Vec<(u32, u32)>,
+    /// Sparse [x, y] list of telemetry terminals: Interact while facing
+    /// one opens the game's own live dashboard (dashboard.rs). The screen
+    /// art is the map's; these cells block movement and get an indicator.
+    /// Defaults to empty.
+    #[serde(default)]
+    pub terminals: Vec<(u32, u32)>,
+    /// Looping background sounds layered under everything else - wind, a
+    /// crowd, a server room's hum (see soundscape.rs). Defaults to silence.
+    #[serde(default)]
+    pub ambience: Vec<AmbienceData>,
+    pub npcs: Vec<NpcData>,
+    /// Where the player starts when the map is entered other than through
+    /// an exit (which names its own spawn tile): a new game, a debug jump.
+    /// Defaults to none - the player stays where they are, the world
+    /// origin in a new game.
+    #[serde(default)]
+    pub player_spawn: Option<PlayerSpawnData>,
+    /// Doors and warp tiles out of the map. Also read as `warps`.
+    #[serde(default, alias = "warps")]
+    pub exits: Vec<ExitData>,
+    /// Visible door sprites sitting on exit trigger tiles (the town's
+    /// `!doors` events). Purely visual - the exit logic itself lives in
+    /// `exits`. Defaults to empty for map JSON predating this field.
+    #[serde(default)]
+    pub doors: Vec<DoorData>,
+    /// Ambient visual props: image-bearing events with no dialogue and no
+    /// transfer (doggo, The Boss's Truck). Defaults to empty for map JSON
+    /// predating this field.
+    #[serde(default)]
+    pub props: Vec<PropData>,
+    /// Things that can be read rather than talked to: signs, terminals,
+    /// runbooks (see map_objects.rs). Defaults to none.
+    #[serde(default)]
+    pub objects: Vec<ObjectData>,
+    /// NPCs that aren't placed when the map loads, only on request - a
+    /// dialogue's `spawn_npc` outcome names one (see npc_spawning.rs).
+    /// Defaults to empty.
+    #[serde(default)]
+    pub spawnable: Vec<NpcData>,
+    /// Areas that hold the camera to a smaller rectangle than the whole map
+    /// (an interior drawn inside a bigger map), optionally zoomed in. See
+    /// camera.rs. Defaults to empty: the map's own bounds everywhere.
+    #[serde(default)]
+    pub camera_zones: Vec<CameraZoneData>,
+    /// NPCs who talk among themselves when the player comes near (see
+    /// group_conversation.rs). Defaults to none.
+    #[serde(default)]
+    pub group_dialogues: Vec<GroupDialogueData>,
+    /// The scripts `group_dialogues` play, by `dialogue_ref`. Defaults to
+    /// none.
+    #[serde(default)]
+    pub conversations: std::collections::BTreeMap<String, Vec<GroupLineData>>,
+    /// Where the dialogue box sits on this map, for conversations that
+    /// don't say (see `DialogueBoxLayout`). Defaults to the bottom third.
+    #[serde(flatten)]
+    pub box_layout: DialogueBoxLayout,
+}
+
+/// `MapData::player_spawn`: `{"x": 16, "y": 23, "facing": "up"}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlayerSpawnData {
+    pub x: u32,
+    pub y: u32,
+    /// One of `FACINGS`. Defaults to none - the way the player was facing.
+    #[serde(default)]
+    pub facing: Option<String>,
+}
+
+impl PlayerSpawnData {
+    /// Why the player won't start here as authored, if they won't: off the
+    /// `width` x `height` map, or a facing that isn't one of `FACINGS`.
+    pub fn problem(&self, width: u32, height: u32) -> Option<String> {
+        if self.x >= width || self.y >= height {
+            return Some(format!("player_spawn ({}, {}) is off the {width}x{height} map", self.x, self.y));
+        }
+        let facing = self.facing.as_deref().filter(|facing| !FACINGS.contains(facing))?;
+        Some(format!("player_spawn faces {facing:?}, not one of {}", FACINGS.join(", ")))
+    }
+}
+
+/// One layer of tiles: `{"name": "canopy", "tiles": [...], "overhead":
+/// true}`. Same shape as `MapData::tiles` and indices into the same atlas,
+/// with 0 an empty cell - nothing drawn there.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TileLayerData {
+    /// For problems and debugging; defaults to none.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub tiles: Vec<u32>,
+    /// Drawn above the player and NPCs (tree canopies, roofs) rather than
+    /// under them. Defaults to false.
+    #[serde(default)]
+    pub overhead: bool,
+    /// Order within its band - ground or overhead - from 0 to
+    /// `MAX_LAYER_Z`, higher on top. Defaults to 0; layers at the same `z`
+    /// stack in listed order.
+    #[serde(default)]
+    pub z: f32,
+}
+
+/// One of `MapData::animated_tiles`: the atlas indices shown in turn, each
+/// for `frame_seconds`, looping.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AnimatedTileData {
+    pub frames: Vec<u32>,
+    pub frame_seconds: f32,
+}
+
+impl AnimatedTileData {
+    /// Why the animation of `tile` can't play as authored, if it can't: no
+    /// frames, a frame time that isn't a positive number of seconds, or -
+    /// given the tileset's size in tiles, once it is known - a frame past
+    /// its last tile.
+    pub fn problem(&self, tile: u32, tileset_tiles: Option<u32>) -> Option<String> {
+        if self.frames.is_empty() {
+            return Some(format!("animated tile {tile} has no frames"));
+        }
+        if !(self.frame_seconds.is_finite() && self.frame_seconds > 0.0) {
+            return Some(format!("animated tile {tile} frame_seconds {} isn't a positive number", self.frame_seconds));
+        }
+        let tiles = tileset_tiles?;
+        let frame = self.frames.iter().find(|&&frame| frame >= tiles)?;
+        Some(format!("animated tile {tile} frame {frame} is past the tileset's last tile, {}", tiles.saturating_sub(1)))
+    }
+}
+
+/// The highest `TileLayerData::z`: any higher and a ground layer would
+/// reach the door sprites (0.9, see depth.rs).
+pub const MAX_LAYER_Z: f32 = 0.5;
+
+impl TileLayerData {
+    /// What problems call it: its name, else its place in `layers`.
+    pub fn label(&self, index: usize) -> String {
+        self.name.as_ref().map_or_else(|| format!("layers[{index}]"), |name| format!("layer {name:?}"))
+    }
+
+    /// Why this layer (`index` in `layers`) can't be ordered as authored,
+    /// if it can't: a `z` outside 0..=`MAX_LAYER_Z`. Its cell count is
+    /// `MapData::validate`'s to check.
+    pub fn problem(&self, index: usize) -> Option<String> {
+        if !(0.0..=MAX_LAYER_Z).contains(&self.z) {
+            return Some(format!("{} z {} isn't between 0 and {MAX_LAYER_Z}", self.label(index), self.z));
+        }
+        None
+    }
+}
+
+/// One camera zone: while the player's tile is inside `rect`, the camera
+/// stays inside `bounds` (default: `rect` itself) at `zoom` (default 1.0;
+/// 2.0 shows half as much). Rectangles are `[x, y, w, h]` in tiles, RPGMaker
+/// orientation like everything else here. Where zones overlap, the smaller
+/// `rect` wins, so a room can sit inside a district.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CameraZoneData {
+    pub rect: [u32; 4],
+    #[serde(default)]
+    pub bounds: Option<[u32; 4]>,
+    #[serde(default = "default_zoom")]
+    pub zoom: f32,
+}
+
+fn default_zoom() -> f32 {
+    1.0
+}
+
+impl CameraZoneData {
+    pub fn bounds(&self) -> [u32; 4] {
+        self.bounds.unwrap_or(self.rect)
+    }
+
+    pub fn contains(&self, tile_x: i32, tile_y: i32) -> bool {
+        let [x, y, w, h] = self.rect.map(|v| v as i32);
+        (x..x + w).contains(&tile_x) && (y..y + h).contains(&tile_y)
+    }
+
+    pub fn area(&self) -> u32 {
+        self.rect[2] * self.rect[3]
+    }
+
+    /// Why this zone can't be used on a `width` x `height` map, if it
+    /// can't: an empty or out-of-map rectangle, or a zoom that isn't
+    /// positive.
+    pub fn problem(&self, width: u32, height: u32) -> Option<String> {
+        for (name, [x, y, w, h]) in [("rect", self.rect), ("bounds", self.bounds())] {
+            if w == 0 || h == 0 {
+                return Some(format!("camera zone {:?} has an empty {name}", self.rect));
+            }
+            if x.saturating_add(w) > width || y.saturating_add(h) > height {
+                return Some(format!(
+                    "camera zone {:?} {name} {:?} doesn't fit the {width}x{height} map",
+                    self.rect,
+                    [x, y, w, h]
+                ));
+            }
+        }
+        if self.zoom.is_nan() || self.zoom <= 0.0 {
+            return Some(format!("camera zone {:?} zoom {} must be positive", self.rect, self.zoom));
+        }
+        None
+    }
+}
+
+/// One ambience layer: `{"sound": "audio/wind.ogg", "volume": 0.4}` plays
+/// everywhere on the map; with `near_tiles` it's positional, loudest on the
+/// nearest listed tile and fading out to nothing `radius` tiles from it
+/// (default `DEFAULT_AMBIENCE_RADIUS`). `sound` is relative to assets/ and
+/// names the layer, so a map transition between two maps playing the same
+/// sound carries it on rather than fading it out and in again.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AmbienceData {
+    pub sound: String,
+    /// 0 to 1. Defaults to 1.
+    #[serde(default = "default_ambience_volume")]
+    pub volume: f32,
+    #[serde(default)]
+    pub near_tiles: Vec<(u32, u32)>,
+    #[serde(default)]
+    pub radius: Option<f32>,
+}
+
+/// How far, in tiles, a positional layer carries when it doesn't say.
+pub const DEFAULT_AMBIENCE_RADIUS: f32 = 6.0;
+
+fn default_ambience_volume() -> f32 {
+    1.0
+}
+
+impl AmbienceData {
+    /// Heard only near `near_tiles`, rather than map-wide.
+    pub fn is_positional(&self) -> bool {
+        !self.near_tiles.is_empty()
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius.unwrap_or(DEFAULT_AMBIENCE_RADIUS)
+    }
+
+    /// Why this layer can't play on a `width` x `height` map, if it can't: no
+    /// sound, a volume outside 0..=1, a radius that isn't positive or has no
+    /// tiles to be measured from, or a tile off the map.
+    pub fn problem(&self, width: u32, height: u32) -> Option<String> {
+        let sound = &self.sound;
+        if sound.trim().is_empty() {
+            return Some("ambience layer has no sound".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Some(format!("ambience {sound:?} volume {} isn't between 0 and 1", self.volume));
+        }
+        if let Some(radius) = self.radius {
+            if !self.is_positional() {
+                return Some(format!("ambience {sound:?} has a radius but no near_tiles to measure it from"));
+            }
+            if radius.is_nan() || radius <= 0.0 {
+                return Some(format!("ambience {sound:?} radius {radius} must be positive"));
+            }
+        }
+        if let Some(&(x, y)) = self.near_tiles.iter().find(|&&(x, y)| x >= width || y >= height) {
+            return Some(format!("ambience {sound:?} tile ({x}, {y}) is off the {width}x{height} map"));
+        }
+        None
+    }
+}
+
+/// A conversation between NPCs on this map: `{"participants": ["Casey",
+/// "Mando"], "trigger_radius": 200, "dialogue_ref": "standup", "once":
+/// true}`. It starts when the player comes within `trigger_radius` pixels
+/// of the participants' midpoint and plays `conversations[dialogue_ref]`
+/// as speech bubbles. A `once` conversation plays a single time per save;
+/// otherwise it can start again `cooldown_seconds` after it ends.
+/// `dialogue_ref` doubles as the conversation's name in saves and traces,
+/// so it has to be unique across maps.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupDialogueData {
+    pub participants: Vec<String>,
+    pub trigger_radius: f32,
+    pub dialogue_ref: String,
+    #[serde(default)]
+    pub once: bool,
+    #[serde(default = "default_group_cooldown")]
+    pub cooldown_seconds: f32,
+}
+
+fn default_group_cooldown() -> f32 {
+    60.0
+}
+
+/// One bubble of a group conversation. `speaker` is one of the
+/// participants' NPC names.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupLineData {
+    pub speaker: String,
+    pub text: String,
+}
+
+impl GroupDialogueData {
+    /// Why this conversation can't play on `map`, if it can't: fewer than
+    /// two participants, one that isn't an NPC of the map, a script that
+    /// is missing or empty, or a line by someone outside the group or too
+    /// long for a bubble (`MAX_AMBIENT_LINE_CHARS`).
+    pub fn problem(&self, map: &MapData) -> Option<String> {
+        let name = &self.dialogue_ref;
+        if self.participants.len() < 2 {
+            return Some(format!("group dialogue {name:?} needs at least two participants"));
+        }
+        if let Some(missing) = self.participants.iter().find(|participant| {
+            !map.npcs.iter().chain(&map.spawnable).any(|npc| &npc.name == *participant)
+        }) {
+            return Some(format!("group dialogue {name:?} participant {missing:?} isn't an NPC on this map"));
+        }
+        let Some(lines) = map.conversations.get(name).filter(|lines| !lines.is_empty()) else {
+            return Some(format!("group dialogue {name:?} has no conversation with that name, or it's empty"));
+        };
+        for line in lines {
+            if !self.participants.contains(&line.speaker) {
+                return Some(format!("group dialogue {name:?} has a line by {:?}, who isn't taking part", line.speaker));
+            }
+            if line.text.chars().count() > MAX_AMBIENT_LINE_CHARS {
+                return Some(format!(
+                    "group dialogue {name:?} line {:?} is over {MAX_AMBIENT_LINE_CHARS} characters",
+                    line.text
+                ));
+            }
+        }
+        if self.trigger_radius.is_nan() || self.trigger_radius <= 0.0 {
+            return Some(format!("group dialogue {name:?} trigger_radius {} must be positive", self.trigger_radius));
+        }
+        None
+    }
+}
+
+/// One ambient prop sprite. Same sheet-slicing rules as `DoorData`;
+/// `blocks` carries RPGMaker's event collision (priority "same as
+/// characters" + through=false makes the event's tile impassable, which our
+/// tile-flag-baked collision can't know about).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropData {
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub sprite: String,
+    pub sprite_index: u32,
+    pub facing: String,
+    pub pattern: u32,
+    #[serde(default)]
+    pub step_anime: bool,
+    #[serde(default)]
+    pub blocks: bool,
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+/// A readable map object: interacted with like an NPC, but with no
+/// character sheet, portrait or turning to face the player. What it says
+/// reads in a box of its own color (see map_objects.rs).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectData {
+    /// As `NpcData::id`: unique among the map's NPCs and objects. Derived
+    /// from `name` when left out.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Shown as the box's speaker ("Town Notice Board").
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    /// A single still image, relative to assets/ (e.g.
+    /// `textures/objects/runbook.png`). Defaults to none: the map's own
+    /// tiles already draw the object - a sign painted into the tileset.
+    #[serde(default)]
+    pub sprite: Option<String>,
+    /// Read one box each, like a dialogue's (choices and actions too).
+    pub lines: Vec<DialogueLine>,
+}
+
+impl ObjectData {
+    /// `id` as authored, else `name`'s slug.
+    pub fn id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| slug(&self.name))
+    }
+
+    /// What it reads, as a conversation with no portrait.
+    pub fn dialogue(&self) -> DialogueData {
+        DialogueData {
+            id: Some(self.id()),
+            speaker: self.name.clone(),
+            lines: self.lines.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Why it can't be read, if it can't.
+    pub fn problem(&self) -> Option<String> {
+        self.lines.is_empty().then(|| format!("object {:?} has no lines", self.name))
+    }
+}
+
+/// One door sprite. `frame_width`/`frame_height` are baked by
+/// tools/convert_maps.py from the sheet's dimensions (RPGMaker frames are
+/// sheet_width/12 x sheet_height/8; doors.png is 576x768, so door frames
+/// are 48x96 - one tile wide, two tiles tall). `facing` is the resting
+/// animation row ("down" = closed); `pattern` the resting column.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoorData {
+    pub x: u32,
+    pub y: u32,
+    pub sprite: String,
+    pub sprite_index: u32,
+    pub facing: String,
+    pub pattern: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+/// How an exit fires. RPGMaker trigger 0 = Action Button (stand on the
+/// event and press confirm); triggers 1/2 fire on contact. Flattening
+/// action events into touch exits made Map009's "retro dialog" event (by
+/// the inn table) warp unsuspecting players straight to the End scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExitTrigger {
+    #[default]
+    Touch,
+    Action,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExitData {
+    pub trigger_x: u32,
+    pub trigger_y: u32,
+    /// Matches a `Scene` variant name (e.g. "TeamMarathon"), see `scene_from_str`.
+    pub target_scene: String,
+    pub target_spawn_x: u32,
+    pub target_spawn_y: u32,
+    /// Which way the player faces on arrival: "down", "left", "right" or
+    /// "up". Defaults to none - the way they were walking.
+    #[serde(default)]
+    pub target_facing: Option<String>,
+    /// Defaults to Touch for map JSON predating this field.
+    #[serde(default)]
+    pub trigger: ExitTrigger,
+    /// Scripted scene played before the transfer fires (empty for plain
+    /// exits). Each segment is one RPGMaker message box with its own
+    /// speaker/portrait - Map009's "retro dialog" retrospective is the
+    /// motivating case.
+    #[serde(default)]
+    pub dialogue: Vec<DialogueSegmentData>,
+    /// When true, force-closing this exit's dialogue with Escape CANCELS
+    /// the transfer instead of firing it (a consent prompt: the End
+    /// fairies). When false - the default, and the retro retrospective's
+    /// behavior - Escape skips the scene but still transfers, so skipping
+    /// the climax can't strand the player.
+    #[serde(default)]
+    pub cancel_on_escape: bool,
+}
+
+/// The facings map data may name, as `NpcData::facing` and
+/// `ExitData::target_facing` spell them.
+pub const FACINGS: [&str; 4] = ["down", "left", "right", "up"];
+
+impl ExitData {
+    /// Why the player won't face the way this exit says on arrival, if
+    /// they won't: it isn't one of `FACINGS`.
+    pub fn facing_problem(&self) -> Option<String> {
+        let facing = self.target_facing.as_deref().filter(|facing| !FACINGS.contains(facing))?;
+        Some(format!(
+            "exit at ({}, {}) to {} faces the player {facing:?} on arrival, not one of {}",
+            self.trigger_x,
+            self.trigger_y,
+            self.target_scene,
+            FACINGS.join(", ")
+        ))
+    }
+
+    /// Why this exit's scripted scene won't play as authored, if it won't:
+    /// a blank box (dropped at display time - see dialogue.rs) is almost
+    /// always a conversion slip; a box size out of range is shown at the
+    /// default size instead. See content_errors.rs.
+    pub fn dialogue_problem(&self) -> Option<String> {
+        let exit = format!("exit at ({}, {}) to {}", self.trigger_x, self.trigger_y, self.target_scene);
+        self.dialogue.iter().enumerate().find_map(|(index, segment)| {
+            if segment.text.trim().is_empty() {
+                return Some(format!("{exit} has an empty dialogue box (#{})", index + 1));
+            }
+            let problem = segment.box_layout.problem()?;
+            Some(format!("{exit} dialogue box #{} {problem}", index + 1))
+        })
+    }
+}
+
+/// Where a face sheet named in map data lives: `"Nature"` ->
+/// `textures/portraits/Nature.png`. Empty (no portrait) stays empty.
+pub fn portrait_asset_path(name: &str) -> String {
+    if name.is_empty() {
+        String::new()
+    } else {
+        format!("textures/portraits/{name}.png")
+    }
+}
+
+/// One message box of a scripted scene: RPGMaker code-101 parameters plus
+/// the box's joined 401 text.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueSegmentData {
+    pub speaker: String,
+    /// Face sheet name (empty = no portrait), resolved against
+    /// assets/textures/portraits/<name>.png at display time.
+    pub portrait: String,
+    #[serde(default)]
+    pub face_index: u32,
+    pub text: String,
+    /// This box's mood (see mood.rs) - scripted scenes can change it box
+    /// by box. Defaults to none (neutral).
+    #[serde(default)]
+    pub mood: Option<String>,
+    /// Where this box sits - a scene can move the box mid-conversation,
+    /// out of the way of what it's showing. Defaults to the map's.
+    #[serde(flatten)]
+    pub box_layout: DialogueBoxLayout,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NpcData {
+    /// What telemetry, the times-talked count and spawning key on (see
+    /// `id()`), so renaming or translating `name` breaks no dashboard or
+    /// save. Unique per map. Derived from `name` when left out, with a
+    /// warning at load.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub sprite: String,
+    /// Which character slot (0-7) of the `sprite` sheet this NPC uses -
+    /// RPGMaker MZ's `image.characterIndex` (sheets hold a 4x2 grid of
+    /// characters, see character_sheet.rs). Defaults to 0 (top-left slot)
+    /// for map JSON predating this field.
+    #[serde(default)]
+    pub sprite_index: u32,
+    /// RPGMaker's "Stepping Animation": play the walk cycle in place while
+    /// standing still. Nearly every NPC in the original has this enabled.
+    /// Defaults to false (a statue) for map JSON predating this field.
+    #[serde(default)]
+    pub step_anime: bool,
+    /// Random tile-step wandering (doggo). Wandering respects map
+    /// passability even for `through` characters - engine-divergent,
+    /// intent-faithful (see npc_movement.rs). Defaults to false.
+    #[serde(default)]
+    pub wander: bool,
+    /// How far a wanderer may stray from where it's placed, in tiles across
+    /// or up and down. Defaults to anywhere.
+    #[serde(default)]
+    pub wander_radius: Option<u32>,
+    /// Tiles to patrol, `[x, y]`, walked to in order and back to the first
+    /// after the last (see npc_movement.rs). Not with `wander`. Defaults
+    /// to none - the NPC stays put.
+    #[serde(default)]
+    pub path: Vec<(u32, u32)>,
+    /// Walking speed for `wander` and `path`, in tiles per second.
+    /// Defaults to doggo's (npc_movement::DEFAULT_MOVE_SPEED).
+    #[serde(default)]
+    pub move_speed: Option<f32>,
+    /// RPGMaker's Through flag: the character never blocks the player
+    /// (skips the NPC body collider - see NpcBody in npc.rs). Only doggo
+    /// has it in the original. Defaults to false.
+    #[serde(default)]
+    pub through: bool,
+    /// What an E press does while this NPC is mid-step (see npc.rs::Busy):
+    /// `"wait"` queues the conversation until the step lands, `"decline"`
+    /// toasts "They're busy". Defaults to wait.
+    #[serde(default)]
+    pub when_busy: BusyBehavior,
+    /// Only present while this story flag is set (see flags.rs): checked
+    /// when the map spawns and again whenever flags change, so the NPC
+    /// appears (or leaves) mid-visit. Defaults to always present.
+    #[serde(default)]
+    pub requires_flag: Option<String>,
+    /// One-liners the NPC says to no one in particular when the player is
+    /// near (see ambient.rs), at most `MAX_AMBIENT_LINE_CHARS` each.
+    /// Defaults to none - the NPC keeps quiet until talked to.
+    #[serde(default)]
+    pub ambient_lines: Vec<String>,
+    /// How close the player has to be to talk, in pixels (a tile is 48).
+    /// Defaults to npc.rs's `Interactable` radius, 64.
+    #[serde(default)]
+    pub interaction_radius: Option<f32>,
+    /// Shown over the NPC while the player is in range, `{interact}` the
+    /// key or button (see interaction_prompt.rs). Defaults to "Press
+    /// {interact} to talk".
+    #[serde(default)]
+    pub prompt: Option<String>,
+    pub facing: String,
+    /// What the NPC says when talked to, inline. Defaults to nothing, for
+    /// NPCs whose dialogue lives in a `dialogue_file`.
+    #[serde(default)]
+    pub dialogue: DialogueData,
+    /// A `.dialogue.json` asset (e.g. `data/dialogue/casey_intro.dialogue.json`,
+    /// relative to assets/) holding a `DialogueData`, loaded with the map
+    /// and used instead of `dialogue` once it has loaded. Defaults to none.
+    #[serde(default)]
+    pub dialogue_file: Option<String>,
+    /// Conversations said instead of `dialogue` while their `conditions`
+    /// hold, tried in order - the first that holds is said (see npc.rs's
+    /// `NpcDialogue::select`). `dialogue` (or `dialogue_file`) is the
+    /// default, for when none do; it may be left out if one of these has
+    /// no conditions. Defaults to none.
+    #[serde(default)]
+    pub dialogues: Vec<DialogueData>,
+    /// Whether the NPC says its dialogue every time it's talked to:
+    /// `"always"`, `"once"`, or a number of seconds before it will again.
+    /// In between it says `repeat_line`. Defaults to always.
+    #[serde(default)]
+    pub repeat: DialogueRepeat,
+    /// What the NPC says instead of a dialogue it won't repeat ("We already
+    /// covered the postmortem."). Defaults to `DEFAULT_REPEAT_LINE`.
+    #[serde(default)]
+    pub repeat_line: Option<String>,
+}
+
+/// Per-NPC answer to an E press while busy (map JSON `when_busy`; see
+/// npc.rs's `Busy`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BusyBehavior {
+    /// Queue the conversation until the step lands, with a "..." emote so
+    /// the press visibly registered.
+    #[default]
+    Wait,
+    /// Refuse with a "They're busy" toast.
+    Decline,
+}
+
+/// How often an NPC says its dialogue (map JSON `repeat`; see npc.rs's
+/// `NpcDialogueState`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(try_from = "RepeatJson")]
+pub enum DialogueRepeat {
+    /// Every time.
+    #[default]
+    Always,
+    /// The first time only.
+    Once,
+    /// Again once this many seconds have passed since it was last said.
+    Cooldown(f32),
+}
+
+/// `repeat` as written: a word or a number of seconds.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RepeatJson {
+    Word(String),
+    Seconds(f32),
+}
+
+impl TryFrom<RepeatJson> for DialogueRepeat {
+    type Error = String;
+
+    fn try_from(json: RepeatJson) -> Result<Self, Self::Error> {
+        match json {
+            RepeatJson::Word(word) if word == "always" => Ok(Self::Always),
+            RepeatJson::Word(word) if word == "once" => Ok(Self::Once),
+            RepeatJson::Word(word) => Err(format!("repeat {word:?} should be \"always\", \"once\" or seconds")),
+            RepeatJson::Seconds(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(Self::Cooldown(seconds)),
+            RepeatJson::Seconds(seconds) => Err(format!("repeat cooldown {seconds} isn't a number of seconds")),
+        }
+    }
+}
+
+/// What an NPC says instead of a dialogue it won't repeat, when its map
+/// entry doesn't say.
+pub const DEFAULT_REPEAT_LINE: &str = "We already covered that.";
+
+/// Longest ambient line, in characters: a bubble wider than this covers
+/// the neighbors' heads. Longer thoughts belong in the dialogue.
+pub const MAX_AMBIENT_LINE_CHARS: usize = 40;
+
+impl NpcData {
+    /// `id` as authored, else `name`'s slug.
+    pub fn id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| slug(&self.name))
+    }
+
+    /// Why `id()` - or its inline dialogue's - can't key anything, if it
+    /// can't (see `DialogueData::id_problem`). Like `dialogue_problem`,
+    /// leaves a `dialogue_file`'s inline fallback alone.
+    pub fn id_problems(&self) -> Vec<String> {
+        let own = match &self.id {
+            Some(id) if !is_slug(id) => Some(format!(
+                "NPC {:?} id {id:?} isn't lowercase letters, digits and underscores",
+                self.name
+            )),
+            None if slug(&self.name).is_empty() => Some(format!("NPC {:?} has no id, and its name doesn't make one", self.name)),
+            _ => None,
+        };
+        let dialogues = self
+            .inline_dialogues()
+            .filter_map(DialogueData::id_problem)
+            .map(|problem| format!("NPC {:?} dialogue {problem}", self.name));
+        own.into_iter().chain(dialogues).collect()
+    }
+
+    /// The inline dialogue, unless a `dialogue_file` stands in for it - or
+    /// it's been left out, with `dialogues` to say instead.
+    fn inline_dialogue(&self) -> Option<&DialogueData> {
+        let left_out = self.dialogue.lines.is_empty() && !self.dialogues.is_empty();
+        (self.dialogue_file.is_none() && !left_out).then_some(&self.dialogue)
+    }
+
+    /// Every conversation authored on the map entry: `dialogues`, then
+    /// `inline_dialogue`.
+    fn inline_dialogues(&self) -> impl Iterator<Item = &DialogueData> {
+        self.dialogues.iter().chain(self.inline_dialogue())
+    }
+
+    /// Why this NPC's inline dialogue can't be shown as authored, if it
+    /// can't (see `DialogueData::problem`; content_errors.rs). An NPC with a
+    /// `dialogue_file` has its file checked when it loads instead.
+    pub fn dialogue_problem(&self) -> Option<String> {
+        let inline = self.inline_dialogue().and_then(DialogueData::problem);
+        inline.map(|problem| format!("NPC {:?} {problem}", self.name)).or_else(|| {
+            self.dialogues.iter().find_map(|dialogue| {
+                let problem = dialogue.problem()?;
+                Some(format!("NPC {:?} dialogue {:?} {problem}", self.name, dialogue.id()))
+            })
+        })
+    }
+
+    /// Why the NPC can be left with nothing to say, if it can: every one of
+    /// its conversations has `conditions`, so there's no default for when
+    /// none of them hold.
+    pub fn default_dialogue_problem(&self) -> Option<String> {
+        let has_default =
+            self.dialogue_file.is_some() || self.inline_dialogues().any(|dialogue| dialogue.conditions.is_empty());
+        (!has_default).then(|| {
+            format!("NPC {:?} has only conditional dialogues - nothing to say when none of them hold", self.name)
+        })
+    }
+
+    /// What the NPC says instead of a dialogue it won't repeat.
+    pub fn repeat_line(&self) -> &str {
+        self.repeat_line.as_deref().unwrap_or(DEFAULT_REPEAT_LINE)
+    }
+
+    /// Why the NPC won't move as authored, if it won't: a patrol `path`
+    /// point off the `width`x`height` map (the path isn't followed), a
+    /// path and `wander` both, a `move_speed` that isn't a speed.
+    pub fn movement_problems(&self, width: u32, height: u32) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .path
+            .iter()
+            .enumerate()
+            .filter(|(_, (x, y))| *x >= width || *y >= height)
+            .map(|(index, (x, y))| format!(
+                "NPC {:?} path point #{} ({x}, {y}) is off the {width}x{height} map",
+                self.name,
+                index + 1
+            ))
+            .collect();
+        if self.wander && !self.path.is_empty() {
+            problems.push(format!("NPC {:?} has both wander and a path - it wanders", self.name));
+        }
+        if let Some(speed) = self.move_speed
+            && !(speed.is_finite() && speed > 0.0)
+        {
+            problems.push(format!("NPC {:?} move_speed {speed} isn't a number of tiles per second", self.name));
+        }
+        problems
+    }
+
+    /// Why `interaction_radius` can't be used, if it can't - the NPC keeps
+    /// the default.
+    pub fn interaction_problem(&self) -> Option<String> {
+        let radius = self.interaction_radius?;
+        (!(radius.is_finite() && radius > 0.0))
+            .then(|| format!("NPC {:?} interaction_radius {radius} isn't a positive number of pixels", self.name))
+    }
+
+    /// Ambient lines over `MAX_AMBIENT_LINE_CHARS`, one message each.
+    pub fn ambient_line_problems(&self) -> Vec<String> {
+        self.ambient_lines
+            .iter()
+            .filter(|line| line.chars().count() > MAX_AMBIENT_LINE_CHARS)
+            .map(|line| format!(
+                "NPC {:?} ambient line {line:?} is over {MAX_AMBIENT_LINE_CHARS} characters",
+                self.name
+            ))
+            .collect()
+    }
+
+    /// Ambient lines short enough to show; the rest are
+    /// `ambient_line_problems`.
+    pub fn shown_ambient_lines(&self) -> Vec<String> {
+        self.ambient_lines
+            .iter()
+            .filter(|line| line.chars().count() <= MAX_AMBIENT_LINE_CHARS)
+            .cloned()
+            .collect()
+    }
+}
+
+
+impl MapData {
+    /// The layers to draw, bottom to top: `layers` as authored, or for a
+    /// map without them `tiles` as the ground and `upper_tiles` (when
+    /// there are any) overhead.
+    pub fn tile_layers(&self) -> std::borrow::Cow<'_, [TileLayerData]> {
+        if !self.layers.is_empty() {
+            return std::borrow::Cow::Borrowed(&self.layers);
+        }
+        let layer = |name: &str, tiles: &[u32], overhead| TileLayerData {
+            name: Some(name.to_string()),
+            tiles: tiles.to_vec(),
+            overhead,
+            z: 0.0,
+        };
+        let mut layers = vec![layer("tiles", &self.tiles, false)];
+        if !self.upper_tiles.is_empty() {
+            layers.push(layer("upper_tiles", &self.upper_tiles, true));
+        }
+        std::borrow::Cow::Owned(layers)
+    }
+
+    /// What's wrong with the map's shape, as `MapProblem`s: layers with the
+    /// wrong number of cells, NPCs off the map, tiles past the tileset's
+    /// last one - given its size in tiles, once it is known - and NPCs
+    /// sharing a name. spawn_map won't draw a map with a hard problem
+    /// (`MapProblem::is_hard`).
+    pub fn validate(&self, tileset_tiles: Option<u32>) -> Vec<MapProblem> {
+        let mut problems = Vec::new();
+        let (width, height) = (self.width, self.height);
+        let cells = width as usize * height as usize;
+
+        // With `layers`, those are the tiles; `tiles` and `upper_tiles` are
+        // ignored.
+        let tile_layers: Vec<(String, &[u32])> = if self.layers.is_empty() {
+            vec![("tiles".into(), self.tiles.as_slice()), ("upper_tiles".into(), self.upper_tiles.as_slice())]
+        } else {
+            self.layers.iter().enumerate().map(|(index, layer)| (layer.label(index), layer.tiles.as_slice())).collect()
+        };
+        // Every layer but `tiles` and those in `layers` may be left out of
+        // older JSON.
+        let required = |index: usize| index == 0 || !self.layers.is_empty();
+        let sized = tile_layers.iter().enumerate().map(|(index, (layer, tiles))| (layer.clone(), *tiles, required(index)));
+        let sized = sized.chain([
+            (String::from("collision"), self.collision.as_slice(), false),
+            (String::from("passability"), self.passability.as_slice(), false),
+        ]);
+        for (layer, tiles, required) in sized {
+            if (required || !tiles.is_empty()) && tiles.len() != cells {
+                problems.push(MapProblem::LayerCells { layer, cells: tiles.len(), width, height });
+            }
+        }
+        if let Some(tileset_tiles) = tileset_tiles {
+            for (layer, tiles) in &tile_layers {
+                // One problem per tile, at its first cell: a map drawn
+                // against the wrong tileset shouldn't make thousands.
+                let mut past: Vec<(u32, usize, usize)> = Vec::new();
+                for (cell, &tile) in tiles.iter().enumerate().filter(|&(_, &tile)| tile >= tileset_tiles) {
+                    match past.iter_mut().find(|(seen, _, _)| *seen == tile) {
+                        Some((_, _, count)) => *count += 1,
+                        None => past.push((tile, cell, 1)),
+                    }
+                }
+                let columns = width.max(1) as usize;
+                for (tile, cell, count) in past {
+                    let (x, y) = ((cell % columns) as u32, (cell / columns) as u32);
+                    problems.push(MapProblem::TilePastTileset { layer: layer.clone(), tile, x, y, count, tileset_tiles });
+                }
+            }
+        }
+        let npcs: Vec<&NpcData> = self.npcs.iter().chain(&self.spawnable).collect();
+        for (index, npc) in npcs.iter().enumerate() {
+            if npc.x >= width || npc.y >= height {
+                problems.push(MapProblem::NpcOffMap { npc: npc.name.clone(), x: npc.x, y: npc.y, width, height });
+            }
+            if npcs[..index].iter().filter(|other| other.name == npc.name).count() == 1 {
+                problems.push(MapProblem::DuplicateNpcName { npc: npc.name.clone() });
+            }
+        }
+        problems
+    }
+
+    /// Every mood the map's conversations and scripted scenes name, each
+    /// once - checked against the palette at load (see mood.rs).
+    pub fn moods(&self) -> Vec<&str> {
+        let npcs = self.npcs.iter().chain(&self.spawnable);
+        let dialogues = npcs.flat_map(|npc| std::iter::once(&npc.dialogue).chain(&npc.dialogues));
+        let boxes = self.exits.iter().flat_map(|exit| &exit.dialogue).map(|segment| &segment.mood);
+        let mut moods: Vec<&str> =
+            dialogues.map(|dialogue| &dialogue.mood).chain(boxes).filter_map(Option::as_deref).collect();
+        moods.sort_unstable();
+        moods.dedup();
+        moods
+    }
+
+    /// Every NPC and inline dialogue authored without an `id`, with the
+    /// one it was given - warned about at load, since a rename will now
+    /// change it.
+    pub fn derived_ids(&self) -> Vec<String> {
+        let mut derived = Vec::new();
+        for npc in self.npcs.iter().chain(&self.spawnable) {
+            if npc.id.is_none() {
+                derived.push(format!("NPC {:?} has no id - using {:?}", npc.name, npc.id()));
+            }
+            for dialogue in npc.inline_dialogues().filter(|dialogue| dialogue.id.is_none()) {
+                derived.push(format!("NPC {:?} dialogue has no id - using {:?}", npc.name, dialogue.id()));
+            }
+        }
+        derived
+    }
+
+    /// Ids two NPCs, or two dialogues, share on this map.
+    fn repeated_ids(&self) -> Vec<String> {
+        let npcs: Vec<&NpcData> = self.npcs.iter().chain(&self.spawnable).collect();
+        let mut repeated = Vec::new();
+        for (index, npc) in npcs.iter().enumerate() {
+            if let Some(other) = npcs[..index].iter().find(|other| other.id() == npc.id()) {
+                repeated.push(format!("NPCs {:?} and {:?} both have id {:?}", other.name, npc.name, npc.id()));
+            }
+        }
+        // Objects are found by the same ids (npc.rs).
+        let names: Vec<(&str, String)> = npcs
+            .iter()
+            .map(|npc| (npc.name.as_str(), npc.id()))
+            .chain(self.objects.iter().map(|object| (object.name.as_str(), object.id())))
+            .collect();
+        for (index, (name, id)) in names.iter().enumerate().skip(npcs.len()) {
+            if let Some((other, _)) = names[..index].iter().find(|(_, other)| other == id) {
+                repeated.push(format!("{other:?} and object {name:?} both have id {id:?}"));
+            }
+        }
+        let dialogues: Vec<(&NpcData, String)> =
+            npcs.iter().flat_map(|npc| npc.inline_dialogues().map(move |dialogue| (*npc, dialogue.id()))).collect();
+        for (index, (npc, id)) in dialogues.iter().enumerate() {
+            let Some((other, _)) = dialogues[..index].iter().find(|(_, other)| other == id) else { continue };
+            if std::ptr::eq(*other, *npc) {
+                repeated.push(format!("NPC {:?} has two dialogues with id {id:?}", npc.name));
+            } else {
+                repeated.push(format!("NPCs {:?} and {:?} both have dialogue id {id:?}", other.name, npc.name));
+            }
+        }
+        repeated
+    }
+
+    /// Map JSON as an editor may have saved it: Notepad leads with a UTF-8
+    /// BOM, which serde_json rejects, and writes CRLF, which would show as
+    /// a stray glyph at the end of a dialogue box's lines.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MapLoadError> {
+        parse_map(authored_text(bytes)?)
+    }
+
+    /// A map file as its extension says: Tiled's JSON export (`.tmj`, see
+    /// tiled.rs) or the game's own map JSON. `.tmx` is Tiled's XML, which
+    /// isn't read - an error saying to export JSON instead.
+    pub fn parse_file(path: &std::path::Path, bytes: &[u8]) -> Result<Self, MapLoadError> {
+        let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("tmj") => super::tiled::parse_tiled_map(authored_text(bytes)?, name),
+            Some("tmx") => Err(MapLoadError::Unsupported(
+                "Tiled's XML (.tmx) isn't read; export the map as JSON (.tmj) instead".into(),
+            )),
+            _ => Self::parse(bytes),
+        }
+    }
+
+    /// Map JSON someone has already parsed and changed - a content pack's
+    /// patched map (see content_pack.rs).
+    pub fn from_value(value: serde_json::Value) -> Result<Self, MapLoadError> {
+        let mut map: MapData = serde_json::from_value(value)?;
+        map.normalize_newlines();
+        Ok(map)
+    }
+
+    /// `\r\n` to `\n` in everything the dialogue box or a speech bubble
+    /// shows.
+    fn normalize_newlines(&mut self) {
+        for npc in self.npcs.iter_mut().chain(&mut self.spawnable) {
+            npc.dialogue.normalize_newlines();
+            npc.dialogues.iter_mut().for_each(DialogueData::normalize_newlines);
+            npc.ambient_lines.iter_mut().for_each(normalize_newlines);
+            npc.repeat_line.iter_mut().for_each(normalize_newlines);
+        }
+        for line in self.objects.iter_mut().flat_map(|object| &mut object.lines) {
+            normalize_newlines(&mut line.text);
+            line.choices.iter_mut().for_each(|choice| normalize_newlines(&mut choice.label));
+        }
+        for line in self.conversations.values_mut().flatten() {
+            normalize_newlines(&mut line.text);
+        }
+    }
+
+    /// Story flags this map's conversations can set (see flags.rs).
+    pub fn flags_set(&self) -> impl Iterator<Item = &str> {
+        let dialogues = || self.npcs.iter().flat_map(|npc| std::iter::once(&npc.dialogue).chain(&npc.dialogues));
+        let outcomes = dialogues().flat_map(|dialogue| &dialogue.on_complete).filter_map(|outcome| match outcome {
+            DialogueOutcome::SetFlag(flag) => Some(flag.as_str()),
+            _ => None,
+        });
+        let actions = dialogues()
+            .flat_map(|dialogue| &dialogue.lines)
+            .flat_map(|line| &line.actions)
+            .filter_map(|action| match action {
+                LineAction::SetFlag(flag) => Some(flag.as_str()),
+                LineAction::ChangeScene(_) => None,
+            });
+        outcomes.chain(actions)
+    }
+}
+
+/// The `target_scene`s exits can name: `game_state::Scene`'s variants (see
+/// map_data.rs's `scene_from_str`).
+pub const SCENE_NAMES: [&str; 8] = [
+    "TownOfEndgame",
+    "TeamMarathon",
+    "TeamMarathonRetro",
+    "TeamDisco",
+    "TeamInferno",
+    "MahoganyRow",
+    "Intro",
+    "End",
+];
+
+/// A map file's text. A BOM is tolerated and CRLFs become `\n` (see
+/// `MapData::parse`).
+pub fn parse_map(json: &str) -> Result<MapData, MapLoadError> {
+    let mut map: MapData = serde_json::from_str(json.strip_prefix('\u{feff}').unwrap_or(json))?;
+    map.normalize_newlines();
+    Ok(map)
+}
+
+/// One of `MapData::validate`'s findings, saying where: the layer and
+/// cell, or the NPC. Hard ones (`is_hard`) would draw a broken map, so
+/// spawn_map refuses it; the rest are warned about. `validate_map` - the
+/// validate subcommand - reports them all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapProblem {
+    /// A layer - `tiles`, a `layers` entry, `collision`... - without one
+    /// cell per tile of the `width` x `height` map.
+    LayerCells { layer: String, cells: usize, width: u32, height: u32 },
+    /// `tile` placed in `layer`, first at (`x`, `y`) and in `count` cells
+    /// all told, on a tileset of only `tileset_tiles`.
+    TilePastTileset { layer: String, tile: u32, x: u32, y: u32, count: usize, tileset_tiles: u32 },
+    /// A placed or spawnable NPC standing outside the map.
+    NpcOffMap { npc: String, x: u32, y: u32, width: u32, height: u32 },
+    /// A second NPC called `npc`: both load, but talking to either reads
+    /// the same in the log and on screen.
+    DuplicateNpcName { npc: String },
+}
+
+impl MapProblem {
+    /// Tiles past the tileset aren't: spawn_map only knows the tileset's
+    /// size once it has loaded, which on a first visit it may not have,
+    /// and whether a map comes up mustn't hang on that.
+    pub fn is_hard(&self) -> bool {
+        !matches!(self, MapProblem::DuplicateNpcName { .. } | MapProblem::TilePastTileset { .. })
+    }
+}
+
+impl std::fmt::Display for MapProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapProblem::LayerCells { layer, cells, width, height } => {
+                write!(f, "{layer} has {cells} cells, not the {width}x{height} map's {}", *width as usize * *height as usize)
+            }
+            MapProblem::TilePastTileset { layer, tile, x, y, count, tileset_tiles } => {
+                match count {
+                    1 => write!(f, "{layer} uses tile {tile} at ({x}, {y})")?,
+                    _ => write!(f, "{layer} uses tile {tile} in {count} cells, first at ({x}, {y})")?,
+                }
+                write!(f, ", past the tileset's last tile, {}", tileset_tiles.saturating_sub(1))
+            }
+            MapProblem::NpcOffMap { npc, x, y, width, height } => {
+                write!(f, "NPC {npc:?} at ({x}, {y}) is off the {width}x{height} map")
+            }
+            MapProblem::DuplicateNpcName { npc } => {
+                write!(f, "two NPCs are named {npc:?}; rename one so the player can tell them apart")
+            }
+        }
+    }
+}
+
+/// Everything wrong with a map that the game would otherwise find out at
+/// play time: the problems content_errors.rs reports, plus layers that
+/// don't match the map's size, animated tiles without frames or a frame
+/// time, NPCs, patrols, exits and the player spawn off the map, NPCs
+/// sharing a name, tiles past the tileset's last when `options` has its
+/// size, and - when `options` lists the scenes - exits and dialogue lines
+/// going to a scene that doesn't exist.
+/// `dialogue_file`s aren't opened; validate them on their own.
+pub fn validate_map(map: &MapData, options: &ValidationOptions) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut report = |message: String| issues.push(Issue::new(message));
+    let (width, height) = (map.width, map.height);
+
+    map.validate(options.tileset_tiles).iter().map(MapProblem::to_string).for_each(&mut report);
+    map.layers.iter().enumerate().filter_map(|(index, layer)| layer.problem(index)).for_each(&mut report);
+    map.animated_tiles.iter().filter_map(|(&tile, animation)| animation.problem(tile, None)).for_each(&mut report);
+    for npc in map.npcs.iter().chain(&map.spawnable) {
+        npc.dialogue_problem().into_iter().chain(npc.ambient_line_problems()).for_each(&mut report);
+        npc.default_dialogue_problem().into_iter().chain(npc.id_problems()).for_each(&mut report);
+        npc.movement_problems(width, height).into_iter().for_each(&mut report);
+        let dialogues = std::iter::once(&npc.dialogue).chain(&npc.dialogues);
+        for problem in dialogues.flat_map(|dialogue| dialogue.unknown_scenes(options)) {
+            report(format!("NPC {:?} {problem}", npc.name));
+        }
+    }
+    for object in &map.objects {
+        if object.x >= width || object.y >= height {
+            report(format!("object {:?} at ({}, {}) is off the {width}x{height} map", object.name, object.x, object.y));
+        }
+        object.problem().into_iter().for_each(&mut report);
+    }
+    map.repeated_ids().into_iter().for_each(&mut report);
+    for exit in &map.exits {
+        if exit.trigger_x >= width || exit.trigger_y >= height {
+            report(format!("exit at ({}, {}) is off the {width}x{height} map", exit.trigger_x, exit.trigger_y));
+        }
+        if !options.known_scenes.is_empty() && !options.known_scenes.contains(&exit.target_scene) {
+            report(format!(
+                "exit at ({}, {}) goes to {:?}, which isn't a scene",
+                exit.trigger_x, exit.trigger_y, exit.target_scene
+            ));
+        }
+        exit.dialogue_problem().into_iter().chain(exit.facing_problem()).for_each(&mut report);
+    }
+    map.player_spawn.iter().filter_map(|spawn| spawn.problem(width, height)).for_each(&mut report);
+    map.camera_zones.iter().filter_map(|zone| zone.problem(width, height)).for_each(&mut report);
+    map.ambience.iter().filter_map(|layer| layer.problem(width, height)).for_each(&mut report);
+    for (index, layer) in map.ambience.iter().enumerate() {
+        if map.ambience[..index].iter().any(|earlier| earlier.sound == layer.sound) {
+            report(format!("ambience {:?} is layered twice", layer.sound));
+        }
+    }
+    map.group_dialogues.iter().filter_map(|group| group.problem(map)).for_each(&mut report);
+    map.box_layout.problem().into_iter().for_each(&mut report);
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::dialogue::DialogueCondition;
+
+    #[test]
+    fn validation_finds_what_the_map_would_trip_over_later() {
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 2, "height": 2, "tiles": [1, 1, 1],
+                 "npcs": [{ "name": "Casey", "x": 5, "y": 0, "sprite": "People1", "facing": "down",
+                            "dialogue": { "speaker": "Casey", "portrait": "", "lines": ["Hi."] } }],
+                 "exits": [{ "trigger_x": 0, "trigger_y": 1, "target_scene": "Narnia",
+                             "target_spawn_x": 0, "target_spawn_y": 0 }] }"#,
+        )
+        .unwrap();
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(issues, ["tiles has 3 cells, not the 2x2 map's 4", "NPC \"Casey\" at (5, 0) is off the 2x2 map"]);
+
+        let options = ValidationOptions { known_scenes: SCENE_NAMES.map(String::from).to_vec(), ..Default::default() };
+        let issues = validate_map(&map, &options);
+        assert!(issues.iter().any(|issue| issue.message.contains("\"Narnia\", which isn't a scene")), "{issues:?}");
+    }
+
+    #[test]
+    fn repeat_is_always_once_or_a_cooldown_in_seconds() {
+        let npc = |repeat: &str| {
+            parse_map(&format!(
+                r#"{{ "name": "Tiny", "width": 1, "height": 1, "tiles": [1],
+                     "npcs": [{{ "name": "Casey", "x": 0, "y": 0, "sprite": "People1", "facing": "down",
+                                 {repeat}
+                                 "dialogue": {{ "speaker": "Casey", "portrait": "", "lines": ["Hi."] }} }}] }}"#
+            ))
+            .map(|map| (map.npcs[0].repeat, map.npcs[0].repeat_line().to_string()))
+        };
+        assert_eq!(npc("").unwrap(), (DialogueRepeat::Always, DEFAULT_REPEAT_LINE.to_string()));
+        assert_eq!(npc(r#""repeat": "always","#).unwrap().0, DialogueRepeat::Always);
+        assert_eq!(
+            npc(r#""repeat": "once", "repeat_line": "We already covered the postmortem.","#).unwrap(),
+            (DialogueRepeat::Once, "We already covered the postmortem.".to_string())
+        );
+        assert_eq!(npc(r#""repeat": 30,"#).unwrap().0, DialogueRepeat::Cooldown(30.0));
+        assert!(npc(r#""repeat": "twice","#).is_err());
+        assert!(npc(r#""repeat": -5,"#).is_err());
+    }
+
+    #[test]
+    fn ids_default_to_the_name_and_must_be_unique_on_the_map() {
+        assert_eq!(slug("Doctor McFire"), "doctor_mcfire");
+        assert_eq!(slug("  Nanny Ogg-Vorbis!"), "nanny_ogg_vorbis");
+        assert_eq!(slug("???"), "");
+
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 1],
+                 "npcs": [{ "name": "Doctor McFire", "x": 0, "y": 0, "sprite": "People1", "facing": "down",
+                            "dialogue": { "id": "fire_drill", "speaker": "Dr. M", "portrait": "", "lines": ["Hi."] } },
+                          { "id": "doctor_mcfire", "name": "Doc", "x": 1, "y": 0, "sprite": "People1", "facing": "down",
+                            "dialogue": { "id": "Fire Drill", "speaker": "Doc", "portrait": "", "lines": ["Hi."] } }] }"#,
+        )
+        .unwrap();
+        assert_eq!(map.npcs[0].id(), "doctor_mcfire");
+        assert_eq!(map.npcs[1].dialogue.id(), "Fire Drill");
+        assert_eq!(map.derived_ids(), ["NPC \"Doctor McFire\" has no id - using \"doctor_mcfire\""]);
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "NPC \"Doc\" dialogue id \"Fire Drill\" isn't lowercase letters, digits and underscores",
+                "NPCs \"Doctor McFire\" and \"Doc\" both have id \"doctor_mcfire\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn objects_read_like_a_faceless_dialogue() {
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 1],
+                 "npcs": [{ "name": "Runbook", "x": 0, "y": 0, "sprite": "People1", "facing": "down",
+                            "dialogue": { "id": "hi", "speaker": "Runbook", "portrait": "", "lines": ["Hi."] } }],
+                 "objects": [{ "name": "Notice Board", "x": 1, "y": 0, "lines": ["Postmortem at 3.\r\nBring snacks."] },
+                             { "name": "Runbook", "x": 1, "y": 0, "sprite": "textures/objects/runbook.png", "lines": [] },
+                             { "id": "sign", "name": "Sign", "x": 2, "y": 0, "lines": ["Edge of the world."] }] }"#,
+        )
+        .unwrap();
+        let board = map.objects[0].dialogue();
+        assert_eq!((board.id(), board.speaker.as_str(), board.portrait.as_str()), ("notice_board".into(), "Notice Board", ""));
+        assert_eq!(board.lines[0].text, "Postmortem at 3.\nBring snacks.");
+        assert_eq!(map.objects[1].sprite.as_deref(), Some("textures/objects/runbook.png"));
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "object \"Runbook\" has no lines",
+                "object \"Sign\" at (2, 0) is off the 2x1 map",
+                "\"Runbook\" and object \"Runbook\" both have id \"runbook\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn tile_layers_stack_bottom_to_top_and_older_maps_get_ground_and_upper() {
+        let old = parse_map(r#"{ "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 2], "upper_tiles": [0, 3], "npcs": [] }"#)
+            .unwrap();
+        let layers = old.tile_layers();
+        assert_eq!(layers.iter().map(|layer| (layer.tiles.clone(), layer.overhead)).collect::<Vec<_>>(), [
+            (vec![1, 2], false),
+            (vec![0, 3], true)
+        ]);
+        let flat = parse_map(r#"{ "name": "Tiny", "width": 1, "height": 1, "tiles": [1], "npcs": [] }"#).unwrap();
+        assert_eq!(flat.tile_layers().len(), 1, "no upper_tiles, no overhead layer");
+
+        let layered = parse_map(
+            r#"{ "name": "Tiny", "width": 2, "height": 1, "npcs": [],
+                 "layers": [{ "name": "grass", "tiles": [1, 1] },
+                            { "name": "path", "tiles": [0, 4], "z": 0.1 },
+                            { "name": "canopy", "tiles": [7], "overhead": true },
+                            { "tiles": [0, 0], "z": 3 }] }"#,
+        )
+        .unwrap();
+        assert_eq!(layered.tile_layers().len(), 4);
+        assert!(layered.tile_layers()[2].overhead);
+        let issues: Vec<String> =
+            validate_map(&layered, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(issues, ["layer \"canopy\" has 1 cells, not the 2x1 map's 2", "layers[3] z 3 isn't between 0 and 0.5"]);
+    }
+
+    #[test]
+    fn validation_says_where_the_map_is_wrong_and_only_names_and_tiles_hold_it_back_softly() {
+        let problems = |json: &str, tileset_tiles| {
+            let map = parse_map(json).unwrap();
+            map.validate(tileset_tiles).into_iter().map(|problem| (problem.to_string(), problem.is_hard())).collect::<Vec<_>>()
+        };
+        let npc = |name: &str, x: u32| {
+            format!(
+                r#"{{ "name": "{name}", "x": {x}, "y": 0, "sprite": "People1", "facing": "down",
+                     "dialogue": {{ "speaker": "{name}", "portrait": "", "lines": ["Hi."] }} }}"#
+            )
+        };
+
+        let short = r#"{ "name": "Tiny", "width": 3, "height": 1, "tiles": [1, 1, 1], "collision": [true], "npcs": [],
+                          "layers": [{ "name": "grass", "tiles": [1, 1] }] }"#;
+        assert_eq!(problems(short, None), [
+            ("layer \"grass\" has 2 cells, not the 3x1 map's 3".to_string(), true),
+            ("collision has 1 cells, not the 3x1 map's 3".to_string(), true),
+        ]);
+
+        let tiles = r#"{ "name": "Tiny", "width": 3, "height": 2, "tiles": [1, 20, 3, 20, 0, 25], "npcs": [] }"#;
+        assert_eq!(problems(tiles, None), [], "unchecked until the tileset's size is known");
+        // Soft, so a map spawns whether or not its tileset has loaded.
+        assert_eq!(problems(tiles, Some(20)), [
+            ("tiles uses tile 20 in 2 cells, first at (1, 0), past the tileset's last tile, 19".to_string(), false),
+            ("tiles uses tile 25 at (2, 1), past the tileset's last tile, 19".to_string(), false),
+        ]);
+        let validated = |tileset_tiles| {
+            let options = ValidationOptions { tileset_tiles, ..Default::default() };
+            validate_map(&parse_map(tiles).unwrap(), &options).len()
+        };
+        assert_eq!((validated(None), validated(Some(20))), (0, 2), "validate fails on them, given the size");
+
+        let npcs = format!(
+            r#"{{ "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 1], "npcs": [{}, {}],
+                  "spawnable": [{}, {}] }}"#,
+            npc("Casey", 0),
+            npc("Casey", 1),
+            npc("Vendor", 2),
+            npc("Casey", 0)
+        );
+        assert_eq!(problems(&npcs, Some(20)), [
+            ("two NPCs are named \"Casey\"; rename one so the player can tell them apart".to_string(), false),
+            ("NPC \"Vendor\" at (2, 0) is off the 2x1 map".to_string(), true),
+        ]);
+    }
+
+    #[test]
+    fn ambience_layers_are_map_wide_or_measured_from_tiles_on_the_map() {
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 1], "npcs": [],
+                 "ambience": [{ "sound": "audio/wind.ogg", "volume": 0.4 },
+                              { "sound": "audio/crowd.ogg", "near_tiles": [[1, 0]] },
+                              { "sound": "audio/hum.ogg", "radius": 3 },
+                              { "sound": "audio/rain.ogg", "volume": 1.5 },
+                              { "sound": "audio/beeps.ogg", "near_tiles": [[0, 0], [4, 0]], "radius": 2 },
+                              { "sound": "audio/wind.ogg" }] }"#,
+        )
+        .unwrap();
+        let (wind, crowd) = (&map.ambience[0], &map.ambience[1]);
+        assert_eq!((wind.volume, wind.is_positional()), (0.4, false));
+        assert_eq!((crowd.volume, crowd.is_positional(), crowd.radius()), (1.0, true, DEFAULT_AMBIENCE_RADIUS));
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "ambience \"audio/hum.ogg\" has a radius but no near_tiles to measure it from",
+                "ambience \"audio/rain.ogg\" volume 1.5 isn't between 0 and 1",
+                "ambience \"audio/beeps.ogg\" tile (4, 0) is off the 2x1 map",
+                "ambience \"audio/wind.ogg\" is layered twice",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_player_spawn_and_arrivals_are_on_the_map_facing_a_real_way() {
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 1], "npcs": [],
+                 "player_spawn": { "x": 1, "y": 0, "facing": "up" },
+                 "warps": [{ "trigger_x": 0, "trigger_y": 0, "target_scene": "TeamDisco",
+                             "target_spawn_x": 1, "target_spawn_y": 1, "target_facing": "north" }] }"#,
+        )
+        .unwrap();
+        assert_eq!(map.player_spawn, Some(PlayerSpawnData { x: 1, y: 0, facing: Some("up".into()) }));
+        assert_eq!(map.exits.len(), 1, "warps are exits");
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(issues, ["exit at (0, 0) to TeamDisco faces the player \"north\" on arrival, not one of down, left, right, up"]);
+
+        let off = PlayerSpawnData { x: 2, y: 0, facing: None };
+        assert_eq!(off.problem(2, 1).as_deref(), Some("player_spawn (2, 0) is off the 2x1 map"));
+        let sideways = PlayerSpawnData { x: 0, y: 0, facing: Some("sideways".into()) };
+        assert_eq!(sideways.problem(2, 1).as_deref(), Some("player_spawn faces \"sideways\", not one of down, left, right, up"));
+    }
+
+    #[test]
+    fn animated_tiles_need_frames_a_frame_time_and_frames_in_the_tileset() {
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 1, "height": 1, "tiles": [17], "npcs": [],
+                 "animated_tiles": { "17": { "frames": [17, 18, 19], "frame_seconds": 0.25 },
+                                     "40": { "frames": [], "frame_seconds": 0.25 },
+                                     "41": { "frames": [41], "frame_seconds": 0 } } }"#,
+        )
+        .unwrap();
+        let water = &map.animated_tiles[&17];
+        assert_eq!(water.frames, [17, 18, 19]);
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            issues,
+            ["animated tile 40 has no frames", "animated tile 41 frame_seconds 0 isn't a positive number"]
+        );
+
+        assert_eq!(water.problem(17, Some(20)), None);
+        assert_eq!(
+            water.problem(17, Some(19)).as_deref(),
+            Some("animated tile 17 frame 19 is past the tileset's last tile, 18")
+        );
+    }
+
+    #[test]
+    fn conditional_dialogues_need_a_default_to_fall_back_on() {
+        let map = |dialogues: &str| {
+            parse_map(&format!(
+                r#"{{ "name": "Tiny", "width": 1, "height": 1, "tiles": [1],
+                     "npcs": [{{ "id": "casey", "name": "Casey", "x": 0, "y": 0, "sprite": "People1", "facing": "down",
+                                 "dialogues": [{dialogues}] }}] }}"#
+            ))
+            .unwrap()
+        };
+        let issues = |map: &MapData| -> Vec<String> {
+            validate_map(map, &ValidationOptions::default()).iter().map(Issue::to_string).collect()
+        };
+        let quest_done = r#"{ "id": "thanks", "conditions": [{ "has_flag": "quest_done" }],
+                              "speaker": "Casey", "portrait": "", "lines": ["Thanks!"] }"#;
+
+        let conditional_only = map(quest_done);
+        assert_eq!(
+            conditional_only.npcs[0].dialogues[0].conditions,
+            [DialogueCondition::HasFlag("quest_done".into())]
+        );
+        assert_eq!(
+            issues(&conditional_only),
+            ["NPC \"Casey\" has only conditional dialogues - nothing to say when none of them hold"]
+        );
+
+        let fallback = r#"{ "id": "hello", "speaker": "Casey", "portrait": "", "lines": ["Hi."] }"#;
+        assert_eq!(issues(&map(&format!("{quest_done}, {fallback}"))), Vec::<String>::new());
+
+        let unnamed = r#"{ "speaker": "Casey", "portrait": "", "lines": ["Hi again."] }"#;
+        assert_eq!(
+            issues(&map(&format!("{unnamed}, {quest_done}, {unnamed}"))),
+            ["NPC \"Casey\" has two dialogues with id \"casey\""]
+        );
+    }
+
+    #[test]
+    fn patrol_paths_must_stay_on_the_map() {
+        let map = parse_map(
+            r#"{ "name": "Tiny", "width": 3, "height": 2, "tiles": [1, 1, 1, 1, 1, 1],
+                 "npcs": [{ "id": "casey", "name": "Casey", "x": 0, "y": 0, "sprite": "People1", "facing": "down",
+                            "path": [[0, 0], [2, 1], [3, 1]], "move_speed": 2.5,
+                            "dialogue": { "id": "hi", "speaker": "Casey", "portrait": "", "lines": ["Hi."] } },
+                          { "id": "doggo", "name": "doggo", "x": 1, "y": 1, "sprite": "Nature", "facing": "down",
+                            "wander": true, "wander_radius": 2, "path": [[1, 1]], "move_speed": 0,
+                            "dialogue": { "id": "wan", "speaker": "doggo", "portrait": "", "lines": ["Wan wan!"] } }] }"#,
+        )
+        .unwrap();
+        assert_eq!(map.npcs[0].path, [(0, 0), (2, 1), (3, 1)]);
+        assert_eq!(map.npcs[0].move_speed, Some(2.5));
+        assert_eq!(map.npcs[1].wander_radius, Some(2));
+
+        let issues: Vec<String> =
+            validate_map(&map, &ValidationOptions::default()).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "NPC \"Casey\" path point #3 (3, 1) is off the 3x2 map",
+                "NPC \"doggo\" has both wander and a path - it wanders",
+                "NPC \"doggo\" move_speed 0 isn't a number of tiles per second",
+            ]
+        );
+    }
+}
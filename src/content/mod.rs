@@ -0,0 +1,144 @@
+//! The game's content formats - map JSON and `.dialogue.json` files - with
+//! nothing of Bevy in them: parse a file, then ask what's wrong with it.
+//! Tools that check or convert content (the `validate` subcommand in
+//! main.rs, an editor plugin) use this module alone; the game adds its
+//! asset loading on top in map_data.rs.
+//!
+//! ```
+//! use sregame::content::{ValidationOptions, parse_map, validate_map};
+//!
+//! let map = parse_map(r#"{
+//!     "name": "Tiny", "width": 2, "height": 1, "tiles": [1, 1],
+//!     "npcs": [{ "name": "Casey", "x": 0, "y": 0, "sprite": "People1",
+//!                "facing": "down", "dialogue": { "speaker": "Casey",
+//!                "portrait": "", "lines": [] } }]
+//! }"#).unwrap();
+//!
+//! let issues = validate_map(&map, &ValidationOptions::default());
+//! assert_eq!(issues.len(), 1);
+//! assert!(issues[0].message.contains("no dialogue lines"), "{}", issues[0]);
+//! ```
+
+pub mod dialogue;
+pub mod map;
+pub mod tiled;
+
+pub use dialogue::{parse_dialogue, validate_dialogue};
+pub use map::{parse_map, validate_map};
+pub use tiled::parse_tiled_map;
+
+use std::fmt;
+
+/// Why a content file couldn't be read at all.
+#[derive(Debug)]
+pub enum MapLoadError {
+    /// Not UTF-8; `offset` is the first bad byte, which is where an author
+    /// will find a stray Latin-1 character.
+    Utf8 { offset: usize },
+    /// Not JSON, or not the shape of the format.
+    Json(serde_json::Error),
+    /// A map from another editor (tiled.rs) using something the game can't
+    /// draw the way that editor shows it.
+    Unsupported(String),
+}
+
+impl fmt::Display for MapLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapLoadError::Utf8 { offset } => write!(f, "invalid UTF-8 at byte offset {offset}"),
+            MapLoadError::Json(e) => e.fmt(f),
+            MapLoadError::Unsupported(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for MapLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MapLoadError::Utf8 { .. } | MapLoadError::Unsupported(_) => None,
+            MapLoadError::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for MapLoadError {
+    fn from(e: serde_json::Error) -> Self {
+        MapLoadError::Json(e)
+    }
+}
+
+/// One thing wrong with a content file that parsed: the game would load
+/// it, then misbehave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub message: String,
+}
+
+impl Issue {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// What validation can check beyond the file itself.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationOptions {
+    /// Scene names exits may go to (`game_state::Scene`'s variants). Empty:
+    /// don't check.
+    pub known_scenes: Vec<String>,
+    /// How many tiles the map's tileset has, to check the map's tiles
+    /// against. None: don't check.
+    pub tileset_tiles: Option<u32>,
+}
+
+/// A content file's bytes as text, without the UTF-8 BOM Windows editors
+/// put first. Text that isn't UTF-8 is an error naming the first bad byte.
+pub fn authored_text(bytes: &[u8]) -> Result<&str, MapLoadError> {
+    let text = std::str::from_utf8(bytes).map_err(|e| MapLoadError::Utf8 { offset: e.valid_up_to() })?;
+    Ok(text.strip_prefix('\u{feff}').unwrap_or(text))
+}
+
+/// `name` as a content id: lowercase ASCII letters and digits, anything
+/// else run together into one `_` - "Doctor McFire" is `doctor_mcfire`.
+/// What an NPC or dialogue without an `id` is known by.
+pub fn slug(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    let trimmed = slug.trim_end_matches('_').len();
+    slug.truncate(trimmed);
+    slug
+}
+
+/// Whether `id` is what `slug` makes: not empty, and nothing but lowercase
+/// ASCII letters, digits and single inner underscores.
+pub fn is_slug(id: &str) -> bool {
+    !id.is_empty() && slug(id) == id
+}
+
+/// `id` qualified by the content file it was authored in, for what keys on
+/// it game-wide - ids are only unique within their file. The file's name
+/// up to its first dot: `data/maps/town_of_endgame.json` and
+/// `town_of_endgame.patch.json` both give `town_of_endgame/<id>`.
+pub fn qualified_id(file: &str, id: &str) -> String {
+    let name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+    let stem = name.split('.').next().unwrap_or(name);
+    format!("{stem}/{id}")
+}
+
+fn normalize_newlines(text: &mut String) {
+    if text.contains('\r') {
+        *text = text.replace("\r\n", "\n");
+    }
+}
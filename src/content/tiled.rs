@@ -0,0 +1,366 @@
+//! Maps made in Tiled (mapeditor.org), read from its JSON export (`.tmj`)
+//! into the game's `MapData`:
+//!
+//! - every tile layer becomes one of `MapData::layers`, bottom to top, with
+//!   optional `overhead` (bool) and `z` (float) layer properties;
+//! - a tile layer named "collision" isn't drawn: any tile in it blocks
+//!   that cell. So does any tile whose tileset gives it a true `collision`
+//!   property. With neither, the map has no collision data (see
+//!   `CollisionMap::from_map_data`);
+//! - each object on an object layer named "npcs" is an NPC on the tile
+//!   under its middle: the object's name, and `sprite`, `facing` (default
+//!   "down") and `dialogue` (a `.dialogue.json` path, `NpcData::
+//!   dialogue_file`) properties. Any other property sets the `NpcData`
+//!   field of that name (`id`, `wander`, `step_anime`...);
+//! - map properties likewise set `MapData` fields (`name` - else the file
+//!   name - `indoor`, ...). A string property holding a JSON array or
+//!   object is read as that JSON, for fields like `exits`.
+//!
+//! Tile ids count from the tileset's first tile, which is the game's atlas
+//! index: author against the map's own tileset image. The game's tile 0 is
+//! an empty cell, so leave the tileset's first tile blank - a layer that
+//! draws it is an error (the collision layer may use any tile). Whatever
+//! the game couldn't draw the way Tiled shows it is an error naming it,
+//! not a guess: infinite or non-orthogonal maps, tiles other than 48x48, more
+//! than one tileset, flipped or rotated tiles, compressed layer data,
+//! image layers and object layers other than "npcs". TMX (Tiled's XML) is
+//! not read; export as JSON.
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use super::MapLoadError;
+use super::map::MapData;
+use crate::tilemap::TILE_PX;
+
+/// Tiled keeps flips and rotations in a gid's top four bits.
+const TRANSFORM_BITS: u32 = 0xF000_0000;
+
+#[derive(Deserialize)]
+struct TiledMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    /// An array of gids, or base64 text with `encoding`.
+    #[serde(default)]
+    data: Value,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+    /// A group layer's own.
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledObject {
+    #[serde(default)]
+    name: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    /// A tile object, anchored at its bottom-left rather than top-left.
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledTileset {
+    firstgid: u32,
+    /// Per-tile properties; an external tileset (`source`) has none here.
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize)]
+struct TiledTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: Value,
+}
+
+fn unsupported(message: impl Into<String>) -> MapLoadError {
+    MapLoadError::Unsupported(message.into())
+}
+
+/// A property's value, JSON held in a string read as that JSON.
+fn property_value(property: &TiledProperty) -> Value {
+    if let Value::String(text) = &property.value
+        && text.trim_start().starts_with(['[', '{'])
+        && let Ok(value) = serde_json::from_str(text)
+    {
+        return value;
+    }
+    property.value.clone()
+}
+
+fn property<'a>(properties: &'a [TiledProperty], name: &str) -> Option<&'a Value> {
+    properties.iter().find(|property| property.name == name).map(|property| &property.value)
+}
+
+/// Tiled's JSON export of the map called `name` (its file name, unless a
+/// `name` property says otherwise).
+pub fn parse_tiled_map(json: &str, name: &str) -> Result<MapData, MapLoadError> {
+    let tiled: TiledMap = serde_json::from_str(json.strip_prefix('\u{feff}').unwrap_or(json))?;
+    if tiled.infinite {
+        return Err(unsupported("infinite Tiled maps aren't supported; untick Infinite in the map properties"));
+    }
+    if !tiled.orientation.is_empty() && tiled.orientation != "orthogonal" {
+        return Err(unsupported(format!("{} Tiled maps aren't supported, only orthogonal", tiled.orientation)));
+    }
+    if (tiled.tilewidth, tiled.tileheight) != (TILE_PX, TILE_PX) {
+        return Err(unsupported(format!(
+            "tiles are {}x{}; the game's are {TILE_PX}x{TILE_PX}",
+            tiled.tilewidth, tiled.tileheight
+        )));
+    }
+    let tileset = match tiled.tilesets.as_slice() {
+        [] => None,
+        [tileset] => Some(tileset),
+        tilesets => {
+            return Err(unsupported(format!("the map uses {} tilesets; the game draws from one", tilesets.len())));
+        }
+    };
+    let first_gid = tileset.map_or(1, |tileset| tileset.firstgid);
+    let blocking: Vec<u32> = tileset
+        .iter()
+        .flat_map(|tileset| &tileset.tiles)
+        .filter(|tile| property(&tile.properties, "collision") == Some(&Value::Bool(true)))
+        .map(|tile| tile.id)
+        .collect();
+
+    let cells = tiled.width as usize * tiled.height as usize;
+    let mut layers = Vec::new();
+    let mut collision: Option<Vec<bool>> = None;
+    let mut npcs = Vec::new();
+    let mut flat = Vec::new();
+    flatten(&tiled.layers, &mut flat);
+    for layer in flat {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                let tiles = layer_tiles(layer, cells, first_gid)?;
+                if layer.name.eq_ignore_ascii_case("collision") {
+                    let blocked = collision.get_or_insert_with(|| vec![false; cells]);
+                    tiles.iter().zip(blocked.iter_mut()).for_each(|(tile, blocked)| *blocked |= tile.is_some());
+                    continue;
+                }
+                if !blocking.is_empty() {
+                    let blocked = collision.get_or_insert_with(|| vec![false; cells]);
+                    for (tile, blocked) in tiles.iter().zip(blocked.iter_mut()) {
+                        *blocked |= tile.is_some_and(|tile| blocking.contains(&tile));
+                    }
+                }
+                // The game's tile 0 is an empty cell, so the tileset's first
+                // tile can't be drawn.
+                if let Some(cell) = tiles.iter().position(|&tile| tile == Some(0)) {
+                    return Err(unsupported(format!(
+                        "layer {:?} draws the tileset's first tile in cell {cell}, which the game reads as empty; \
+                         leave that tile blank in the tileset",
+                        layer.name
+                    )));
+                }
+                let tiles: Vec<u32> = tiles.into_iter().map(Option::unwrap_or_default).collect();
+                let mut entry = json!({ "name": layer.name, "tiles": tiles });
+                for key in ["overhead", "z"] {
+                    if let Some(value) = property(&layer.properties, key) {
+                        entry[key] = value.clone();
+                    }
+                }
+                layers.push(entry);
+            }
+            "objectgroup" if layer.name.eq_ignore_ascii_case("npcs") => {
+                for object in &layer.objects {
+                    npcs.push(npc(object, &tiled)?);
+                }
+            }
+            "objectgroup" => {
+                return Err(unsupported(format!("object layer {:?} isn't one the game reads (only \"npcs\")", layer.name)));
+            }
+            kind => return Err(unsupported(format!("layer {:?} is a {kind}, which the game can't draw", layer.name))),
+        }
+    }
+
+    let mut map = Map::new();
+    map.insert("name".into(), name.into());
+    for property in &tiled.properties {
+        map.insert(property.name.clone(), property_value(property));
+    }
+    map.insert("width".into(), tiled.width.into());
+    map.insert("height".into(), tiled.height.into());
+    map.insert("layers".into(), layers.into());
+    if let Some(collision) = collision {
+        map.insert("collision".into(), collision.into());
+    }
+    map.insert("npcs".into(), npcs.into());
+    MapData::from_value(Value::Object(map))
+}
+
+/// Group layers' contents in their place in the stack.
+fn flatten<'a>(layers: &'a [TiledLayer], into: &mut Vec<&'a TiledLayer>) {
+    for layer in layers {
+        if layer.kind == "group" {
+            flatten(&layer.layers, into);
+        } else {
+            into.push(layer);
+        }
+    }
+}
+
+/// A tile layer's gids as atlas indices (tile ids in the tileset), None
+/// for an empty cell.
+fn layer_tiles(layer: &TiledLayer, cells: usize, first_gid: u32) -> Result<Vec<Option<u32>>, MapLoadError> {
+    let Value::Array(gids) = &layer.data else {
+        return Err(unsupported(format!(
+            "layer {:?} is base64-encoded; set its Tile Layer Format to CSV",
+            layer.name
+        )));
+    };
+    if gids.len() != cells {
+        return Err(unsupported(format!("layer {:?} has {} cells, not the map's {cells}", layer.name, gids.len())));
+    }
+    gids.iter()
+        .enumerate()
+        .map(|(cell, gid)| {
+            let gid = gid.as_u64().and_then(|gid| u32::try_from(gid).ok()).unwrap_or(u32::MAX);
+            if gid == 0 {
+                return Ok(None);
+            }
+            if gid & TRANSFORM_BITS != 0 {
+                return Err(unsupported(format!(
+                    "layer {:?} flips or rotates the tile in cell {cell}, which the game can't draw",
+                    layer.name
+                )));
+            }
+            gid.checked_sub(first_gid)
+                .map(Some)
+                .ok_or_else(|| unsupported(format!("layer {:?} cell {cell} has tile {gid}, before the tileset's first", layer.name)))
+        })
+        .collect()
+}
+
+/// An "npcs" object as NPC JSON.
+fn npc(object: &TiledObject, tiled: &TiledMap) -> Result<Value, MapLoadError> {
+    let top = if object.gid.is_some() { object.y - object.height } else { object.y };
+    let (x, y) = (object.x + object.width / 2.0, top + object.height / 2.0);
+    let on_map = x >= 0.0 && y >= 0.0;
+    let (x, y) = ((x / TILE_PX as f32) as u32, (y / TILE_PX as f32) as u32);
+    if !on_map || x >= tiled.width || y >= tiled.height {
+        return Err(unsupported(format!("NPC {:?} is off the map", object.name)));
+    }
+    if property(&object.properties, "sprite").is_none() {
+        return Err(unsupported(format!("NPC {:?} has no sprite property", object.name)));
+    }
+    let mut npc = Map::new();
+    npc.insert("name".into(), object.name.clone().into());
+    npc.insert("facing".into(), "down".into());
+    for property in &object.properties {
+        let field = if property.name == "dialogue" { "dialogue_file" } else { property.name.as_str() };
+        npc.insert(field.into(), property_value(property));
+    }
+    npc.insert("x".into(), x.into());
+    npc.insert("y".into(), y.into());
+    Ok(Value::Object(npc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x2 Tiled map, firstgid 5: grass everywhere, a canopy over one
+    /// cell, a wall in the collision layer, and Casey.
+    fn tiled() -> Value {
+        json!({
+            "type": "map", "orientation": "orthogonal", "renderorder": "right-down", "infinite": false,
+            "width": 3, "height": 2, "tilewidth": 48, "tileheight": 48,
+            "properties": [{ "name": "indoor", "type": "bool", "value": true }],
+            "tilesets": [{ "firstgid": 5, "tiles": [{ "id": 3, "properties": [
+                { "name": "collision", "type": "bool", "value": true }] }] }],
+            "layers": [
+                { "type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [6, 6, 6, 6, 8, 6] },
+                { "type": "group", "name": "above", "layers": [
+                    { "type": "tilelayer", "name": "canopy", "data": [0, 9, 0, 0, 0, 0],
+                      "properties": [{ "name": "overhead", "type": "bool", "value": true }] }] },
+                { "type": "tilelayer", "name": "Collision", "data": [0, 0, 5, 0, 0, 0] },
+                { "type": "objectgroup", "name": "npcs", "objects": [
+                    { "name": "Casey", "x": 60, "y": 50, "width": 0, "height": 0, "properties": [
+                        { "name": "sprite", "type": "string", "value": "People1" },
+                        { "name": "dialogue", "type": "string", "value": "dialogue/casey.dialogue.json" },
+                        { "name": "wander", "type": "bool", "value": true }] }] }
+            ]
+        })
+    }
+
+    fn parse(value: &Value) -> Result<MapData, MapLoadError> {
+        parse_tiled_map(&value.to_string(), "meadow")
+    }
+
+    #[test]
+    fn tiled_layers_objects_and_properties_become_the_map() {
+        let map = parse(&tiled()).unwrap();
+        assert_eq!((map.name.as_str(), map.width, map.height, map.indoor), ("meadow", 3, 2, true));
+        let layers = map.tile_layers();
+        assert_eq!(layers.iter().map(|layer| layer.name.as_deref().unwrap()).collect::<Vec<_>>(), ["ground", "canopy"]);
+        assert_eq!(layers[0].tiles, [1, 1, 1, 1, 3, 1], "gids less the first gid");
+        assert!(layers[1].overhead);
+        assert_eq!(
+            map.collision,
+            [false, false, true, false, true, false],
+            "the collision layer - its tile the tileset's first - and a blocking tile"
+        );
+
+        let casey = &map.npcs[0];
+        assert_eq!((casey.name.as_str(), casey.x, casey.y, casey.facing.as_str()), ("Casey", 1, 1, "down"));
+        assert_eq!(casey.dialogue_file.as_deref(), Some("dialogue/casey.dialogue.json"));
+        assert!(casey.wander);
+        assert!(super::super::validate_map(&map, &Default::default()).is_empty());
+    }
+
+    #[test]
+    fn what_the_game_cannot_draw_is_an_error_not_a_guess() {
+        let error = |change: &dyn Fn(&mut Value)| {
+            let mut map = tiled();
+            change(&mut map);
+            parse(&map).unwrap_err().to_string()
+        };
+        assert!(error(&|map| map["infinite"] = true.into()).contains("infinite"));
+        assert!(error(&|map| map["orientation"] = "isometric".into()).contains("isometric"));
+        assert!(error(&|map| map["tilewidth"] = 32.into()).contains("32x48"));
+        assert!(error(&|map| map["tilesets"].as_array_mut().unwrap().push(json!({ "firstgid": 100 }))).contains("2 tilesets"));
+        assert!(error(&|map| map["layers"][0]["data"][0] = (0x8000_0006u32).into()).contains("flips or rotates"));
+        assert!(error(&|map| map["layers"][0]["data"] = "eJxjYGBgYAAAAAQAAQ==".into()).contains("CSV"));
+        assert!(error(&|map| map["layers"][0]["data"][0] = 2.into()).contains("before the tileset's first"));
+        assert!(error(&|map| map["layers"][0]["data"][0] = 5.into()).contains("the tileset's first tile in cell 0"));
+        assert!(error(&|map| map["layers"][3]["name"] = "exits".into()).contains("\"exits\""));
+        assert!(error(&|map| map["layers"][3]["objects"][0]["x"] = 500.into()).contains("off the map"));
+    }
+}
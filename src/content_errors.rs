@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use std::time::Duration;
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Counter, Meter};
+use crate::instrumentation::{MetricsBundle, init_metrics};
+
+/// Broken content made visible. A map that fails to parse or an NPC whose
+/// dialogue can't be shown used to leave nothing but a console line and a
+/// silent NPC - easy to miss mid-playtest. Loaders and validators now
+/// `record` every failure into `ContentErrors`, which the F3 overlay lists
+/// (debug_overlay.rs), and the `game.content.errors` counter puts on the
+/// dashboards. NPCs spawned from broken data carry `BrokenContent` and, in
+/// debug builds, a warning marker over their heads.
+pub struct ContentErrorsPlugin;
+
+impl Plugin for ContentErrorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContentErrors>();
+        init_metrics::<ContentMetrics>(app);
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, mark_broken_npcs.run_if(in_state(crate::game_state::GameState::Playing)));
+    }
+}
+
+/// One content failure. `path` is the file at fault (as the game addressed
+/// it, e.g. "maps/town_of_endgame.json"); `at` is game time since startup,
+/// so the overlay reads "12.3s" rather than a wall clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentError {
+    pub path: String,
+    pub error: String,
+    pub at: Duration,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ContentErrors {
+    pub errors: Vec<ContentError>,
+}
+
+impl ContentErrors {
+    /// Log, count, and remember a failure. Re-entering a scene re-runs its
+    /// loader, so a repeat of an already-listed (path, error) only refreshes
+    /// its timestamp instead of growing the list - the metric still counts
+    /// every occurrence.
+    pub fn record(
+        &mut self,
+        path: impl Into<String>,
+        error: impl Into<String>,
+        at: Duration,
+        metrics: Option<&ContentMetrics>,
+    ) {
+        let path = path.into();
+        let error = error.into();
+        error!("🧩 Content error in {path}: {error}");
+
+        if let Some(metrics) = metrics {
+            metrics.errors.add(1, &[KeyValue::new("content.path", path.clone())]);
+        }
+
+        match self.errors.iter_mut().find(|e| e.path == path && e.error == error) {
+            Some(existing) => existing.at = at,
+            None => self.errors.push(ContentError { path, error, at }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// `game.content.errors`, by file. Every plugin that loads or validates
+/// content registers it alongside `ContentErrors`.
+#[derive(Resource)]
+pub struct ContentMetrics {
+    pub errors: Counter<u64>,
+}
+
+impl MetricsBundle for ContentMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            errors: meter
+                .u64_counter("game.content.errors")
+                .with_description("Content load/validation failures (see content_errors.rs)")
+                .build(),
+        }
+    }
+}
+
+/// On an NPC spawned from content that failed validation: which file, and
+/// what's wrong with it.
+#[derive(Component, Debug, Clone)]
+pub struct BrokenContent {
+    pub path: String,
+    pub error: String,
+}
+
+impl BrokenContent {
+    /// What a broken NPC says in debug builds instead of nothing, so the
+    /// playtester can read the fault straight off the dialogue box.
+    pub fn fallback_line(&self) -> String {
+        format!("… (broken content: {}: {})", self.path, self.error)
+    }
+}
+
+#[cfg(debug_assertions)]
+fn mark_broken_npcs(
+    mut commands: Commands,
+    broken: Query<Entity, Added<BrokenContent>>,
+) {
+    for entity in &broken {
+        let marker = crate::npc::spawn_emote(&mut commands, entity, "(!)", None);
+        commands
+            .entity(marker)
+            .insert(TextColor(Color::srgb(1.0, 0.8, 0.1)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_errors_are_listed_once_with_the_latest_time() {
+        let mut errors = ContentErrors::default();
+        errors.record("maps/a.json", "bad", Duration::from_secs(1), None);
+        errors.record("maps/b.json", "bad", Duration::from_secs(2), None);
+        errors.record("maps/a.json", "bad", Duration::from_secs(3), None);
+
+        assert_eq!(errors.errors.len(), 2);
+        assert_eq!(errors.errors[0].path, "maps/a.json");
+        assert_eq!(errors.errors[0].at, Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,336 @@
+//! Content packs: `--content-pack <dir>` overlays a directory on `assets/`,
+//! for a workshop that wants its own dialogue, portraits or NPCs without a
+//! fork. A pack mirrors `assets/`'s layout, and a file in it shadows the
+//! base file at the same relative path. Packs stack in the order given; the
+//! later one wins.
+//!
+//! Two layers do the shadowing, so nothing else has to know packs exist:
+//!
+//! - Everything the `AssetServer` loads (`.dialogue.json` files, portraits,
+//!   sprites) goes through `PackReader`, the default asset source once
+//!   `ContentPacks::install` has run.
+//! - Maps are embedded by build.rs rather than loaded, so `load_scene_map`
+//!   asks `ContentPacks::load_map` first. A pack's
+//!   `data/maps/<map>.json` replaces a map whole; its
+//!   `data/maps/<map>.patch.json` is merged into it (see `merge_patch`),
+//!   which is how a pack adds one NPC without restating the town.
+//!
+//! - Data files the game embeds rather than loads are overlaid whole where
+//!   they're read through `data_file` (portraits.json, and sprites.json -
+//!   whose entries may name sheets the pack brings); the rest (moods.json
+//!   and the like) aren't.
+//!
+//! Native only: the browser build has no directories to overlay.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{collections::BTreeMap, path::Path};
+
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Context;
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::{
+    AssetReader, AssetReaderError, AssetSourceBuilder, AssetSourceId, PathStream, Reader, file::FileAssetReader,
+};
+use serde_json::Value;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::map_data::{MapData, authored_text};
+
+/// Where a pack keeps its maps, as in `assets/`.
+#[cfg(not(target_arch = "wasm32"))]
+const MAPS_DIR: &str = "data/maps";
+
+/// The `--content-pack` directories, first to last. Absent without any.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ContentPacks(pub Vec<PathBuf>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ContentPacks {
+    /// Serve the default asset source from `assets_root` with these packs
+    /// over it, and keep them for `load_map`. Must run before `AssetPlugin`
+    /// is added - it only sets up a default source when none is registered.
+    pub fn install(self, app: &mut App, assets_root: impl Into<PathBuf>) {
+        let root = assets_root.into();
+        let packs = self.0.clone();
+        app.register_asset_source(
+            AssetSourceId::Default,
+            AssetSourceBuilder::new(move || Box::new(PackReader::new(&root, &packs))),
+        );
+        app.insert_resource(self);
+    }
+
+    /// The pack `relative` is served from - the last one that has it - or
+    /// `None` when it comes from the base.
+    pub fn origin(&self, relative: &Path) -> Option<&Path> {
+        self.0.iter().rev().map(PathBuf::as_path).find(|pack| pack.join(relative).is_file())
+    }
+
+    /// Every file the packs provide, by relative path, with the pack it's
+    /// served from.
+    pub fn files(&self) -> BTreeMap<PathBuf, &Path> {
+        let mut files = BTreeMap::new();
+        for pack in &self.0 {
+            for file in files_under(pack) {
+                if let Ok(relative) = file.strip_prefix(pack) {
+                    files.insert(relative.to_path_buf(), pack.as_path());
+                }
+            }
+        }
+        files
+    }
+
+    /// `map_file` as the packs leave it, or `None` when none of them touch
+    /// it. Starts from the last pack that replaces the map whole, else the
+    /// base (`map_directory` or the embedded manifest, as in
+    /// `load_scene_map`), then merges every later patch in order. Also
+    /// returns the files involved, for content errors to name.
+    pub fn load_map(&self, map_file: &str, map_directory: Option<&Path>) -> Option<(anyhow::Result<MapData>, String)> {
+        let whole = format!("{MAPS_DIR}/{map_file}.json");
+        let patch = format!("{MAPS_DIR}/{map_file}.patch.json");
+        let replaced_by = self.0.iter().rposition(|pack| pack.join(&whole).is_file());
+        let patches: Vec<PathBuf> = self.0[replaced_by.unwrap_or(0)..]
+            .iter()
+            .map(|pack| pack.join(&patch))
+            .filter(|path| path.is_file())
+            .collect();
+        if replaced_by.is_none() && patches.is_empty() {
+            return None;
+        }
+
+        let (base_path, base) = match (replaced_by, map_directory) {
+            (Some(pack), _) => read_file(self.0[pack].join(&whole)),
+            (None, Some(dir)) => read_file(dir.join(format!("{map_file}.json"))),
+            (None, None) => (
+                format!("assets/{whole}"),
+                crate::asset_manifest::map_json(map_file)
+                    .map(|json| json.as_bytes().to_vec())
+                    .ok_or_else(|| anyhow::anyhow!("no map named {map_file:?} in the embedded manifest")),
+            ),
+        };
+        let path = std::iter::once(base_path)
+            .chain(patches.iter().map(|patch| patch.display().to_string()))
+            .collect::<Vec<_>>()
+            .join(" + ");
+
+        let loaded = base.and_then(|bytes| {
+            let mut map = parse_json(&bytes).context("Failed to parse map JSON")?;
+            for patch in &patches {
+                let (_, bytes) = read_file(patch.clone());
+                let value = parse_json(&bytes?).with_context(|| format!("Failed to parse map patch {}", patch.display()))?;
+                merge_patch(&mut map, value);
+            }
+            MapData::from_value(map).context("Patched map doesn't fit the map format")
+        });
+        Some((loaded, path))
+    }
+}
+
+/// An embedded data file, `assets/<relative>`, as the packs leave it: the
+/// last pack's copy if one has it, else `embedded`. With the path it came
+/// from, for content errors to name.
+pub fn data_file(
+    packs: Option<&ContentPacks>,
+    relative: &str,
+    embedded: &'static str,
+) -> (String, anyhow::Result<Cow<'static, str>>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(pack) = packs.and_then(|packs| packs.origin(Path::new(relative))) {
+        let path = pack.join(relative);
+        let text = std::fs::read_to_string(&path)
+            .map(Cow::Owned)
+            .with_context(|| format!("Failed to read {}", path.display()));
+        return (path.display().to_string(), text);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = packs;
+    (format!("assets/{relative}"), Ok(Cow::Borrowed(embedded)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: PathBuf) -> (String, anyhow::Result<Vec<u8>>) {
+    let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()));
+    (path.display().to_string(), bytes)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_json(bytes: &[u8]) -> anyhow::Result<Value> {
+    Ok(serde_json::from_str(authored_text(bytes)?)?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .flat_map(|entry| {
+            let path = entry.path();
+            if path.is_dir() { files_under(&path) } else { vec![path] }
+        })
+        .collect()
+}
+
+/// Applies a map patch: an RFC 7386 JSON merge patch - objects merge key by
+/// key, `null` removes a key, anything else replaces - with one addition.
+/// An array whose elements are all objects with a `"name"` merges into a
+/// like array by name, appending the names it doesn't have: `"npcs"` in a
+/// patch adds or adjusts NPCs rather than replacing the town's cast.
+pub fn merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+            let Value::Object(target) = target else {
+                unreachable!("just made an object");
+            };
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else {
+                    merge_patch(target.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        Value::Array(patch) if named(&patch) && target.as_array().is_some_and(|target| named(target)) => {
+            let Value::Array(target) = target else {
+                unreachable!("checked above");
+            };
+            for item in patch {
+                match target.iter_mut().find(|existing| existing["name"] == item["name"]) {
+                    Some(existing) => merge_patch(existing, item),
+                    None => target.push(item),
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+fn named(items: &[Value]) -> bool {
+    !items.is_empty() && items.iter().all(|item| item.get("name").is_some_and(Value::is_string))
+}
+
+/// The default asset source with packs over it: a read tries the packs
+/// last to first, then the base directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PackReader {
+    base: FileAssetReader,
+    packs: Vec<FileAssetReader>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PackReader {
+    pub fn new(base: &Path, packs: &[PathBuf]) -> Self {
+        Self {
+            base: FileAssetReader::new(base),
+            packs: packs.iter().map(FileAssetReader::new).collect(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AssetReader for PackReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        for pack in self.packs.iter().rev() {
+            match pack.read(path).await {
+                Err(AssetReaderError::NotFound(_)) => continue,
+                found => return found,
+            }
+        }
+        self.base.read(path).await
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        for pack in self.packs.iter().rev() {
+            match pack.read_meta(path).await {
+                Err(AssetReaderError::NotFound(_)) => continue,
+                found => return found,
+            }
+        }
+        self.base.read_meta(path).await
+    }
+
+    async fn read_directory<'a>(&'a self, path: &'a Path) -> Result<Box<PathStream>, AssetReaderError> {
+        self.base.read_directory(path).await
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        self.base.is_directory(path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn patches_merge_objects_and_remove_nulls() {
+        let mut map = json!({ "name": "town", "width": 7, "music": "theme", "exits": [{ "x": 1 }] });
+        merge_patch(&mut map, json!({ "width": 9, "music": null, "exits": [{ "x": 2 }] }));
+        assert_eq!(map, json!({ "name": "town", "width": 9, "exits": [{ "x": 2 }] }));
+    }
+
+    #[test]
+    fn named_arrays_merge_by_name_and_append_newcomers() {
+        let mut map = json!({ "npcs": [
+            { "name": "Isabella", "x": 3, "y": 1 },
+            { "name": "Casey", "x": 5, "y": 1 },
+        ] });
+        merge_patch(&mut map, json!({ "npcs": [
+            { "name": "Casey", "x": 4 },
+            { "name": "Morgan", "x": 1, "y": 3 },
+        ] }));
+        assert_eq!(map, json!({ "npcs": [
+            { "name": "Isabella", "x": 3, "y": 1 },
+            { "name": "Casey", "x": 4, "y": 1 },
+            { "name": "Morgan", "x": 1, "y": 3 },
+        ] }));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn later_packs_win_and_a_whole_map_drops_earlier_patches() {
+        let root = std::env::temp_dir().join(format!("sregame-packs-{}", std::process::id()));
+        let write = |pack: &str, file: &str, json: &str| {
+            let path = root.join(pack).join(MAPS_DIR).join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, json).unwrap();
+        };
+        let town = r#"{ "name": "PACK TOWN", "width": 1, "height": 1, "tiles": [0], "collision": [false],
+            "npcs": [{ "name": "Isabella", "x": 0, "y": 0, "sprite": "Isabella",
+                "dialogue": { "speaker": "Isabella", "portrait": "", "lines": ["Hi."] } }] }"#;
+        write("early", "intro.patch.json", r#"{ "name": "EARLY" }"#);
+        write("middle", "intro.json", town);
+        write("late", "intro.patch.json", r#"{ "name": "LATE" }"#);
+        let packs = ContentPacks(["early", "middle", "late"].map(|pack| root.join(pack)).to_vec());
+
+        let (loaded, path) = packs.load_map("intro", None).expect("the packs touch intro");
+        assert_eq!(loaded.unwrap().name, "LATE");
+        assert!(path.contains("middle") && path.contains("late") && !path.contains("early"), "{path}");
+        assert_eq!(packs.origin(Path::new("data/maps/intro.patch.json")), Some(root.join("late").as_path()));
+        assert!(packs.load_map("end", None).is_none());
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn a_packs_data_file_replaces_the_embedded_one() {
+        let root = std::env::temp_dir().join(format!("sregame-pack-data-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data/portraits.json"), "{}").unwrap();
+        let packs = ContentPacks(vec![root.clone()]);
+
+        let (path, json) = data_file(Some(&packs), "data/portraits.json", "shipped");
+        assert_eq!((path, json.unwrap().as_ref()), (root.join("data/portraits.json").display().to_string(), "{}"));
+        let (path, json) = data_file(Some(&packs), "data/moods.json", "shipped");
+        assert_eq!((path.as_str(), json.unwrap().as_ref()), ("assets/data/moods.json", "shipped"));
+        assert_eq!(data_file(None, "data/portraits.json", "shipped").1.unwrap(), "shipped");
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
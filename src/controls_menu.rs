@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use crate::assets::GameAssets;
+use crate::game_state::{GameState, Mode};
+use crate::glyphs::InputPrompt;
+use crate::input::{ActiveDevice, Binding, GameAction, GameInput, GamepadStyle, InputMap, button_label, key_label};
+use crate::toast::ShowToast;
+
+/// The Controls screen, over the paused game (`Mode::Menu`). F1 lists every
+/// `GameAction` with its key and gamepad button; pick one and press the new
+/// key or button to rebind it, or Esc to leave it be. When another action
+/// already has it, the screen offers to swap the two. The last row puts
+/// every binding back.
+///
+/// A change takes effect at once - `GameInput` and the prompts (glyphs.rs)
+/// read `InputMap` - and is written to the settings file (settings.rs).
+/// There is no settings menu around it yet: F1 is where its Controls entry
+/// will point.
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        app.init_resource::<ActiveDevice>().add_message::<ShowToast>().add_systems(
+            Update,
+            (
+                open_controls_menu.run_if(in_state(Mode::Exploring)),
+                (navigate_controls_menu, sync_controls_menu).chain().run_if(in_state(Mode::Menu)),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnEnter(Mode::Menu), spawn_controls_menu)
+        .add_systems(OnExit(Mode::Menu), close_controls_menu);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(
+            Update,
+            save_controls
+                .after(navigate_controls_menu)
+                .run_if(resource_exists::<ControlsMenu>.and(resource_changed::<InputMap>)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlsStep {
+    Choose,
+    /// Waiting for the key or button to put on this action.
+    Listen(GameAction),
+    /// `binding` is `with`'s already: swap the two?
+    Swap { action: GameAction, binding: Binding, with: GameAction },
+}
+
+#[derive(Resource, Debug)]
+pub struct ControlsMenu {
+    cursor: usize,
+    step: ControlsStep,
+}
+
+impl Default for ControlsMenu {
+    fn default() -> Self {
+        Self { cursor: 0, step: ControlsStep::Choose }
+    }
+}
+
+/// Every action, then "Reset to defaults".
+const ROWS: usize = GameAction::ALL.len() + 1;
+
+impl ControlsMenu {
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn step(&self) -> ControlsStep {
+        self.step
+    }
+
+    /// Up (-1) or down (+1), wrapping at either end.
+    fn move_cursor(&mut self, step: isize) {
+        self.cursor = (self.cursor as isize + step).rem_euclid(ROWS as isize) as usize;
+    }
+
+    fn title(&self, style: GamepadStyle) -> String {
+        match self.step {
+            ControlsStep::Choose => "Controls".into(),
+            ControlsStep::Listen(action) => format!("Press a key or button for {}", action.label()),
+            ControlsStep::Swap { binding, with, .. } => {
+                format!("{} is {}'s - swap the two?", binding_label(binding, style), with.label())
+            }
+        }
+    }
+
+    /// The footer, an `InputPrompt` template.
+    fn hint(&self) -> &'static str {
+        match self.step {
+            ControlsStep::Choose => "{advance}: change   {cancel}: back",
+            ControlsStep::Listen(_) => "Esc: leave it as it is",
+            ControlsStep::Swap { .. } => "Y / {advance}: swap   N / {cancel}: keep",
+        }
+    }
+}
+
+pub fn binding_label(binding: Binding, style: GamepadStyle) -> &'static str {
+    match binding {
+        Binding::Key(key) => key_label(key),
+        Binding::Button(button) => button_label(button, style),
+    }
+    .unwrap_or("?")
+}
+
+/// One row of the screen: "Talk / use      E            A".
+pub fn row_line(action: GameAction, map: &InputMap, style: GamepadStyle) -> String {
+    let key = binding_label(Binding::Key(map.key(action)), style);
+    let button = binding_label(Binding::Button(map.button(action)), style);
+    format!("{:<14} {key:<12} {button}", action.label())
+}
+
+#[derive(Component)]
+struct ControlsMenuRoot;
+
+#[derive(Component)]
+struct ControlsMenuTitle;
+
+/// The nth row: an action's, or the reset.
+#[derive(Component)]
+struct ControlsMenuRow(usize);
+
+#[derive(Component)]
+struct ControlsMenuHint;
+
+fn open_controls_menu(mut commands: Commands, keyboard: GameInput, mut next_mode: ResMut<NextState<Mode>>) {
+    if !keyboard.just_pressed(KeyCode::F1) {
+        return;
+    }
+    commands.insert_resource(ControlsMenu::default());
+    next_mode.set(Mode::Menu);
+}
+
+fn navigate_controls_menu(
+    keyboard: GameInput,
+    raw_keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    menu: Option<ResMut<ControlsMenu>>,
+    mut map: ResMut<InputMap>,
+    mut next_mode: ResMut<NextState<Mode>>,
+    mut toasts: MessageWriter<ShowToast>,
+) {
+    let Some(mut menu) = menu else { return };
+    let pick = keyboard.action_just_pressed(GameAction::Advance) || keyboard.action_just_pressed(GameAction::Interact);
+    let back = keyboard.just_pressed(KeyCode::Escape);
+
+    match menu.step {
+        // Raw input: whatever is pressed is the answer, bound or not.
+        ControlsStep::Listen(action) => {
+            if raw_keyboard.just_pressed(KeyCode::Escape) {
+                menu.step = ControlsStep::Choose;
+                return;
+            }
+            let pressed = raw_keyboard.get_just_pressed().next().map(|&key| Binding::Key(key)).or_else(|| {
+                gamepads.iter().find_map(|gamepad| gamepad.get_just_pressed().next().map(|&button| Binding::Button(button)))
+            });
+            let Some(binding) = pressed else { return };
+            if let Some(reason) = InputMap::refusal(action, binding) {
+                // Still listening: try another.
+                toasts.write(ShowToast::new(reason));
+                return;
+            }
+            menu.step = match map.conflict(action, binding) {
+                Some(with) => ControlsStep::Swap { action, binding, with },
+                None => {
+                    if map.binding(action, binding) != binding {
+                        map.bind(action, binding);
+                    }
+                    ControlsStep::Choose
+                }
+            };
+        }
+        ControlsStep::Swap { action, binding, with } => {
+            if pick || keyboard.just_pressed(KeyCode::KeyY) {
+                map.bind(action, binding);
+                toasts.write(ShowToast::new(format!("Swapped {} and {}", action.label(), with.label())));
+                menu.step = ControlsStep::Choose;
+            } else if back || keyboard.just_pressed(KeyCode::KeyN) {
+                menu.step = ControlsStep::Choose;
+            }
+        }
+        ControlsStep::Choose => {
+            if keyboard.just_pressed(KeyCode::KeyW) || keyboard.just_pressed(KeyCode::ArrowUp) {
+                menu.move_cursor(-1);
+            } else if keyboard.just_pressed(KeyCode::KeyS) || keyboard.just_pressed(KeyCode::ArrowDown) {
+                menu.move_cursor(1);
+            }
+            if back {
+                next_mode.set(Mode::Exploring);
+            } else if pick {
+                match GameAction::ALL.get(menu.cursor) {
+                    Some(&action) => menu.step = ControlsStep::Listen(action),
+                    None if map.is_default() => {}
+                    None => {
+                        *map = InputMap::default();
+                        toasts.write(ShowToast::new("Controls back to the defaults"));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_controls(
+    map: Res<InputMap>,
+    save_dir: Option<Res<crate::save::SaveDirectory>>,
+    mut toasts: MessageWriter<ShowToast>,
+) {
+    let Some(dir) = save_dir else { return };
+    if let Err(e) = crate::settings::write_controls(&dir.0, &map) {
+        warn!("Controls: {e:#}");
+        toasts.write(ShowToast::new("Couldn't save the controls - see the log"));
+    }
+}
+
+fn spawn_controls_menu(mut commands: Commands, menu: Option<Res<ControlsMenu>>, game_assets: Option<Res<GameAssets>>) {
+    if menu.is_none() {
+        return;
+    }
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    // At 1080p: 26px title, 20px rows, 16px hint - as the save picker.
+    let text = |px: f32| TextFont { font: font.clone().into(), font_size: FontSize::Vh(px / 10.8), ..default() };
+
+    commands
+        .spawn((
+            ControlsMenuRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(14.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.92)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((ControlsMenuTitle, Text::default(), text(26.0), TextColor(Color::WHITE)));
+                    for index in 0..ROWS {
+                        panel.spawn((ControlsMenuRow(index), Text::default(), text(20.0), TextColor(Color::WHITE)));
+                    }
+                    panel.spawn((
+                        ControlsMenuHint,
+                        InputPrompt::new("", font.clone(), 16.0, Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                        Node { align_items: AlignItems::Center, ..default() },
+                    ));
+                });
+        });
+}
+
+/// Redraws the screen from `ControlsMenu` and the bindings: "> " on the
+/// cursor's row, the row being rebound dimmed.
+fn sync_controls_menu(
+    menu: Option<Res<ControlsMenu>>,
+    map: Res<InputMap>,
+    device: Res<ActiveDevice>,
+    mut title: Query<&mut Text, (With<ControlsMenuTitle>, Without<ControlsMenuRow>)>,
+    mut rows: Query<(&ControlsMenuRow, &mut Text, &mut TextColor)>,
+    mut hint: Query<&mut InputPrompt, With<ControlsMenuHint>>,
+) {
+    let Some(menu) = menu.filter(|menu| menu.is_changed() || map.is_changed() || device.is_changed()) else { return };
+    let style = match *device {
+        ActiveDevice::Gamepad(style) => style,
+        ActiveDevice::Keyboard => GamepadStyle::Xbox,
+    };
+    if let Ok(mut text) = title.single_mut() {
+        **text = menu.title(style);
+    }
+    if let Ok(mut prompt) = hint.single_mut()
+        && prompt.template != menu.hint()
+    {
+        prompt.template = menu.hint().into();
+    }
+    for (row, mut text, mut color) in &mut rows {
+        let marker = if row.0 == menu.cursor { "> " } else { "  " };
+        let (line, listening) = match GameAction::ALL.get(row.0) {
+            Some(&action) => (row_line(action, &map, style), menu.step == ControlsStep::Listen(action)),
+            None => ("Reset to defaults".to_string(), false),
+        };
+        **text = format!("{marker}{line}");
+        color.set_if_neq(TextColor(if listening { Color::srgba(1.0, 1.0, 1.0, 0.45) } else { Color::WHITE }));
+    }
+}
+
+fn close_controls_menu(mut commands: Commands, roots: Query<Entity, With<ControlsMenuRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+    commands.remove_resource::<ControlsMenu>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_show_each_actions_key_and_button() {
+        let mut map = InputMap::default();
+        let line = row_line(GameAction::Interact, &map, GamepadStyle::Xbox);
+        assert!(line.starts_with("Talk / use") && line.contains(" E ") && line.ends_with(" A"), "{line}");
+
+        map.bind(GameAction::Skip, Binding::Button(GamepadButton::RightTrigger));
+        let line = row_line(GameAction::Skip, &map, GamepadStyle::PlayStation);
+        assert!(line.contains("Tab") && line.ends_with("R1"), "{line}");
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use opentelemetry::metrics::{Gauge, Meter};
+use crate::camera::{MainCamera, VIEW_HEIGHT, VIEW_WIDTH};
+use crate::game_state::GameState;
+use crate::instrumentation::{MetricsBundle, init_metrics};
+use crate::npc::Npc;
+
+/// View-based activity culling. NPCs farther off screen than `CULL_MARGIN`
+/// get a `Culled` marker, and the per-frame NPC work that only matters to
+/// someone watching - stepping animation (npc.rs) and walking about
+/// (npc_movement.rs) - skips them. Interaction, proximity, and collision
+/// never look at `Culled`.
+///
+/// The margin is what keeps culling invisible: it is wider than any
+/// interaction radius and than the extra width AutoMin scaling shows on a
+/// wide window, and the player is always on screen, so nothing the player
+/// can reach is ever culled. Culling reads last frame's camera position,
+/// which the margin also absorbs.
+///
+/// The visible count is the `NpcVisibility` resource (F3 overlay) and the
+/// `game.npcs.visible` gauge. There's no spatial index in this tree; the
+/// pass is a plain loop, which is fine at today's NPC counts and the one
+/// place to change if it stops being.
+pub struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NpcVisibility>()
+            .add_systems(Update, cull_offscreen_npcs.run_if(in_state(GameState::Playing)));
+        init_metrics::<CullingMetrics>(app);
+    }
+}
+
+/// `game.npcs.visible`, recorded when the count changes.
+#[derive(Resource)]
+pub struct CullingMetrics {
+    pub npcs_visible: Gauge<u64>,
+}
+
+impl MetricsBundle for CullingMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            npcs_visible: meter
+                .u64_gauge("game.npcs.visible")
+                .with_description("NPCs inside the view plus the culling margin (see culling.rs)")
+                .build(),
+        }
+    }
+}
+
+/// Four tiles past every screen edge.
+pub const CULL_MARGIN: f32 = 4.0 * 48.0;
+
+/// Paused while off screen - see `CullingPlugin`.
+#[derive(Component)]
+pub struct Culled;
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NpcVisibility {
+    /// NPCs inside the view plus margin.
+    pub visible: usize,
+    pub total: usize,
+}
+
+/// Whether `position` is outside the view centered on `camera` with
+/// `half_size` half-extents, grown by `CULL_MARGIN`.
+pub fn outside_view(camera: Vec2, half_size: Vec2, position: Vec2) -> bool {
+    let reach = half_size + Vec2::splat(CULL_MARGIN);
+    let offset = (position - camera).abs();
+    offset.x > reach.x || offset.y > reach.y
+}
+
+fn cull_offscreen_npcs(
+    mut commands: Commands,
+    camera: Query<(&Transform, &Projection), With<MainCamera>>,
+    npcs: Query<(Entity, &Transform, Has<Culled>), With<Npc>>,
+    mut visibility: ResMut<NpcVisibility>,
+    metrics: Option<Res<CullingMetrics>>,
+) {
+    let Ok((camera_transform, projection)) = camera.single() else { return };
+    // AutoMin always shows at least the design view. The projection's
+    // area says how much more; it's computed by the renderer, so headless
+    // runs only ever see the floor.
+    let design_half = Vec2::new(VIEW_WIDTH, VIEW_HEIGHT) / 2.0;
+    let half_size = match projection {
+        Projection::Orthographic(ortho) => ortho.area.half_size().max(design_half),
+        _ => design_half,
+    };
+    let camera_pos = camera_transform.translation.truncate();
+
+    let mut counts = NpcVisibility::default();
+    for (entity, transform, culled) in &npcs {
+        counts.total += 1;
+        let outside = outside_view(camera_pos, half_size, transform.translation.truncate());
+        if !outside {
+            counts.visible += 1;
+        }
+        match (outside, culled) {
+            (true, false) => {
+                commands.entity(entity).insert(Culled);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Culled>();
+            }
+            _ => {}
+        }
+    }
+
+    if visibility.set_if_neq(counts)
+        && let Some(metrics) = metrics
+    {
+        metrics.npcs_visible.record(counts.visible as u64, &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::tile_to_world;
+
+    /// A 60x40 map with the player pressed into its top-left corner: the
+    /// camera clamps to the map edge, so the player sits near the corner of
+    /// the screen rather than its center. Their neighbor must stay active;
+    /// the far corner is culled.
+    #[test]
+    fn npcs_next_to_a_player_in_the_corner_are_never_culled() {
+        let (width, height) = (60, 40);
+        let map_half = Vec2::new(width as f32, height as f32) * 48.0 / 2.0;
+        let half_view = Vec2::new(VIEW_WIDTH, VIEW_HEIGHT) / 2.0;
+        let camera = Vec2::new(-(map_half.x - half_view.x), map_half.y - half_view.y);
+
+        let mut app = App::new();
+        app.init_resource::<NpcVisibility>()
+            .add_systems(Update, cull_offscreen_npcs);
+        app.world_mut().spawn((
+            MainCamera,
+            Transform::from_translation(camera.extend(999.9)),
+            Projection::Orthographic(OrthographicProjection::default_2d()),
+        ));
+        let npc = |name: &str| Npc {
+            id: crate::content::slug(name),
+            name: name.into(),
+            sprite_facing: crate::npc::NpcFacing::Down,
+            sprite_slot: 0,
+        };
+        let player_tile = tile_to_world(0, 0, width, height);
+        let neighbor = app.world_mut().spawn((
+            npc("Neighbor"),
+            Transform::from_translation(tile_to_world(1, 0, width, height).extend(1.0)),
+        )).id();
+        let far = app.world_mut().spawn((
+            npc("Far"),
+            Transform::from_translation(tile_to_world(59, 39, width, height).extend(1.0)),
+        )).id();
+        assert!(!outside_view(camera, half_view, player_tile), "player on screen");
+
+        app.update();
+        assert!(app.world().get::<Culled>(neighbor).is_none());
+        assert!(app.world().get::<Culled>(far).is_some());
+        assert_eq!(*app.world().resource::<NpcVisibility>(), NpcVisibility { visible: 1, total: 2 });
+    }
+}
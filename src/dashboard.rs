@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+use opentelemetry::KeyValue;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use crate::assets::GameAssets;
+use crate::game_state::{GameState, Mode};
+use crate::glyphs::InputPrompt;
+use crate::input::GameInput;
+use crate::dialogue::DialogueMetrics;
+use crate::instrumentation::GameMeter;
+use crate::player::{Facing, Player};
+use crate::tilemap::CollisionMap;
+
+/// The error budget terminal: facing one of a map's `terminals`
+/// (map_data.rs) and pressing Interact opens a green-screen panel of the
+/// game's own telemetry - FPS, p95 frame time over the last minute,
+/// dialogue lines read, and whether exports to the collector are getting
+/// through. The game observing itself.
+///
+/// It reads `LiveMetrics`, a rolling in-process aggregate, so it needs no
+/// collector. The recording sites it shows go through `MeterTee`, which
+/// records on the OTel instrument and `LiveMetrics` alike, so the terminal
+/// and the backend count the same things. Frame times have no instrument;
+/// they're sampled here from `Time<Real>`, like the frame watchdog does.
+///
+/// Export health counts the OpenTelemetry SDK's own error logs
+/// (telemetry.rs). A failed span or log export logs one; this SDK
+/// version only logs failed metric exports at debug, so those don't show.
+///
+/// The panel is `Mode::Dashboard`; Escape closes it.
+pub struct DashboardPlugin;
+
+impl Plugin for DashboardPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        app.init_resource::<LiveMetrics>()
+            .add_systems(Last, sample_live_metrics.run_if(crate::focus::recording_frames))
+            .add_systems(
+                Update,
+                (
+                    open_dashboard.run_if(in_state(Mode::Exploring)),
+                    (close_dashboard, refresh_dashboard).chain().run_if(in_state(Mode::Dashboard)),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(Mode::Dashboard), spawn_dashboard)
+            .add_systems(OnExit(Mode::Dashboard), despawn_dashboard);
+    }
+}
+
+/// Errors the OpenTelemetry SDK has logged this run, counted by a tracing
+/// layer installed with the exporters (telemetry.rs). main.rs makes one,
+/// hands a clone to that layer and inserts another; the run report reads
+/// it at exit. Stays 0 without exporters, and isn't there at all without
+/// main.rs (the test harness).
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ExportErrors(Arc<AtomicU64>);
+
+impl ExportErrors {
+    pub fn record(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The dashboard's numbers, kept whether or not it's open.
+#[derive(Resource, Debug, Default)]
+pub struct LiveMetrics {
+    /// (`Time<Real>` elapsed, frame time) for every frame of the last
+    /// `WINDOW`, oldest first.
+    frames: VecDeque<(Duration, Duration)>,
+    /// `game.dialogue_lines_read`, this run.
+    pub dialogue_lines_read: u64,
+    /// `ExportErrors` as last seen, and when it last went up.
+    export_errors: u64,
+    last_export_error: Option<Duration>,
+}
+
+impl LiveMetrics {
+    pub const WINDOW: Duration = Duration::from_secs(60);
+
+    /// A frame that took `frame` and ended at `now`.
+    pub fn record_frame(&mut self, now: Duration, frame: Duration) {
+        self.frames.push_back((now, frame));
+        while self.frames.front().is_some_and(|&(at, _)| now.saturating_sub(at) > Self::WINDOW) {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Frames per second over the last second; None before the first frame.
+    pub fn fps(&self) -> Option<f64> {
+        let &(now, _) = self.frames.back()?;
+        let last_second: Vec<Duration> = self
+            .frames
+            .iter()
+            .rev()
+            .take_while(|&&(at, _)| now.saturating_sub(at) < Duration::from_secs(1))
+            .map(|&(_, frame)| frame)
+            .collect();
+        let total: Duration = last_second.iter().sum();
+        (!total.is_zero()).then(|| last_second.len() as f64 / total.as_secs_f64())
+    }
+
+    /// 95th percentile frame time over the last `WINDOW`.
+    pub fn p95_frame_time(&self) -> Option<Duration> {
+        let mut frames: Vec<Duration> = self.frames.iter().map(|&(_, frame)| frame).collect();
+        if frames.is_empty() {
+            return None;
+        }
+        frames.sort();
+        Some(frames[(frames.len() - 1) * 95 / 100])
+    }
+
+    /// Note the SDK's error count as of `now`.
+    pub fn observe_export_errors(&mut self, count: u64, now: Duration) {
+        if count > self.export_errors {
+            self.last_export_error = Some(now);
+        }
+        self.export_errors = count;
+    }
+
+    /// `exporting`: whether there's a collector to export to at all.
+    pub fn export_health(&self, exporting: bool, now: Duration) -> ExportHealth {
+        match self.last_export_error {
+            Some(at) if now.saturating_sub(at) <= Self::WINDOW => ExportHealth::Failing {
+                errors: self.export_errors,
+                secs_ago: now.saturating_sub(at).as_secs(),
+            },
+            _ if !exporting => ExportHealth::Off,
+            _ => ExportHealth::Ok { earlier_errors: self.export_errors },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportHealth {
+    /// No collector configured.
+    Off,
+    /// No errors in the last `LiveMetrics::WINDOW`.
+    Ok { earlier_errors: u64 },
+    Failing { errors: u64, secs_ago: u64 },
+}
+
+/// The OTel instruments and `LiveMetrics` as one: a recording site the
+/// dashboard shows goes through here, so the terminal and the collector
+/// agree.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct MeterTee<'w> {
+    dialogue: Option<Res<'w, DialogueMetrics>>,
+    live: Option<ResMut<'w, LiveMetrics>>,
+}
+
+impl MeterTee<'_> {
+    /// `game.dialogue_lines_read`, by speaker id
+    /// (`DialogueSegment::speaker_id`).
+    pub fn dialogue_line_read(&mut self, speaker_id: &str) {
+        if let Some(dialogue) = &self.dialogue {
+            dialogue.lines_read.add(1, &[KeyValue::new("speaker.id", speaker_id.to_string())]);
+        }
+        if let Some(live) = &mut self.live {
+            live.dialogue_lines_read += 1;
+        }
+    }
+}
+
+/// The panel's readings, one per line.
+pub fn dashboard_text(live: &LiveMetrics, health: ExportHealth) -> String {
+    let fps = live.fps().map_or("--".to_string(), |fps| format!("{fps:.0}"));
+    let p95 = live
+        .p95_frame_time()
+        .map_or("--".to_string(), |p95| format!("{:.1}ms", p95.as_secs_f64() * 1000.0));
+    let export = match health {
+        ExportHealth::Off => "off (no collector)".to_string(),
+        ExportHealth::Ok { earlier_errors: 0 } => "ok".to_string(),
+        ExportHealth::Ok { earlier_errors } => format!("ok ({earlier_errors} errors earlier)"),
+        ExportHealth::Failing { errors, secs_ago } => format!("FAILING ({errors} errors, last {secs_ago}s ago)"),
+    };
+    [
+        ("FPS", fps),
+        ("Frame p95 (1m)", p95),
+        ("Lines read", live.dialogue_lines_read.to_string()),
+        ("Export", export),
+    ]
+    .map(|(label, value)| format!("{label:.<18} {value}"))
+    .join("\n")
+}
+
+/// CRT phosphor green.
+const PHOSPHOR: Color = Color::srgb(0.35, 1.0, 0.45);
+
+#[derive(Component)]
+struct DashboardRoot;
+
+#[derive(Component)]
+struct DashboardText;
+
+fn sample_live_metrics(time: Res<Time<Real>>, mut live: ResMut<LiveMetrics>, export_errors: Option<Res<ExportErrors>>) {
+    live.record_frame(time.elapsed(), time.delta());
+    let errors = export_errors.map_or(0, |errors| errors.count());
+    live.observe_export_errors(errors, time.elapsed());
+}
+
+fn open_dashboard(
+    keyboard: GameInput,
+    player: Query<(&Transform, &Facing), With<Player>>,
+    collision_map: Option<Res<CollisionMap>>,
+    mut next_mode: ResMut<NextState<Mode>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let (Some(map), Ok((transform, facing))) = (collision_map, player.single()) else { return };
+    // Same tile logic as handle_interaction_input in npc.rs, which leaves
+    // this press alone.
+    let logical_pos = crate::player::logical_position(transform.translation.truncate());
+    let (x, y) = crate::map_data::world_to_tile(logical_pos, map.width, map.height);
+    let (dx, dy) = facing.tile_delta();
+    if map.is_terminal(x + dx, y + dy) {
+        info!("📟 Terminal at ({}, {}): opening the dashboard", x + dx, y + dy);
+        next_mode.set(Mode::Dashboard);
+    }
+}
+
+fn close_dashboard(keyboard: GameInput, mut next_mode: ResMut<NextState<Mode>>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_mode.set(Mode::Exploring);
+    }
+}
+
+fn spawn_dashboard(mut commands: Commands, game_assets: Option<Res<GameAssets>>) {
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    // At 1080p: 26px title, 22px readings, 16px hint - Vh like the save menu.
+    let text = |px: f32| TextFont { font: font.clone().into(), font_size: FontSize::Vh(px / 10.8), ..default() };
+
+    commands
+        .spawn((
+            DashboardRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(15.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        border: UiRect::all(Val::Px(3.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.02, 0.06, 0.03, 0.95)),
+                    BorderColor::all(PHOSPHOR),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new("SREGAME STATUS // LIVE"), text(26.0), TextColor(PHOSPHOR)));
+                    panel.spawn((DashboardText, Text::default(), text(22.0), TextColor(PHOSPHOR)));
+                    panel.spawn((
+                        InputPrompt::new("The game, observing itself.   {cancel}: close", font.clone(), 16.0, PHOSPHOR.with_alpha(0.6)),
+                        Node { align_items: AlignItems::Center, ..default() },
+                    ));
+                });
+        });
+}
+
+/// Every frame while open: the numbers move, that's the point.
+fn refresh_dashboard(
+    time: Res<Time<Real>>,
+    live: Res<LiveMetrics>,
+    meter: Option<Res<GameMeter>>,
+    mut texts: Query<&mut Text, With<DashboardText>>,
+) {
+    let health = live.export_health(meter.is_some(), time.elapsed());
+    for mut text in &mut texts {
+        text.0 = dashboard_text(&live, health);
+    }
+}
+
+fn despawn_dashboard(mut commands: Commands, roots: Query<Entity, With<DashboardRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn frame_readings_cover_the_last_minute() {
+        let mut live = LiveMetrics::default();
+        assert_eq!(live.fps(), None);
+        assert_eq!(live.p95_frame_time(), None);
+
+        // A 500ms hitch, then a minute and a bit of steady 20ms frames.
+        live.record_frame(ms(500), ms(500));
+        let mut now = ms(500);
+        while now < ms(60_000) {
+            now += ms(20);
+            live.record_frame(now, ms(20));
+        }
+        assert_eq!(live.fps().map(f64::round), Some(50.0));
+        assert_eq!(live.p95_frame_time(), Some(ms(20)), "one hitch in 3000 frames is under the p95");
+
+        live.record_frame(now + ms(100), ms(100));
+        live.record_frame(now + ms(200), ms(100));
+        assert!(live.fps().unwrap() < 50.0);
+        now += ms(2000);
+        live.record_frame(now, ms(20));
+        assert!(live.frames.iter().all(|&(_, frame)| frame != ms(500)), "the hitch aged out");
+    }
+
+    #[test]
+    fn export_health_reads_off_ok_and_failing() {
+        let mut live = LiveMetrics::default();
+        assert_eq!(live.export_health(false, ms(1000)), ExportHealth::Off);
+        assert_eq!(live.export_health(true, ms(1000)), ExportHealth::Ok { earlier_errors: 0 });
+
+        live.observe_export_errors(2, ms(5_000));
+        live.observe_export_errors(2, ms(6_000));
+        assert_eq!(live.export_health(true, ms(17_000)), ExportHealth::Failing { errors: 2, secs_ago: 12 });
+        assert_eq!(live.export_health(true, ms(70_000)), ExportHealth::Ok { earlier_errors: 2 });
+    }
+
+    #[test]
+    fn the_panel_shows_each_reading() {
+        let mut live = LiveMetrics::default();
+        live.dialogue_lines_read = 7;
+        let text = dashboard_text(&live, ExportHealth::Off);
+        assert!(text.contains("FPS............... --"), "{text}");
+        assert!(text.contains("Lines read........ 7"), "{text}");
+        assert!(text.ends_with("Export............ off (no collector)"), "{text}");
+
+        live.record_frame(ms(16), ms(16));
+        let text = dashboard_text(&live, ExportHealth::Failing { errors: 3, secs_ago: 4 });
+        assert!(text.contains("Frame p95 (1m).... 16.0ms"), "{text}");
+        assert!(text.contains("FAILING (3 errors, last 4s ago)"), "{text}");
+    }
+}
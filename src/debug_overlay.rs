@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use std::time::Duration;
+use crate::assets::GameAssets;
+use crate::content_errors::ContentErrors;
+use crate::culling::NpcVisibility;
+use crate::entity_audit::EntityCensus;
+use crate::game_state::Scene;
+use crate::input_latency::{InputLatency, LatencyAction};
+use crate::scene_timings::SceneTimings;
+
+/// F3 developer overlay: a plain text panel in the top-left corner for the
+/// things a playtester should be able to read without a console attached.
+/// Today that's time in the current map (scene_timings.rs), entity counts
+/// (entity_audit.rs), on-screen NPCs (culling.rs), input latency
+/// (input_latency.rs), and broken content (content_errors.rs). Hidden until toggled, in every build - it only
+/// shows information the game already has.
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        // Everything the overlay reports on, zeroed when the plugin that
+        // tracks it isn't in the app.
+        app.init_resource::<ContentErrors>()
+            .init_resource::<SceneTimings>()
+            .init_resource::<EntityCensus>()
+            .init_resource::<NpcVisibility>()
+            .init_resource::<InputLatency>()
+            .add_systems(Update, (toggle_debug_overlay, refresh_debug_overlay).chain());
+    }
+}
+
+#[derive(Component)]
+struct DebugOverlay;
+
+#[derive(Component)]
+struct DebugOverlayText;
+
+fn toggle_debug_overlay(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    existing: Query<Entity, With<DebugOverlay>>,
+    game_assets: Option<Res<GameAssets>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    if let Ok(overlay) = existing.single() {
+        commands.entity(overlay).despawn();
+        return;
+    }
+
+    let font = game_assets
+        .map(|assets| assets.dialogue_font.clone())
+        .unwrap_or_default();
+
+    commands
+        .spawn((
+            DebugOverlay,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                left: Val::Px(8.0),
+                max_width: Val::Percent(60.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+            // Above the dialogue box and toasts.
+            GlobalZIndex(100),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                DebugOverlayText,
+                Text::new(""),
+                TextFont {
+                    font: font.into(),
+                    font_size: FontSize::Px(16.0),
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+/// Rebuild the panel text. Cheap enough to do every frame while it's open,
+/// and it keeps the overlay honest about errors recorded after it opened.
+fn refresh_debug_overlay(
+    content_errors: Res<ContentErrors>,
+    scene_timings: Res<SceneTimings>,
+    scene: Option<Res<State<Scene>>>,
+    census: Res<EntityCensus>,
+    npc_visibility: Res<NpcVisibility>,
+    input_latency: Res<InputLatency>,
+    mut texts: Query<&mut Text, With<DebugOverlayText>>,
+) {
+    let scene_time = scene.map(|scene| (*scene.get(), scene_timings.total(*scene.get())));
+    for mut text in &mut texts {
+        text.0 = overlay_text(scene_time, &census, &npc_visibility, &input_latency, &content_errors);
+    }
+}
+
+fn overlay_text(
+    scene_time: Option<(Scene, Duration)>,
+    census: &EntityCensus,
+    npc_visibility: &NpcVisibility,
+    input_latency: &InputLatency,
+    content_errors: &ContentErrors,
+) -> String {
+    let mut out = String::from("F3 debug\n");
+    if let Some((scene, elapsed)) = scene_time {
+        out.push_str(&format!("Scene: {scene:?} ({:.0}s this session)\n", elapsed.as_secs_f32()));
+    }
+    if let Some(latest) = &census.latest {
+        out.push_str(&format!("Entities: {}", latest.total));
+        if let Some((group, growth)) = census.largest_growth() {
+            out.push_str(&format!(" (most growth: {group} {growth:+})"));
+        }
+        out.push('\n');
+    }
+    if npc_visibility.total > 0 {
+        out.push_str(&format!("NPCs visible: {}/{}\n", npc_visibility.visible, npc_visibility.total));
+    }
+    let latencies: Vec<String> = [LatencyAction::Interact, LatencyAction::Move]
+        .into_iter()
+        .filter_map(|action| Some(format!("{} {:.0}ms", action.as_str(), input_latency.p95(action)?.as_secs_f64() * 1000.0)))
+        .collect();
+    if !latencies.is_empty() {
+        out.push_str(&format!("Input p95: {}\n", latencies.join(", ")));
+    }
+    if content_errors.is_empty() {
+        out.push_str("Content errors: none");
+    } else {
+        out.push_str(&format!("Content errors ({}):", content_errors.errors.len()));
+        for error in &content_errors.errors {
+            out.push_str(&format!(
+                "\n  [{:.1}s] {}: {}",
+                error.at.as_secs_f32(),
+                error.path,
+                error.error
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_lists_each_content_error() {
+        let mut errors = ContentErrors::default();
+        assert!(overlay_text(None, &EntityCensus::default(), &NpcVisibility::default(), &InputLatency::default(), &errors).ends_with("Content errors: none"));
+
+        errors.record("maps/town.json", "expected `,`", Duration::from_millis(12_340), None);
+        let text = overlay_text(None, &EntityCensus::default(), &NpcVisibility::default(), &InputLatency::default(), &errors);
+        assert!(text.contains("Content errors (1):"), "{text}");
+        assert!(text.contains("[12.3s] maps/town.json: expected `,`"), "{text}");
+    }
+
+    #[test]
+    fn overlay_shows_time_in_the_current_scene() {
+        let text = overlay_text(
+            Some((Scene::TeamDisco, Duration::from_secs(95))),
+            &EntityCensus::default(),
+            &NpcVisibility { visible: 3, total: 5 },
+            &InputLatency::default(),
+            &ContentErrors::default(),
+        );
+        assert!(text.contains("Scene: TeamDisco (95s this session)"), "{text}");
+        assert!(text.contains("NPCs visible: 3/5"), "{text}");
+        assert!(!text.contains("Input p95"), "nothing measured yet: {text}");
+    }
+
+    #[test]
+    fn overlay_shows_input_latency_once_measured() {
+        let mut latency = InputLatency::default();
+        latency.record(LatencyAction::Move, Duration::from_millis(17), None);
+        let text = overlay_text(None, &EntityCensus::default(), &NpcVisibility::default(), &latency, &ContentErrors::default());
+        assert!(text.contains("Input p95: move 17ms\n"), "{text}");
+    }
+}
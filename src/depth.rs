@@ -22,7 +22,8 @@ impl Plugin for DepthPlugin {
 }
 
 /// Everything y-sorted lives in a band around this z, between the door
-/// sprites (0.9, always behind characters) and the upper tile layer (2.0).
+/// sprites (0.9, always behind characters) and the overhead tile layers (2.0
+/// and up).
 const CHARACTER_Z_BASE: f32 = 1.0;
 
 /// Chosen so the largest map (town, 39 tiles tall = ±936 world y plus a
@@ -30,6 +31,12 @@ const CHARACTER_Z_BASE: f32 = 1.0;
 /// the (0.9, 2.0) band above.
 const Y_SORT_SCALE: f32 = 1.0 / 40_000.0;
 
+/// Drop shadows (shadow.rs) are children of y-sorted characters, so their
+/// z is relative: this puts every shadow below the whole character band -
+/// a shadow never draws over a neighbour's feet - and still above the
+/// doors at 0.9.
+pub const SHADOW_Z_OFFSET: f32 = -0.06;
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct YSorted {
@@ -89,4 +96,14 @@ mod tests {
             assert!(z > 0.9 && z < 2.0, "z {z} escaped the character band");
         }
     }
+
+    #[test]
+    fn shadows_stay_between_doors_and_every_character() {
+        let lowest_character = z_for(1000.0, 0.0);
+        for feet_y in [-1000.0_f32, 1000.0] {
+            let shadow_z = z_for(feet_y, 0.0) + SHADOW_Z_OFFSET;
+            assert!(shadow_z > 0.9, "shadow {shadow_z} sank under the doors");
+            assert!(shadow_z < lowest_character, "shadow {shadow_z} drew over a character");
+        }
+    }
 }
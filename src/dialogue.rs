@@ -1,29 +1,182 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use crate::game_state::Mode;
+use crate::game_state::{Mode, SceneChangeRequest};
 use crate::assets::GameAssets;
-use crate::instrumentation::{GameTracer, GameMeter, ActiveDialogue, record_dialogue_line_event};
-use opentelemetry::{KeyValue, Context as OtelContext, trace::{Tracer, Span as _}};
+use crate::content_errors::{BrokenContent, ContentErrors, ContentMetrics};
+use crate::dialogue_history::{DialogueHistory, HistoryLog};
+use crate::flags::SetFlagEvent;
+use crate::game_events::{GameEvent, GameEvents};
+use crate::glyphs::InputPrompt;
+use crate::hooks::{DialogueEndOutcome, DialogueEnded, DialogueLineShown, DialogueStarted, DialogueTopicChosen};
+use crate::input::GameAction;
+use crate::input_latency::{InputLatency, LatencyAction, LatencyMetrics};
+use crate::instrumentation::{GameTracer, ActiveDialogue, MetricsBundle, PreviousDialogues, init_metrics, record_dialogue_line_event};
+use crate::map_data::{
+    BoxPosition, DialogueBoxLayout, DialogueData, DialogueTopic, FACE_SHEET_COLUMNS, FACE_SHEET_ROWS, LineAction,
+};
+use crate::mood::{Mood, Moods, MoodTint, PLAYER_NAME_COLOR, TintTarget};
+use crate::portrait::{PortraitAnimation, PortraitSheet, Portraits};
+use crate::rumble::RumbleEvent;
+use crate::variables::{GameVariables, GameVariablesSet};
+use opentelemetry::metrics::{Counter, Histogram, Meter};
+use opentelemetry::{Array, KeyValue, StringValue, Value, Context as OtelContext, trace::{Tracer, Span as _, SpanContext, TraceContextExt as _}};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use web_time::Instant;
 
 pub struct DialoguePlugin;
 
 impl Plugin for DialoguePlugin {
     fn build(&self, app: &mut App) {
-        app.add_message::<StartDialogueEvent>()
-            .add_systems(Update, handle_dialogue_events.run_if(in_state(Mode::Exploring)))
+        app.add_message::<DialogueRequest>()
+            .add_message::<DialogueCompleted>()
+            .register_type::<DialogueOutcome>()
+            .add_message::<DialogueStarted>()
+            .add_message::<DialogueLineShown>()
+            .add_message::<DialogueTopicChosen>()
+            .add_message::<DialogueEnded>()
+            .add_message::<SetFlagEvent>()
+            .add_message::<SceneChangeRequest>()
+            .add_message::<RumbleEvent>()
+            .add_message::<bevy::window::WindowResized>()
+            .init_resource::<SeenDialogues>()
+            .init_resource::<PreviousDialogues>()
+            .init_resource::<QueuedDialogues>()
+            // init, not insert: main.rs's `--text-speed` wins.
+            .init_resource::<DialogueSettings>()
+            // Owned by other plugins; the defaults are a box with no art,
+            // moods or portraits, which still reads fine.
+            .init_resource::<GameAssets>()
+            .init_resource::<Moods>()
+            .init_resource::<Portraits>()
+            .init_resource::<ContentErrors>()
+            .init_resource::<crate::npc::NpcsMet>()
+            .init_resource::<InputLatency>()
+            .add_systems(
+                OnEnter(crate::game_state::GameState::Playing),
+                (reset_previous_dialogues, forget_seen_dialogues, forget_queued_dialogues),
+            )
+            // After whoever asked, so a talk opens the box the same frame,
+            // and after the variables its lines quote are up to date. In
+            // Dialogue too, to turn away (or queue) what comes mid-talk.
+            .add_systems(Update, handle_dialogue_events
+                .after(DialogueRequestSet)
+                .after(GameVariablesSet)
+                .run_if(in_state(Mode::Exploring).or(in_state(Mode::Dialogue))))
             .add_systems(OnEnter(Mode::Dialogue), spawn_dialogue_ui)
+            .add_systems(Update, forget_pages_on_resize
+                .before(DialogueReadingSet)
+                .run_if(in_state(Mode::Dialogue)))
             .add_systems(Update, (
                 type_dialogue_text,
+                navigate_dialogue_menus,
+                point_at_menu_rows,
                 advance_dialogue,
-            ).run_if(in_state(Mode::Dialogue)))
+                render_typewriter,
+                skip_seen_dialogue,
+                sync_topic_menu,
+                sync_choice_list,
+                sync_continue_indicator,
+                fall_back_from_failed_portraits,
+                animate_portrait,
+                place_dialogue_box,
+            ).chain()
+                .in_set(DialogueReadingSet)
+                .run_if(in_state(Mode::Dialogue).and(not(resource_exists::<HistoryLog>)))
+                // Each on its own: once one ends the conversation, the
+                // rest leave it be.
+                .distributive_run_if(dialogue_open))
+            // After layout, so computed sizes are this frame's text.
+            .add_systems(PostUpdate, detect_text_overflow
+                .after(bevy::ui::UiSystems::Layout)
+                .run_if(in_state(Mode::Dialogue)))
+            // After Update, so whatever ended the conversation this frame
+            // (Escape lives in game_state.rs) has.
+            .add_systems(PostUpdate, finish_dialogue_session)
             .add_systems(OnExit(Mode::Dialogue), despawn_dialogue_ui);
+        crate::input::init_game_input(app);
+        init_metrics::<DialogueMetrics>(app);
+        init_metrics::<ContentMetrics>(app);
+        init_metrics::<LatencyMetrics>(app);
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<crate::game_state::GameStatePlugin>(app, "DialoguePlugin");
+    }
+}
+
+/// Systems that write `DialogueRequest`s: NPC interaction (npc.rs) and
+/// scripted scenes (transitions.rs). `handle_dialogue_events` runs after
+/// them.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DialogueRequestSet;
+
+/// The open conversation's own systems: typing, advancing, skipping.
+/// Escape (game_state.rs) runs after them, so a conversation read to the
+/// end this frame has ended by the time Escape could end it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DialogueReadingSet;
+
+/// How conversations are read: speeds, dwell, skips and overflows.
+#[derive(Resource)]
+pub struct DialogueMetrics {
+    /// Deprecated: over the whole session, typewriter included.
+    pub reading_speed: Histogram<f64>,
+    pub line_dwell: Histogram<f64>,
+    pub dwell_reading_speed: Histogram<f64>,
+    /// Recorded through dashboard::MeterTee, which also counts it live.
+    pub lines_read: Counter<u64>,
+    pub skipped_seen: Counter<u64>,
+    pub text_overflow: Counter<u64>,
+    pub topics_selected: Counter<u64>,
+}
+
+impl MetricsBundle for DialogueMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            reading_speed: meter
+                .f64_histogram("game.dialogue.reading_speed")
+                .with_description(
+                    "Deprecated: characters per second over the whole dialogue, typewriter time included, so \
+                     mostly the text speed; kept for continuity - see game.dialogue.dwell_reading_speed",
+                )
+                .with_unit("chars/sec")
+                .build(),
+            line_dwell: meter
+                .f64_histogram("game.dialogue.line_dwell")
+                .with_description("Time each dialogue box stayed on screen whole before the player moved on (see dialogue.rs)")
+                .with_unit("s")
+                .build(),
+            dwell_reading_speed: meter
+                .f64_histogram("game.dialogue.dwell_reading_speed")
+                .with_description("Characters per second of a dialogue, over its boxes' dwell time alone (see dialogue.rs)")
+                .with_unit("chars/sec")
+                .build(),
+            lines_read: meter
+                .u64_counter("game.dialogue_lines_read")
+                .with_description("Total number of dialogue lines displayed")
+                .build(),
+            skipped_seen: meter
+                .u64_counter("game.dialogue.skipped_seen")
+                .with_description("Already-read dialogues skipped with Tab (see dialogue.rs)")
+                .build(),
+            text_overflow: meter
+                .u64_counter("game.ui.text_overflow")
+                .with_description("Dialogue boxes whose text outgrew the box (see dialogue.rs)")
+                .build(),
+            topics_selected: meter
+                .u64_counter("game.dialogue.topic_selected")
+                .with_description("Topics chosen from a hub character's menu, by topic id (see dialogue.rs)")
+                .build(),
+        }
     }
 }
 
 /// One message box: its own speaker and portrait. A plain NPC conversation
 /// is a run of segments sharing one speaker; a scripted scene (the retro
 /// retrospective) switches speaker/portrait between segments.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct DialogueSegment {
     pub speaker: String,
     /// Asset path like "textures/portraits/Nature.png"; empty = no portrait.
@@ -32,423 +185,2809 @@ pub struct DialogueSegment {
     pub portrait_path: String,
     /// Which cell of the face sheet to crop - see FACE_SHEET_* below.
     pub portrait_face_index: u32,
+    /// As authored, markup and all (see `parse_markup`).
     pub text: String,
+    /// Mood name from assets/data/moods.json (see mood.rs); None = neutral.
+    pub mood: Option<String>,
+    /// Where the box sits while this one is up; what it leaves out comes
+    /// from the map (`MapDialogueBox`). A change between boxes slides the
+    /// box over (see `BoxSlide`).
+    pub box_layout: DialogueBoxLayout,
+    /// Run as the player reads past this box (see `LineAction`).
+    pub actions: Vec<LineAction>,
+    /// The stable id telemetry knows `speaker` by, from senders that have
+    /// one: an NPC's own lines carry its `NpcData::id`. None: see
+    /// `speaker_id()`.
+    pub speaker_id: Option<String>,
 }
 
-#[derive(Message)]
-pub struct StartDialogueEvent {
-    pub segments: Vec<DialogueSegment>,
+impl DialogueSegment {
+    /// `speaker_id`, else `speaker`'s slug (`content::slug`) - what metrics
+    /// label the line with, so renaming a character keeps their series.
+    pub fn speaker_id(&self) -> String {
+        self.speaker_id.clone().unwrap_or_else(|| crate::content::slug(&self.speaker))
+    }
+
+    /// The line as the player reads it: `text` without its markup.
+    pub fn plain_text(&self) -> String {
+        parse_markup(&self.text).text
+    }
 }
 
-/// RPGMaker MZ face sheets are always a 4-column x 2-row grid of 144x144px
-/// cells (`ImageManager.faceWidth`/`faceHeight` in rmmz_managers.js and
-/// `Window_Base.prototype.drawFace` in rmmz_windows.js are hardcoded to this
-/// regardless of a given sheet's actual pixel dimensions - some of ours are
-/// taller than 288px, e.g. assets/textures/portraits/casey.png, with the
-/// extra rows simply unused), so `faceIndex` 0-7 always maps into this one
-/// fixed grid across every portrait file.
-const FACE_SHEET_CELL_SIZE: UVec2 = UVec2::new(144, 144);
-const FACE_SHEET_COLUMNS: u32 = 4;
-const FACE_SHEET_ROWS: u32 = 2;
+/// Open a conversation. Build one with `DialogueRequestBuilder` - NPC
+/// interaction (npc.rs) and scripted scenes (transitions.rs) both do -
+/// rather than by hand: the builder fills in the id and keeps the fields
+/// consistent with each other.
+#[derive(Message, Clone, Debug)]
+pub struct DialogueRequest {
+    /// `dialogue_id` of the content unless the sender chose one.
+    pub id: String,
+    /// The entity being talked to, if any (an NPC; not a scripted scene).
+    pub source: Option<Entity>,
+    pub content: DialogueContent,
+    pub presentation: DialoguePresentation,
+    /// The span this conversation belongs under (npc.interaction). The
+    /// request is read a system later, after any attached context is gone,
+    /// so the parent has to travel with it.
+    pub parent: Option<SpanContext>,
+    /// Applied when the conversation completes - see `DialogueCompleted`.
+    pub on_complete: Vec<DialogueOutcome>,
+    /// A hub's topic menu, shown after `content` (see `TopicMenu`). Gated
+    /// topics are the sender's to filter out.
+    pub topics: Vec<DialogueTopic>,
+    /// Where the conversation goes after a box other than the next one,
+    /// by box index (see `map_data::DialogueLine`). Empty for a linear
+    /// conversation.
+    pub branches: BTreeMap<usize, DialogueBranch>,
+    /// The keypress that asked for this conversation, if one did: the box's
+    /// first frame is timed against it (input_latency.rs).
+    pub pressed_at: Option<Instant>,
+}
 
-#[derive(Component)]
-struct DialogueRoot;
+/// What a conversation says: authored map data as loaded, shared rather
+/// than copied, or boxes the sender already resolved (per-box speakers).
+#[derive(Clone, Debug)]
+pub enum DialogueContent {
+    Authored(Arc<DialogueData>),
+    Segments(Vec<DialogueSegment>),
+}
 
-#[derive(Component)]
-struct DialogueTextNode;
+impl DialogueContent {
+    pub fn segments(&self) -> Vec<DialogueSegment> {
+        match self {
+            DialogueContent::Authored(data) => data
+                .lines
+                .iter()
+                .map(|line| DialogueSegment {
+                    speaker: data.speaker_of(line).to_string(),
+                    portrait_path: crate::map_data::portrait_asset_path(data.portrait_of(line)),
+                    portrait_face_index: data.face_index,
+                    text: line.text.clone(),
+                    mood: data.mood.clone(),
+                    box_layout: data.box_layout,
+                    actions: line.actions.clone(),
+                    speaker_id: None,
+                })
+                .collect(),
+            DialogueContent::Segments(segments) => segments.clone(),
+        }
+    }
+}
 
-#[derive(Component)]
-struct SpeakerNameNode;
+/// What follows one box of a branching conversation: the choices offered
+/// once it has typed out, or (`end`) nothing - the conversation is over.
+#[derive(Clone, Debug, Default, PartialEq, Reflect)]
+pub struct DialogueBranch {
+    pub choices: Vec<BranchChoice>,
+    pub end: bool,
+}
 
-#[derive(Component)]
-struct PortraitNode;
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct BranchChoice {
+    pub label: String,
+    /// The box picking it goes to.
+    pub target: usize,
+}
 
-#[derive(Component)]
-struct TypewriterEffect {
-    full_text: String,
-    current_index: usize,
-    timer: Timer,
+/// How a conversation looks, over what its content says.
+#[derive(Clone, Debug, Default)]
+pub struct DialoguePresentation {
+    /// Every box shows this mood, whatever its own.
+    pub mood: Option<String>,
+    /// Typing speed, relative to `DialogueSettings` (see
+    /// `DialogueData::text_speed`). None is 1.0.
+    pub text_speed: Option<f32>,
+    /// The conversation's own portrait (an asset path), shown instead of a
+    /// box's that fails to load - a line's `portrait` naming a file that
+    /// didn't ship. None: such a box keeps its broken face.
+    pub fallback_portrait: Option<String>,
+    /// The box's background, instead of `PANEL_COLOR` - a sign's
+    /// parchment (map_objects.rs).
+    pub panel_color: Option<Color>,
 }
 
-impl TypewriterEffect {
-    fn new(text: String) -> Self {
+/// The dialogue box's background, and the topic menu's.
+pub const PANEL_COLOR: Color = Color::srgba(0.1, 0.1, 0.15, 0.95);
+
+pub struct DialogueRequestBuilder {
+    request: DialogueRequest,
+    custom_id: bool,
+}
+
+impl DialogueRequestBuilder {
+    fn with_content(content: DialogueContent) -> Self {
         Self {
-            full_text: text,
-            current_index: 0,
-            timer: Timer::from_seconds(0.03, TimerMode::Repeating),
+            request: DialogueRequest {
+                id: String::new(),
+                source: None,
+                content,
+                presentation: DialoguePresentation::default(),
+                parent: None,
+                on_complete: Vec::new(),
+                topics: Vec::new(),
+                branches: BTreeMap::new(),
+                pressed_at: None,
+            },
+            custom_id: false,
         }
     }
 
-    fn is_complete(&self) -> bool {
-        self.current_index >= self.full_text.len()
+    /// Authored dialogue; its `on_complete`, every topic and its branching
+    /// come along.
+    pub fn authored(data: Arc<DialogueData>) -> Self {
+        let on_complete = data.on_complete.clone();
+        let topics = data.topics.clone();
+        let branches = data.branches();
+        let text_speed = data.text_speed;
+        let portrait = crate::map_data::portrait_asset_path(&data.portrait);
+        let mut builder = Self::with_content(DialogueContent::Authored(data))
+            .on_complete(on_complete)
+            .topics(topics)
+            .branches(branches);
+        builder.request.presentation.text_speed = text_speed;
+        if !portrait.is_empty() {
+            builder = builder.fallback_portrait(portrait);
+        }
+        builder
     }
 
-    fn skip_to_end(&mut self) {
-        self.current_index = self.full_text.len();
+    pub fn segments(segments: Vec<DialogueSegment>) -> Self {
+        Self::with_content(DialogueContent::Segments(segments))
     }
-}
 
-#[derive(Resource)]
-pub struct DialogueQueue {
-    segments: Vec<DialogueSegment>,
-    current: usize,
-    /// One shared face-sheet atlas layout for the whole conversation
-    /// (created by spawn_dialogue_ui) so segment changes don't mint a new
-    /// layout asset per box.
-    face_layout: Option<Handle<TextureAtlasLayout>>,
-}
+    /// Override the content hash with an authored id (an NPC's
+    /// conversation, npc.rs). Seen-tracking and traces key on it, so it
+    /// stays put through rewording and renames.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.request.id = id.into();
+        self.custom_id = true;
+        self
+    }
 
-impl DialogueQueue {
-    fn new(segments: Vec<DialogueSegment>) -> Self {
-        Self { segments, current: 0, face_layout: None }
+    pub fn source(mut self, entity: Entity) -> Self {
+        self.request.source = Some(entity);
+        self
     }
 
-    fn current_segment(&self) -> Option<&DialogueSegment> {
-        self.segments.get(self.current)
+    pub fn parent(mut self, parent: SpanContext) -> Self {
+        self.request.parent = Some(parent);
+        self
     }
 
-    fn advance(&mut self) -> bool {
-        self.current += 1;
-        self.current < self.segments.len()
+    pub fn mood(mut self, mood: impl Into<String>) -> Self {
+        self.request.presentation.mood = Some(mood.into());
+        self
     }
-}
 
-fn spawn_dialogue_ui(
-    mut commands: Commands,
-    game_assets: Res<GameAssets>,
-    asset_server: Res<AssetServer>,
-    dialogue_queue: Option<ResMut<DialogueQueue>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let Some(mut queue) = dialogue_queue else {
-        error!("❌ DialogueQueue resource not found!");
-        return;
-    };
+    pub fn text_speed(mut self, text_speed: f32) -> Self {
+        self.request.presentation.text_speed = Some(text_speed);
+        self
+    }
 
-    let font = game_assets.dialogue_font.clone();
+    pub fn fallback_portrait(mut self, portrait_path: impl Into<String>) -> Self {
+        self.request.presentation.fallback_portrait = Some(portrait_path.into());
+        self
+    }
 
-    // Face sheets are a grid of cells, not one portrait each (see
-    // FACE_SHEET_* docs above) - render only the segment's own cell via a
-    // texture atlas, the same idiom npc.rs::spawn_npc uses for walk-sprite
-    // sheets. One layout serves every segment of the conversation.
-    let atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-        FACE_SHEET_CELL_SIZE,
-        FACE_SHEET_COLUMNS,
-        FACE_SHEET_ROWS,
-        None,
-        None,
-    ));
-    queue.face_layout = Some(atlas_layout.clone());
+    pub fn panel_color(mut self, color: Color) -> Self {
+        self.request.presentation.panel_color = Some(color);
+        self
+    }
 
-    let first = queue.current_segment().cloned().unwrap_or(DialogueSegment {
-        speaker: "Unknown".into(),
-        portrait_path: String::new(),
-        portrait_face_index: 0,
-        text: String::new(),
-    });
+    pub fn on_complete(mut self, outcomes: Vec<DialogueOutcome>) -> Self {
+        self.request.on_complete = outcomes;
+        self
+    }
 
-    // Presentation-scale layout: the box claims the bottom third of the
-    // window so the text can be read from the back of a conference room.
-    commands.spawn((
-        DialogueRoot,
-        Node {
-            position_type: PositionType::Absolute,
-            bottom: Val::Px(0.0),
-            left: Val::Px(0.0),
-            right: Val::Px(0.0),
-            height: Val::Percent(33.3),
-            padding: UiRect::all(Val::Px(24.0)),
-            flex_direction: FlexDirection::Row,
-            column_gap: Val::Px(24.0),
-            ..default()
-        },
-        BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.95)),
-        BorderColor::all(Color::WHITE),
-    ))
-    .with_children(|parent| {
-        // The portrait node always exists so later segments can swap faces
-        // in (or hide it) without re-spawning UI - Display::None when the
-        // current segment has no portrait. Square aspect + full height so
-        // it scales with the box instead of a hardcoded pixel size.
-        let (image_node, display) = portrait_for_segment(&first, &asset_server, &atlas_layout);
-        parent.spawn((
-            PortraitNode,
-            image_node,
-            Node {
-                height: Val::Percent(100.0),
-                aspect_ratio: Some(1.0),
-                display,
-                ..default()
-            },
-        ));
+    pub fn topics(mut self, topics: Vec<DialogueTopic>) -> Self {
+        self.request.topics = topics;
+        self
+    }
 
-        parent.spawn((
-            Node {
-                flex_direction: FlexDirection::Column,
-                row_gap: Val::Px(10.0),
-                flex_grow: 1.0,
-                ..default()
-            },
-        ))
-        .with_children(|text_parent| {
-            text_parent.spawn((
-                SpeakerNameNode,
-                Text::new(first.speaker.clone()),
-                TextFont {
-                    font: font.clone().into(),
-                    // 52px at 1080p, scaling with the window (Amy sized these on a
-                        // 1080 display; small embeds like the blog iframe shrink to fit).
-                        font_size: FontSize::Vh(52.0 / 10.8),
-                    ..default()
-                },
-                TextColor(Color::srgb(1.0, 0.85, 0.3)),
-            ));
+    pub fn branches(mut self, branches: BTreeMap<usize, DialogueBranch>) -> Self {
+        self.request.branches = branches;
+        self
+    }
 
-            text_parent.spawn((
-                DialogueTextNode,
-                Text::new(""),
-                TextFont {
-                    font: font.clone().into(),
-                    // 46px at 1080p, scaling with the window.
-                        font_size: FontSize::Vh(46.0 / 10.8),
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                TextLayout::justify(Justify::Left),
-                TypewriterEffect::new(first.text.clone()),
-            ));
-        });
-    });
+    pub fn pressed_at(mut self, pressed_at: Instant) -> Self {
+        self.request.pressed_at = Some(pressed_at);
+        self
+    }
+
+    pub fn build(mut self) -> DialogueRequest {
+        if !self.custom_id {
+            self.request.id = dialogue_id(&self.request.content.segments());
+        }
+        self.request
+    }
 }
 
-/// Builds the portrait ImageNode (and node display state) for a segment.
-/// Empty portrait path = hidden node.
-fn portrait_for_segment(
-    segment: &DialogueSegment,
-    asset_server: &AssetServer,
-    atlas_layout: &Handle<TextureAtlasLayout>,
-) -> (ImageNode, Display) {
-    if segment.portrait_path.is_empty() {
-        return (ImageNode::default(), Display::None);
+/// A one-speaker conversation, for tests: `("Isabella", lines).into()`.
+impl From<(&str, Vec<String>)> for DialogueRequest {
+    fn from((speaker, lines): (&str, Vec<String>)) -> Self {
+        let segments = lines
+            .into_iter()
+            .map(|text| DialogueSegment {
+                speaker: speaker.to_string(),
+                portrait_path: String::new(),
+                portrait_face_index: 0,
+                text,
+                mood: None,
+                box_layout: DialogueBoxLayout::default(),
+                actions: Vec::new(),
+                speaker_id: None,
+            })
+            .collect();
+        DialogueRequestBuilder::segments(segments).build()
     }
+}
 
-    #[cfg(debug_assertions)]
-    {
-        let max_index = FACE_SHEET_COLUMNS * FACE_SHEET_ROWS - 1;
-        if segment.portrait_face_index > max_index {
-            error!(
-                "❌ Portrait face_index {} exceeds face sheet grid bounds (max {})",
-                segment.portrait_face_index, max_index
-            );
-        }
+/// Something a conversation does once it's over - authored in map data
+/// (see `content::dialogue`).
+pub use crate::content::dialogue::DialogueOutcome;
+
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::dialogue"]
+    enum DialogueOutcome {
+        SpawnNpc(String),
+        SetFlag(String),
     }
+);
 
-    (
-        ImageNode::from_atlas_image(
-            asset_server.load(&segment.portrait_path),
-            TextureAtlas {
-                layout: atlas_layout.clone(),
-                index: segment.portrait_face_index as usize,
-            },
-        ),
-        Display::Flex,
-    )
+/// A conversation ended by reading it to the end, or by skipping it after
+/// reading it before (`skipped`). Escape-closing one is not completion and
+/// writes nothing. Each outcome's owner applies its own kind.
+#[derive(Message, Debug, Clone)]
+pub struct DialogueCompleted {
+    pub id: String,
+    pub skipped: bool,
+    pub outcomes: Vec<DialogueOutcome>,
 }
 
-fn handle_dialogue_events(
-    mut commands: Commands,
-    mut events: MessageReader<StartDialogueEvent>,
-    mut next_mode: ResMut<NextState<Mode>>,
-    tracer: Option<Res<GameTracer>>,
-) {
-    for event in events.read() {
-        if event.segments.is_empty() {
-            warn!("StartDialogueEvent with no segments - ignoring");
-            continue;
+/// A conversation's identity for "skip seen" when it has no authored one
+/// (NPCs' do - see `DialogueData::id`): a hash of what the player reads
+/// (speaker and text of every box). Editing the content changes the hash,
+/// so a rewritten conversation counts as unread again. FNV-1a rather than
+/// `DefaultHasher`, whose output may change between Rust releases and would
+/// forget every save's history.
+pub fn dialogue_id(segments: &[DialogueSegment]) -> String {
+    content_hash(segments.iter().map(|segment| (segment.speaker.as_str(), segment.text.as_str())))
+}
+
+/// `dialogue_id` of boxes given as (speaker, text): what an NPC's
+/// conversation was known by in saves from before ids (save.rs).
+pub fn content_hash<'a>(boxes: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (speaker, text) in boxes {
+        for part in [speaker.as_bytes(), b"\0", text.as_bytes(), b"\0"] {
+            for &byte in part {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
         }
-        let first_speaker = event.segments[0].speaker.clone();
-        info!("📖 Starting dialogue: {} ({} segments)", first_speaker, event.segments.len());
+    }
+    format!("{hash:016x}")
+}
 
-        // Create dialogue session span (if telemetry is enabled)
-        if let Some(tracer) = tracer.as_ref() {
-            // Note: This span will be a child of the current context (from NPC interaction)
-            let context = OtelContext::current();
-            let mut span = tracer.tracer()
-                .start_with_context("dialogue.session", &context);
+/// Every conversation read to the end (or skipped after being read), by
+/// `DialogueRequest::id`. Saved with the game (save.rs) so replays remember.
+#[derive(Resource, Default, Debug, Clone, PartialEq)]
+pub struct SeenDialogues {
+    ids: BTreeSet<String>,
+}
 
-            span.set_attribute(KeyValue::new("dialogue.speaker", first_speaker.clone()));
-            span.set_attribute(KeyValue::new("dialogue.total_lines", event.segments.len() as i64));
+impl SeenDialogues {
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
 
-            // Add telemetry event for dialogue start
-            span.add_event(
-                "dialogue.resources_created",
-                vec![
-                    KeyValue::new("queue.lines", event.segments.len() as i64),
-                    KeyValue::new("queue.speaker", first_speaker.clone()),
-                ],
-            );
+    pub fn insert(&mut self, id: String) {
+        self.ids.insert(id);
+    }
 
-            // Store active dialogue component as resource
-            let active_dialogue = ActiveDialogue {
-                span,
-                start_time: Instant::now(),
-                speaker: first_speaker,
-                chars_read: 0,
-            };
-            commands.insert_resource(active_dialogue);
-        }
+    /// Sorted, so a save's list doesn't churn between writes.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.iter().map(String::as_str)
+    }
+}
 
-        commands.insert_resource(DialogueQueue::new(event.segments.clone()));
-        info!("🎮 Transitioning to Dialogue mode");
-        next_mode.set(Mode::Dialogue);
+impl FromIterator<String> for SeenDialogues {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self { ids: iter.into_iter().collect() }
     }
 }
 
-fn type_dialogue_text(
-    time: Res<Time>,
-    mut query: Query<(&mut Text, &mut TypewriterEffect), With<DialogueTextNode>>,
-    mut active_dialogue: Option<ResMut<ActiveDialogue>>,
-    dialogue_queue: Option<Res<DialogueQueue>>,
-    meter: Option<Res<GameMeter>>,
-) {
-    for (mut text, mut typewriter) in &mut query {
-        let was_complete = typewriter.is_complete();
+/// How long Tab must be held to skip: long enough that brushing it while
+/// reading doesn't throw the conversation away.
+pub(crate) const SKIP_HOLD: Duration = Duration::from_millis(500);
 
-        if was_complete {
-            continue;
-        }
+/// The only language the dialogue ships in. Overflow telemetry is labelled
+/// with it anyway: overflow is a font-metrics-per-language problem, and the
+/// label is what tells a translation's reports apart later.
+pub const DIALOGUE_LOCALE: &str = "en";
+
+/// RPGMaker MZ face sheets are always a 4-column x 2-row grid of 144x144px
+/// cells (`ImageManager.faceWidth`/`faceHeight` in rmmz_managers.js and
+/// `Window_Base.prototype.drawFace` in rmmz_windows.js are hardcoded to this
+/// regardless of a given sheet's actual pixel dimensions - some of ours are
+/// taller than 288px, e.g. assets/textures/portraits/casey.png, with the
+/// extra rows simply unused), so `faceIndex` 0-7 always maps into this one
+/// fixed grid across every portrait file.
+const FACE_SHEET_CELL_SIZE: UVec2 = UVec2::new(144, 144);
 
-        typewriter.timer.tick(time.delta());
+/// The dialogue box. It is also a click target (`Interaction`): a click
+/// anywhere on it does what Space does - complete the line, then advance -
+/// for trackpad players who'd rather not reach for the keyboard.
+#[derive(Component)]
+pub struct DialogueRoot;
 
-        if typewriter.timer.just_finished() {
-            if let Some(next_char) = typewriter.full_text.chars().nth(typewriter.current_index) {
-                text.push(next_char);
-                typewriter.current_index += 1;
+/// The current map's dialogue box placement (`MapData::box_layout`), for
+/// conversations that leave it out. tilemap.rs inserts it with the map.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct MapDialogueBox(pub DialogueBoxLayout);
 
-                // Track characters read
-                if let Some(ref mut dialogue) = active_dialogue {
-                    dialogue.chars_read += 1;
-                }
-            }
-        }
+/// How long the box takes to slide to a new place mid-conversation.
+pub const BOX_SLIDE_SECONDS: f32 = 0.2;
 
-        // Record event when line completes
-        if !was_complete && typewriter.is_complete() {
-            if let (Some(dialogue), Some(queue)) = (&mut active_dialogue, &dialogue_queue) {
-                record_dialogue_line_event(
-                    &mut dialogue.span,
-                    &typewriter.full_text,
-                    queue.current,
-                );
+/// Where the box is on screen, in percent of the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoxPlacement {
+    pub top: f32,
+    pub left: f32,
+    pub width: f32,
+    pub height: f32,
+}
 
-                if let Some(ref meter) = meter {
-                    let speaker = queue
-                        .current_segment()
-                        .map(|s| s.speaker.clone())
-                        .unwrap_or_else(|| dialogue.speaker.clone());
-                    meter.dialogue_lines_read.add(1, &[
-                        KeyValue::new("speaker", speaker)
-                    ]);
-                }
+impl BoxPlacement {
+    /// `layout` on screen: against its edge, centered across.
+    pub fn of(layout: DialogueBoxLayout) -> Self {
+        let (width, height) = (layout.width(), layout.height());
+        let top = match layout.position() {
+            BoxPosition::Top => 0.0,
+            BoxPosition::Bottom => 100.0 - height,
+        };
+        Self { top, left: (100.0 - width) / 2.0, width, height }
+    }
 
-                info!("📝 Dialogue segment {} complete: {} chars",
-                    queue.current,
-                    typewriter.full_text.len());
-            }
+    fn lerp(self, to: Self, t: f32) -> Self {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            top: mix(self.top, to.top),
+            left: mix(self.left, to.left),
+            width: mix(self.width, to.width),
+            height: mix(self.height, to.height),
         }
     }
+
+    fn apply(self, node: &mut Node) {
+        node.top = Val::Percent(self.top);
+        node.left = Val::Percent(self.left);
+        node.width = Val::Percent(self.width);
+        node.height = Val::Percent(self.height);
+    }
 }
 
-fn advance_dialogue(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    asset_server: Res<AssetServer>,
-    mut next_mode: ResMut<NextState<Mode>>,
-    mut dialogue_queue: Option<ResMut<DialogueQueue>>,
-    mut typewriter_query: Query<(&mut Text, &mut TypewriterEffect), With<DialogueTextNode>>,
-    mut speaker_query: Query<&mut Text, (With<SpeakerNameNode>, Without<DialogueTextNode>)>,
-    mut portrait_query: Query<(&mut ImageNode, &mut Node), With<PortraitNode>>,
-) {
-    if !keyboard.just_pressed(KeyCode::Space) && !keyboard.just_pressed(KeyCode::Enter) {
-        return;
+/// The box on its way from one placement to another, like `MoodTint`
+/// (mood.rs) for position.
+#[derive(Component, Debug)]
+pub struct BoxSlide {
+    /// The edge it's headed for.
+    pub position: BoxPosition,
+    from: BoxPlacement,
+    to: BoxPlacement,
+    timer: Timer,
+}
+
+impl BoxSlide {
+    /// Already placed - nothing to animate.
+    pub fn settled(layout: DialogueBoxLayout) -> Self {
+        let placement = BoxPlacement::of(layout);
+        let mut timer = Timer::from_seconds(BOX_SLIDE_SECONDS, TimerMode::Once);
+        timer.finish();
+        Self { position: layout.position(), from: placement, to: placement, timer }
     }
 
-    if let Ok((mut text, mut typewriter)) = typewriter_query.single_mut() {
-        if !typewriter.is_complete() {
-            **text = typewriter.full_text.clone();
-            typewriter.skip_to_end();
+    /// Slide from wherever the box is right now to `layout`'s place.
+    pub fn retarget(&mut self, layout: DialogueBoxLayout) {
+        let to = BoxPlacement::of(layout);
+        if to == self.to {
             return;
         }
+        self.from = self.current();
+        self.to = to;
+        self.position = layout.position();
+        self.timer.reset();
     }
 
-    if let Some(ref mut queue) = dialogue_queue {
-        if queue.advance() {
-            let Some(segment) = queue.current_segment().cloned() else {
-                return;
+    /// Eased out: quick off the mark, settling gently.
+    pub fn current(&self) -> BoxPlacement {
+        let t = self.timer.fraction();
+        self.from.lerp(self.to, 1.0 - (1.0 - t) * (1.0 - t))
+    }
+}
+
+/// The topic menu sits on the box's edge facing the middle of the screen:
+/// on top of a box at the bottom, under one at the top.
+fn hang_topic_menu(node: &mut Node, position: BoxPosition) {
+    (node.top, node.bottom) = match position {
+        BoxPosition::Bottom => (Val::Auto, Val::Percent(100.0)),
+        BoxPosition::Top => (Val::Percent(100.0), Val::Auto),
+    };
+}
+
+/// "Go on", from whichever device: Advance's keys (Space, Enter), its
+/// gamepad button (South), or a left click on the dialogue box or one of
+/// its `MenuRow`s - `point_at_menu_rows` has already moved the highlight to
+/// a clicked row, so going on picks it. Clicks anywhere else are left to
+/// whatever is under them.
+#[derive(SystemParam)]
+pub struct AdvanceInput<'w, 's> {
+    input: crate::input::GameInput<'w, 's>,
+    clicks: Query<'w, 's, &'static Interaction, (Changed<Interaction>, Or<(With<DialogueRoot>, With<MenuRow>)>)>,
+}
+
+impl AdvanceInput<'_, '_> {
+    pub fn requested(&self) -> bool {
+        // Changed: one click is one advance, however long the button is held.
+        self.input.action_just_pressed(GameAction::Advance)
+            || self.clicks.iter().any(|interaction| *interaction == Interaction::Pressed)
+    }
+}
+
+/// The typed text; `TestGame::dialogue_text` reads it.
+#[derive(Component)]
+pub(crate) struct DialogueTextNode;
+
+/// The speaker-name-over-text column. Its height is fixed by the box, so
+/// text that doesn't fit shows up as content taller (or wider) than the
+/// column - see `detect_text_overflow`.
+#[derive(Component)]
+pub struct DialogueTextColumn;
+
+/// The speaker's name; `TestGame::speaker_name` reads it.
+#[derive(Component)]
+pub(crate) struct SpeakerNameNode;
+
+/// The portrait; `TestGame::portrait_path` reads it.
+#[derive(Component)]
+pub(crate) struct PortraitNode;
+
+#[derive(Component)]
+struct SkipSeenPrompt;
+
+/// "Space" (or the gamepad's button) in the box's corner once the line has
+/// finished typing: the next press goes on rather than completing the line.
+/// Hidden while the topic menu is up.
+#[derive(Component)]
+struct ContinueIndicator;
+
+/// A hub's topic list, over the box's right edge. Hidden while a topic (or
+/// the greeting) is being read.
+#[derive(Component)]
+struct TopicMenuNode;
+
+/// A branching box's choices, under its text (see `DialogueBranch`).
+/// Spawned only for conversations that branch.
+#[derive(Component)]
+struct ChoiceListNode;
+
+/// A row to pick from, the keyboard's or the mouse's: W/S and the arrows
+/// move the highlight over them (`navigate_dialogue_menus`), and as buttons
+/// a hover moves it too and a click picks the row, just as Advance would.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[require(Button)]
+pub enum MenuRow {
+    /// The nth choice of a branching box.
+    Choice(usize),
+    /// The nth visible row of the topic menu (not the nth topic - see
+    /// `TopicMenu::window`).
+    Topic(usize),
+}
+
+/// "..." above or below the rows when the menu scrolls past them.
+#[derive(Component)]
+struct TopicMenuMore {
+    below: bool,
+}
+
+/// A character every 30ms.
+pub const DEFAULT_CHARS_PER_SECOND: f32 = 1.0 / 0.03;
+
+/// How dialogue types out. main.rs sets it from `--text-speed`, a multiple
+/// of `DEFAULT_CHARS_PER_SECOND` like `DialogueData::text_speed`.
+#[derive(Resource, Debug, Clone)]
+pub struct DialogueSettings {
+    /// At a speaker's normal pace (see `DialogueData::text_speed`). 0
+    /// shows every box whole at once, for automated runs.
+    pub chars_per_second: f32,
+    /// What's done with a word too long for a row (`--shrink-long-words`).
+    pub long_words: LongWords,
+    /// What's done with a conversation asked for while another is open
+    /// (`--queue-dialogues`).
+    pub while_talking: WhileTalking,
+}
+
+impl Default for DialogueSettings {
+    fn default() -> Self {
+        Self {
+            chars_per_second: DEFAULT_CHARS_PER_SECOND,
+            long_words: LongWords::default(),
+            while_talking: WhileTalking::default(),
+        }
+    }
+}
+
+/// A `DialogueRequest` that arrives while a conversation is open - a
+/// scripted scene firing mid-talk, two NPCs answering one press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhileTalking {
+    /// Dropped: the open conversation is what the player is reading.
+    #[default]
+    Ignore,
+    /// Opened once the open one closes, in the order they came
+    /// (`QueuedDialogues`).
+    QueueAfter,
+}
+
+/// Conversations waiting for the open one to close (`WhileTalking::QueueAfter`).
+#[derive(Resource, Default)]
+pub struct QueuedDialogues(VecDeque<DialogueRequest>);
+
+impl QueuedDialogues {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Component)]
+struct TypewriterEffect {
+    /// What the player sees, markup read (see `parse_markup`).
+    full_text: String,
+    /// Bytes of `full_text` shown so far.
+    current_index: usize,
+    /// A character each time it finishes; None shows the text whole.
+    timer: Option<Timer>,
+    /// `full_text`'s styling, for render_typewriter.
+    runs: Vec<MarkupRun>,
+    /// `Markup::soft_breaks`: newlines in `full_text` no one wrote.
+    soft_breaks: Vec<usize>,
+    /// The page's text size, against the box's usual (see `TextFit`).
+    text_scale: f32,
+    /// `{pause}`s not reached yet, by byte of `full_text`.
+    pauses: std::collections::VecDeque<(usize, Duration)>,
+    /// What's left of the pause being waited out.
+    paused: Duration,
+    /// render_typewriter has made this page's spans.
+    spans_built: bool,
+}
+
+impl TypewriterEffect {
+    fn new(text: String, chars_per_second: f32) -> Self {
+        Self::styled(Markup::plain(text), chars_per_second)
+    }
+
+    fn styled(markup: Markup, chars_per_second: f32) -> Self {
+        let timer = (chars_per_second > 0.0 && chars_per_second.is_finite())
+            .then(|| Timer::from_seconds(1.0 / chars_per_second, TimerMode::Repeating));
+        Self {
+            full_text: markup.text,
+            current_index: 0,
+            timer,
+            runs: markup.runs,
+            soft_breaks: markup.soft_breaks,
+            text_scale: 1.0,
+            pauses: markup.pauses.into(),
+            paused: Duration::ZERO,
+            spans_built: false,
+        }
+    }
+
+    /// Characters of `full_text[range]` there are to read: the soft
+    /// breaks aren't any.
+    fn read_chars(&self, range: std::ops::Range<usize>) -> usize {
+        let breaks = self.soft_breaks.iter().filter(|at| range.contains(at)).count();
+        self.full_text[range].chars().count() - breaks
+    }
+
+    /// Ticks `timer` and takes the text it has typed since last time. A
+    /// pause stops it where it stands; the timer waits with it.
+    fn type_for(&mut self, mut delta: Duration) -> &str {
+        let start = self.current_index;
+        let due = match &mut self.timer {
+            Some(timer) => {
+                let waited = self.paused.min(delta);
+                self.paused -= waited;
+                delta -= waited;
+                if !self.paused.is_zero() {
+                    return "";
+                }
+                timer.tick(delta).times_finished_this_tick() as usize
+            }
+            None => usize::MAX,
+        };
+        let rest = &self.full_text[start..];
+        self.current_index += rest.char_indices().nth(due).map_or(rest.len(), |(end, _)| end);
+        if self.timer.is_some()
+            && let Some(&(at, pause)) = self.pauses.front().filter(|(at, _)| *at <= self.current_index)
+        {
+            self.pauses.pop_front();
+            self.current_index = at.max(start);
+            self.paused = pause;
+        }
+        &self.full_text[start..self.current_index]
+    }
+
+    fn is_complete(&self) -> bool {
+        self.current_index >= self.full_text.len()
+    }
+
+    /// Takes everything not typed yet, all at once.
+    fn reveal_rest(&mut self) -> &str {
+        let start = self.current_index;
+        self.current_index = self.full_text.len();
+        self.pauses.clear();
+        self.paused = Duration::ZERO;
+        &self.full_text[start..]
+    }
+
+    /// Each run's typed part, in order, with its style.
+    fn shown_runs(&self) -> impl Iterator<Item = (&str, SpanStyle)> {
+        self.runs.iter().map(|run| {
+            let (start, end) = (run.range.start.min(self.current_index), run.range.end.min(self.current_index));
+            (&self.full_text[start..end], run.style)
+        })
+    }
+}
+
+/// Reading time, as against typing time: each box counts from the moment
+/// it's all on screen - typed out, or revealed early by a press - to the
+/// press that moves on. The typewriter's part measures the text speed, not
+/// the reader.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadingClock {
+    /// When the box on screen came up whole, and its length in characters.
+    shown: Option<(Duration, usize)>,
+    /// Summed over every box moved on from.
+    pub dwell: Duration,
+    pub chars: usize,
+}
+
+impl ReadingClock {
+    /// The box on screen is whole as of `now` (game time).
+    pub fn shown(&mut self, now: Duration, chars: usize) {
+        self.shown = Some((now, chars));
+    }
+
+    /// The press that leaves the box: how long it was read, and its length.
+    /// `None` if it wasn't whole yet, or was already left.
+    pub fn moved_on(&mut self, now: Duration) -> Option<(Duration, usize)> {
+        let (since, chars) = self.shown.take()?;
+        let dwell = now.saturating_sub(since);
+        self.dwell += dwell;
+        self.chars += chars;
+        Some((dwell, chars))
+    }
+
+    /// Characters per second of dwell, once any time has been read.
+    pub fn reading_speed(&self) -> Option<f64> {
+        (!self.dwell.is_zero()).then(|| self.chars as f64 / self.dwell.as_secs_f64())
+    }
+}
+
+/// Characters to a row of dialogue text, and rows to a box, for
+/// `paginate`. Font sizes follow the window height (`FontSize::Vh`), so
+/// these hold at any 16:9 size; they're sized for the narrower column of
+/// a box with a portrait, with room under the text for a branch's choices.
+pub const BOX_ROW_CHARS: usize = 52;
+pub const BOX_ROWS: usize = 3;
+
+/// Dialogue text's size: 46px at 1080p, scaling with the window.
+const TEXT_VH: f32 = 46.0 / 10.8;
+
+/// `LongWords::Shrink` takes a line's text no smaller than this.
+pub const MIN_TEXT_SCALE: f32 = 0.6;
+
+/// What's done with a word too long for a row - a URL to reading
+/// material, a command line - which `paginate` has no space to break at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongWords {
+    /// Break it across rows (see `Markup::break_long_words`).
+    #[default]
+    Break,
+    /// Shrink that line's text until the word fits a row, down to
+    /// `MIN_TEXT_SCALE`; a word still too long is broken.
+    Shrink,
+}
+
+/// A line's size in the box: its text's scale, and the characters to a
+/// row and rows to a box at that scale. Widths are `BOX_ROW_CHARS`'
+/// approximation of the font's, so a half-size text holds twice the
+/// characters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextFit {
+    pub scale: f32,
+    pub row_chars: usize,
+    pub rows: usize,
+}
+
+impl TextFit {
+    pub fn for_line(text: &str, long_words: LongWords) -> Self {
+        let longest = text.split([' ', '\n']).map(|word| word.chars().count()).max().unwrap_or(0);
+        let scale = match long_words {
+            LongWords::Shrink if longest > BOX_ROW_CHARS => (BOX_ROW_CHARS as f32 / longest as f32).max(MIN_TEXT_SCALE),
+            _ => 1.0,
+        };
+        // A hair of slack so 52 / (52 / 120) is 120, not 119.
+        let fit = |count: usize| (count as f32 / scale + 1e-3) as usize;
+        Self { scale, row_chars: fit(BOX_ROW_CHARS), rows: fit(BOX_ROWS) }
+    }
+}
+
+/// One box's worth of a line: `range` of the line's text, shown as
+/// `text` - the same characters, with the spaces it wraps at turned into
+/// newlines, so a byte index means the same thing in both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub range: std::ops::Range<usize>,
+    pub text: String,
+}
+
+/// Word-wraps `text` to `row_chars` and cuts it into pages of `rows` rows.
+/// Rows break at a space or an authored newline, never inside a word: a
+/// word longer than a row gets a row to itself, and overflows (see
+/// `detect_text_overflow`). The space a page break falls on isn't shown.
+pub fn paginate(text: &str, row_chars: usize, rows: usize) -> Vec<Page> {
+    let mut wrapped: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut offset = 0;
+    for paragraph in text.split('\n') {
+        // (start, end, chars) of the row being filled.
+        let mut row: Option<(usize, usize, usize)> = None;
+        let mut at = offset;
+        for word in paragraph.split(' ') {
+            let (start, end, chars) = (at, at + word.len(), word.chars().count());
+            at = end + 1;
+            row = Some(match row {
+                Some((row_start, _, row_len)) if row_len + 1 + chars <= row_chars => (row_start, end, row_len + 1 + chars),
+                Some((row_start, row_end, _)) => {
+                    wrapped.push(row_start..row_end);
+                    (start, end, chars)
+                }
+                None => (start, end, chars),
+            });
+        }
+        if let Some((start, end, _)) = row {
+            wrapped.push(start..end);
+        }
+        offset += paragraph.len() + 1;
+    }
+    wrapped
+        .chunks(rows.max(1))
+        .map(|page| Page {
+            range: page[0].start..page[page.len() - 1].end,
+            // Rows are one separator byte apart, so this is the range's
+            // text with those separators as newlines.
+            text: page.iter().map(|row| &text[row.clone()]).collect::<Vec<_>>().join("\n"),
+        })
+        .collect()
+}
+
+/// Colors a line can name in markup: `{red}error budget{/red}`.
+pub const MARKUP_COLORS: &[(&str, Color)] = &[
+    ("red", Color::srgb(1.0, 0.4, 0.4)),
+    ("green", Color::srgb(0.5, 0.95, 0.5)),
+    ("blue", Color::srgb(0.5, 0.7, 1.0)),
+    ("yellow", Color::srgb(1.0, 0.9, 0.4)),
+    ("orange", Color::srgb(1.0, 0.65, 0.3)),
+    ("gray", Color::srgb(0.65, 0.65, 0.7)),
+];
+
+/// How a stretch of a line looks, over the box's own white regular text.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SpanStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+}
+
+/// A stretch of `Markup::text` in one style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkupRun {
+    pub range: std::ops::Range<usize>,
+    pub style: SpanStyle,
+}
+
+/// A line with its markup read: `text` is what the player sees, `runs` its
+/// styling (in order, covering it), `pauses` where the typewriter stops
+/// and for how long, by byte of `text`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Markup {
+    pub text: String,
+    pub runs: Vec<MarkupRun>,
+    pub pauses: Vec<(usize, Duration)>,
+    /// Tags that couldn't be read - they're in `text` as written.
+    pub problems: Vec<String>,
+    /// Newlines put into `text` to break long words (see
+    /// `break_long_words`), by byte. Nobody wrote them, so nobody reads
+    /// them: they stay out of `chars_read` and the reading speed.
+    pub soft_breaks: Vec<usize>,
+}
+
+impl Markup {
+    /// Unmarked text, one run.
+    pub fn plain(text: String) -> Self {
+        let runs = match text.len() {
+            0 => Vec::new(),
+            len => vec![MarkupRun { range: 0..len, style: SpanStyle::default() }],
+        };
+        Self { text, runs, pauses: Vec::new(), problems: Vec::new(), soft_breaks: Vec::new() }
+    }
+
+    /// Breaks every word longer than `row_chars` into rows, for
+    /// `paginate` - which breaks only at spaces - to lay out: after the
+    /// last `/`, `-` or `.` in the back half of the row, or where the row
+    /// is full if there's none. A break is a newline, in `soft_breaks`; runs and
+    /// pauses move with the text.
+    pub fn break_long_words(mut self, row_chars: usize) -> Markup {
+        let row_chars = row_chars.max(1);
+        let mut breaks = Vec::new();
+        let mut word_start = 0;
+        for word in self.text.split([' ', '\n']) {
+            let chars: Vec<(usize, char)> = word.char_indices().collect();
+            let mut row = 0;
+            while chars.len() - row > row_chars {
+                let full = row + row_chars;
+                // A separator too near the row's start would leave it mostly
+                // empty ("https://" alone).
+                let cut = ((row + row_chars / 2).max(row + 1)..=full)
+                    .rev()
+                    .find(|&next| matches!(chars[next - 1].1, '/' | '-' | '.'))
+                    .unwrap_or(full);
+                breaks.push(word_start + chars[cut].0);
+                row = cut;
+            }
+            word_start += word.len() + 1;
+        }
+        if breaks.is_empty() {
+            return self;
+        }
+
+        // Where byte `at` of the text ends up: after every break at or
+        // before it, so a break at a run's edge goes with the run before.
+        let moved = |at: usize| at + breaks.iter().take_while(|&&at_break| at_break <= at).count();
+        let mut text = String::with_capacity(self.text.len() + breaks.len());
+        let mut from = 0;
+        for &at in &breaks {
+            text.push_str(&self.text[from..at]);
+            text.push('\n');
+            from = at;
+        }
+        text.push_str(&self.text[from..]);
+        for run in &mut self.runs {
+            run.range = moved(run.range.start)..moved(run.range.end);
+        }
+        for (at, _) in &mut self.pauses {
+            *at = moved(*at);
+        }
+        let mut soft_breaks: Vec<usize> = self.soft_breaks.iter().map(|&at| moved(at)).collect();
+        soft_breaks.extend(breaks.iter().enumerate().map(|(earlier, at)| at + earlier));
+        soft_breaks.sort_unstable();
+        Markup { text, soft_breaks, ..self }
+    }
+
+    /// What `page` (of `paginate(&self.text, ..)`) shows, as its own
+    /// markup: the page's text, with the runs and pauses that fall on it.
+    pub fn page(&self, page: &Page) -> Markup {
+        let range = &page.range;
+        let runs = self
+            .runs
+            .iter()
+            .filter_map(|run| {
+                let (start, end) = (run.range.start.max(range.start), run.range.end.min(range.end));
+                (start < end).then(|| MarkupRun { range: start - range.start..end - range.start, style: run.style })
+            })
+            .collect();
+        let pauses = self
+            .pauses
+            .iter()
+            .filter(|(at, _)| range.contains(at))
+            .map(|&(at, pause)| (at - range.start, pause))
+            .collect();
+        let soft_breaks = self.soft_breaks.iter().filter(|at| range.contains(at)).map(|at| at - range.start).collect();
+        Markup { text: page.text.clone(), runs, pauses, problems: Vec::new(), soft_breaks }
+    }
+}
+
+enum MarkupToken<'a> {
+    Text(&'a str),
+    Open { raw: &'a str, name: &'a str, style: SpanStyle },
+    Close { raw: &'a str, name: &'a str },
+    Pause(Duration),
+}
+
+/// Reads a line's markup: `{red}`...`{/red}` (any of `MARKUP_COLORS`),
+/// `{b}`...`{/b}` for bold, and `{pause:0.5}` to stop typing for half a
+/// second. Tags nest. One that can't be read - unknown, unclosed, closing
+/// the wrong tag - stays in the text as written and is noted in
+/// `problems`; a line without tags comes back as it was.
+pub fn parse_markup(line: &str) -> Markup {
+    let mut tokens = Vec::new();
+    let mut problems = Vec::new();
+    let mut rest = line;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|close| open + close) else { break };
+        if open > 0 {
+            tokens.push(MarkupToken::Text(&rest[..open]));
+        }
+        let raw = &rest[open..=close];
+        let tag = &rest[open + 1..close];
+        let color = |name: &str| MARKUP_COLORS.iter().find(|(color, _)| *color == name).map(|&(_, color)| color);
+        tokens.push(if let Some(seconds) = tag.strip_prefix("pause:") {
+            match seconds.trim().parse::<f32>().ok().and_then(|seconds| Duration::try_from_secs_f32(seconds).ok()) {
+                Some(pause) => MarkupToken::Pause(pause),
+                None => {
+                    problems.push(format!("{raw} isn't a pause in seconds"));
+                    MarkupToken::Text(raw)
+                }
+            }
+        } else if let Some(name) = tag.strip_prefix('/').filter(|name| *name == "b" || color(*name).is_some()) {
+            MarkupToken::Close { raw, name }
+        } else if tag == "b" {
+            MarkupToken::Open { raw, name: tag, style: SpanStyle { color: None, bold: true } }
+        } else if let Some(color) = color(tag) {
+            MarkupToken::Open { raw, name: tag, style: SpanStyle { color: Some(color), bold: false } }
+        } else {
+            problems.push(format!("unknown tag {raw}"));
+            MarkupToken::Text(raw)
+        });
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        tokens.push(MarkupToken::Text(rest));
+    }
+
+    // Pair each open with its close; the unpaired become text.
+    let mut open: Vec<usize> = Vec::new();
+    let mut paired = vec![false; tokens.len()];
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            MarkupToken::Open { .. } => open.push(index),
+            MarkupToken::Close { raw, name } => match open.last() {
+                Some(&start) if matches!(tokens[start], MarkupToken::Open { name: open_name, .. } if open_name == *name) => {
+                    open.pop();
+                    paired[start] = true;
+                    paired[index] = true;
+                }
+                _ => problems.push(format!("{raw} closes no open tag")),
+            },
+            _ => {}
+        }
+    }
+    for &index in &open {
+        if let MarkupToken::Open { raw, .. } = tokens[index] {
+            problems.push(format!("{raw} is never closed"));
+        }
+    }
+
+    let mut markup = Markup { problems, ..default() };
+    let mut styles: Vec<SpanStyle> = Vec::new();
+    for (token, paired) in tokens.iter().zip(paired) {
+        let text = match *token {
+            MarkupToken::Open { style, .. } if paired => {
+                styles.push(style);
+                continue;
+            }
+            MarkupToken::Close { .. } if paired => {
+                styles.pop();
+                continue;
+            }
+            MarkupToken::Pause(pause) => {
+                markup.pauses.push((markup.text.len(), pause));
+                continue;
+            }
+            MarkupToken::Text(text) | MarkupToken::Open { raw: text, .. } | MarkupToken::Close { raw: text, .. } => text,
+        };
+        let style = SpanStyle {
+            color: styles.iter().rev().find_map(|style| style.color),
+            bold: styles.iter().any(|style| style.bold),
+        };
+        let start = markup.text.len();
+        markup.text.push_str(text);
+        match markup.runs.last_mut() {
+            Some(run) if run.style == style => run.range.end = markup.text.len(),
+            _ => markup.runs.push(MarkupRun { range: start..markup.text.len(), style }),
+        }
+    }
+    markup
+}
+
+/// Most topic rows on screen at once; a longer menu scrolls.
+pub const TOPIC_MENU_ROWS: usize = 8;
+
+/// The menu's last entry, always there: how a hub conversation ends.
+pub const GOODBYE_LABEL: &str = "Goodbye";
+
+/// After a read topic's label, on its dimmed row.
+pub const READ_MARK: char = '✓';
+
+/// A hub character's "Ask about..." menu (`DialogueData::topics`): shown
+/// after the greeting and again after each topic, until the player picks
+/// Goodbye. W/S or the arrows move the cursor, Space/Enter or a click on
+/// the box picks. A topic read to the end counts as read - by the
+/// `dialogue_id` of its lines, in `SeenDialogues`, so it keeps its
+/// `READ_MARK` across saves and loses it when its lines are rewritten.
+/// Each pick is a `DialogueTopicChosen`, and an event on the
+/// conversation's npc.interaction span (npc.rs).
+pub struct TopicMenu {
+    topics: Vec<MenuTopic>,
+    /// Into the entries, Goodbye included.
+    cursor: usize,
+    /// First entry on screen.
+    scroll: usize,
+    open: bool,
+    /// The topic being read, to mark read once it's done.
+    reading: Option<usize>,
+    /// Topic ids in the order first picked, for the dialogue span.
+    visited: Vec<String>,
+}
+
+struct MenuTopic {
+    id: String,
+    label: String,
+    segments: Vec<DialogueSegment>,
+    content_id: String,
+}
+
+/// What picking the highlighted entry does.
+#[derive(Debug)]
+pub enum TopicChoice {
+    Topic { id: String, segments: Vec<DialogueSegment> },
+    Goodbye,
+}
+
+impl TopicMenu {
+    /// Topics are voiced like `template` (the greeting's first box).
+    fn new(topics: &[DialogueTopic], template: &DialogueSegment) -> Self {
+        let topics = topics
+            .iter()
+            .map(|topic| {
+                let segments: Vec<DialogueSegment> = topic
+                    .lines
+                    .iter()
+                    .map(|line| DialogueSegment { text: line.clone(), actions: Vec::new(), ..template.clone() })
+                    .collect();
+                MenuTopic {
+                    id: topic.id.clone(),
+                    label: topic.label.clone(),
+                    content_id: dialogue_id(&segments),
+                    segments,
+                }
+            })
+            .collect();
+        Self { topics, cursor: 0, scroll: 0, open: false, reading: None, visited: Vec::new() }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Topic ids picked so far this conversation, first pick first.
+    pub fn visited(&self) -> &[String] {
+        &self.visited
+    }
+
+    /// Every entry's label and whether it has been read, Goodbye last.
+    pub fn entries(&self, seen: &SeenDialogues) -> Vec<(String, bool)> {
+        self.topics
+            .iter()
+            .map(|topic| (topic.label.clone(), seen.contains(&topic.content_id)))
+            .chain(std::iter::once((GOODBYE_LABEL.to_string(), false)))
+            .collect()
+    }
+
+    /// The entries on screen: a range into `entries`.
+    pub fn window(&self) -> std::ops::Range<usize> {
+        self.scroll..(self.scroll + TOPIC_MENU_ROWS).min(self.len())
+    }
+
+    fn len(&self) -> usize {
+        self.topics.len() + 1
+    }
+
+    /// Up (-1) or down (+1), wrapping at either end.
+    fn move_cursor(&mut self, step: isize) {
+        self.cursor = (self.cursor as isize + step).rem_euclid(self.len() as isize) as usize;
+        self.scroll = scroll_to(self.scroll, self.cursor, self.len(), TOPIC_MENU_ROWS);
+    }
+
+    /// Onto the nth row on screen, if there's an entry there.
+    fn point_at(&mut self, row: usize) {
+        let index = self.window().start + row;
+        if index < self.len() {
+            self.cursor = index;
+        }
+    }
+
+    fn choose(&mut self) -> TopicChoice {
+        let Some(topic) = self.topics.get(self.cursor) else {
+            return TopicChoice::Goodbye;
+        };
+        self.open = false;
+        self.reading = Some(self.cursor);
+        if !self.visited.contains(&topic.id) {
+            self.visited.push(topic.id.clone());
+        }
+        TopicChoice::Topic { id: topic.id.clone(), segments: topic.segments.clone() }
+    }
+
+    /// Back to the menu once the greeting or a topic has been read; for a
+    /// topic, returns its `dialogue_id` to mark it read.
+    fn reopen(&mut self) -> Option<String> {
+        self.open = true;
+        self.reading.take().map(|index| self.topics[index].content_id.clone())
+    }
+}
+
+/// The first row to show so `cursor` is on screen, moving the window as
+/// little as possible from `first`.
+fn scroll_to(first: usize, cursor: usize, len: usize, rows: usize) -> usize {
+    let first = if cursor < first {
+        cursor
+    } else if cursor >= first + rows {
+        cursor + 1 - rows
+    } else {
+        first
+    };
+    first.min(len.saturating_sub(rows))
+}
+
+/// What a conversation announces as it goes: its `DialogueCompleted`
+/// outcomes, its lines' actions and the public hooks (hooks.rs), plus each
+/// line shown into the dialogue log when there is one (dialogue_history.rs)
+/// and a rumble for each choice made (rumble.rs).
+#[derive(SystemParam)]
+struct DialogueAnnouncements<'w> {
+    completions: MessageWriter<'w, DialogueCompleted>,
+    lines_shown: MessageWriter<'w, DialogueLineShown>,
+    topics_chosen: MessageWriter<'w, DialogueTopicChosen>,
+    ended: MessageWriter<'w, DialogueEnded>,
+    set_flags: MessageWriter<'w, SetFlagEvent>,
+    scene_changes: MessageWriter<'w, SceneChangeRequest>,
+    rumble: MessageWriter<'w, RumbleEvent>,
+    history: Option<ResMut<'w, DialogueHistory>>,
+}
+
+impl DialogueAnnouncements<'_> {
+    /// The current box's line is all on screen, typed out or (`skipped`)
+    /// revealed with a press.
+    fn line_shown(&mut self, queue: &DialogueQueue, skipped: bool) {
+        self.lines_shown.write(DialogueLineShown { id: queue.id.clone(), index: queue.current, skipped });
+        if let (Some(history), Some(segment)) = (self.history.as_mut(), queue.current_segment()) {
+            history.record(&segment.speaker, &segment.plain_text());
+        }
+    }
+
+    /// The current box has been read past: its actions, the first time,
+    /// each as a message for its owner and an event on the conversation's
+    /// span.
+    fn read_past(&mut self, queue: &mut DialogueQueue, mut dialogue: Option<&mut ActiveDialogue>) {
+        let Some(segment) = queue.segments.get(queue.current) else { return };
+        if !queue.acted.insert(queue.current) {
+            return;
+        }
+        for action in &segment.actions {
+            let (kind, value) = match action {
+                LineAction::SetFlag(flag) => {
+                    self.set_flags.write(SetFlagEvent { flag: flag.clone() });
+                    ("set_flag", flag)
+                }
+                LineAction::ChangeScene(name) => {
+                    match crate::map_data::scene_from_str(name) {
+                        Some(scene) => {
+                            self.scene_changes.write(SceneChangeRequest { scene });
+                        }
+                        None => warn!("💬 Line {} changes scene to unknown {name:?}", queue.current),
+                    }
+                    ("change_scene", name)
+                }
+            };
+            info!("💬 Line {} action: {kind} {value}", queue.current);
+            if let Some(dialogue) = dialogue.as_deref_mut() {
+                dialogue.span.add_event("dialogue.line_action", vec![
+                    KeyValue::new("action.type", kind),
+                    KeyValue::new("action.value", value.clone()),
+                    KeyValue::new("line.index", queue.current as i64),
+                ]);
+            }
+        }
+    }
+
+    /// Read to the end, or (`skipped`) skipped after reading it before -
+    /// unless it has already ended another way.
+    fn completed(&mut self, queue: &DialogueQueue, exit: &mut DialogueExit, skipped: bool) {
+        let outcome = if skipped { DialogueEndOutcome::Skipped } else { DialogueEndOutcome::Completed };
+        if exit.end(&queue.id, outcome, &mut self.ended) {
+            self.completions.write(queue.completed(skipped));
+        }
+    }
+}
+
+/// The conversation under way: its boxes, its `dialogue.session` span
+/// when telemetry is on, and how it ended once it has. One resource for
+/// the three, so none outlives the others: only `handle_dialogue_events`
+/// opens one, its span is closed by `finalize_session` alone, and it's
+/// gone with the box (despawn_dialogue_ui).
+#[derive(Resource)]
+pub struct DialogueSession {
+    pub queue: DialogueQueue,
+    /// None without telemetry, and once finalized.
+    pub telemetry: Option<ActiveDialogue>,
+    pub exit: DialogueExit,
+}
+
+impl DialogueSession {
+    /// Read through (or skipped), not walked away from - so far.
+    fn read_through(&self) -> bool {
+        self.exit.outcome().is_some_and(|outcome| outcome != DialogueEndOutcome::Forced)
+    }
+}
+
+/// How a conversation ended: by whichever way out got there first.
+#[derive(Debug, Default)]
+pub struct DialogueExit(Option<DialogueEndOutcome>);
+
+impl DialogueExit {
+    pub fn outcome(&self) -> Option<DialogueEndOutcome> {
+        self.0
+    }
+
+    /// Ends conversation `id` as `outcome`, announced with its one
+    /// `DialogueEnded`. False if it had already ended - Escape on the
+    /// frame the last line was read past - and that ending stands.
+    pub fn end(&mut self, id: &str, outcome: DialogueEndOutcome, ended: &mut MessageWriter<DialogueEnded>) -> bool {
+        if self.0.is_some() {
+            return false;
+        }
+        self.0 = Some(outcome);
+        ended.write(DialogueEnded { id: id.to_string(), outcome });
+        true
+    }
+}
+
+/// `DialogueQueue::with_pages`' pages of one line, and what they were
+/// worked out from.
+struct PageCache {
+    line: usize,
+    text: String,
+    long_words: LongWords,
+    pages: Vec<Page>,
+}
+
+/// A new window size is a new box to fit: the current line paginates
+/// again.
+fn forget_pages_on_resize(mut resized: MessageReader<bevy::window::WindowResized>, session: Option<ResMut<DialogueSession>>) {
+    if resized.read().count() == 0 {
+        return;
+    }
+    if let Some(mut session) = session {
+        session.queue.forget_pages();
+    }
+}
+
+/// Still being read: not ended this frame by some other way out.
+fn dialogue_open(session: Option<Res<DialogueSession>>) -> bool {
+    session.is_some_and(|session| session.exit.outcome().is_none())
+}
+
+/// A conversation in progress: its boxes as nodes, `current` the one on
+/// screen. Each leads to the next box, unless `branches` says it offers
+/// choices (jumping to whichever box the pick names) or ends the
+/// conversation.
+pub struct DialogueQueue {
+    segments: Vec<DialogueSegment>,
+    current: usize,
+    /// By box index; see `DialogueRequest::branches`.
+    branches: BTreeMap<usize, DialogueBranch>,
+    /// The highlighted entry of the current box's choices.
+    choice_cursor: usize,
+    /// One shared face-sheet atlas layout for the whole conversation
+    /// (created by spawn_dialogue_ui) so segment changes don't mint a new
+    /// layout asset per box.
+    face_layout: Option<Handle<TextureAtlasLayout>>,
+    /// `dialogue_id` of `segments`.
+    id: String,
+    /// Read to the end before - offers "Hold Tab to skip".
+    seen: bool,
+    on_complete: Vec<DialogueOutcome>,
+    /// The last box `detect_text_overflow` reported, so each overflowing
+    /// box counts once.
+    overflow_reported: Option<usize>,
+    /// Boxes whose `actions` have run: a choice going back to one doesn't
+    /// run them again.
+    acted: BTreeSet<usize>,
+    /// A hub's menu; `segments` is the greeting, then whichever topic is
+    /// being read.
+    topics: Option<TopicMenu>,
+    /// `DialogueRequest::pressed_at`, until spawn_dialogue_ui measures it.
+    pressed_at: Option<Instant>,
+    /// `DialoguePresentation::text_speed`.
+    text_speed: f32,
+    /// Into the current box's pages, for a line longer than the box holds
+    /// (see `paginate`).
+    page: usize,
+    /// The current box's pages, worked out once per line rather than on
+    /// every ask - see `with_pages`.
+    page_cache: Mutex<Option<PageCache>>,
+    /// `DialogueSettings::long_words`, as the box opened.
+    long_words: LongWords,
+    /// The NPC talked to, by name - None for scripted scenes. Met
+    /// (`NpcsMet`) if the box closes on a read-through.
+    npc_name: Option<String>,
+    /// How long each box was read, for the reading speed.
+    reading: ReadingClock,
+    /// `DialoguePresentation::fallback_portrait`.
+    fallback_portrait: Option<String>,
+    /// `DialoguePresentation::panel_color`.
+    panel_color: Option<Color>,
+    /// The boxes' own portraits, loading from the moment the conversation
+    /// opens so a change of face mid-conversation doesn't blank the
+    /// portrait for a frame or two.
+    preloaded_portraits: Vec<Handle<Image>>,
+}
+
+impl DialogueQueue {
+    fn new(request: &DialogueRequest, seen_dialogues: &SeenDialogues) -> Self {
+        let mut segments = request.content.segments();
+        // A blank box is one more Space press for nothing. What's left may
+        // be nothing at all - see handle_dialogue_events. Branches are
+        // renumbered to match; one aimed at a dropped box lands on the box
+        // after it.
+        let kept: Vec<usize> = (0..segments.len()).filter(|&i| !segments[i].text.trim().is_empty()).collect();
+        let renumber = |index: usize| kept.partition_point(|&k| k < index);
+        let branches = request
+            .branches
+            .iter()
+            .filter(|&(&index, _)| kept.binary_search(&index).is_ok())
+            .map(|(&index, branch)| {
+                let choices = branch
+                    .choices
+                    .iter()
+                    .map(|choice| BranchChoice { label: choice.label.clone(), target: renumber(choice.target) })
+                    .collect();
+                (renumber(index), DialogueBranch { choices, end: branch.end })
+            })
+            .collect();
+        segments.retain(|segment| !segment.text.trim().is_empty());
+        if let Some(mood) = &request.presentation.mood {
+            for segment in &mut segments {
+                segment.mood = Some(mood.clone());
+            }
+        }
+        let id = request.id.clone();
+        // A hub is a menu, not a script to skip through.
+        let seen = request.topics.is_empty() && seen_dialogues.contains(&id);
+        // Topics are the NPC's to answer, even when the player speaks first.
+        let owner = segments.iter().find(|segment| segment.speaker != crate::player::PLAYER_NAME).or(segments.first());
+        let topics = match owner {
+            Some(first) if !request.topics.is_empty() => {
+                // Topics wear the conversation's face, not the greeting's
+                // first line's.
+                let mut template = first.clone();
+                if let Some(portrait) = &request.presentation.fallback_portrait {
+                    template.portrait_path = portrait.clone();
+                }
+                Some(TopicMenu::new(&request.topics, &template))
+            }
+            _ => None,
+        };
+        Self {
+            segments,
+            current: 0,
+            branches,
+            choice_cursor: 0,
+            face_layout: None,
+            id,
+            seen,
+            on_complete: request.on_complete.clone(),
+            overflow_reported: None,
+            acted: BTreeSet::new(),
+            topics,
+            pressed_at: request.pressed_at,
+            text_speed: request.presentation.text_speed.unwrap_or(1.0),
+            page: 0,
+            page_cache: Mutex::new(None),
+            long_words: LongWords::default(),
+            npc_name: None,
+            reading: ReadingClock::default(),
+            fallback_portrait: request.presentation.fallback_portrait.clone(),
+            panel_color: request.presentation.panel_color,
+            preloaded_portraits: Vec::new(),
+        }
+    }
+
+    /// How fast this conversation types under `settings`.
+    fn chars_per_second(&self, settings: &DialogueSettings) -> f32 {
+        settings.chars_per_second * self.text_speed
+    }
+
+    fn completed(&self, skipped: bool) -> DialogueCompleted {
+        DialogueCompleted {
+            id: self.id.clone(),
+            skipped,
+            outcomes: self.on_complete.clone(),
+        }
+    }
+
+    /// See `DialogueRequest::id`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether this conversation was read to the end before.
+    pub fn seen(&self) -> bool {
+        self.seen
+    }
+
+    pub fn current_segment(&self) -> Option<&DialogueSegment> {
+        self.segments.get(self.current)
+    }
+
+    /// Every box's `{variable}`s filled in, topics' too (see variables.rs).
+    /// The id stays the authored content's.
+    fn fill_in_variables(&mut self, variables: &GameVariables) {
+        let topics = self.topics.iter_mut().flat_map(|menu| &mut menu.topics).flat_map(|topic| &mut topic.segments);
+        for segment in self.segments.iter_mut().chain(topics) {
+            if segment.text.contains('{') {
+                segment.text = variables.substitute(&segment.text);
+            }
+        }
+    }
+
+    /// The current box's line, a box at a time - its text as shown,
+    /// markup read. Paginated the first time it's asked for; kept until
+    /// the line, its text or `long_words` changes, or the window resizes
+    /// (`forget_pages`).
+    fn with_pages<R>(&self, read: impl FnOnce(&[Page]) -> R) -> R {
+        let text = self.current_segment().map(|segment| segment.text.as_str()).unwrap_or_default();
+        let mut cache = self.page_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let fresh = cache
+            .as_ref()
+            .is_some_and(|cache| cache.line == self.current && cache.long_words == self.long_words && cache.text == text);
+        if !fresh {
+            let fit = self.fit();
+            let pages = paginate(&self.markup(&fit).text, fit.row_chars, fit.rows);
+            *cache = Some(PageCache { line: self.current, text: text.to_string(), long_words: self.long_words, pages });
+        }
+        read(&cache.as_ref().expect("just filled").pages)
+    }
+
+    /// Paginate the current line afresh next time.
+    fn forget_pages(&mut self) {
+        *self.page_cache.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// How the current box's line fits the box.
+    fn fit(&self) -> TextFit {
+        let text = self.current_segment().map(DialogueSegment::plain_text).unwrap_or_default();
+        TextFit::for_line(&text, self.long_words)
+    }
+
+    /// The current box's line, markup read and long words broken to `fit`.
+    fn markup(&self, fit: &TextFit) -> Markup {
+        let markup = self.current_segment().map(|segment| parse_markup(&segment.text)).unwrap_or_default();
+        markup.break_long_words(fit.row_chars)
+    }
+
+    /// What the box shows of its line: the current page.
+    pub fn page_text(&self) -> Option<String> {
+        self.with_pages(|pages| pages.get(self.page).map(|page| page.text.clone()))
+    }
+
+    /// The current page, typing. A line's unreadable markup is logged as
+    /// its first page comes up.
+    fn typewriter(&self, settings: &DialogueSettings) -> TypewriterEffect {
+        let fit = self.fit();
+        let markup = self.markup(&fit);
+        if self.page == 0 {
+            for problem in &markup.problems {
+                warn!("💬 Dialogue {} box {}: {problem} - shown as written", self.id, self.current);
+            }
+        }
+        let page = self.with_pages(|pages| pages.get(self.page).map(|page| markup.page(page))).unwrap_or_default();
+        TypewriterEffect { text_scale: fit.scale, ..TypewriterEffect::styled(page, self.chars_per_second(settings)) }
+    }
+
+    pub fn on_last_page(&self) -> bool {
+        self.with_pages(|pages| self.page + 1 >= pages.len())
+    }
+
+    /// On to the next page of the current line; false on its last.
+    fn next_page(&mut self) -> bool {
+        if self.on_last_page() {
+            return false;
+        }
+        self.page += 1;
+        true
+    }
+
+    /// A hub conversation's menu; None for a linear one.
+    pub fn topic_menu(&self) -> Option<&TopicMenu> {
+        self.topics.as_ref()
+    }
+
+    /// The current box's choices; empty unless it branches, and until its
+    /// last page.
+    pub fn choices(&self) -> &[BranchChoice] {
+        if !self.on_last_page() {
+            return &[];
+        }
+        self.branches.get(&self.current).map_or(&[], |branch| &branch.choices)
+    }
+
+    pub fn choice_cursor(&self) -> usize {
+        self.choice_cursor
+    }
+
+    /// Up (-1) or down (+1) the current box's choices, wrapping.
+    fn move_choice(&mut self, step: isize) {
+        let count = self.choices().len();
+        if count > 0 {
+            self.choice_cursor = (self.choice_cursor as isize + step).rem_euclid(count as isize) as usize;
+        }
+    }
+
+    /// Highlight the nth choice, if the current box has that many.
+    fn point_at_choice(&mut self, index: usize) {
+        if index < self.choices().len() {
+            self.choice_cursor = index;
+        }
+    }
+
+    /// Pick the highlighted choice, if the current box has any, and go
+    /// to its box.
+    fn choose(&mut self) -> Option<BranchChoice> {
+        let choice = self.choices().get(self.choice_cursor)?.clone();
+        self.current = choice.target;
+        self.choice_cursor = 0;
+        self.page = 0;
+        Some(choice)
+    }
+
+    /// On to the box after the current one; false once the conversation
+    /// is over.
+    fn advance(&mut self) -> bool {
+        if self.branches.get(&self.current).is_some_and(|branch| branch.end) {
+            self.current = self.segments.len();
+            return false;
+        }
+        self.current += 1;
+        self.page = 0;
+        self.current < self.segments.len()
+    }
+}
+
+fn spawn_dialogue_ui(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    moods: Res<Moods>,
+    portraits: Res<Portraits>,
+    session: Option<ResMut<DialogueSession>>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut input_latency: ResMut<InputLatency>,
+    latency_metrics: Option<Res<LatencyMetrics>>,
+    settings: Res<DialogueSettings>,
+    map_box: Option<Res<MapDialogueBox>>,
+) {
+    let Some(mut session) = session else {
+        error!("❌ DialogueSession resource not found!");
+        return;
+    };
+    let DialogueSession { queue, telemetry, .. } = &mut *session;
+    queue.long_words = settings.long_words;
+
+    // The box is up this frame: the end of the interact latency.
+    if let Some(pressed_at) = queue.pressed_at.take() {
+        let latency = pressed_at.elapsed();
+        input_latency.record(LatencyAction::Interact, latency, latency_metrics.as_deref());
+        if let Some(active_dialogue) = telemetry {
+            active_dialogue.span.set_attribute(KeyValue::new("input.latency_ms", latency.as_secs_f64() * 1000.0));
+        }
+    }
+
+    let font = game_assets.dialogue_font.clone();
+
+    // Face sheets are a grid of cells, not one portrait each (see
+    // FACE_SHEET_* docs above) - render only the segment's own cell via a
+    // texture atlas, the same idiom npc.rs::spawn_npc uses for walk-sprite
+    // sheets. One layout serves every segment of the conversation.
+    let atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+        FACE_SHEET_CELL_SIZE,
+        FACE_SHEET_COLUMNS,
+        FACE_SHEET_ROWS,
+        None,
+        None,
+    ));
+    queue.face_layout = Some(atlas_layout.clone());
+
+    let first = queue.current_segment().cloned().unwrap_or(DialogueSegment {
+        speaker: "Unknown".into(),
+        portrait_path: String::new(),
+        portrait_face_index: 0,
+        text: String::new(),
+        mood: None,
+        box_layout: DialogueBoxLayout::default(),
+        actions: Vec::new(),
+        speaker_id: None,
+    });
+    // The opening mood is applied as-is; only changes between boxes blend.
+    let mood = moods.resolve(first.mood.as_deref());
+    // Likewise the opening placement.
+    let layout = first.box_layout.or(map_box.map_or_else(DialogueBoxLayout::default, |map_box| map_box.0));
+    let slide = BoxSlide::settled(layout);
+
+    // Presentation-scale layout: the box claims the bottom third of the
+    // window, unless the map or conversation says otherwise, so the text
+    // can be read from the back of a conference room.
+    let mut root_node = Node {
+        position_type: PositionType::Absolute,
+        padding: UiRect::all(Val::Px(24.0)),
+        flex_direction: FlexDirection::Row,
+        column_gap: Val::Px(24.0),
+        ..default()
+    };
+    slide.current().apply(&mut root_node);
+    let mut topic_menu_node = Node {
+        position_type: PositionType::Absolute,
+        right: Val::Px(24.0),
+        padding: UiRect::all(Val::Px(16.0)),
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(6.0),
+        display: Display::None,
+        ..default()
+    };
+    hang_topic_menu(&mut topic_menu_node, slide.position);
+    commands.spawn((
+        DialogueRoot,
+        root_node,
+        slide,
+        BackgroundColor(queue.panel_color.unwrap_or(PANEL_COLOR)),
+        BorderColor::all(Color::WHITE),
+        Interaction::default(),
+    ))
+    .with_children(|parent| {
+        // The portrait node always exists so later segments can swap faces
+        // in (or hide it) without re-spawning UI - Display::None when the
+        // current segment has no portrait. Square aspect + full height so
+        // it scales with the box instead of a hardcoded pixel size.
+        let (mut image_node, display, sheet) = portrait_for_segment(&first, queue.fallback_portrait.as_deref(), &asset_server, &atlas_layout, &portraits);
+        image_node.color = mood.tint;
+        let mut animation = PortraitAnimation::default();
+        animation.set_sheet(sheet);
+        parent.spawn((
+            PortraitNode,
+            image_node,
+            animation,
+            MoodTint::settled(TintTarget::Portrait, mood.tint),
+            Node {
+                height: Val::Percent(100.0),
+                aspect_ratio: Some(1.0),
+                display,
+                ..default()
+            },
+        ));
+
+        parent.spawn((
+            DialogueTextColumn,
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                flex_grow: 1.0,
+                // Whatever paginate misjudges stays inside the box;
+                // detect_text_overflow still sees it.
+                overflow: Overflow::clip(),
+                ..default()
+            },
+        ))
+        .with_children(|text_parent| {
+            text_parent.spawn((
+                SpeakerNameNode,
+                Text::new(first.speaker.clone()),
+                TextFont {
+                    font: font.clone().into(),
+                    // 52px at 1080p, scaling with the window (Amy sized these on a
+                        // 1080 display; small embeds like the blog iframe shrink to fit).
+                        font_size: FontSize::Vh(52.0 / 10.8),
+                    ..default()
+                },
+                TextColor(speaker_name_color(&first, &mood)),
+                MoodTint::settled(TintTarget::SpeakerName, speaker_name_color(&first, &mood)),
+            ));
+
+            text_parent.spawn((
+                DialogueTextNode,
+                Text::new(""),
+                TextFont {
+                    font: font.clone().into(),
+                    // 46px at 1080p, scaling with the window.
+                        font_size: FontSize::Vh(TEXT_VH),
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::justify(Justify::Left),
+                queue.typewriter(&settings),
+            ));
+
+            let rows = queue.branches.values().map(|branch| branch.choices.len()).max().unwrap_or(0);
+            if rows > 0 {
+                let row_font = TextFont {
+                    font: font.clone().into(),
+                    font_size: FontSize::Vh(40.0 / 10.8),
+                    ..default()
+                };
+                text_parent
+                    .spawn((
+                        ChoiceListNode,
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(6.0),
+                            display: Display::None,
+                            ..default()
+                        },
+                    ))
+                    .with_children(|list| {
+                        for row in 0..rows {
+                            list.spawn((MenuRow::Choice(row), Text::new(""), row_font.clone(), TextColor(Color::WHITE), Node::default()));
+                        }
+                    });
+            }
+        });
+
+        if queue.seen {
+            parent.spawn((
+                SkipSeenPrompt,
+                InputPrompt::new("Hold {skip} to skip", font.clone(), 28.0, Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(12.0),
+                    right: Val::Px(24.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ));
+        }
+
+        parent.spawn((
+            ContinueIndicator,
+            InputPrompt::new("{advance}", font.clone(), 28.0, Color::srgba(1.0, 1.0, 1.0, 0.8)),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                right: Val::Px(24.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+        ));
+
+        // Sitting on the box's inner edge (see `hang_topic_menu`), so the
+        // line that led into the menu ("What would you like to know?")
+        // stays readable next to it.
+        if let Some(menu) = &queue.topics {
+            let rows = menu.len().min(TOPIC_MENU_ROWS);
+            let row_font = TextFont {
+                font: font.clone().into(),
+                font_size: FontSize::Vh(40.0 / 10.8),
+                ..default()
+            };
+            parent.spawn((
+                TopicMenuNode,
+                topic_menu_node,
+                BackgroundColor(queue.panel_color.unwrap_or(PANEL_COLOR)),
+            ))
+            .with_children(|menu_parent| {
+                menu_parent.spawn((TopicMenuMore { below: false }, Text::new("..."), row_font.clone(), TextColor(Color::srgba(1.0, 1.0, 1.0, 0.6))));
+                for row in 0..rows {
+                    menu_parent.spawn((MenuRow::Topic(row), Text::new(""), row_font.clone(), TextColor(Color::WHITE)));
+                }
+                menu_parent.spawn((TopicMenuMore { below: true }, Text::new("..."), row_font, TextColor(Color::srgba(1.0, 1.0, 1.0, 0.6))));
+            });
+        }
+    });
+}
+
+/// Builds the portrait ImageNode (and node display state) for a segment,
+/// plus its animation when the portrait is an animated sheet (portrait.rs).
+/// Empty portrait path = hidden node. A portrait already known not to
+/// load is swapped for `fallback`, the conversation's own (see
+/// `fall_back_from_failed_portraits`).
+fn portrait_for_segment(
+    segment: &DialogueSegment,
+    fallback: Option<&str>,
+    asset_server: &AssetServer,
+    atlas_layout: &Handle<TextureAtlasLayout>,
+    portraits: &Portraits,
+) -> (ImageNode, Display, Option<Arc<PortraitSheet>>) {
+    if segment.portrait_path.is_empty() {
+        return (ImageNode::default(), Display::None, None);
+    }
+    let failed = asset_server
+        .get_handle::<Image>(segment.portrait_path.as_str())
+        .is_some_and(|handle| asset_server.load_state(&handle).is_failed());
+    let portrait_path = match fallback {
+        Some(fallback) if failed => fallback,
+        _ => segment.portrait_path.as_str(),
+    };
+
+    if let Some(sheet) = portraits.for_path(portrait_path) {
+        let image = ImageNode::from_atlas_image(
+            asset_server.load(portrait_path),
+            TextureAtlas {
+                layout: sheet.layout.clone(),
+                index: sheet.first_frame() as usize,
+            },
+        );
+        return (image, Display::Flex, Some(sheet));
+    }
+
+    #[cfg(debug_assertions)]
+    {
+        let max_index = FACE_SHEET_COLUMNS * FACE_SHEET_ROWS - 1;
+        if segment.portrait_face_index > max_index {
+            error!(
+                "❌ Portrait face_index {} exceeds face sheet grid bounds (max {})",
+                segment.portrait_face_index, max_index
+            );
+        }
+    }
+
+    (
+        ImageNode::from_atlas_image(
+            asset_server.load(portrait_path),
+            TextureAtlas {
+                layout: atlas_layout.clone(),
+                index: segment.portrait_face_index as usize,
+            },
+        ),
+        Display::Flex,
+        None,
+    )
+}
+
+/// A new play session starts with no conversations to link back to.
+fn reset_previous_dialogues(mut previous: ResMut<PreviousDialogues>) {
+    previous.clear();
+}
+
+/// Nor has it read any.
+fn forget_seen_dialogues(mut seen: ResMut<SeenDialogues>) {
+    seen.set_if_neq(SeenDialogues::default());
+}
+
+/// Nor is anything waiting to be said.
+fn forget_queued_dialogues(mut queued: ResMut<QueuedDialogues>) {
+    queued.0.clear();
+}
+
+fn handle_dialogue_events(
+    mut commands: Commands,
+    mut requests: MessageReader<DialogueRequest>,
+    mut started: MessageWriter<DialogueStarted>,
+    mut next_mode: ResMut<NextState<Mode>>,
+    tracer: Option<Res<GameTracer>>,
+    seen_dialogues: Res<SeenDialogues>,
+    previous: Res<PreviousDialogues>,
+    npcs: Query<(&crate::npc::Npc, Has<crate::map_objects::MapObject>)>,
+    (mut content_errors, time, content_metrics): (ResMut<ContentErrors>, Res<Time>, Option<Res<ContentMetrics>>),
+    (chaos, chaos_metrics): (Option<Res<crate::chaos::Chaos>>, Option<Res<crate::chaos::ChaosMetrics>>),
+    asset_server: Option<Res<AssetServer>>,
+    variables: Option<Res<GameVariables>>,
+    npcs_met: Res<crate::npc::NpcsMet>,
+    mut session: Option<ResMut<DialogueSession>>,
+    (settings, mut queued): (Res<DialogueSettings>, ResMut<QueuedDialogues>),
+) {
+    // Those kept waiting go first, once nothing's open.
+    let waiting = match session {
+        Some(_) => VecDeque::new(),
+        None => std::mem::take(&mut queued.0),
+    };
+    // One conversation at a time: the first request opens it, and every
+    // other - this frame's after the first, or any while it's open - is
+    // held back.
+    let mut opened: Option<DialogueSession> = None;
+    for request in waiting.into_iter().chain(requests.read().cloned()) {
+        if let Some(open) = opened.as_mut().or(session.as_deref_mut()) {
+            hold_back(request, open, settings.while_talking, &mut queued);
+            continue;
+        }
+        let request = &request;
+        let mut queue = DialogueQueue::new(request, &seen_dialogues);
+        // Before anything types, so telemetry and the log see what's shown.
+        if let Some(variables) = &variables {
+            queue.fill_in_variables(variables);
+        }
+        if queue.segments.is_empty() {
+            // No file to name by the time a request gets here; the id finds
+            // it (and map loading has usually reported the file already).
+            let broken = BrokenContent {
+                path: format!("dialogue {}", request.id),
+                error: "nothing to say: every line is empty".into(),
+            };
+            content_errors.record(&broken.path, &broken.error, time.elapsed(), content_metrics.as_deref());
+            // Release builds skip it outright; debug builds say why, like a
+            // broken NPC does - and a note has no outcomes or topics.
+            if !cfg!(debug_assertions) {
+                continue;
+            }
+            let speaker = request.content.segments().first().map_or_else(|| "Unknown".into(), |segment| segment.speaker.clone());
+            queue.segments = vec![DialogueSegment {
+                speaker,
+                portrait_path: String::new(),
+                portrait_face_index: 0,
+                text: broken.fallback_line(),
+                mood: None,
+                box_layout: DialogueBoxLayout::default(),
+                actions: Vec::new(),
+                speaker_id: None,
+            }];
+            queue.on_complete.clear();
+            queue.topics = None;
+        }
+        // Every face the conversation changes to, loading before its box
+        // comes up.
+        if let Some(asset_server) = &asset_server {
+            let line_portraits: BTreeSet<&str> = queue
+                .segments
+                .iter()
+                .map(|segment| segment.portrait_path.as_str())
+                .filter(|&path| !path.is_empty() && Some(path) != queue.fallback_portrait.as_deref())
+                .collect();
+            let preloaded = line_portraits.into_iter().map(|path| asset_server.load::<Image>(path)).collect();
+            queue.preloaded_portraits = preloaded;
+        }
+        // A sign read isn't someone met.
+        queue.npc_name = request
+            .source
+            .and_then(|entity| npcs.get(entity).ok())
+            .filter(|&(_, object)| !object)
+            .map(|(npc, _)| npc.name.clone());
+        let first_speaker = queue.segments[0].speaker.clone();
+        let first_speaker_id = queue.segments[0].speaker_id();
+        let total_lines = queue.segments.len();
+        info!("📖 Starting dialogue: {} ({} segments)", first_speaker, total_lines);
+        let topic_lines: usize = queue.topics.as_ref().map_or(0, |menu| menu.topics.iter().map(|topic| topic.segments.len()).sum());
+        started.write(DialogueStarted {
+            id: queue.id.clone(),
+            speaker: first_speaker.clone(),
+            npc_entity: request.source,
+            lines: total_lines + topic_lines,
+        });
+        // `--chaos dialogue_latency` (chaos.rs): the open takes longer.
+        let chaos_delay = chaos.as_deref().and_then(|chaos| chaos.dialogue_delay(chaos_metrics.as_deref()));
+
+        // Create dialogue session span (if telemetry is enabled)
+        let mut telemetry = None;
+        if let Some(tracer) = tracer.as_ref() {
+            // Under the sender's span (npc.interaction) when it passed one.
+            let context = match &request.parent {
+                Some(parent) => OtelContext::new().with_remote_span_context(parent.clone()),
+                None => OtelContext::new(),
+            };
+            let npc = request
+                .source
+                .and_then(|entity| npcs.get(entity).ok())
+                .map(|(npc, _)| npc.id.clone());
+            let mut builder = tracer.tracer().span_builder("dialogue.session");
+            if let Some(link) = npc.as_deref().and_then(|npc| previous.link_to_previous(npc)) {
+                builder = builder.with_links(vec![link]);
+            }
+            let mut span = builder.start_with_context(tracer.tracer(), &context);
+
+            span.set_attribute(KeyValue::new("dialogue.speaker_id", first_speaker_id.clone()));
+            span.set_attribute(KeyValue::new("dialogue.speaker", first_speaker.clone()));
+            span.set_attribute(KeyValue::new("dialogue.total_lines", total_lines as i64));
+            span.set_attribute(KeyValue::new("dialogue.id", queue.id.clone()));
+            span.set_attribute(KeyValue::new("dialogue.seen", queue.seen));
+            if let Some(name) = &queue.npc_name {
+                span.set_attribute(KeyValue::new("dialogue.first_time", !npcs_met.contains(name)));
+            }
+            if let (Some(delay), Some(chaos)) = (chaos_delay, chaos.as_deref()) {
+                span.set_attribute(chaos.attribute());
+                span.set_attribute(KeyValue::new("chaos.injected_ms", delay.as_secs_f64() * 1000.0));
+            }
+
+            // Add telemetry event for dialogue start
+            span.add_event(
+                "dialogue.resources_created",
+                vec![
+                    KeyValue::new("queue.lines", total_lines as i64),
+                    KeyValue::new("queue.speaker", first_speaker.clone()),
+                ],
+            );
+
+            telemetry = Some(ActiveDialogue {
+                span,
+                start_time: Instant::now(),
+                speaker: first_speaker,
+                speaker_id: first_speaker_id,
+                chars_read: 0,
+                npc,
+            });
+        }
+        // After the span starts, so the delay is inside it.
+        if let Some(delay) = chaos_delay {
+            std::thread::sleep(delay);
+        }
+
+        opened = Some(DialogueSession { queue, telemetry, exit: DialogueExit::default() });
+    }
+    if let Some(session) = opened {
+        commands.insert_resource(session);
+        info!("🎮 Transitioning to Dialogue mode");
+        next_mode.set(Mode::Dialogue);
+    }
+}
+
+/// A request that came while `open` was. One that's over (ended this
+/// frame, its box not down yet) isn't in the way: the request is simply
+/// next.
+fn hold_back(request: DialogueRequest, open: &mut DialogueSession, while_talking: WhileTalking, queued: &mut QueuedDialogues) {
+    let while_talking = if open.exit.outcome().is_some() { WhileTalking::QueueAfter } else { while_talking };
+    let id = request.id.clone();
+    let handling = match while_talking {
+        WhileTalking::Ignore => {
+            info!("🔇 Dialogue {id} asked for during {} - ignored", open.queue.id);
+            "ignored"
+        }
+        WhileTalking::QueueAfter => {
+            info!("⏳ Dialogue {id} asked for during {} - queued after it", open.queue.id);
+            queued.0.push_back(request);
+            "queued"
+        }
+    };
+    if let Some(dialogue) = open.telemetry.as_mut() {
+        dialogue.span.add_event("dialogue.request_held", vec![
+            KeyValue::new("request.id", id),
+            KeyValue::new("request.handling", handling),
+        ]);
+    }
+}
+
+fn type_dialogue_text(
+    time: Res<Time>,
+    mut query: Query<&mut TypewriterEffect, With<DialogueTextNode>>,
+    session: Option<ResMut<DialogueSession>>,
+    mut meter: crate::dashboard::MeterTee,
+    mut announce: DialogueAnnouncements,
+) {
+    let Some(mut session) = session else { return };
+    let DialogueSession { queue, telemetry: active_dialogue, .. } = &mut *session;
+    for mut typewriter in &mut query {
+        let was_complete = typewriter.is_complete();
+
+        if was_complete {
+            continue;
+        }
+
+        // The frame the box spawns (OnEnter, before Update) it starts
+        // empty: this frame's delta passed before it was on screen. A line
+        // swapped in by advance_dialogue, after this system, starts the
+        // same way - so a line has typed exactly N frames' worth N frames
+        // after it appeared, however the open lined up with the frame.
+        let delta = if typewriter.is_added() { Duration::ZERO } else { time.delta() };
+        let from = typewriter.current_index;
+        typewriter.type_for(delta);
+        let typed = typewriter.read_chars(from..typewriter.current_index);
+        // Track characters read - shown ones, markup, pauses and soft
+        // breaks aside
+        if typed > 0
+            && let Some(dialogue) = active_dialogue.as_mut()
+        {
+            dialogue.chars_read += typed;
+        }
+
+        // Whole on screen: reading starts now (see ReadingClock).
+        if typewriter.is_complete() {
+            queue.reading.shown(time.elapsed(), typewriter.read_chars(0..typewriter.full_text.len()));
+        }
+
+        // A line is shown, and read, once its last page is.
+        if typewriter.is_complete() && queue.on_last_page() {
+            announce.line_shown(queue, false);
+        }
+
+        // Record event when line completes. The line counts with or
+        // without a trace to put the event on (the dashboard shows it).
+        if !was_complete && typewriter.is_complete() && queue.on_last_page() {
+            let line = queue.current_segment().map_or_else(|| typewriter.full_text.clone(), DialogueSegment::plain_text);
+            let mut speaker = queue.current_segment().map(DialogueSegment::speaker_id);
+            if let Some(dialogue) = active_dialogue.as_mut() {
+                record_dialogue_line_event(
+                    &mut dialogue.span,
+                    &line,
+                    queue.current,
+                );
+                speaker.get_or_insert_with(|| dialogue.speaker_id.clone());
+            }
+            meter.dialogue_line_read(&speaker.unwrap_or_default());
+
+            info!("📝 Dialogue segment {} complete: {} chars",
+                queue.current,
+                line.len());
+        }
+    }
+}
+
+/// Puts what the typewriter has typed on screen, a span per styled run
+/// (see `parse_markup`): the first run in the node's own `Text`, the rest
+/// as `TextSpan` children, made afresh for each page. An unmarked line is
+/// one run - just the `Text`.
+fn render_typewriter(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut nodes: Query<
+        (Entity, &mut TypewriterEffect, &mut Text, &mut TextColor, &mut TextFont, Option<&Children>),
+        (With<DialogueTextNode>, Changed<TypewriterEffect>),
+    >,
+    mut spans: Query<&mut TextSpan>,
+) {
+    for (entity, mut typewriter, mut text, mut color, mut font, children) in &mut nodes {
+        let styled = |style: SpanStyle, base: &TextFont| {
+            let (face, weight) = match style.bold {
+                true => (&game_assets.dialogue_font_bold, FontWeight::BOLD),
+                false => (&game_assets.dialogue_font, FontWeight::NORMAL),
             };
-            if let Ok((mut text, mut typewriter)) = typewriter_query.single_mut() {
-                **text = String::new();
-                *typewriter = TypewriterEffect::new(segment.text.clone());
+            (TextColor(style.color.unwrap_or(Color::WHITE)), TextFont { font: face.clone().into(), weight, ..base.clone() })
+        };
+        let mut shown = typewriter
+            .shown_runs()
+            .map(|(typed, style)| (typed.to_string(), style))
+            .collect::<Vec<_>>()
+            .into_iter();
+        let (first, first_style) = shown.next().unwrap_or_default();
+        text.0 = first;
+
+        if typewriter.spans_built {
+            for (&child, (typed, _)) in children.into_iter().flatten().zip(shown) {
+                if let Ok(mut span) = spans.get_mut(child) {
+                    span.0 = typed;
+                }
+            }
+            continue;
+        }
+        for &child in children.into_iter().flatten() {
+            commands.entity(child).despawn();
+        }
+        // A new page: at its line's size (see `TextFit`).
+        let base = TextFont { font_size: FontSize::Vh(TEXT_VH * typewriter.text_scale), ..font.clone() };
+        (*color, *font) = styled(first_style, &base);
+        commands.entity(entity).with_children(|node| {
+            for (typed, style) in shown {
+                node.spawn((TextSpan::new(typed), styled(style, &base)));
+            }
+        });
+        typewriter.bypass_change_detection().spans_built = true;
+    }
+}
+
+fn advance_dialogue(
+    advance: AdvanceInput,
+    asset_server: Res<AssetServer>,
+    moods: Res<Moods>,
+    mut seen_dialogues: ResMut<SeenDialogues>,
+    mut announce: DialogueAnnouncements,
+    mut next_mode: ResMut<NextState<Mode>>,
+    session: Option<ResMut<DialogueSession>>,
+    mut typewriter_query: Query<&mut TypewriterEffect, With<DialogueTextNode>>,
+    mut speaker_query: Query<(&mut Text, &mut MoodTint), (With<SpeakerNameNode>, Without<DialogueTextNode>)>,
+    mut portrait_query: Query<(&mut ImageNode, &mut Node, &mut MoodTint, &mut PortraitAnimation), (With<PortraitNode>, Without<SpeakerNameNode>)>,
+    portraits: Res<Portraits>,
+    mut events: Option<ResMut<GameEvents>>,
+    metrics: Option<Res<DialogueMetrics>>,
+    (settings, time): (Res<DialogueSettings>, Res<Time>),
+) {
+    if !advance.requested() {
+        return;
+    }
+    let Some(mut session) = session else {
+        next_mode.set(Mode::Exploring);
+        return;
+    };
+    let DialogueSession { queue, telemetry: active_dialogue, exit } = &mut *session;
+
+    if let Ok(mut typewriter) = typewriter_query.single_mut()
+        && !typewriter.is_complete()
+    {
+        // Cut short: reading starts at the press.
+        queue.reading.shown(time.elapsed(), typewriter.read_chars(0..typewriter.full_text.len()));
+        let from = typewriter.current_index;
+        typewriter.reveal_rest();
+        // Shown is read, as far as reading speed is concerned.
+        if let Some(dialogue) = active_dialogue.as_mut() {
+            dialogue.chars_read += typewriter.read_chars(from..typewriter.full_text.len());
+        }
+        if queue.on_last_page() {
+            announce.line_shown(queue, true);
+        }
+        return;
+    }
+
+    if let Some((dwell, chars)) = queue.reading.moved_on(time.elapsed()) {
+        if let Some(metrics) = &metrics {
+            let speaker = queue.current_segment().map(DialogueSegment::speaker_id).unwrap_or_default();
+            metrics.line_dwell.record(dwell.as_secs_f64(), &[KeyValue::new("speaker.id", speaker)]);
+        }
+        if let Some(dialogue) = active_dialogue.as_mut() {
+            dialogue.span.add_event("dialogue.line_dwell", vec![
+                KeyValue::new("line.index", queue.current as i64),
+                KeyValue::new("line.length", chars as i64),
+                KeyValue::new("line.dwell_secs", dwell.as_secs_f64()),
+            ]);
+        }
+    }
+    if let Some(menu) = queue.topics.as_mut().filter(|menu| menu.open) {
+        match menu.choose() {
+            TopicChoice::Goodbye => {
+                info!("Hub dialogue ended with goodbye");
+                seen_dialogues.insert(queue.id.clone());
+                announce.completed(queue, exit, false);
+                next_mode.set(Mode::Exploring);
             }
-            // Each segment carries its own speaker/portrait - a scripted
-            // scene switches faces mid-conversation.
-            if let Ok(mut speaker_text) = speaker_query.single_mut() {
-                **speaker_text = segment.speaker.clone();
+            TopicChoice::Topic { id, segments } => {
+                info!("💬 Topic chosen: {id}");
+                announce.topics_chosen.write(DialogueTopicChosen { id: queue.id.clone(), topic: id.clone() });
+                if let Some(metrics) = &metrics {
+                    metrics.topics_selected.add(1, &[KeyValue::new("topic", id.clone())]);
+                }
+                if let Some(dialogue) = active_dialogue.as_mut() {
+                    let visited: Vec<StringValue> = menu.visited.iter().cloned().map(StringValue::from).collect();
+                    dialogue.span.set_attribute(KeyValue::new("dialogue.topics_visited", Value::Array(Array::String(visited))));
+                }
+                queue.segments = segments;
+                queue.current = 0;
+                queue.page = 0;
+                // A topic is read straight through; the greeting's
+                // branching was about the greeting.
+                queue.branches.clear();
+                queue.overflow_reported = None;
+                queue.acted.clear();
+                show_current_segment(queue, &settings, &asset_server, &moods, &mut typewriter_query, &mut speaker_query, &mut portrait_query, &portraits);
             }
-            if let (Ok((mut image, mut node)), Some(layout)) =
-                (portrait_query.single_mut(), queue.face_layout.as_ref())
-            {
-                let (new_image, display) = portrait_for_segment(&segment, &asset_server, layout);
-                *image = new_image;
-                node.display = display;
+        }
+        return;
+    }
+    if let (Some(events), Some(segment)) = (events.as_deref_mut(), queue.current_segment()) {
+        events.publish(GameEvent::LineRead {
+            dialogue: queue.id.clone(),
+            speaker: segment.speaker.clone(),
+            line: queue.current,
+        });
+    }
+    // The rest of a long line before anything else.
+    if queue.next_page() {
+        show_current_segment(queue, &settings, &asset_server, &moods, &mut typewriter_query, &mut speaker_query, &mut portrait_query, &portraits);
+        return;
+    }
+    announce.read_past(queue, active_dialogue.as_mut());
+    let line = queue.current;
+    let more = match queue.choose() {
+        Some(choice) => {
+            info!("🔀 Choice made: {}", choice.label);
+            announce.rumble.write(RumbleEvent::MEDIUM);
+            if let Some(dialogue) = active_dialogue.as_mut() {
+                dialogue.span.add_event("dialogue.choice_selected", vec![
+                    KeyValue::new("choice.label", choice.label.clone()),
+                    KeyValue::new("line.index", line as i64),
+                    KeyValue::new("choice.target", choice.target as i64),
+                ]);
             }
-        } else {
-            info!("Dialogue sequence complete");
-            next_mode.set(Mode::Exploring);
+            queue.current < queue.segments.len()
+        }
+        None => queue.advance(),
+    };
+    if more {
+        show_current_segment(queue, &settings, &asset_server, &moods, &mut typewriter_query, &mut speaker_query, &mut portrait_query, &portraits);
+    } else if let Some(menu) = queue.topics.as_mut() {
+        if let Some(read) = menu.reopen() {
+            seen_dialogues.insert(read);
         }
     } else {
+        info!("Dialogue sequence complete");
+        seen_dialogues.insert(queue.id.clone());
+        announce.completed(queue, exit, false);
         next_mode.set(Mode::Exploring);
     }
 }
 
-fn despawn_dialogue_ui(
-    mut commands: Commands,
-    dialogue_root: Query<Entity, With<DialogueRoot>>,
-    active_dialogue: Option<ResMut<ActiveDialogue>>,
-    meter: Option<Res<GameMeter>>,
+/// Puts the queue's current box on screen. Each segment carries its own
+/// speaker/portrait/mood - a scripted scene switches faces
+/// mid-conversation. Mood changes blend over MOOD_TINT_SECONDS (mood.rs)
+/// instead of snapping.
+fn show_current_segment(
+    queue: &DialogueQueue,
+    settings: &DialogueSettings,
+    asset_server: &AssetServer,
+    moods: &Moods,
+    typewriter_query: &mut Query<&mut TypewriterEffect, With<DialogueTextNode>>,
+    speaker_query: &mut Query<(&mut Text, &mut MoodTint), (With<SpeakerNameNode>, Without<DialogueTextNode>)>,
+    portrait_query: &mut Query<(&mut ImageNode, &mut Node, &mut MoodTint, &mut PortraitAnimation), (With<PortraitNode>, Without<SpeakerNameNode>)>,
+    portraits: &Portraits,
 ) {
-    for entity in &dialogue_root {
-        commands.entity(entity).despawn();
+    let Some(segment) = queue.current_segment() else {
+        return;
+    };
+    if let Ok(mut typewriter) = typewriter_query.single_mut() {
+        *typewriter = queue.typewriter(settings);
+    }
+    let mood = moods.resolve(segment.mood.as_deref());
+    if let Ok((mut speaker_text, mut tint)) = speaker_query.single_mut() {
+        **speaker_text = segment.speaker.clone();
+        tint.retarget(speaker_name_color(segment, &mood));
+    }
+    if let (Ok((mut image, mut node, mut tint, mut animation)), Some(layout)) =
+        (portrait_query.single_mut(), queue.face_layout.as_ref())
+    {
+        let (new_image, display, sheet) = portrait_for_segment(segment, queue.fallback_portrait.as_deref(), asset_server, layout, portraits);
+        *image = new_image;
+        // The same animated face carries on where it was.
+        animation.set_sheet(sheet);
+        if let (Some(frame), Some(atlas)) = (animation.tick(Duration::ZERO, true), image.texture_atlas.as_mut()) {
+            atlas.index = frame as usize;
+        }
+        // The fresh ImageNode is untinted; keep the blend's current
+        // color until animate_mood_tints moves it on.
+        image.color = tint.current();
+        tint.retarget(mood.tint);
+        node.display = display;
+    }
+}
+
+/// The speaker's name in `segment`'s mood - or, on the player's own
+/// lines (`player::PLAYER_NAME`), their color whatever the mood.
+fn speaker_name_color(segment: &DialogueSegment, mood: &Mood) -> Color {
+    if segment.speaker == crate::player::PLAYER_NAME {
+        PLAYER_NAME_COLOR
+    } else {
+        mood.name_color
+    }
+}
+
+/// A box's portrait that fails to load - a line's `portrait` naming a
+/// file that didn't ship - gives way to the conversation's own
+/// (`DialogueQueue::fallback_portrait`) rather than a broken image. Later
+/// boxes with it go straight to the fallback (`portrait_for_segment`).
+/// Each such file is a content error, reported when its preload fails,
+/// whether or not its box has come up yet.
+fn fall_back_from_failed_portraits(
+    asset_server: Res<AssetServer>,
+    portraits: Res<Portraits>,
+    session: Option<ResMut<DialogueSession>>,
+    mut portrait_query: Query<(&mut ImageNode, &mut PortraitAnimation), With<PortraitNode>>,
+    mut content_errors: ResMut<ContentErrors>,
+    time: Res<Time>,
+    content_metrics: Option<Res<ContentMetrics>>,
+) {
+    let Some(mut session) = session else { return };
+    let queue = &mut session.queue;
+    let failed = |handle: &Handle<Image>| asset_server.load_state(handle).is_failed();
+    if queue.preloaded_portraits.iter().any(failed) {
+        let (broken, loading): (Vec<_>, Vec<_>) = std::mem::take(&mut queue.preloaded_portraits).into_iter().partition(failed);
+        queue.preloaded_portraits = loading;
+        for handle in broken {
+            if let (Some(path), bevy::asset::LoadState::Failed(error)) = (handle.path(), asset_server.load_state(&handle)) {
+                content_errors.record(path.to_string(), format!("portrait failed to load: {error}"), time.elapsed(), content_metrics.as_deref());
+            }
+        }
+    }
+
+    let (Some(fallback), Some(segment), Some(layout)) =
+        (queue.fallback_portrait.as_deref(), queue.current_segment(), queue.face_layout.as_ref())
+    else {
+        return;
+    };
+    let Ok((mut image, mut animation)) = portrait_query.single_mut() else { return };
+    // Nothing to mend - or nothing better to show, when the conversation's
+    // own face is the broken one.
+    if !failed(&image.image) || image.image.path().is_some_and(|path| path.path() == std::path::Path::new(fallback)) {
+        return;
+    }
+    debug!("🖼️ {} didn't load - showing {fallback}", segment.portrait_path);
+    let (new_image, _, sheet) = portrait_for_segment(segment, Some(fallback), &asset_server, layout, &portraits);
+    let color = image.color;
+    *image = new_image;
+    image.color = color;
+    animation.set_sheet(sheet);
+    if let (Some(frame), Some(atlas)) = (animation.tick(Duration::ZERO, true), image.texture_atlas.as_mut()) {
+        atlas.index = frame as usize;
+    }
+}
+
+/// Plays an animated portrait (portrait.rs): `talking` while the
+/// typewriter is revealing the line, `idle` and its blinks once it's out.
+fn animate_portrait(
+    time: Res<Time>,
+    typewriter: Query<&TypewriterEffect, With<DialogueTextNode>>,
+    mut portraits: Query<(&mut PortraitAnimation, &mut ImageNode), With<PortraitNode>>,
+) {
+    let talking = typewriter.single().is_ok_and(|typewriter| !typewriter.is_complete());
+    for (mut animation, mut image) in &mut portraits {
+        let Some(frame) = animation.tick(time.delta(), talking) else { continue };
+        if image.texture_atlas.as_ref().is_some_and(|atlas| atlas.index != frame as usize)
+            && let Some(atlas) = image.texture_atlas.as_mut()
+        {
+            atlas.index = frame as usize;
+        }
+    }
+}
+
+/// Puts the box where the current one wants it (`DialogueSegment::box_layout`
+/// over `MapDialogueBox`), sliding it over for `BOX_SLIDE_SECONDS` when
+/// that changes mid-conversation. The topic menu moves with it.
+fn place_dialogue_box(
+    time: Res<Time>,
+    session: Option<Res<DialogueSession>>,
+    map_box: Option<Res<MapDialogueBox>>,
+    mut boxes: Query<(&mut BoxSlide, &mut Node), With<DialogueRoot>>,
+    mut menus: Query<&mut Node, (With<TopicMenuNode>, Without<DialogueRoot>)>,
+) {
+    let Ok((mut slide, mut node)) = boxes.single_mut() else { return };
+    if let Some(segment) = session.as_ref().and_then(|session| session.queue.current_segment()) {
+        slide.retarget(segment.box_layout.or(map_box.map_or_else(DialogueBoxLayout::default, |map_box| map_box.0)));
+    }
+    if slide.timer.is_finished() {
+        return;
+    }
+    slide.timer.tick(time.delta());
+    slide.current().apply(&mut node);
+    for mut menu in &mut menus {
+        hang_topic_menu(&mut menu, slide.position);
+    }
+}
+
+/// W/S or the arrows move an open topic menu's cursor, or the highlight
+/// of a box's choices once they're on screen.
+fn navigate_dialogue_menus(
+    keyboard: crate::input::GameInput,
+    session: Option<ResMut<DialogueSession>>,
+    typewriter: Query<&TypewriterEffect, With<DialogueTextNode>>,
+) {
+    let step = if keyboard.just_pressed(KeyCode::KeyW) || keyboard.just_pressed(KeyCode::ArrowUp) {
+        -1
+    } else if keyboard.just_pressed(KeyCode::KeyS) || keyboard.just_pressed(KeyCode::ArrowDown) {
+        1
+    } else {
+        return;
+    };
+    let Some(mut session) = session else { return };
+    let queue = &mut session.queue;
+    if let Some(menu) = queue.topics.as_mut().filter(|menu| menu.open) {
+        menu.move_cursor(step);
+    } else if typewriter.single().is_ok_and(TypewriterEffect::is_complete) {
+        queue.move_choice(step);
+    }
+}
+
+/// The mouse on a menu: hovering a `MenuRow` moves the highlight to it, as
+/// does pressing one (a click that came without a hover first), so the
+/// press `AdvanceInput` then sees picks that row. After the keys, so the
+/// last thing the player did wins.
+fn point_at_menu_rows(
+    session: Option<ResMut<DialogueSession>>,
+    typewriter: Query<&TypewriterEffect, With<DialogueTextNode>>,
+    rows: Query<(&MenuRow, &Interaction), Changed<Interaction>>,
+) {
+    let Some(mut session) = session else { return };
+    let queue = &mut session.queue;
+    for (row, interaction) in &rows {
+        if *interaction == Interaction::None {
+            continue;
+        }
+        match *row {
+            MenuRow::Choice(index) => {
+                if typewriter.single().is_ok_and(TypewriterEffect::is_complete) {
+                    queue.point_at_choice(index);
+                }
+            }
+            MenuRow::Topic(row) => {
+                if let Some(menu) = queue.topics.as_mut().filter(|menu| menu.open) {
+                    menu.point_at(row);
+                }
+            }
+        }
+    }
+}
+
+/// Shows the current box's choices under its text once the line has
+/// typed out, "> " on the highlighted one; hidden otherwise.
+fn sync_choice_list(
+    session: Option<Res<DialogueSession>>,
+    typewriter: Query<&TypewriterEffect, With<DialogueTextNode>>,
+    mut lists: Query<&mut Node, With<ChoiceListNode>>,
+    mut rows: Query<(&MenuRow, &mut Text, &mut Node), Without<ChoiceListNode>>,
+) {
+    let Some(queue) = session.as_deref().map(|session| &session.queue) else { return };
+    let Ok(mut list) = lists.single_mut() else { return };
+    let choices = queue.choices();
+    let shown = !choices.is_empty() && typewriter.single().is_ok_and(TypewriterEffect::is_complete);
+    let display = if shown { Display::Flex } else { Display::None };
+    if list.display != display {
+        list.display = display;
+    }
+    if !shown {
+        return;
+    }
+    for (row, mut text, mut node) in &mut rows {
+        let MenuRow::Choice(row) = *row else { continue };
+        let display = if row < choices.len() { Display::Flex } else { Display::None };
+        if node.display != display {
+            node.display = display;
+        }
+        let Some(choice) = choices.get(row) else { continue };
+        let marker = if row == queue.choice_cursor { "> " } else { "  " };
+        let line = format!("{marker}{}", choice.label);
+        if **text != line {
+            **text = line;
+        }
+    }
+}
+
+/// Redraws the topic menu from `TopicMenu`: shown only while open, "> "
+/// on the cursor's row, read topics dimmed.
+fn sync_topic_menu(
+    session: Option<Res<DialogueSession>>,
+    seen_dialogues: Res<SeenDialogues>,
+    mut panels: Query<&mut Node, With<TopicMenuNode>>,
+    mut rows: Query<(&MenuRow, &mut Text, &mut TextColor), Without<TopicMenuMore>>,
+    mut more: Query<(&TopicMenuMore, &mut Node), Without<TopicMenuNode>>,
+) {
+    let Some(menu) = session.as_ref().and_then(|session| session.queue.topics.as_ref()) else {
+        return;
+    };
+    let Ok(mut panel) = panels.single_mut() else { return };
+    let display = if menu.open { Display::Flex } else { Display::None };
+    if panel.display != display {
+        panel.display = display;
+    }
+    if !menu.open {
+        return;
+    }
+
+    let entries = menu.entries(&seen_dialogues);
+    let window = menu.window();
+    for (row, mut text, mut color) in &mut rows {
+        let MenuRow::Topic(row) = *row else { continue };
+        let index = window.start + row;
+        let Some((label, read)) = entries.get(index) else { continue };
+        let marker = if index == menu.cursor { "> " } else { "  " };
+        let line = match read {
+            true => format!("{marker}{label} {READ_MARK}"),
+            false => format!("{marker}{label}"),
+        };
+        if **text != line {
+            **text = line;
+        }
+        let alpha = if *read { 0.45 } else { 1.0 };
+        color.set_if_neq(TextColor(Color::srgba(1.0, 1.0, 1.0, alpha)));
+    }
+    for (hint, mut node) in &mut more {
+        let hidden = if hint.below { window.end >= entries.len() } else { window.start == 0 };
+        let display = if hidden { Display::None } else { Display::Flex };
+        if node.display != display {
+            node.display = display;
+        }
+    }
+}
+
+/// Text that doesn't fit its box, measured after layout: the column's
+/// content outgrowing the column means the renderer is clipping or
+/// spilling the line, whatever the pagination thought. Each overflowing
+/// box is counted (`game.ui.text_overflow`, by locale and dialogue id) and
+/// noted on the dialogue span; debug builds also outline the box in red
+/// so a playtester sees which one, for as long as it overflows.
+fn detect_text_overflow(
+    session: Option<ResMut<DialogueSession>>,
+    columns: Query<&ComputedNode, With<DialogueTextColumn>>,
+    mut boxes: Query<(&mut Node, &mut BorderColor), With<DialogueRoot>>,
+    metrics: Option<Res<DialogueMetrics>>,
+    mut outlined: Local<bool>,
+) {
+    let Some(mut session) = session else { return };
+    let DialogueSession { queue, telemetry: active_dialogue, .. } = &mut *session;
+    let Ok(column) = columns.single() else { return };
+    // Half a pixel of slack for layout rounding.
+    let overflow = column.content_size() - column.size();
+    let overflowing = overflow.x > 0.5 || overflow.y > 0.5;
+    // The next line or a resize can make it fit again: back to the box's
+    // own border (spawn_dialogue_ui's) then.
+    if cfg!(debug_assertions)
+        && *outlined != overflowing
+        && let Ok((mut node, mut border)) = boxes.single_mut()
+    {
+        (node.border, *border) = if overflowing {
+            (UiRect::all(Val::Px(4.0)), BorderColor::all(Color::srgb(1.0, 0.0, 0.0)))
+        } else {
+            (UiRect::ZERO, BorderColor::all(Color::WHITE))
+        };
+        *outlined = overflowing;
+    }
+    if !overflowing {
+        return;
+    }
+    let line = queue.current;
+    if queue.overflow_reported == Some(line) {
+        return;
+    }
+    queue.overflow_reported = Some(line);
+
+    warn!(
+        "📏 Dialogue {} box {line} overflows by {:.0}x{:.0}px",
+        queue.id,
+        overflow.x.max(0.0),
+        overflow.y.max(0.0)
+    );
+    if let Some(metrics) = metrics {
+        metrics.text_overflow.add(1, &[
+            KeyValue::new("locale", DIALOGUE_LOCALE),
+            KeyValue::new("dialogue.id", queue.id.clone()),
+        ]);
+    }
+    if let Some(dialogue) = active_dialogue.as_mut() {
+        dialogue.span.add_event("dialogue.text_overflow", vec![
+            KeyValue::new("line.index", line as i64),
+            KeyValue::new("overflow.height", overflow.y.max(0.0) as f64),
+        ]);
+    }
+}
+
+/// Holding Tab through a conversation already read ends it as if it had
+/// been read to the end: its `on_complete` outcomes apply, and a scripted
+/// scene's transfer (transitions.rs) still happens, unlike Escape's decline.
+fn skip_seen_dialogue(
+    keyboard: crate::input::GameInput,
+    time: Res<Time>,
+    session: Option<ResMut<DialogueSession>>,
+    metrics: Option<Res<DialogueMetrics>>,
+    mut announce: DialogueAnnouncements,
+    mut next_mode: ResMut<NextState<Mode>>,
+    mut held: Local<Duration>,
+) {
+    let Some(mut session) = session.filter(|session| session.queue.seen) else {
+        *held = Duration::ZERO;
+        return;
+    };
+    if !keyboard.pressed(KeyCode::Tab) {
+        *held = Duration::ZERO;
+        return;
+    }
+    *held += time.delta();
+    if *held < SKIP_HOLD {
+        return;
+    }
+    *held = Duration::ZERO;
+
+    let DialogueSession { queue, telemetry: active_dialogue, exit } = &mut *session;
+    let speaker = queue.segments.first().map(|s| s.speaker.clone()).unwrap_or_default();
+    info!("⏭️ Skipping already-read dialogue with {speaker} ({})", queue.id);
+    if let Some(dialogue) = active_dialogue.as_mut() {
+        dialogue.span.set_attribute(KeyValue::new("dialogue.skipped_seen", true));
+    }
+    if let Some(metrics) = metrics {
+        let speaker = queue.segments.first().map(DialogueSegment::speaker_id).unwrap_or_default();
+        metrics.skipped_seen.add(1, &[KeyValue::new("speaker.id", speaker)]);
     }
+    announce.completed(queue, exit, true);
+    next_mode.set(Mode::Exploring);
+}
+
+fn sync_continue_indicator(
+    session: Option<Res<DialogueSession>>,
+    typewriter: Query<&TypewriterEffect, With<DialogueTextNode>>,
+    mut indicator: Query<&mut Visibility, With<ContinueIndicator>>,
+) {
+    let Ok(mut visibility) = indicator.single_mut() else { return };
+    // A menu or a box's choices say what the next press does instead.
+    let menu_open = session
+        .as_deref()
+        .map(|session| &session.queue)
+        .is_some_and(|queue| queue.topics.as_ref().is_some_and(|menu| menu.open) || !queue.choices().is_empty());
+    let waiting = !menu_open && typewriter.single().is_ok_and(TypewriterEffect::is_complete);
+    visibility.set_if_neq(if waiting { Visibility::Inherited } else { Visibility::Hidden });
+}
+
+/// Closes the session's span once it has ended - at the end of the frame
+/// it ended in, whichever way it did.
+fn finish_dialogue_session(
+    session: Option<ResMut<DialogueSession>>,
+    mut previous: ResMut<PreviousDialogues>,
+    metrics: Option<Res<DialogueMetrics>>,
+) {
+    let Some(mut session) = session else { return };
+    let Some(outcome) = session.exit.outcome() else { return };
+    finalize_session(&mut session, outcome, Some(&mut previous), metrics.as_deref());
+}
+
+/// Ends the session's span, the one place it is: the first call takes
+/// the telemetry, so a session's span ends exactly once. A read-through
+/// (or skip) records the reading speed; an Escape just closes the span.
+fn finalize_session(
+    session: &mut DialogueSession,
+    outcome: DialogueEndOutcome,
+    previous: Option<&mut PreviousDialogues>,
+    metrics: Option<&DialogueMetrics>,
+) {
+    let Some(mut dialogue) = session.telemetry.take() else { return };
 
-    // Finalize dialogue session span and record metrics
-    if let Some(mut dialogue) = active_dialogue {
+    if outcome != DialogueEndOutcome::Forced {
         let duration_secs = dialogue.start_time.elapsed().as_secs_f64();
         let chars_read = dialogue.chars_read;
-        let speaker = dialogue.speaker.clone();
+        let speaker = dialogue.speaker_id.clone();
 
-        // Calculate reading speed (chars/second)
+        // Calculate reading speed (chars/second). Deprecated: the whole
+        // session includes the typewriter, so this is mostly the text
+        // speed. Kept for continuity; `dwell_reading_speed` is the reader's.
         let reading_speed = if duration_secs > 0.0 {
             chars_read as f64 / duration_secs
         } else {
             0.0
         };
+        let reading = &session.queue.reading;
+        let dwell_reading_speed = reading.reading_speed();
 
         // Add final attributes to span
         dialogue.span.set_attribute(KeyValue::new("dialogue.chars_read", chars_read as i64));
         dialogue.span.set_attribute(KeyValue::new("dialogue.duration_secs", duration_secs));
         dialogue.span.set_attribute(KeyValue::new("dialogue.reading_speed", reading_speed));
+        dialogue.span.set_attribute(KeyValue::new("dialogue.dwell_secs", reading.dwell.as_secs_f64()));
+        if let Some(speed) = dwell_reading_speed {
+            dialogue.span.set_attribute(KeyValue::new("dialogue.dwell_reading_speed", speed));
+        }
 
-        if let Some(ref meter) = meter {
-            meter.dialogue_reading_speed.record(
+        // Nothing read is no reading speed: a zero would only drag the
+        // histogram down.
+        if let Some(metrics) = metrics
+            && chars_read > 0
+        {
+            metrics.reading_speed.record(
                 reading_speed,
-                &[KeyValue::new("speaker", speaker.clone())]
+                &[KeyValue::new("speaker.id", speaker.clone())]
             );
         }
+        if let (Some(metrics), Some(speed)) = (metrics, dwell_reading_speed) {
+            metrics.dwell_reading_speed.record(speed, &[KeyValue::new("speaker.id", speaker.clone())]);
+        }
 
-        info!("📊 Dialogue session complete: {} chars in {:.2}s ({:.1} chars/sec)",
+        info!("📊 Dialogue session complete: {} chars in {:.2}s ({:.1} chars/sec, {:.1} over {:.2}s of reading)",
             chars_read,
             duration_secs,
-            reading_speed);
+            reading_speed,
+            dwell_reading_speed.unwrap_or_default(),
+            reading.dwell.as_secs_f64());
 
         // Add telemetry event for resource cleanup
         dialogue.span.add_event(
@@ -458,12 +2997,471 @@ fn despawn_dialogue_ui(
                 KeyValue::new("dialogue.completed", true),
             ],
         );
+    }
+
+    dialogue.end(outcome.as_str(), previous);
+}
+
+/// Takes the box down, and the conversation with it. Read through (or
+/// skipped as already read), its NPC is met; ended `Forced` - Escape
+/// (game_state.rs) - it isn't.
+fn despawn_dialogue_ui(
+    mut commands: Commands,
+    dialogue_root: Query<Entity, With<DialogueRoot>>,
+    session: Option<ResMut<DialogueSession>>,
+    mut npcs_met: ResMut<crate::npc::NpcsMet>,
+) {
+    for entity in &dialogue_root {
+        commands.entity(entity).despawn();
+    }
+
+    if let Some(mut session) = session {
+        if session.read_through()
+            && let Some(name) = session.queue.npc_name.as_deref()
+            && npcs_met.meet(name)
+        {
+            info!("🤝 Met {name} ({} so far)", npcs_met.count());
+        }
 
-        // Span ends when dropped (but we'll explicitly end it for clarity)
-        dialogue.span.end();
-        commands.remove_resource::<ActiveDialogue>();
+        // Every way out ends the session first; one still open here left
+        // some other way, and would otherwise never end its span.
+        if session.telemetry.is_some() && session.exit.outcome().is_none() {
+            warn!("Dialogue mode exited without the conversation ending - closing the session as forced");
+        }
+        let outcome = session.exit.outcome().unwrap_or(DialogueEndOutcome::Forced);
+        finalize_session(&mut session, outcome, None, None);
     }
 
-    commands.remove_resource::<DialogueQueue>();
+    commands.remove_resource::<DialogueSession>();
     info!("Dialogue UI despawned");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(speaker: &str, text: &str) -> DialogueSegment {
+        DialogueSegment {
+            speaker: speaker.into(),
+            portrait_path: String::new(),
+            portrait_face_index: 0,
+            text: text.into(),
+            mood: None,
+            box_layout: DialogueBoxLayout::default(),
+            actions: Vec::new(),
+            speaker_id: None,
+        }
+    }
+
+    #[test]
+    fn the_box_slides_between_placements() {
+        use crate::map_data::BOX_HEIGHT_PERCENT;
+
+        let bottom = DialogueBoxLayout::default();
+        let top = DialogueBoxLayout { box_position: Some(BoxPosition::Top), box_height: Some(25.0), ..default() };
+        assert_eq!(BoxPlacement::of(bottom), BoxPlacement {
+            top: 100.0 - BOX_HEIGHT_PERCENT,
+            left: 0.0,
+            width: 100.0,
+            height: BOX_HEIGHT_PERCENT,
+        });
+        assert_eq!(BoxPlacement::of(top), BoxPlacement { top: 0.0, left: 0.0, width: 100.0, height: 25.0 });
+        let narrow = DialogueBoxLayout { box_width: Some(60.0), ..default() };
+        assert_eq!(BoxPlacement::of(narrow).left, 20.0, "centered");
+
+        let mut slide = BoxSlide::settled(bottom);
+        assert_eq!(slide.current(), BoxPlacement::of(bottom));
+        slide.retarget(top);
+        assert_eq!(slide.position, BoxPosition::Top);
+        assert_eq!(slide.current(), BoxPlacement::of(bottom), "starts where it was");
+        slide.timer.tick(Duration::from_secs_f32(BOX_SLIDE_SECONDS / 2.0));
+        let halfway = slide.current();
+        assert!(halfway.top > 0.0 && halfway.top < 100.0 - BOX_HEIGHT_PERCENT, "halfway: {halfway:?}");
+        slide.timer.tick(Duration::from_secs_f32(BOX_SLIDE_SECONDS));
+        assert_eq!(slide.current(), BoxPlacement::of(top));
+    }
+
+    #[test]
+    fn dialogue_id_follows_what_the_player_reads() {
+        let original = vec![segment("Isabella", "Welcome."), segment("Isabella", "Mind the wall.")];
+        let id = dialogue_id(&original);
+        assert_eq!(id, dialogue_id(&original.clone()), "stable for the same content");
+        // Pinned: a changed hash would forget every saved seen-set.
+        assert_eq!(dialogue_id(&[segment("A", "b")]), "ad22d88cd6227156");
+
+        let mut reworded = original.clone();
+        reworded[1].text = "Mind the gap.".into();
+        assert_ne!(dialogue_id(&reworded), id, "an edit makes it unread again");
+
+        let mut restyled = original;
+        restyled[0].mood = Some("happy".into());
+        restyled[0].portrait_path = "textures/portraits/Nature.png".into();
+        assert_eq!(dialogue_id(&restyled), id, "presentation changes don't");
+    }
+
+    #[test]
+    fn built_requests_default_their_id_to_the_content_hash() {
+        let data = Arc::new(DialogueData {
+            id: None,
+            conditions: Vec::new(),
+            speaker: "Isabella".into(),
+            portrait: "Nature".into(),
+            face_index: 2,
+            lines: vec!["Welcome.".into()],
+            mood: None,
+            text_speed: Some(0.5),
+            on_complete: vec![DialogueOutcome::SetFlag("met_isabella".into())],
+            topics: Vec::new(),
+            box_layout: DialogueBoxLayout::default(),
+        });
+        let request = DialogueRequestBuilder::authored(data).mood("happy").build();
+        let segments = request.content.segments();
+        assert_eq!(request.id, dialogue_id(&segments));
+        assert_eq!(segments[0].portrait_path, "textures/portraits/Nature.png");
+        assert_eq!(request.on_complete, [DialogueOutcome::SetFlag("met_isabella".into())]);
+        assert_eq!(request.presentation.mood.as_deref(), Some("happy"));
+        assert_eq!(request.presentation.text_speed, Some(0.5));
+
+        let shorthand: DialogueRequest = ("Isabella", vec!["Welcome.".to_string()]).into();
+        assert_eq!(shorthand.id, request.id, "same words, same conversation");
+
+        let renamed = DialogueRequestBuilder::segments(segments).id("intro").build();
+        assert_eq!(renamed.id, "intro");
+    }
+
+    #[test]
+    fn blank_boxes_are_dropped_from_the_queue() {
+        let queue = |lines: &[&str]| {
+            let request: DialogueRequest = ("Isabella", lines.iter().map(|line| line.to_string()).collect()).into();
+            DialogueQueue::new(&request, &SeenDialogues::default())
+        };
+        assert!(queue(&[]).segments.is_empty());
+        assert!(queue(&[""]).segments.is_empty());
+        assert!(queue(&[" \n\t"]).segments.is_empty());
+
+        let mixed = queue(&["Welcome.", "   ", "Mind the wall."]);
+        let texts: Vec<&str> = mixed.segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(texts, ["Welcome.", "Mind the wall."]);
+    }
+
+    #[test]
+    fn the_typewriter_keeps_pace_at_any_speed() {
+        let frame = Duration::from_secs_f32(1.0 / 60.0);
+        let mut normal = TypewriterEffect::new("Café au lait".into(), DEFAULT_CHARS_PER_SECOND);
+        assert_eq!(normal.type_for(frame), "");
+        assert_eq!(normal.type_for(frame), "C", "a character every other frame at ~33/s");
+
+        let mut fast = TypewriterEffect::new("Café au lait".into(), 250.0);
+        assert_eq!(fast.type_for(frame), "Café", "several characters a frame, whole ones");
+        assert_eq!(fast.type_for(Duration::from_secs(1)), " au lait");
+        assert!(fast.is_complete());
+
+        let mut instant = TypewriterEffect::new("Café au lait".into(), 0.0);
+        assert_eq!(instant.type_for(Duration::ZERO), "Café au lait");
+        assert!(instant.is_complete());
+        assert_eq!(instant.type_for(frame), "");
+    }
+
+    #[test]
+    fn markup_becomes_styled_runs_and_pauses() {
+        let markup = parse_markup("The {red}error budget{/red} is {pause:0.5}gone.");
+        assert_eq!(markup.text, "The error budget is gone.");
+        let red = SpanStyle { color: Some(MARKUP_COLORS[0].1), bold: false };
+        let runs: Vec<_> = markup.runs.iter().map(|run| (&markup.text[run.range.clone()], run.style)).collect();
+        assert_eq!(runs, [("The ", SpanStyle::default()), ("error budget", red), (" is gone.", SpanStyle::default())]);
+        assert_eq!(markup.pauses, [(20, Duration::from_millis(500))]);
+        assert!(markup.problems.is_empty());
+
+        let nested = parse_markup("{b}{red}Down{/red} again{/b}");
+        let styles: Vec<_> = nested.runs.iter().map(|run| run.style).collect();
+        assert_eq!(styles, [SpanStyle { color: red.color, bold: true }, SpanStyle { color: None, bold: true }]);
+
+        assert_eq!(parse_markup("Plain line."), Markup::plain("Plain line.".into()));
+    }
+
+    #[test]
+    fn tags_that_cant_be_read_show_as_written() {
+        for line in ["Hello {blue}world", "{red}odd{/b}", "A {sparkle}", "Wait {pause:soon}", "{/red}"] {
+            let markup = parse_markup(line);
+            assert_eq!(markup.text, line);
+            assert!(!markup.problems.is_empty(), "{line}");
+        }
+        let half = parse_markup("{red}{b}half{/b}");
+        assert_eq!(half.text, "{red}half");
+        assert_eq!(half.problems, ["{red} is never closed"]);
+        assert!(half.runs.last().is_some_and(|run| run.style.bold && run.style.color.is_none()));
+    }
+
+    #[test]
+    fn a_pause_holds_the_typewriter_and_counts_no_characters() {
+        let markup = parse_markup("The {red}error budget{/red} is {pause:0.5}gone.");
+        let mut typewriter = TypewriterEffect::styled(markup, 10.0);
+        assert_eq!(typewriter.type_for(Duration::from_secs(10)), "The error budget is ");
+        assert_eq!(typewriter.type_for(Duration::from_millis(300)), "", "still pausing");
+        assert_eq!(typewriter.type_for(Duration::from_secs(1)), "gone.");
+        assert!(typewriter.is_complete());
+
+        let shown: Vec<&str> = typewriter.shown_runs().map(|(typed, _)| typed).collect();
+        assert_eq!(shown, ["The ", "error budget", " is gone."]);
+    }
+
+    #[test]
+    fn a_page_keeps_the_markup_that_falls_on_it() {
+        let markup = parse_markup("One {b}two{/b} {pause:1}three");
+        let pages = paginate(&markup.text, 7, 1);
+        assert_eq!(pages.len(), 2);
+        let first = markup.page(&pages[0]);
+        assert_eq!(first.text, "One two");
+        assert_eq!(first.runs.last().map(|run| (run.range.clone(), run.style.bold)), Some((4..7, true)));
+        let second = markup.page(&pages[1]);
+        assert_eq!(second.text, "three");
+        assert_eq!(second.pauses, [(0, Duration::from_secs(1))]);
+    }
+
+    #[test]
+    fn long_lines_wrap_at_spaces_and_page_by_rows() {
+        let text = "The pager went off at three in the morning, and nobody knew who owned the service.";
+        let pages = paginate(text, 24, 2);
+        let texts: Vec<&str> = pages.iter().map(|page| page.text.as_str()).collect();
+        assert_eq!(texts, [
+            "The pager went off at\nthree in the morning,",
+            "and nobody knew who\nowned the service.",
+        ]);
+        for page in &pages {
+            assert_eq!(page.text.len(), page.range.len(), "one byte for one byte");
+            assert_eq!(page.text.replace('\n', " "), text[page.range.clone()]);
+        }
+        assert_eq!(paginate(text, 20, 2).len(), 3);
+    }
+
+    #[test]
+    fn a_word_too_long_for_a_row_gets_a_row_to_itself() {
+        let pages = paginate("see https://status.example.com/incidents/42 now", 12, 3);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].text, "see\nhttps://status.example.com/incidents/42\nnow");
+
+        let authored = paginate("First.\nSecond line.", 40, 1);
+        let texts: Vec<&str> = authored.iter().map(|page| page.text.as_str()).collect();
+        assert_eq!(texts, ["First.", "Second line."], "authored newlines break rows");
+        assert_eq!(paginate("", 40, 3), [Page { range: 0..0, text: String::new() }]);
+    }
+
+    #[test]
+    fn long_words_break_after_a_separator_or_where_the_row_is_full() {
+        let markup = parse_markup("Read {b}https://sre.example.com/books/workbook/chapter-2{/b} now").break_long_words(20);
+        assert_eq!(markup.text, "Read https://sre.example.\ncom/books/workbook/\nchapter-2 now");
+        assert_eq!(markup.soft_breaks, [25, 45]);
+        let bold = markup.runs.iter().find(|run| run.style.bold).expect("the bold run");
+        assert_eq!(&markup.text[bold.range.clone()], "https://sre.example.\ncom/books/workbook/\nchapter-2");
+
+        let unbroken = Markup::plain("x".repeat(45)).break_long_words(20);
+        assert_eq!(unbroken.text.split('\n').map(str::len).collect::<Vec<_>>(), [20, 20, 5]);
+        assert_eq!(parse_markup("short words stay put").break_long_words(20).soft_breaks, []);
+    }
+
+    #[test]
+    fn soft_breaks_are_not_read() {
+        let url = format!("https://{}.example.com/", "a".repeat(100));
+        let markup = Markup::plain(url.clone()).break_long_words(BOX_ROW_CHARS);
+        let pages = paginate(&markup.text, BOX_ROW_CHARS, BOX_ROWS);
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].text.split('\n').all(|row| row.chars().count() <= BOX_ROW_CHARS), "{}", pages[0].text);
+
+        let mut typewriter = TypewriterEffect::styled(markup.page(&pages[0]), 0.0);
+        typewriter.reveal_rest();
+        assert_eq!(typewriter.read_chars(0..typewriter.full_text.len()), url.chars().count());
+    }
+
+    #[test]
+    fn a_long_url_breaks_to_fit_any_row() {
+        let url = format!("https://sre.example.com/{}", "a".repeat(96));
+        for row_chars in [BOX_ROW_CHARS, 86, 30, 17] {
+            let markup = Markup::plain(format!("Start at {url}")).break_long_words(row_chars);
+            let rows: Vec<&str> =
+                paginate(&markup.text, row_chars, BOX_ROWS).iter().flat_map(|page| page.text.split('\n')).collect();
+            assert!(rows.iter().all(|row| row.chars().count() <= row_chars), "{row_chars}: {rows:?}");
+            assert_eq!(rows.concat(), format!("Start at{url}"), "{row_chars}: nothing lost");
+        }
+
+        let fit = TextFit::for_line(&url, LongWords::Shrink);
+        assert_eq!((fit.scale, fit.row_chars), (MIN_TEXT_SCALE, 86));
+    }
+
+    #[test]
+    fn shrinking_fits_a_long_word_down_to_the_smallest_size() {
+        let url = "u".repeat(78);
+        let fit = TextFit::for_line(&format!("see {url}"), LongWords::Shrink);
+        assert_eq!((fit.scale, fit.row_chars, fit.rows), (BOX_ROW_CHARS as f32 / 78.0, 78, 4));
+        let huge = TextFit::for_line(&"u".repeat(500), LongWords::Shrink);
+        assert_eq!(huge.scale, MIN_TEXT_SCALE, "no smaller than the minimum - the rest is broken");
+        assert_eq!(TextFit::for_line(&url, LongWords::Break).scale, 1.0);
+        assert_eq!(TextFit::for_line("fits", LongWords::Shrink).row_chars, BOX_ROW_CHARS);
+    }
+
+    #[test]
+    fn choices_wait_for_the_last_page_of_their_line() {
+        let long = "word ".repeat(BOX_ROW_CHARS * BOX_ROWS / 5 + 10);
+        let mut request: DialogueRequest = ("Isabella", vec![long.trim_end().to_string(), "Done.".into()]).into();
+        request.branches = BTreeMap::from([(0, DialogueBranch {
+            choices: vec![BranchChoice { label: "Go on".into(), target: 1 }],
+            end: false,
+        })]);
+        let mut queue = DialogueQueue::new(&request, &SeenDialogues::default());
+        assert!(!queue.on_last_page());
+        assert!(queue.choices().is_empty());
+        assert!(queue.next_page());
+        assert!(queue.on_last_page() && !queue.next_page());
+        assert_eq!(queue.choices().len(), 1);
+        queue.choose();
+        assert_eq!(queue.page_text().as_deref(), Some("Done."), "a new line starts on its first page");
+    }
+
+    #[test]
+    fn a_lines_pages_are_kept_until_what_they_fit_changes() {
+        let url = format!("see https://sre.google/{}", "u".repeat(80));
+        let request: DialogueRequest = ("Isabella", vec![url.clone(), "Done.".into()]).into();
+        let mut queue = DialogueQueue::new(&request, &SeenDialogues::default());
+        let broken = queue.page_text();
+        assert_eq!(queue.page_cache.get_mut().unwrap().as_ref().map(|cache| cache.line), Some(0));
+
+        queue.long_words = LongWords::Shrink;
+        assert_ne!(queue.page_text(), broken, "shrunk, not broken");
+        queue.forget_pages();
+        assert!(queue.page_cache.get_mut().unwrap().is_none());
+        queue.segments[0].text = "Rewritten.".into();
+        assert_eq!(queue.page_text().as_deref(), Some("Rewritten."));
+        queue.current = 1;
+        assert_eq!(queue.page_text().as_deref(), Some("Done."));
+    }
+
+    #[test]
+    fn reading_time_runs_from_whole_on_screen_to_the_next_press() {
+        let secs = Duration::from_secs_f32;
+        let mut clock = ReadingClock::default();
+        assert_eq!(clock.moved_on(secs(1.0)), None, "a press before the box is whole reads nothing");
+        assert_eq!(clock.reading_speed(), None);
+
+        clock.shown(secs(2.0), 20);
+        assert_eq!(clock.moved_on(secs(4.0)), Some((secs(2.0), 20)));
+        assert_eq!(clock.moved_on(secs(5.0)), None, "a box is left once");
+        clock.shown(secs(6.0), 10);
+        clock.moved_on(secs(7.0));
+        assert_eq!((clock.dwell, clock.chars), (secs(3.0), 30));
+        assert_eq!(clock.reading_speed(), Some(10.0));
+    }
+
+    /// Isabella's "Which team?": a choice on box 0, Disco (1) ending the
+    /// conversation, Marathon (3) after a blank box.
+    fn branching_queue() -> DialogueQueue {
+        let mut request: DialogueRequest = (
+            "Isabella",
+            ["Which team?", "Disco!", "", "Marathon!"].map(String::from).to_vec(),
+        )
+            .into();
+        let choice = |label: &str, target| BranchChoice { label: label.into(), target };
+        request.branches = BTreeMap::from([
+            (0, DialogueBranch { choices: vec![choice("Disco", 1), choice("Marathon", 2)], end: false }),
+            (1, DialogueBranch { choices: Vec::new(), end: true }),
+        ]);
+        DialogueQueue::new(&request, &SeenDialogues::default())
+    }
+
+    #[test]
+    fn choices_jump_and_ending_lines_end() {
+        let mut queue = branching_queue();
+        assert_eq!(queue.choices().len(), 2);
+        queue.move_choice(1);
+        queue.move_choice(1);
+        assert_eq!(queue.choice_cursor(), 0, "the highlight wraps");
+        let choice = queue.choose().expect("box 0 branches");
+        assert_eq!(choice.label, "Disco");
+        assert_eq!(queue.current_segment().map(|segment| segment.text.as_str()), Some("Disco!"));
+        assert!(queue.choose().is_none(), "Disco offers no choices");
+        assert!(!queue.advance(), "and ends the conversation");
+
+        let mut queue = branching_queue();
+        queue.move_choice(-1);
+        queue.choose();
+        assert_eq!(
+            queue.current_segment().map(|segment| segment.text.as_str()),
+            Some("Marathon!"),
+            "a choice aimed at a dropped blank box lands on the next one"
+        );
+        assert!(!queue.advance());
+    }
+
+    #[test]
+    fn linear_conversations_never_offer_choices() {
+        let request: DialogueRequest = ("Isabella", vec!["Welcome.".to_string(), "Bye.".to_string()]).into();
+        let mut queue = DialogueQueue::new(&request, &SeenDialogues::default());
+        assert!(queue.choices().is_empty());
+        assert!(queue.choose().is_none());
+        assert!(queue.advance());
+        assert!(!queue.advance());
+    }
+
+    fn menu(topics: usize) -> TopicMenu {
+        let topics: Vec<DialogueTopic> = (0..topics)
+            .map(|i| DialogueTopic {
+                id: format!("t{i}"),
+                label: format!("Topic {i}"),
+                lines: vec![format!("About {i}.")],
+                requires_flag: None,
+            })
+            .collect();
+        TopicMenu::new(&topics, &segment("Mentor", "Ask away."))
+    }
+
+    #[test]
+    fn topic_menus_scroll_only_past_eight_entries() {
+        // One topic plus Goodbye, seven plus Goodbye: everything fits.
+        for topics in [1, 7] {
+            let mut menu = menu(topics);
+            for _ in 0..=topics {
+                menu.move_cursor(1);
+                assert_eq!(menu.window(), 0..topics + 1);
+            }
+        }
+
+        // Twelve topics plus Goodbye: the window follows the cursor.
+        let mut menu = menu(12);
+        assert_eq!(menu.window(), 0..8);
+        for _ in 0..8 {
+            menu.move_cursor(1);
+        }
+        assert_eq!(menu.cursor(), 8);
+        assert_eq!(menu.window(), 1..9);
+        menu.move_cursor(-1);
+        assert_eq!(menu.window(), 1..9, "moving back inside the window doesn't scroll");
+
+        // Wrapping up from the top lands on Goodbye, last page showing.
+        let mut menu = self::menu(12);
+        menu.move_cursor(-1);
+        assert_eq!(menu.cursor(), 12);
+        assert_eq!(menu.window(), 5..13);
+        assert_eq!(menu.entries(&SeenDialogues::default())[12].0, GOODBYE_LABEL);
+    }
+
+    #[test]
+    fn a_topic_read_to_the_end_is_marked_read() {
+        let mut menu = menu(2);
+        assert_eq!(menu.reopen(), None, "the greeting isn't a topic");
+        menu.move_cursor(1);
+        let TopicChoice::Topic { id, segments } = menu.choose() else {
+            panic!("the cursor is on a topic");
+        };
+        assert_eq!(id, "t1");
+        assert_eq!(segments[0].speaker, "Mentor", "voiced like the greeting");
+        assert!(!menu.is_open());
+
+        let mut seen = SeenDialogues::default();
+        seen.insert(menu.reopen().expect("t1 was being read"));
+        assert!(menu.is_open());
+        let read: Vec<bool> = menu.entries(&seen).into_iter().map(|(_, read)| read).collect();
+        assert_eq!(read, [false, true, false]);
+        assert_eq!(menu.visited(), ["t1"]);
+
+        menu.move_cursor(1);
+        assert!(matches!(menu.choose(), TopicChoice::Goodbye));
+    }
+}
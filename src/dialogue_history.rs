@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+use crate::assets::GameAssets;
+use crate::dialogue::SKIP_HOLD;
+use crate::game_state::{GameState, Mode};
+use crate::glyphs::InputPrompt;
+use crate::input::{GameAction, GameInput};
+use crate::mood::{NEUTRAL_NAME_COLOR, PLAYER_NAME_COLOR};
+use crate::player::PLAYER_NAME;
+
+/// The dialogue log: every line shown this play session, speaker and all,
+/// for the player who pressed Space once too often. In a conversation, L
+/// (or a tap of Tab - holding it still skips a read conversation) opens the
+/// last lines over the box, W/S or the arrows scroll back, and L or Tab
+/// again closes it.
+///
+/// Lines are recorded as they finish showing (dialogue.rs), typed out or
+/// revealed, whole rather than by page. While the log is open the
+/// conversation waits underneath - its systems don't run - so closing it
+/// picks the typewriter up where it stopped.
+pub struct DialogueHistoryPlugin;
+
+impl Plugin for DialogueHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        // init, not insert: main.rs's `--history-lines` wins.
+        app.init_resource::<DialogueHistory>()
+            .add_systems(OnEnter(GameState::Playing), clear_history)
+            .add_systems(
+                Update,
+                (toggle_history_log, scroll_history_log, sync_history_log)
+                    .chain()
+                    .run_if(in_state(Mode::Dialogue)),
+            )
+            .add_systems(OnExit(Mode::Dialogue), close_history_log);
+    }
+}
+
+/// How many lines the log keeps unless told otherwise.
+pub const DEFAULT_HISTORY_LINES: usize = 200;
+
+/// Rows the open log shows at once.
+pub const HISTORY_LOG_ROWS: usize = 8;
+
+/// One line as it was shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub speaker: String,
+    pub line: String,
+}
+
+/// The lines shown this play session, oldest first, the oldest dropped
+/// past `capacity`. Emptied when a new session starts (entering
+/// `GameState::Playing`).
+#[derive(Resource, Debug, Clone)]
+pub struct DialogueHistory {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+}
+
+impl Default for DialogueHistory {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_HISTORY_LINES)
+    }
+}
+
+impl DialogueHistory {
+    /// A log keeping the last `capacity` lines; 0 keeps none.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { entries: VecDeque::new(), capacity }
+    }
+
+    pub fn record(&mut self, speaker: &str, line: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry { speaker: speaker.to_string(), line: line.to_string() });
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Present while the log is open.
+#[derive(Resource, Debug, Default)]
+pub struct HistoryLog {
+    /// Lines scrolled back from the newest.
+    pub scroll: usize,
+}
+
+/// The entries `rows` rows show, `scroll` lines back from the newest of
+/// `len`. Scrolling stops with the oldest line on the top row.
+pub fn history_window(len: usize, scroll: usize, rows: usize) -> Range<usize> {
+    let end = len - scroll.min(len.saturating_sub(rows));
+    end.saturating_sub(rows)..end
+}
+
+#[derive(Component)]
+struct HistoryLogRoot;
+
+/// A new play session starts with nothing said.
+fn clear_history(mut history: ResMut<DialogueHistory>) {
+    history.clear();
+}
+
+fn toggle_history_log(
+    mut commands: Commands,
+    keyboard: GameInput,
+    time: Res<Time>,
+    log: Option<Res<HistoryLog>>,
+    mut tab_held: Local<Option<Duration>>,
+) {
+    // A tap of Tab toggles; a hold is skip_seen_dialogue's (dialogue.rs).
+    let mut tapped = false;
+    if keyboard.pressed(KeyCode::Tab) {
+        *tab_held = Some(tab_held.unwrap_or_default() + time.delta());
+    } else if let Some(held) = tab_held.take() {
+        tapped = held < SKIP_HOLD;
+    }
+    if !tapped && !keyboard.just_pressed(GameAction::History.default_key()) {
+        return;
+    }
+    if log.is_some() {
+        commands.remove_resource::<HistoryLog>();
+    } else {
+        commands.insert_resource(HistoryLog::default());
+    }
+}
+
+fn scroll_history_log(keyboard: GameInput, history: Res<DialogueHistory>, log: Option<ResMut<HistoryLog>>) {
+    let Some(mut log) = log else { return };
+    let up = keyboard.just_pressed(KeyCode::KeyW) || keyboard.just_pressed(KeyCode::ArrowUp);
+    let down = keyboard.just_pressed(KeyCode::KeyS) || keyboard.just_pressed(KeyCode::ArrowDown);
+    let oldest = history.len().saturating_sub(HISTORY_LOG_ROWS);
+    let scroll = match (up, down) {
+        (true, false) => (log.scroll + 1).min(oldest),
+        (false, true) => log.scroll.saturating_sub(1),
+        _ => return,
+    };
+    if scroll != log.scroll {
+        log.scroll = scroll;
+    }
+}
+
+/// Rebuilds the panel when it opens or scrolls; it's a handful of rows.
+fn sync_history_log(
+    mut commands: Commands,
+    log: Option<Res<HistoryLog>>,
+    history: Res<DialogueHistory>,
+    roots: Query<Entity, With<HistoryLogRoot>>,
+    game_assets: Option<Res<GameAssets>>,
+) {
+    if log.as_ref().is_some_and(|log| !log.is_changed() && !history.is_changed() && !roots.is_empty()) {
+        return;
+    }
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+    let Some(log) = log else { return };
+
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    // At 1080p: 36px lines, 24px hint - Vh like the dialogue box.
+    let text = |px: f32| TextFont { font: font.clone().into(), font_size: FontSize::Vh(px / 10.8), ..default() };
+    let window = history_window(history.len(), log.scroll, HISTORY_LOG_ROWS);
+    let (older, newer) = (window.start, history.len() - window.end);
+
+    commands
+        .spawn((
+            HistoryLogRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(6.0),
+                bottom: Val::Percent(6.0),
+                left: Val::Percent(8.0),
+                right: Val::Percent(8.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                border: UiRect::all(Val::Px(3.0)),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.97)),
+            BorderColor::all(Color::WHITE),
+            // Over the dialogue box it pauses.
+            GlobalZIndex(10),
+        ))
+        .with_children(|panel| {
+            let dim = Color::srgba(1.0, 1.0, 1.0, 0.6);
+            let more = |count: usize, direction: &str| match count {
+                0 => String::new(),
+                count => format!("{count} {direction}"),
+            };
+            panel.spawn((Text::new(more(older, "earlier")), text(24.0), TextColor(dim)));
+            if history.is_empty() {
+                panel.spawn((Text::new("Nothing said yet."), text(36.0), TextColor(dim)));
+            }
+            for entry in history.entries().skip(window.start).take(window.len()) {
+                let name_color = if entry.speaker == PLAYER_NAME { PLAYER_NAME_COLOR } else { NEUTRAL_NAME_COLOR };
+                panel
+                    .spawn((Text::new(format!("{}: ", entry.speaker)), text(36.0), TextColor(name_color)))
+                    .with_child((TextSpan::new(entry.line.clone()), text(36.0), TextColor(Color::WHITE)));
+            }
+            panel.spawn((Text::new(more(newer, "later")), text(24.0), TextColor(dim)));
+            panel.spawn((
+                InputPrompt::new("{move}: scroll   L: back to the conversation", font.clone(), 24.0, dim),
+                Node { align_items: AlignItems::Center, margin: UiRect::top(Val::Auto), ..default() },
+            ));
+        });
+}
+
+fn close_history_log(mut commands: Commands, roots: Query<Entity, With<HistoryLogRoot>>) {
+    commands.remove_resource::<HistoryLog>();
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_log_keeps_the_newest_lines_up_to_its_capacity() {
+        let mut history = DialogueHistory::with_capacity(3);
+        for line in ["one", "two", "three", "four"] {
+            history.record("Isabella", line);
+        }
+        let lines: Vec<&str> = history.entries().map(|entry| entry.line.as_str()).collect();
+        assert_eq!(lines, ["two", "three", "four"]);
+
+        let mut off = DialogueHistory::with_capacity(0);
+        off.record("Isabella", "one");
+        assert!(off.is_empty());
+    }
+
+    #[test]
+    fn scrolling_stops_at_the_oldest_line() {
+        assert_eq!(history_window(20, 0, 8), 12..20);
+        assert_eq!(history_window(20, 5, 8), 7..15);
+        assert_eq!(history_window(20, 50, 8), 0..8);
+        assert_eq!(history_window(3, 2, 8), 0..3);
+        assert_eq!(history_window(0, 0, 8), 0..0);
+    }
+}
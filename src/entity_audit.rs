@@ -0,0 +1,218 @@
+use bevy::ecs::resource::IsResource;
+use bevy::prelude::*;
+use bevy::state::state::{StateTransition, StateTransitionEvent, StateTransitionSystems};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use crate::game_state::Scene;
+use crate::tilemap::Map;
+
+/// Entity leak hunting. Entity counts crept up over long sessions before
+/// (NPCs outliving their map is the known case - see tilemap.rs), so this
+/// keeps a periodic census grouped by *marker fingerprint*: the zero-sized
+/// components an entity carries (`Map`, `Npc`-adjacent markers, `Toast`, ...),
+/// which name what an entity is far better than its data components do.
+///
+/// The census always runs (cheap: one pass over archetypes every
+/// `CENSUS_INTERVAL`) so the F3 overlay can show totals; `--audit-entities`
+/// inserts `EntityAudit`, which also logs every group that changed. Scene
+/// exit is checked every time: once the old scene's OnExit has run, no `Map`
+/// entity may remain.
+pub struct EntityAuditPlugin;
+
+impl Plugin for EntityAuditPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityCensus>()
+            .add_systems(Update, take_periodic_census)
+            .add_systems(
+                StateTransition,
+                check_scene_scoped_entities_cleared
+                    .after(StateTransitionSystems::ExitSchedules)
+                    .before(StateTransitionSystems::EnterSchedules),
+            );
+    }
+}
+
+const CENSUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Present when launched with `--audit-entities`: log census deltas.
+#[derive(Resource, Default)]
+pub struct EntityAudit;
+
+/// Entity counts by marker fingerprint at one instant.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Census {
+    pub total: usize,
+    pub groups: BTreeMap<String, usize>,
+}
+
+/// Entities with no zero-sized components group under this name.
+pub const UNMARKED: &str = "(unmarked)";
+
+impl Census {
+    pub fn take(world: &World) -> Self {
+        // Resources live on entities too; they aren't what leaks.
+        let resource_marker = world.components().component_id::<IsResource>();
+        let mut census = Census::default();
+        for archetype in world.archetypes().iter() {
+            if archetype.is_empty()
+                || resource_marker.is_some_and(|id| archetype.contains(id))
+            {
+                continue;
+            }
+            let mut markers: Vec<String> = archetype
+                .components()
+                .iter()
+                .filter_map(|&id| world.components().get_info(id))
+                .filter(|info| info.layout().size() == 0)
+                .map(|info| info.name().shortname().to_string())
+                .collect();
+            markers.sort();
+            let fingerprint = if markers.is_empty() {
+                UNMARKED.to_string()
+            } else {
+                markers.join("+")
+            };
+            let count = archetype.len() as usize;
+            census.total += count;
+            *census.groups.entry(fingerprint).or_default() += count;
+        }
+        census
+    }
+
+    /// Per-group change from `earlier` to `self`, non-zero entries only.
+    pub fn delta(&self, earlier: &Census) -> BTreeMap<String, i64> {
+        self.groups
+            .keys()
+            .chain(earlier.groups.keys())
+            .filter_map(|group| {
+                let now = self.groups.get(group).copied().unwrap_or(0) as i64;
+                let then = earlier.groups.get(group).copied().unwrap_or(0) as i64;
+                (now != then).then(|| (group.clone(), now - then))
+            })
+            .collect()
+    }
+}
+
+/// Latest census plus the first one of the session, for "what grew".
+#[derive(Resource, Default)]
+pub struct EntityCensus {
+    pub baseline: Option<Census>,
+    pub latest: Option<Census>,
+    since_last: Duration,
+}
+
+impl EntityCensus {
+    /// The group that has grown the most since the session's first census.
+    pub fn largest_growth(&self) -> Option<(String, i64)> {
+        let (baseline, latest) = (self.baseline.as_ref()?, self.latest.as_ref()?);
+        latest
+            .delta(baseline)
+            .into_iter()
+            .filter(|(_, growth)| *growth > 0)
+            .max_by_key(|(_, growth)| *growth)
+    }
+}
+
+fn take_periodic_census(world: &mut World) {
+    let delta = world.resource::<Time>().delta();
+    let due = {
+        let mut census = world.resource_mut::<EntityCensus>();
+        census.since_last += delta;
+        let due = census.latest.is_none() || census.since_last >= CENSUS_INTERVAL;
+        if due {
+            census.since_last = Duration::ZERO;
+        }
+        due
+    };
+    if !due {
+        return;
+    }
+
+    let snapshot = Census::take(world);
+    let audit = world.contains_resource::<EntityAudit>();
+    let mut census = world.resource_mut::<EntityCensus>();
+    if audit && let Some(previous) = &census.latest {
+        let changes = snapshot.delta(previous);
+        if !changes.is_empty() {
+            info!("🔎 Entities: {} total ({:+})", snapshot.total, snapshot.total as i64 - previous.total as i64);
+            for (group, change) in changes {
+                info!("🔎   {group}: {change:+}");
+            }
+        }
+    }
+    if census.baseline.is_none() {
+        census.baseline = Some(snapshot.clone());
+    }
+    census.latest = Some(snapshot);
+}
+
+/// Runs between the exit and enter schedules of a transition: OnExit has
+/// despawned the old scene and OnEnter hasn't spawned the new one yet, so
+/// any `Map` entity still alive escaped despawn_map. Test builds (unit and
+/// `--features testing`) fail hard; a playtest build just logs.
+fn check_scene_scoped_entities_cleared(
+    mut transitions: MessageReader<StateTransitionEvent<Scene>>,
+    leftovers: Query<Entity, With<Map>>,
+) {
+    let Some(transition) = transitions.read().last() else { return };
+    let Some(exited) = transition.exited else { return };
+    if transition.entered == Some(exited) {
+        return;
+    }
+    let count = leftovers.iter().count();
+    if count == 0 {
+        return;
+    }
+    let message = format!("{count} scene-scoped (Map) entities survived leaving {exited:?}");
+    if cfg!(any(test, feature = "testing")) {
+        panic!("{message}");
+    }
+    error!("❌ {message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Marker;
+
+    #[derive(Component)]
+    struct Data(#[allow(dead_code)] u32);
+
+    #[test]
+    fn census_groups_by_zero_sized_markers() {
+        let mut world = World::new();
+        world.spawn((Marker, Data(1)));
+        world.spawn((Marker, Data(2)));
+        world.spawn(Marker);
+        world.spawn(Data(3));
+
+        let census = Census::take(&world);
+        assert_eq!(census.total, 4);
+        assert_eq!(census.groups.get("Marker"), Some(&3), "data components don't split groups");
+        assert_eq!(census.groups.get(UNMARKED), Some(&1));
+    }
+
+    #[test]
+    fn delta_and_largest_growth_report_what_grew() {
+        let mut world = World::new();
+        world.spawn(Marker);
+        let baseline = Census::take(&world);
+        world.spawn(Marker);
+        world.spawn(Marker);
+        world.spawn(Data(0));
+        let latest = Census::take(&world);
+
+        let delta = latest.delta(&baseline);
+        assert_eq!(delta.get("Marker"), Some(&2));
+        assert_eq!(delta.get(UNMARKED), Some(&1));
+
+        let census = EntityCensus {
+            baseline: Some(baseline),
+            latest: Some(latest),
+            ..default()
+        };
+        assert_eq!(census.largest_growth(), Some(("Marker".to_string(), 2)));
+    }
+}
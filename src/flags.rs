@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use crate::dialogue::{DialogueCompleted, DialogueOutcome};
+use crate::game_events::{GameEvent, GameEvents};
+use crate::game_state::GameState;
+use crate::map_data::NpcData;
+
+/// Story flags: named facts about what the player has done ("met_isabella").
+/// Set by dialogue outcomes (`{"set_flag": ...}`), by `SetFlagEvent`s - a
+/// line's `set_flag` action sends one as it's read past - and read by
+/// whatever gates on them: NPC entries with `requires_flag`
+/// (npc_spawning.rs), hub topics, save progress.
+/// Systems that care run on `resource_changed::<GameFlags>`; insert and
+/// clear through `GameFlags` methods so a no-op set doesn't trip that.
+pub struct FlagsPlugin;
+
+impl Plugin for FlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameFlags>()
+            .add_message::<DialogueCompleted>()
+            .add_message::<SetFlagEvent>()
+            .add_systems(OnEnter(GameState::Playing), clear_flags)
+            .add_systems(Update, (apply_flag_outcomes, apply_set_flag_events));
+    }
+}
+
+/// Set a story flag now.
+#[derive(Message, Debug, Clone)]
+pub struct SetFlagEvent {
+    pub flag: String,
+}
+
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameFlags {
+    set: BTreeSet<String>,
+}
+
+impl GameFlags {
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.set.contains(flag)
+    }
+
+    /// Whether the flag was newly set.
+    pub fn set(&mut self, flag: impl Into<String>) -> bool {
+        self.set.insert(flag.into())
+    }
+
+    /// Whether the flag was set before.
+    pub fn clear(&mut self, flag: &str) -> bool {
+        self.set.remove(flag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.set.iter().map(String::as_str)
+    }
+
+    /// Whether an NPC entry's `requires_flag` (if any) is satisfied.
+    pub fn allows(&self, npc: &NpcData) -> bool {
+        npc.requires_flag.as_deref().is_none_or(|flag| self.is_set(flag))
+    }
+}
+
+impl FromIterator<String> for GameFlags {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self { set: iter.into_iter().collect() }
+    }
+}
+
+/// A new play session starts with nothing done.
+fn clear_flags(mut flags: ResMut<GameFlags>) {
+    flags.set_if_neq(GameFlags::default());
+}
+
+fn apply_flag_outcomes(
+    mut completions: MessageReader<DialogueCompleted>,
+    mut flags: ResMut<GameFlags>,
+    mut events: Option<ResMut<GameEvents>>,
+) {
+    for completed in completions.read() {
+        for outcome in &completed.outcomes {
+            let DialogueOutcome::SetFlag(flag) = outcome else { continue };
+            set_flag(&mut flags, events.as_deref_mut(), flag);
+        }
+    }
+}
+
+fn apply_set_flag_events(
+    mut requests: MessageReader<SetFlagEvent>,
+    mut flags: ResMut<GameFlags>,
+    mut events: Option<ResMut<GameEvents>>,
+) {
+    for request in requests.read() {
+        set_flag(&mut flags, events.as_deref_mut(), &request.flag);
+    }
+}
+
+fn set_flag(flags: &mut ResMut<GameFlags>, events: Option<&mut GameEvents>, flag: &str) {
+    // Only borrow mutably for a real change, or every conversation that
+    // re-sets a flag would re-run the flag-gated systems.
+    if !flags.is_set(flag) {
+        flags.set(flag);
+        info!("🚩 Flag set: {flag}");
+        if let Some(events) = events {
+            events.publish(GameEvent::FlagSet { flag: flag.to_string() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_clear_report_whether_anything_changed() {
+        let mut flags = GameFlags::default();
+        assert!(flags.set("met_isabella"));
+        assert!(!flags.set("met_isabella"), "already set");
+        assert!(flags.is_set("met_isabella"));
+        assert_eq!(flags.iter().collect::<Vec<_>>(), ["met_isabella"]);
+        assert!(flags.clear("met_isabella"));
+        assert!(!flags.clear("met_isabella"));
+        assert!(!flags.is_set("met_isabella"));
+    }
+
+    #[test]
+    fn completed_dialogue_sets_its_flags() {
+        let mut app = App::new();
+        app.add_message::<DialogueCompleted>()
+            .add_plugins(FlagsPlugin);
+        app.world_mut().write_message(DialogueCompleted {
+            id: "0".into(),
+            skipped: false,
+            outcomes: vec![
+                DialogueOutcome::SpawnNpc("Vendor".into()),
+                DialogueOutcome::SetFlag("met_isabella".into()),
+            ],
+        });
+        app.update();
+        let flags = app.world().resource::<GameFlags>();
+        assert_eq!(flags.iter().collect::<Vec<_>>(), ["met_isabella"]);
+    }
+
+    #[test]
+    fn a_set_flag_event_sets_it() {
+        let mut app = App::new();
+        app.add_plugins(FlagsPlugin);
+        app.world_mut().write_message(SetFlagEvent { flag: "met_casey".into() });
+        app.update();
+        assert!(app.world().resource::<GameFlags>().is_set("met_casey"));
+    }
+}
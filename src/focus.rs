@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use opentelemetry::KeyValue;
+use opentelemetry::trace::Span as _;
+use std::time::Duration;
+use crate::instrumentation::PlayerSessionTrace;
+use crate::settings::GameSettings;
+
+/// Time away from the window. A minimized game left running overnight
+/// shouldn't age its NPCs' chatter timers or fill the dashboards with
+/// idle frames, so losing focus:
+///
+/// - adds a `game.focus_lost` event to the session span, and regaining it
+///   a `game.focus_gained` event with `focus.gap_ms`; the span's
+///   `session.unfocused_ms` and `session.unfocused_count` attributes keep
+///   the running total, for duration analyses to subtract;
+/// - pauses the game clock (`Time<Virtual>`) - walking NPCs, ambient
+///   chatter, every timer - unless `GameSettings::pause_when_unfocused` is
+///   off;
+/// - stops frame-time sampling (the dashboard's, and stall reports - see
+///   `recording_frames`) unless `GameSettings::frame_metrics_when_unfocused`
+///   is on.
+///
+/// Gaps are measured on `Time<Real>`, which keeps running whatever the
+/// game clock does.
+pub struct WindowFocusPlugin;
+
+impl Plugin for WindowFocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<WindowFocused>()
+            .init_resource::<WindowFocus>()
+            .init_resource::<GameSettings>()
+            .add_systems(PreUpdate, track_window_focus);
+    }
+}
+
+/// Whether the window has focus, and the unfocused time so far.
+#[derive(Resource, Debug, Default)]
+pub struct WindowFocus {
+    /// `Time<Real>::elapsed` when focus went; `None` while focused.
+    unfocused_since: Option<Duration>,
+    /// Finished gaps, summed.
+    excluded: Duration,
+    gaps: u32,
+    /// The clock was paused here, so it's this to unpause.
+    paused_clock: bool,
+}
+
+impl WindowFocus {
+    pub fn is_focused(&self) -> bool {
+        self.unfocused_since.is_none()
+    }
+
+    /// Real time spent unfocused as of `now`, the gap in progress included.
+    pub fn unfocused_time(&self, now: Duration) -> Duration {
+        self.excluded + self.unfocused_since.map_or(Duration::ZERO, |since| now.saturating_sub(since))
+    }
+
+    /// Times focus has come back.
+    pub fn gaps(&self) -> u32 {
+        self.gaps
+    }
+
+    /// Focus went at `now`. False if it was already gone.
+    pub fn lose(&mut self, now: Duration) -> bool {
+        if self.unfocused_since.is_some() {
+            return false;
+        }
+        self.unfocused_since = Some(now);
+        true
+    }
+
+    /// Focus came back at `now`: how long it was gone, `None` if it wasn't.
+    pub fn regain(&mut self, now: Duration) -> Option<Duration> {
+        let gap = now.saturating_sub(self.unfocused_since.take()?);
+        self.excluded += gap;
+        self.gaps += 1;
+        Some(gap)
+    }
+}
+
+/// Run condition for frame-time sampling: off while the window is
+/// unfocused, unless `GameSettings::frame_metrics_when_unfocused`.
+pub fn recording_frames(focus: Option<Res<WindowFocus>>, settings: Option<Res<GameSettings>>) -> bool {
+    focus.is_none_or(|focus| focus.is_focused())
+        || settings.is_some_and(|settings| settings.frame_metrics_when_unfocused)
+}
+
+/// One window, so the frame's last focus change is the one that counts.
+fn track_window_focus(
+    mut changes: MessageReader<WindowFocused>,
+    mut focus: ResMut<WindowFocus>,
+    settings: Res<GameSettings>,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut sessions: Query<&mut PlayerSessionTrace>,
+) {
+    let Some(focused) = changes.read().last().map(|change| change.focused) else { return };
+    let now = real_time.elapsed();
+    let mut session = sessions.single_mut().ok();
+
+    if !focused {
+        if !focus.lose(now) {
+            return;
+        }
+        info!("💤 Window lost focus");
+        if settings.pause_when_unfocused && !virtual_time.is_paused() {
+            virtual_time.pause();
+            focus.paused_clock = true;
+        }
+        if let Some(session) = &mut session {
+            let elapsed_ms = session.session_start.elapsed().as_millis() as i64;
+            session.span.add_event("game.focus_lost", vec![KeyValue::new("session.elapsed_ms", elapsed_ms)]);
+        }
+        return;
+    }
+
+    let Some(gap) = focus.regain(now) else { return };
+    info!("👀 Window focused again after {:.1}s", gap.as_secs_f64());
+    if std::mem::take(&mut focus.paused_clock) {
+        virtual_time.unpause();
+    }
+    if let Some(session) = &mut session {
+        session.span.add_event("game.focus_gained", vec![KeyValue::new("focus.gap_ms", gap.as_millis() as i64)]);
+        session.span.set_attribute(KeyValue::new("session.unfocused_ms", focus.unfocused_time(now).as_millis() as i64));
+        session.span.set_attribute(KeyValue::new("session.unfocused_count", focus.gaps() as i64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfocused_time_counts_each_gap_once() {
+        let at = Duration::from_secs;
+        let mut focus = WindowFocus::default();
+        assert_eq!(focus.regain(at(1)), None, "focused already");
+
+        assert!(focus.lose(at(10)));
+        assert!(!focus.lose(at(12)), "a second loss doesn't restart the gap");
+        assert_eq!(focus.unfocused_time(at(15)), at(5), "the gap so far");
+        assert_eq!(focus.regain(at(20)), Some(at(10)));
+
+        assert!(focus.lose(at(30)));
+        assert_eq!(focus.regain(at(33)), Some(at(3)));
+        assert_eq!((focus.unfocused_time(at(100)), focus.gaps()), (at(13), 2));
+        assert!(focus.is_focused());
+    }
+}
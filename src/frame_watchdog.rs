@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::{KeyValue, trace::{Span as _, Tracer}};
+use std::time::{Duration, SystemTime};
+use crate::chaos::Chaos;
+use crate::game_state::{GameState, Mode, Scene};
+use crate::instrumentation::{GameTracer, MetricsBundle, init_metrics};
+
+/// Frame stall watchdog: when a frame takes longer than
+/// `FrameWatchdog::threshold` (100ms by default, `--stall-threshold-ms`),
+/// record a `game.frame.stall` span backdated to cover that frame, with
+/// the game/scene/mode states and the entity count, and bump the
+/// `game.frame.stalls` counter.
+///
+/// Frame time is `Time<Real>`, not the virtual clock: virtual time is
+/// clamped (250ms max delta) and pausable, so exactly the frames worth
+/// reporting would be understated.
+///
+/// Every stall is counted, but spans are rate-limited to one per
+/// `cooldown` - a stall that causes a burst of exports shouldn't be able
+/// to stall the next frame and report itself forever. Stalls swallowed by
+/// the cooldown are attached to the next span as `frame.suppressed`.
+///
+/// A stall that a `--chaos frame_spikes` spike caused (chaos.rs) is tagged
+/// `chaos.scenario`, on the span and the counter alike.
+///
+/// Nothing is watched while the window is unfocused (see focus.rs): a
+/// minimized window's throttled frames aren't the game stalling.
+///
+/// There's no per-system profiler in this tree, so the span can't name the
+/// slowest systems yet; the state attributes narrow it to a map and mode.
+pub struct FrameWatchdogPlugin;
+
+impl Plugin for FrameWatchdogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameWatchdog>()
+            .add_systems(Last, watch_frame_time.run_if(crate::focus::recording_frames));
+        init_metrics::<FrameMetrics>(app);
+    }
+}
+
+/// `game.frame.stalls`, tagged when chaos caused the stall.
+#[derive(Resource)]
+pub struct FrameMetrics {
+    pub stalls: Counter<u64>,
+}
+
+impl MetricsBundle for FrameMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            stalls: meter
+                .u64_counter("game.frame.stalls")
+                .with_description("Frames over the stall threshold (see frame_watchdog.rs)")
+                .build(),
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct FrameWatchdog {
+    pub threshold: Duration,
+    /// Minimum real time between two stall spans.
+    pub cooldown: Duration,
+    /// `Time<Real>::elapsed` of the last reported stall.
+    last_report: Option<Duration>,
+    suppressed: u64,
+}
+
+impl FrameWatchdog {
+    pub const DEFAULT_THRESHOLD: Duration = Duration::from_millis(100);
+
+    pub fn with_threshold(threshold: Duration) -> Self {
+        Self { threshold, ..default() }
+    }
+
+    /// Whether a stall seen at `now` gets a span; a stall that doesn't is
+    /// counted toward the next one's `frame.suppressed`.
+    fn should_report(&mut self, now: Duration) -> bool {
+        let due = self
+            .last_report
+            .is_none_or(|last| now.saturating_sub(last) >= self.cooldown);
+        if due {
+            self.last_report = Some(now);
+        } else {
+            self.suppressed += 1;
+        }
+        due
+    }
+}
+
+impl Default for FrameWatchdog {
+    fn default() -> Self {
+        Self {
+            threshold: Self::DEFAULT_THRESHOLD,
+            cooldown: Duration::from_secs(10),
+            last_report: None,
+            suppressed: 0,
+        }
+    }
+}
+
+fn watch_frame_time(
+    time: Res<Time<Real>>,
+    mut watchdog: ResMut<FrameWatchdog>,
+    game_state: Option<Res<State<GameState>>>,
+    scene: Option<Res<State<Scene>>>,
+    mode: Option<Res<State<Mode>>>,
+    entities: Query<()>,
+    mut chaos: Option<ResMut<Chaos>>,
+    tracer: Option<Res<GameTracer>>,
+    metrics: Option<Res<FrameMetrics>>,
+) {
+    let delta = time.delta();
+    if delta <= watchdog.threshold {
+        return;
+    }
+
+    let chaos_tag = chaos
+        .as_mut()
+        .and_then(|chaos| chaos.take_spike().then(|| chaos.attribute()));
+    if let Some(metrics) = metrics.as_deref() {
+        metrics.stalls.add(1, chaos_tag.as_slice());
+    }
+    if !watchdog.should_report(time.elapsed()) {
+        return;
+    }
+    let suppressed = std::mem::take(&mut watchdog.suppressed);
+
+    let entity_count = entities.iter().count();
+    let scene = scene.map(|s| format!("{:?}", s.get())).unwrap_or_default();
+    warn!(
+        "🐢 Frame stall: {:.0}ms (threshold {:.0}ms) in {scene}, {entity_count} entities",
+        delta.as_secs_f64() * 1000.0,
+        watchdog.threshold.as_secs_f64() * 1000.0,
+    );
+
+    let Some(tracer) = tracer else { return };
+    let end = SystemTime::now();
+    let mut span = tracer
+        .tracer()
+        .span_builder("game.frame.stall")
+        .with_start_time(end - delta)
+        .start(tracer.tracer());
+    if let Some(game_state) = game_state {
+        span.set_attribute(KeyValue::new("game.state", format!("{:?}", game_state.get())));
+    }
+    span.set_attribute(KeyValue::new("game.scene", scene));
+    if let Some(mode) = mode {
+        span.set_attribute(KeyValue::new("game.mode", format!("{:?}", mode.get())));
+    }
+    span.set_attribute(KeyValue::new("frame.duration_ms", delta.as_secs_f64() * 1000.0));
+    span.set_attribute(KeyValue::new("frame.threshold_ms", watchdog.threshold.as_secs_f64() * 1000.0));
+    span.set_attribute(KeyValue::new("frame.suppressed", suppressed as i64));
+    span.set_attribute(KeyValue::new("entity.count", entity_count as i64));
+    if let Some(tag) = chaos_tag {
+        span.set_attribute(tag);
+    }
+    span.end_with_timestamp(end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_are_rate_limited_and_count_what_they_dropped() {
+        let mut watchdog = FrameWatchdog::default();
+        let at = Duration::from_secs;
+        assert!(watchdog.should_report(at(1)));
+        assert!(!watchdog.should_report(at(2)));
+        assert!(!watchdog.should_report(at(10)));
+        assert_eq!(watchdog.suppressed, 2);
+        assert!(watchdog.should_report(at(11)), "cooldown over");
+    }
+}
@@ -0,0 +1,391 @@
+use bevy::prelude::*;
+use opentelemetry::metrics::{Counter, Meter};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+use crate::hooks::{MapChanged, NpcInteracted};
+use crate::instrumentation::{MetricsBundle, init_metrics};
+
+/// A plain, machine-readable stream of gameplay happenings, for things
+/// that want "what just happened" without an OTLP pipeline - the workshop's
+/// live scoreboard first. Systems publish a `GameEvent` into the
+/// `GameEvents` log; sinks read it by cursor:
+///
+/// - `--events-file <path>`: one JSON object per line (NDJSON), appended.
+/// - `--events-port <port>`: the same lines to every TCP client connected
+///   to 127.0.0.1:<port> (read with `nc 127.0.0.1 <port>`).
+/// - BRP (`--remote`): `sregame/poll_events {"cursor": n}` returns the
+///   events since `n` and the cursor to ask with next time.
+///
+/// The log is a bounded queue (`GameEvents::CAPACITY`): a sink that falls
+/// behind loses the oldest events, never the game's memory. Every event
+/// pushed out unread is counted (`game.events.dropped`), and a reader whose
+/// cursor fell off the back is told how many it missed - the file and TCP
+/// sinks write it as a `{"event":"dropped","count":n}` line.
+///
+/// There are no quests in this tree yet, so there's no quest event; add
+/// the variant where quests complete when they land.
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameEvents>()
+            .add_message::<MapChanged>()
+            .add_message::<NpcInteracted>()
+            .add_systems(First, stamp_game_events)
+            // After Update, so a hook written this frame is logged this frame.
+            .add_systems(PostUpdate, publish_hooks);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Last, sinks::write_event_sinks);
+        init_metrics::<EventMetrics>(app);
+    }
+}
+
+/// `game.events.dropped`: the log's overflow, for anyone not reading it.
+#[derive(Resource)]
+pub struct EventMetrics {
+    pub dropped: Counter<u64>,
+}
+
+impl MetricsBundle for EventMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            dropped: meter
+                .u64_counter("game.events.dropped")
+                .with_description("Gameplay events pushed out of the event log unread (see game_events.rs)")
+                .build(),
+        }
+    }
+}
+
+/// One gameplay happening. Serialized flat with an `event` tag, e.g.
+/// `{"event":"flag_set","flag":"met_isabella"}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// The player started talking to an NPC.
+    InteractionStarted { npc: String },
+    /// A dialogue box was read and advanced past. `line` counts from 0.
+    LineRead { dialogue: String, speaker: String, line: usize },
+    /// The scene changed; `from` is empty for the first map of a game.
+    MapChanged { from: String, to: String },
+    FlagSet { flag: String },
+    AchievementUnlocked { id: String },
+}
+
+/// A published event with its place in the stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventRecord {
+    /// Position in the stream, from 0. A cursor is the `seq` to read next.
+    pub seq: u64,
+    /// Game time (`Time::elapsed`) when published, in seconds.
+    pub t: f64,
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
+
+/// What a reader gets for a cursor: see `GameEvents::since`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventPage {
+    pub events: Vec<EventRecord>,
+    /// The cursor for the next read.
+    pub next: u64,
+    /// Events between the cursor and the oldest one kept, already pushed
+    /// out of the queue.
+    pub missed: u64,
+}
+
+#[derive(Resource, Debug)]
+pub struct GameEvents {
+    queue: VecDeque<EventRecord>,
+    capacity: usize,
+    next_seq: u64,
+    dropped: u64,
+    /// Set each frame, so publishers needn't pass the time in.
+    now: Duration,
+}
+
+impl Default for GameEvents {
+    fn default() -> Self {
+        Self::with_capacity(Self::CAPACITY)
+    }
+}
+
+impl GameEvents {
+    /// A few minutes of busy play: sinks drain every frame, so only a
+    /// stalled reader (BRP polling slowly) ever gets near it.
+    pub const CAPACITY: usize = 1024;
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            next_seq: 0,
+            dropped: 0,
+            now: Duration::ZERO,
+        }
+    }
+
+    /// Add an event, pushing out the oldest if the queue is full. Returns
+    /// whether one was pushed out.
+    pub fn publish(&mut self, event: GameEvent) -> bool {
+        debug!("📣 Game event: {event:?}");
+        let dropped = self.queue.len() >= self.capacity;
+        if dropped {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(EventRecord {
+            seq: self.next_seq,
+            t: self.now.as_secs_f64(),
+            event,
+        });
+        self.next_seq += 1;
+        dropped
+    }
+
+    /// Every event from `cursor` on. A cursor older than the queue gets
+    /// what's left and a `missed` count; one from the future (a reader
+    /// that outlived a previous run) gets nothing and the current cursor,
+    /// so it resyncs rather than waiting for a number this run may never
+    /// reach.
+    pub fn since(&self, cursor: u64) -> EventPage {
+        let oldest = self.next_seq - self.queue.len() as u64;
+        let start = cursor.clamp(oldest, self.next_seq);
+        EventPage {
+            events: self.queue.iter().skip((start - oldest) as usize).cloned().collect(),
+            next: self.next_seq,
+            missed: oldest.saturating_sub(cursor),
+        }
+    }
+
+    /// Events pushed out of the queue so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+fn stamp_game_events(time: Res<Time>, mut events: ResMut<GameEvents>, metrics: Option<Res<EventMetrics>>, mut reported: Local<u64>) {
+    events.now = time.elapsed();
+    // Counted here rather than in publish, which has no metrics to hand.
+    if let Some(metrics) = metrics
+        && events.dropped > *reported
+    {
+        metrics.dropped.add(events.dropped - *reported, &[]);
+        *reported = events.dropped;
+    }
+}
+
+/// The events that have a public hook (hooks.rs) are logged from it.
+fn publish_hooks(
+    mut interactions: MessageReader<NpcInteracted>,
+    mut map_changes: MessageReader<MapChanged>,
+    mut events: ResMut<GameEvents>,
+) {
+    for interaction in interactions.read() {
+        events.publish(GameEvent::InteractionStarted { npc: interaction.name.clone() });
+    }
+    for change in map_changes.read() {
+        events.publish(GameEvent::MapChanged {
+            from: change.from.map(|scene| format!("{scene:?}")).unwrap_or_default(),
+            to: format!("{:?}", change.to),
+        });
+    }
+}
+
+/// The native sinks: files and sockets.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sinks {
+    use super::*;
+    use std::io::{BufWriter, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    /// Render a page as NDJSON lines, a `dropped` line first if the reader
+    /// missed any.
+    pub fn ndjson_lines(page: &EventPage) -> String {
+        let mut out = String::new();
+        if page.missed > 0 {
+            out.push_str(&serde_json::json!({ "event": "dropped", "count": page.missed }).to_string());
+            out.push('\n');
+        }
+        for record in &page.events {
+            // EventRecord is plain data; serializing it can't fail.
+            out.push_str(&serde_json::to_string(record).unwrap_or_default());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// `--events-file`: appends every event as a line.
+    #[derive(Resource)]
+    pub struct EventFile {
+        writer: BufWriter<std::fs::File>,
+        cursor: u64,
+    }
+
+    impl EventFile {
+        pub fn create(path: &Path) -> std::io::Result<Self> {
+            let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            Ok(Self { writer: BufWriter::new(file), cursor: 0 })
+        }
+
+        /// Write what's new since the last call, and flush - a scoreboard
+        /// tailing the file should see this frame's events this frame.
+        pub fn write_new(&mut self, events: &GameEvents) -> std::io::Result<()> {
+            let page = events.since(self.cursor);
+            self.cursor = page.next;
+            if page.events.is_empty() && page.missed == 0 {
+                return Ok(());
+            }
+            self.writer.write_all(ndjson_lines(&page).as_bytes())?;
+            self.writer.flush()
+        }
+    }
+
+    /// `--events-port`: a listener thread accepts clients; the game writes
+    /// new lines to each at the end of the frame. Clients are non-blocking:
+    /// one that stops reading is dropped rather than allowed to stall a
+    /// frame. A plain thread, not the telemetry tokio runtime: that only
+    /// exists when OTLP is configured, and accepting a socket needs no
+    /// async.
+    #[derive(Resource)]
+    pub struct EventSocket {
+        clients: Arc<Mutex<Vec<TcpStream>>>,
+        cursor: u64,
+    }
+
+    impl EventSocket {
+        pub fn bind(port: u16) -> std::io::Result<Self> {
+            let listener = TcpListener::bind(("127.0.0.1", port))?;
+            let clients = Arc::new(Mutex::new(Vec::new()));
+            let accepted = Arc::clone(&clients);
+            std::thread::Builder::new()
+                .name("game-events".into())
+                .spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        if stream.set_nonblocking(true).is_ok()
+                            && let Ok(mut clients) = accepted.lock()
+                        {
+                            info!("📣 Events client connected: {:?}", stream.peer_addr());
+                            clients.push(stream);
+                        }
+                    }
+                })?;
+            Ok(Self { clients, cursor: 0 })
+        }
+
+        pub fn write_new(&mut self, events: &GameEvents) {
+            let page = events.since(self.cursor);
+            self.cursor = page.next;
+            if page.events.is_empty() && page.missed == 0 {
+                return;
+            }
+            let lines = ndjson_lines(&page);
+            let Ok(mut clients) = self.clients.lock() else { return };
+            clients.retain_mut(|client| client.write_all(lines.as_bytes()).is_ok());
+        }
+    }
+
+    pub(super) fn write_event_sinks(
+        events: Res<GameEvents>,
+        file: Option<ResMut<EventFile>>,
+        socket: Option<ResMut<EventSocket>>,
+    ) {
+        if let Some(mut file) = file
+            && let Err(e) = file.write_new(&events)
+        {
+            warn!("⚠️ Couldn't write game events: {e}");
+        }
+        if let Some(mut socket) = socket {
+            socket.write_new(&events);
+        }
+    }
+
+    /// BRP method name for `poll_events`.
+    pub const POLL_EVENTS_METHOD: &str = "sregame/poll_events";
+
+    /// `sregame/poll_events`: params `{"cursor": n}` (default 0), result an
+    /// `EventPage`.
+    pub fn poll_events(
+        In(params): In<Option<serde_json::Value>>,
+        events: Res<GameEvents>,
+    ) -> bevy_remote::BrpResult {
+        let cursor = params
+            .as_ref()
+            .and_then(|params| params.get("cursor"))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0);
+        serde_json::to_value(events.since(cursor)).map_err(|e| bevy_remote::BrpError {
+            code: bevy_remote::error_codes::INTERNAL_ERROR,
+            message: e.to_string(),
+            data: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(name: &str) -> GameEvent {
+        GameEvent::FlagSet { flag: name.into() }
+    }
+
+    #[test]
+    fn cursors_read_what_is_new_and_count_what_fell_off() {
+        let mut events = GameEvents::with_capacity(3);
+        assert_eq!(events.since(0), EventPage { events: vec![], next: 0, missed: 0 });
+        events.publish(flag("a"));
+        events.publish(flag("b"));
+
+        let page = events.since(0);
+        assert_eq!(page.events.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!((page.next, page.missed), (2, 0));
+        assert!(events.since(page.next).events.is_empty(), "nothing new");
+
+        // Two more overflow the queue: "a" is pushed out.
+        assert!(!events.publish(flag("c")));
+        assert!(events.publish(flag("d")));
+        assert_eq!(events.dropped(), 1);
+        let page = events.since(0);
+        assert_eq!(page.missed, 1);
+        assert_eq!(page.events.first().map(|r| r.seq), Some(1));
+        assert_eq!(events.since(3).events, vec![EventRecord { seq: 3, t: 0.0, event: flag("d") }]);
+
+        // A cursor from a longer previous run resyncs to now.
+        assert_eq!(events.since(99), EventPage { events: vec![], next: 4, missed: 0 });
+    }
+
+    #[test]
+    fn the_file_sink_appends_one_json_line_per_event() {
+        let path = std::env::temp_dir().join(format!("sregame-events-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut events = GameEvents::with_capacity(2);
+        let mut file = sinks::EventFile::create(&path).unwrap();
+
+        events.publish(GameEvent::InteractionStarted { npc: "Isabella".into() });
+        file.write_new(&events).unwrap();
+        file.write_new(&events).unwrap();
+        // Three more before the next write: one never reaches the file.
+        for name in ["a", "b", "c"] {
+            events.publish(flag(name));
+        }
+        file.write_new(&events).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let lines: Vec<serde_json::Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4, "{written}");
+        assert_eq!(lines[0]["event"], "interaction_started");
+        assert_eq!(lines[0]["npc"], "Isabella");
+        assert_eq!(lines[0]["seq"], 0);
+        assert_eq!(lines[1], serde_json::json!({ "event": "dropped", "count": 1 }));
+        assert_eq!(lines[2]["flag"], "b");
+        assert_eq!(lines[3]["seq"], 3);
+    }
+}
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
-use crate::instrumentation::ActiveDialogue;
-use crate::dialogue::DialogueQueue;
+use crate::dialogue::DialogueSession;
+use crate::hooks::{DialogueEndOutcome, DialogueEnded};
+use crate::instrumentation::PlayerSessionTrace;
+use crate::player::Player;
 use opentelemetry::{KeyValue, trace::Span as _};
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -24,6 +26,19 @@ pub enum Scene {
     End,
 }
 
+impl Scene {
+    pub const ALL: [Scene; 8] = [
+        Scene::TownOfEndgame,
+        Scene::TeamMarathon,
+        Scene::TeamMarathonRetro,
+        Scene::TeamDisco,
+        Scene::TeamInferno,
+        Scene::MahoganyRow,
+        Scene::Intro,
+        Scene::End,
+    ];
+}
+
 /// Whether the player is freely exploring the current `Scene` or reading a
 /// dialogue box. This is a *sibling* `SubState` to `Scene` - both are sourced
 /// from `GameState::Playing` - rather than a variant of `GameState` itself.
@@ -45,6 +60,13 @@ pub enum Mode {
     #[default]
     Exploring,
     Dialogue,
+    /// The save slot picker (save_menu.rs) is open over the paused game.
+    Menu,
+    /// The telemetry terminal's dashboard (dashboard.rs) is open. The game
+    /// keeps running underneath - it's what the dashboard is watching.
+    Dashboard,
+    /// The developer console (console.rs) is open, taking the keyboard.
+    Console,
 }
 
 pub struct GameStatePlugin;
@@ -54,11 +76,89 @@ impl Plugin for GameStatePlugin {
         app.init_state::<GameState>()
             .add_sub_state::<Scene>()
             .add_sub_state::<Mode>()
+            .add_message::<DialogueEnded>()
+            .add_message::<SceneChangeRequest>()
+            .add_message::<NewGameRequest>()
             .add_systems(Update, (
                 debug_state_changes,
-                handle_escape_key.run_if(in_state(Mode::Dialogue)),
+                handle_escape_key
+                    .after(crate::dialogue::DialogueReadingSet)
+                    .run_if(in_state(Mode::Dialogue)),
+                apply_scene_change_requests,
+                start_new_game.run_if(in_state(GameState::Playing)),
             ));
+        crate::input::init_game_input(app);
+    }
+}
+
+/// Go to a scene, arriving at its default spawn. A dialogue line's
+/// `change_scene` action sends one (dialogue.rs); it waits for the box to
+/// close, so the map never changes under a conversation.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SceneChangeRequest {
+    pub scene: Scene,
+}
+
+fn apply_scene_change_requests(
+    mut requests: MessageReader<SceneChangeRequest>,
+    mut waiting: Local<Option<Scene>>,
+    mode: Option<Res<State<Mode>>>,
+    scene: Option<Res<State<Scene>>>,
+    next_scene: Option<ResMut<NextState<Scene>>>,
+) {
+    if let Some(request) = requests.read().last() {
+        *waiting = Some(request.scene);
+    }
+    if mode.is_none_or(|mode| *mode.get() != Mode::Exploring) {
+        return;
+    }
+    let (Some(target), Some(mut next_scene)) = (waiting.take(), next_scene) else { return };
+    // Setting the current scene would respawn it (see lib.rs's
+    // on_enter_playing).
+    if scene.is_some_and(|scene| *scene.get() == target) {
+        return;
+    }
+    info!("🚪 Changing scene to {target:?}");
+    next_scene.set(target);
+}
+
+/// Start a new game without restarting the process (kiosk.rs's resets).
+/// A new game is entering `GameState::Playing`: whatever keeps state for a
+/// play session empties it then (flags, conversations read, times talked,
+/// the tutorial, playtime), and the player spawns at the start under a new
+/// `game_session` span. So this re-enters Playing rather than resetting
+/// anything itself - a launch and a reset can't start different games.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct NewGameRequest {
+    /// Why, on the ended session's span ("kiosk.idle", ...).
+    pub reason: &'static str,
+}
+
+fn start_new_game(
+    mut commands: Commands,
+    mut requests: MessageReader<NewGameRequest>,
+    mut sessions: Query<&mut PlayerSessionTrace, With<Player>>,
+    mut next_game: ResMut<NextState<GameState>>,
+    mut next_scene: ResMut<NextState<Scene>>,
+    mut next_mode: ResMut<NextState<Mode>>,
+) {
+    let Some(request) = requests.read().last() else { return };
+    info!("🔄 Starting a new game ({})", request.reason);
+    if let Ok(mut session) = sessions.single_mut() {
+        session.span.set_attribute(KeyValue::new("session.end_reason", request.reason));
     }
+
+    // Nothing on its way from the old game arrives in the new one.
+    commands.remove_resource::<crate::tilemap::PendingArrival>();
+    commands.remove_resource::<crate::transitions::PendingTransferAfterDialogue>();
+    commands.remove_resource::<crate::npc::PendingInteraction>();
+
+    next_game.set(GameState::Playing);
+    // Re-entering Playing leaves its sub-states as they were. The first
+    // scene loads afresh - `set` respawns it even from itself (see lib.rs's
+    // on_enter_playing) - and a conversation or menu left open closes.
+    next_scene.set(Scene::default());
+    next_mode.set_if_neq(Mode::Exploring);
 }
 
 fn debug_state_changes(
@@ -71,17 +171,45 @@ fn debug_state_changes(
 
 /// Force-exits dialogue mode. Gated on `run_if(in_state(Mode::Dialogue))` at
 /// the call site, so this only ever runs while `Mode::Dialogue` is current.
+/// After `DialogueReadingSet`: too late for a conversation already ended
+/// this frame - its last line read past the same frame - which stays
+/// ended the way it was.
 fn handle_escape_key(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    keyboard: crate::input::GameInput,
     mut next_mode: ResMut<NextState<Mode>>,
     mut commands: Commands,
-    active_dialogue: Option<ResMut<ActiveDialogue>>,
+    session: Option<ResMut<DialogueSession>>,
+    mut ended: MessageWriter<DialogueEnded>,
     pending_transfer: Option<Res<crate::transitions::PendingTransferAfterDialogue>>,
 ) {
     if !keyboard.just_pressed(KeyCode::Escape) {
         return;
     }
 
+    // The session itself is ended from its exit (dialogue.rs). Forced,
+    // walking away doesn't count as having met the NPC (NpcsMet).
+    if let Some(mut session) = session {
+        let DialogueSession { queue, telemetry, exit } = &mut *session;
+        if !exit.end(queue.id(), DialogueEndOutcome::Forced, &mut ended) {
+            return;
+        }
+        if let Some(dialogue) = telemetry {
+            let chars_read = dialogue.chars_read;
+
+            // Add telemetry event for forced exit
+            dialogue.span.add_event(
+                "dialogue.forced_exit",
+                vec![
+                    KeyValue::new("cleanup.type", "forced"),
+                    KeyValue::new("dialogue.completed", false),
+                    KeyValue::new("chars_read", chars_read as i64),
+                ],
+            );
+
+            info!("📊 Dialogue force-closed: {} chars read", chars_read);
+        }
+    }
+
     info!("🚫 Force-exiting dialogue mode");
 
     // Consent prompts (the End fairies): declining with Escape must drop
@@ -89,37 +217,13 @@ fn handle_escape_key(
     // fire_transfer_after_dialogue would warp the player anyway. Scripted
     // scenes (cancel_on_escape = false) keep their skip-still-transfers
     // semantics.
-    if let Some(pending) = pending_transfer {
-        if pending.cancel_on_escape {
-            info!("🧚 Transfer declined - staying put");
-            commands.remove_resource::<crate::transitions::PendingTransferAfterDialogue>();
-        }
+    if let Some(pending) = pending_transfer
+        && pending.cancel_on_escape
+    {
+        info!("🧚 Transfer declined - staying put");
+        commands.remove_resource::<crate::transitions::PendingTransferAfterDialogue>();
     }
 
-    // Clean up dialogue resources with telemetry
-    if let Some(mut dialogue) = active_dialogue {
-        let chars_read = dialogue.chars_read;
-
-        // Add telemetry event for forced exit
-        dialogue.span.add_event(
-            "dialogue.forced_exit",
-            vec![
-                KeyValue::new("cleanup.type", "forced"),
-                KeyValue::new("dialogue.completed", false),
-                KeyValue::new("chars_read", chars_read as i64),
-            ],
-        );
-
-        info!("📊 Dialogue force-closed: {} chars read", chars_read);
-
-        // End span and remove resource
-        dialogue.span.end();
-        commands.remove_resource::<ActiveDialogue>();
-    }
-
-    // Remove dialogue queue
-    commands.remove_resource::<DialogueQueue>();
-
     next_mode.set(Mode::Exploring);
 }
 
@@ -138,6 +242,10 @@ mod tests {
             .add_sub_state::<Scene>()
             .add_sub_state::<Mode>()
             .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<crate::input::InputLatch>()
+            .init_resource::<crate::input::KeyPressTimes>()
+            .init_resource::<crate::input::InputMap>()
+            .add_message::<DialogueEnded>()
             .add_systems(Update, handle_escape_key.run_if(in_state(Mode::Dialogue)));
 
         app.world_mut()
@@ -153,6 +261,7 @@ mod tests {
             target_scene: Scene::TownOfEndgame,
             spawn_x: 16,
             spawn_y: 16,
+            facing: None,
             cancel_on_escape,
         });
         app.world_mut()
@@ -190,6 +299,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn a_scene_change_waits_for_the_dialogue_to_close() {
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .init_state::<GameState>()
+            .add_sub_state::<Scene>()
+            .add_sub_state::<Mode>()
+            .add_message::<SceneChangeRequest>()
+            .add_systems(Update, apply_scene_change_requests);
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+        app.world_mut().resource_mut::<NextState<Mode>>().set(Mode::Dialogue);
+        app.update();
+
+        app.world_mut().write_message(SceneChangeRequest { scene: Scene::TeamDisco });
+        app.update();
+        app.update();
+        assert_eq!(*app.world().resource::<State<Scene>>().get(), Scene::TownOfEndgame, "not under an open box");
+
+        app.world_mut().resource_mut::<NextState<Mode>>().set(Mode::Exploring);
+        app.update();
+        app.update();
+        assert_eq!(*app.world().resource::<State<Scene>>().get(), Scene::TeamDisco);
+    }
+
     /// Regression test for "the scene disappears during dialog": entering
     /// and leaving `Mode::Dialogue` from a non-default `Scene` must leave
     /// `Scene` completely untouched.
@@ -228,7 +362,7 @@ mod tests {
         assert_eq!(*app.world().resource::<State<Scene>>().get(), Scene::TeamMarathonRetro);
 
         // Start a dialogue (mirrors dialogue::handle_dialogue_events setting
-        // Mode::Dialogue on StartDialogueEvent).
+        // Mode::Dialogue on DialogueRequest).
         app.world_mut()
             .resource_mut::<NextState<Mode>>()
             .set(Mode::Dialogue);
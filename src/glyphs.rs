@@ -0,0 +1,343 @@
+use bevy::prelude::*;
+use crate::assets::GameAssets;
+use crate::game_state::GameState;
+use crate::input::{ActiveDevice, GameAction, GamepadStyle, InputMap, button_label, key_label};
+
+/// Button prompts that match what the player is holding. A prompt is a
+/// template - "Press {interact} to talk" - on an `InputPrompt` node; each
+/// `{control}` becomes the key or button for the active device
+/// (`input::ActiveDevice`): a glyph from the atlas, or a text chip ("E",
+/// "A", "Cross") when the atlas didn't ship. Switching between keyboard and
+/// gamepad rebuilds every prompt on screen the same frame, as does a
+/// rebinding (`InputMap`) - a rebound control is always a text chip.
+///
+/// The atlas (`textures/ui/input_glyphs.png`, optional - see build.rs) is a
+/// grid of `GLYPH_CELL` squares: one column per `PromptControl` in `ALL`
+/// order, one row per device - keyboard, Xbox, PlayStation.
+pub struct GlyphsPlugin;
+
+impl Plugin for GlyphsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputGlyphs>()
+            .init_resource::<ActiveDevice>()
+            .init_resource::<InputMap>()
+            .add_systems(OnExit(GameState::Loading), prepare_glyph_atlas)
+            .add_systems(PostUpdate, build_input_prompts.before(bevy::ui::UiSystems::Layout));
+    }
+}
+
+pub const GLYPH_CELL: UVec2 = UVec2::splat(32);
+
+/// What a prompt can name: one action, or all four moves as one glyph
+/// ("WASD", the d-pad).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptControl {
+    Move,
+    Action(GameAction),
+}
+
+impl PromptControl {
+    pub const ALL: [PromptControl; 5] = [
+        PromptControl::Move,
+        PromptControl::Action(GameAction::Interact),
+        PromptControl::Action(GameAction::Advance),
+        PromptControl::Action(GameAction::Cancel),
+        PromptControl::Action(GameAction::Skip),
+    ];
+
+    /// The name templates use for it, between braces.
+    pub fn id(self) -> &'static str {
+        match self {
+            PromptControl::Move => "move",
+            PromptControl::Action(GameAction::Interact) => "interact",
+            PromptControl::Action(GameAction::Advance) => "advance",
+            PromptControl::Action(GameAction::Cancel) => "cancel",
+            PromptControl::Action(GameAction::Skip) => "skip",
+            PromptControl::Action(GameAction::History) => "history",
+            PromptControl::Action(GameAction::Sprint) => "sprint",
+            PromptControl::Action(GameAction::MoveUp) => "move_up",
+            PromptControl::Action(GameAction::MoveDown) => "move_down",
+            PromptControl::Action(GameAction::MoveLeft) => "move_left",
+            PromptControl::Action(GameAction::MoveRight) => "move_right",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .chain(GameAction::MOVEMENT.map(PromptControl::Action))
+            .find(|control| control.id() == id)
+    }
+
+    /// The actions it names.
+    fn actions(self) -> Vec<GameAction> {
+        match self {
+            PromptControl::Move => vec![GameAction::MoveUp, GameAction::MoveLeft, GameAction::MoveDown, GameAction::MoveRight],
+            PromptControl::Action(action) => vec![action],
+        }
+    }
+
+    /// Its name on the device as bound now, for the text chip. The moves
+    /// read "WASD" or "D-pad" until one is rebound, then list all four.
+    pub fn label(self, device: ActiveDevice, map: &InputMap) -> String {
+        let label = |action: GameAction| match device {
+            ActiveDevice::Keyboard => key_label(map.key(action)),
+            ActiveDevice::Gamepad(style) => button_label(map.button(action), style),
+        };
+        match self {
+            PromptControl::Move if device != ActiveDevice::Keyboard && self.is_default(device, map) => "D-pad".into(),
+            PromptControl::Move => {
+                let labels: Vec<&str> = self.actions().into_iter().map(|action| label(action).unwrap_or("?")).collect();
+                let separator = if labels.iter().all(|label| label.chars().count() == 1) { "" } else { "/" };
+                labels.join(separator)
+            }
+            PromptControl::Action(action) => label(action).unwrap_or("?").into(),
+        }
+    }
+
+    /// Whether it's still on the device's default bindings - what the
+    /// atlas draws.
+    fn is_default(self, device: ActiveDevice, map: &InputMap) -> bool {
+        self.actions().into_iter().all(|action| match device {
+            ActiveDevice::Keyboard => map.key(action) == action.default_key(),
+            ActiveDevice::Gamepad(_) => map.button(action) == action.gamepad_button(),
+        })
+    }
+
+    /// Its cell in the atlas. The single moves have none of their own and
+    /// share `Move`'s.
+    fn atlas_index(self, device: ActiveDevice) -> usize {
+        let row = match device {
+            ActiveDevice::Keyboard => 0,
+            ActiveDevice::Gamepad(GamepadStyle::Xbox) => 1,
+            ActiveDevice::Gamepad(GamepadStyle::PlayStation) => 2,
+        };
+        let column = Self::ALL.iter().position(|&control| control == self).unwrap_or(0);
+        row * Self::ALL.len() + column
+    }
+}
+
+/// How one control is shown.
+#[derive(Debug, Clone)]
+pub enum Glyph {
+    Icon(ImageNode),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct GlyphAtlas {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+/// Picks each control's glyph; icons once the atlas is loaded, text until
+/// then or without one.
+#[derive(Resource, Debug, Default)]
+pub struct InputGlyphs {
+    atlas: Option<GlyphAtlas>,
+}
+
+impl InputGlyphs {
+    pub fn with_atlas(image: Handle<Image>, layout: Handle<TextureAtlasLayout>) -> Self {
+        Self { atlas: Some(GlyphAtlas { image, layout }) }
+    }
+
+    pub fn glyph(&self, control: PromptControl, device: ActiveDevice, map: &InputMap) -> Glyph {
+        match &self.atlas {
+            Some(atlas) if control.is_default(device, map) => Glyph::Icon(ImageNode::from_atlas_image(
+                atlas.image.clone(),
+                TextureAtlas { layout: atlas.layout.clone(), index: control.atlas_index(device) },
+            )),
+            _ => Glyph::Text(control.label(device, map)),
+        }
+    }
+
+    /// `template` split into text and controls; an unknown `{name}` stays
+    /// as written.
+    pub fn segments(template: &str) -> Vec<PromptSegment> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            let Some(close) = rest[open..].find('}').map(|close| open + close) else { break };
+            match PromptControl::from_id(&rest[open + 1..close]) {
+                Some(control) => {
+                    if open > 0 {
+                        segments.push(PromptSegment::Text(rest[..open].to_string()));
+                    }
+                    segments.push(PromptSegment::Control(control));
+                }
+                None => segments.push(PromptSegment::Text(rest[..=close].to_string())),
+            }
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(PromptSegment::Text(rest.to_string()));
+        }
+        segments
+    }
+
+    /// The prompt as plain text, chips in brackets: "Press [E] to talk".
+    /// For logs and tests.
+    pub fn plain(template: &str, device: ActiveDevice, map: &InputMap) -> String {
+        Self::segments(template)
+            .into_iter()
+            .map(|segment| match segment {
+                PromptSegment::Text(text) => text,
+                PromptSegment::Control(control) => format!("[{}]", control.label(device, map)),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptSegment {
+    Text(String),
+    Control(PromptControl),
+}
+
+/// A line of text with controls in it, laid out as a row of children:
+/// text runs, text chips and glyph icons. Change `template` to reword it.
+#[derive(Component, Debug, Clone)]
+#[require(Node)]
+pub struct InputPrompt {
+    pub template: String,
+    pub font: Handle<Font>,
+    /// At 1080p, scaling with the window like the rest of the UI.
+    pub font_px: f32,
+    pub color: Color,
+}
+
+impl InputPrompt {
+    pub fn new(template: impl Into<String>, font: Handle<Font>, font_px: f32, color: Color) -> Self {
+        Self { template: template.into(), font, font_px, color }
+    }
+}
+
+fn prepare_glyph_atlas(
+    game_assets: Option<Res<GameAssets>>,
+    layouts: Option<ResMut<Assets<TextureAtlasLayout>>>,
+    mut glyphs: ResMut<InputGlyphs>,
+) {
+    let (Some(image), Some(mut layouts)) = (game_assets.and_then(|assets| assets.input_glyphs.clone()), layouts) else {
+        info!("No button glyph atlas - prompts show text");
+        return;
+    };
+    let layout = layouts.add(TextureAtlasLayout::from_grid(GLYPH_CELL, PromptControl::ALL.len() as u32, 3, None, None));
+    *glyphs = InputGlyphs::with_atlas(image, layout);
+}
+
+fn build_input_prompts(
+    mut commands: Commands,
+    glyphs: Res<InputGlyphs>,
+    device: Res<ActiveDevice>,
+    map: Res<InputMap>,
+    prompts: Query<(Entity, Ref<InputPrompt>)>,
+) {
+    let rebuild_all = glyphs.is_changed() || device.is_changed() || map.is_changed();
+    for (entity, prompt) in &prompts {
+        if !rebuild_all && !prompt.is_changed() {
+            continue;
+        }
+        let font = TextFont {
+            font: prompt.font.clone().into(),
+            font_size: FontSize::Vh(prompt.font_px / 10.8),
+            ..default()
+        };
+        let mut entity = commands.entity(entity);
+        entity.despawn_related::<Children>();
+        entity.with_children(|parent| {
+            for segment in InputGlyphs::segments(&prompt.template) {
+                let control = match segment {
+                    PromptSegment::Text(text) => {
+                        parent.spawn((Text::new(text), font.clone(), TextColor(prompt.color)));
+                        continue;
+                    }
+                    PromptSegment::Control(control) => control,
+                };
+                match glyphs.glyph(control, *device, &map) {
+                    Glyph::Icon(image) => {
+                        parent.spawn((
+                            image,
+                            Node {
+                                height: Val::Vh(prompt.font_px * 1.2 / 10.8),
+                                aspect_ratio: Some(1.0),
+                                margin: UiRect::horizontal(Val::Px(4.0)),
+                                ..default()
+                            },
+                        ));
+                    }
+                    Glyph::Text(label) => {
+                        parent
+                            .spawn((
+                                Node {
+                                    padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                                    margin: UiRect::horizontal(Val::Px(4.0)),
+                                    border: UiRect::all(Val::Px(2.0)),
+                                    border_radius: BorderRadius::all(Val::Px(6.0)),
+                                    ..default()
+                                },
+                                BorderColor::all(prompt.color),
+                            ))
+                            .with_child((Text::new(label), font.clone(), TextColor(prompt.color)));
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_split_into_text_and_controls() {
+        assert_eq!(
+            InputGlyphs::segments("Press {interact} to talk"),
+            [
+                PromptSegment::Text("Press ".into()),
+                PromptSegment::Control(PromptControl::Action(GameAction::Interact)),
+                PromptSegment::Text(" to talk".into()),
+            ]
+        );
+        assert_eq!(InputGlyphs::segments("{bogus} {advance}").len(), 3);
+        let map = InputMap::default();
+        assert_eq!(InputGlyphs::plain("Hold {skip} to skip", ActiveDevice::Keyboard, &map), "Hold [Tab] to skip");
+    }
+
+    #[test]
+    fn each_device_names_its_own_buttons() {
+        let (talk, map) = ("Press {interact} to talk", InputMap::default());
+        assert_eq!(InputGlyphs::plain(talk, ActiveDevice::Gamepad(GamepadStyle::Xbox), &map), "Press [A] to talk");
+        assert_eq!(InputGlyphs::plain(talk, ActiveDevice::Gamepad(GamepadStyle::PlayStation), &map), "Press [Cross] to talk");
+        assert_eq!(InputGlyphs::plain("Move with {move}", ActiveDevice::Gamepad(GamepadStyle::Xbox), &map), "Move with [D-pad]");
+    }
+
+    #[test]
+    fn without_an_atlas_glyphs_are_text() {
+        let (control, map) = (PromptControl::Action(GameAction::Cancel), InputMap::default());
+        assert!(matches!(InputGlyphs::default().glyph(control, ActiveDevice::Keyboard, &map), Glyph::Text(label) if label == "Esc"));
+
+        let glyphs = InputGlyphs::with_atlas(Handle::default(), Handle::default());
+        let Glyph::Icon(icon) = glyphs.glyph(control, ActiveDevice::Gamepad(GamepadStyle::PlayStation), &map) else {
+            panic!("atlas present");
+        };
+        assert_eq!(icon.texture_atlas.map(|atlas| atlas.index), Some(2 * 5 + 3));
+    }
+
+    #[test]
+    fn rebound_controls_are_named_as_bound() {
+        use crate::input::Binding;
+        let mut map = InputMap::default();
+        map.bind(GameAction::Interact, Binding::Key(KeyCode::KeyQ));
+        map.bind(GameAction::MoveUp, Binding::Key(KeyCode::KeyI));
+        assert_eq!(InputGlyphs::plain("Press {interact} to talk", ActiveDevice::Keyboard, &map), "Press [Q] to talk");
+        assert_eq!(InputGlyphs::plain("Move with {move}", ActiveDevice::Keyboard, &map), "Move with [IASD]");
+
+        // The atlas only draws the defaults.
+        let glyphs = InputGlyphs::with_atlas(Handle::default(), Handle::default());
+        let interact = PromptControl::Action(GameAction::Interact);
+        assert!(matches!(glyphs.glyph(interact, ActiveDevice::Keyboard, &map), Glyph::Text(label) if label == "Q"));
+        assert!(matches!(glyphs.glyph(interact, ActiveDevice::Gamepad(GamepadStyle::Xbox), &map), Glyph::Icon(_)));
+    }
+}
@@ -0,0 +1,392 @@
+use bevy::prelude::*;
+use opentelemetry::global::BoxedSpan;
+use opentelemetry::trace::{Span as _, Tracer as _};
+use opentelemetry::{Array, KeyValue, StringValue, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::ambient::{BUBBLE_SECONDS, ChatterBubble, spawn_chatter_bubble};
+use crate::assets::GameAssets;
+use crate::character_sheet::STANDING_PATTERN;
+use crate::game_state::{GameState, Mode};
+use crate::instrumentation::{GameTracer, PlayerSessionTrace};
+use crate::map_data::{GroupDialogueData, GroupLineData};
+use crate::npc::{Busy, CharacterFrames, Npc, NpcFacing, PendingInteraction};
+use crate::player::Player;
+
+/// Group conversations: NPCs listed together in a map's `group_dialogues`
+/// turn to face each other and talk among themselves, one speech bubble
+/// at a time, once the player comes within the group's `trigger_radius`.
+/// Overheard, not joined - no dialogue box, no input.
+///
+/// Only one plays at a time. It starts only in `Mode::Exploring`, with no
+/// participant `Busy` and no conversation queued, and pauses - its bubble
+/// gone, the current line shown again on resume - whenever the player
+/// opens a real dialogue or any other mode takes over. Participants stand
+/// still (no wandering, no ambient lines of their own) until it ends, and
+/// are `Busy`: an E press at one of them waits for the line being said,
+/// then the group breaks up and the player's conversation starts.
+///
+/// How often each group has played and how long until it may play again
+/// is `GroupConversationLog`, which saves keep: a `once` group never
+/// replays, even after a load. Each conversation is a
+/// `npc.group_conversation` span under the player session, with the
+/// participants as attributes and an event per line.
+pub struct GroupConversationPlugin;
+
+impl Plugin for GroupConversationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GroupConversationLog>()
+            .add_systems(OnEnter(GameState::Playing), clear_group_log)
+            .add_systems(
+                Update,
+                (
+                    tick_group_cooldowns,
+                    advance_group_conversation,
+                    start_group_conversation.run_if(in_state(Mode::Exploring)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How long each line of a group conversation stays up.
+pub const GROUP_LINE_SECONDS: f32 = BUBBLE_SECONDS;
+
+/// The loaded map's playable group conversations - the ones that passed
+/// `GroupDialogueData::problem`. Inserted by `tilemap::spawn_map`, same
+/// lifecycle as `tilemap::MapExits`.
+#[derive(Resource, Default)]
+pub struct MapGroupConversations(pub Vec<GroupConversation>);
+
+/// One group and its script.
+#[derive(Debug, Clone)]
+pub struct GroupConversation {
+    pub group: GroupDialogueData,
+    pub lines: Vec<GroupLineData>,
+}
+
+/// One group's history, by `dialogue_ref`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupConversationState {
+    pub plays: u32,
+    /// Seconds until it may start again.
+    pub cooldown_left_secs: f32,
+}
+
+/// Every group conversation's history - saved (save.rs), so `once` means
+/// once per playthrough.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GroupConversationLog(BTreeMap<String, GroupConversationState>);
+
+impl GroupConversationLog {
+    pub fn get(&self, id: &str) -> GroupConversationState {
+        self.0.get(id).copied().unwrap_or_default()
+    }
+
+    /// Whether `group` may start now.
+    fn ready(&self, group: &GroupDialogueData) -> bool {
+        let state = self.get(&group.dialogue_ref);
+        !(group.once && state.plays > 0) && state.cooldown_left_secs <= 0.0
+    }
+}
+
+/// Marks the NPCs of the conversation in progress.
+#[derive(Component)]
+pub struct InGroupConversation;
+
+/// The group conversation in progress, if any.
+#[derive(Resource)]
+pub struct ActiveGroupConversation {
+    pub id: String,
+    pub participants: Vec<Entity>,
+    /// Each line as (index into `participants`, text).
+    lines: Vec<(usize, String)>,
+    pub line: usize,
+    /// The current line's bubble; `None` until it's shown, and again
+    /// while paused.
+    bubble: Option<Entity>,
+    timer: Timer,
+    paused: bool,
+    pauses: u32,
+    cooldown_secs: f32,
+    span: Option<BoxedSpan>,
+}
+
+impl ActiveGroupConversation {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+/// The way to face from `from` to look at `to`, along whichever axis is
+/// further.
+pub fn facing_toward(from: Vec2, to: Vec2) -> NpcFacing {
+    let delta = to - from;
+    if delta.x.abs() > delta.y.abs() {
+        if delta.x < 0.0 { NpcFacing::Left } else { NpcFacing::Right }
+    } else if delta.y > 0.0 {
+        NpcFacing::Up
+    } else {
+        NpcFacing::Down
+    }
+}
+
+fn tick_group_cooldowns(time: Res<Time>, mut log: ResMut<GroupConversationLog>) {
+    // Only touch the log when something is cooling down, so change
+    // detection stays quiet otherwise.
+    if log.0.values().all(|state| state.cooldown_left_secs <= 0.0) {
+        return;
+    }
+    for state in log.0.values_mut() {
+        state.cooldown_left_secs = (state.cooldown_left_secs - time.delta_secs()).max(0.0);
+    }
+}
+
+fn start_group_conversation(
+    mut commands: Commands,
+    groups: Option<Res<MapGroupConversations>>,
+    active: Option<Res<ActiveGroupConversation>>,
+    pending: Option<Res<PendingInteraction>>,
+    mut log: ResMut<GroupConversationLog>,
+    player: Query<(&Transform, Option<&PlayerSessionTrace>), With<Player>>,
+    mut npcs: Query<(Entity, &Npc, &Transform, &mut CharacterFrames, &mut Sprite), Without<Player>>,
+    busy: Query<(), With<Busy>>,
+    tracer: Option<Res<GameTracer>>,
+) {
+    if active.is_some() || pending.is_some() {
+        return;
+    }
+    let Some(groups) = groups else { return };
+    let Ok((player, session)) = player.single() else { return };
+    let player_pos = player.translation.truncate();
+
+    for conversation in &groups.0 {
+        let group = &conversation.group;
+        if !log.ready(group) {
+            continue;
+        }
+        // Everyone has to be on the map (flag-gated NPCs may not be yet)
+        // and standing still.
+        let Some(members) = group
+            .participants
+            .iter()
+            .map(|name| {
+                npcs.iter()
+                    .find(|(_, npc, ..)| &npc.name == name)
+                    .map(|(entity, _, transform, ..)| (entity, transform.translation.truncate()))
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        if members.iter().any(|&(entity, _)| busy.contains(entity)) {
+            continue;
+        }
+        let centre = members.iter().map(|&(_, position)| position).sum::<Vec2>() / members.len() as f32;
+        if centre.distance(player_pos) > group.trigger_radius {
+            continue;
+        }
+
+        // Everyone turns to the middle of the group - for two, each other.
+        for &(entity, position) in &members {
+            if let Ok((.., mut frames, mut sprite)) = npcs.get_mut(entity) {
+                frames.facing_row = facing_toward(position, centre) as u32;
+                frames.frame(STANDING_PATTERN).apply(&mut sprite);
+            }
+            // Busy: an E press waits for the line being said (npc.rs).
+            commands.entity(entity).insert((InGroupConversation, Busy));
+        }
+
+        let span = tracer.as_ref().map(|tracer| {
+            let context = session.map(PlayerSessionTrace::as_context).unwrap_or_default();
+            let mut span = tracer.tracer().start_with_context("npc.group_conversation", &context);
+            let names: Vec<StringValue> = group.participants.iter().cloned().map(StringValue::from).collect();
+            span.set_attribute(KeyValue::new("group.id", group.dialogue_ref.clone()));
+            span.set_attribute(KeyValue::new("group.participants", Value::Array(Array::String(names))));
+            span.set_attribute(KeyValue::new("group.size", group.participants.len() as i64));
+            span.set_attribute(KeyValue::new("group.trigger_radius", group.trigger_radius as f64));
+            span.set_attribute(KeyValue::new("group.once", group.once));
+            span
+        });
+
+        log.0.entry(group.dialogue_ref.clone()).or_default().plays += 1;
+        info!("🗨️ Group conversation {} ({})", group.dialogue_ref, group.participants.join(", "));
+        // `problem` already checked every speaker is a participant.
+        let lines = conversation
+            .lines
+            .iter()
+            .filter_map(|line| {
+                let speaker = group.participants.iter().position(|name| *name == line.speaker)?;
+                Some((speaker, line.text.clone()))
+            })
+            .collect();
+        commands.insert_resource(ActiveGroupConversation {
+            id: group.dialogue_ref.clone(),
+            participants: members.iter().map(|&(entity, _)| entity).collect(),
+            lines,
+            line: 0,
+            bubble: None,
+            timer: Timer::from_seconds(GROUP_LINE_SECONDS, TimerMode::Once),
+            paused: false,
+            pauses: 0,
+            cooldown_secs: group.cooldown_seconds,
+            span,
+        });
+        return;
+    }
+}
+
+/// A new play session has overheard nothing.
+fn clear_group_log(mut log: ResMut<GroupConversationLog>) {
+    log.set_if_neq(GroupConversationLog::default());
+}
+
+fn advance_group_conversation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mode: Option<Res<State<Mode>>>,
+    pending: Option<Res<PendingInteraction>>,
+    active: Option<ResMut<ActiveGroupConversation>>,
+    mut log: ResMut<GroupConversationLog>,
+    npcs: Query<(), With<Npc>>,
+    bubbles: Query<(), With<ChatterBubble>>,
+    game_assets: Option<Res<GameAssets>>,
+) {
+    let Some(mut active) = active else { return };
+
+    // Someone left the map (or the map itself went): the conversation
+    // can't go on.
+    if active.participants.iter().any(|&entity| !npcs.contains(entity)) {
+        end_group_conversation(&mut commands, &mut active, &mut log, "abandoned");
+        return;
+    }
+
+    // The player waiting to talk to one of them: the line being said is
+    // finished, then the group breaks up for them. Waiting on anyone else
+    // pauses it, like a conversation would.
+    let waited_on = pending.as_ref().is_some_and(|pending| active.participants.contains(&pending.npc));
+    let exploring = mode.is_some_and(|mode| *mode.get() == Mode::Exploring);
+    if !exploring || (pending.is_some() && !waited_on) {
+        if !active.paused {
+            active.paused = true;
+            active.pauses += 1;
+            let line = active.line as i64;
+            if let Some(span) = active.span.as_mut() {
+                span.add_event("group.paused", vec![KeyValue::new("group.line", line)]);
+            }
+        }
+        // Entering dialogue clears chatter bubbles (ambient.rs) already;
+        // anything else leaves this one to take down.
+        if let Some(bubble) = active.bubble.take()
+            && bubbles.contains(bubble)
+        {
+            commands.entity(bubble).try_despawn();
+        }
+        return;
+    }
+    active.paused = false;
+
+    if active.bubble.is_none() {
+        if waited_on {
+            end_group_conversation(&mut commands, &mut active, &mut log, "interrupted");
+            return;
+        }
+        let (speaker, text) = active.lines[active.line].clone();
+        // Outlives the line, so it's this system (not ambient's expiry)
+        // that takes it down.
+        let bubble = spawn_chatter_bubble(
+            &mut commands,
+            active.participants[speaker],
+            text,
+            game_assets.as_deref(),
+            GROUP_LINE_SECONDS * 2.0,
+        );
+        active.bubble = Some(bubble);
+        active.timer.reset();
+        let line = active.line as i64;
+        if let Some(span) = active.span.as_mut() {
+            span.add_event("group.line", vec![
+                KeyValue::new("group.line", line),
+                KeyValue::new("group.speaker_index", speaker as i64),
+            ]);
+        }
+        return;
+    }
+
+    if !active.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if let Some(bubble) = active.bubble.take() {
+        commands.entity(bubble).try_despawn();
+    }
+    active.line += 1;
+    if active.line >= active.lines.len() {
+        end_group_conversation(&mut commands, &mut active, &mut log, "finished");
+    } else if waited_on {
+        end_group_conversation(&mut commands, &mut active, &mut log, "interrupted");
+    }
+}
+
+/// Close the span, start the cooldown and let the participants go.
+/// `outcome` is "finished", "interrupted" (the player waited to talk to
+/// one of them) or "abandoned".
+fn end_group_conversation(
+    commands: &mut Commands,
+    active: &mut ActiveGroupConversation,
+    log: &mut GroupConversationLog,
+    outcome: &'static str,
+) {
+    info!("🗨️ Group conversation {} {outcome}", active.id);
+    if let Some(bubble) = active.bubble.take() {
+        commands.entity(bubble).try_despawn();
+    }
+    for &entity in &active.participants {
+        if let Ok(mut participant) = commands.get_entity(entity) {
+            participant.try_remove::<(InGroupConversation, Busy)>();
+        }
+    }
+    log.0.entry(active.id.clone()).or_default().cooldown_left_secs = active.cooldown_secs;
+    if let Some(mut span) = active.span.take() {
+        span.set_attribute(KeyValue::new("group.outcome", outcome));
+        span.set_attribute(KeyValue::new("group.lines_shown", active.line.min(active.lines.len()) as i64));
+        span.set_attribute(KeyValue::new("group.pauses", active.pauses as i64));
+        span.end();
+    }
+    commands.remove_resource::<ActiveGroupConversation>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn participants_face_along_the_longer_axis() {
+        let facing = |from: Vec2, to: Vec2| facing_toward(from, to) as u32;
+        assert_eq!(facing(Vec2::ZERO, Vec2::new(48.0, 10.0)), NpcFacing::Right as u32);
+        assert_eq!(facing(Vec2::ZERO, Vec2::new(-48.0, 10.0)), NpcFacing::Left as u32);
+        assert_eq!(facing(Vec2::ZERO, Vec2::new(10.0, 48.0)), NpcFacing::Up as u32);
+        assert_eq!(facing(Vec2::ZERO, Vec2::new(10.0, -48.0)), NpcFacing::Down as u32);
+    }
+
+    #[test]
+    fn once_groups_never_replay_and_others_wait_out_their_cooldown() {
+        let group = |once| GroupDialogueData {
+            participants: vec!["Casey".into(), "Mando".into()],
+            trigger_radius: 200.0,
+            dialogue_ref: "standup".into(),
+            once,
+            cooldown_seconds: 60.0,
+        };
+        let mut log = GroupConversationLog::default();
+        assert!(log.ready(&group(true)));
+
+        log.0.insert("standup".into(), GroupConversationState { plays: 1, cooldown_left_secs: 0.0 });
+        assert!(!log.ready(&group(true)));
+        assert!(log.ready(&group(false)));
+
+        log.0.get_mut("standup").unwrap().cooldown_left_secs = 5.0;
+        assert!(!log.ready(&group(false)));
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use crate::game_state::Scene;
+
+/// Gameplay hooks for plugins outside this crate: Bevy messages written at
+/// the moment each thing happens, read with a `MessageReader` like any
+/// other. They are part of the public API (re-exported from
+/// `sregame::prelude`) - fields are added, not renamed - and the game reads
+/// them itself: the dialogue metrics are finalized from the ending
+/// `DialogueEnded` announces and the gameplay event log (game_events.rs)
+/// is fed from `NpcInteracted` and `MapChanged`, so what a plugin sees is
+/// what the game did.
+///
+/// ```ignore
+/// fn award(mut ended: MessageReader<DialogueEnded>, mut points: ResMut<Points>) {
+///     for ended in ended.read() {
+///         if ended.outcome == DialogueEndOutcome::Completed {
+///             points.0 += 5;
+///         }
+///     }
+/// }
+/// ```
+///
+/// examples/points_plugin.rs is a whole consumer.
+pub struct HooksPlugin;
+
+impl Plugin for HooksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<DialogueStarted>()
+            .add_message::<DialogueLineShown>()
+            .add_message::<DialogueTopicChosen>()
+            .add_message::<DialogueEnded>()
+            .add_message::<NpcInteracted>()
+            .add_message::<MapChanged>()
+            .add_systems(Update, detect_map_changes);
+    }
+}
+
+/// A conversation opened. `id` is its `dialogue::dialogue_id` (or the id
+/// its sender gave it); the other dialogue hooks carry the same one.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct DialogueStarted {
+    pub id: String,
+    /// The first box's speaker.
+    pub speaker: String,
+    /// The NPC being talked to - None for scripted scenes.
+    pub npc_entity: Option<Entity>,
+    /// Every box there is to read: the greeting's and, for a hub, all its
+    /// topics'.
+    pub lines: usize,
+}
+
+/// A box's whole text is on screen: typed out, or (`skipped`) hurried to
+/// the end with Space. `index` counts from 0 within what's being read - a
+/// hub's topic starts again from 0.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct DialogueLineShown {
+    pub id: String,
+    pub index: usize,
+    pub skipped: bool,
+}
+
+/// A hub's topic picked from its menu (`DialogueData::topics`), by the
+/// topic's id; its lines follow as `DialogueLineShown`s.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct DialogueTopicChosen {
+    pub id: String,
+    pub topic: String,
+}
+
+/// A conversation closed, however it closed - once: the first way out
+/// stands (Escape pressed as the last line is read past is too late).
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct DialogueEnded {
+    pub id: String,
+    pub outcome: DialogueEndOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogueEndOutcome {
+    /// Read to the end (or, for a hub, left with Goodbye).
+    Completed,
+    /// Already read, and skipped by holding Tab.
+    Skipped,
+    /// Closed with Escape.
+    Forced,
+}
+
+impl DialogueEndOutcome {
+    /// The name traces use (`dialogue.outcome`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DialogueEndOutcome::Completed => "completed",
+            DialogueEndOutcome::Skipped => "skipped",
+            DialogueEndOutcome::Forced => "forced",
+        }
+    }
+}
+
+/// The player started talking to an NPC (before its `DialogueStarted`).
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct NpcInteracted {
+    /// `Npc::id`, which survives the NPC being renamed.
+    pub id: String,
+    pub name: String,
+    pub entity: Entity,
+}
+
+/// The scene changed; `from` is None for the first map of a game.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct MapChanged {
+    pub from: Option<Scene>,
+    pub to: Scene,
+}
+
+fn detect_map_changes(
+    scene: Option<Res<State<Scene>>>,
+    mut changes: MessageWriter<MapChanged>,
+    mut last: Local<Option<Scene>>,
+) {
+    let current = scene.map(|scene| *scene.get());
+    if current == *last {
+        return;
+    }
+    // Leaving Playing (back to the title) isn't a map change.
+    if let Some(to) = current {
+        changes.write(MapChanged { from: *last, to });
+    }
+    *last = current;
+}
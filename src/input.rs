@@ -0,0 +1,653 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::state::state::{StateTransition, StateTransitionEvent, StateTransitionSystems};
+use std::collections::{HashMap, HashSet};
+use web_time::Instant;
+use crate::game_state::{GameState, Mode};
+
+/// Input latching across state changes. A key that is down when the game
+/// changes `GameState` or `Mode` belongs to whatever came before: holding W
+/// while a conversation opens, or the E press that opened it, must not act
+/// on the dialogue box (or a future menu/choice list) the moment it appears.
+/// On every such transition the held keys are latched, and `GameInput`
+/// reports them as neither pressed nor just-pressed until they have been
+/// released once.
+///
+/// Scene changes deliberately don't latch: holding W through a door should
+/// keep walking into the next room, as in the original.
+///
+/// Gamepads drive the same keys: each `GameAction`'s button (the d-pad, A
+/// to talk and continue, B to close) reads as its key through `GameInput`,
+/// latching included. `ActiveDevice` follows whichever was used last, for
+/// the on-screen prompts (glyphs.rs).
+///
+/// Both are rebindable (`InputMap`, edited on the Controls screen -
+/// controls_menu.rs). Gameplay keeps asking for the default keys; GameInput
+/// reads whatever is bound to that action now.
+pub struct GameInputPlugin;
+
+impl Plugin for GameInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputLatch>()
+            .init_resource::<KeyPressTimes>()
+            .init_resource::<InputMap>()
+            .init_resource::<ActiveDevice>()
+            .add_systems(
+                StateTransition,
+                latch_held_keys.after(StateTransitionSystems::EnterSchedules),
+            )
+            .add_systems(
+                PreUpdate,
+                (release_latches, record_key_presses, track_active_device).after(bevy::input::InputSystems),
+            );
+    }
+}
+
+/// Keys (and gamepad buttons) held at the last state transition and not
+/// yet released.
+#[derive(Resource, Default, Debug)]
+pub struct InputLatch {
+    latched: HashSet<KeyCode>,
+    buttons: HashSet<GamepadButton>,
+}
+
+impl InputLatch {
+    pub fn is_latched(&self, key: KeyCode) -> bool {
+        self.latched.contains(&key)
+    }
+}
+
+fn latch_held_keys(
+    mut game_transitions: MessageReader<StateTransitionEvent<GameState>>,
+    mut mode_transitions: MessageReader<StateTransitionEvent<Mode>>,
+    keyboard: Option<Res<ButtonInput<KeyCode>>>,
+    gamepads: Query<&Gamepad>,
+    mut latch: ResMut<InputLatch>,
+) {
+    let transitioned = game_transitions.read().count() + mode_transitions.read().count() > 0;
+    if !transitioned {
+        return;
+    }
+    if let Some(keyboard) = keyboard {
+        latch.latched.extend(keyboard.get_pressed().copied());
+    }
+    for gamepad in &gamepads {
+        latch.buttons.extend(gamepad.get_pressed().copied());
+    }
+}
+
+/// When each key was last pressed, as the game first saw it: the frame's
+/// input update, before any gameplay system ran. The start of an input
+/// latency measurement (input_latency.rs).
+#[derive(Resource, Default, Debug)]
+pub struct KeyPressTimes {
+    pressed: HashMap<KeyCode, Instant>,
+}
+
+/// A button press counts as a press of its action's key.
+fn record_key_presses(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    map: Res<InputMap>,
+    mut times: ResMut<KeyPressTimes>,
+) {
+    let now = Instant::now();
+    times.pressed.extend(keyboard.get_just_pressed().map(|&key| (key, now)));
+    for gamepad in &gamepads {
+        let actions = GameAction::ALL.into_iter().filter(|&action| gamepad.just_pressed(map.button(action)));
+        times.pressed.extend(actions.map(|action| (map.key(action), now)));
+    }
+}
+
+fn release_latches(keyboard: Res<ButtonInput<KeyCode>>, gamepads: Query<&Gamepad>, mut latch: ResMut<InputLatch>) {
+    if !latch.latched.is_empty() {
+        latch.latched.retain(|&key| keyboard.pressed(key));
+    }
+    if !latch.buttons.is_empty() {
+        latch.buttons.retain(|&button| gamepads.iter().any(|gamepad| gamepad.pressed(button)));
+    }
+}
+
+/// What the player is playing with: whatever they last pressed a button on.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ActiveDevice {
+    #[default]
+    Keyboard,
+    Gamepad(GamepadStyle),
+}
+
+/// Whose button names and icons a gamepad gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadStyle {
+    Xbox,
+    PlayStation,
+}
+
+impl GamepadStyle {
+    /// PlayStation pads by Sony's USB vendor id; everything else reads as
+    /// the Xbox layout, which is what most PC pads copy.
+    pub fn of(gamepad: &Gamepad) -> Self {
+        const SONY: u16 = 0x054c;
+        match gamepad.vendor_id() {
+            Some(SONY) => GamepadStyle::PlayStation,
+            _ => GamepadStyle::Xbox,
+        }
+    }
+}
+
+fn track_active_device(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut device: ResMut<ActiveDevice>,
+) {
+    let used = if let Some(gamepad) = gamepads.iter().find(|gamepad| gamepad.get_just_pressed().next().is_some()) {
+        ActiveDevice::Gamepad(GamepadStyle::of(gamepad))
+    } else if keyboard.get_just_pressed().next().is_some() {
+        ActiveDevice::Keyboard
+    } else {
+        return;
+    };
+    device.set_if_neq(used);
+}
+
+/// The resources `GameInput` reads, for plugins that take it without
+/// `GameInputPlugin` in the app. Nothing gets latched then, which is just
+/// the raw keyboard.
+pub fn init_game_input(app: &mut App) {
+    app.init_resource::<InputLatch>().init_resource::<KeyPressTimes>().init_resource::<InputMap>();
+}
+
+/// Keyboard state as gameplay should see it: `ButtonInput<KeyCode>` minus
+/// latched keys (see `GameInputPlugin`), plus the gamepad buttons standing
+/// in for them. Read this instead of the raw resource in anything that
+/// reacts to a key right after a state change.
+///
+/// Keys are asked for by their default: `pressed(KeyCode::KeyE)` is
+/// Interact, whatever `InputMap` has it on now.
+#[derive(SystemParam)]
+pub struct GameInput<'w, 's> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    latch: Res<'w, InputLatch>,
+    press_times: Res<'w, KeyPressTimes>,
+    map: Res<'w, InputMap>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+impl GameInput<'_, '_> {
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        let bound = self.bound_key(key);
+        (self.keyboard.pressed(bound) && !self.latch.is_latched(bound))
+            || self.button_for(key).is_some_and(|button| self.gamepads.iter().any(|gamepad| gamepad.pressed(button)))
+    }
+
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        let bound = self.bound_key(key);
+        (self.keyboard.just_pressed(bound) && !self.latch.is_latched(bound))
+            || self.button_for(key).is_some_and(|button| self.gamepads.iter().any(|gamepad| gamepad.just_pressed(button)))
+    }
+
+    /// The key to read for `key`: an action's default key means whatever
+    /// the action is bound to; every other key is itself.
+    fn bound_key(&self, key: KeyCode) -> KeyCode {
+        match GameAction::ALL.into_iter().find(|action| action.default_key() == key) {
+            Some(action) => self.map.key(action),
+            None => key,
+        }
+    }
+
+    /// `action` just pressed on any of its keys or its gamepad button -
+    /// one signal however the player asked.
+    pub fn action_just_pressed(&self, action: GameAction) -> bool {
+        action.keys().iter().any(|&key| self.just_pressed(key))
+    }
+
+    /// `action` held on any of its keys or its gamepad button.
+    pub fn action_pressed(&self, action: GameAction) -> bool {
+        action.keys().iter().any(|&key| self.pressed(key))
+    }
+
+    /// The unlatched gamepad button that stands in for `key`, if any.
+    fn button_for(&self, key: KeyCode) -> Option<GamepadButton> {
+        GameAction::for_key(key)
+            .map(|action| self.map.button(action))
+            .filter(|button| !self.latch.buttons.contains(button))
+    }
+
+    /// When `key` was last pressed (see `KeyPressTimes`); None if it never
+    /// has been.
+    pub fn pressed_at(&self, key: KeyCode) -> Option<Instant> {
+        self.press_times.pressed.get(&self.bound_key(key)).copied()
+    }
+}
+
+/// The player-facing verbs the game responds to, independent of which key
+/// produces them. Gameplay systems read keys through `GameInput`; this names
+/// the vocabulary so tooling (testing::TestGame) and docs can talk about
+/// "interact" rather than "KeyE".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Talk to the NPC in range / use an action exit or a terminal (E).
+    Interact,
+    /// Skip the typewriter or go to the next dialogue box (Space).
+    Advance,
+    /// Force-close dialogue, or close the dashboard (Escape).
+    Cancel,
+    /// Hold to skip a dialogue you've already read (Tab).
+    Skip,
+    /// Open or close the dialogue log (L, or a tap of Tab - see
+    /// dialogue_history.rs).
+    History,
+    /// Hold to run (Shift).
+    Sprint,
+}
+
+impl GameAction {
+    pub const ALL: [GameAction; 10] = [
+        GameAction::MoveUp,
+        GameAction::MoveDown,
+        GameAction::MoveLeft,
+        GameAction::MoveRight,
+        GameAction::Interact,
+        GameAction::Advance,
+        GameAction::Cancel,
+        GameAction::Skip,
+        GameAction::History,
+        GameAction::Sprint,
+    ];
+
+    /// The four directions: "moving" is any of them held.
+    pub const MOVEMENT: [GameAction; 4] = [
+        GameAction::MoveUp,
+        GameAction::MoveDown,
+        GameAction::MoveLeft,
+        GameAction::MoveRight,
+    ];
+
+    /// Its name in the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameAction::MoveUp => "move_up",
+            GameAction::MoveDown => "move_down",
+            GameAction::MoveLeft => "move_left",
+            GameAction::MoveRight => "move_right",
+            GameAction::Interact => "interact",
+            GameAction::Advance => "advance",
+            GameAction::Cancel => "cancel",
+            GameAction::Skip => "skip",
+            GameAction::History => "history",
+            GameAction::Sprint => "sprint",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Its name on the Controls screen.
+    pub fn label(self) -> &'static str {
+        match self {
+            GameAction::MoveUp => "Move up",
+            GameAction::MoveDown => "Move down",
+            GameAction::MoveLeft => "Move left",
+            GameAction::MoveRight => "Move right",
+            GameAction::Interact => "Talk / use",
+            GameAction::Advance => "Continue",
+            GameAction::Cancel => "Close",
+            GameAction::Skip => "Skip",
+            GameAction::History => "Dialogue log",
+            GameAction::Sprint => "Run",
+        }
+    }
+
+    /// Whether the two can be on the same key or button: Interact only
+    /// acts while exploring, Advance, Skip and History only in a
+    /// conversation, so one press never means both. (A and South are
+    /// Interact and Advance out of the box.)
+    pub fn may_share(self, other: GameAction) -> bool {
+        let in_conversation = |action| matches!(action, GameAction::Advance | GameAction::Skip | GameAction::History);
+        (self == GameAction::Interact && in_conversation(other))
+            || (other == GameAction::Interact && in_conversation(self))
+    }
+
+    /// The primary key bound to this action. Movement also answers to the
+    /// arrow keys and Advance to Enter - see player.rs and dialogue.rs.
+    /// MoveUp/MoveDown double as the cursor keys of a topic menu or a
+    /// box's choices.
+    pub fn default_key(self) -> KeyCode {
+        match self {
+            GameAction::MoveUp => KeyCode::KeyW,
+            GameAction::MoveDown => KeyCode::KeyS,
+            GameAction::MoveLeft => KeyCode::KeyA,
+            GameAction::MoveRight => KeyCode::KeyD,
+            GameAction::Interact => KeyCode::KeyE,
+            GameAction::Advance => KeyCode::Space,
+            GameAction::Cancel => KeyCode::Escape,
+            GameAction::Skip => KeyCode::Tab,
+            GameAction::History => KeyCode::KeyL,
+            GameAction::Sprint => KeyCode::ShiftLeft,
+        }
+    }
+
+    /// Every key bound to this action: `default_key`, then the secondary
+    /// ones `for_key` knows.
+    pub fn keys(self) -> &'static [KeyCode] {
+        match self {
+            GameAction::MoveUp => &[KeyCode::KeyW, KeyCode::ArrowUp],
+            GameAction::MoveDown => &[KeyCode::KeyS, KeyCode::ArrowDown],
+            GameAction::MoveLeft => &[KeyCode::KeyA, KeyCode::ArrowLeft],
+            GameAction::MoveRight => &[KeyCode::KeyD, KeyCode::ArrowRight],
+            GameAction::Interact => &[KeyCode::KeyE],
+            GameAction::Advance => &[KeyCode::Space, KeyCode::Enter],
+            GameAction::Cancel => &[KeyCode::Escape],
+            GameAction::Skip => &[KeyCode::Tab],
+            GameAction::History => &[KeyCode::KeyL],
+            GameAction::Sprint => &[KeyCode::ShiftLeft],
+        }
+    }
+
+    /// The action a key means, secondary keys (arrows, Enter) included.
+    pub fn for_key(key: KeyCode) -> Option<GameAction> {
+        match key {
+            KeyCode::ArrowUp => Some(GameAction::MoveUp),
+            KeyCode::ArrowDown => Some(GameAction::MoveDown),
+            KeyCode::ArrowLeft => Some(GameAction::MoveLeft),
+            KeyCode::ArrowRight => Some(GameAction::MoveRight),
+            KeyCode::Enter => Some(GameAction::Advance),
+            key => GameAction::ALL.into_iter().find(|action| action.default_key() == key),
+        }
+    }
+
+    /// The gamepad button bound to this action: the d-pad moves, South (A,
+    /// or Cross) talks and continues, East closes, West skips, North opens
+    /// the dialogue log, the left shoulder runs.
+    pub fn gamepad_button(self) -> GamepadButton {
+        match self {
+            GameAction::MoveUp => GamepadButton::DPadUp,
+            GameAction::MoveDown => GamepadButton::DPadDown,
+            GameAction::MoveLeft => GamepadButton::DPadLeft,
+            GameAction::MoveRight => GamepadButton::DPadRight,
+            GameAction::Interact | GameAction::Advance => GamepadButton::South,
+            GameAction::Cancel => GamepadButton::East,
+            GameAction::Skip => GamepadButton::West,
+            GameAction::History => GamepadButton::North,
+            GameAction::Sprint => GamepadButton::LeftTrigger,
+        }
+    }
+}
+
+/// A key or a gamepad button, as bound to an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButton),
+}
+
+/// What each action is bound to: one key and one gamepad button. Starts
+/// as `default_key` and `gamepad_button`; the Controls screen
+/// (controls_menu.rs) edits it and settings.rs keeps it across runs.
+///
+/// The arrow keys and Enter aren't in here - they always answer for the
+/// moves and Advance as well - and neither is Escape, which stays Cancel's
+/// so there is always a way back out of a menu.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct InputMap {
+    keys: HashMap<GameAction, KeyCode>,
+    buttons: HashMap<GameAction, GamepadButton>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            keys: GameAction::ALL.into_iter().map(|action| (action, action.default_key())).collect(),
+            buttons: GameAction::ALL.into_iter().map(|action| (action, action.gamepad_button())).collect(),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn key(&self, action: GameAction) -> KeyCode {
+        self.keys.get(&action).copied().unwrap_or(action.default_key())
+    }
+
+    pub fn button(&self, action: GameAction) -> GamepadButton {
+        self.buttons.get(&action).copied().unwrap_or(action.gamepad_button())
+    }
+
+    /// `action`'s binding of the same kind as `like`: its key for a key,
+    /// its button for a button.
+    pub fn binding(&self, action: GameAction, like: Binding) -> Binding {
+        match like {
+            Binding::Key(_) => Binding::Key(self.key(action)),
+            Binding::Button(_) => Binding::Button(self.button(action)),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Why `binding` can't go to `action`, or `None` if it can.
+    pub fn refusal(action: GameAction, binding: Binding) -> Option<&'static str> {
+        match binding {
+            Binding::Key(_) if action == GameAction::Cancel => Some("Close stays on Esc"),
+            Binding::Key(KeyCode::Escape) => Some("Esc is kept for backing out"),
+            Binding::Key(KeyCode::Enter | KeyCode::ArrowUp | KeyCode::ArrowDown | KeyCode::ArrowLeft | KeyCode::ArrowRight) => {
+                Some("Enter and the arrow keys already work as well")
+            }
+            Binding::Key(key) if key_label(key).is_none() => Some("That key can't be bound"),
+            Binding::Button(button) if button_label(button, GamepadStyle::Xbox).is_none() => {
+                Some("That button can't be bound")
+            }
+            _ => None,
+        }
+    }
+
+    /// The action already on `binding` that `action` can't share it with.
+    pub fn conflict(&self, action: GameAction, binding: Binding) -> Option<GameAction> {
+        GameAction::ALL
+            .into_iter()
+            .filter(|&other| other != action && !action.may_share(other))
+            .find(|&other| self.binding(other, binding) == binding)
+    }
+
+    /// Put `binding` on `action`. If it conflicts, the two swap: the other
+    /// action takes over what `action` had. Returns that action. Check
+    /// `refusal` first; this doesn't.
+    pub fn bind(&mut self, action: GameAction, binding: Binding) -> Option<GameAction> {
+        let conflict = self.conflict(action, binding);
+        if let Some(other) = conflict {
+            self.set(other, self.binding(action, binding));
+        }
+        self.set(action, binding);
+        conflict
+    }
+
+    /// Put `binding` on `action` as is, conflict or not - for loading a
+    /// settings file.
+    pub(crate) fn set(&mut self, action: GameAction, binding: Binding) {
+        match binding {
+            Binding::Key(key) => {
+                self.keys.insert(action, key);
+            }
+            Binding::Button(button) => {
+                self.buttons.insert(action, button);
+            }
+        }
+    }
+}
+
+/// What a bindable key is called on screen; `None` for keys that can't be
+/// bound at all. Escape, Enter and the arrows have names, for prompts,
+/// though `InputMap::refusal` keeps them off other actions.
+pub fn key_label(key: KeyCode) -> Option<&'static str> {
+    KEY_LABELS.iter().find(|&&(known, _)| known == key).map(|&(_, label)| label)
+}
+
+/// The key a settings file names (`KeyCode`'s variant, "KeyQ").
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_LABELS.iter().map(|&(key, _)| key).find(|key| format!("{key:?}") == name)
+}
+
+const KEY_LABELS: &[(KeyCode, &str)] = &[
+    (KeyCode::KeyA, "A"), (KeyCode::KeyB, "B"), (KeyCode::KeyC, "C"), (KeyCode::KeyD, "D"),
+    (KeyCode::KeyE, "E"), (KeyCode::KeyF, "F"), (KeyCode::KeyG, "G"), (KeyCode::KeyH, "H"),
+    (KeyCode::KeyI, "I"), (KeyCode::KeyJ, "J"), (KeyCode::KeyK, "K"), (KeyCode::KeyL, "L"),
+    (KeyCode::KeyM, "M"), (KeyCode::KeyN, "N"), (KeyCode::KeyO, "O"), (KeyCode::KeyP, "P"),
+    (KeyCode::KeyQ, "Q"), (KeyCode::KeyR, "R"), (KeyCode::KeyS, "S"), (KeyCode::KeyT, "T"),
+    (KeyCode::KeyU, "U"), (KeyCode::KeyV, "V"), (KeyCode::KeyW, "W"), (KeyCode::KeyX, "X"),
+    (KeyCode::KeyY, "Y"), (KeyCode::KeyZ, "Z"),
+    (KeyCode::Digit0, "0"), (KeyCode::Digit1, "1"), (KeyCode::Digit2, "2"), (KeyCode::Digit3, "3"),
+    (KeyCode::Digit4, "4"), (KeyCode::Digit5, "5"), (KeyCode::Digit6, "6"), (KeyCode::Digit7, "7"),
+    (KeyCode::Digit8, "8"), (KeyCode::Digit9, "9"),
+    (KeyCode::Space, "Space"), (KeyCode::Tab, "Tab"), (KeyCode::Backspace, "Backspace"),
+    (KeyCode::ShiftLeft, "Shift"), (KeyCode::ShiftRight, "Right Shift"),
+    (KeyCode::ControlLeft, "Ctrl"), (KeyCode::ControlRight, "Right Ctrl"),
+    (KeyCode::AltLeft, "Alt"), (KeyCode::AltRight, "Right Alt"),
+    (KeyCode::Comma, ","), (KeyCode::Period, "."), (KeyCode::Slash, "/"), (KeyCode::Semicolon, ";"),
+    (KeyCode::Quote, "'"), (KeyCode::BracketLeft, "["), (KeyCode::BracketRight, "]"),
+    (KeyCode::Minus, "-"), (KeyCode::Equal, "="), (KeyCode::Backquote, "`"), (KeyCode::Backslash, "\\"),
+    (KeyCode::Escape, "Esc"), (KeyCode::Enter, "Enter"),
+    (KeyCode::ArrowUp, "Up"), (KeyCode::ArrowDown, "Down"), (KeyCode::ArrowLeft, "Left"), (KeyCode::ArrowRight, "Right"),
+];
+
+/// What a bindable button is called on `style`'s pads; `None` for
+/// buttons that can't be bound (the sticks' clicks, Mode).
+pub fn button_label(button: GamepadButton, style: GamepadStyle) -> Option<&'static str> {
+    let (xbox, playstation) = match button {
+        GamepadButton::South => ("A", "Cross"),
+        GamepadButton::East => ("B", "Circle"),
+        GamepadButton::West => ("X", "Square"),
+        GamepadButton::North => ("Y", "Triangle"),
+        GamepadButton::LeftTrigger => ("LB", "L1"),
+        GamepadButton::RightTrigger => ("RB", "R1"),
+        GamepadButton::LeftTrigger2 => ("LT", "L2"),
+        GamepadButton::RightTrigger2 => ("RT", "R2"),
+        GamepadButton::Select => ("View", "Share"),
+        GamepadButton::Start => ("Menu", "Options"),
+        GamepadButton::DPadUp => ("D-pad up", "D-pad up"),
+        GamepadButton::DPadDown => ("D-pad down", "D-pad down"),
+        GamepadButton::DPadLeft => ("D-pad left", "D-pad left"),
+        GamepadButton::DPadRight => ("D-pad right", "D-pad right"),
+        _ => return None,
+    };
+    Some(match style {
+        GamepadStyle::Xbox => xbox,
+        GamepadStyle::PlayStation => playstation,
+    })
+}
+
+/// The button a settings file names (`GamepadButton`'s variant, "North").
+pub fn button_from_name(name: &str) -> Option<GamepadButton> {
+    GamepadButton::all()
+        .into_iter()
+        .filter(|&button| button_label(button, GamepadStyle::Xbox).is_some())
+        .find(|button| format!("{button:?}") == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::state::app::StatesPlugin;
+
+    fn app_in_exploring() -> App {
+        let mut app = App::new();
+        app.add_plugins((StatesPlugin, GameInputPlugin))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_state::<GameState>()
+            .add_sub_state::<crate::game_state::Scene>()
+            .add_sub_state::<Mode>();
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+        app
+    }
+
+    fn interact_just_pressed(app: &mut App) -> bool {
+        app.world_mut()
+            .run_system_once(|input: GameInput| input.just_pressed(KeyCode::KeyE))
+            .unwrap()
+    }
+
+    #[test]
+    fn keys_held_across_a_mode_change_stay_latched_until_released() {
+        let mut app = app_in_exploring();
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyE);
+        app.world_mut().resource_mut::<NextState<Mode>>().set(Mode::Dialogue);
+        app.update();
+
+        // Still just_pressed in the raw input this frame, but latched.
+        assert!(app.world().resource::<InputLatch>().is_latched(KeyCode::KeyE));
+        assert!(!interact_just_pressed(&mut app));
+
+        // Release, then press again: a fresh press goes through.
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().release(KeyCode::KeyE);
+        app.update();
+        assert!(!app.world().resource::<InputLatch>().is_latched(KeyCode::KeyE));
+        let mut keyboard = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keyboard.clear();
+        keyboard.press(KeyCode::KeyE);
+        assert!(interact_just_pressed(&mut app));
+    }
+
+    #[test]
+    fn every_key_of_an_action_means_that_action() {
+        for action in GameAction::ALL {
+            assert_eq!(action.keys()[0], action.default_key());
+            for &key in action.keys() {
+                assert_eq!(GameAction::for_key(key), Some(action), "{key:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn a_conflicting_binding_swaps_with_the_action_that_had_it() {
+        let mut map = InputMap::default();
+        assert_eq!(map.conflict(GameAction::Interact, Binding::Key(KeyCode::KeyL)), Some(GameAction::History));
+        assert_eq!(map.bind(GameAction::Interact, Binding::Key(KeyCode::KeyL)), Some(GameAction::History));
+        assert_eq!(map.key(GameAction::Interact), KeyCode::KeyL);
+        assert_eq!(map.key(GameAction::History), KeyCode::KeyE);
+
+        // Advance onto Skip's X: Skip takes A, which it may share with Interact.
+        assert_eq!(map.bind(GameAction::Advance, Binding::Button(GamepadButton::West)), Some(GameAction::Skip));
+        assert_eq!(map.button(GameAction::Skip), GamepadButton::South);
+        assert_eq!(map.conflict(GameAction::Interact, Binding::Button(GamepadButton::South)), None);
+
+        assert_eq!(map.bind(GameAction::MoveUp, Binding::Key(KeyCode::KeyI)), None);
+        assert_eq!(map.key(GameAction::MoveUp), KeyCode::KeyI);
+        assert!(!map.is_default());
+    }
+
+    #[test]
+    fn escape_enter_and_the_arrows_stay_where_they_are() {
+        let refused = |action, key| InputMap::refusal(action, Binding::Key(key)).is_some();
+        assert!(refused(GameAction::Interact, KeyCode::Escape));
+        assert!(refused(GameAction::Skip, KeyCode::Enter));
+        assert!(refused(GameAction::MoveLeft, KeyCode::ArrowRight));
+        assert!(refused(GameAction::Cancel, KeyCode::KeyQ));
+        assert!(refused(GameAction::Interact, KeyCode::F12));
+        assert!(!refused(GameAction::Interact, KeyCode::KeyQ));
+        assert_eq!(InputMap::refusal(GameAction::Cancel, Binding::Button(GamepadButton::Start)), None);
+    }
+
+    #[test]
+    fn a_rebound_key_reads_as_the_default_one() {
+        let mut app = app_in_exploring();
+        app.world_mut().resource_mut::<InputMap>().bind(GameAction::Interact, Binding::Key(KeyCode::KeyQ));
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyE);
+        assert!(!interact_just_pressed(&mut app));
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyQ);
+        assert!(interact_just_pressed(&mut app));
+    }
+
+    #[test]
+    fn keys_pressed_without_a_transition_are_not_latched() {
+        let mut app = app_in_exploring();
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyE);
+        app.update();
+        assert!(interact_just_pressed(&mut app));
+    }
+}
@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Histogram, Meter};
+use opentelemetry::trace::Span;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use web_time::Instant;
+use crate::game_state::Mode;
+use crate::input::{GameAction, GameInput};
+use crate::instrumentation::{MetricsBundle, PlayerSessionTrace, init_metrics};
+use crate::player::{Player, PlayerMovementSet};
+
+/// Input latency: how long from a keypress to the first visible effect of
+/// it, exported as `game.input.latency{action}` with a rolling p95 in the
+/// F3 overlay (debug_overlay.rs).
+///
+/// - `interact`: E to the dialogue box's first frame. The press time rides
+///   on the `DialogueRequest` (npc.rs), so a conversation queued behind a
+///   busy NPC counts the wait too - that's what the player sat through.
+///   Recorded by dialogue.rs, on the `dialogue.session` span as well.
+/// - `move`: a movement key pressed from standstill to the first frame the
+///   player's transform changes. Recorded here, as an event on the session
+///   span. Walking into a wall and letting go measures nothing.
+///
+/// Both start from `input::KeyPressTimes`, the frame's input update.
+pub struct InputLatencyPlugin;
+
+impl Plugin for InputLatencyPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        app.init_resource::<InputLatency>().add_systems(
+            Update,
+            (
+                start_movement_latency.before(PlayerMovementSet),
+                finish_movement_latency.after(PlayerMovementSet),
+            )
+                .run_if(in_state(Mode::Exploring)),
+        );
+        init_metrics::<LatencyMetrics>(app);
+    }
+}
+
+/// `game.input.latency`, recorded through `InputLatency::record`.
+#[derive(Resource)]
+pub struct LatencyMetrics {
+    pub latency: Histogram<f64>,
+}
+
+impl MetricsBundle for LatencyMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            latency: meter
+                .f64_histogram("game.input.latency")
+                .with_description("Keypress to first visible effect, by action (see input_latency.rs)")
+                .with_unit("s")
+                .build(),
+        }
+    }
+}
+
+/// Samples kept per action for the overlay's p95.
+const WINDOW: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyAction {
+    Interact,
+    Move,
+}
+
+impl LatencyAction {
+    /// The metric's `action` label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LatencyAction::Interact => "interact",
+            LatencyAction::Move => "move",
+        }
+    }
+}
+
+/// The latest samples of each action, for the overlay.
+#[derive(Resource, Default, Debug)]
+pub struct InputLatency {
+    samples: HashMap<LatencyAction, VecDeque<Duration>>,
+    movement: MovementLatency,
+}
+
+#[derive(Default, Debug)]
+enum MovementLatency {
+    /// No movement key held.
+    #[default]
+    Idle,
+    /// Pressed from standstill: when, and where the player stood.
+    Waiting { pressed_at: Instant, from: Vec3 },
+    /// Measured (or not measurable); nothing more until every key is up.
+    Held,
+}
+
+impl InputLatency {
+    /// Keep `latency` and export it.
+    pub fn record(&mut self, action: LatencyAction, latency: Duration, metrics: Option<&LatencyMetrics>) {
+        let samples = self.samples.entry(action).or_default();
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+        if let Some(metrics) = metrics {
+            metrics.latency.record(latency.as_secs_f64(), &[KeyValue::new("action", action.as_str())]);
+        }
+    }
+
+    /// 95th percentile of the last `WINDOW` samples; None before the first.
+    pub fn p95(&self, action: LatencyAction) -> Option<Duration> {
+        let mut samples: Vec<Duration> = self.samples.get(&action)?.iter().copied().collect();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        Some(samples[(samples.len() - 1) * 95 / 100])
+    }
+}
+
+fn start_movement_latency(
+    keyboard: GameInput,
+    player: Query<&Transform, With<Player>>,
+    mut latency: ResMut<InputLatency>,
+) {
+    // Each direction's keys as GameInput reads them: through InputMap, so
+    // a rebound direction is timed on its new key.
+    let held: Vec<KeyCode> = GameAction::MOVEMENT
+        .iter()
+        .flat_map(|action| action.keys())
+        .copied()
+        .filter(|&key| keyboard.pressed(key))
+        .collect();
+    if held.is_empty() {
+        latency.movement = MovementLatency::Idle;
+        return;
+    }
+    if !matches!(latency.movement, MovementLatency::Idle) {
+        return;
+    }
+    // A key still held from before (out of a conversation, say) isn't a
+    // press from standstill.
+    let pressed_at = held.iter().filter_map(|&key| keyboard.pressed_at(key)).min();
+    latency.movement = match (player.single(), pressed_at) {
+        (Ok(transform), Some(pressed_at)) if held.iter().all(|&key| keyboard.just_pressed(key)) => {
+            MovementLatency::Waiting { pressed_at, from: transform.translation }
+        }
+        _ => MovementLatency::Held,
+    };
+}
+
+fn finish_movement_latency(
+    mut player: Query<(&Transform, Option<&mut PlayerSessionTrace>), With<Player>>,
+    mut latency: ResMut<InputLatency>,
+    metrics: Option<Res<LatencyMetrics>>,
+) {
+    let MovementLatency::Waiting { pressed_at, from } = latency.movement else {
+        return;
+    };
+    let Ok((transform, session_trace)) = player.single_mut() else {
+        return;
+    };
+    if transform.translation == from {
+        return;
+    }
+    let elapsed = pressed_at.elapsed();
+    latency.movement = MovementLatency::Held;
+    latency.record(LatencyAction::Move, elapsed, metrics.as_deref());
+    if let Some(mut session_trace) = session_trace {
+        session_trace.span.add_event(
+            "input.latency",
+            vec![
+                KeyValue::new("input.action", LatencyAction::Move.as_str()),
+                KeyValue::new("input.latency_ms", elapsed.as_secs_f64() * 1000.0),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn p95_covers_the_last_hundred_samples() {
+        let mut latency = InputLatency::default();
+        assert_eq!(latency.p95(LatencyAction::Move), None);
+
+        for millis in 1..=100 {
+            latency.record(LatencyAction::Move, ms(millis), None);
+        }
+        assert_eq!(latency.p95(LatencyAction::Move), Some(ms(95)));
+        assert_eq!(latency.p95(LatencyAction::Interact), None, "kept per action");
+
+        // Fifty fast frames push the slowest fifty out of the window.
+        for _ in 0..50 {
+            latency.record(LatencyAction::Move, ms(1), None);
+        }
+        assert_eq!(latency.p95(LatencyAction::Move), Some(ms(95)));
+        for _ in 0..50 {
+            latency.record(LatencyAction::Move, ms(1), None);
+        }
+        assert_eq!(latency.p95(LatencyAction::Move), Some(ms(1)));
+    }
+}
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use opentelemetry::global::{BoxedSpan, BoxedTracer};
-use opentelemetry::trace::{Span as _, SpanContext, Tracer};
+use opentelemetry::trace::{Link, Span as _, SpanContext, Tracer};
 use opentelemetry::{Context as OtelContext, KeyValue};
+use opentelemetry::metrics::Meter;
+use std::marker::PhantomData;
 #[cfg(not(target_arch = "wasm32"))]
 use opentelemetry::global;
 #[cfg(not(target_arch = "wasm32"))]
@@ -28,17 +30,80 @@ pub struct GameTracer {
 }
 
 impl GameTracer {
+    pub fn new(tracer: BoxedTracer) -> Self {
+        Self { tracer }
+    }
+
     pub fn tracer(&self) -> &BoxedTracer {
         &self.tracer
     }
 }
 
-/// Bevy resource holding the OpenTelemetry meter for metrics
-#[derive(Resource)]
+/// The game's OpenTelemetry meter. It holds no instruments itself: each
+/// feature declares its own next to the code that records them - a
+/// `MetricsBundle` resource (`DialogueMetrics` in dialogue.rs,
+/// `ContentMetrics` in content_errors.rs, ...) that the feature's plugin
+/// registers with `init_metrics`. A feature that isn't in the app creates
+/// none, and a new metric never means editing this module.
+#[derive(Resource, Clone)]
 pub struct GameMeter {
-    pub dialogue_reading_speed: opentelemetry::metrics::Histogram<f64>,
-    pub interactions_total: opentelemetry::metrics::Counter<u64>,
-    pub dialogue_lines_read: opentelemetry::metrics::Counter<u64>,
+    meter: Meter,
+}
+
+impl GameMeter {
+    /// Shared by the OTLP setup below and the in-memory exporters of
+    /// testing::TestGame, so both see the same metric names.
+    pub fn new(meter: &Meter) -> Self {
+        Self { meter: meter.clone() }
+    }
+
+    pub fn meter(&self) -> &Meter {
+        &self.meter
+    }
+}
+
+/// One feature's instruments, as a resource of their own. Name metrics
+/// `game.<feature>.<what>` and say in the description which module
+/// records them.
+///
+/// ```ignore
+/// #[derive(Resource)]
+/// pub struct TutorialMetrics {
+///     pub steps: Counter<u64>,
+/// }
+///
+/// impl MetricsBundle for TutorialMetrics {
+///     fn new(meter: &Meter) -> Self {
+///         Self { steps: meter.u64_counter("game.tutorial.step_completed").build() }
+///     }
+/// }
+///
+/// // In the plugin's build, then `Option<Res<TutorialMetrics>>` to record.
+/// crate::instrumentation::init_metrics::<TutorialMetrics>(app);
+/// ```
+pub trait MetricsBundle: Resource + Sized {
+    /// Create the instruments on `meter`.
+    fn new(meter: &Meter) -> Self;
+}
+
+/// Have `M` as a resource once the app starts - if it has a `GameMeter`
+/// (no telemetry, no instruments). Every plugin that records into `M`
+/// calls this; the first one registers it.
+pub fn init_metrics<M: MetricsBundle>(app: &mut App) {
+    if app.world().contains_resource::<MetricsRegistered<M>>() {
+        return;
+    }
+    app.insert_resource(MetricsRegistered::<M>(PhantomData))
+        .add_systems(PreStartup, create_metrics::<M>);
+}
+
+#[derive(Resource)]
+struct MetricsRegistered<M>(PhantomData<fn() -> M>);
+
+fn create_metrics<M: MetricsBundle>(mut commands: Commands, meter: Option<Res<GameMeter>>) {
+    if let Some(meter) = meter {
+        commands.insert_resource(M::new(meter.meter()));
+    }
 }
 
 /// Component attached to the player entity to track the session-level trace
@@ -74,24 +139,92 @@ impl PlayerSessionTrace {
     }
 }
 
-/// Resource for tracking active dialogue session
-#[derive(Resource)]
+/// A dialogue session's telemetry: its `dialogue.session` span and what
+/// was read under it (`DialogueSession::telemetry`).
 pub struct ActiveDialogue {
     pub span: BoxedSpan,
     pub start_time: Instant,
     pub speaker: String,
+    /// `DialogueSegment::speaker_id` of `speaker`: what its metrics key on.
+    pub speaker_id: String,
     pub chars_read: usize,
+    /// The NPC being talked to, by `Npc::id` - None for scripted scenes.
+    pub npc: Option<String>,
+}
+
+impl ActiveDialogue {
+    /// End the session span, remembering it as this NPC's latest
+    /// conversation. `outcome` is a `DialogueEndOutcome` name (hooks.rs).
+    pub fn end(&mut self, outcome: &'static str, previous: Option<&mut PreviousDialogues>) {
+        self.span.set_attribute(KeyValue::new("dialogue.outcome", outcome));
+        if let (Some(npc), Some(previous)) = (&self.npc, previous) {
+            previous.last.insert(npc.clone(), PreviousDialogue {
+                span: self.span.span_context().clone(),
+                ended: Instant::now(),
+                outcome,
+            });
+        }
+        self.span.end();
+    }
+}
+
+/// Each NPC's most recent dialogue session this play session, so the next
+/// conversation with them links back to it - a funnel of first, second,
+/// third talk in the trace backend instead of unrelated spans. By NPC id,
+/// since NPC entities are respawned on every map visit. Emptied when a new
+/// session starts (entering `GameState::Playing`).
+#[derive(Resource, Default)]
+pub struct PreviousDialogues {
+    last: std::collections::HashMap<String, PreviousDialogue>,
+}
+
+struct PreviousDialogue {
+    span: SpanContext,
+    ended: Instant,
+    outcome: &'static str,
+}
+
+impl PreviousDialogues {
+    /// A link to the last session with `npc`, if there was one, carrying
+    /// the gap since it ended and how it ended.
+    pub fn link_to_previous(&self, npc: &str) -> Option<Link> {
+        let previous = self.last.get(npc)?;
+        Some(Link::new(
+            previous.span.clone(),
+            vec![
+                KeyValue::new("link.gap_secs", previous.ended.elapsed().as_secs_f64()),
+                KeyValue::new("previous.outcome", previous.outcome),
+            ],
+            0,
+        ))
+    }
+
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+}
+
+/// The SDK providers behind `GameTracer` and `GameMeter`, for flushing
+/// them on demand (the console's `telemetry flush`). Handles to the same
+/// providers main.rs shuts down at exit.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource, Clone)]
+pub struct TelemetryProviders {
+    pub tracer: SdkTracerProvider,
+    pub meter: SdkMeterProvider,
 }
 
 /// Initialize OpenTelemetry tracer and meter
 /// Call this alongside init_telemetry() in main
 /// endpoint should match the one used for logging (e.g., "http://127.0.0.1:4317")
 /// metric_interval_ms is the export interval in milliseconds (default: 10000ms)
+/// profile goes on both providers' resources, as on the logger's
 #[cfg(not(target_arch = "wasm32"))]
 pub fn init_instrumentation(
     runtime: &tokio::runtime::Runtime,
     endpoint: &str,
-    metric_interval_ms: Option<u64>
+    metric_interval_ms: Option<u64>,
+    profile: &crate::telemetry::SystemProfile,
 ) -> anyhow::Result<(GameTracer, GameMeter, SdkTracerProvider, SdkMeterProvider)> {
 
     // Create tracer provider
@@ -103,11 +236,7 @@ pub fn init_instrumentation(
 
         let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
             .with_batch_exporter(exporter)
-            .with_resource(
-                opentelemetry_sdk::Resource::builder_empty()
-                    .with_service_name("sregame")
-                    .build()
-            )
+            .with_resource(crate::telemetry::resource(profile))
             .build();
 
         Ok::<_, anyhow::Error>(provider)
@@ -133,11 +262,7 @@ pub fn init_instrumentation(
 
         let provider = SdkMeterProvider::builder()
             .with_reader(reader)
-            .with_resource(
-                opentelemetry_sdk::Resource::builder_empty()
-                    .with_service_name("sregame")
-                    .build()
-            )
+            .with_resource(crate::telemetry::resource(profile))
             .build();
 
         Ok::<_, anyhow::Error>(provider)
@@ -149,29 +274,9 @@ pub fn init_instrumentation(
     // Get meter from provider
     let meter = meter_provider.meter("sregame");
 
-    let dialogue_reading_speed = meter
-        .f64_histogram("game.dialogue.reading_speed")
-        .with_description("Characters per second during dialogue reading")
-        .with_unit("chars/sec")
-        .build();
-
-    let interactions_total = meter
-        .u64_counter("game.interactions.total")
-        .with_description("Total number of player interactions")
-        .build();
-
-    let dialogue_lines_read = meter
-        .u64_counter("game.dialogue_lines_read")
-        .with_description("Total number of dialogue lines displayed")
-        .build();
-
     Ok((
         GameTracer { tracer },
-        GameMeter {
-            dialogue_reading_speed,
-            interactions_total,
-            dialogue_lines_read,
-        },
+        GameMeter::new(&meter),
         tracer_provider,
         meter_provider,
     ))
@@ -181,6 +286,7 @@ pub fn init_instrumentation(
 pub fn start_npc_interaction_span(
     tracer: &GameTracer,
     session: &PlayerSessionTrace,
+    npc_id: &str,
     npc_name: &str,
     player_pos: Vec2,
     distance: f32,
@@ -189,6 +295,7 @@ pub fn start_npc_interaction_span(
     let mut span = tracer.tracer()
         .start_with_context("npc.interaction", &context);
 
+    span.set_attribute(KeyValue::new("npc.id", npc_id.to_string()));
     span.set_attribute(KeyValue::new("npc.name", npc_name.to_string()));
     span.set_attribute(KeyValue::new("player.x", player_pos.x as f64));
     span.set_attribute(KeyValue::new("player.y", player_pos.y as f64));
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+use crate::assets::GameAssets;
+use crate::game_state::{GameState, Mode};
+use crate::glyphs::{InputGlyphs, PromptSegment};
+use crate::input::{ActiveDevice, InputMap};
+use crate::npc::{InRange, Interactable, InteractionSettings, Npc, pick_talk_target};
+use crate::player::{Facing, Player};
+
+/// "Press E to talk" over the NPC an E press would talk to: of those in
+/// range (`InRange`), the closest the player is facing -
+/// `npc::pick_talk_target`, the same pick handle_interaction_input makes. The label is the NPC's
+/// `Interactable::prompt`, its `{interact}` the key or button bound now,
+/// on the active device.
+///
+/// Prompts fade in and out over `FADE` rather than popping: moving between
+/// two NPCs crossfades them, and a conversation starting fades the prompt
+/// out under the box. A prompt is a child of its NPC, so one leaving the
+/// map takes its prompt with it.
+pub struct InteractionPromptPlugin;
+
+impl Plugin for InteractionPromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveDevice>()
+            .init_resource::<InputMap>()
+            .init_resource::<InteractionSettings>()
+            .add_systems(
+                Update,
+                update_interaction_prompts
+                    .after(crate::dialogue::DialogueRequestSet)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// How long a prompt takes to fade all the way in or out.
+pub const FADE: f32 = 0.2;
+
+/// The prompt over `npc`, `opacity` 0 (gone) to 1.
+#[derive(Component, Debug)]
+pub struct InteractionPrompt {
+    pub npc: Entity,
+    pub opacity: f32,
+}
+
+impl InteractionPrompt {
+    /// Fade toward shown or hidden by `delta` seconds' worth.
+    pub fn fade(&mut self, shown: bool, delta: f32) {
+        let step = if shown { delta / FADE } else { -delta / FADE };
+        self.opacity = (self.opacity + step).clamp(0.0, 1.0);
+    }
+}
+
+/// `template` with its controls named as `device` has them bound in `map`:
+/// "Press {interact} to talk" is "Press E to talk" on a keyboard.
+pub fn prompt_text(template: &str, device: ActiveDevice, map: &InputMap) -> String {
+    InputGlyphs::segments(template)
+        .into_iter()
+        .map(|segment| match segment {
+            PromptSegment::Text(text) => text,
+            PromptSegment::Control(control) => control.label(device, map),
+        })
+        .collect()
+}
+
+fn update_interaction_prompts(
+    mut commands: Commands,
+    mode: Res<State<Mode>>,
+    player: Query<(&Transform, &Facing), With<Player>>,
+    in_range: Query<(Entity, &Transform, &Interactable), (With<Npc>, With<InRange>)>,
+    mut prompts: Query<(Entity, &mut InteractionPrompt, &mut Text2d, &mut TextColor)>,
+    game_assets: Option<Res<GameAssets>>,
+    device: Res<ActiveDevice>,
+    map: Res<InputMap>,
+    settings: Res<InteractionSettings>,
+    time: Res<Time>,
+) {
+    let player = player.single().ok().filter(|_| *mode.get() == Mode::Exploring);
+    let target = player.and_then(|(transform, facing)| {
+        pick_talk_target(
+            transform.translation.truncate(),
+            facing.direction(),
+            &settings,
+            in_range.iter().map(|(entity, transform, interactable)| (transform.translation.truncate(), (entity, interactable))),
+        )
+    });
+    let target = target.map(|((npc, interactable), ..)| (npc, prompt_text(&interactable.prompt, *device, &map)));
+
+    let mut shown = false;
+    for (entity, mut prompt, mut text, mut color) in &mut prompts {
+        let is_target = target.as_ref().is_some_and(|(npc, _)| *npc == prompt.npc);
+        prompt.fade(is_target, time.delta_secs());
+        if prompt.opacity == 0.0 && !is_target {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        color.0.set_alpha(prompt.opacity);
+        if let Some((_, label)) = target.as_ref().filter(|_| is_target) {
+            shown = true;
+            if text.0 != *label {
+                text.0.clone_from(label);
+            }
+        }
+    }
+
+    let Some((npc, label)) = target.filter(|_| !shown) else { return };
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    let prompt = commands
+        .spawn((
+            InteractionPrompt { npc, opacity: 0.0 },
+            Text2d::new(label),
+            TextFont { font: font.into(), font_size: FontSize::Px(16.0), ..default() },
+            TextColor(Color::WHITE.with_alpha(0.0)),
+            // Over the head, clear of emotes (npc.rs's spawn_emote, at 36).
+            Transform::from_xyz(0.0, 56.0, 0.02),
+        ))
+        .id();
+    commands.entity(npc).add_child(prompt);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_fade_over_a_fifth_of_a_second() {
+        let mut prompt = InteractionPrompt { npc: Entity::PLACEHOLDER, opacity: 0.0 };
+        prompt.fade(true, 0.1);
+        assert!((prompt.opacity - 0.5).abs() < 1e-6);
+        prompt.fade(true, 0.5);
+        assert_eq!(prompt.opacity, 1.0);
+        prompt.fade(false, 0.05);
+        assert!((prompt.opacity - 0.75).abs() < 1e-6);
+        prompt.fade(false, 1.0);
+        assert_eq!(prompt.opacity, 0.0);
+    }
+
+    #[test]
+    fn the_prompt_names_the_bound_key() {
+        let mut map = InputMap::default();
+        assert_eq!(prompt_text("Press {interact} to talk", ActiveDevice::Keyboard, &map), "Press E to talk");
+        map.bind(crate::input::GameAction::Interact, crate::input::Binding::Key(KeyCode::KeyQ));
+        assert_eq!(prompt_text("Press {interact} to talk", ActiveDevice::Keyboard, &map), "Press Q to talk");
+    }
+}
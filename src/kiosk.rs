@@ -0,0 +1,268 @@
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::KeyValue;
+use std::time::Duration;
+use crate::game_state::{GameState, GameStatePlugin, Mode, NewGameRequest, Scene};
+use crate::instrumentation::{MetricsBundle, init_metrics};
+
+/// `--kiosk <minutes>`, for an unattended conference booth: once nobody
+/// has touched a key, button, the mouse or the screen for
+/// `Kiosk::idle_limit` - or a player has
+/// reached the ending and seen it through - the screen fades to black, a
+/// new game starts under it (game_state::NewGameRequest: fresh flags,
+/// conversations, player and session span, back in the first scene), and
+/// the screen fades back in for the next visitor. No process restart.
+///
+/// Every reset bumps `game.kiosk.resets`, labelled by what triggered it,
+/// so a booth's telemetry tells walk-aways from finished games.
+///
+/// Off unless `Kiosk` is inserted; main.rs does that for `--kiosk`.
+pub struct KioskPlugin;
+
+impl Plugin for KioskPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<NewGameRequest>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<AccumulatedMouseMotion>()
+            .init_resource::<Touches>()
+            .add_systems(
+            Update,
+            (watch_for_reset, run_reset)
+                .chain()
+                .run_if(resource_exists::<Kiosk>.and(in_state(GameState::Playing))),
+        );
+        init_metrics::<KioskMetrics>(app);
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<GameStatePlugin>(app, "KioskPlugin");
+    }
+}
+
+/// `game.kiosk.resets`, by trigger.
+#[derive(Resource)]
+pub struct KioskMetrics {
+    pub resets: Counter<u64>,
+}
+
+impl MetricsBundle for KioskMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            resets: meter
+                .u64_counter("game.kiosk.resets")
+                .with_description("Kiosk sessions reset to a fresh game, by trigger (see kiosk.rs)")
+                .build(),
+        }
+    }
+}
+
+/// How long the screen takes to fade out before a reset, and in after.
+pub const FADE_SECONDS: f32 = 1.0;
+
+/// How long the ending stays up, its conversations over, before the game
+/// resets for the next visitor.
+pub const ENDING_LINGER: Duration = Duration::from_secs(15);
+
+/// Present in kiosk mode.
+#[derive(Resource, Debug)]
+pub struct Kiosk {
+    pub idle_limit: Duration,
+    idle: Duration,
+    /// Time spent exploring `Scene::End`.
+    at_the_end: Duration,
+    reset: Option<KioskReset>,
+    resets: u32,
+}
+
+impl Kiosk {
+    pub fn new(idle_limit: Duration) -> Self {
+        Self { idle_limit, idle: Duration::ZERO, at_the_end: Duration::ZERO, reset: None, resets: 0 }
+    }
+
+    /// Games reset so far.
+    pub fn resets(&self) -> u32 {
+        self.resets
+    }
+
+    /// Whether a reset is under way, the screen fading out or back in.
+    pub fn is_resetting(&self) -> bool {
+        self.reset.is_some()
+    }
+
+    /// Count `delta` toward a reset: idle time unless `input` is held,
+    /// ending time while `at_the_end`. The trigger once one is due.
+    fn tick(&mut self, delta: Duration, input: bool, at_the_end: bool) -> Option<KioskTrigger> {
+        self.idle = if input { Duration::ZERO } else { self.idle + delta };
+        self.at_the_end = if at_the_end { self.at_the_end + delta } else { Duration::ZERO };
+        if self.idle >= self.idle_limit {
+            Some(KioskTrigger::Idle)
+        } else if self.at_the_end >= ENDING_LINGER {
+            Some(KioskTrigger::Ending)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a kiosk game reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KioskTrigger {
+    /// Nobody touched the controls for `Kiosk::idle_limit`.
+    Idle,
+    /// The ending was reached and has had its `ENDING_LINGER`.
+    Ending,
+}
+
+impl KioskTrigger {
+    pub fn name(self) -> &'static str {
+        match self {
+            KioskTrigger::Idle => "idle",
+            KioskTrigger::Ending => "ending",
+        }
+    }
+
+    /// The `NewGameRequest::reason`.
+    fn reason(self) -> &'static str {
+        match self {
+            KioskTrigger::Idle => "kiosk.idle",
+            KioskTrigger::Ending => "kiosk.ending",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KioskReset {
+    trigger: KioskTrigger,
+    elapsed: f32,
+    requested: bool,
+}
+
+/// The black screen over a reset.
+#[derive(Component)]
+struct KioskCurtain;
+
+/// The curtain's opacity `elapsed` seconds into a reset: fading in over
+/// `FADE_SECONDS`, then out over as long again.
+fn curtain_alpha(elapsed: f32) -> f32 {
+    if elapsed <= FADE_SECONDS {
+        elapsed / FADE_SECONDS
+    } else {
+        (2.0 - elapsed / FADE_SECONDS).clamp(0.0, 1.0)
+    }
+}
+
+fn watch_for_reset(
+    mut commands: Commands,
+    mut kiosk: ResMut<Kiosk>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    scene: Res<State<Scene>>,
+    mode: Res<State<Mode>>,
+    time: Res<Time>,
+) {
+    if kiosk.is_resetting() {
+        return;
+    }
+    let input = keyboard.get_pressed().next().is_some()
+        || mouse.get_pressed().next().is_some()
+        || motion.delta != Vec2::ZERO
+        || touches.iter().next().is_some()
+        || gamepads.iter().any(|gamepad| gamepad.get_pressed().next().is_some());
+    let at_the_end = *scene.get() == Scene::End && *mode.get() == Mode::Exploring;
+    let Some(trigger) = kiosk.tick(time.delta(), input, at_the_end) else { return };
+
+    info!("🎪 Kiosk reset ({}) - fading out", trigger.name());
+    kiosk.reset = Some(KioskReset { trigger, elapsed: 0.0, requested: false });
+    commands.spawn((
+        KioskCurtain,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.0)),
+        // Over everything, the console included.
+        GlobalZIndex(120),
+    ));
+}
+
+fn run_reset(
+    mut commands: Commands,
+    mut kiosk: ResMut<Kiosk>,
+    mut curtains: Query<(Entity, &mut BackgroundColor), With<KioskCurtain>>,
+    mut new_games: MessageWriter<NewGameRequest>,
+    metrics: Option<Res<KioskMetrics>>,
+    time: Res<Time>,
+) {
+    let kiosk = &mut *kiosk;
+    let Some(reset) = kiosk.reset.as_mut() else { return };
+    reset.elapsed += time.delta_secs();
+
+    // Black: the new game starts out of sight.
+    if reset.elapsed >= FADE_SECONDS && !reset.requested {
+        reset.requested = true;
+        kiosk.resets += 1;
+        new_games.write(NewGameRequest { reason: reset.trigger.reason() });
+        if let Some(metrics) = metrics {
+            metrics.resets.add(1, &[KeyValue::new("kiosk.trigger", reset.trigger.name())]);
+        }
+    }
+
+    let alpha = curtain_alpha(reset.elapsed);
+    for (entity, mut color) in &mut curtains {
+        if reset.elapsed >= 2.0 * FADE_SECONDS {
+            commands.entity(entity).despawn();
+        } else {
+            color.0.set_alpha(alpha);
+        }
+    }
+    if reset.elapsed >= 2.0 * FADE_SECONDS {
+        info!("🎪 Kiosk game {} ready", kiosk.resets + 1);
+        kiosk.reset = None;
+        kiosk.idle = Duration::ZERO;
+        kiosk.at_the_end = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_time_builds_only_while_nothing_is_held() {
+        let mut kiosk = Kiosk::new(Duration::from_secs(60));
+        let second = Duration::from_secs(1);
+        for _ in 0..59 {
+            assert_eq!(kiosk.tick(second, false, false), None);
+        }
+        assert_eq!(kiosk.tick(second, true, false), None, "a press starts the count over");
+        for _ in 0..59 {
+            assert_eq!(kiosk.tick(second, false, false), None);
+        }
+        assert_eq!(kiosk.tick(second, false, false), Some(KioskTrigger::Idle));
+    }
+
+    #[test]
+    fn the_ending_resets_after_its_linger_even_with_someone_there() {
+        let mut kiosk = Kiosk::new(Duration::from_secs(600));
+        let second = Duration::from_secs(1);
+        for _ in 1..ENDING_LINGER.as_secs() {
+            assert_eq!(kiosk.tick(second, true, true), None);
+        }
+        assert_eq!(kiosk.tick(second, true, true), Some(KioskTrigger::Ending));
+    }
+
+    #[test]
+    fn the_curtain_falls_then_lifts() {
+        assert_eq!(curtain_alpha(0.0), 0.0);
+        assert!((curtain_alpha(FADE_SECONDS / 2.0) - 0.5).abs() < 1e-6);
+        assert_eq!(curtain_alpha(FADE_SECONDS), 1.0);
+        assert!((curtain_alpha(FADE_SECONDS * 1.5) - 0.5).abs() < 1e-6);
+        assert_eq!(curtain_alpha(FADE_SECONDS * 2.0), 0.0);
+    }
+}
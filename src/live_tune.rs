@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+use std::time::SystemTime;
+use crate::ambient::AmbientChatter;
+use crate::content_pack::ContentPacks;
+use crate::flags::GameFlags;
+use crate::game_state::{Mode, Scene};
+use crate::map_data::{MapDirectory, NpcData, facing_from_string};
+use crate::npc::{CharacterFrames, Interactable, Npc};
+use crate::npc_spawning::{DespawnNpcEvent, SpawnNpcEvent};
+use crate::tilemap::{MapNpcs, load_scene_map};
+
+/// Tuning NPCs by saving the map file and looking. With maps read from
+/// disk (`MapDirectory` - `--map-dir`), the current map's file is checked
+/// every `POLL_SECONDS`; once it changes, its NPC entries are diffed
+/// against the ones spawned, by id (see `classify`). An edit to nothing
+/// but `IN_PLACE_FIELDS` is applied to the NPC where it stands - no
+/// respawn, so it keeps its place in a patrol and its conversation
+/// count - and anything else (moved, another sprite, new dialogue, added
+/// or removed) despawns and spawns it again through npc_spawning.rs. Every
+/// NPC touched is logged, with the fields that changed.
+///
+/// Only while exploring: an edit saved mid-conversation lands once it's
+/// over. A map that no longer parses is warned about and left as it was,
+/// so a half-typed save costs nothing. A map a content pack patches is
+/// more than one file, and isn't watched.
+pub struct LiveTunePlugin;
+
+impl Plugin for LiveTunePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<SpawnNpcEvent>()
+            .add_message::<DespawnNpcEvent>()
+            .init_resource::<GameFlags>()
+            .init_resource::<WatchedMap>()
+            .add_systems(
+                Update,
+                reload_edited_map
+                    .run_if(resource_exists::<MapDirectory>)
+                    .run_if(in_state(Mode::Exploring)),
+            );
+    }
+}
+
+/// How often the map file is checked for a save.
+pub const POLL_SECONDS: f32 = 0.5;
+
+/// NPC fields an edit to can be applied to the spawned NPC.
+pub const IN_PLACE_FIELDS: [&str; 4] = ["ambient_lines", "interaction_radius", "prompt", "facing"];
+
+/// What an edited map entry means for its NPC, with the fields that
+/// changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NpcEdit {
+    /// Only `IN_PLACE_FIELDS`: updated where it stands.
+    InPlace(Vec<&'static str>),
+    /// Despawned and spawned again from the new entry.
+    Respawn(Vec<&'static str>),
+}
+
+/// The fields of an NPC entry that differ, in `NpcData` order.
+pub fn changed_fields(old: &NpcData, new: &NpcData) -> Vec<&'static str> {
+    // Destructured, so a field added to NpcData can't be missed here.
+    let NpcData {
+        id,
+        name,
+        x,
+        y,
+        sprite,
+        sprite_index,
+        step_anime,
+        wander,
+        wander_radius,
+        path,
+        move_speed,
+        through,
+        when_busy,
+        requires_flag,
+        ambient_lines,
+        interaction_radius,
+        prompt,
+        facing,
+        dialogue,
+        dialogue_file,
+        dialogues,
+        repeat,
+        repeat_line,
+    } = old;
+    let mut changed = Vec::new();
+    macro_rules! compare {
+        ($($field:ident),*) => {
+            $(if *$field != new.$field {
+                changed.push(stringify!($field));
+            })*
+        };
+    }
+    compare!(
+        id, name, x, y, sprite, sprite_index, step_anime, wander, wander_radius, path, move_speed, through,
+        when_busy, requires_flag, ambient_lines, interaction_radius, prompt, facing, dialogue, dialogue_file,
+        dialogues, repeat, repeat_line
+    );
+    changed
+}
+
+/// None if the entry is unchanged.
+pub fn classify(old: &NpcData, new: &NpcData) -> Option<NpcEdit> {
+    let changed = changed_fields(old, new);
+    if changed.is_empty() {
+        None
+    } else if changed.iter().all(|field| IN_PLACE_FIELDS.contains(field)) {
+        Some(NpcEdit::InPlace(changed))
+    } else {
+        Some(NpcEdit::Respawn(changed))
+    }
+}
+
+/// The file behind the current map, as last seen.
+#[derive(Resource)]
+struct WatchedMap {
+    path: String,
+    stamp: Option<(SystemTime, u64)>,
+    poll: Timer,
+}
+
+impl Default for WatchedMap {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            stamp: None,
+            poll: Timer::from_seconds(POLL_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Modified time and length: a save changes at least one.
+fn stamp(path: &str) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn reload_edited_map(
+    time: Res<Time>,
+    mut watched: ResMut<WatchedMap>,
+    scene: Res<State<Scene>>,
+    map_directory: Res<MapDirectory>,
+    content_packs: Option<Res<ContentPacks>>,
+    map_npcs: Option<ResMut<MapNpcs>>,
+    flags: Res<GameFlags>,
+    mut commands: Commands,
+    mut npcs: Query<(Entity, &mut Npc, &mut CharacterFrames, &mut Sprite, &mut Interactable)>,
+    mut spawns: MessageWriter<SpawnNpcEvent>,
+    mut despawns: MessageWriter<DespawnNpcEvent>,
+) {
+    let Some(mut map_npcs) = map_npcs else { return };
+    // A map just spawned is the one to watch from here.
+    if map_npcs.is_added() || watched.path != map_npcs.path {
+        watched.path = map_npcs.path.clone();
+        watched.stamp = stamp(&watched.path);
+        watched.poll.reset();
+        return;
+    }
+    if !watched.poll.tick(time.delta()).just_finished() {
+        return;
+    }
+    let now = stamp(&watched.path);
+    if now.is_none() || now == watched.stamp {
+        return;
+    }
+    watched.stamp = now;
+
+    let (loaded, path) = load_scene_map(*scene.get(), Some(map_directory.0.as_path()), content_packs.as_deref());
+    let map = match loaded {
+        Ok(map) => map,
+        Err(e) => {
+            warn!("🔧 {path} changed but doesn't load - keeping the NPCs as they are: {e:#}");
+            return;
+        }
+    };
+
+    for old in &map_npcs.placed {
+        let id = old.id();
+        // Held back by its flag, an NPC has nothing to despawn.
+        let present = npcs.iter().any(|(_, npc, ..)| npc.id == id);
+        let Some(new) = map.npcs.iter().find(|npc| npc.id() == id) else {
+            info!("🔧 {path}: {} removed", old.name);
+            if present {
+                despawns.write(DespawnNpcEvent { id });
+            }
+            continue;
+        };
+        match classify(old, new) {
+            None => {}
+            Some(NpcEdit::InPlace(fields)) => {
+                info!("🔧 {path}: {} updated in place ({})", new.name, fields.join(", "));
+                for problem in new.ambient_line_problems().into_iter().chain(new.interaction_problem()) {
+                    warn!("🔧 {path}: {problem}");
+                }
+                let lines = new.shown_ambient_lines();
+                for (entity, mut npc, mut frames, mut sprite, mut interactable) in
+                    npcs.iter_mut().filter(|(_, npc, ..)| npc.id == id)
+                {
+                    *interactable = Interactable::from_data(new);
+                    npc.sprite_facing = facing_from_string(&new.facing);
+                    frames.facing_row = npc.sprite_facing as u32;
+                    frames.frame(crate::character_sheet::STANDING_PATTERN).apply(&mut sprite);
+                    if lines.is_empty() {
+                        commands.entity(entity).remove::<AmbientChatter>();
+                    } else {
+                        commands.entity(entity).insert(AmbientChatter::new(lines.clone()));
+                    }
+                }
+            }
+            Some(NpcEdit::Respawn(fields)) => {
+                info!("🔧 {path}: {} respawned ({})", new.name, fields.join(", "));
+                if present {
+                    despawns.write(DespawnNpcEvent { id });
+                }
+                if flags.allows(new) {
+                    spawns.write(SpawnNpcEvent { data: new.clone(), scene_scoped: true });
+                }
+            }
+        }
+    }
+    for new in map.npcs.iter().filter(|new| !map_npcs.placed.iter().any(|old| old.id() == new.id())) {
+        info!("🔧 {path}: {} added", new.name);
+        if flags.allows(new) {
+            spawns.write(SpawnNpcEvent { data: new.clone(), scene_scoped: true });
+        }
+    }
+
+    // Flags (npc_spawning.rs) and `spawn_npc` outcomes go by the new
+    // entries from here on.
+    map_npcs.placed = map.npcs;
+    map_npcs.spawnable = map.spawnable;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_data::{BusyBehavior, DialogueRepeat};
+
+    fn isabella() -> NpcData {
+        serde_json::from_str(
+            r#"{"id": "isabella", "name": "Isabella", "x": 3, "y": 1, "sprite": "Isabella", "facing": "down",
+                "dialogue": {"speaker": "Isabella", "portrait": "", "lines": ["Welcome."]}}"#,
+        )
+        .unwrap()
+    }
+
+    fn edited(edit: impl FnOnce(&mut NpcData)) -> Option<NpcEdit> {
+        let mut new = isabella();
+        edit(&mut new);
+        classify(&isabella(), &new)
+    }
+
+    #[test]
+    fn cheap_fields_are_tuned_in_place() {
+        assert_eq!(edited(|_| {}), None);
+        assert_eq!(edited(|npc| npc.facing = "left".into()), Some(NpcEdit::InPlace(vec!["facing"])));
+        assert_eq!(
+            edited(|npc| npc.ambient_lines = vec!["Coffee?".into()]),
+            Some(NpcEdit::InPlace(vec!["ambient_lines"]))
+        );
+        assert_eq!(
+            edited(|npc| {
+                npc.facing = "up".into();
+                npc.ambient_lines = vec!["Coffee?".into()];
+            }),
+            Some(NpcEdit::InPlace(vec!["ambient_lines", "facing"]))
+        );
+        assert_eq!(
+            edited(|npc| npc.interaction_radius = Some(96.0)),
+            Some(NpcEdit::InPlace(vec!["interaction_radius"]))
+        );
+        assert_eq!(
+            edited(|npc| npc.prompt = Some("Press {interact} to page".into())),
+            Some(NpcEdit::InPlace(vec!["prompt"]))
+        );
+    }
+
+    #[test]
+    fn structural_fields_respawn() {
+        let respawn = |field| Some(NpcEdit::Respawn(vec![field]));
+        assert_eq!(edited(|npc| npc.x = 4), respawn("x"));
+        assert_eq!(edited(|npc| npc.y = 2), respawn("y"));
+        assert_eq!(edited(|npc| npc.sprite = "Casey".into()), respawn("sprite"));
+        assert_eq!(edited(|npc| npc.sprite_index = 3), respawn("sprite_index"));
+        assert_eq!(edited(|npc| npc.name = "Izzy".into()), respawn("name"));
+        assert_eq!(edited(|npc| npc.step_anime = true), respawn("step_anime"));
+        assert_eq!(edited(|npc| npc.wander = true), respawn("wander"));
+        assert_eq!(edited(|npc| npc.path = vec![(1, 1)]), respawn("path"));
+        assert_eq!(edited(|npc| npc.through = true), respawn("through"));
+        assert_eq!(edited(|npc| npc.when_busy = BusyBehavior::Decline), respawn("when_busy"));
+        assert_eq!(edited(|npc| npc.requires_flag = Some("met".into())), respawn("requires_flag"));
+        assert_eq!(edited(|npc| npc.dialogue.lines.clear()), respawn("dialogue"));
+        assert_eq!(edited(|npc| npc.repeat = DialogueRepeat::Once), respawn("repeat"));
+        // Any structural change takes the cheap ones along with it.
+        assert_eq!(
+            edited(|npc| {
+                npc.x = 4;
+                npc.facing = "left".into();
+            }),
+            Some(NpcEdit::Respawn(vec!["x", "facing"]))
+        );
+    }
+}
@@ -1,43 +1,15 @@
 use bevy::prelude::*;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 #[cfg(not(target_arch = "wasm32"))]
 use bevy::app::ScheduleRunnerPlugin;
 #[cfg(not(target_arch = "wasm32"))]
 use bevy::window::{ExitCondition, MonitorSelection, WindowMode};
 #[cfg(not(target_arch = "wasm32"))]
 use bevy::winit::WinitPlugin;
-#[cfg(not(target_arch = "wasm32"))]
 use std::time::Duration;
 
-mod game_state;
-mod assets;
-mod character_sheet;
-mod player;
-mod camera;
-mod tilemap;
-mod dialogue;
-mod npc;
-mod map_data;
-mod asset_manifest;
-mod viewport;
-mod semantic_state;
 #[cfg(not(target_arch = "wasm32"))]
-mod telemetry;
-mod instrumentation;
-mod transitions;
-mod depth;
-
-use game_state::{GameState, GameStatePlugin, Mode};
-use assets::AssetsPlugin;
-use player::PlayerPlugin;
-use camera::{CameraPlugin, MainCamera, CameraFollow};
-use tilemap::TilemapPlugin;
-use dialogue::DialoguePlugin;
-use npc::NpcPlugin;
-use viewport::SemanticViewportPlugin;
-use semantic_state::SemanticStatePlugin;
-use transitions::TransitionsPlugin;
-use depth::DepthPlugin;
+use sregame::{game_events, instrumentation, quit, run_report, save, save_menu, telemetry, thumbnails};
 
 /// The Endgame of SRE - An educational game about Site Reliability Engineering
 #[derive(Parser, Debug, Clone, Resource)]
@@ -65,7 +37,7 @@ struct Args {
     frames: Option<u64>,
 
     /// Exit the game after N seconds
-    #[arg(long)]
+    #[arg(long, allow_negative_numbers = true)]
     seconds: Option<f32>,
 
     /// Run in headless mode (no window, no GPU required)
@@ -81,6 +53,358 @@ struct Args {
     /// OTLP metric export interval in milliseconds (default: 10000)
     #[arg(long)]
     otlp_metric_interval: Option<u64>,
+
+    /// Leave the CPU model and core count, memory and GPU out of the
+    /// telemetry's system profile (see telemetry.rs)
+    #[arg(long)]
+    no_system_profile: bool,
+
+    /// Directory for save files (default: $XDG_DATA_HOME/sregame, or
+    /// ~/.local/share/sregame)
+    #[arg(long)]
+    save_dir: Option<std::path::PathBuf>,
+
+    /// Open the save slot picker once the game starts, on its Continue
+    /// entry (the newest save)
+    #[arg(long = "continue")]
+    continue_game: bool,
+
+    /// Log entity counts by marker group every few seconds, to hunt leaks
+    /// (see entity_audit.rs)
+    #[arg(long)]
+    audit_entities: bool,
+
+    /// Turn off drop shadows under characters
+    #[arg(long)]
+    no_shadows: bool,
+
+    /// Turn off the first-run control hints
+    #[arg(long)]
+    no_tutorial: bool,
+
+    /// Turn off controller rumble
+    #[arg(long)]
+    no_rumble: bool,
+
+    /// Enable the developer console (backquote key) in a release build;
+    /// debug builds always have it (see console.rs)
+    #[arg(long)]
+    dev_console: bool,
+
+    /// Clicking an NPC out of reach walks over to talk to them, rather
+    /// than only naming them (see click_to_talk.rs)
+    #[arg(long)]
+    click_to_walk: bool,
+
+    /// Keep the game running while its window is unfocused, rather than
+    /// pausing until it's back (see focus.rs)
+    #[arg(long)]
+    run_unfocused: bool,
+
+    /// Keep sampling frame times while the window is unfocused (see
+    /// focus.rs)
+    #[arg(long)]
+    frame_metrics_unfocused: bool,
+
+    /// Kiosk mode for an unattended booth: start a new game after this many
+    /// minutes without input, or once the ending has been seen (see
+    /// kiosk.rs)
+    #[arg(long, allow_negative_numbers = true)]
+    kiosk: Option<f32>,
+
+    /// Dialogue typing speed, as a multiple of the normal pace (about 33
+    /// characters a second): 2 is twice as fast, like a map's per-speaker
+    /// `text_speed`; 0 shows each box whole, for automated runs (see
+    /// dialogue.rs)
+    #[arg(long, allow_negative_numbers = true)]
+    text_speed: Option<f32>,
+
+    /// Shrink a dialogue line's text to fit a word too long for a row (a
+    /// URL, a command), rather than breaking the word across rows
+    #[arg(long)]
+    shrink_long_words: bool,
+
+    /// Open a conversation asked for while another is on screen once that
+    /// one closes, rather than dropping it (see dialogue.rs)
+    #[arg(long)]
+    queue_dialogues: bool,
+
+    /// Lines the dialogue log keeps (L in a conversation; see
+    /// dialogue_history.rs)
+    #[arg(long, default_value_t = sregame::dialogue_history::DEFAULT_HISTORY_LINES)]
+    history_lines: usize,
+
+    /// How often NPCs chatter on their own: 1.0 normal, 0 off
+    /// (see ambient.rs)
+    #[arg(long, default_value_t = 1.0, allow_negative_numbers = true)]
+    ambient_chatter: f32,
+
+    /// Seed for all game randomness, to replay a session (default: from
+    /// the clock; the seed in use is logged - see rng.rs)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Report frames slower than this as stalls (see frame_watchdog.rs)
+    #[arg(long, default_value_t = 100)]
+    stall_threshold_ms: u64,
+
+    /// Append gameplay events to this file, one JSON object per line
+    /// (see game_events.rs)
+    #[arg(long)]
+    events_file: Option<std::path::PathBuf>,
+
+    /// Stream gameplay events as JSON lines to TCP clients on
+    /// 127.0.0.1:<port>
+    #[arg(long)]
+    events_port: Option<u16>,
+
+    /// Show session time on screen and split it at milestones, printed as
+    /// a table at exit (see splits.rs)
+    #[arg(long)]
+    timer: bool,
+
+    /// Milestones for --timer (default: assets/data/splits.json)
+    #[arg(long)]
+    splits: Option<std::path::PathBuf>,
+
+    /// Write who was talked to, and for how long, to this file as JSON at
+    /// exit (see session_summary.rs)
+    #[arg(long)]
+    summary_json: Option<std::path::PathBuf>,
+
+    /// Write how the run went - exit, states reached, failed assets,
+    /// content problems, frame times, telemetry export, panics - to this
+    /// file as JSON at exit, for CI (see run_report.rs)
+    #[arg(long)]
+    run_report: Option<std::path::PathBuf>,
+
+    /// Inject a synthetic anomaly into the telemetry, for teaching:
+    /// frame_spikes, dialogue_latency or error_burst (see chaos.rs)
+    #[arg(long)]
+    chaos: Option<String>,
+
+    /// Parameters for --chaos (default: assets/data/chaos.json)
+    #[arg(long)]
+    chaos_params: Option<std::path::PathBuf>,
+
+    /// Overlay a directory on assets/: its files shadow the base ones by
+    /// relative path, and data/maps/<map>.patch.json patches a map.
+    /// Repeatable; later packs win (see content_pack.rs)
+    #[arg(long = "content-pack", global = true)]
+    content_packs: Vec<std::path::PathBuf>,
+
+    /// Read map JSON from this directory (e.g. assets/data/maps) instead of
+    /// the copies built into the game, and apply edits to the current
+    /// map's NPCs as they're saved (see live_tune.rs)
+    #[arg(long)]
+    map_dir: Option<std::path::PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Check map (.json, or Tiled's .tmj) and .dialogue.json files for
+    /// problems the game would only find at play time, without starting
+    /// it. Exits non-zero if any has one
+    Validate {
+        files: Vec<std::path::PathBuf>,
+    },
+}
+
+/// Exit code for flags that parse but make no sense together (see
+/// `Args::problems`): sysexits' EX_USAGE. Clap's own parse errors exit 2,
+/// and `validate` 1.
+#[cfg(not(target_arch = "wasm32"))]
+const BAD_FLAGS_EXIT: i32 = 64;
+
+impl Args {
+    /// Everything wrong with the flags as given, each with what to do
+    /// about it - all of them, not just the first. `env_endpoint` is
+    /// OTEL_EXPORTER_OTLP_ENDPOINT, which stands in for --otlp-endpoint.
+    /// New flag interactions get checked here.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn problems(&self, env_endpoint: Option<&str>) -> Vec<String> {
+        let mut problems = Vec::new();
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        let non_negative = |value: f32| value.is_finite() && value >= 0.0;
+
+        if self.frames == Some(0) {
+            problems.push("--frames 0 would exit before the first frame; give at least 1".to_string());
+        }
+        if let Some(seconds) = self.seconds
+            && !positive(seconds)
+        {
+            problems.push(format!("--seconds {seconds} isn't a positive number of seconds"));
+        }
+        if let Some(minutes) = self.kiosk
+            && !positive(minutes)
+        {
+            problems.push(format!("--kiosk {minutes} isn't a positive number of minutes"));
+        }
+        if let Some(speed) = self.text_speed
+            && !non_negative(speed)
+        {
+            problems.push(format!("--text-speed {speed} can't be negative; 0 shows each box whole"));
+        }
+        if !non_negative(self.ambient_chatter) {
+            problems.push(format!("--ambient-chatter {} can't be negative; 0 turns chatter off", self.ambient_chatter));
+        }
+        if self.stall_threshold_ms == 0 {
+            problems.push("--stall-threshold-ms 0 would report every frame as a stall".to_string());
+        }
+        let endpoint = self.otlp_endpoint.as_deref().or(env_endpoint).is_some_and(|endpoint| !endpoint.is_empty());
+        match self.otlp_metric_interval {
+            Some(0) => problems.push("--otlp-metric-interval 0 isn't an interval; give milliseconds, e.g. 10000".to_string()),
+            Some(_) if !endpoint => problems.push(
+                "--otlp-metric-interval has nothing to export to; add --otlp-endpoint or set OTEL_EXPORTER_OTLP_ENDPOINT"
+                    .to_string(),
+            ),
+            _ => {}
+        }
+        if self.remote_port != 15702 && !self.remote {
+            problems.push(format!("--remote-port {} does nothing without --remote", self.remote_port));
+        }
+        if self.splits.is_some() && !self.timer {
+            problems.push("--splits does nothing without --timer".to_string());
+        }
+        if self.chaos_params.is_some() && self.chaos.is_none() {
+            problems.push("--chaos-params does nothing without --chaos".to_string());
+        }
+        if let Some(name) = &self.chaos
+            && sregame::chaos::ChaosScenario::from_name(name).is_none()
+        {
+            let known: Vec<_> = sregame::chaos::ChaosScenario::ALL.map(|scenario| scenario.name()).to_vec();
+            problems.push(format!("--chaos {name:?} isn't a scenario; pick one of {}", known.join(", ")));
+        }
+        if self.headless {
+            for (flag, set) in [
+                ("--fullscreen", self.fullscreen),
+                ("--run-unfocused", self.run_unfocused),
+                ("--frame-metrics-unfocused", self.frame_metrics_unfocused),
+            ] {
+                if set {
+                    problems.push(format!("{flag} does nothing with --headless, which has no window"));
+                }
+            }
+        }
+        problems
+    }
+
+    /// The configuration this run settled on, as one structured log line -
+    /// the first one, so a trace or log search starts from what was asked.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn log_configuration(&self, otlp_endpoint: Option<&str>) {
+        let settings = self.settings();
+        info!(
+            headless = self.headless,
+            fullscreen = self.fullscreen,
+            frames = ?self.frames,
+            seconds = ?self.seconds,
+            otlp_endpoint = otlp_endpoint.unwrap_or("off"),
+            otlp_metric_interval_ms = ?self.otlp_metric_interval,
+            remote = self.remote,
+            remote_port = self.remote_port,
+            seed = ?self.seed,
+            kiosk_minutes = ?self.kiosk,
+            text_speed = ?self.text_speed,
+            chaos = self.chaos.as_deref().unwrap_or("off"),
+            timer = self.timer,
+            run_report = ?self.run_report,
+            content_packs = self.content_packs.len(),
+            map_dir = ?self.map_dir,
+            shadows = settings.shadows,
+            tutorial = settings.tutorial,
+            ambient_chatter = settings.ambient_chatter,
+            rumble = settings.rumble,
+            dev_console = settings.dev_console,
+            click_to_walk = settings.click_to_walk,
+            pause_when_unfocused = settings.pause_when_unfocused,
+            stall_threshold_ms = self.stall_threshold_ms,
+            "⚙️  Configuration"
+        );
+    }
+
+    /// Settings the flags override; everything else keeps its default.
+    fn settings(&self) -> sregame::settings::GameSettings {
+        sregame::settings::GameSettings {
+            shadows: !self.no_shadows,
+            tutorial: !self.no_tutorial,
+            ambient_chatter: self.ambient_chatter.max(0.0),
+            rumble: !self.no_rumble,
+            dev_console: self.dev_console || cfg!(debug_assertions),
+            click_to_walk: self.click_to_walk,
+            pause_when_unfocused: !self.run_unfocused,
+            frame_metrics_when_unfocused: self.frame_metrics_unfocused,
+        }
+    }
+
+    fn dialogue_settings(&self) -> sregame::dialogue::DialogueSettings {
+        sregame::dialogue::DialogueSettings {
+            chars_per_second: sregame::dialogue::DEFAULT_CHARS_PER_SECOND * self.text_speed.map_or(1.0, |speed| speed.max(0.0)),
+            long_words: match self.shrink_long_words {
+                true => sregame::dialogue::LongWords::Shrink,
+                false => sregame::dialogue::LongWords::Break,
+            },
+            while_talking: match self.queue_dialogues {
+                true => sregame::dialogue::WhileTalking::QueueAfter,
+                false => sregame::dialogue::WhileTalking::Ignore,
+            },
+        }
+    }
+
+    /// The `--seed` RNG, or a clock-seeded one.
+    fn rng(&self) -> sregame::rng::GameRng {
+        self.seed.map(sregame::rng::GameRng::new).unwrap_or_default()
+    }
+
+    /// `--timer`'s milestones, from `--splits` or the shipped file.
+    fn split_timer(&self) -> anyhow::Result<sregame::splits::SplitTimer> {
+        use anyhow::Context;
+
+        let milestones = match &self.splits {
+            Some(path) => {
+                let json = std::fs::read_to_string(path).with_context(|| format!("can't read {}", path.display()))?;
+                sregame::splits::parse_milestones(&json).with_context(|| format!("{} isn't a splits file", path.display()))?
+            }
+            None => sregame::splits::parse_milestones(sregame::splits::SPLITS_JSON).context(sregame::splits::SPLITS_PATH)?,
+        };
+        Ok(sregame::splits::SplitTimer::new(milestones))
+    }
+
+    /// The `--content-pack` directories, absolute: the asset reader
+    /// resolves relative paths against the executable, not the shell.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn content_packs(&self) -> sregame::content_pack::ContentPacks {
+        sregame::content_pack::ContentPacks(
+            self.content_packs.iter().map(|pack| std::path::absolute(pack).unwrap_or_else(|_| pack.clone())).collect(),
+        )
+    }
+
+    /// The `--chaos` scenario, with its parameters from `--chaos-params`
+    /// or the shipped file.
+    fn chaos(&self, name: &str) -> anyhow::Result<sregame::chaos::Chaos> {
+        use anyhow::Context;
+
+        match &self.chaos_params {
+            Some(path) => {
+                let json = std::fs::read_to_string(path).with_context(|| format!("can't read {}", path.display()))?;
+                sregame::chaos::Chaos::from_json(name, &json).with_context(|| path.display().to_string())
+            }
+            None => sregame::chaos::Chaos::from_json(name, sregame::chaos::CHAOS_JSON).context(sregame::chaos::CHAOS_PATH),
+        }
+    }
+
+    /// The machine's profile for the telemetry, as `--no-system-profile`
+    /// allows.
+    fn system_profile(&self, headless: bool) -> sregame::telemetry::SystemProfile {
+        sregame::telemetry::SystemProfile::gather(&sregame::telemetry::Host, headless, !self.no_system_profile)
+    }
+
+    fn frame_watchdog(&self) -> sregame::frame_watchdog::FrameWatchdog {
+        sregame::frame_watchdog::FrameWatchdog::with_threshold(Duration::from_millis(self.stall_threshold_ms))
+    }
 }
 
 fn main() {
@@ -90,27 +414,6 @@ fn main() {
     web_main();
 }
 
-/// The game itself - everything that is identical on native and web.
-fn add_game(app: &mut App) {
-    app.add_plugins((
-        GameStatePlugin,
-        AssetsPlugin,
-        PlayerPlugin,
-        CameraPlugin,
-        TilemapPlugin,
-        DialoguePlugin,
-        NpcPlugin,
-        SemanticViewportPlugin,
-        SemanticStatePlugin,
-        TransitionsPlugin,
-        DepthPlugin,
-    ))
-    .add_systems(Startup, setup)
-    .add_systems(OnEnter(GameState::Playing), on_enter_playing)
-    .add_systems(OnEnter(Mode::Dialogue), on_enter_dialogue)
-    .add_systems(Update, exit_after_n_frames_or_seconds);
-}
-
 /// Browser entry point: no CLI args or env vars exist, telemetry/BRP/headless
 /// are native-only, and Bevy's LogPlugin stays enabled because it is what
 /// routes logs to the browser console.
@@ -138,14 +441,32 @@ fn web_main() {
             }),
     );
 
+    app.insert_resource(args.settings());
+    app.insert_resource(args.dialogue_settings());
+    app.insert_resource(sregame::dialogue_history::DialogueHistory::with_capacity(args.history_lines));
+    app.insert_resource(args.rng());
+    app.insert_resource(args.frame_watchdog());
+    app.insert_resource(args.system_profile(false));
     app.insert_resource(args);
-    add_game(&mut app);
+    sregame::add_game(&mut app);
+    app.add_systems(Update, exit_after_n_frames_or_seconds);
     app.run();
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 fn native_main() {
     let args = Args::parse();
+    if let Some(Command::Validate { files }) = &args.command {
+        std::process::exit(validate(files, &args.content_packs()));
+    }
+    let problems = args.problems(std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().as_deref());
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("❌ {problem}");
+        }
+        eprintln!("   See sregame --help");
+        std::process::exit(BAD_FLAGS_EXIT);
+    }
 
     // Determine OTLP endpoint: CLI flag takes precedence over env var
     let otlp_endpoint = args.otlp_endpoint.clone()
@@ -159,9 +480,20 @@ fn native_main() {
             }
         });
 
+    // Gathered first: it goes on the telemetry's resources
+    let system_profile = args.system_profile(args.headless);
+
     // Initialize OpenTelemetry BEFORE Bevy app
     // This sets up the tracing subscriber before Bevy's LogPlugin does
-    let telemetry_result = telemetry::init_telemetry(otlp_endpoint.clone());
+    let export_errors = sregame::dashboard::ExportErrors::default();
+    let telemetry_result = telemetry::init_telemetry(otlp_endpoint.clone(), &system_profile, export_errors.clone());
+    if !matches!(telemetry_result, Ok(Some(_))) {
+        // Fall back to basic console logging
+        tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::new("info"))
+            .init();
+    }
+    args.log_configuration(otlp_endpoint.as_deref());
     let (logger_provider, runtime, tracer, meter, tracer_provider, meter_provider) = match telemetry_result {
         Ok(Some((logger, runtime))) => {
             eprintln!("🔭 OpenTelemetry enabled: {}", otlp_endpoint.as_ref().unwrap());
@@ -171,7 +503,8 @@ fn native_main() {
             match instrumentation::init_instrumentation(
                 &runtime, 
                 otlp_endpoint.as_ref().unwrap(),
-                args.otlp_metric_interval
+                args.otlp_metric_interval,
+                &system_profile,
             ) {
                 Ok((tracer, meter, tracer_prov, meter_prov)) => {
                     info!("📊 Instrumentation initialized with traces and metrics");
@@ -187,25 +520,29 @@ fn native_main() {
         Ok(None) => {
             eprintln!("ℹ️  OpenTelemetry disabled (no endpoint configured)");
             eprintln!("   Use --otlp-endpoint or OTEL_EXPORTER_OTLP_ENDPOINT to enable");
-            // Fall back to basic console logging
-            tracing_subscriber::fmt()
-                .with_env_filter(tracing_subscriber::EnvFilter::new("info"))
-                .init();
             (None, None, None, None, None, None)
         }
         Err(e) => {
             eprintln!("⚠️  OpenTelemetry unavailable: {}", e);
             eprintln!("   Continuing with console-only logging");
-            // Fall back to basic console logging
-            tracing_subscriber::fmt()
-                .with_env_filter(tracing_subscriber::EnvFilter::new("info"))
-                .init();
             (None, None, None, None, None, None)
         }
     };
 
     let mut app = App::new();
 
+    // Before DefaultPlugins: the packs replace the default asset source.
+    if !args.content_packs.is_empty() {
+        let packs = args.content_packs();
+        for pack in packs.0.iter().filter(|pack| !pack.is_dir()) {
+            eprintln!("⚠️  Content pack {} isn't a directory", pack.display());
+        }
+        for (file, pack) in packs.files() {
+            eprintln!("📦 {} from {}", file.display(), pack.display());
+        }
+        packs.install(&mut app, "assets");
+    }
+
     if args.headless {
         info!("🔧 Running in headless mode (no window, no display server required)");
         app.add_plugins(
@@ -240,6 +577,9 @@ fn native_main() {
                         mode: window_mode,
                         ..default()
                     }),
+                    // The close button asks first when there's unsaved
+                    // progress (quit.rs).
+                    close_when_requested: false,
                     ..default()
                 })
                 .disable::<bevy::log::LogPlugin>()
@@ -247,76 +587,261 @@ fn native_main() {
     }
 
     if args.remote {
+        // Ours first: brp_extras reuses an existing RemotePlugin.
+        app.add_plugins(bevy_remote::RemotePlugin::default().with_method_main(
+            game_events::sinks::POLL_EVENTS_METHOD,
+            game_events::sinks::poll_events,
+        ));
         app.add_plugins(bevy_brp_extras::BrpExtrasPlugin::with_port(args.remote_port));
     }
 
+    if let Some(path) = &args.events_file {
+        match game_events::sinks::EventFile::create(path) {
+            Ok(file) => {
+                app.insert_resource(file);
+            }
+            Err(e) => eprintln!("⚠️  Can't open events file {}: {e}", path.display()),
+        }
+    }
+    if let Some(port) = args.events_port {
+        match game_events::sinks::EventSocket::bind(port) {
+            Ok(socket) => {
+                eprintln!("📣 Streaming game events on 127.0.0.1:{port}");
+                app.insert_resource(socket);
+            }
+            Err(e) => eprintln!("⚠️  Can't listen for events clients on port {port}: {e}"),
+        }
+    }
+
     // Insert CLI args as resource
     app.insert_resource(args.clone());
 
     // Insert telemetry resources if available
+    app.insert_resource(export_errors.clone());
     if let Some(t) = tracer {
         app.insert_resource(t);
     }
     if let Some(m) = meter {
         app.insert_resource(m);
     }
+    if let (Some(tracer), Some(meter)) = (&tracer_provider, &meter_provider) {
+        app.insert_resource(instrumentation::TelemetryProviders { tracer: tracer.clone(), meter: meter.clone() });
+    }
 
-    add_game(&mut app);
-    app.run();
-
-    // Shutdown telemetry when app exits
-    info!("Shutting down instrumentation providers");
-    if let Some(tp) = tracer_provider {
-        if let Err(e) = tp.shutdown() {
-            eprintln!("Failed to shutdown tracer: {}", e);
+    let save_dir = args.save_dir.clone().unwrap_or_else(save::SaveDirectory::default_dir);
+    if args.continue_game {
+        if save::newest_slot(&save_dir).is_some() {
+            app.insert_resource(save_menu::OpenSaveMenuOnStart);
+        } else {
+            eprintln!("ℹ️  No save found in {}, starting a new game", save_dir.display());
         }
     }
-    if let Some(mp) = meter_provider {
-        if let Err(e) = mp.shutdown() {
-            eprintln!("Failed to shutdown meter: {}", e);
+    app.insert_resource(save::SaveDirectory(save_dir));
+    app.insert_resource(thumbnails::ThumbnailCache::default());
+    app.insert_resource(quit::ConfirmQuit::for_run(args.headless, args.frames, args.seconds));
+
+    app.insert_resource(args.settings());
+    app.insert_resource(args.dialogue_settings());
+    app.insert_resource(sregame::dialogue_history::DialogueHistory::with_capacity(args.history_lines));
+    app.insert_resource(args.rng());
+    app.insert_resource(args.frame_watchdog());
+    app.insert_resource(system_profile);
+
+    if args.audit_entities {
+        app.insert_resource(sregame::entity_audit::EntityAudit);
+    }
+    if let Some(minutes) = args.kiosk {
+        eprintln!("🎪 Kiosk mode: a new game after {minutes} idle minutes");
+        app.insert_resource(sregame::kiosk::Kiosk::new(Duration::from_secs_f32(minutes.max(0.0) * 60.0)));
+    }
+    if args.timer {
+        match args.split_timer() {
+            Ok(timer) => {
+                app.insert_resource(timer);
+            }
+            Err(e) => eprintln!("⚠️  No split timer: {e:#}"),
         }
     }
-    if let Some(lp) = logger_provider {
-        if let Err(e) = telemetry::shutdown_telemetry(lp) {
-            eprintln!("Failed to shutdown logger: {}", e);
+    if let Some(path) = &args.summary_json {
+        app.insert_resource(sregame::session_summary::SummaryJson(path.clone()));
+    }
+    let report = args.run_report.as_ref().map(|path| {
+        let report = run_report::RunReportHandle::default();
+        report.install_panic_hook(path.clone());
+        app.insert_resource(report.clone());
+        report
+    });
+    if let Some(dir) = &args.map_dir {
+        app.insert_resource(sregame::map_data::MapDirectory(dir.clone()));
+    }
+    if let Some(name) = &args.chaos {
+        match args.chaos(name) {
+            Ok(chaos) => {
+                eprintln!("🐒 Chaos scenario: {name}");
+                app.insert_resource(chaos);
+            }
+            Err(e) => eprintln!("⚠️  No chaos: {e:#}"),
+        }
+    }
+
+    sregame::add_game(&mut app);
+    app.add_systems(Update, exit_after_n_frames_or_seconds);
+    let exit = app.run();
+
+    // Everything the app does on exit is done; the telemetry isn't shut
+    // down yet, so its export errors are all counted.
+    if let (Some(report), Some(path)) = (report, &args.run_report) {
+        report.finish(&exit, tracer_provider.is_some(), export_errors.count());
+        if let Err(e) = report.snapshot().write(path) {
+            eprintln!("⚠️  Couldn't write the run report to {}: {e}", path.display());
         }
     }
 
+    // Shutdown telemetry when app exits
+    info!("Shutting down instrumentation providers");
+    if let Some(tp) = tracer_provider
+        && let Err(e) = tp.shutdown()
+    {
+        eprintln!("Failed to shutdown tracer: {}", e);
+    }
+    if let Some(mp) = meter_provider
+        && let Err(e) = mp.shutdown()
+    {
+        eprintln!("Failed to shutdown meter: {}", e);
+    }
+    if let Some(lp) = logger_provider
+        && let Err(e) = telemetry::shutdown_telemetry(lp)
+    {
+        eprintln!("Failed to shutdown logger: {}", e);
+    }
+
     // Keep runtime alive for final flush if telemetry was active
     if runtime.is_some() {
         std::thread::sleep(std::time::Duration::from_secs(2));
     }
 }
 
-fn setup(mut commands: Commands) {
-    commands.spawn((
-        Camera2d,
-        MainCamera,
-        CameraFollow::default(),
-        Projection::Orthographic(OrthographicProjection {
-            scaling_mode: bevy::camera::ScalingMode::AutoMin {
-                min_width: camera::VIEW_WIDTH,
-                min_height: camera::VIEW_HEIGHT,
-            },
-            ..OrthographicProjection::default_2d()
-        }),
-        Transform::from_xyz(0.0, 0.0, 999.9),
-    ));
-
-    info!("SRE Game initialized");
+/// `sregame validate`: every issue in `files`, one per line, and the exit
+/// code. Only `sregame::content` - no app, no window.
+///
+/// With `--content-pack`s a file is checked as the game would load it: a
+/// pack's copy, or the map with the packs' patches merged in. What the
+/// packs bring that isn't listed (their maps, patches and dialogue) is
+/// checked too. Every line says where its file came from, the base or a
+/// pack.
+#[cfg(not(target_arch = "wasm32"))]
+fn validate(files: &[std::path::PathBuf], packs: &sregame::content_pack::ContentPacks) -> i32 {
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+    use sregame::content::{self, ValidationOptions};
+
+    let options = ValidationOptions {
+        known_scenes: content::map::SCENE_NAMES.map(String::from).to_vec(),
+        ..Default::default()
+    };
+    // A map's tiles are checked against its scene's tileset.
+    let map_options =
+        |map: &str| ValidationOptions { tileset_tiles: map_tileset_tiles(map, packs), ..options.clone() };
+    let check = |path: &Path, bytes: &[u8]| -> Result<Vec<content::Issue>, String> {
+        if path.to_string_lossy().ends_with(".dialogue.json") {
+            content::dialogue::DialogueData::parse(bytes)
+                .map(|dialogue| content::validate_dialogue(&dialogue, &options))
+                .map_err(|e| e.to_string())
+        } else {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            content::map::MapData::parse_file(path, bytes)
+                .map(|map| content::validate_map(&map, &map_options(&name)))
+                .map_err(|e| e.to_string())
+        }
+    };
+    let check_map = |map: &str, loaded: anyhow::Result<content::map::MapData>| {
+        loaded.map(|loaded| content::validate_map(&loaded, &map_options(map))).map_err(|e| format!("{e:#}"))
+    };
+    let (mut checked, mut failed) = (0, 0);
+    let mut report = |shown: &str, origin: &str, issues: Result<Vec<content::Issue>, String>| {
+        checked += 1;
+        let issues = issues.map_or_else(|e| vec![e], |issues| issues.iter().map(ToString::to_string).collect());
+        failed += usize::from(!issues.is_empty());
+        for issue in issues {
+            println!("{shown} ({origin}): {issue}");
+        }
+    };
+    let pack_origin = |pack: &Path| format!("pack {}", pack.display());
+
+    let mut listed = BTreeSet::new();
+    for path in files {
+        // Packs mirror assets/, so that's what a listed file is relative to.
+        let relative = path.strip_prefix("assets").unwrap_or(path).to_path_buf();
+        if let Some(map) = pack_map_name(&relative)
+            && let Some((loaded, shown)) = packs.load_map(&map, path.parent())
+        {
+            report(&shown, "patched by packs", check_map(&map, loaded));
+        } else {
+            let (read, origin) = match packs.origin(&relative) {
+                Some(pack) => (pack.join(&relative), pack_origin(pack)),
+                None => (path.clone(), "base".to_string()),
+            };
+            let issues = std::fs::read(&read).map_err(|e| e.to_string()).and_then(|bytes| check(&read, &bytes));
+            report(&read.display().to_string(), &origin, issues);
+        }
+        listed.insert(pack_map_name(&relative).map_or(relative, PathBuf::from));
+    }
+    for (relative, pack) in packs.files() {
+        if let Some(map) = pack_map_name(&relative) {
+            if listed.insert(PathBuf::from(&map))
+                && let Some((loaded, shown)) = packs.load_map(&map, None)
+            {
+                report(&shown, "patched by packs", check_map(&map, loaded));
+            }
+        } else if relative.to_string_lossy().ends_with(".dialogue.json") && listed.insert(relative.clone()) {
+            let read = pack.join(&relative);
+            let issues = std::fs::read(&read).map_err(|e| e.to_string()).and_then(|bytes| check(&read, &bytes));
+            report(&read.display().to_string(), &pack_origin(pack), issues);
+        }
+    }
+    println!("{checked} file(s) checked, {failed} with problems");
+    i32::from(failed > 0)
 }
 
-/// `Scene` is a sub-state sourced from `GameState::Playing`, so entering
-/// Playing already creates it at its `#[default]` (`TownOfEndgame`). Do not
-/// `set()` it here: since Bevy 0.18, setting a state to its current value
-/// re-fires OnExit/OnEnter, which would despawn and respawn the town map one
-/// frame after it first spawned.
-fn on_enter_playing() {
-    info!("Entering Playing state - player can explore");
+/// How many tiles the tileset of the scene drawn from map `map` has, read
+/// from its image under assets/ - or a pack's, as the game would load it.
+/// None when no scene draws the map or the image won't read: its tiles go
+/// unchecked.
+#[cfg(not(target_arch = "wasm32"))]
+fn map_tileset_tiles(map: &str, packs: &sregame::content_pack::ContentPacks) -> Option<u32> {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
+    use sregame::tilemap::scene_config;
+
+    let scene = sregame::game_state::Scene::ALL.into_iter().find(|&scene| scene_config(scene).map_file == map)?;
+    let relative = std::path::PathBuf::from(format!("{}/{}.png", sregame::assets::TILESETS_DIR, scene_config(scene).tileset_key));
+    let path = packs.origin(&relative).map_or_else(|| std::path::Path::new("assets").join(&relative), |pack| pack.join(&relative));
+    let bytes = std::fs::read(path).ok()?;
+    let image = Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    )
+    .ok()?;
+    Some(sregame::tile_animation::tileset_tiles(image.size()))
 }
 
-fn on_enter_dialogue() {
-    info!("Entering Dialogue state - reading conversation");
+/// The map a pack file at `relative` replaces or patches
+/// (`data/maps/<map>.json`, `data/maps/<map>.patch.json`).
+#[cfg(not(target_arch = "wasm32"))]
+fn pack_map_name(relative: &std::path::Path) -> Option<String> {
+    if relative.parent() != Some(std::path::Path::new("data/maps")) {
+        return None;
+    }
+    let name = relative.file_name()?.to_str()?;
+    if name.ends_with(".dialogue.json") {
+        return None;
+    }
+    let map = name.strip_suffix(".patch.json").or_else(|| name.strip_suffix(".json"))?;
+    Some(map.to_string())
 }
 
 fn exit_after_n_frames_or_seconds(
@@ -324,78 +849,92 @@ fn exit_after_n_frames_or_seconds(
     time: Res<Time>,
     mut frame_count: Local<u64>,
     mut exit: MessageWriter<bevy::app::AppExit>,
+    report: Option<Res<sregame::run_report::RunReportHandle>>,
 ) {
     *frame_count += 1;
     let elapsed = time.elapsed_secs_f64() as f32;
 
-    if let Some(frames) = args.frames {
-        if *frame_count >= frames {
-            info!("Reached target frame count ({frames}), exiting.");
-            exit.write(bevy::app::AppExit::Success);
+    if let Some(frames) = args.frames
+        && *frame_count >= frames
+    {
+        info!("Reached target frame count ({frames}), exiting.");
+        if let Some(report) = &report {
+            report.exit_reason("frame limit");
         }
+        exit.write(bevy::app::AppExit::Success);
     }
 
-    if let Some(seconds) = args.seconds {
-        if elapsed >= seconds {
-            info!("Reached target duration ({seconds}s), exiting.");
-            exit.write(bevy::app::AppExit::Success);
+    if let Some(seconds) = args.seconds
+        && elapsed >= seconds
+    {
+        info!("Reached target duration ({seconds}s), exiting.");
+        if let Some(report) = &report {
+            report.exit_reason("time limit");
         }
+        exit.write(bevy::app::AppExit::Success);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use bevy::state::app::StatesPlugin;
-    use crate::game_state::Scene;
-
-    #[derive(Resource, Default)]
-    struct TownEnterCount(u32);
-
-    /// Entering `GameState::Playing` must spawn the town exactly once.
-    ///
-    /// `on_enter_playing` sets `Scene::TownOfEndgame` even though the `Scene`
-    /// sub-state is created with that same `#[default]` value the moment
-    /// `Playing` is entered. Under Bevy <= 0.17 an identity `set()` is
-    /// swallowed; Bevy 0.18 changes state semantics so that setting the
-    /// current value re-fires `OnExit`/`OnEnter`. If that redundant set
-    /// survives the 0.18 hop, `OnEnter(Scene::TownOfEndgame)` fires twice -
-    /// in the real game that is a full despawn + respawn of the town map one
-    /// frame after it first spawned. This test drives the real state machine
-    /// with `on_enter_playing` wired up and counts town entries.
+
+    fn problems(flags: &[&str]) -> Vec<String> {
+        Args::parse_from(std::iter::once("sregame").chain(flags.iter().copied())).problems(None)
+    }
+
+    #[test]
+    fn sensible_flags_pass() {
+        assert!(problems(&[]).is_empty());
+        assert!(problems(&["--headless", "--frames", "10", "--seconds", "2.5", "--text-speed", "0"]).is_empty());
+        assert!(problems(&["--timer", "--splits", "splits.json", "--chaos", "frame_spikes"]).is_empty());
+        let with_env = Args::parse_from(["sregame", "--otlp-metric-interval", "500"]).problems(Some("127.0.0.1:4317"));
+        assert!(with_env.is_empty(), "the env var is an endpoint too: {with_env:?}");
+    }
+
     #[test]
-    fn entering_playing_enters_town_exactly_once() {
-        let mut app = App::new();
-        app.add_plugins(StatesPlugin)
-            .init_state::<GameState>()
-            .add_sub_state::<Scene>()
-            .add_sub_state::<Mode>()
-            .init_resource::<TownEnterCount>()
-            .add_systems(OnEnter(GameState::Playing), on_enter_playing)
-            .add_systems(
-                OnEnter(Scene::TownOfEndgame),
-                |mut count: ResMut<TownEnterCount>| count.0 += 1,
-            );
-
-        // Loading -> Playing, mirroring assets::check_asset_loading.
-        app.world_mut()
-            .resource_mut::<NextState<GameState>>()
-            .set(GameState::Playing);
-        app.update();
-        assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Playing);
-        assert_eq!(*app.world().resource::<State<Scene>>().get(), Scene::TownOfEndgame);
-
-        // Give any redundant NextState::set queued by on_enter_playing time
-        // to apply (state transitions resolve on the following update).
-        app.update();
-        app.update();
-        app.update();
+    fn pack_files_name_the_map_they_replace_or_patch() {
+        use std::path::Path;
+        assert_eq!(pack_map_name(Path::new("data/maps/town.json")).as_deref(), Some("town"));
+        assert_eq!(pack_map_name(Path::new("data/maps/town.patch.json")).as_deref(), Some("town"));
+        assert_eq!(pack_map_name(Path::new("data/maps/intro.dialogue.json")), None);
+        assert_eq!(pack_map_name(Path::new("data/portraits.json")), None);
+    }
 
+    #[test]
+    fn text_speed_is_a_multiple_of_the_normal_pace() {
+        use sregame::dialogue::DEFAULT_CHARS_PER_SECOND;
+        let chars_per_second = |flags: &[&str]| {
+            Args::parse_from(std::iter::once("sregame").chain(flags.iter().copied())).dialogue_settings().chars_per_second
+        };
+        assert_eq!(chars_per_second(&[]), DEFAULT_CHARS_PER_SECOND);
+        assert_eq!(chars_per_second(&["--text-speed", "2"]), 2.0 * DEFAULT_CHARS_PER_SECOND);
+        assert_eq!(chars_per_second(&["--text-speed", "0"]), 0.0);
+    }
+
+    #[test]
+    fn every_problem_is_reported_together() {
+        let found = problems(&[
+            "--frames", "0",
+            "--seconds", "-5",
+            "--otlp-metric-interval", "500",
+            "--remote-port", "16000",
+            "--splits", "splits.json",
+            "--chaos", "meteor",
+            "--headless", "--fullscreen",
+        ]);
         assert_eq!(
-            app.world().resource::<TownEnterCount>().0,
-            1,
-            "OnEnter(Scene::TownOfEndgame) fired more than once entering Playing; \
-             the town map would despawn and respawn after its first spawn"
+            found,
+            [
+                "--frames 0 would exit before the first frame; give at least 1",
+                "--seconds -5 isn't a positive number of seconds",
+                "--otlp-metric-interval has nothing to export to; add --otlp-endpoint or set OTEL_EXPORTER_OTLP_ENDPOINT",
+                "--remote-port 16000 does nothing without --remote",
+                "--splits does nothing without --timer",
+                "--chaos \"meteor\" isn't a scenario; pick one of frame_spikes, dialogue_latency, error_burst",
+                "--fullscreen does nothing with --headless, which has no window",
+            ]
         );
+        assert_eq!(problems(&["--ambient-chatter", "-1", "--kiosk", "0"]).len(), 2);
     }
 }
@@ -1,209 +1,130 @@
 use bevy::prelude::*;
-use serde::Deserialize;
 use anyhow::{Context, Result};
 
-#[derive(Debug, Deserialize)]
-pub struct MapData {
-    pub name: String,
-    pub width: u32,
-    pub height: u32,
-    /// Ground-layer atlas indices, one per cell (row-major, RPGMaker
-    /// orientation: row 0 is the TOP row of the map, matching the source
-    /// Map*.json data planes). Index 0 is a reserved fully-transparent tile.
-    /// The top-down -> bottom-up (+y up) conversion happens exactly once, at
-    /// the world boundary: `tile_to_world`/`world_to_tile` here and the
-    /// `TilePos` mapping in tilemap.rs::spawn_map.
-    pub tiles: Vec<u32>,
-    /// Upper-layer (drawn above the player/NPCs) atlas indices into the
-    /// *same* atlas as `tiles`, same shape as `tiles`. 0 means "no
-    /// upper-layer decoration on this cell". Produced by
-    /// tools/convert_maps.py from RPGMaker's per-tile 0x10 "higher" flag.
-    /// Defaults to empty for map JSON predating this field; an absent index
-    /// renders as tile 0 (blank), same as an explicit empty array.
-    #[serde(default)]
-    pub upper_tiles: Vec<u32>,
-    /// Per-cell fully-blocked flag (row-major, same shape as `tiles`),
-    /// baked from RPGMaker tileset passability flags by
-    /// tools/convert_maps.py. See CollisionMap in tilemap.rs. Defaults to
-    /// empty for map JSON predating this field; an absent index then reads
-    /// as blocked (fail closed) via `.unwrap_or(true)` in tilemap.rs, not
-    /// walkable. Superseded by `passability` when present - kept as the
-    /// coarse fallback for older JSON.
-    #[serde(default)]
-    pub collision: Vec<bool>,
-    /// Per-cell 4-bit directional passability masks (row-major, same shape
-    /// as `tiles`), baked from RPGMaker's Game_Map.checkPassage semantics:
-    /// bit set = can move OUT of this cell in that direction. Bit values
-    /// match RPGMaker's flag nibble and tilemap.rs's PASS_* constants:
-    /// 1=down, 2=left, 4=right, 8=up (down = +y in RPGMaker orientation).
-    /// This is what represents shop counters, storefront edges, and wall
-    /// bands that are passable from some sides only. Defaults to empty for
-    /// map JSON predating this field (tilemap.rs then falls back to
-    /// `collision`).
-    #[serde(default)]
-    pub passability: Vec<u8>,
-    /// Sparse [x, y] list of counter cells (RPGMaker's Counter tile flag,
-    /// 0x80): the action button reaches ONE tile across a counter, which is
-    /// how shopkeepers standing behind counters are talkable. Baked by
-    /// tools/convert_maps.py; defaults to empty for map JSON predating this
-    /// field. See handle_interaction_input in npc.rs.
-    #[serde(default)]
-    pub counters: Vec<(u32, u32)>,
-    /// Sparse [x, y] list of tiles that pulse a soft "interact here"
-    /// highlight (the retro table's parchment map, the End fairies, ...).
-    /// Purely visual, decoupled from exit trigger tiles so the marker can
-    /// sit on the eye-catching graphic while the trigger stays on the
-    /// walkable tile(s). Baked by tools/convert_maps.py's synthesis passes;
-    /// defaults to empty for map JSON predating this field.
-    #[serde(default)]
-    pub indicators: Vec<(u32, u32)>,
-    pub npcs: Vec<NpcData>,
-    #[serde(default)]
-    pub exits: Vec<ExitData>,
-    /// Visible door sprites sitting on exit trigger tiles (the town's
-    /// `!doors` events). Purely visual - the exit logic itself lives in
-    /// `exits`. Defaults to empty for map JSON predating this field.
-    #[serde(default)]
-    pub doors: Vec<DoorData>,
-    /// Ambient visual props: image-bearing events with no dialogue and no
-    /// transfer (doggo, The Boss's Truck). Defaults to empty for map JSON
-    /// predating this field.
-    #[serde(default)]
-    pub props: Vec<PropData>,
-}
+// The formats themselves live in `content`, free of Bevy; this module is
+// how the game loads them.
+pub use crate::content::dialogue::*;
+pub use crate::content::map::*;
+pub use crate::content::authored_text;
 
-/// One ambient prop sprite. Same sheet-slicing rules as `DoorData`;
-/// `blocks` carries RPGMaker's event collision (priority "same as
-/// characters" + through=false makes the event's tile impassable, which our
-/// tile-flag-baked collision can't know about).
-#[derive(Debug, Clone, Deserialize)]
-pub struct PropData {
-    pub name: String,
-    pub x: u32,
-    pub y: u32,
-    pub sprite: String,
-    pub sprite_index: u32,
-    pub facing: String,
-    pub pattern: u32,
-    #[serde(default)]
-    pub step_anime: bool,
-    #[serde(default)]
-    pub blocks: bool,
-    pub frame_width: u32,
-    pub frame_height: u32,
+// `DialogueData` as an asset: it holds no handles.
+impl bevy::asset::VisitAssetDependencies for DialogueData {
+    fn visit_dependencies(&self, _visit: &mut impl FnMut(bevy::asset::UntypedAssetId)) {}
 }
 
-/// One door sprite. `frame_width`/`frame_height` are baked by
-/// tools/convert_maps.py from the sheet's dimensions (RPGMaker frames are
-/// sheet_width/12 x sheet_height/8; doors.png is 576x768, so door frames
-/// are 48x96 - one tile wide, two tiles tall). `facing` is the resting
-/// animation row ("down" = closed); `pattern` the resting column.
-#[derive(Debug, Clone, Deserialize)]
-pub struct DoorData {
-    pub x: u32,
-    pub y: u32,
-    pub sprite: String,
-    pub sprite_index: u32,
-    pub facing: String,
-    pub pattern: u32,
-    pub frame_width: u32,
-    pub frame_height: u32,
-}
+impl Asset for DialogueData {}
 
-/// How an exit fires. RPGMaker trigger 0 = Action Button (stand on the
-/// event and press confirm); triggers 1/2 fire on contact. Flattening
-/// action events into touch exits made Map009's "retro dialog" event (by
-/// the inn table) warp unsuspecting players straight to the End scene.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum ExitTrigger {
-    #[default]
-    Touch,
-    Action,
-}
+bevy::reflect::impl_type_path!((in sregame::map_data) DialogueData);
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct ExitData {
-    pub trigger_x: u32,
-    pub trigger_y: u32,
-    /// Matches a `Scene` variant name (e.g. "TeamMarathon"), see `scene_from_str`.
-    pub target_scene: String,
-    pub target_spawn_x: u32,
-    pub target_spawn_y: u32,
-    /// Defaults to Touch for map JSON predating this field.
-    #[serde(default)]
-    pub trigger: ExitTrigger,
-    /// Scripted scene played before the transfer fires (empty for plain
-    /// exits). Each segment is one RPGMaker message box with its own
-    /// speaker/portrait - Map009's "retro dialog" retrospective is the
-    /// motivating case.
-    #[serde(default)]
-    pub dialogue: Vec<DialogueSegmentData>,
-    /// When true, force-closing this exit's dialogue with Escape CANCELS
-    /// the transfer instead of firing it (a consent prompt: the End
-    /// fairies). When false - the default, and the retro retrospective's
-    /// behavior - Escape skips the scene but still transfers, so skipping
-    /// the climax can't strand the player.
-    #[serde(default)]
-    pub cancel_on_escape: bool,
-}
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::map_data"]
+    struct DialogueTopic {
+        id: String,
+        label: String,
+        lines: Vec<String>,
+        requires_flag: Option<String>,
+    }
+);
 
-/// One message box of a scripted scene: RPGMaker code-101 parameters plus
-/// the box's joined 401 text.
-#[derive(Debug, Clone, Deserialize)]
-pub struct DialogueSegmentData {
-    pub speaker: String,
-    /// Face sheet name (empty = no portrait), resolved against
-    /// assets/textures/portraits/<name>.png at display time.
-    pub portrait: String,
-    #[serde(default)]
-    pub face_index: u32,
-    pub text: String,
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::map_data"]
+    enum BoxPosition {
+        Top,
+        Bottom,
+    }
+);
+
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::map_data"]
+    struct DialogueBoxLayout {
+        box_position: Option<BoxPosition>,
+        box_width: Option<f32>,
+        box_height: Option<f32>,
+    }
+);
+
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::map_data"]
+    enum DialogueCondition {
+        HasFlag(String),
+        NotFlag(String),
+    }
+);
+
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::map_data"]
+    enum LineAction {
+        SetFlag(String),
+        ChangeScene(String),
+    }
+);
+
+bevy::reflect::impl_reflect!(
+    #[type_path = "sregame::map_data"]
+    enum DialogueRepeat {
+        Always,
+        Once,
+        Cooldown(f32),
+    }
+);
+
+impl DialogueData {
+    /// Every line's branching with its targets resolved to line indices,
+    /// by line; empty for a linear conversation. Targets that don't
+    /// resolve are left out (`branch_problem` reports them).
+    pub fn branches(&self) -> std::collections::BTreeMap<usize, crate::dialogue::DialogueBranch> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !line.choices.is_empty() || line.end)
+            .map(|(index, line)| {
+                let choices = line
+                    .choices
+                    .iter()
+                    .filter_map(|choice| {
+                        Some(crate::dialogue::BranchChoice {
+                            label: choice.label.clone(),
+                            target: self.resolve(&choice.goto)?,
+                        })
+                    })
+                    .collect();
+                (index, crate::dialogue::DialogueBranch { choices, end: line.end })
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct NpcData {
-    pub name: String,
-    pub x: u32,
-    pub y: u32,
-    pub sprite: String,
-    /// Which character slot (0-7) of the `sprite` sheet this NPC uses -
-    /// RPGMaker MZ's `image.characterIndex` (sheets hold a 4x2 grid of
-    /// characters, see character_sheet.rs). Defaults to 0 (top-left slot)
-    /// for map JSON predating this field.
-    #[serde(default)]
-    pub sprite_index: u32,
-    /// RPGMaker's "Stepping Animation": play the walk cycle in place while
-    /// standing still. Nearly every NPC in the original has this enabled.
-    /// Defaults to false (a statue) for map JSON predating this field.
-    #[serde(default)]
-    pub step_anime: bool,
-    /// Random tile-step wandering (doggo). Wandering respects map
-    /// passability even for `through` characters - engine-divergent,
-    /// intent-faithful (see npc.rs::wander_npcs). Defaults to false.
-    #[serde(default)]
-    pub wander: bool,
-    /// RPGMaker's Through flag: the character never blocks the player
-    /// (skips the NPC body collider - see NpcBody in npc.rs). Only doggo
-    /// has it in the original. Defaults to false.
-    #[serde(default)]
-    pub through: bool,
-    pub facing: String,
-    pub dialogue: DialogueData,
+/// Loads `*.dialogue.json` as `DialogueData` (see `NpcData::dialogue_file`).
+#[derive(Default, TypePath)]
+pub struct DialogueDataLoader;
+
+impl bevy::asset::AssetLoader for DialogueDataLoader {
+    type Asset = DialogueData;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn bevy::asset::io::Reader,
+        _settings: &(),
+        _load_context: &mut bevy::asset::LoadContext<'_>,
+    ) -> Result<DialogueData> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(DialogueData::parse(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.json"]
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct DialogueData {
-    pub speaker: String,
-    pub portrait: String,
-    /// Which cell of `portrait`'s face sheet to display (RPGMaker MZ code-101
-    /// "Show Face" `faceIndex`, 0-7 in the standard 4-column x 2-row 144x144px
-    /// grid layout - see tools/convert_maps.py's
-    /// `extract_dialogue_from_commands`). Defaults to 0 (top-left cell) for
-    /// map JSON predating this field.
-    #[serde(default)]
-    pub face_index: u32,
-    pub lines: Vec<String>,
+/// `DialogueData` as an asset, for every plugin that loads or reads
+/// dialogue files - whichever of them is added first registers it.
+pub fn init_dialogue_assets(app: &mut App) {
+    if app.world().contains_resource::<AssetServer>() && !app.world().contains_resource::<Assets<DialogueData>>() {
+        app.init_asset::<DialogueData>().register_asset_loader(DialogueDataLoader);
+    }
 }
 
 impl MapData {
@@ -212,13 +133,44 @@ impl MapData {
             anyhow::anyhow!("no map named {map_name:?} in the embedded manifest")
         })?;
 
-        let map: MapData = serde_json::from_str(json)
-            .context("Failed to parse map JSON")?;
+        Self::parse(json.as_bytes()).context("Failed to parse map JSON")
+    }
+
+    /// Load `<dir>/<map_name>.json` from disk instead of the embedded
+    /// manifest - see `MapDirectory` - or, failing that, a map exported
+    /// from Tiled (`.tmj`, see content/tiled.rs). Native only: the browser
+    /// has no filesystem, and scripts/check-wasm.sh keeps std::fs out of
+    /// wasm.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_from_dir(dir: &std::path::Path, map_name: &str) -> Result<Self> {
+        let path = Self::path_in_dir(dir, map_name);
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::parse_file(&path, &bytes).with_context(|| format!("Failed to parse map {}", path.display()))
+    }
 
-        Ok(map)
+    /// The file `load_from_dir` reads: the first of `<map_name>.json`,
+    /// `.tmj` and `.tmx` there is, else the `.json` that isn't.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path_in_dir(dir: &std::path::Path, map_name: &str) -> std::path::PathBuf {
+        ["json", "tmj", "tmx"]
+            .into_iter()
+            .map(|extension| dir.join(format!("{map_name}.{extension}")))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| dir.join(format!("{map_name}.json")))
     }
 }
 
+/// When present, scenes load their map JSON from this directory rather than
+/// the copies build.rs embedded in the binary - so test fixtures
+/// (testing::TestGame) can supply tiny purpose-built maps without touching
+/// the shipped content, and `--map-dir` can pick up map edits without a
+/// rebuild (live_tune.rs applies them to the current map's NPCs).
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource, Clone, Debug)]
+pub struct MapDirectory(pub std::path::PathBuf);
+
 /// Converts a tile coordinate in RPGMaker orientation (y = 0 is the TOP row,
 /// y grows downward - the convention all map JSON, NPC, and exit data is
 /// stored in) to a Bevy world-space position (+y is up, map centered on the
@@ -248,6 +200,18 @@ pub fn world_to_tile(world_pos: Vec2, map_width: u32, map_height: u32) -> (i32,
     (tile_x, tile_y)
 }
 
+/// An exit's `target_facing` as the player's `Facing`; `None` for anything
+/// but one of `FACINGS` (a content error, see `ExitData::facing_problem`).
+pub fn player_facing_from_string(facing: &str) -> Option<crate::player::Facing> {
+    match facing {
+        "down" => Some(crate::player::Facing::Down),
+        "left" => Some(crate::player::Facing::Left),
+        "right" => Some(crate::player::Facing::Right),
+        "up" => Some(crate::player::Facing::Up),
+        _ => None,
+    }
+}
+
 pub fn facing_from_string(facing: &str) -> crate::npc::NpcFacing {
     match facing {
         "down" => crate::npc::NpcFacing::Down,
@@ -367,6 +331,11 @@ mod tests {
         for (name, expected) in cases {
             assert_eq!(scene_from_str(name), Some(expected), "failed for '{name}'");
         }
+        // What `validate` checks exits against is the same list.
+        assert!(SCENE_NAMES.iter().all(|name| scene_from_str(name).is_some()));
+        assert_eq!(SCENE_NAMES.len(), cases.len());
+        // And every scene gets its map spawned (tilemap.rs).
+        assert!(cases.iter().all(|(_, scene)| Scene::ALL.contains(scene)));
     }
 
     #[test]
@@ -396,19 +365,133 @@ mod tests {
         assert_eq!(map.exits[0].target_scene, "TeamMarathon");
     }
 
+    #[test]
+    fn windows_authored_maps_parse_without_bom_or_crs() {
+        let json = "\u{feff}{\r\n \"name\": \"Notepad\", \"width\": 1, \"height\": 1, \"tiles\": [],\r\n \"npcs\": [ {\r\n  \"name\": \"Isabella\", \"x\": 0, \"y\": 0, \"sprite\": \"Isabella\", \"facing\": \"down\",\r\n  \"ambient_lines\": [\"Hi\\r\\nthere\"],\r\n  \"dialogue\": { \"speaker\": \"Isabella\", \"portrait\": \"\",\r\n   \"lines\": [\"One\\r\\nTwo\"] } } ]\r\n}\r\n";
+        let map = MapData::parse(json.as_bytes()).expect("BOM and CRLF should parse");
+        assert_eq!(map.npcs[0].dialogue.lines[0].text, "One\nTwo");
+        assert_eq!(map.npcs[0].ambient_lines, ["Hi\nthere"]);
+    }
+
+    #[test]
+    fn dialogue_files_parse_like_maps_and_excuse_the_inline_check() {
+        let file = DialogueData::parse("\u{feff}{ \"speaker\": \"Casey\", \"portrait\": \"\",\r\n \"lines\": [\"One\\r\\nTwo\"] }".as_bytes())
+            .expect("a dialogue file should parse");
+        assert_eq!(file.lines[0].text, "One\nTwo");
+        assert_eq!(file.problem(), None);
+        assert_eq!(DialogueData::default().problem().as_deref(), Some("has no dialogue lines"));
+
+        let json = r#"{ "name": "Casey", "x": 0, "y": 0, "sprite": "Casey", "facing": "down",
+                        "dialogue_file": "data/dialogue/casey_intro.dialogue.json" }"#;
+        let npc: NpcData = serde_json::from_str(json).expect("an NPC without inline dialogue should parse");
+        assert_eq!(npc.dialogue_file.as_deref(), Some("data/dialogue/casey_intro.dialogue.json"));
+        assert_eq!(npc.dialogue_problem(), None, "its file is checked when it loads");
+    }
+
+    #[test]
+    fn invalid_utf8_names_its_byte_offset() {
+        assert_eq!(authored_text(b"\xef\xbb\xbf{}").unwrap(), "{}");
+        assert_eq!(authored_text(b"{}").unwrap(), "{}");
+        // "café" as Latin-1, not UTF-8.
+        let error = authored_text(b"{\"name\": \"caf\xe9\"}").unwrap_err().to_string();
+        assert_eq!(error, "invalid UTF-8 at byte offset 13");
+    }
+
+    #[test]
+    fn dialogue_lines_are_text_or_branching_objects() {
+        let json = r#"{
+            "speaker": "Isabella",
+            "portrait": "",
+            "lines": [
+                { "text": "Which team?", "choices": [
+                    { "label": "Disco", "goto": "disco" },
+                    { "label": "Marathon", "goto": 2 } ] },
+                { "text": "Disco!", "id": "disco", "end": true },
+                "Marathon!"
+            ]
+        }"#;
+        let data: DialogueData = serde_json::from_str(json).expect("branching dialogue should parse");
+        assert_eq!(data.lines[2], DialogueLine::from("Marathon!"));
+        assert_eq!(data.branch_problem(), None);
+        let branches = data.branches();
+        let targets: Vec<usize> = branches[&0].choices.iter().map(|choice| choice.target).collect();
+        assert_eq!(targets, [1, 2]);
+        assert!(branches[&1].end);
+        assert_eq!(branches.len(), 2, "plain lines just lead on");
+
+        let mut broken = data.clone();
+        broken.lines[0].choices[1].goto = LineTarget::Id("inferno".into());
+        assert!(broken.branch_problem().is_some_and(|problem| problem.contains("goes to no line")));
+        let mut broken = data;
+        broken.lines[2].id = Some("disco".into());
+        assert!(broken.branch_problem().is_some_and(|problem| problem.contains("used twice")));
+    }
+
+    #[test]
+    fn group_dialogues_name_their_problems() {
+        let json = r#"{
+            "name": "Test Map",
+            "width": 10,
+            "height": 10,
+            "tiles": [],
+            "npcs": [
+                { "name": "Casey", "x": 1, "y": 1, "sprite": "Casey", "facing": "down",
+                  "dialogue": { "speaker": "Casey", "portrait": "", "lines": ["Hi."] } },
+                { "name": "Mando", "x": 3, "y": 1, "sprite": "Mando", "facing": "down",
+                  "dialogue": { "speaker": "Mando", "portrait": "", "lines": ["Hey."] } }
+            ],
+            "group_dialogues": [
+                { "participants": ["Casey", "Mando"], "trigger_radius": 200, "dialogue_ref": "standup", "once": true },
+                { "participants": ["Casey", "Doggo"], "trigger_radius": 200, "dialogue_ref": "standup" },
+                { "participants": ["Casey", "Mando"], "trigger_radius": 200, "dialogue_ref": "retro" },
+                { "participants": ["Casey", "Mando"], "trigger_radius": 200, "dialogue_ref": "gossip" }
+            ],
+            "conversations": {
+                "standup": [
+                    { "speaker": "Casey", "text": "Deploys are green." },
+                    { "speaker": "Mando", "text": "Pager's quiet." }
+                ],
+                "gossip": [{ "speaker": "Isabella", "text": "Psst." }]
+            }
+        }"#;
+
+        let map: MapData = serde_json::from_str(json).expect("valid map JSON should parse");
+        let problems: Vec<Option<String>> = map.group_dialogues.iter().map(|group| group.problem(&map)).collect();
+        assert_eq!(problems[0], None);
+        assert_eq!(map.group_dialogues[0].cooldown_seconds, 60.0);
+        assert!(problems[1].as_deref().is_some_and(|p| p.contains("\"Doggo\" isn't an NPC")), "{problems:?}");
+        assert!(problems[2].as_deref().is_some_and(|p| p.contains("no conversation")), "{problems:?}");
+        assert!(problems[3].as_deref().is_some_and(|p| p.contains("isn't taking part")), "{problems:?}");
+    }
+
     #[test]
     fn every_shipped_map_npc_sprite_and_portrait_file_exists() {
-        // GameAssets loads sprites/portraits by scanning
-        // assets/textures/{characters,portraits}/*.png at startup (see
-        // assets.rs) - there's no compile-time check that a map's NPC data
-        // references a file that's actually on disk. A missing sprite is a
-        // silent "warn + skip" in tilemap.rs (the NPC just never appears),
-        // and a missing portrait falls back to no portrait in the dialogue
-        // box - both easy to miss when adding a new map's content by hand.
-        // This test catches that at `cargo test` time instead of by
-        // noticing an NPC silently didn't spawn during a playtest.
-        let characters_dir = std::path::Path::new("assets/textures/characters");
+        // GameAssets finds sprites through the sprite registry
+        // (assets/data/sprites.json) and portraits by scanning
+        // assets/textures/portraits/*.png at startup (see assets.rs) -
+        // there's no compile-time check that a map's NPC data names
+        // something that's actually there. A missing sprite is a "warn +
+        // skip" in tilemap.rs (the NPC just never appears), and a missing
+        // portrait falls back to no portrait in the dialogue box - both easy
+        // to miss when adding a new map's content by hand. This test catches
+        // that at `cargo test` time instead of by noticing an NPC didn't
+        // spawn during a playtest.
         let portraits_dir = std::path::Path::new("assets/textures/portraits");
+        let shipped: Vec<String> = crate::asset_manifest::CHARACTER_SPRITES
+            .iter()
+            .map(|stem| format!("textures/characters/{stem}.png"))
+            .collect();
+        let (sprites, _) = crate::character_sheet::load_character_sprites(&shipped);
+        // What's wrong with `what` drawing character `slot` of `sprite`.
+        let sprite_problem = |what: String, sprite: &str, slot: u32| match sprites.get(sprite) {
+            None => Some(format!("{what} sprite '{sprite}' isn't in assets/data/sprites.json")),
+            Some(entry) if slot >= entry.options.grid.slots() => Some(format!(
+                "{what} sprite_index {slot} is past the {} characters '{sprite}' holds \
+                 (character_sheet.rs would panic at spawn)",
+                entry.options.grid.slots()
+            )),
+            Some(_) => None,
+        };
 
         let mut missing = Vec::new();
 
@@ -418,20 +501,7 @@ mod tests {
             let map = MapData::load(map_name).expect("shipped map JSON should parse");
 
             for npc in &map.npcs {
-                if !characters_dir.join(format!("{}.png", npc.sprite)).exists() {
-                    missing.push(format!(
-                        "{map_name}: NPC '{}' sprite '{}' -> assets/textures/characters/{}.png",
-                        npc.name, npc.sprite, npc.sprite
-                    ));
-                }
-                if npc.sprite_index > 7 {
-                    missing.push(format!(
-                        "{map_name}: NPC '{}' sprite_index {} exceeds the 0-7 \
-                         character slots a sheet holds (character_sheet.rs \
-                         would panic at spawn)",
-                        npc.name, npc.sprite_index
-                    ));
-                }
+                missing.extend(sprite_problem(format!("{map_name}: NPC '{}'", npc.name), &npc.sprite, npc.sprite_index));
                 let portrait = &npc.dialogue.portrait;
                 if !portrait.is_empty() && !portraits_dir.join(format!("{portrait}.png")).exists() {
                     missing.push(format!(
@@ -456,27 +526,12 @@ mod tests {
             }
 
             for door in &map.doors {
-                if !characters_dir.join(format!("{}.png", door.sprite)).exists() {
-                    missing.push(format!(
-                        "{map_name}: door at ({}, {}) sprite '{}' -> assets/textures/characters/{}.png",
-                        door.x, door.y, door.sprite, door.sprite
-                    ));
-                }
+                let what = format!("{map_name}: door at ({}, {})", door.x, door.y);
+                missing.extend(sprite_problem(what, &door.sprite, door.sprite_index));
             }
 
             for prop in &map.props {
-                if !characters_dir.join(format!("{}.png", prop.sprite)).exists() {
-                    missing.push(format!(
-                        "{map_name}: prop '{}' sprite '{}' -> assets/textures/characters/{}.png",
-                        prop.name, prop.sprite, prop.sprite
-                    ));
-                }
-                if prop.sprite_index > 7 {
-                    missing.push(format!(
-                        "{map_name}: prop '{}' sprite_index {} exceeds the 0-7 slots",
-                        prop.name, prop.sprite_index
-                    ));
-                }
+                missing.extend(sprite_problem(format!("{map_name}: prop '{}'", prop.name), &prop.sprite, prop.sprite_index));
             }
         }
 
@@ -632,7 +687,7 @@ mod tests {
         let doggo = map.npcs.iter().find(|n| n.name == "doggo").expect("doggo should be an NPC now");
         assert!(doggo.wander, "doggo wanders");
         assert!(doggo.through, "doggo never blocks");
-        assert_eq!(doggo.dialogue.lines, vec!["wan wan!"]);
+        assert_eq!(doggo.dialogue.lines, vec![DialogueLine::from("wan wan!")]);
         assert!(
             map.npcs.iter().all(|n| n.wander == (n.name == "doggo")),
             "nobody but doggo wanders"
@@ -733,4 +788,66 @@ mod tests {
         let map: MapData = serde_json::from_str(json).expect("map JSON without exits should still parse");
         assert!(map.exits.is_empty());
     }
+
+    #[test]
+    fn npc_dialogue_problems_are_reported() {
+        let npc = |lines: &str, face_index: u32| -> NpcData {
+            serde_json::from_str(&format!(r#"{{
+                "name": "Nature Spirit", "x": 1, "y": 2, "sprite": "Nature", "facing": "down",
+                "dialogue": {{ "speaker": "Nature Spirit", "portrait": "Nature",
+                               "face_index": {face_index}, "lines": {lines} }}
+            }}"#)).expect("NPC JSON should parse")
+        };
+
+        assert_eq!(npc(r#"["Hello."]"#, 7).dialogue_problem(), None);
+        assert!(npc("[]", 0).dialogue_problem().unwrap().contains("no dialogue lines"));
+        assert!(npc(r#"["  "]"#, 0).dialogue_problem().unwrap().contains("no dialogue lines"));
+        assert!(npc(r#"["Hello."]"#, 8).dialogue_problem().unwrap().contains("face_index 8"));
+    }
+
+    #[test]
+    fn blank_scripted_scene_boxes_are_reported() {
+        let exit = |texts: &str| -> ExitData {
+            serde_json::from_str(&format!(r#"{{
+                "trigger_x": 4, "trigger_y": 7, "target_scene": "TeamDisco",
+                "target_spawn_x": 1, "target_spawn_y": 1,
+                "dialogue": [{texts}]
+            }}"#)).expect("exit JSON should parse")
+        };
+        let segment = |text: &str| format!(r#"{{ "speaker": "Fairy", "portrait": "", "text": "{text}" }}"#);
+
+        assert_eq!(exit("").dialogue_problem(), None, "no scene at all is fine");
+        assert_eq!(exit(&segment("Ready?")).dialogue_problem(), None);
+        let problem = exit(&[segment("Ready?"), segment("  ")].join(",")).dialogue_problem().unwrap();
+        assert!(problem.contains("(4, 7) to TeamDisco") && problem.contains("#2"), "{problem}");
+    }
+
+    #[test]
+    fn overlong_ambient_lines_are_reported() {
+        let npc: NpcData = serde_json::from_str(r#"{
+            "name": "Tenchi", "x": 1, "y": 2, "sprite": "Nature", "facing": "down",
+            "ambient_lines": ["Velocity...", "We split the projects up so we could keep up with requests."],
+            "dialogue": { "speaker": "Tenchi", "portrait": "", "lines": ["Hi."] }
+        }"#).expect("NPC JSON should parse");
+        let problems = npc.ambient_line_problems();
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].contains("We split"));
+    }
+
+    #[test]
+    fn camera_zones_must_fit_the_map() {
+        let zone = |json: &str| -> CameraZoneData { serde_json::from_str(json).expect("zone JSON should parse") };
+
+        let room = zone(r#"{ "rect": [2, 1, 4, 3] }"#);
+        assert_eq!(room.problem(6, 4), None);
+        assert_eq!(room.bounds(), [2, 1, 4, 3], "bounds default to the rect");
+        assert_eq!(room.zoom, 1.0);
+        assert!(room.contains(5, 3) && !room.contains(6, 3) && !room.contains(1, 1));
+
+        assert!(room.problem(5, 4).unwrap().contains("doesn't fit the 5x4 map"));
+        let wide = zone(r#"{ "rect": [2, 1, 2, 2], "bounds": [0, 0, 9, 4] }"#);
+        assert!(wide.problem(8, 4).unwrap().contains("bounds"));
+        assert!(zone(r#"{ "rect": [0, 0, 0, 2] }"#).problem(8, 4).unwrap().contains("empty rect"));
+        assert!(zone(r#"{ "rect": [0, 0, 2, 2], "zoom": 0 }"#).problem(8, 4).unwrap().contains("zoom"));
+    }
 }
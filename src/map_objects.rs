@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use crate::map_data::{ObjectData, tile_to_world};
+use crate::npc::{Interactable, Npc, NpcBody, NpcDialogue, NpcFacing};
+use crate::tilemap::Map;
+
+/// Signs, terminals and runbooks: map `objects` (see `ObjectData`) that are
+/// read rather than talked to. Each is an `Npc` as far as interacting goes -
+/// in range, E, the talk prompt, a click, the `npc.interaction` span
+/// (`interaction.kind` "object") - so npc.rs takes them as they are. What
+/// they lack is character: no sheet to step or turn, no portrait, no
+/// shadow (shadow.rs), and reading one isn't meeting or talking to anyone:
+/// it's left out of `NpcsMet`, `TimesTalked` and `NpcInteracted` (so the
+/// session summary and the gameplay event log).
+/// Their box is `OBJECT_PANEL_COLOR` instead of the usual.
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct MapObject;
+
+/// Parchment, for what's written down - against the conversations' navy.
+pub const OBJECT_PANEL_COLOR: Color = Color::srgba(0.2, 0.16, 0.1, 0.95);
+
+/// Shown over an object in range.
+pub const OBJECT_PROMPT: &str = "Press {interact} to read";
+
+/// One object from map data, scene-scoped like the map's NPCs. Solid
+/// (`NpcBody`): the player stops at a sign, as at a person. `source` names
+/// the map file, for the dialogue's id.
+pub(crate) fn spawn_object_from_data(
+    commands: &mut Commands,
+    object: &ObjectData,
+    map_size: (u32, u32),
+    source: &str,
+    asset_server: Option<&AssetServer>,
+) -> Entity {
+    let world_pos = tile_to_world(object.x, object.y, map_size.0, map_size.1);
+    let mut entity = commands.spawn((
+        MapObject,
+        Npc { id: object.id(), name: object.name.clone(), sprite_facing: NpcFacing::Down, sprite_slot: 0 },
+        NpcDialogue::from_data(&object.dialogue(), None, source.to_string()),
+        Interactable { prompt: OBJECT_PROMPT.to_string(), ..default() },
+        NpcBody,
+        Transform::from_xyz(world_pos.x, world_pos.y, 1.0),
+        Visibility::default(),
+        Map,
+    ));
+    // Without an image of its own, it's whatever the map's tiles draw there.
+    match (&object.sprite, asset_server) {
+        (Some(sprite), Some(asset_server)) => {
+            entity.insert((Sprite::from_image(asset_server.load(sprite.clone())), crate::depth::YSorted { foot_offset: -24.0 }));
+        }
+        (Some(sprite), None) => warn!("No asset server to load {sprite} - {} is drawn by the map alone", object.name),
+        (None, _) => {}
+    }
+    info!("Spawned object: {} at tile ({}, {})", object.name, object.x, object.y);
+    entity.id()
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::content_errors::{ContentErrors, ContentMetrics};
+
+/// Dialogue moods: a cheap way to show how a speaker feels. A dialogue (or
+/// a single box of a scripted scene) names a mood - `"angry"`, `"happy"`,
+/// `"worried"` - and the dialogue box tints the portrait and recolors the
+/// speaker name to match. The palette is data (`assets/data/moods.json`,
+/// embedded at build time), validated at startup into `ContentErrors`.
+///
+/// Each mood also carries a voice-blip pitch multiplier. The game plays no
+/// voice blips yet; the field is loaded and validated now so content can
+/// author it, and blips will read it when they land.
+pub struct MoodPlugin;
+
+impl Plugin for MoodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Moods>()
+            .init_resource::<ContentErrors>()
+            .add_systems(Startup, load_moods)
+            .add_systems(Update, animate_mood_tints);
+        crate::instrumentation::init_metrics::<ContentMetrics>(app);
+    }
+}
+
+const MOODS_PATH: &str = "assets/data/moods.json";
+const MOODS_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/moods.json"));
+
+/// How long a mid-conversation mood change takes to blend in.
+pub const MOOD_TINT_SECONDS: f32 = 0.2;
+
+/// The speaker-name color with no mood - the dialogue box's gold.
+pub const NEUTRAL_NAME_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// The player's name on their own lines, whatever the mood - a cool blue
+/// against the NPCs' gold, so a back-and-forth reads at a glance.
+pub const PLAYER_NAME_COLOR: Color = Color::srgb(0.55, 0.8, 1.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mood {
+    /// Multiplied into the portrait (`ImageNode::color`).
+    pub tint: Color,
+    pub name_color: Color,
+    pub blip_pitch: f32,
+}
+
+impl Mood {
+    pub const NEUTRAL: Mood = Mood {
+        tint: Color::WHITE,
+        name_color: NEUTRAL_NAME_COLOR,
+        blip_pitch: 1.0,
+    };
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Moods {
+    moods: HashMap<String, Mood>,
+}
+
+impl Moods {
+    /// The look for an optional mood name. No mood, or one the palette
+    /// doesn't define, is neutral - unknown names are warned about once, as
+    /// their map loads (`unknown`), not every time they're shown.
+    pub fn resolve(&self, name: Option<&str>) -> Mood {
+        name.and_then(|name| self.moods.get(name)).copied().unwrap_or(Mood::NEUTRAL)
+    }
+
+    /// A problem for each of `names` the palette doesn't define.
+    pub fn unknown<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        names
+            .into_iter()
+            .filter(|name| !self.moods.contains_key(*name))
+            .map(|name| format!("unknown dialogue mood {name:?} - shown neutral (see {MOODS_PATH})"))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct MoodData {
+    tint: String,
+    name_color: String,
+    #[serde(default = "default_pitch")]
+    blip_pitch: f32,
+}
+
+fn default_pitch() -> f32 {
+    1.0
+}
+
+/// Parse and validate a moods file. Every bad entry is reported; the good
+/// ones still load.
+fn parse_moods(json: &str) -> (Moods, Vec<String>) {
+    let data: HashMap<String, MoodData> = match serde_json::from_str(json) {
+        Ok(data) => data,
+        Err(e) => return (Moods::default(), vec![format!("Failed to parse moods: {e}")]),
+    };
+
+    let mut moods = Moods::default();
+    let mut problems = Vec::new();
+    for (name, entry) in data {
+        let tint = Srgba::hex(&entry.tint);
+        let name_color = Srgba::hex(&entry.name_color);
+        let (Ok(tint), Ok(name_color)) = (tint, name_color) else {
+            problems.push(format!("mood {name:?} has an invalid hex color"));
+            continue;
+        };
+        if !(entry.blip_pitch > 0.0 && entry.blip_pitch <= 4.0) {
+            problems.push(format!("mood {name:?} blip_pitch {} is outside (0, 4]", entry.blip_pitch));
+            continue;
+        }
+        moods.moods.insert(name, Mood {
+            tint: tint.into(),
+            name_color: name_color.into(),
+            blip_pitch: entry.blip_pitch,
+        });
+    }
+    problems.sort();
+    (moods, problems)
+}
+
+fn load_moods(
+    mut moods: ResMut<Moods>,
+    mut content_errors: ResMut<ContentErrors>,
+    content_metrics: Option<Res<ContentMetrics>>,
+) {
+    let (loaded, problems) = parse_moods(MOODS_JSON);
+    for problem in problems {
+        content_errors.record(MOODS_PATH, problem, Duration::ZERO, content_metrics.as_deref());
+    }
+    info!("🎭 Loaded {} dialogue moods", loaded.moods.len());
+    *moods = loaded;
+}
+
+/// Which color a `MoodTint` drives.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TintTarget {
+    Portrait,
+    SpeakerName,
+}
+
+/// A color blend in progress on a dialogue box node.
+#[derive(Component)]
+pub struct MoodTint {
+    pub target: TintTarget,
+    from: Color,
+    to: Color,
+    timer: Timer,
+}
+
+impl MoodTint {
+    /// Already at `color` - nothing to animate.
+    pub fn settled(target: TintTarget, color: Color) -> Self {
+        let mut timer = Timer::from_seconds(MOOD_TINT_SECONDS, TimerMode::Once);
+        timer.finish();
+        Self { target, from: color, to: color, timer }
+    }
+
+    /// Blend from wherever the color is right now to `to`.
+    pub fn retarget(&mut self, to: Color) {
+        if to == self.to {
+            return;
+        }
+        self.from = self.current();
+        self.to = to;
+        self.timer.reset();
+    }
+
+    pub fn current(&self) -> Color {
+        let t = self.timer.fraction();
+        let from = LinearRgba::from(self.from);
+        let to = LinearRgba::from(self.to);
+        Color::from(from.mix(&to, t))
+    }
+}
+
+fn animate_mood_tints(
+    time: Res<Time>,
+    mut tints: Query<(&mut MoodTint, Option<&mut ImageNode>, Option<&mut TextColor>)>,
+) {
+    for (mut tint, image, text_color) in &mut tints {
+        if tint.timer.is_finished() {
+            continue;
+        }
+        tint.timer.tick(time.delta());
+        let color = tint.current();
+        match tint.target {
+            TintTarget::Portrait => {
+                if let Some(mut image) = image {
+                    image.color = color;
+                }
+            }
+            TintTarget::SpeakerName => {
+                if let Some(mut text_color) = text_color {
+                    text_color.0 = color;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_moods_are_valid() {
+        let (moods, problems) = parse_moods(MOODS_JSON);
+        assert!(problems.is_empty(), "{problems:?}");
+        for name in ["angry", "happy", "worried"] {
+            assert_ne!(moods.resolve(Some(name)), Mood::NEUTRAL, "{name} should be defined");
+        }
+    }
+
+    #[test]
+    fn invalid_moods_are_reported_and_skipped() {
+        let (moods, problems) = parse_moods(r##"{
+            "ok": { "tint": "#ffffff", "name_color": "#000000" },
+            "bad_color": { "tint": "not a color", "name_color": "#000000" },
+            "bad_pitch": { "tint": "#ffffff", "name_color": "#000000", "blip_pitch": 0 }
+        }"##);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].contains("bad_color"));
+        assert!(problems[1].contains("bad_pitch"));
+        assert_eq!(moods.resolve(Some("ok")).blip_pitch, 1.0, "pitch defaults to 1");
+        assert_eq!(moods.resolve(Some("bad_pitch")), Mood::NEUTRAL);
+        assert_eq!(moods.resolve(None), Mood::NEUTRAL);
+        let unknown = moods.unknown(["ok", "bad_pitch"]);
+        assert_eq!(unknown.len(), 1, "{unknown:?}");
+        assert!(unknown[0].contains("\"bad_pitch\""));
+    }
+
+    #[test]
+    fn retargeting_blends_over_the_tint_duration() {
+        let mut tint = MoodTint::settled(TintTarget::Portrait, Color::WHITE);
+        assert_eq!(tint.current(), Color::WHITE);
+
+        let red = Color::from(LinearRgba::rgb(1.0, 0.0, 0.0));
+        tint.retarget(red);
+        assert_eq!(tint.current(), Color::WHITE, "no snap on the change itself");
+
+        tint.timer.tick(Duration::from_secs_f32(MOOD_TINT_SECONDS / 2.0));
+        let halfway = LinearRgba::from(tint.current());
+        assert!((halfway.green - 0.5).abs() < 1e-3, "halfway: {halfway:?}");
+
+        tint.timer.tick(Duration::from_secs_f32(MOOD_TINT_SECONDS));
+        assert_eq!(LinearRgba::from(tint.current()), LinearRgba::from(red));
+    }
+}
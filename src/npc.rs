@@ -1,10 +1,22 @@
 use bevy::prelude::*;
-use crate::game_state::{GameState, Mode};
+use crate::game_state::{GameState, GameStatePlugin, Mode};
 use crate::player::Player;
-use crate::dialogue::StartDialogueEvent;
+use crate::dialogue::{DialogueRequest, DialogueRequestBuilder, DialogueRequestSet};
 use crate::assets::GameAssets;
-use crate::instrumentation::{GameTracer, GameMeter, PlayerSessionTrace, start_npc_interaction_span};
+use crate::toast::ShowToast;
+use crate::rumble::RumbleEvent;
+use crate::hooks::{DialogueEnded, DialogueTopicChosen, NpcInteracted};
+use crate::input::{ActiveDevice, GameAction};
+use crate::instrumentation::{GameTracer, MetricsBundle, PlayerSessionTrace, init_metrics, start_npc_interaction_span};
+use opentelemetry::metrics::{Counter, Meter, ObservableGauge};
+use opentelemetry::global::BoxedSpan;
 use opentelemetry::{KeyValue, trace::{Span as _, Tracer}};
+use crate::map_data::{DialogueData, DialogueRepeat};
+use crate::map_objects::MapObject;
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 pub struct NpcPlugin;
 
@@ -13,19 +25,90 @@ impl Plugin for NpcPlugin {
         app.register_type::<Npc>()
             .register_type::<NpcFacing>()
             .register_type::<NpcDialogue>()
+            .register_type::<NpcDialogueState>()
             .register_type::<CharacterFrames>()
             .register_type::<Interactable>()
             .register_type::<NpcBody>()
+            .register_type::<Busy>()
+            .add_message::<DialogueRequest>()
+            .add_message::<NpcInteracted>()
+            .add_message::<DialogueTopicChosen>()
+            .add_message::<DialogueEnded>()
+            .add_message::<NpcClicked>()
+            .add_message::<ShowToast>()
+            .add_message::<RumbleEvent>()
+            .init_resource::<TimesTalked>()
+            .init_resource::<NpcsMet>()
+            .init_resource::<InteractionSettings>()
+            .init_resource::<ActiveDevice>()
+            .init_resource::<crate::flags::GameFlags>()
             .add_systems(Update, (
                 check_npc_proximity,
                 handle_interaction_input,
-            ).chain().run_if(in_state(Mode::Exploring)))
-            // Wandering pauses during dialogue - doggo shouldn't stroll off
-            // mid-"wan wan".
-            .add_systems(Update, wander_npcs.run_if(in_state(Mode::Exploring)))
+                resolve_pending_interaction,
+            ).chain().in_set(DialogueRequestSet).run_if(in_state(Mode::Exploring)))
+            .add_systems(Update, turn_to_the_player.after(DialogueRequestSet).run_if(in_state(GameState::Playing)))
+            // The frame the conversation ends, whichever way it does -
+            // Escape on its first frame included - and, for one that never
+            // got to end (a box that never opened), leaving Dialogue.
+            .add_systems(PostUpdate, restore_talker_facing.run_if(on_message::<DialogueEnded>))
+            .add_systems(Update, trace_topic_choices
+                .after(crate::dialogue::DialogueReadingSet)
+                .run_if(in_state(Mode::Dialogue)))
+            .add_systems(OnExit(Mode::Dialogue), (end_hub_interaction, (restore_talker_facing, forget_interaction_target).chain()))
+            .add_systems(OnEnter(GameState::Playing), (forget_times_talked, forget_npcs_met))
+            .add_systems(Update, export_npcs_met.run_if(resource_changed::<NpcsMet>))
+            .add_systems(Update, expire_emotes.run_if(in_state(GameState::Playing)))
             // Stepping runs whenever the game is playing - in the original,
             // NPCs keep bobbing behind an open dialogue box too.
             .add_systems(Update, animate_stepping_npcs.run_if(in_state(GameState::Playing)));
+        crate::input::init_game_input(app);
+        crate::map_data::init_dialogue_assets(app);
+        init_metrics::<InteractionMetrics>(app);
+        init_metrics::<NpcsMetMetrics>(app);
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<GameStatePlugin>(app, "NpcPlugin");
+    }
+}
+
+/// `game.interactions.total`, by NPC (`npc.id`, and `npc.name` to read by)
+/// and whether it's a repeat visit.
+#[derive(Resource)]
+pub struct InteractionMetrics {
+    pub total: Counter<u64>,
+}
+
+impl MetricsBundle for InteractionMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            total: meter
+                .u64_counter("game.interactions.total")
+                .with_description("Total number of player interactions")
+                .build(),
+        }
+    }
+}
+
+/// `game.npcs_met`: how many NPCs have been met (`NpcsMet`), read by the
+/// meter whenever it exports.
+#[derive(Resource)]
+pub struct NpcsMetMetrics {
+    met: Arc<AtomicU64>,
+    _gauge: ObservableGauge<u64>,
+}
+
+impl MetricsBundle for NpcsMetMetrics {
+    fn new(meter: &Meter) -> Self {
+        let met = Arc::new(AtomicU64::new(0));
+        let observed = met.clone();
+        let gauge = meter
+            .u64_observable_gauge("game.npcs_met")
+            .with_description("NPCs with a conversation read to the end this session (see NpcsMet in npc.rs)")
+            .with_callback(move |observer| observer.observe(observed.load(Ordering::Relaxed), &[]))
+            .build();
+        Self { met, _gauge: gauge }
     }
 }
 
@@ -36,98 +119,86 @@ impl Plugin for NpcPlugin {
 #[reflect(Component)]
 pub struct NpcBody;
 
-/// Random tile-step wandering (doggo). Steps one tile at a time in a
-/// random direction, honoring map passability even though doggo is Through
-/// in the source - engine-divergent (RPGMaker's Through ignores terrain)
-/// but intent-faithful: a dog that wanders into the pond or through house
-/// walls reads as a bug, not a feature. Amy's spec (2026-07-12): random
-/// movement, not the original's scripted left/right patrol.
-#[derive(Component)]
-pub struct Wanderer {
-    /// Pause between step decisions.
-    idle: Timer,
-    /// World-space destination of the step in progress, if any.
-    target: Option<Vec2>,
-    /// xorshift64 state, lazily seeded from the clock on first use.
-    rng: u64,
+/// "Can't talk right now": the NPC is mid tile-step (npc_movement inserts it
+/// for the length of each glide) or held by a scripted scene. Whoever
+/// starts the activity inserts it and removes it when done; an E press in
+/// the meantime is handled per `BusyBehavior` instead of opening dialogue
+/// on a sliding sprite.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Busy;
+
+/// Per-NPC answer to an E press while `Busy` (map JSON `when_busy`).
+pub use crate::content::map::BusyBehavior;
+
+impl Component for BusyBehavior {
+    const STORAGE_TYPE: bevy::ecs::component::StorageType = bevy::ecs::component::StorageType::Table;
+    type Mutability = bevy::ecs::component::Mutable;
 }
 
-impl Default for Wanderer {
-    fn default() -> Self {
-        Self {
-            idle: Timer::from_seconds(1.5, TimerMode::Repeating),
-            target: None,
-            rng: 0,
-        }
-    }
+/// A conversation queued behind a busy NPC. At most one at a time; resolved
+/// or cancelled by `resolve_pending_interaction`.
+#[derive(Resource)]
+pub struct PendingInteraction {
+    pub npc: Entity,
+    /// The "..." emote over the NPC, despawned when the wait ends.
+    pub(crate) emote: Entity,
+    /// Player-to-NPC distance past which the wait is abandoned.
+    reach: f32,
+    selected_by: SelectedBy,
+    /// `Time::elapsed` when E was pressed: the wait counts from here, in
+    /// game time, so a pause mid-wait isn't part of it.
+    requested_at: Duration,
+    /// When E was pressed, by the wall clock: input latency counts from here.
+    pressed_at: Option<web_time::Instant>,
 }
 
-/// RPGMaker move speed 3 (doggo's): 2^3/256 tiles per frame at 60fps on
-/// 48px tiles = 90 px/s.
-const WANDER_SPEED: f32 = 90.0;
+/// A little world-space text bubble ("...", "!") floating over a character.
+/// `lifetime: None` stays up until its owner despawns it.
+#[derive(Component)]
+pub struct Emote {
+    lifetime: Option<Timer>,
+}
 
-fn wander_npcs(
+/// Float `text` over `owner` as a child entity, so it follows the character
+/// and is cleaned up with it on scene exit.
+pub fn spawn_emote(
+    commands: &mut Commands,
+    owner: Entity,
+    text: &str,
+    seconds: Option<f32>,
+) -> Entity {
+    let emote = commands
+        .spawn((
+            Emote {
+                lifetime: seconds.map(|s| Timer::from_seconds(s, TimerMode::Once)),
+            },
+            Text2d::new(text),
+            TextFont {
+                font_size: FontSize::Px(20.0),
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            // Just above a 48px character's head; the tiny z bump keeps it
+            // in front of its own sprite.
+            Transform::from_xyz(0.0, 36.0, 0.01),
+        ))
+        .id();
+    commands.entity(owner).add_child(emote);
+    emote
+}
+
+fn expire_emotes(
+    mut commands: Commands,
     time: Res<Time>,
-    collision_map: Option<Res<crate::tilemap::CollisionMap>>,
-    mut query: Query<(&mut Wanderer, &mut Transform, &mut CharacterFrames)>,
+    mut emotes: Query<(Entity, &mut Emote)>,
 ) {
-    let Some(map) = collision_map else { return };
-
-    for (mut wanderer, mut transform, mut frames) in &mut query {
-        // A step in progress: glide to the target tile, snap on arrival.
-        if let Some(target) = wanderer.target {
-            let position = transform.translation.truncate();
-            let step = WANDER_SPEED * time.delta_secs();
-            if position.distance(target) <= step {
-                transform.translation.x = target.x;
-                transform.translation.y = target.y;
-                wanderer.target = None;
-            } else {
-                let direction = (target - position).normalize_or_zero();
-                transform.translation.x += direction.x * step;
-                transform.translation.y += direction.y * step;
-            }
-            continue;
-        }
-
-        wanderer.idle.tick(time.delta());
-        if !wanderer.idle.just_finished() {
-            continue;
-        }
-
-        if wanderer.rng == 0 {
-            // |1 keeps the seed nonzero (xorshift's absorbing state).
-            wanderer.rng = time.elapsed().as_nanos() as u64 | 1;
-        }
-        wanderer.rng ^= wanderer.rng << 13;
-        wanderer.rng ^= wanderer.rng >> 7;
-        wanderer.rng ^= wanderer.rng << 17;
-
-        // Direction deltas in RPGMaker tile orientation (y grows downward).
-        let (dx, dy, facing) = match wanderer.rng % 4 {
-            0 => (0, 1, NpcFacing::Down),
-            1 => (-1, 0, NpcFacing::Left),
-            2 => (1, 0, NpcFacing::Right),
-            _ => (0, -1, NpcFacing::Up),
-        };
-
-        let position = transform.translation.truncate();
-        let from = crate::map_data::world_to_tile(position, map.width, map.height);
-        let to = (from.0 + dx, from.1 + dy);
-        if !map.can_step(from, to) {
-            // Blocked step: just turn toward it and wait for the next tick,
-            // like a dog sniffing at a wall.
-            frames.facing_row = facing as u32;
-            continue;
+    for (entity, mut emote) in &mut emotes {
+        let Some(timer) = &mut emote.lifetime else { continue };
+        timer.tick(time.delta());
+        if timer.is_finished() {
+            commands.entity(entity).despawn();
         }
-
-        frames.facing_row = facing as u32;
-        wanderer.target = Some(crate::map_data::tile_to_world(
-            to.0 as u32,
-            to.1 as u32,
-            map.width,
-            map.height,
-        ));
     }
 }
 
@@ -139,6 +210,14 @@ fn wander_npcs(
 pub struct CharacterFrames {
     pub slot: u32,
     pub facing_row: u32,
+    /// The sheet's options (character_sheet.rs), e.g. a mirrored left row.
+    pub sheet: crate::character_sheet::SheetOptions,
+}
+
+impl CharacterFrames {
+    pub fn frame(&self, pattern: u32) -> crate::character_sheet::SheetFrame {
+        self.sheet.frame(self.slot, self.facing_row, pattern)
+    }
 }
 
 /// RPGMaker's "Stepping Animation": cycle the walk patterns in place.
@@ -169,7 +248,7 @@ pub fn step_pattern(step: u8) -> u32 {
 
 fn animate_stepping_npcs(
     time: Res<Time>,
-    mut query: Query<(&CharacterFrames, &mut StepAnimation, &mut Sprite)>,
+    mut query: Query<(&CharacterFrames, &mut StepAnimation, &mut Sprite), Without<crate::culling::Culled>>,
 ) {
     for (frames, mut anim, mut sprite) in &mut query {
         anim.timer.tick(time.delta());
@@ -177,19 +256,16 @@ fn animate_stepping_npcs(
             continue;
         }
         anim.step = (anim.step + 1) % 4;
-        if let Some(atlas) = &mut sprite.texture_atlas {
-            atlas.index = crate::character_sheet::atlas_index(
-                frames.slot,
-                frames.facing_row,
-                step_pattern(anim.step),
-            ) as usize;
-        }
+        frames.frame(step_pattern(anim.step)).apply(&mut sprite);
     }
 }
 
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct Npc {
+    /// `NpcData::id`: what traces, metrics, `TimesTalked` and spawning know
+    /// this NPC by. `name` is for showing.
+    pub id: String,
     pub name: String,
     pub sprite_facing: NpcFacing,
     /// Character slot (0-7) within the sprite sheet - see character_sheet.rs.
@@ -205,9 +281,13 @@ pub enum NpcFacing {
     Up = 3,
 }
 
-#[derive(Component, Reflect)]
+#[derive(Component, Reflect, Clone)]
 #[reflect(Component)]
 pub struct NpcDialogue {
+    /// `DialogueData::id`, qualified by the file it was authored in
+    /// (`content::qualified_id`): the conversation's `DialogueRequest::id`,
+    /// so traces and `SeenDialogues` survive a rename.
+    pub id: String,
     pub speaker: String,
     pub portrait_path: String,
     /// Which cell of the `portrait_path` face sheet to crop and display (see
@@ -215,12 +295,408 @@ pub struct NpcDialogue {
     /// `dialogue.rs::spawn_dialogue_ui`).
     pub portrait_face_index: u32,
     pub lines: Vec<String>,
+    /// By line, the portrait asset path a line switches to (see
+    /// `DialogueData::portrait_of` in map_data.rs) - empty for none; None,
+    /// or no entry at all: `portrait_path`.
+    pub line_portraits: Vec<Option<String>>,
+    /// By line, who says it when it isn't `speaker` (see
+    /// `DialogueLine::speaker` in map_data.rs); None, or no entry at all:
+    /// `speaker`.
+    pub line_speakers: Vec<Option<String>>,
+    /// By line, what reading past it does (see `DialogueLine::actions` in
+    /// map_data.rs); no entry at all: nothing.
+    pub line_actions: Vec<Vec<crate::map_data::LineAction>>,
+    /// See `DialogueData::mood` in map_data.rs.
+    pub mood: Option<String>,
+    /// See `DialogueData::text_speed` in map_data.rs.
+    pub text_speed: Option<f32>,
+    /// See `DialogueData::box_layout` in map_data.rs.
+    pub box_layout: crate::map_data::DialogueBoxLayout,
+    /// See `DialogueData::on_complete` in map_data.rs.
+    pub on_complete: Vec<crate::dialogue::DialogueOutcome>,
+    /// The NPC's `requires_flag` (see `NpcData`): it only says this while
+    /// the flag is set, so it's a condition of the selection.
+    pub requires_flag: Option<String>,
+    /// See `DialogueData::conditions` in map_data.rs: said only while all
+    /// of these hold.
+    pub conditions: Vec<crate::map_data::DialogueCondition>,
+    /// `NpcData::dialogues`, in order: said instead of this one while
+    /// their conditions hold (see `select`). Empty on those themselves.
+    pub alternatives: Vec<NpcDialogue>,
+    /// See `DialogueData::topics` in map_data.rs.
+    pub topics: Vec<crate::map_data::DialogueTopic>,
+    /// `DialogueData::branches`, resolved at spawn.
+    pub branches: std::collections::BTreeMap<usize, crate::dialogue::DialogueBranch>,
+    /// The map file this dialogue was authored in, for traces.
+    pub source: String,
+    /// `NpcData::dialogue_file`, loading or loaded. Once loaded it is what
+    /// the NPC says (see `spoken`); the fields above are the inline
+    /// fallback.
+    pub file: Option<Handle<DialogueData>>,
+}
+
+impl NpcDialogue {
+    /// `data` as an NPC says it, with `requires_flag` from its map entry.
+    /// `source` names the file it came from, for traces.
+    pub fn from_data(data: &DialogueData, requires_flag: Option<String>, source: String) -> Self {
+        Self {
+            id: crate::content::qualified_id(&source, &data.id()),
+            speaker: data.speaker.clone(),
+            portrait_path: crate::map_data::portrait_asset_path(&data.portrait),
+            portrait_face_index: data.face_index,
+            lines: data.lines.iter().map(|line| line.text.clone()).collect(),
+            line_portraits: data
+                .lines
+                .iter()
+                .map(|line| {
+                    let portrait = data.portrait_of(line);
+                    (portrait != data.portrait).then(|| crate::map_data::portrait_asset_path(portrait))
+                })
+                .collect(),
+            line_speakers: data.lines.iter().map(|line| line.speaker.clone()).collect(),
+            line_actions: data.lines.iter().map(|line| line.actions.clone()).collect(),
+            mood: data.mood.clone(),
+            text_speed: data.text_speed,
+            box_layout: data.box_layout,
+            on_complete: data.on_complete.clone(),
+            requires_flag,
+            conditions: data.conditions.clone(),
+            alternatives: Vec::new(),
+            topics: data.topics.clone(),
+            branches: data.branches(),
+            source,
+            file: None,
+        }
+    }
+
+    /// What the NPC says right now: its `file` once that has loaded, else
+    /// the inline dialogue. A file that is missing, broken or still loading
+    /// is warned about and the inline lines stand in - None if there are
+    /// none either.
+    fn spoken(&self, files: &DialogueFiles) -> Option<Cow<'_, Self>> {
+        let Some(handle) = &self.file else {
+            return Some(Cow::Borrowed(self));
+        };
+        let path = handle.path().map_or_else(|| "dialogue file".to_string(), ToString::to_string);
+        match files.files.as_ref().and_then(|files| files.get(handle)) {
+            Some(data) => match data.problem() {
+                Some(problem) => warn!("📄 {path} {problem} - falling back to inline dialogue"),
+                None => {
+                    let alternatives = self.alternatives.clone();
+                    return Some(Cow::Owned(Self { alternatives, ..Self::from_data(data, self.requires_flag.clone(), path) }));
+                }
+            },
+            None => match files.asset_server.as_ref().map(|server| server.load_state(handle)) {
+                Some(bevy::asset::LoadState::Failed(error)) => {
+                    warn!("📄 Couldn't load {path}: {error} - falling back to inline dialogue")
+                }
+                _ => warn!("📄 {path} is still loading - falling back to inline dialogue"),
+            },
+        }
+        (!self.lines.is_empty() || !self.alternatives.is_empty()).then_some(Cow::Borrowed(self))
+    }
+
+    /// Which conversation to open with `flags` as they are: the first of
+    /// `alternatives` whose conditions all hold, else this one if its own
+    /// do. None when nothing with lines holds.
+    pub fn select(&self, flags: &crate::flags::GameFlags) -> Option<&Self> {
+        let holds = |dialogue: &&Self| {
+            dialogue.conditions.iter().all(|condition| condition.holds(|flag| flags.is_set(flag)))
+        };
+        self.alternatives.iter().chain(std::iter::once(self)).filter(|dialogue| !dialogue.lines.is_empty()).find(holds)
+    }
+
+    /// Just `line`, in this dialogue's voice and box: no topics, choices
+    /// or outcomes - none of it is being said.
+    fn instead(&self, line: String) -> Self {
+        Self {
+            lines: vec![line],
+            line_portraits: Vec::new(),
+            line_speakers: Vec::new(),
+            line_actions: Vec::new(),
+            on_complete: Vec::new(),
+            conditions: Vec::new(),
+            alternatives: Vec::new(),
+            topics: Vec::new(),
+            branches: Default::default(),
+            file: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// Loaded `.dialogue.json` assets, for resolving `NpcDialogue::file`.
+#[derive(bevy::ecs::system::SystemParam)]
+struct DialogueFiles<'w> {
+    files: Option<Res<'w, Assets<DialogueData>>>,
+    asset_server: Option<Res<'w, AssetServer>>,
+}
+
+/// Conversations started per NPC id (`Npc::id`) this session - by id, not
+/// entity, since NPCs are respawned on every visit to their map.
+#[derive(Resource, Debug, Default)]
+pub struct TimesTalked {
+    times: std::collections::HashMap<String, u32>,
+    /// When each NPC last said its dialogue in full, on `clock`.
+    last_said: std::collections::HashMap<String, Duration>,
+    /// How far `clock` runs ahead of `Time` elapsed: as far back as a
+    /// restored save's NPCs last spoke, so those times aren't negative.
+    clock_offset: Duration,
+}
+
+impl TimesTalked {
+    pub fn get(&self, npc: &str) -> u32 {
+        self.times.get(npc).copied().unwrap_or(0)
+    }
+
+    /// When `npc` last said its dialogue in full; None if it never has.
+    pub fn last_said(&self, npc: &str) -> Option<Duration> {
+        self.last_said.get(npc).copied()
+    }
+
+    /// How many NPCs have been talked to at all.
+    pub fn npcs(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Every NPC's count, for a save (save.rs).
+    pub fn counts(&self) -> &std::collections::HashMap<String, u32> {
+        &self.times
+    }
+
+    /// `elapsed` (`Time`) on the clock `last_said` keeps.
+    pub fn clock(&self, elapsed: Duration) -> Duration {
+        elapsed + self.clock_offset
+    }
+
+    /// How many seconds before `elapsed` each NPC last said its dialogue
+    /// in full, for a save (save.rs).
+    pub fn said_ago(&self, elapsed: Duration) -> std::collections::HashMap<String, f32> {
+        let now = self.clock(elapsed);
+        self.last_said.iter().map(|(npc, said)| (npc.clone(), now.saturating_sub(*said).as_secs_f32())).collect()
+    }
+
+    /// What a save kept: the counts, and how long before it each NPC last
+    /// said its dialogue in full - taken as that long before `elapsed`, so
+    /// `repeat` once and cooldowns carry across sessions.
+    pub fn restore(
+        &mut self,
+        counts: std::collections::HashMap<String, u32>,
+        said_ago: &std::collections::HashMap<String, f32>,
+        elapsed: Duration,
+    ) {
+        let ago: Vec<(String, Duration)> = said_ago
+            .iter()
+            .map(|(npc, secs)| (npc.clone(), Duration::try_from_secs_f32(*secs).unwrap_or_default()))
+            .collect();
+        let clock_offset = ago.iter().map(|(_, ago)| *ago).max().unwrap_or_default();
+        let now = elapsed + clock_offset;
+        *self = Self {
+            times: counts,
+            last_said: ago.into_iter().map(|(npc, ago)| (npc, now - ago)).collect(),
+            clock_offset,
+        };
+    }
+
+    fn record(&mut self, npc: &str) {
+        *self.times.entry(npc.to_string()).or_default() += 1;
+    }
+}
+
+/// A new play session hasn't talked to anyone.
+fn forget_times_talked(mut times_talked: ResMut<TimesTalked>) {
+    *times_talked = TimesTalked::default();
+}
+
+/// The NPCs, by name, the player has read a conversation with to the end
+/// this session - what quests will ask after. Unlike `TimesTalked`, a
+/// conversation walked away from (Escape) doesn't count: dialogue.rs marks
+/// the NPC as the box closes on a read-through.
+#[derive(Resource, Debug, Default)]
+pub struct NpcsMet {
+    names: std::collections::BTreeSet<String>,
+}
+
+impl NpcsMet {
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    pub fn count(&self) -> usize {
+        self.names.len()
+    }
+
+    /// True if this is the first time.
+    pub fn meet(&mut self, name: &str) -> bool {
+        self.names.insert(name.to_string())
+    }
+}
+
+/// Nor has it met anyone.
+fn forget_npcs_met(mut met: ResMut<NpcsMet>) {
+    *met = NpcsMet::default();
+}
+
+fn export_npcs_met(met: Res<NpcsMet>, metrics: Option<Res<NpcsMetMetrics>>) {
+    if let Some(metrics) = metrics {
+        metrics.met.store(met.count() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Whether an NPC says its dialogue again (map JSON `repeat`, see
+/// `DialogueRepeat`) and how talking to it has gone. `TimesTalked` is
+/// what's remembered across respawns; this is refreshed from it whenever
+/// the NPC is talked to.
+#[derive(Component, Reflect, Debug, Clone, Default)]
+#[reflect(Component)]
+pub struct NpcDialogueState {
+    pub repeat: DialogueRepeat,
+    /// Said instead of a dialogue `repeat` holds back.
+    pub repeat_line: String,
+    /// Conversations started, repeat lines included.
+    pub times_talked: u32,
+    /// When the dialogue was last said in full, on `TimesTalked::clock`.
+    pub last_talked: Option<Duration>,
+}
+
+impl NpcDialogueState {
+    pub fn new(repeat: DialogueRepeat, repeat_line: impl Into<String>) -> Self {
+        Self { repeat, repeat_line: repeat_line.into(), ..default() }
+    }
+
+    /// Whether the dialogue is said in full at `now`, rather than
+    /// `repeat_line`.
+    pub fn recites(&self, now: Duration) -> bool {
+        let Some(last) = self.last_talked else { return true };
+        match self.repeat {
+            DialogueRepeat::Always => true,
+            DialogueRepeat::Once => false,
+            DialogueRepeat::Cooldown(seconds) => now.saturating_sub(last).as_secs_f32() >= seconds,
+        }
+    }
+}
+
+/// The `variant` of a conversation that was only the NPC's repeat line.
+pub const REPEAT_LINE_VARIANT: &str = "repeat_line";
+
+/// Talking to NPCs, as `repeat` has it: their `NpcDialogueState`, and the
+/// `TimesTalked` it's kept in.
+#[derive(bevy::ecs::system::SystemParam)]
+struct TalkHistory<'w, 's> {
+    times_talked: ResMut<'w, TimesTalked>,
+    states: Query<'w, 's, &'static mut NpcDialogueState>,
+    time: Res<'w, Time>,
+}
+
+impl TalkHistory<'_, '_> {
+    /// Record a conversation with `entity` (`npc_id`) opening `selected`.
+    /// Some with the NPC's repeat line, as a conversation of its own, when
+    /// `repeat` holds the dialogue back. An NPC without a state always
+    /// says it.
+    fn talk(&mut self, entity: Entity, npc_id: &str, selected: &NpcDialogue) -> Option<NpcDialogue> {
+        let now = self.times_talked.clock(self.time.elapsed());
+        self.times_talked.record(npc_id);
+        let Ok(mut state) = self.states.get_mut(entity) else {
+            self.times_talked.last_said.insert(npc_id.to_string(), now);
+            return None;
+        };
+        state.last_talked = self.times_talked.last_said(npc_id);
+        let recites = state.recites(now);
+        if recites {
+            self.times_talked.last_said.insert(npc_id.to_string(), now);
+            state.last_talked = Some(now);
+        }
+        state.times_talked = self.times_talked.get(npc_id);
+        (!recites).then(|| selected.instead(state.repeat_line.clone()))
+    }
+}
+
+/// Which of an NPC's dialogues an interaction opened, and why - the
+/// answer to "why did they say that", put on the `npc.interaction` span
+/// and logged. `variant` is "default" for a conversation said whatever
+/// the flags, else the conditional one's id - or `REPEAT_LINE_VARIANT`
+/// when the NPC's `repeat` held it back; `conditions` are what it needed,
+/// the NPC's own `requires_flag` first.
+///
+/// `flags` is an allowlist, not a dump of `GameFlags`: only the flags this
+/// NPC's content reads or sets, so a trace can't leak (or grow with) every
+/// flag in the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialogueSelection {
+    pub variant: String,
+    pub conditions: Vec<String>,
+    /// Conversations with this NPC before this one.
+    pub times_talked: u32,
+    pub flags: Vec<(String, bool)>,
+    pub source: String,
+}
+
+impl DialogueSelection {
+    /// `selected` is what `dialogue.select` chose.
+    pub fn resolve(
+        npc_id: &str,
+        dialogue: &NpcDialogue,
+        selected: &NpcDialogue,
+        times_talked: &TimesTalked,
+        flags: &crate::flags::GameFlags,
+    ) -> Self {
+        let mut relevant: Vec<&str> = dialogue.requires_flag.iter().map(String::as_str).collect();
+        let candidates = || dialogue.alternatives.iter().chain(std::iter::once(dialogue));
+        let read = candidates().flat_map(|candidate| {
+            let conditions = candidate.conditions.iter().map(|condition| condition.flag());
+            conditions.chain(candidate.topics.iter().filter_map(|topic| topic.requires_flag.as_deref()))
+        });
+        let set = candidates().flat_map(|candidate| &candidate.on_complete).filter_map(|outcome| match outcome {
+            crate::dialogue::DialogueOutcome::SetFlag(flag) => Some(flag.as_str()),
+            _ => None,
+        });
+        for flag in read.chain(set) {
+            if !relevant.contains(&flag) {
+                relevant.push(flag);
+            }
+        }
+        Self {
+            variant: if selected.conditions.is_empty() { "default".to_string() } else { selected.id.clone() },
+            conditions: selected
+                .requires_flag
+                .iter()
+                .map(|flag| format!("has_flag:{flag}"))
+                .chain(selected.conditions.iter().map(ToString::to_string))
+                .collect(),
+            times_talked: times_talked.get(npc_id),
+            flags: relevant
+                .into_iter()
+                .map(|flag| (flag.to_string(), flags.is_set(flag)))
+                .collect(),
+            source: selected.source.clone(),
+        }
+    }
+
+    fn attributes(&self) -> Vec<KeyValue> {
+        let mut attributes = vec![
+            KeyValue::new("dialogue.variant", self.variant.clone()),
+            KeyValue::new("dialogue.conditions", opentelemetry::Value::Array(
+                self.conditions.iter().cloned().map(Into::into).collect::<Vec<opentelemetry::StringValue>>().into(),
+            )),
+            KeyValue::new("dialogue.source", self.source.clone()),
+            KeyValue::new("npc.times_talked", self.times_talked as i64),
+        ];
+        attributes.extend(
+            self.flags
+                .iter()
+                .map(|(flag, set)| KeyValue::new(format!("flag.{flag}"), *set)),
+        );
+        attributes
+    }
 }
 
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct Interactable {
     pub radius: f32,
+    /// Shown over the NPC while the player is in range
+    /// (interaction_prompt.rs); `{interact}` is the key or button, as in
+    /// glyphs.rs templates.
     pub prompt: String,
 }
 
@@ -228,13 +704,26 @@ impl Default for Interactable {
     fn default() -> Self {
         Self {
             radius: 64.0,
-            prompt: "Press E to talk".to_string(),
+            prompt: "Press {interact} to talk".to_string(),
+        }
+    }
+}
+
+impl Interactable {
+    /// What `npc` asks for: its own radius and prompt where authored and
+    /// sound (`NpcData::interaction_problem`), the defaults elsewhere.
+    pub fn from_data(npc: &crate::map_data::NpcData) -> Self {
+        let default = Self::default();
+        Self {
+            radius: npc.interaction_radius.filter(|radius| radius.is_finite() && *radius > 0.0).unwrap_or(default.radius),
+            prompt: npc.prompt.clone().unwrap_or(default.prompt),
         }
     }
 }
 
+/// The player is within this NPC's `Interactable::radius`.
 #[derive(Component)]
-struct InRange;
+pub struct InRange;
 
 pub fn spawn_npc(
     commands: &mut Commands,
@@ -243,23 +732,27 @@ pub fn spawn_npc(
     position: Vec3,
     sprite_handle: Handle<Image>,
     npc_data: Npc,
+    sheet: crate::character_sheet::SheetOptions,
     step_anime: bool,
     dialogue: NpcDialogue,
     tracer: Option<&GameTracer>,
 ) -> Entity {
     let texture = sprite_handle;
 
-    let atlas_layout = texture_atlas_layouts.add(crate::character_sheet::sheet_layout());
+    let atlas_layout = texture_atlas_layouts.add(sheet.grid.layout(UVec2::splat(crate::character_sheet::FRAME_SIZE)));
 
-    let sprite_index = crate::character_sheet::atlas_index(
-        npc_data.sprite_slot,
-        npc_data.sprite_facing as u32,
-        crate::character_sheet::STANDING_PATTERN,
-    ) as usize;
+    let frames = CharacterFrames {
+        slot: npc_data.sprite_slot,
+        facing_row: npc_data.sprite_facing as u32,
+        sheet,
+    };
+    let frame = frames.frame(crate::character_sheet::STANDING_PATTERN);
+    let sprite_index = frame.index as usize;
 
     // Add telemetry for NPC spawn
     if let Some(t) = tracer {
         let mut span = t.tracer().start("npc.spawned");
+        span.set_attribute(KeyValue::new("npc.id", npc_data.id.clone()));
         span.set_attribute(KeyValue::new("npc.name", npc_data.name.clone()));
         span.set_attribute(KeyValue::new("npc.x", position.x as f64));
         span.set_attribute(KeyValue::new("npc.y", position.y as f64));
@@ -269,24 +762,22 @@ pub fn spawn_npc(
 
     info!("👤 NPC spawned: {} at ({:.0}, {:.0})", npc_data.name, position.x, position.y);
 
-    let frames = CharacterFrames {
-        slot: npc_data.sprite_slot,
-        facing_row: npc_data.sprite_facing as u32,
-    };
-
     let mut entity_commands = commands.spawn((
         npc_data,
         frames,
         dialogue,
         Interactable::default(),
         crate::depth::YSorted { foot_offset: -24.0 },
-        Sprite::from_atlas_image(
-            texture,
-            TextureAtlas {
-                layout: atlas_layout,
-                index: sprite_index,
-            },
-        ),
+        Sprite {
+            flip_x: frame.flip_x,
+            ..Sprite::from_atlas_image(
+                texture,
+                TextureAtlas {
+                    layout: atlas_layout,
+                    index: sprite_index,
+                },
+            )
+        },
         Transform::from_translation(position),
     ));
     if step_anime {
@@ -300,6 +791,7 @@ fn check_npc_proximity(
     player_query: Query<&Transform, With<Player>>,
     npc_query: Query<(Entity, &Transform, &Interactable), (With<Npc>, Without<InRange>)>,
     in_range_query: Query<(Entity, &Transform, &Interactable), (With<Npc>, With<InRange>)>,
+    mut rumble: MessageWriter<RumbleEvent>,
 ) {
     let Ok(player_transform) = player_query.single() else {
         return;
@@ -313,6 +805,7 @@ fn check_npc_proximity(
 
         if distance <= interactable.radius {
             commands.entity(entity).insert(InRange);
+            rumble.write(RumbleEvent::LIGHT);
         }
     }
 
@@ -326,18 +819,156 @@ fn check_npc_proximity(
     }
 }
 
+/// Which NPC in range an E press talks to.
+#[derive(Resource, Debug, Clone)]
+pub struct InteractionSettings {
+    /// How wide the cone in front of the player is, in degrees: NPCs in it
+    /// are talked to before any outside it, however close.
+    pub facing_cone_degrees: f32,
+    /// With nobody in the cone, talk to the closest NPC in range anyway.
+    /// Off, the player has to turn to them.
+    pub distance_fallback: bool,
+}
+
+impl Default for InteractionSettings {
+    fn default() -> Self {
+        Self { facing_cone_degrees: 90.0, distance_fallback: true }
+    }
+}
+
+/// What an E press talked to - the `npc.interaction` span's
+/// `interaction.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionKind {
+    Npc,
+    /// A sign or the like (map_objects.rs).
+    Object,
+}
+
+impl InteractionKind {
+    fn of(object: bool) -> Self {
+        if object { InteractionKind::Object } else { InteractionKind::Npc }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InteractionKind::Npc => "npc",
+            InteractionKind::Object => "object",
+        }
+    }
+}
+
+/// Why an E press talked to the NPC it did - the `npc.interaction` span's
+/// `interaction.selected_by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectedBy {
+    /// The closest in range in the facing cone.
+    Facing,
+    /// The closest in range, nobody being in the cone.
+    Distance,
+    /// Reached across a counter (see handle_interaction_input).
+    Counter,
+    /// Clicked or tapped (`NpcClicked`).
+    Click,
+}
+
+impl SelectedBy {
+    pub fn name(self) -> &'static str {
+        match self {
+            SelectedBy::Facing => "facing",
+            SelectedBy::Distance => "distance",
+            SelectedBy::Counter => "counter",
+            SelectedBy::Click => "click",
+        }
+    }
+
+    /// The span's `input.source`: what the player talked with.
+    fn input_source(self, device: ActiveDevice) -> &'static str {
+        match (self, device) {
+            (SelectedBy::Click, _) => "mouse",
+            (_, ActiveDevice::Keyboard) => "keyboard",
+            (_, ActiveDevice::Gamepad(_)) => "gamepad",
+        }
+    }
+}
+
+/// An NPC in range was clicked or tapped (click_to_talk.rs): talk to them
+/// as an E press would, facing or not.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct NpcClicked {
+    pub npc: Entity,
+}
+
+/// Which of `candidates` (position, whatever identifies it) is nearest
+/// `player_pos`, and how far: the first of any tied.
+pub fn closest_to<T>(player_pos: Vec2, candidates: impl IntoIterator<Item = (Vec2, T)>) -> Option<(T, f32)> {
+    candidates.into_iter().fold(None, |closest, (position, candidate)| {
+        let distance = player_pos.distance(position);
+        match closest {
+            Some((_, closest_distance)) if closest_distance <= distance => closest,
+            _ => Some((candidate, distance)),
+        }
+    })
+}
+
+/// Who of `candidates` in range a player at `player_pos` looking along
+/// `facing` talks to: the closest in the facing cone, else - if
+/// `settings` allow - the closest at all. The pick both an E press and
+/// the talk prompt (interaction_prompt.rs) make.
+pub fn pick_talk_target<T>(
+    player_pos: Vec2,
+    facing: Vec2,
+    settings: &InteractionSettings,
+    candidates: impl IntoIterator<Item = (Vec2, T)>,
+) -> Option<(T, f32, SelectedBy)> {
+    let half_cone = (settings.facing_cone_degrees / 2.0).to_radians();
+    let (ahead, aside): (Vec<_>, Vec<_>) = candidates.into_iter().partition(|(position, _)| {
+        let offset = *position - player_pos;
+        // Standing on the player counts as in front of them.
+        offset == Vec2::ZERO || facing.angle_to(offset).abs() <= half_cone
+    });
+    if let Some((candidate, distance)) = closest_to(player_pos, ahead) {
+        return Some((candidate, distance, SelectedBy::Facing));
+    }
+    if !settings.distance_fallback {
+        return None;
+    }
+    closest_to(player_pos, aside).map(|(candidate, distance)| (candidate, distance, SelectedBy::Distance))
+}
+
 fn handle_interaction_input(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    keyboard: crate::input::GameInput,
     player_query: Query<(&Transform, &crate::player::Facing, Option<&PlayerSessionTrace>), With<Player>>,
-    npc_query: Query<(&Transform, &NpcDialogue), (With<Npc>, With<InRange>)>,
-    all_npcs: Query<(&Transform, &NpcDialogue), With<Npc>>,
-    mut dialogue_events: MessageWriter<StartDialogueEvent>,
-    map_exits: Option<Res<crate::tilemap::MapExits>>,
-    collision_map: Option<Res<crate::tilemap::CollisionMap>>,
+    npc_query: Query<(Entity, &Transform, &NpcDialogue, &Npc, Has<MapObject>), With<InRange>>,
+    all_npcs: Query<(Entity, &Transform, &NpcDialogue, &Npc, Has<MapObject>)>,
+    busy_query: Query<(Option<&BusyBehavior>, Option<&Interactable>), With<Busy>>,
+    pending: Option<Res<PendingInteraction>>,
+    mut dialogue_events: MessageWriter<DialogueRequest>,
+    mut toasts: MessageWriter<ShowToast>,
+    (map_exits, collision_map, interaction_settings, device, mut clicks, time): (
+        Option<Res<crate::tilemap::MapExits>>,
+        Option<Res<crate::tilemap::CollisionMap>>,
+        Res<InteractionSettings>,
+        Res<ActiveDevice>,
+        MessageReader<NpcClicked>,
+        Res<Time>,
+    ),
+    files: DialogueFiles,
+    mut history: TalkHistory,
+    flags: Res<crate::flags::GameFlags>,
     tracer: Option<Res<GameTracer>>,
-    meter: Option<Res<GameMeter>>,
+    metrics: Option<Res<InteractionMetrics>>,
+    mut interactions: MessageWriter<NpcInteracted>,
 ) {
-    if !keyboard.just_pressed(KeyCode::KeyE) {
+    let clicked = clicks.read().last().map(|click| click.npc);
+    if clicked.is_none() && !keyboard.action_just_pressed(GameAction::Interact) {
+        return;
+    }
+
+    // Already waiting on somebody: mashing E shouldn't re-queue or start a
+    // second conversation over the top of the pending one.
+    if pending.is_some() {
         return;
     }
 
@@ -355,35 +986,42 @@ fn handle_interaction_input(
     // (both activate it, see check_map_exits) - owns the E press. Without
     // this, E on/at the retro-dialog tile with an NPC in range would fire
     // both the scripted scene AND that NPC's dialogue in the same frame
-    // (kaibo review 2026-07-12).
-    if let (Some(exits), Some(map)) = (&map_exits, &collision_map) {
+    // (kaibo review 2026-07-12). A terminal being faced owns it the same
+    // way (see dashboard.rs). A click says who it's for.
+    if clicked.is_none()
+        && let Some(map) = &collision_map
+    {
         let (tile_x, tile_y) = crate::map_data::world_to_tile(logical_pos, map.width, map.height);
         let (dx, dy) = player_facing.tile_delta();
-        let claims_press = exits.0.iter().any(|exit| {
+        let claims_press = map_exits.iter().flat_map(|exits| &exits.0).any(|exit| {
             exit.trigger == crate::map_data::ExitTrigger::Action
                 && ((exit.trigger_x as i32 == tile_x && exit.trigger_y as i32 == tile_y)
                     || (exit.trigger_x as i32 == tile_x + dx
                         && exit.trigger_y as i32 == tile_y + dy))
-        });
+        }) || map.is_terminal(tile_x + dx, tile_y + dy);
         if claims_press {
             return;
         }
     }
 
-    let mut closest_npc: Option<(&NpcDialogue, f32)> = None;
-
-    for (npc_transform, dialogue) in &npc_query {
-        let npc_pos = npc_transform.translation.truncate();
-        let distance = player_pos.distance(npc_pos);
-
-        if let Some((_, closest_dist)) = closest_npc {
-            if distance < closest_dist {
-                closest_npc = Some((dialogue, distance));
-            }
-        } else {
-            closest_npc = Some((dialogue, distance));
+    // (entity, npc, dialogue, is it an object, distance, why it's them)
+    let closest_npc = match clicked {
+        // A click says who: gone or out of range since, it's nobody - not
+        // whoever a counter puts in reach.
+        Some(npc) => {
+            let Ok((entity, npc_transform, dialogue, npc, object)) = npc_query.get(npc) else { return };
+            Some((entity, npc, dialogue, object, player_pos.distance(npc_transform.translation.truncate()), SelectedBy::Click))
         }
-    }
+        None => pick_talk_target(
+            player_pos,
+            player_facing.direction(),
+            &interaction_settings,
+            npc_query.iter().map(|(entity, npc_transform, dialogue, npc, object)| {
+                (npc_transform.translation.truncate(), (entity, npc, dialogue, object))
+            }),
+        )
+        .map(|((entity, npc, dialogue, object), distance, selected_by)| (entity, npc, dialogue, object, distance, selected_by)),
+    };
 
     // Counter reach (RPGMaker Game_Player.checkEventTriggerThere): with
     // nobody in plain interaction range, if the tile directly ahead is a
@@ -398,64 +1036,398 @@ fn handle_interaction_input(
             return None;
         }
         let beyond = (px + 2 * dx, py + 2 * dy);
-        all_npcs.iter().find_map(|(npc_transform, dialogue)| {
+        all_npcs.iter().find_map(|(entity, npc_transform, dialogue, npc, object)| {
             let npc_pos = npc_transform.translation.truncate();
             let npc_tile = crate::map_data::world_to_tile(npc_pos, map.width, map.height);
-            (npc_tile == beyond).then(|| (dialogue, player_pos.distance(npc_pos)))
+            (npc_tile == beyond).then(|| (entity, npc, dialogue, object, player_pos.distance(npc_pos), SelectedBy::Counter))
         })
     });
 
-    if let Some((dialogue, distance)) = closest_npc {
-        info!("🤝 NPC interaction started: {} (distance: {:.1}px)", dialogue.speaker, distance);
-
-        // Telemetry: Start NPC interaction span (if available)
-        let telemetry_guard = if let (Some(tracer), Some(meter), Some(session_trace)) = (&tracer, &meter, session_trace) {
-            let span = start_npc_interaction_span(
-                tracer,
-                session_trace,
-                &dialogue.speaker,
-                player_pos,
-                distance,
-            );
-
-            // Record interaction metric
-            meter.interactions_total.add(
-                1,
-                &[KeyValue::new("npc.name", dialogue.speaker.clone())]
-            );
-
-            // Set this span as the current context for dialogue event processing
-            let context = opentelemetry::Context::current_with_value(span.span_context().clone());
-            let guard = context.attach();
-            Some((span, guard))
-        } else {
-            None
-        };
+    let Some((entity, npc, dialogue, object, distance, selected_by)) = closest_npc else {
+        return;
+    };
 
-        // One segment per paragraph, all sharing this NPC's speaker and
-        // portrait (scripted scenes with per-box speakers come from exit
-        // events instead - see transitions.rs).
-        let segments = dialogue
-            .lines
-            .iter()
-            .map(|line| crate::dialogue::DialogueSegment {
-                speaker: dialogue.speaker.clone(),
-                portrait_path: dialogue.portrait_path.clone(),
-                portrait_face_index: dialogue.portrait_face_index,
-                text: line.clone(),
-            })
-            .collect();
+    // Mid-step: talking now would open the box on a character still
+    // sliding between tiles (and snap them back on the next frame).
+    if let Ok((behavior, interactable)) = busy_query.get(entity) {
+        match behavior.copied().unwrap_or_default() {
+            BusyBehavior::Wait => {
+                info!("⏳ {} is busy - queueing interaction", npc.name);
+                let reach = if selected_by == SelectedBy::Counter {
+                    COUNTER_REACH
+                } else {
+                    interactable.map_or(Interactable::default().radius, |i| i.radius)
+                };
+                let emote = spawn_emote(&mut commands, entity, "...", None);
+                commands.insert_resource(PendingInteraction {
+                    npc: entity,
+                    emote,
+                    reach,
+                    selected_by,
+                    requested_at: time.elapsed(),
+                    pressed_at: keyboard.pressed_at(KeyCode::KeyE).filter(|_| clicked.is_none()),
+                });
+            }
+            BusyBehavior::Decline => {
+                toasts.write(ShowToast::new("They're busy"));
+            }
+        }
+        return;
+    }
+
+    let Some(dialogue) = dialogue.spoken(&files) else {
+        return;
+    };
+    let Some(selected) = dialogue.select(&flags) else {
+        info!("🤐 {} has nothing to say with the flags as they are", npc.name);
+        return;
+    };
+    let mut selection = DialogueSelection::resolve(&npc.id, &dialogue, selected, &history.times_talked, &flags);
+    let instead = if object { None } else { history.talk(entity, &npc.id, selected) };
+    if instead.is_some() {
+        selection.variant = REPEAT_LINE_VARIANT.to_string();
+    }
+    start_interaction(
+        &mut commands,
+        entity,
+        npc,
+        InteractionKind::of(object),
+        instead.as_ref().unwrap_or(selected),
+        selection,
+        selected_by,
+        selected_by.input_source(*device),
+        distance,
+        player_pos,
+        session_trace,
+        tracer.as_deref(),
+        metrics.as_deref(),
+        &mut dialogue_events,
+        &mut interactions,
+        &flags,
+        None,
+        keyboard.pressed_at(KeyCode::KeyE).filter(|_| clicked.is_none()),
+    );
+}
+
+/// Finish (or abandon) a queued interaction: starts the conversation the
+/// moment the NPC's step lands, cancels it if the player wanders off or the
+/// NPC goes away (scene exit).
+fn resolve_pending_interaction(
+    mut commands: Commands,
+    pending: Option<Res<PendingInteraction>>,
+    player_query: Query<(&Transform, Option<&PlayerSessionTrace>), With<Player>>,
+    npc_query: Query<(&Npc, &Transform, &NpcDialogue, Has<Busy>, Has<MapObject>)>,
+    files: DialogueFiles,
+    mut dialogue_events: MessageWriter<DialogueRequest>,
+    mut history: TalkHistory,
+    flags: Res<crate::flags::GameFlags>,
+    tracer: Option<Res<GameTracer>>,
+    metrics: Option<Res<InteractionMetrics>>,
+    device: Res<ActiveDevice>,
+    time: Res<Time>,
+    mut interactions: MessageWriter<NpcInteracted>,
+) {
+    let Some(pending) = pending else { return };
+
+    let finish = |commands: &mut Commands| {
+        commands.entity(pending.emote).try_despawn();
+        commands.remove_resource::<PendingInteraction>();
+    };
+
+    let Ok((npc, npc_transform, dialogue, busy, object)) = npc_query.get(pending.npc) else {
+        finish(&mut commands);
+        return;
+    };
+    let Ok((player_transform, session_trace)) = player_query.single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+    let distance = player_pos.distance(npc_transform.translation.truncate());
+    if distance > pending.reach {
+        info!("⏳ Queued interaction with {} cancelled - player left", dialogue.speaker);
+        finish(&mut commands);
+        return;
+    }
+    if busy {
+        return;
+    }
 
-        dialogue_events.write(StartDialogueEvent { segments });
+    let waited = time.elapsed().saturating_sub(pending.requested_at);
+    finish(&mut commands);
+    let Some(dialogue) = dialogue.spoken(&files) else {
+        return;
+    };
+    let Some(selected) = dialogue.select(&flags) else {
+        info!("🤐 {} has nothing to say with the flags as they are", npc.name);
+        return;
+    };
+    let mut selection = DialogueSelection::resolve(&npc.id, &dialogue, selected, &history.times_talked, &flags);
+    let instead = if object { None } else { history.talk(pending.npc, &npc.id, selected) };
+    if instead.is_some() {
+        selection.variant = REPEAT_LINE_VARIANT.to_string();
+    }
+    start_interaction(
+        &mut commands,
+        pending.npc,
+        npc,
+        InteractionKind::of(object),
+        instead.as_ref().unwrap_or(selected),
+        selection,
+        pending.selected_by,
+        pending.selected_by.input_source(*device),
+        distance,
+        player_pos,
+        session_trace,
+        tracer.as_deref(),
+        metrics.as_deref(),
+        &mut dialogue_events,
+        &mut interactions,
+        &flags,
+        Some(waited),
+        pending.pressed_at,
+    );
+}
 
-        // Clean up telemetry span if it was created
-        if let Some((mut span, guard)) = telemetry_guard {
-            drop(guard);
-            span.end();
+/// Who the conversation in progress is with: the entity the player
+/// engaged, and its `Npc` id and name (not the dialogue's speaker - a
+/// "???" speaker is still the Mysterious Monster here). Set as an
+/// interaction starts, taken down as the game leaves `Mode::Dialogue`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct CurrentInteractionTarget {
+    pub npc: Entity,
+    pub id: String,
+    pub name: String,
+    /// The facing row the NPC had before it turned to the player. Taken -
+    /// the NPC turned back - as the conversation ends (`DialogueEnded`:
+    /// read through, skipped or Escaped), or failing that as the game
+    /// leaves `Mode::Dialogue`, wherever the player has gone since.
+    pub turned_from: Option<u32>,
+}
+
+fn forget_interaction_target(mut commands: Commands) {
+    commands.remove_resource::<CurrentInteractionTarget>();
+}
+
+/// Whoever was just talked to looks at the player, even one who came up
+/// from behind.
+fn turn_to_the_player(
+    mut interactions: MessageReader<NpcInteracted>,
+    target: Option<ResMut<CurrentInteractionTarget>>,
+    player: Query<&Transform, With<Player>>,
+    mut npcs: Query<(&Transform, &mut CharacterFrames, &mut Sprite), With<Npc>>,
+) {
+    let Some(interaction) = interactions.read().last() else { return };
+    let Some(mut target) = target.filter(|target| target.npc == interaction.entity) else { return };
+    let Ok(player) = player.single() else { return };
+    let Ok((transform, mut frames, mut sprite)) = npcs.get_mut(interaction.entity) else { return };
+    // Talked to again before turning back: the facing to return to is
+    // still the first one.
+    target.turned_from.get_or_insert(frames.facing_row);
+    let toward = crate::group_conversation::facing_toward(transform.translation.truncate(), player.translation.truncate());
+    frames.facing_row = toward as u32;
+    frames.frame(crate::character_sheet::STANDING_PATTERN).apply(&mut sprite);
+}
+
+/// Turns the talker back, onto the frame it would be showing had it never
+/// been talked to: standing, or a stepper's place in its cycle.
+fn restore_talker_facing(
+    target: Option<ResMut<CurrentInteractionTarget>>,
+    mut npcs: Query<(&mut CharacterFrames, &mut Sprite, Option<&StepAnimation>), With<Npc>>,
+) {
+    let Some(mut target) = target else { return };
+    let Some(facing_row) = target.turned_from.take() else { return };
+    // Gone with its map (the conversation ended in a transfer): nothing
+    // to turn.
+    if let Ok((mut frames, mut sprite, stepping)) = npcs.get_mut(target.npc) {
+        frames.facing_row = facing_row;
+        let pattern = stepping.map_or(crate::character_sheet::STANDING_PATTERN, |anim| step_pattern(anim.step));
+        frames.frame(pattern).apply(&mut sprite);
+    }
+}
+
+/// A hub's npc.interaction span (`DialogueData::topics`), kept open
+/// through the conversation so each topic picked is an event on it: the
+/// trace shows what the player asked about, in order. Ended as the game
+/// leaves `Mode::Dialogue`.
+#[derive(Resource)]
+pub struct HubInteraction {
+    /// The conversation's `DialogueRequest::id`.
+    dialogue: String,
+    span: BoxedSpan,
+}
+
+fn trace_topic_choices(mut chosen: MessageReader<DialogueTopicChosen>, hub: Option<ResMut<HubInteraction>>) {
+    let Some(mut hub) = hub else { return };
+    for chosen in chosen.read() {
+        if chosen.id == hub.dialogue {
+            hub.span.add_event("dialogue.topic_selected", vec![KeyValue::new("topic.id", chosen.topic.clone())]);
         }
     }
 }
 
+fn end_hub_interaction(mut commands: Commands, hub: Option<ResMut<HubInteraction>>) {
+    let Some(mut hub) = hub else { return };
+    hub.span.end();
+    commands.remove_resource::<HubInteraction>();
+}
+
+/// Two 48px tiles plus slack: how far a counter hop reaches (see
+/// handle_interaction_input). A conversation queued across a counter
+/// survives until the player backs off past this - the NPC is never InRange
+/// there, so the usual radius would cancel it immediately.
+const COUNTER_REACH: f32 = 110.0;
+
+/// Open `dialogue` as a conversation: span, metric, and the
+/// DialogueRequest - the span ended, unless it's a hub's
+/// (`HubInteraction`). `waited` is how long the press sat queued behind a
+/// busy NPC, recorded on the span so slow patrols show up in traces;
+/// `pressed_at` rides on the request so the box's first frame can be
+/// timed against the keypress (input_latency.rs).
+fn start_interaction(
+    commands: &mut Commands,
+    entity: Entity,
+    npc: &Npc,
+    kind: InteractionKind,
+    dialogue: &NpcDialogue,
+    selection: DialogueSelection,
+    selected_by: SelectedBy,
+    input_source: &'static str,
+    distance: f32,
+    player_pos: Vec2,
+    session_trace: Option<&PlayerSessionTrace>,
+    tracer: Option<&GameTracer>,
+    metrics: Option<&InteractionMetrics>,
+    dialogue_events: &mut MessageWriter<DialogueRequest>,
+    interactions: &mut MessageWriter<NpcInteracted>,
+    flags: &crate::flags::GameFlags,
+    waited: Option<std::time::Duration>,
+    pressed_at: Option<web_time::Instant>,
+) {
+    info!("🤝 NPC interaction started: {} (distance: {:.1}px)", npc.name, distance);
+    // A sign read isn't someone talked to: no `NpcInteracted` for the
+    // session summary or the gameplay event log.
+    if kind == InteractionKind::Npc {
+        interactions.write(NpcInteracted { id: npc.id.clone(), name: npc.name.clone(), entity });
+    }
+    info!(
+        "🎯 Dialogue selected for {}: variant={} conditions={:?} times_talked={} flags={:?} source={}",
+        npc.name, selection.variant, selection.conditions, selection.times_talked, selection.flags, selection.source,
+    );
+
+    if let Some(metrics) = metrics {
+        metrics.total.add(
+            1,
+            &[
+                KeyValue::new("npc.id", npc.id.clone()),
+                KeyValue::new("npc.name", npc.name.clone()),
+                KeyValue::new("repeat", selection.times_talked > 0),
+            ]
+        );
+    }
+
+    // Telemetry: Start NPC interaction span (if available)
+    let interaction_span = if let (Some(tracer), Some(session_trace)) = (tracer, session_trace) {
+        let mut span = start_npc_interaction_span(
+            tracer,
+            session_trace,
+            &npc.id,
+            &npc.name,
+            player_pos,
+            distance,
+        );
+        span.set_attribute(KeyValue::new("interaction.kind", kind.name()));
+        span.set_attribute(KeyValue::new("interaction.selected_by", selected_by.name()));
+        span.set_attribute(KeyValue::new("input.source", input_source));
+        if let Some(waited) = waited {
+            span.set_attribute(KeyValue::new("interaction.wait_ms", waited.as_millis() as i64));
+        }
+        for attribute in selection.attributes() {
+            span.set_attribute(attribute);
+        }
+
+        Some(span)
+    } else {
+        None
+    };
+
+    // One segment per paragraph, all sharing this NPC's speaker and
+    // portrait unless a line changes face or is someone else's - the
+    // player answering back. The NPC's own lines go by its id.
+    let segments = dialogue
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let other_speaker =
+                dialogue.line_speakers.get(index).cloned().flatten().filter(|other| *other != dialogue.speaker);
+            crate::dialogue::DialogueSegment {
+                speaker_id: other_speaker.is_none().then(|| npc.id.clone()),
+                speaker: other_speaker.unwrap_or_else(|| dialogue.speaker.clone()),
+                portrait_path: dialogue
+                    .line_portraits
+                    .get(index)
+                    .cloned()
+                    .flatten()
+                    .unwrap_or_else(|| dialogue.portrait_path.clone()),
+                portrait_face_index: dialogue.portrait_face_index,
+                text: line.clone(),
+                mood: dialogue.mood.clone(),
+                box_layout: dialogue.box_layout,
+                actions: dialogue.line_actions.get(index).cloned().unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    // Gated topics are offered once their flag is set, like gated NPCs.
+    let topics: Vec<_> = dialogue
+        .topics
+        .iter()
+        .filter(|topic| topic.requires_flag.as_ref().is_none_or(|flag| flags.is_set(flag)))
+        .cloned()
+        .collect();
+    let hub = !topics.is_empty();
+
+    let mut request = DialogueRequestBuilder::segments(segments)
+        .id(dialogue.id.clone())
+        .source(entity)
+        .topics(topics)
+        .branches(dialogue.branches.clone())
+        .on_complete(dialogue.on_complete.clone());
+    if let Some(text_speed) = dialogue.text_speed {
+        request = request.text_speed(text_speed);
+    }
+    if !dialogue.portrait_path.is_empty() {
+        request = request.fallback_portrait(dialogue.portrait_path.clone());
+    }
+    if let Some(pressed_at) = pressed_at {
+        request = request.pressed_at(pressed_at);
+    }
+    if kind == InteractionKind::Object {
+        request = request.panel_color(crate::map_objects::OBJECT_PANEL_COLOR);
+    }
+    // dialogue.session goes under this interaction.
+    if let Some(span) = &interaction_span {
+        request = request.parent(span.span_context().clone());
+    }
+    dialogue_events.write(request.build());
+    let target = CurrentInteractionTarget { npc: entity, id: npc.id.clone(), name: npc.name.clone(), turned_from: None };
+    commands.queue(move |world: &mut World| {
+        // Talked to again before turning back: still turned, from where
+        // it first was.
+        let turned_from = world
+            .get_resource::<CurrentInteractionTarget>()
+            .filter(|current| current.npc == target.npc)
+            .and_then(|current| current.turned_from);
+        world.insert_resource(CurrentInteractionTarget { turned_from, ..target });
+    });
+
+    match interaction_span {
+        Some(span) if hub => commands.insert_resource(HubInteraction { dialogue: dialogue.id.clone(), span }),
+        Some(mut span) => span.end(),
+        None => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -464,6 +1436,59 @@ mod tests {
     use crate::player::Facing;
     use crate::tilemap::CollisionMap;
 
+    #[test]
+    fn selection_reports_the_gating_flag_and_only_flags_the_npc_touches() {
+        let dialogue = NpcDialogue {
+            id: "town_of_endgame/greeter".into(),
+            speaker: "Greeter".into(),
+            portrait_path: String::new(),
+            portrait_face_index: 0,
+            lines: vec!["Isabella said you'd come.".into()],
+            line_portraits: Vec::new(),
+            line_speakers: Vec::new(),
+            line_actions: Vec::new(),
+            mood: None,
+            text_speed: None,
+            box_layout: Default::default(),
+            on_complete: vec![crate::dialogue::DialogueOutcome::SetFlag("greeted".into())],
+            requires_flag: Some("met_isabella".into()),
+            conditions: Vec::new(),
+            alternatives: Vec::new(),
+            topics: Vec::new(),
+            branches: Default::default(),
+            source: "maps/town_of_endgame.json".into(),
+            file: None,
+        };
+        let flags: crate::flags::GameFlags = ["met_isabella".to_string(), "secret".to_string()].into_iter().collect();
+        let mut times_talked = TimesTalked::default();
+        times_talked.record("greeter");
+
+        let selection = DialogueSelection::resolve("greeter", &dialogue, &dialogue, &times_talked, &flags);
+        assert_eq!(selection.conditions, ["has_flag:met_isabella"]);
+        assert_eq!(selection.times_talked, 1);
+        assert_eq!(selection.flags, [("met_isabella".to_string(), true), ("greeted".to_string(), false)]);
+    }
+
+    #[test]
+    fn when_npcs_last_spoke_survives_a_save() {
+        let mut times_talked = TimesTalked::default();
+        times_talked.record("greeter");
+        times_talked.last_said.insert("greeter".into(), Duration::from_secs(10));
+        times_talked.last_said.insert("guard".into(), Duration::from_secs(95));
+        let said_ago = times_talked.said_ago(Duration::from_secs(100));
+        assert_eq!(said_ago, std::collections::HashMap::from([("greeter".into(), 90.0), ("guard".into(), 5.0)]));
+
+        // Loaded two seconds into the next session: longer ago than that.
+        let mut restored = TimesTalked::default();
+        restored.restore(times_talked.counts().clone(), &said_ago, Duration::from_secs(2));
+        assert_eq!(restored.get("greeter"), 1);
+        let now = restored.clock(Duration::from_secs(2));
+        let state = |last| NpcDialogueState { repeat: DialogueRepeat::Cooldown(60.0), last_talked: last, ..default() };
+        assert!(state(restored.last_said("greeter")).recites(now), "90s ago is past the cooldown");
+        assert!(!state(restored.last_said("guard")).recites(now), "5s ago isn't");
+        assert_eq!(restored.said_ago(Duration::from_secs(2)), said_ago);
+    }
+
     #[test]
     fn step_pattern_ping_pongs_through_the_middle() {
         // RPGMaker's stationary cycle: 0, 1, 2, 1, then wraps.
@@ -482,8 +1507,19 @@ mod tests {
     // between them at (2,2) - a counter or not, per test.
     fn setup_counter_world(counter_between: bool) -> World {
         let mut world = World::new();
-        world.init_resource::<Messages<StartDialogueEvent>>();
+        world.init_resource::<Messages<DialogueRequest>>();
+        world.init_resource::<Messages<NpcInteracted>>();
+        world.init_resource::<Messages<ShowToast>>();
+        world.init_resource::<Messages<NpcClicked>>();
+        world.init_resource::<ActiveDevice>();
+        world.init_resource::<TimesTalked>();
+        world.init_resource::<InteractionSettings>();
+        world.init_resource::<Time>();
+        world.init_resource::<crate::flags::GameFlags>();
         world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<crate::input::InputLatch>();
+        world.init_resource::<crate::input::KeyPressTimes>();
+        world.init_resource::<crate::input::InputMap>();
 
         let mut map = CollisionMap::new(5, 5);
         if counter_between {
@@ -500,12 +1536,27 @@ mod tests {
 
         let npc_pos = tile_to_world(2, 1, 5, 5);
         world.spawn((
-            Npc { name: "Isabella".into(), sprite_facing: NpcFacing::Down, sprite_slot: 0 },
+            Npc { id: "isabella".into(), name: "Isabella".into(), sprite_facing: NpcFacing::Down, sprite_slot: 0 },
             NpcDialogue {
+                id: "town_of_endgame/isabella".into(),
                 speaker: "Isabella".into(),
                 portrait_path: String::new(),
                 portrait_face_index: 0,
                 lines: vec!["Welcome to the shop.".into()],
+                line_portraits: Vec::new(),
+                line_speakers: Vec::new(),
+                line_actions: Vec::new(),
+                mood: None,
+                text_speed: None,
+                box_layout: Default::default(),
+                on_complete: Vec::new(),
+                requires_flag: None,
+                conditions: Vec::new(),
+                alternatives: Vec::new(),
+                topics: Vec::new(),
+                branches: Default::default(),
+                source: String::new(),
+                file: None,
             },
             Transform::from_xyz(npc_pos.x, npc_pos.y, 1.0),
         ));
@@ -514,9 +1565,39 @@ mod tests {
         world
     }
 
+    #[test]
+    fn the_npc_talked_to_turns_to_the_player_and_back_once() {
+        let mut world = World::new();
+        world.init_resource::<Messages<NpcInteracted>>();
+        // The player comes up from behind: north of an NPC facing south.
+        world.spawn((Player, Transform::from_xyz(0.0, 48.0, 1.0)));
+        let npc = world
+            .spawn((
+                Npc { id: "isabella".into(), name: "Isabella".into(), sprite_facing: NpcFacing::Down, sprite_slot: 0 },
+                CharacterFrames { slot: 0, facing_row: NpcFacing::Down as u32, sheet: default() },
+                Sprite::default(),
+                Transform::default(),
+            ))
+            .id();
+        let facing_row = |world: &World| world.get::<CharacterFrames>(npc).unwrap().facing_row;
+
+        world.insert_resource(CurrentInteractionTarget { npc, id: "isabella".into(), name: "Isabella".into(), turned_from: None });
+        world.write_message(NpcInteracted { id: "isabella".into(), name: "Isabella".into(), entity: npc });
+        world.run_system_once(turn_to_the_player).unwrap();
+        assert_eq!(facing_row(&world), NpcFacing::Up as u32);
+        assert_eq!(world.resource::<CurrentInteractionTarget>().turned_from, Some(NpcFacing::Down as u32));
+
+        world.run_system_once(restore_talker_facing).unwrap();
+        assert_eq!(facing_row(&world), NpcFacing::Down as u32);
+        // Back to exploring again later: already restored, so left alone.
+        world.get_mut::<CharacterFrames>(npc).unwrap().facing_row = NpcFacing::Left as u32;
+        world.run_system_once(restore_talker_facing).unwrap();
+        assert_eq!(facing_row(&world), NpcFacing::Left as u32);
+    }
+
     fn dialogue_count(world: &World) -> usize {
         world
-            .resource::<Messages<StartDialogueEvent>>()
+            .resource::<Messages<DialogueRequest>>()
             .iter_current_update_messages()
             .count()
     }
@@ -541,6 +1622,23 @@ mod tests {
         assert_eq!(dialogue_count(&world), 0, "no counter, no long reach");
     }
 
+    #[test]
+    fn a_click_on_someone_out_of_range_never_reaches_across_a_counter() {
+        // Facing the counter with the shopkeeper beyond it, but the click
+        // was on her neighbour, who isn't in range: nobody is talked to.
+        let mut world = setup_counter_world(true);
+        let neighbour = world
+            .spawn((
+                Npc { id: "doggo".into(), name: "Doggo".into(), sprite_facing: NpcFacing::Down, sprite_slot: 0 },
+                Transform::default(),
+            ))
+            .id();
+        world.write_message(NpcClicked { npc: neighbour });
+
+        world.run_system_once(handle_interaction_input).unwrap();
+        assert_eq!(dialogue_count(&world), 0, "the click was for someone else");
+    }
+
     #[test]
     fn counter_reach_only_works_when_facing_the_counter() {
         // Facing AWAY from the counter (down) must not reach the NPC north
@@ -553,67 +1651,146 @@ mod tests {
         assert_eq!(dialogue_count(&world), 0, "reach must follow facing");
     }
 
+    // Player at (2,3) facing Up with a busy NPC one tile north, in range.
+    fn setup_busy_world(behavior: BusyBehavior) -> (World, Entity) {
+        let mut world = setup_counter_world(false);
+        let near = tile_to_world(2, 2, 5, 5);
+        let npc = world
+            .spawn((
+                Npc { id: "doggo".into(), name: "Doggo".into(), sprite_facing: NpcFacing::Down, sprite_slot: 0 },
+                NpcDialogue {
+                    id: "town_of_endgame/doggo".into(),
+                    speaker: "Doggo".into(),
+                    portrait_path: String::new(),
+                    portrait_face_index: 0,
+                    lines: vec!["Wan wan!".into()],
+                    line_portraits: Vec::new(),
+                    line_speakers: Vec::new(),
+                    line_actions: Vec::new(),
+                    mood: None,
+                    text_speed: None,
+                    box_layout: Default::default(),
+                    on_complete: Vec::new(),
+                    requires_flag: None,
+                    conditions: Vec::new(),
+                    alternatives: Vec::new(),
+                    topics: Vec::new(),
+                    branches: Default::default(),
+                    source: String::new(),
+                    file: None,
+                },
+                Interactable::default(),
+                InRange,
+                Busy,
+                behavior,
+                Transform::from_xyz(near.x, near.y, 1.0),
+            ))
+            .id();
+        (world, npc)
+    }
+
+    #[test]
+    fn busy_npc_queues_the_conversation_until_the_step_lands() {
+        let (mut world, npc) = setup_busy_world(BusyBehavior::Wait);
+        world.run_system_once(handle_interaction_input).unwrap();
+        assert_eq!(dialogue_count(&world), 0, "must not talk to a sliding NPC");
+        assert_eq!(world.resource::<PendingInteraction>().npc, npc);
+
+        // Still mid-step: the queue holds.
+        world.run_system_once(resolve_pending_interaction).unwrap();
+        assert_eq!(dialogue_count(&world), 0);
+
+        world.entity_mut(npc).remove::<Busy>();
+        world.run_system_once(resolve_pending_interaction).unwrap();
+        assert_eq!(dialogue_count(&world), 1, "step landed - conversation starts");
+        assert!(!world.contains_resource::<PendingInteraction>());
+        assert_eq!(world.query::<&Emote>().iter(&world).count(), 0, "emote cleaned up");
+    }
+
+    #[test]
+    fn queued_conversation_cancels_when_the_player_walks_off() {
+        let (mut world, npc) = setup_busy_world(BusyBehavior::Wait);
+        world.run_system_once(handle_interaction_input).unwrap();
+
+        let far = tile_to_world(4, 4, 5, 5);
+        let mut players = world.query_filtered::<&mut Transform, With<Player>>();
+        *players.single_mut(&mut world).unwrap() = Transform::from_xyz(far.x, far.y, 1.0);
+        world.run_system_once(resolve_pending_interaction).unwrap();
+        assert!(!world.contains_resource::<PendingInteraction>(), "walking away cancels");
+
+        // The NPC finishing its step afterwards must not start anything.
+        world.entity_mut(npc).remove::<Busy>();
+        world.run_system_once(resolve_pending_interaction).unwrap();
+        assert_eq!(dialogue_count(&world), 0);
+    }
+
+    #[test]
+    fn declining_npc_toasts_instead_of_queueing() {
+        let (mut world, _) = setup_busy_world(BusyBehavior::Decline);
+        world.run_system_once(handle_interaction_input).unwrap();
+        assert_eq!(dialogue_count(&world), 0);
+        assert!(!world.contains_resource::<PendingInteraction>());
+        let toasts = world.resource::<Messages<ShowToast>>();
+        let texts: Vec<&str> = toasts.iter_current_update_messages().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["They're busy"]);
+    }
+
     #[test]
-    fn wanderer_steps_onto_a_walkable_tile_and_stops_at_walls() {
-        // A wanderer on a 3x3 map whose center is the only walkable cell
-        // can never leave it; once the ring opens up, a step decision picks
-        // some adjacent walkable tile. Exercises the can_step gate with
-        // every direction blocked vs. open.
+    fn an_unloaded_dialogue_file_falls_back_to_the_inline_lines() {
+        let data: DialogueData = serde_json::from_str(r#"{ "speaker": "Casey", "portrait": "", "lines": ["Hi."] }"#).unwrap();
+        let mut dialogue = NpcDialogue::from_data(&data, None, "maps/town.json".into());
+        dialogue.file = Some(Handle::default());
+
         let mut world = World::new();
-        world.init_resource::<Time>();
+        let spoken = |dialogue: NpcDialogue| {
+            move |files: DialogueFiles| dialogue.spoken(&files).map(|spoken| spoken.lines.clone())
+        };
+        let lines = world.run_system_once(spoken(dialogue.clone())).unwrap();
+        assert_eq!(lines, Some(vec!["Hi.".to_string()]));
 
-        let mut map = CollisionMap::new(3, 3);
-        for x in 0..3 {
-            for y in 0..3 {
-                if (x, y) != (1, 1) {
-                    map.set_tile(x, y, crate::tilemap::TileCollision::Blocked);
-                }
-            }
-        }
-        world.insert_resource(map);
+        dialogue.lines.clear();
+        assert_eq!(world.run_system_once(spoken(dialogue)).unwrap(), None, "nothing to say yet");
+    }
 
-        let center = tile_to_world(1, 1, 3, 3);
-        world.spawn((
-            Wanderer::default(),
-            CharacterFrames { slot: 0, facing_row: 0 },
-            Transform::from_xyz(center.x, center.y, 1.0),
-        ));
+    #[test]
+    fn the_npc_faced_is_talked_to_before_a_closer_one_behind() {
+        // Facing up between Casey, 40px behind, and Isabella, 60px ahead
+        // and a little to the side.
+        let candidates = [(Vec2::new(0.0, -40.0), "casey"), (Vec2::new(20.0, 60.0), "isabella")];
+        let settings = InteractionSettings::default();
+        assert_eq!(
+            pick_talk_target(Vec2::ZERO, Vec2::Y, &settings, candidates).map(|(npc, _, by)| (npc, by)),
+            Some(("isabella", SelectedBy::Facing))
+        );
+        assert_eq!(
+            pick_talk_target(Vec2::ZERO, Vec2::NEG_X, &settings, candidates).map(|(npc, _, by)| (npc, by)),
+            Some(("casey", SelectedBy::Distance)),
+            "nobody in the cone: the closest"
+        );
+        let strict = InteractionSettings { distance_fallback: false, ..default() };
+        assert_eq!(pick_talk_target(Vec2::ZERO, Vec2::NEG_X, &strict, candidates), None);
+        let narrow = InteractionSettings { facing_cone_degrees: 20.0, ..default() };
+        assert_eq!(
+            pick_talk_target(Vec2::ZERO, Vec2::Y, &narrow, candidates).map(|(npc, _, by)| (npc, by)),
+            Some(("casey", SelectedBy::Distance)),
+            "Isabella is 18 degrees off, outside a 20 degree cone"
+        );
+    }
 
-        // Tick well past the idle timer several times: every step decision
-        // must refuse (all four neighbors are blocked).
-        for _ in 0..8 {
-            world
-                .resource_mut::<Time>()
-                .advance_by(std::time::Duration::from_secs(2));
-            world.run_system_once(wander_npcs).unwrap();
-        }
-        let mut wanderers = world.query::<(&Wanderer, &Transform)>();
-        let (wanderer, transform) = wanderers.single(&world).unwrap();
-        assert!(wanderer.target.is_none(), "boxed-in wanderer must not pick a target");
-        assert_eq!(transform.translation.truncate(), center, "and must not move");
-
-        // Open the ring: the next decision must pick an adjacent tile.
-        world.insert_resource(CollisionMap::new(3, 3));
-        let mut stepped = false;
-        for _ in 0..8 {
-            world
-                .resource_mut::<Time>()
-                .advance_by(std::time::Duration::from_secs(2));
-            world.run_system_once(wander_npcs).unwrap();
-            let mut wanderers = world.query::<&Wanderer>();
-            if let Some(target) = wanderers.single(&world).unwrap().target {
-                let neighbors: Vec<Vec2> = [(1u32, 0u32), (0, 1), (2, 1), (1, 2)]
-                    .iter()
-                    .map(|&(x, y)| tile_to_world(x, y, 3, 3))
-                    .collect();
-                assert!(
-                    neighbors.contains(&target),
-                    "wander target {target:?} is not an adjacent tile"
-                );
-                stepped = true;
-                break;
-            }
-        }
-        assert!(stepped, "an unboxed wanderer should step within a few ticks");
+    #[test]
+    fn the_closest_candidate_wins_and_the_first_of_a_tie() {
+        let candidates = [(Vec2::new(0.0, 60.0), "north"), (Vec2::new(40.0, 0.0), "east"), (Vec2::new(-40.0, 0.0), "west")];
+        assert_eq!(closest_to(Vec2::ZERO, candidates), Some(("east", 40.0)));
+        assert_eq!(closest_to(Vec2::ZERO, Vec::<(Vec2, &str)>::new()), None);
+    }
+
+    #[test]
+    fn an_npc_is_met_once() {
+        let mut met = NpcsMet::default();
+        assert!(met.meet("Isabella"));
+        assert!(!met.meet("Isabella"), "the second read-through meets no one new");
+        assert!(met.meet("Casey"));
+        assert!(met.contains("Isabella") && !met.contains("Morgan"));
+        assert_eq!(met.count(), 2);
     }
 }
@@ -0,0 +1,554 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use crate::culling::Culled;
+use crate::game_state::{GameState, GameStatePlugin, Mode};
+use crate::group_conversation::InGroupConversation;
+use crate::map_data::{NpcData, tile_to_world, world_to_tile};
+use crate::npc::{Busy, CharacterFrames, InRange, Npc, NpcFacing};
+use crate::player::{Player, logical_position};
+use crate::rng::GameRng;
+use crate::tilemap::{CollisionChangedEvent, CollisionMap};
+
+/// NPCs that get about: a map entry's `wander` (random tile steps, within
+/// `wander_radius` of where it was placed if set - doggo) or `path` (a
+/// patrol round its tiles in order, back to the first after the last,
+/// pausing at each). Both step one tile at a time at `move_speed`, face
+/// the way they're going, honor map passability, and never step onto the
+/// player's tile or another NPC's (`CollisionMap::occupant`, kept here for
+/// every NPC, moving or not). A step's destination is reserved as it sets
+/// off: it's the NPC's tile for the others from then on, and its body
+/// stands there already for the player's (`NpcMovement::destination`,
+/// player.rs), so nobody walks into the tile it's arriving on.
+///
+/// Nobody moves during dialogue, nor while the player is close enough to
+/// talk to them (`InRange`) - a step under way lands first - so the player
+/// is never chasing a talking head. A mid-step NPC is `Busy`, like one a
+/// scripted scene holds.
+pub struct NpcMovementPlugin;
+
+impl Plugin for NpcMovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CollisionChangedEvent>()
+            .init_resource::<GameRng>()
+            .add_systems(Update, npc_movement.run_if(in_state(Mode::Exploring)))
+            .add_systems(
+                Update,
+                (track_npc_tiles, turn_back_from_closed_tiles)
+                    .run_if(in_state(GameState::Playing))
+                    .before(npc_movement),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<GameStatePlugin>(app, "NpcMovementPlugin");
+    }
+}
+
+/// Tiles per second: RPGMaker move speed 3 (doggo's), 2^3/256 tiles per
+/// frame at 60fps - 90 px/s on 48px tiles.
+pub const DEFAULT_MOVE_SPEED: f32 = 1.875;
+
+/// Pixels per tile, for `move_speed`.
+const TILE_SIZE: f32 = 48.0;
+
+/// How an NPC gets about, and the step it's taking.
+#[derive(Component, Debug)]
+pub struct NpcMovement {
+    pub kind: MovementKind,
+    /// Pixels per second.
+    pub speed: f32,
+    /// Wandering: the pause between step decisions. Patrolling: the pause
+    /// at each point of the path.
+    idle: Timer,
+    /// World-space destination of the step in progress, if any.
+    target: Option<Vec2>,
+    /// Where that step started, to turn back to if its tile closes.
+    origin: Vec2,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MovementKind {
+    /// Random tile steps, no more than `radius` tiles (across or up and
+    /// down) from `home`, if there's a radius.
+    Wander { home: (i32, i32), radius: Option<u32> },
+    /// Round `points` in order. `route` is the way to `points[next]`.
+    Patrol { points: Vec<(i32, i32)>, next: usize, route: VecDeque<(i32, i32)> },
+}
+
+impl NpcMovement {
+    /// Random tile steps from `home`.
+    pub fn wander(home: (i32, i32), radius: Option<u32>) -> Self {
+        Self::new(MovementKind::Wander { home, radius })
+    }
+
+    /// A patrol round `points`.
+    pub fn patrol(points: Vec<(i32, i32)>) -> Self {
+        Self::new(MovementKind::Patrol { points, next: 0, route: VecDeque::new() })
+    }
+
+    /// What `npc` asks for, if it moves: a path is only followed if it's
+    /// sound (`NpcData::movement_problems`; its spawn records why not).
+    pub fn from_data(npc: &NpcData, map_size: (u32, u32)) -> Option<Self> {
+        let movement = if npc.wander {
+            Self::wander((npc.x as i32, npc.y as i32), npc.wander_radius)
+        } else if !npc.path.is_empty() && npc.movement_problems(map_size.0, map_size.1).is_empty() {
+            Self::patrol(npc.path.iter().map(|&(x, y)| (x as i32, y as i32)).collect())
+        } else {
+            return None;
+        };
+        let speed = npc.move_speed.filter(|speed| speed.is_finite() && *speed > 0.0).unwrap_or(DEFAULT_MOVE_SPEED);
+        Some(movement.with_speed(speed * TILE_SIZE))
+    }
+
+    fn new(kind: MovementKind) -> Self {
+        Self {
+            kind,
+            speed: DEFAULT_MOVE_SPEED * TILE_SIZE,
+            idle: Timer::from_seconds(1.5, TimerMode::Repeating),
+            target: None,
+            origin: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_speed(mut self, pixels_per_second: f32) -> Self {
+        self.speed = pixels_per_second;
+        self
+    }
+
+    /// Whether a step is under way.
+    pub fn is_stepping(&self) -> bool {
+        self.target.is_some()
+    }
+
+    /// Where the step under way ends, in world space.
+    pub fn destination(&self) -> Option<Vec2> {
+        self.target
+    }
+
+    /// Whether to decide on a step now, standing on `from`: a wanderer
+    /// once per pause; a patroller right away, except at a point of its
+    /// path, where it waits out the pause.
+    fn ready(&mut self, from: (i32, i32), delta: std::time::Duration) -> bool {
+        let pauses = match &self.kind {
+            MovementKind::Wander { .. } => true,
+            MovementKind::Patrol { points, next, .. } => points.get(*next) == Some(&from),
+        };
+        if !pauses {
+            return true;
+        }
+        self.idle.tick(delta);
+        self.idle.just_finished()
+    }
+
+    /// The tile to step to from `from`, if there's one to try. Doesn't
+    /// check it can be stepped to.
+    fn next_tile(&mut self, from: (i32, i32), map: &CollisionMap, rng: &mut GameRng) -> Option<(i32, i32)> {
+        match &mut self.kind {
+            MovementKind::Wander { .. } => {
+                // Direction deltas in RPGMaker tile orientation (y grows downward).
+                let (dx, dy) = match rng.stream("wander").below(4) {
+                    0 => (0, 1),
+                    1 => (-1, 0),
+                    2 => (1, 0),
+                    _ => (0, -1),
+                };
+                Some((from.0 + dx, from.1 + dy))
+            }
+            MovementKind::Patrol { points, next, route } => {
+                if points.is_empty() {
+                    return None;
+                }
+                if points.get(*next) == Some(&from) {
+                    *next = (*next + 1) % points.len();
+                    route.clear();
+                }
+                if route.is_empty() {
+                    let point = points[*next];
+                    // Round anyone standing in the way, if there's a way round.
+                    let taken = |tile: (i32, i32)| tile != point && map.occupant(tile.0, tile.1).is_some();
+                    let path = map.find_path_to(from, |tile| tile == point, taken).or_else(|| map.find_path(from, point));
+                    let Some(path) = path else {
+                        // Walled off for now: on to the point after.
+                        debug!("No way from {from:?} to patrol point {point:?} - skipping it");
+                        *next = (*next + 1) % points.len();
+                        return None;
+                    };
+                    route.extend(path.into_iter().skip(1));
+                }
+                route.front().copied()
+            }
+        }
+    }
+
+    /// Whether a wanderer may be on `tile`.
+    fn allows(&self, tile: (i32, i32)) -> bool {
+        match &self.kind {
+            MovementKind::Wander { home, radius: Some(radius) } => {
+                (tile.0 - home.0).unsigned_abs().max((tile.1 - home.1).unsigned_abs()) <= *radius
+            }
+            _ => true,
+        }
+    }
+
+    /// Set off from `from` to `to`.
+    fn step(&mut self, from: (i32, i32), to: (i32, i32), map: &CollisionMap) {
+        if let MovementKind::Patrol { route, .. } = &mut self.kind {
+            route.pop_front();
+        }
+        self.origin = tile_to_world(from.0 as u32, from.1 as u32, map.width, map.height);
+        self.target = Some(tile_to_world(to.0 as u32, to.1 as u32, map.width, map.height));
+    }
+
+    /// Head back to where the step started; a patroller then works out its
+    /// way again.
+    fn turn_back(&mut self) {
+        self.target = Some(self.origin);
+        if let MovementKind::Patrol { route, .. } = &mut self.kind {
+            route.clear();
+        }
+    }
+}
+
+/// Which way an NPC stepping from `from` to the adjacent `to` faces.
+fn facing_toward(from: (i32, i32), to: (i32, i32)) -> NpcFacing {
+    match (to.0 - from.0, to.1 - from.1) {
+        (-1, _) => NpcFacing::Left,
+        (1, _) => NpcFacing::Right,
+        (_, -1) => NpcFacing::Up,
+        _ => NpcFacing::Down,
+    }
+}
+
+fn npc_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    collision_map: Option<ResMut<CollisionMap>>,
+    mut rng: ResMut<GameRng>,
+    player: Query<&Transform, (With<Player>, Without<NpcMovement>)>,
+    mut query: Query<
+        (Entity, &mut NpcMovement, &mut Transform, &mut CharacterFrames, Has<InRange>),
+        (Without<Culled>, Without<InGroupConversation>),
+    >,
+) {
+    let Some(mut map) = collision_map else { return };
+    let player_tile = player
+        .single()
+        .ok()
+        .map(|transform| world_to_tile(logical_position(transform.translation.truncate()), map.width, map.height));
+
+    for (entity, mut movement, mut transform, mut frames, in_range) in &mut query {
+        // A step in progress: glide to the target tile, snap on arrival.
+        if let Some(target) = movement.target {
+            let position = transform.translation.truncate();
+            let step = movement.speed * time.delta_secs();
+            if position.distance(target) <= step {
+                transform.translation.x = target.x;
+                transform.translation.y = target.y;
+                movement.target = None;
+                commands.entity(entity).remove::<Busy>();
+            } else {
+                let direction = (target - position).normalize_or_zero();
+                transform.translation.x += direction.x * step;
+                transform.translation.y += direction.y * step;
+            }
+            continue;
+        }
+
+        // Hold still for a player who may be about to talk.
+        if in_range {
+            continue;
+        }
+        let from = world_to_tile(transform.translation.truncate(), map.width, map.height);
+        if !movement.ready(from, time.delta()) {
+            continue;
+        }
+        let Some(to) = movement.next_tile(from, &map, &mut rng) else { continue };
+
+        frames.facing_row = facing_toward(from, to) as u32;
+        // Blocked step: just turn toward it and wait for the next go, like
+        // a dog sniffing at a wall. A patroller tries the same tile again,
+        // or - an NPC in the way - a way round them.
+        let taken = map.occupant(to.0, to.1).is_some_and(|occupant| occupant != entity);
+        if taken && let MovementKind::Patrol { route, .. } = &mut movement.kind {
+            route.clear();
+        }
+        if !map.can_step(from, to) || !movement.allows(to) || player_tile == Some(to) || taken {
+            continue;
+        }
+        commands.entity(entity).insert(Busy);
+        map.occupy(to, entity);
+        movement.step(from, to, &map);
+    }
+}
+
+/// Keeps `CollisionMap`'s occupants current: an NPC claims the tile it's
+/// placed on (every one at once on a freshly loaded map) and lets go of it
+/// when despawned. A step claims its destination as it sets off.
+fn track_npc_tiles(
+    collision_map: Option<ResMut<CollisionMap>>,
+    npcs: Query<(Entity, &Transform, Ref<Npc>)>,
+    mut despawned: RemovedComponents<Npc>,
+) {
+    let Some(mut map) = collision_map else {
+        despawned.clear();
+        return;
+    };
+    for entity in despawned.read() {
+        map.vacate(entity);
+    }
+    let fresh = map.is_added();
+    for (entity, transform, npc) in &npcs {
+        if fresh || npc.is_added() {
+            let tile = world_to_tile(transform.translation.truncate(), map.width, map.height);
+            map.occupy(tile, entity);
+        }
+    }
+}
+
+/// An NPC mid-step onto a tile that just closed (a door shutting in its
+/// face) glides back where it came from instead.
+fn turn_back_from_closed_tiles(
+    mut changes: MessageReader<CollisionChangedEvent>,
+    collision_map: Option<ResMut<CollisionMap>>,
+    mut movers: Query<(Entity, &mut NpcMovement)>,
+) {
+    let Some(mut map) = collision_map else {
+        changes.clear();
+        return;
+    };
+    for change in changes.read() {
+        for (entity, mut movement) in &mut movers {
+            let Some(target) = movement.target else { continue };
+            let tile = world_to_tile(target, map.width, map.height);
+            let closed = change.tiles.iter().any(|&(x, y)| (x as i32, y as i32) == tile);
+            if closed && !map.is_walkable(tile.0, tile.1) {
+                movement.turn_back();
+                let origin = world_to_tile(movement.origin, map.width, map.height);
+                map.occupy(origin, entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::tilemap::TileCollision;
+
+    fn frames() -> CharacterFrames {
+        CharacterFrames { slot: 0, facing_row: 0, sheet: default() }
+    }
+
+    #[test]
+    fn wanderer_steps_onto_a_walkable_tile_and_stops_at_walls() {
+        // A wanderer on a 3x3 map whose center is the only walkable cell
+        // can never leave it; once the ring opens up, a step decision picks
+        // some adjacent walkable tile. Exercises the can_step gate with
+        // every direction blocked vs. open.
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(GameRng::new(1));
+
+        let mut map = CollisionMap::new(3, 3);
+        for x in 0..3 {
+            for y in 0..3 {
+                if (x, y) != (1, 1) {
+                    map.set_tile(x, y, TileCollision::Blocked);
+                }
+            }
+        }
+        world.insert_resource(map);
+
+        let center = tile_to_world(1, 1, 3, 3);
+        world.spawn((NpcMovement::wander((1, 1), None), frames(), Transform::from_xyz(center.x, center.y, 1.0)));
+
+        // Tick well past the idle timer several times: every step decision
+        // must refuse (all four neighbors are blocked).
+        for _ in 0..8 {
+            world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs(2));
+            world.run_system_once(npc_movement).unwrap();
+        }
+        let mut wanderers = world.query::<(&NpcMovement, &Transform)>();
+        let (wanderer, transform) = wanderers.single(&world).unwrap();
+        assert!(wanderer.target.is_none(), "boxed-in wanderer must not pick a target");
+        assert_eq!(transform.translation.truncate(), center, "and must not move");
+
+        // Open the ring: the next decision must pick an adjacent tile.
+        world.insert_resource(CollisionMap::new(3, 3));
+        let mut stepped = false;
+        for _ in 0..8 {
+            world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs(2));
+            world.run_system_once(npc_movement).unwrap();
+            let mut wanderers = world.query::<&NpcMovement>();
+            if let Some(target) = wanderers.single(&world).unwrap().target {
+                let neighbors: Vec<Vec2> =
+                    [(1u32, 0u32), (0, 1), (2, 1), (1, 2)].iter().map(|&(x, y)| tile_to_world(x, y, 3, 3)).collect();
+                assert!(neighbors.contains(&target), "wander target {target:?} is not an adjacent tile");
+                let mut busy = world.query_filtered::<(), (With<NpcMovement>, With<Busy>)>();
+                assert_eq!(busy.iter(&world).count(), 1, "a wanderer mid-step is Busy");
+                stepped = true;
+                break;
+            }
+        }
+        assert!(stepped, "an unboxed wanderer should step within a few ticks");
+    }
+
+    #[test]
+    fn a_step_reserves_its_destination_as_it_sets_off() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(GameRng::new(1));
+        world.insert_resource(CollisionMap::new(3, 1));
+        let start = tile_to_world(0, 0, 3, 1);
+        let walker = world
+            .spawn((NpcMovement::patrol(vec![(2, 0)]), frames(), Transform::from_xyz(start.x, start.y, 1.0)))
+            .id();
+
+        world.run_system_once(npc_movement).unwrap();
+        let map = world.resource::<CollisionMap>();
+        assert_eq!(map.occupant(1, 0), Some(walker), "the tile it's stepping to is taken");
+        assert_eq!(map.occupant(0, 0), None);
+        assert_eq!(world.get::<NpcMovement>(walker).unwrap().destination(), Some(tile_to_world(1, 0, 3, 1)));
+    }
+
+    #[test]
+    fn a_wanderer_turns_back_when_its_step_closes() {
+        use crate::tilemap::CollisionEdits;
+
+        let mut world = World::new();
+        world.init_resource::<Messages<CollisionChangedEvent>>();
+        world.insert_resource(CollisionMap::new(3, 1));
+        let (origin, target) = (tile_to_world(0, 0, 3, 1), tile_to_world(1, 0, 3, 1));
+        let mut wanderer = NpcMovement::wander((0, 0), None);
+        wanderer.target = Some(target);
+        wanderer.origin = origin;
+        world.spawn(wanderer);
+
+        world
+            .run_system_once(|mut edits: CollisionEdits| edits.set_tiles([((2, 0), TileCollision::Blocked)]))
+            .unwrap();
+        world.run_system_once(turn_back_from_closed_tiles).unwrap();
+        let mut wanderers = world.query::<&NpcMovement>();
+        assert_eq!(wanderers.single(&world).unwrap().target, Some(target), "another tile closing changes nothing");
+
+        world
+            .run_system_once(|mut edits: CollisionEdits| edits.set_tiles([((1, 0), TileCollision::Blocked)]))
+            .unwrap();
+        world.run_system_once(turn_back_from_closed_tiles).unwrap();
+        assert_eq!(wanderers.single(&world).unwrap().target, Some(origin));
+    }
+
+    #[test]
+    fn a_wanderer_keeps_within_its_radius() {
+        let map = CollisionMap::new(9, 1);
+        let mut rng = GameRng::new(7);
+        let mut wanderer = NpcMovement::wander((4, 0), Some(1));
+        let mut tile = (4, 0);
+        for _ in 0..200 {
+            let Some(to) = wanderer.next_tile(tile, &map, &mut rng) else { continue };
+            if map.can_step(tile, to) && wanderer.allows(to) {
+                tile = to;
+            }
+            assert!((3..=5).contains(&tile.0), "strayed to {tile:?}");
+        }
+    }
+
+    #[test]
+    fn a_patrol_goes_round_its_points_and_waits_for_the_player_to_move() {
+        // A 4x1 corridor, patrolled end to end, with the player in the way
+        // on the third tile at first.
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<GameRng>();
+        world.insert_resource(CollisionMap::new(4, 1));
+        let start = tile_to_world(0, 0, 4, 1);
+        let npc = world
+            .spawn((NpcMovement::patrol(vec![(0, 0), (3, 0)]), frames(), Transform::from_xyz(start.x, start.y, 1.0)))
+            .id();
+        let blocking = tile_to_world(2, 0, 4, 1) - logical_position(Vec2::ZERO);
+        let player = world.spawn((Player, Transform::from_xyz(blocking.x, blocking.y, 1.0))).id();
+
+        let mut tiles = Vec::new();
+        for frame in 0..600 {
+            if frame == 300 {
+                world.entity_mut(player).insert(Transform::from_xyz(0.0, 500.0, 1.0));
+            }
+            world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(1.0 / 60.0));
+            world.run_system_once(npc_movement).unwrap();
+            let position = world.get::<Transform>(npc).unwrap().translation.truncate();
+            let tile = world_to_tile(position, 4, 1);
+            if world.get::<NpcMovement>(npc).unwrap().target.is_none() && tiles.last() != Some(&tile) {
+                tiles.push(tile);
+            }
+            if frame == 299 {
+                assert_eq!(tiles.last(), Some(&(1, 0)), "never onto the player's tile");
+                assert_eq!(world.get::<CharacterFrames>(npc).unwrap().facing_row, NpcFacing::Right as u32);
+            }
+        }
+        assert_eq!(tiles[..7], [(0, 0), (1, 0), (2, 0), (3, 0), (2, 0), (1, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn npcs_never_share_a_tile() {
+        // A 3x1 corridor: a wanderer at one end, someone standing in the
+        // middle.
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<GameRng>();
+        world.insert_resource(CollisionMap::new(3, 1));
+        let at = |x: u32| {
+            let position = tile_to_world(x, 0, 3, 1);
+            Transform::from_xyz(position.x, position.y, 1.0)
+        };
+        let npc = |id: &str| Npc { id: id.into(), name: id.into(), sprite_facing: NpcFacing::Down, sprite_slot: 0 };
+        let wanderer = world.spawn((npc("doggo"), NpcMovement::wander((0, 0), None), frames(), at(0))).id();
+        let standing = world.spawn((npc("casey"), at(1))).id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems((track_npc_tiles, npc_movement).chain());
+        let tile_of = |world: &World| world_to_tile(world.get::<Transform>(wanderer).unwrap().translation.truncate(), 3, 1);
+
+        for _ in 0..600 {
+            world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(1.0 / 60.0));
+            schedule.run(&mut world);
+            assert_eq!(tile_of(&world), (0, 0), "the middle tile is taken");
+        }
+        assert_eq!(world.resource::<CollisionMap>().occupant(1, 0), Some(standing));
+
+        world.despawn(standing);
+        let mut moved = false;
+        for _ in 0..3600 {
+            world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(1.0 / 60.0));
+            schedule.run(&mut world);
+            if tile_of(&world) == (1, 0) {
+                moved = true;
+                break;
+            }
+        }
+        assert!(moved, "a despawned NPC's tile is free again");
+        assert_eq!(world.resource::<CollisionMap>().occupant(1, 0), Some(wanderer));
+    }
+
+    #[test]
+    fn nobody_moves_while_the_player_is_in_range() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<GameRng>();
+        world.insert_resource(CollisionMap::new(3, 1));
+        let start = tile_to_world(0, 0, 3, 1);
+        let npc = world
+            .spawn((
+                NpcMovement::patrol(vec![(2, 0)]),
+                frames(),
+                InRange,
+                Transform::from_xyz(start.x, start.y, 1.0),
+            ))
+            .id();
+        for _ in 0..120 {
+            world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(1.0 / 60.0));
+            world.run_system_once(npc_movement).unwrap();
+        }
+        assert_eq!(world.get::<Transform>(npc).unwrap().translation.truncate(), start);
+        assert!(!world.get::<NpcMovement>(npc).unwrap().is_stepping());
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::KeyValue;
+use crate::ambient::{BUBBLE_SECONDS, ChatterBubble, spawn_chatter_bubble};
+use crate::assets::GameAssets;
+use crate::character_sheet::STANDING_PATTERN;
+use crate::culling::Culled;
+use crate::game_state::Mode;
+use crate::group_conversation::{InGroupConversation, facing_toward};
+use crate::instrumentation::{MetricsBundle, init_metrics};
+use crate::npc::{Busy, CharacterFrames, Npc, PendingInteraction, spawn_emote};
+use crate::player::{Player, PlayerMovementSet, Velocity, is_sprinting};
+use crate::rng::GameRng;
+use crate::settings::GameSettings;
+
+/// NPCs notice the player running past: one within `REACTION_RANGE` of a
+/// sprinting player (`player::is_sprinting` - walking past never counts)
+/// turns to look the way the player is going and throws up a "!" for a
+/// moment. Now and then - `CHATTER_CHANCE`, at most once per
+/// `CHATTER_COOLDOWN_SECONDS` per NPC - it also says so in a chatter bubble
+/// (ambient.rs), unless `--ambient-chatter` is 0.
+///
+/// Each NPC keeps its own `ReactionCooldown`, added the first time it
+/// reacts, so running circles round one doesn't keep it startled. Only in
+/// `Mode::Exploring`, and never from an NPC that is `Busy` (mid-step, or
+/// held by a scripted scene), has a conversation queued behind it, is
+/// talking in a group, or is culled off screen.
+///
+/// There's no spatial index in this tree (see culling.rs), so this walks
+/// the map's NPCs - a handful per map - every frame the player runs.
+/// Every reaction bumps `game.npc.reactions`, labelled `reaction.type`:
+/// "startle" for the turn and emote, "chatter" for a line.
+pub struct NpcReactionsPlugin;
+
+impl Plugin for NpcReactionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>().init_resource::<GameRng>().add_systems(
+            Update,
+            (tick_reaction_cooldowns, react_to_sprinting_player)
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(Mode::Exploring)),
+        );
+        init_metrics::<ReactionMetrics>(app);
+    }
+}
+
+/// `game.npc.reactions`, by reaction type.
+#[derive(Resource)]
+pub struct ReactionMetrics {
+    pub reactions: Counter<u64>,
+}
+
+impl MetricsBundle for ReactionMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            reactions: meter
+                .u64_counter("game.npc.reactions")
+                .with_description("NPCs reacting to the player running past, by reaction type (see npc_reactions.rs)")
+                .build(),
+        }
+    }
+}
+
+/// How close, center to center, the player has to run by.
+pub const REACTION_RANGE: f32 = 48.0;
+
+/// How long an NPC that reacted ignores the player running by again.
+pub const REACTION_COOLDOWN_SECONDS: f32 = 4.0;
+
+/// How long the "!" stays up.
+pub const SURPRISE_SECONDS: f32 = 0.8;
+
+/// The odds a reaction comes with a line.
+pub const CHATTER_CHANCE: f32 = 0.5;
+
+/// How long after a line before that NPC may say another.
+pub const CHATTER_COOLDOWN_SECONDS: f32 = 30.0;
+
+pub const SPRINT_LINES: [&str; 3] = ["Whoa, where's the fire?", "Slow down!", "Someone's in a hurry."];
+
+/// When an NPC may react, and speak, again.
+#[derive(Component, Debug)]
+pub struct ReactionCooldown {
+    startle: Timer,
+    chatter: Option<Timer>,
+}
+
+impl ReactionCooldown {
+    fn new() -> Self {
+        Self { startle: Timer::from_seconds(REACTION_COOLDOWN_SECONDS, TimerMode::Once), chatter: None }
+    }
+
+    fn may_chatter(&self) -> bool {
+        self.chatter.as_ref().is_none_or(Timer::is_finished)
+    }
+}
+
+fn tick_reaction_cooldowns(time: Res<Time>, mut cooldowns: Query<&mut ReactionCooldown>) {
+    for mut cooldown in &mut cooldowns {
+        cooldown.startle.tick(time.delta());
+        if let Some(chatter) = &mut cooldown.chatter {
+            chatter.tick(time.delta());
+        }
+    }
+}
+
+fn react_to_sprinting_player(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    mut rng: ResMut<GameRng>,
+    player: Query<(&Transform, &Velocity), With<Player>>,
+    mut npcs: Query<
+        (Entity, &Transform, &mut CharacterFrames, &mut Sprite, Option<&mut ReactionCooldown>, Option<&Children>),
+        (With<Npc>, Without<Busy>, Without<Culled>, Without<Player>, Without<InGroupConversation>),
+    >,
+    bubbles: Query<(), With<ChatterBubble>>,
+    pending: Option<Res<PendingInteraction>>,
+    game_assets: Option<Res<GameAssets>>,
+    metrics: Option<Res<ReactionMetrics>>,
+) {
+    let Ok((player, velocity)) = player.single() else { return };
+    if !is_sprinting(velocity.0) {
+        return;
+    }
+    let player_pos = player.translation.truncate();
+    let rng = rng.stream("reactions");
+    let count = |reaction: &'static str| {
+        if let Some(metrics) = metrics.as_deref() {
+            metrics.reactions.add(1, &[KeyValue::new("reaction.type", reaction)]);
+        }
+    };
+
+    for (entity, transform, mut frames, mut sprite, cooldown, children) in &mut npcs {
+        if transform.translation.truncate().distance(player_pos) > REACTION_RANGE
+            || pending.as_ref().is_some_and(|pending| pending.npc == entity)
+            || cooldown.as_ref().is_some_and(|cooldown| !cooldown.startle.is_finished())
+        {
+            continue;
+        }
+
+        frames.facing_row = facing_toward(Vec2::ZERO, velocity.0) as u32;
+        frames.frame(STANDING_PATTERN).apply(&mut sprite);
+        spawn_emote(&mut commands, entity, "!", Some(SURPRISE_SECONDS));
+        count("startle");
+
+        let may_chatter = cooldown.as_ref().is_none_or(|cooldown| cooldown.may_chatter())
+            && settings.ambient_chatter > 0.0
+            && !children.is_some_and(|children| children.iter().any(|child| bubbles.contains(child)));
+        let chatter = may_chatter && rng.unit() < CHATTER_CHANCE;
+        if chatter {
+            let line = SPRINT_LINES[rng.below(SPRINT_LINES.len() as u64) as usize];
+            debug!("💬 {line}");
+            spawn_chatter_bubble(&mut commands, entity, line, game_assets.as_deref(), BUBBLE_SECONDS);
+            count("chatter");
+        }
+
+        let chatter_cooldown = || Timer::from_seconds(CHATTER_COOLDOWN_SECONDS, TimerMode::Once);
+        match cooldown {
+            Some(mut cooldown) => {
+                cooldown.startle.reset();
+                if chatter {
+                    cooldown.chatter = Some(chatter_cooldown());
+                }
+            }
+            None => {
+                let mut cooldown = ReactionCooldown::new();
+                cooldown.chatter = chatter.then(chatter_cooldown);
+                commands.entity(entity).insert(cooldown);
+            }
+        }
+    }
+}
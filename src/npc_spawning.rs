@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use opentelemetry::{KeyValue, trace::{Span as _, Tracer}};
+use crate::assets::GameAssets;
+use crate::content_errors::{ContentErrors, ContentMetrics};
+use crate::dialogue::{DialogueCompleted, DialogueOutcome};
+use crate::flags::GameFlags;
+use crate::game_state::GameState;
+use crate::instrumentation::GameTracer;
+use crate::map_data::NpcData;
+use crate::npc::{Npc, PendingInteraction};
+use crate::tilemap::{CollisionMap, Map, MapNpcs, spawn_npc_from_data};
+
+/// NPCs that come and go while a map is loaded. `SpawnNpcEvent` places one
+/// through the same path spawn_map uses (`tilemap::spawn_npc_from_data` -
+/// sprite lookup, body, wandering, broken-content fallback, spawn span);
+/// `DespawnNpcEvent` removes one by id (`Npc::id`).
+///
+/// Two content hooks drive them: a dialogue's `spawn_npc` outcome, which
+/// names an entry in the map's `spawnable` list, and `requires_flag` on a
+/// placed NPC, re-checked whenever `GameFlags` changes.
+///
+/// There is no NPC registry or tile occupancy map in this tree - NPCs are
+/// found by querying `Npc` for their id, and collision reads `NpcBody` carriers live
+/// (player.rs) - so despawning the entity is all the bookkeeping there is.
+/// Emotes and shadows are children and go with it; a conversation queued
+/// behind the NPC (`PendingInteraction`) is dropped here.
+pub struct NpcSpawningPlugin;
+
+impl Plugin for NpcSpawningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<SpawnNpcEvent>()
+            .add_message::<DespawnNpcEvent>()
+            .add_message::<DialogueCompleted>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameFlags>()
+            .init_resource::<ContentErrors>()
+            .add_systems(
+                Update,
+                (
+                    apply_spawn_outcomes,
+                    reconcile_flagged_npcs.run_if(resource_changed::<GameFlags>),
+                    despawn_requested_npcs,
+                    spawn_requested_npcs,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+        crate::map_data::init_dialogue_assets(app);
+        crate::instrumentation::init_metrics::<ContentMetrics>(app);
+    }
+}
+
+/// Spawn an NPC on the current map. `scene_scoped` NPCs carry `Map` and
+/// leave with the map like authored ones; unscoped NPCs stay until
+/// explicitly despawned (they follow no one, so mind where they stand).
+#[derive(Message, Debug, Clone)]
+pub struct SpawnNpcEvent {
+    pub data: NpcData,
+    pub scene_scoped: bool,
+}
+
+/// Remove every NPC with this `Npc::id`.
+#[derive(Message, Debug, Clone)]
+pub struct DespawnNpcEvent {
+    pub id: String,
+}
+
+/// `spawn_npc` dialogue outcomes, resolved against the map's `spawnable`
+/// list by id - or name, as content written before ids says it. One the
+/// map doesn't offer is a content bug, so it's recorded.
+fn apply_spawn_outcomes(
+    mut completions: MessageReader<DialogueCompleted>,
+    map_npcs: Option<Res<MapNpcs>>,
+    mut spawns: MessageWriter<SpawnNpcEvent>,
+    mut content_errors: ResMut<ContentErrors>,
+    time: Res<Time>,
+    content_metrics: Option<Res<ContentMetrics>>,
+) {
+    for completed in completions.read() {
+        for outcome in &completed.outcomes {
+            let DialogueOutcome::SpawnNpc(name) = outcome else { continue };
+            let Some(map_npcs) = &map_npcs else { continue };
+            match map_npcs.spawnable.iter().find(|npc| npc.id() == *name || npc.name == *name) {
+                Some(data) => {
+                    spawns.write(SpawnNpcEvent { data: data.clone(), scene_scoped: true });
+                }
+                None => content_errors.record(
+                    &map_npcs.path,
+                    format!("dialogue outcome spawns {name:?}, which is not in this map's spawnable NPCs"),
+                    time.elapsed(),
+                    content_metrics.as_deref(),
+                ),
+            }
+        }
+    }
+}
+
+/// Bring placed NPCs in line with the flags: spawn the ones whose flag is
+/// now set, despawn the ones whose flag was cleared.
+fn reconcile_flagged_npcs(
+    flags: Res<GameFlags>,
+    map_npcs: Option<Res<MapNpcs>>,
+    npcs: Query<&Npc>,
+    mut spawns: MessageWriter<SpawnNpcEvent>,
+    mut despawns: MessageWriter<DespawnNpcEvent>,
+) {
+    let Some(map_npcs) = map_npcs else { return };
+    for data in map_npcs.placed.iter().filter(|npc| npc.requires_flag.is_some()) {
+        let id = data.id();
+        let present = npcs.iter().any(|npc| npc.id == id);
+        match (flags.allows(data), present) {
+            (true, false) => {
+                spawns.write(SpawnNpcEvent { data: data.clone(), scene_scoped: true });
+            }
+            (false, true) => {
+                despawns.write(DespawnNpcEvent { id });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn despawn_requested_npcs(
+    mut commands: Commands,
+    mut requests: MessageReader<DespawnNpcEvent>,
+    npcs: Query<(Entity, &Npc)>,
+    pending: Option<Res<PendingInteraction>>,
+    tracer: Option<Res<GameTracer>>,
+) {
+    for request in requests.read() {
+        let mut despawned = 0;
+        for (entity, npc) in npcs.iter().filter(|(_, npc)| npc.id == request.id) {
+            if let Some(pending) = &pending
+                && pending.npc == entity
+            {
+                commands.entity(pending.emote).try_despawn();
+                commands.remove_resource::<PendingInteraction>();
+            }
+            commands.entity(entity).despawn();
+            info!("👤 NPC despawned: {}", npc.name);
+            despawned += 1;
+        }
+        if despawned == 0 {
+            warn!("DespawnNpcEvent for {:?}, but no such NPC is here", request.id);
+        }
+        if let Some(t) = tracer.as_deref() {
+            let mut span = t.tracer().start("npc.despawned");
+            span.set_attribute(KeyValue::new("npc.id", request.id.clone()));
+            span.set_attribute(KeyValue::new("npc.count", despawned as i64));
+            span.end();
+        }
+    }
+}
+
+fn spawn_requested_npcs(
+    mut commands: Commands,
+    mut requests: MessageReader<SpawnNpcEvent>,
+    npcs: Query<&Npc>,
+    game_assets: Res<GameAssets>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    collision_map: Option<Res<CollisionMap>>,
+    map_npcs: Option<Res<MapNpcs>>,
+    mut content_errors: ResMut<ContentErrors>,
+    time: Res<Time>,
+    content_metrics: Option<Res<ContentMetrics>>,
+    tracer: Option<Res<GameTracer>>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    let mut spawned_now: Vec<String> = Vec::new();
+    for request in requests.read() {
+        let name = request.data.name.as_str();
+        let id = request.data.id();
+        // Ids are how NPCs are found (despawn, flags, traces), so two with
+        // one id on a map would be ambiguous - and a repeated conversation
+        // would otherwise stack vendors.
+        if spawned_now.contains(&id) || npcs.iter().any(|npc| npc.id == id) {
+            info!("NPC {name} is already here - not spawning another");
+            continue;
+        }
+        let Some(collision_map) = &collision_map else {
+            warn!("SpawnNpcEvent for {name} with no map loaded - ignored");
+            continue;
+        };
+        let source = map_npcs
+            .as_ref()
+            .map_or_else(|| "runtime spawn".to_string(), |map_npcs| map_npcs.path.clone());
+        let Some(entity) = spawn_npc_from_data(
+            &mut commands,
+            &game_assets,
+            &mut texture_atlas_layouts,
+            &request.data,
+            (collision_map.width, collision_map.height),
+            &source,
+            &mut content_errors,
+            time.elapsed(),
+            content_metrics.as_deref(),
+            tracer.as_deref(),
+            asset_server.as_deref(),
+        ) else {
+            continue;
+        };
+        if request.scene_scoped {
+            commands.entity(entity).insert(Map);
+        }
+        spawned_now.push(id);
+    }
+}
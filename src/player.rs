@@ -1,8 +1,11 @@
 use bevy::prelude::*;
-use crate::game_state::{GameState, Mode};
+use crate::game_state::{GameState, GameStatePlugin, Mode};
 use crate::tilemap::CollisionMap;
 use crate::assets::GameAssets;
+use crate::character_sheet::SheetOptions;
 use crate::instrumentation::{GameTracer, PlayerSessionTrace};
+use opentelemetry::{KeyValue, trace::Span as _};
+use std::collections::VecDeque;
 
 pub struct PlayerPlugin;
 
@@ -20,12 +23,19 @@ impl Plugin for PlayerPlugin {
             .register_type::<Velocity>()
             .register_type::<Facing>()
             .add_message::<BumpedIntoTile>()
+            .init_resource::<GameAssets>()
             .add_systems(OnEnter(GameState::Playing), spawn_player)
+            .add_systems(OnExit(GameState::Playing), despawn_player)
             .add_systems(Update, (
                 player_movement_input,
                 apply_movement,
                 animate_player,
             ).chain().in_set(PlayerMovementSet).run_if(in_state(Mode::Exploring)));
+        crate::input::init_game_input(app);
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<GameStatePlugin>(app, "PlayerPlugin");
     }
 }
 
@@ -33,11 +43,23 @@ impl Plugin for PlayerPlugin {
 #[reflect(Component)]
 pub struct Player;
 
+/// A route the player walks on their own, one waypoint at a time - tile
+/// centers, reached by `logical_position`. Set by click_to_talk.rs; a
+/// movement key takes the controls back and drops it, and it goes once
+/// the last waypoint is reached.
+#[derive(Component, Debug, Default)]
+pub struct AutoWalk {
+    pub waypoints: VecDeque<Vec2>,
+}
+
+/// How close to a waypoint counts as there: under a frame's walk.
+const WAYPOINT_REACHED: f32 = 4.0;
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct Velocity(pub Vec2);
 
-#[derive(Component, Default, Reflect)]
+#[derive(Component, Default, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
 #[reflect(Component)]
 pub enum Facing {
     #[default]
@@ -68,11 +90,28 @@ impl Facing {
             Facing::Up => (0, -1),
         }
     }
+
+    /// The way the player looks, in world space (+y is up).
+    pub fn direction(&self) -> Vec2 {
+        match self {
+            Facing::Down => Vec2::NEG_Y,
+            Facing::Left => Vec2::NEG_X,
+            Facing::Right => Vec2::X,
+            Facing::Up => Vec2::Y,
+        }
+    }
 }
 
+/// The player's sheet, by filename stem (`GameAssets::sheet_options`).
+pub const PLAYER_SHEET: &str = "Amy-Walking";
+
 /// Amy's slot in Amy-Walking.png (Actors.json: actor 1, characterIndex 0).
 const AMY_SLOT: u32 = 0;
 
+/// The player's name as a dialogue `speaker`: lines spoken as it are the
+/// player's own (see dialogue.rs's `speaker_name_color`).
+pub const PLAYER_NAME: &str = "Amy";
+
 #[derive(Component)]
 pub struct AnimationState {
     pub frame_timer: Timer,
@@ -90,7 +129,16 @@ impl Default for AnimationState {
     }
 }
 
-const PLAYER_SPEED: f32 = 150.0;
+pub const PLAYER_SPEED: f32 = 150.0;
+
+/// Holding Run (`GameAction::Sprint`, Shift).
+pub const SPRINT_SPEED: f32 = 240.0;
+
+/// Whether `velocity` is a run rather than a walk - for the NPCs who
+/// notice (npc_reactions.rs).
+pub fn is_sprinting(velocity: Vec2) -> bool {
+    velocity.length() > (PLAYER_SPEED + SPRINT_SPEED) / 2.0
+}
 
 /// Emitted when the player tries to walk into a collision-blocked tile.
 /// RPGMaker's "Player Touch" trigger fires on exactly this bump - the
@@ -108,6 +156,8 @@ fn spawn_player(
     game_assets: Res<GameAssets>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     tracer: Option<Res<GameTracer>>,
+    rng: Option<Res<crate::rng::GameRng>>,
+    profile: Option<Res<crate::telemetry::SystemProfile>>,
     existing_players: Query<Entity, With<Player>>,
 ) {
     // Debug assertion: check for existing players before spawning
@@ -119,11 +169,22 @@ fn spawn_player(
         }
     }
     let texture = game_assets.player_sprite.clone();
+    let sheet = game_assets.sheet_options(PLAYER_SHEET);
+    let frame = sheet.frame(AMY_SLOT, Facing::default().sprite_row(), crate::character_sheet::STANDING_PATTERN);
 
-    let atlas_layout = texture_atlas_layouts.add(crate::character_sheet::sheet_layout());
+    let atlas_layout = texture_atlas_layouts.add(sheet.grid.layout(UVec2::splat(crate::character_sheet::FRAME_SIZE)));
 
     // Create session trace for this play session (if telemetry is enabled)
-    let session_trace = tracer.as_ref().map(|t| PlayerSessionTrace::new(t));
+    let mut session_trace = tracer.as_ref().map(|t| PlayerSessionTrace::new(t));
+    // The seed makes the session replayable (`--seed`, see rng.rs). A
+    // string: the span's integers are signed and a seed is any u64.
+    if let (Some(trace), Some(rng)) = (&mut session_trace, &rng) {
+        trace.span.set_attribute(KeyValue::new("game.seed", rng.seed().to_string()));
+    }
+    // The machine, GPU included, which the providers' resources can't say.
+    if let (Some(trace), Some(profile)) = (&mut session_trace, &profile) {
+        trace.span.add_event("system.profile", profile.attributes());
+    }
 
     if let Some(ref trace) = session_trace {
         info!("🎮 Player session started - trace ID: {:?}", trace.span_context().trace_id());
@@ -137,17 +198,17 @@ fn spawn_player(
         Facing::default(),
         AnimationState::default(),
         crate::depth::YSorted { foot_offset: -24.0 },
-        Sprite::from_atlas_image(
-            texture,
-            TextureAtlas {
-                layout: atlas_layout,
-                index: crate::character_sheet::atlas_index(
-                    AMY_SLOT,
-                    Facing::default().sprite_row(),
-                    crate::character_sheet::STANDING_PATTERN,
-                ) as usize,
-            },
-        ),
+        sheet,
+        Sprite {
+            flip_x: frame.flip_x,
+            ..Sprite::from_atlas_image(
+                texture,
+                TextureAtlas {
+                    layout: atlas_layout,
+                    index: frame.index as usize,
+                },
+            )
+        },
         Transform::from_xyz(0.0, 0.0, 1.0),
     ));
 
@@ -159,12 +220,28 @@ fn spawn_player(
     info!("Player (Amy) spawned at origin");
 }
 
+/// Leaving Playing ends the play session, span and all; re-entering it
+/// (game_state::NewGameRequest) spawns the next one's player.
+fn despawn_player(mut commands: Commands, mut players: Query<(Entity, Option<&mut PlayerSessionTrace>), With<Player>>) {
+    for (entity, trace) in &mut players {
+        if let Some(mut trace) = trace {
+            info!("🎮 Player session ended - trace ID: {:?}", trace.span_context().trace_id());
+            trace.span.end();
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
 fn player_movement_input(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    keyboard: crate::input::GameInput,
     departing: Option<Res<crate::transitions::DepartingDoor>>,
-    mut query: Query<(&mut Velocity, &mut Facing, &mut AnimationState), With<Player>>,
+    mut query: Query<
+        (Entity, &Transform, &mut Velocity, &mut Facing, &mut AnimationState, Option<&mut AutoWalk>),
+        With<Player>,
+    >,
 ) {
-    let Ok((mut velocity, mut facing, mut anim_state)) = query.single_mut() else {
+    let Ok((entity, transform, mut velocity, mut facing, mut anim_state, auto_walk)) = query.single_mut() else {
         return;
     };
 
@@ -191,8 +268,30 @@ fn player_movement_input(
         direction.x += 1.0;
     }
 
+    if direction != Vec2::ZERO {
+        if auto_walk.is_some() {
+            commands.entity(entity).remove::<AutoWalk>();
+        }
+    } else if let Some(mut auto_walk) = auto_walk {
+        let position = logical_position(transform.translation.truncate());
+        while auto_walk.waypoints.front().is_some_and(|waypoint| waypoint.distance(position) < WAYPOINT_REACHED) {
+            auto_walk.waypoints.pop_front();
+        }
+        match auto_walk.waypoints.front() {
+            Some(waypoint) => direction = *waypoint - position,
+            None => {
+                commands.entity(entity).remove::<AutoWalk>();
+            }
+        }
+    }
+
     if direction.length_squared() > 0.0 {
-        velocity.0 = direction.normalize() * PLAYER_SPEED;
+        let speed = if keyboard.pressed(crate::input::GameAction::Sprint.default_key()) {
+            SPRINT_SPEED
+        } else {
+            PLAYER_SPEED
+        };
+        velocity.0 = direction.normalize() * speed;
         anim_state.is_moving = true;
 
         if direction.y.abs() > direction.x.abs() {
@@ -211,10 +310,17 @@ fn apply_movement(
     time: Res<Time>,
     collision_map: Option<Res<CollisionMap>>,
     mut query: Query<(&Velocity, &mut Transform), With<Player>>,
-    npcs: Query<&Transform, (With<crate::npc::NpcBody>, Without<Player>)>,
+    npcs: Query<(&Transform, Option<&crate::npc_movement::NpcMovement>), (With<crate::npc::NpcBody>, Without<Player>)>,
     mut bumps: MessageWriter<BumpedIntoTile>,
 ) {
-    let npc_centers: Vec<Vec2> = npcs.iter().map(|t| t.translation.truncate()).collect();
+    // A walker's body is on the tile it's stepping to as well as where it
+    // is: that tile is reserved for it from the moment it sets off.
+    let npc_centers: Vec<Vec2> = npcs
+        .iter()
+        .flat_map(|(transform, movement)| {
+            std::iter::once(transform.translation.truncate()).chain(movement.and_then(|movement| movement.destination()))
+        })
+        .collect();
 
     for (velocity, mut transform) in &mut query {
         if velocity.0.length_squared() == 0.0 {
@@ -356,17 +462,13 @@ fn try_axis_move(
 
 fn animate_player(
     time: Res<Time>,
-    mut query: Query<(&mut AnimationState, &Facing, &mut Sprite), With<Player>>,
+    mut query: Query<(&mut AnimationState, &Facing, &SheetOptions, &mut Sprite), With<Player>>,
 ) {
-    for (mut anim_state, facing, mut sprite) in &mut query {
+    for (mut anim_state, facing, sheet, mut sprite) in &mut query {
         if !anim_state.is_moving {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = crate::character_sheet::atlas_index(
-                    AMY_SLOT,
-                    facing.sprite_row(),
-                    crate::character_sheet::STANDING_PATTERN,
-                ) as usize;
-            }
+            sheet
+                .frame(AMY_SLOT, facing.sprite_row(), crate::character_sheet::STANDING_PATTERN)
+                .apply(&mut sprite);
             continue;
         }
 
@@ -378,13 +480,8 @@ fn animate_player(
             // a stutter (kaibo review 2026-07-12).
             anim_state.current_frame = (anim_state.current_frame + 1) % 4;
 
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = crate::character_sheet::atlas_index(
-                    AMY_SLOT,
-                    facing.sprite_row(),
-                    crate::npc::step_pattern(anim_state.current_frame as u8),
-                ) as usize;
-            }
+            let pattern = crate::npc::step_pattern(anim_state.current_frame as u8);
+            sheet.frame(AMY_SLOT, facing.sprite_row(), pattern).apply(&mut sprite);
         }
     }
 }
@@ -0,0 +1,339 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::content_errors::{ContentErrors, ContentMetrics};
+use crate::content_pack::{ContentPacks, data_file};
+
+/// Animated portraits: a sheet declared in `assets/data/portraits.json`
+/// (keyed by portrait name, the same name map data uses) blinks now and
+/// then while idle and flaps its mouth while the typewriter is revealing a
+/// line. The dialogue box plays it (dialogue.rs); a portrait without an
+/// entry is a plain face sheet cell, as before.
+///
+/// ```json
+/// "Amy": {
+///   "columns": 4, "rows": 2,
+///   "frame_seconds": 0.1,
+///   "blink_every_seconds": 4.0,
+///   "animations": {
+///     "idle": { "frames": [0], "blink": [4] },
+///     "talking": { "frames": [0, 1, 2, 1] }
+///   }
+/// }
+/// ```
+///
+/// Frames count left to right, top to bottom through the grid; an animated
+/// portrait ignores the dialogue's `face_index`. `cell` defaults to the
+/// 144x144 face sheet cell, `frame_seconds` to 0.12 and
+/// `blink_every_seconds` to 4.
+pub struct PortraitPlugin;
+
+impl Plugin for PortraitPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Portraits>()
+            .init_resource::<ContentErrors>()
+            .add_systems(Startup, load_portraits);
+        crate::instrumentation::init_metrics::<ContentMetrics>(app);
+    }
+}
+
+/// Under assets/, or a content pack (see content_pack.rs).
+const PORTRAITS_FILE: &str = "data/portraits.json";
+const PORTRAITS_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/portraits.json"));
+
+/// One animated portrait, validated.
+#[derive(Debug)]
+pub struct PortraitSheet {
+    /// The sheet's own grid; made by `load_portraits`.
+    pub layout: Handle<TextureAtlasLayout>,
+    cell: UVec2,
+    columns: u32,
+    rows: u32,
+    frame: Duration,
+    blink_every: Duration,
+    idle: Vec<u32>,
+    blink: Vec<u32>,
+    talking: Vec<u32>,
+}
+
+impl PortraitSheet {
+    /// What the portrait shows before its first tick.
+    pub fn first_frame(&self) -> u32 {
+        self.idle[0]
+    }
+}
+
+/// Every animated portrait, by name.
+#[derive(Resource, Default, Debug)]
+pub struct Portraits {
+    sheets: HashMap<String, Arc<PortraitSheet>>,
+}
+
+impl Portraits {
+    /// The sheet for a portrait asset path ("textures/portraits/Amy.png"),
+    /// if that portrait is animated.
+    pub fn for_path(&self, asset_path: &str) -> Option<Arc<PortraitSheet>> {
+        let name = std::path::Path::new(asset_path).file_stem()?.to_str()?;
+        self.sheets.get(name).cloned()
+    }
+}
+
+#[derive(Deserialize)]
+struct PortraitData {
+    #[serde(default = "default_cell")]
+    cell: [u32; 2],
+    columns: u32,
+    rows: u32,
+    #[serde(default = "default_frame_seconds")]
+    frame_seconds: f32,
+    #[serde(default = "default_blink_every_seconds")]
+    blink_every_seconds: f32,
+    animations: HashMap<String, AnimationData>,
+}
+
+#[derive(Deserialize)]
+struct AnimationData {
+    frames: Vec<u32>,
+    /// Idle only: played once every `blink_every_seconds`.
+    #[serde(default)]
+    blink: Vec<u32>,
+}
+
+fn default_cell() -> [u32; 2] {
+    [144, 144]
+}
+
+fn default_frame_seconds() -> f32 {
+    0.12
+}
+
+fn default_blink_every_seconds() -> f32 {
+    4.0
+}
+
+/// Parse and validate a portraits file. Every bad entry is reported; the
+/// good ones still load (with a default layout handle - see
+/// `load_portraits`).
+fn parse_portraits(json: &str) -> (HashMap<String, PortraitSheet>, Vec<String>) {
+    let data: HashMap<String, PortraitData> = match serde_json::from_str(json) {
+        Ok(data) => data,
+        Err(e) => return (HashMap::new(), vec![format!("Failed to parse portraits: {e}")]),
+    };
+
+    let mut sheets = HashMap::new();
+    let mut problems = Vec::new();
+    for (name, mut entry) in data {
+        let (Some(idle), Some(talking)) = (entry.animations.remove("idle"), entry.animations.remove("talking")) else {
+            problems.push(format!("portrait {name:?} needs both an idle and a talking animation"));
+            continue;
+        };
+        if idle.frames.is_empty() || talking.frames.is_empty() {
+            problems.push(format!("portrait {name:?} has an animation with no frames"));
+            continue;
+        }
+        let cells = entry.columns * entry.rows;
+        if let Some(frame) = [&idle.frames, &idle.blink, &talking.frames].into_iter().flatten().find(|&&frame| frame >= cells) {
+            problems.push(format!("portrait {name:?} frame {frame} is outside its {}x{} grid", entry.columns, entry.rows));
+            continue;
+        }
+        if !(entry.frame_seconds > 0.0 && entry.blink_every_seconds > 0.0) {
+            problems.push(format!("portrait {name:?} frame_seconds and blink_every_seconds must be positive"));
+            continue;
+        }
+        sheets.insert(name, PortraitSheet {
+            layout: Handle::default(),
+            cell: UVec2::from(entry.cell),
+            columns: entry.columns,
+            rows: entry.rows,
+            frame: Duration::from_secs_f32(entry.frame_seconds),
+            blink_every: Duration::from_secs_f32(entry.blink_every_seconds),
+            idle: idle.frames,
+            blink: idle.blink,
+            talking: talking.frames,
+        });
+    }
+    problems.sort();
+    (sheets, problems)
+}
+
+fn load_portraits(
+    mut portraits: ResMut<Portraits>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut content_errors: ResMut<ContentErrors>,
+    content_metrics: Option<Res<ContentMetrics>>,
+    packs: Option<Res<ContentPacks>>,
+) {
+    let (path, json) = data_file(packs.as_deref(), PORTRAITS_FILE, PORTRAITS_JSON);
+    // A pack's copy that can't be read leaves the shipped portraits.
+    let json = json.unwrap_or_else(|e| {
+        content_errors.record(&path, format!("{e:#}"), Duration::ZERO, content_metrics.as_deref());
+        PORTRAITS_JSON.into()
+    });
+    let (sheets, problems) = parse_portraits(&json);
+    for problem in problems {
+        content_errors.record(&path, problem, Duration::ZERO, content_metrics.as_deref());
+    }
+    info!("🖼️ Loaded {} animated portraits", sheets.len());
+    portraits.sheets = sheets
+        .into_iter()
+        .map(|(name, mut sheet)| {
+            sheet.layout = layouts.add(TextureAtlasLayout::from_grid(sheet.cell, sheet.columns, sheet.rows, None, None));
+            (name, Arc::new(sheet))
+        })
+        .collect();
+}
+
+/// Playback state for the dialogue portrait. Always on the portrait node;
+/// does nothing while the current portrait isn't animated.
+#[derive(Component, Default, Debug)]
+pub struct PortraitAnimation {
+    sheet: Option<Arc<PortraitSheet>>,
+    /// Time toward the next animation frame.
+    frame_elapsed: Duration,
+    idle_frame: usize,
+    talking_frame: usize,
+    /// Time toward the next blink. Counts while talking too, so a line
+    /// finishing doesn't restart it - the blink lands when it would have.
+    blink_elapsed: Duration,
+    /// Mid-blink: which blink frame.
+    blink_frame: Option<usize>,
+}
+
+impl PortraitAnimation {
+    /// Play `sheet` (None: a static portrait). Staying on the same sheet
+    /// between boxes keeps the playback, blink timer included.
+    pub fn set_sheet(&mut self, sheet: Option<Arc<PortraitSheet>>) {
+        let same = match (&self.sheet, &sheet) {
+            (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            *self = Self { sheet, ..default() };
+        }
+    }
+
+    /// Advance by `delta`; the frame to show, if animated.
+    pub fn tick(&mut self, delta: Duration, talking: bool) -> Option<u32> {
+        let sheet = self.sheet.clone()?;
+        self.frame_elapsed += delta;
+        while self.frame_elapsed >= sheet.frame {
+            self.frame_elapsed -= sheet.frame;
+            if talking {
+                self.talking_frame = (self.talking_frame + 1) % sheet.talking.len();
+            } else {
+                self.idle_frame = (self.idle_frame + 1) % sheet.idle.len();
+            }
+            if let Some(frame) = self.blink_frame {
+                self.blink_frame = Some(frame + 1).filter(|&next| next < sheet.blink.len());
+            }
+        }
+        if !talking {
+            self.talking_frame = 0;
+        }
+
+        self.blink_elapsed += delta;
+        if self.blink_elapsed >= sheet.blink_every {
+            self.blink_elapsed -= sheet.blink_every;
+            if !sheet.blink.is_empty() {
+                self.blink_frame = Some(0);
+            }
+        }
+
+        Some(if talking {
+            sheet.talking[self.talking_frame]
+        } else if let Some(frame) = self.blink_frame {
+            sheet.blink[frame]
+        } else {
+            sheet.idle[self.idle_frame]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMY: &str = r#"{
+        "Amy": {
+            "columns": 4, "rows": 2,
+            "frame_seconds": 0.125,
+            "blink_every_seconds": 1.0,
+            "animations": {
+                "idle": { "frames": [0], "blink": [4, 5] },
+                "talking": { "frames": [1, 2] }
+            }
+        }
+    }"#;
+
+    fn amy() -> PortraitAnimation {
+        let (mut sheets, problems) = parse_portraits(AMY);
+        assert!(problems.is_empty(), "{problems:?}");
+        let mut animation = PortraitAnimation::default();
+        animation.set_sheet(sheets.remove("Amy").map(Arc::new));
+        animation
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn shipped_portraits_are_valid() {
+        let (_, problems) = parse_portraits(PORTRAITS_JSON);
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn invalid_portraits_are_reported_and_skipped() {
+        let (sheets, problems) = parse_portraits(r#"{
+            "ok": { "columns": 1, "rows": 1, "animations": {
+                "idle": { "frames": [0] }, "talking": { "frames": [0] } } },
+            "mute": { "columns": 1, "rows": 1, "animations": { "idle": { "frames": [0] } } },
+            "off_grid": { "columns": 4, "rows": 2, "animations": {
+                "idle": { "frames": [0], "blink": [8] }, "talking": { "frames": [1] } } },
+            "frozen": { "columns": 1, "rows": 1, "frame_seconds": 0, "animations": {
+                "idle": { "frames": [0] }, "talking": { "frames": [0] } } }
+        }"#);
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].contains("frozen"));
+        assert!(problems[1].contains("mute"));
+        assert!(problems[2].contains("off_grid"));
+        assert_eq!(sheets.keys().collect::<Vec<_>>(), ["ok"]);
+        assert_eq!(sheets["ok"].cell, UVec2::splat(144), "cells default to face sheet cells");
+    }
+
+    #[test]
+    fn talking_loops_while_the_line_types_and_idle_blinks() {
+        let mut animation = amy();
+        assert_eq!(animation.tick(ms(50), false), Some(0));
+        assert_eq!(animation.tick(ms(125), true), Some(2));
+        assert_eq!(animation.tick(ms(125), true), Some(1), "talking loops");
+        assert_eq!(animation.tick(ms(125), false), Some(0));
+
+        // 425ms in: the blink lands at 1s and plays a frame each.
+        assert_eq!(animation.tick(ms(575), false), Some(4));
+        assert_eq!(animation.tick(ms(125), false), Some(5));
+        assert_eq!(animation.tick(ms(125), false), Some(0));
+    }
+
+    #[test]
+    fn a_line_ending_does_not_restart_the_blink_timer() {
+        let mut animation = amy();
+        // Talk through most of the blink interval, then go idle: the
+        // blink is due at 1s as if the line had never happened.
+        animation.tick(ms(900), true);
+        assert_eq!(animation.tick(ms(50), false), Some(0));
+        assert_eq!(animation.tick(ms(50), false), Some(4));
+
+        // Same sheet again (the next box): playback carries on.
+        let sheet = animation.sheet.clone();
+        animation.set_sheet(sheet);
+        assert_eq!(animation.blink_frame, Some(0));
+
+        animation.set_sheet(None);
+        assert_eq!(animation.tick(ms(1000), false), None, "static portraits aren't animated");
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+use std::collections::HashMap;
+use std::time::Duration;
+use web_time::Instant;
+use crate::game_state::{GameState, Mode, Scene};
+use crate::map_data::{MapData, scene_from_str, world_to_tile};
+use crate::player::Player;
+use crate::content_pack::ContentPacks;
+use crate::tilemap::{CollisionMap, MapExits, load_scene_map};
+use crate::transitions::PendingTransferAfterDialogue;
+
+/// Reads the next map before the player gets there. Entering a scene used
+/// to read and parse its JSON on the transition frame; now, when the player
+/// comes within `PRELOAD_RADIUS_TILES` of a portal - or a scripted scene
+/// that ends in a transfer starts playing - the destination's map is parsed
+/// on the async compute pool into `PreparedScenes`, and spawn_map (tilemap.rs)
+/// takes it from there, leaving only entity spawning for that frame. Tileset
+/// and character textures are already resident - assets.rs loads them at
+/// startup - except lazy ones (`assets/data/assets.json`), which start
+/// loading as the scene spawns.
+///
+/// The `map.transition` span says which kind each transition was
+/// (`map.preloaded`), with `map.load_ms` (on the transition frame) and,
+/// when preloaded, `map.preload_ms` (in the background).
+pub struct PreloadPlugin;
+
+impl Plugin for PreloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PreparedScenes>().add_systems(
+            Update,
+            (
+                preload_near_exits.run_if(in_state(Mode::Exploring)),
+                preload_pending_transfer,
+                collect_preloaded_maps,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// How close (in tiles, either axis) the player has to be to an exit
+/// before its destination starts loading.
+pub const PRELOAD_RADIUS_TILES: i32 = 3;
+
+/// One destination, read and parsed. A map that failed to load is kept as
+/// the error, so spawn_map reports it exactly as a cold load would.
+pub struct PreparedScene {
+    pub loaded: anyhow::Result<MapData>,
+    pub path: String,
+    /// Request to ready, in the background.
+    pub prepared_in: Duration,
+}
+
+/// Destinations being read (`loading`) or ready to enter (`ready`). Kept
+/// until entered, so walking past a door and back doesn't read it twice.
+#[derive(Resource, Default)]
+pub struct PreparedScenes {
+    loading: HashMap<Scene, Task<PreparedScene>>,
+    ready: HashMap<Scene, PreparedScene>,
+}
+
+impl PreparedScenes {
+    /// Start reading `scene`'s map unless it's already read or being read.
+    pub fn request(&mut self, scene: Scene, map_directory: Option<std::path::PathBuf>, content_packs: Option<ContentPacks>) {
+        if self.loading.contains_key(&scene) || self.ready.contains_key(&scene) {
+            return;
+        }
+        debug!("🧳 Preloading {scene:?}");
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let started = Instant::now();
+            let (loaded, path) = load_scene_map(scene, map_directory.as_deref(), content_packs.as_ref());
+            PreparedScene { loaded, path, prepared_in: started.elapsed() }
+        });
+        self.loading.insert(scene, task);
+    }
+
+    pub fn is_ready(&self, scene: Scene) -> bool {
+        self.ready.contains_key(&scene)
+    }
+
+    /// The prepared map for `scene`, if it finished in time. Still-loading
+    /// work is dropped: entering now reads the map cold anyway.
+    pub fn take(&mut self, scene: Scene) -> Option<PreparedScene> {
+        self.loading.remove(&scene);
+        self.ready.remove(&scene)
+    }
+}
+
+fn preload_near_exits(
+    player: Query<&Transform, With<Player>>,
+    map_exits: Option<Res<MapExits>>,
+    collision_map: Option<Res<CollisionMap>>,
+    scene: Res<State<Scene>>,
+    #[cfg(not(target_arch = "wasm32"))] map_directory: Option<Res<crate::map_data::MapDirectory>>,
+    content_packs: Option<Res<ContentPacks>>,
+    mut prepared: ResMut<PreparedScenes>,
+) {
+    let (Ok(transform), Some(map_exits), Some(collision_map)) = (player.single(), map_exits, collision_map) else {
+        return;
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let map_directory = map_directory.map(|dir| dir.0.clone());
+    #[cfg(target_arch = "wasm32")]
+    let map_directory = None;
+    let (tile_x, tile_y) = world_to_tile(
+        crate::player::logical_position(transform.translation.truncate()),
+        collision_map.width,
+        collision_map.height,
+    );
+    for exit in &map_exits.0 {
+        let near = (exit.trigger_x as i32 - tile_x).abs() <= PRELOAD_RADIUS_TILES
+            && (exit.trigger_y as i32 - tile_y).abs() <= PRELOAD_RADIUS_TILES;
+        if let Some(target) = scene_from_str(&exit.target_scene).filter(|&target| near && target != *scene.get()) {
+            prepared.request(target, map_directory.clone(), content_packs.as_deref().cloned());
+        }
+    }
+}
+
+/// A scripted scene that ends in a transfer has the whole conversation to
+/// load its destination.
+fn preload_pending_transfer(
+    pending: Option<Res<PendingTransferAfterDialogue>>,
+    #[cfg(not(target_arch = "wasm32"))] map_directory: Option<Res<crate::map_data::MapDirectory>>,
+    content_packs: Option<Res<ContentPacks>>,
+    mut prepared: ResMut<PreparedScenes>,
+) {
+    let Some(pending) = pending.filter(|pending| pending.is_added()) else {
+        return;
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let map_directory = map_directory.map(|dir| dir.0.clone());
+    #[cfg(target_arch = "wasm32")]
+    let map_directory = None;
+    prepared.request(pending.target_scene, map_directory, content_packs.as_deref().cloned());
+}
+
+fn collect_preloaded_maps(mut prepared: ResMut<PreparedScenes>) {
+    let prepared = &mut *prepared;
+    prepared.loading.retain(|&scene, task| match check_ready(task) {
+        Some(done) => {
+            debug!("🧳 {scene:?} ready in {:?}", done.prepared_in);
+            prepared.ready.insert(scene, done);
+            false
+        }
+        None => true,
+    });
+}
@@ -0,0 +1,291 @@
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use crate::assets::GameAssets;
+use crate::game_state::Mode;
+use crate::glyphs::InputPrompt;
+use crate::input::GameInput;
+use crate::save::{SaveDirectory, SaveSlot, SaveSnapshot, UnsavedChanges, write_save};
+use crate::toast::ShowToast;
+
+/// Quitting without losing progress. The window's close button - and
+/// `QuitRequested`, which the pause menu's Quit will write once there is
+/// one - quits at once when nothing has changed since the last save
+/// (`UnsavedChanges`, save.rs). Otherwise it asks, over the paused game
+/// (`Mode::Menu`): save to the autosave and quit, quit without saving, or
+/// cancel. Closing the window again while it asks is the answer: save and
+/// quit.
+///
+/// The question waits for whatever else has the screen - a conversation,
+/// another menu - to close; the dashboard just gives way to it. main.rs
+/// turns `close_when_requested` off so the close button comes here rather
+/// than straight to Bevy, and `ConfirmQuit` off for unattended runs.
+pub struct QuitPlugin;
+
+impl Plugin for QuitPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        app.add_message::<QuitRequested>()
+            .add_message::<WindowCloseRequested>()
+            .add_message::<ShowToast>()
+            .init_resource::<ConfirmQuit>()
+            .init_resource::<UnsavedChanges>()
+            .add_systems(
+                Update,
+                (
+                    forward_close_requests,
+                    handle_quit_requests,
+                    (navigate_quit_prompt, sync_quit_prompt).chain().run_if(in_state(Mode::Menu)),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(Mode::Menu), spawn_quit_prompt)
+            .add_systems(OnExit(Mode::Menu), close_quit_prompt);
+    }
+}
+
+/// Ask to quit, as the window's close button does.
+#[derive(Message, Debug, Clone, Copy, Default)]
+pub struct QuitRequested;
+
+/// Whether quitting with unsaved changes asks first. Nobody is there to
+/// answer a `--headless`, `--frames` or `--seconds` run, so main.rs turns
+/// it off for those.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmQuit(pub bool);
+
+impl Default for ConfirmQuit {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl ConfirmQuit {
+    pub fn for_run(headless: bool, frames: Option<u64>, seconds: Option<f32>) -> Self {
+        Self(!headless && frames.is_none() && seconds.is_none())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuitChoice {
+    SaveAndQuit,
+    QuitWithoutSaving,
+    Cancel,
+}
+
+impl QuitChoice {
+    pub const ALL: [QuitChoice; 3] = [QuitChoice::SaveAndQuit, QuitChoice::QuitWithoutSaving, QuitChoice::Cancel];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            QuitChoice::SaveAndQuit => "Save and quit",
+            QuitChoice::QuitWithoutSaving => "Quit without saving",
+            QuitChoice::Cancel => "Cancel",
+        }
+    }
+}
+
+/// The question is up.
+#[derive(Resource, Debug, Default)]
+pub struct QuitPrompt {
+    cursor: usize,
+    /// Quitting was asked for again while the question was up.
+    asked_again: bool,
+}
+
+impl QuitPrompt {
+    pub fn selected(&self) -> QuitChoice {
+        QuitChoice::ALL[self.cursor]
+    }
+
+    /// Up (-1) or down (+1), wrapping at either end.
+    fn move_cursor(&mut self, step: isize) {
+        self.cursor = (self.cursor as isize + step).rem_euclid(QuitChoice::ALL.len() as isize) as usize;
+    }
+}
+
+#[derive(Component)]
+struct QuitPromptRoot;
+
+/// The nth choice's row.
+#[derive(Component)]
+struct QuitPromptRow(usize);
+
+fn forward_close_requests(mut closes: MessageReader<WindowCloseRequested>, mut quits: MessageWriter<QuitRequested>) {
+    if closes.read().count() > 0 {
+        quits.write(QuitRequested);
+    }
+}
+
+/// Quit now, or put the question up once the screen is free. A request
+/// that has to wait is held (`waiting`) rather than dropped; one made
+/// while the question is already up answers it.
+fn handle_quit_requests(
+    mut commands: Commands,
+    mut requests: MessageReader<QuitRequested>,
+    confirm: Res<ConfirmQuit>,
+    unsaved: Res<UnsavedChanges>,
+    prompt: Option<ResMut<QuitPrompt>>,
+    mode: Option<Res<State<Mode>>>,
+    next_mode: Option<ResMut<NextState<Mode>>>,
+    mut toasts: MessageWriter<ShowToast>,
+    mut exit: MessageWriter<AppExit>,
+    mut waiting: Local<bool>,
+) {
+    let requested = requests.read().count() > 0;
+    if requested && let Some(mut prompt) = prompt {
+        prompt.asked_again = true;
+    } else if requested {
+        if !*waiting && mode.as_ref().is_some_and(|mode| matches!(mode.get(), Mode::Dialogue | Mode::Menu | Mode::Console)) {
+            toasts.write(ShowToast::new("Quitting once you're done here"));
+        }
+        *waiting = true;
+    }
+    if !*waiting {
+        return;
+    }
+    if !confirm.0 || !unsaved.is_dirty() {
+        info!("👋 Quitting");
+        exit.write(AppExit::Success);
+        *waiting = false;
+        return;
+    }
+    match (mode.map(|mode| *mode.get()), next_mode) {
+        (Some(Mode::Exploring | Mode::Dashboard), Some(mut next_mode)) => {
+            commands.insert_resource(QuitPrompt::default());
+            next_mode.set(Mode::Menu);
+            *waiting = false;
+        }
+        (Some(_), _) => {}
+        // Still loading: there's nothing to save yet.
+        (None, _) => {
+            exit.write(AppExit::Success);
+            *waiting = false;
+        }
+    }
+}
+
+fn navigate_quit_prompt(
+    keyboard: GameInput,
+    prompt: Option<ResMut<QuitPrompt>>,
+    save_dir: Option<Res<SaveDirectory>>,
+    snapshot: SaveSnapshot,
+    mut unsaved: ResMut<UnsavedChanges>,
+    mut next_mode: ResMut<NextState<Mode>>,
+    mut toasts: MessageWriter<ShowToast>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let Some(mut prompt) = prompt else { return };
+    if keyboard.just_pressed(KeyCode::KeyW) || keyboard.just_pressed(KeyCode::ArrowUp) {
+        prompt.move_cursor(-1);
+    } else if keyboard.just_pressed(KeyCode::KeyS) || keyboard.just_pressed(KeyCode::ArrowDown) {
+        prompt.move_cursor(1);
+    }
+    let choice = if prompt.asked_again {
+        prompt.asked_again = false;
+        QuitChoice::SaveAndQuit
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        QuitChoice::Cancel
+    } else if keyboard.just_pressed(KeyCode::Space)
+        || keyboard.just_pressed(KeyCode::Enter)
+        || keyboard.just_pressed(KeyCode::KeyE)
+    {
+        prompt.selected()
+    } else {
+        return;
+    };
+
+    match choice {
+        QuitChoice::Cancel => next_mode.set(Mode::Exploring),
+        QuitChoice::QuitWithoutSaving => {
+            info!("👋 Quitting without saving");
+            exit.write(AppExit::Success);
+        }
+        QuitChoice::SaveAndQuit => {
+            // No SaveDirectory (the test harness): nowhere to save to.
+            if let Some(dir) = save_dir {
+                let Some(data) = snapshot.capture() else { return };
+                if let Err(e) = write_save(&dir.0, SaveSlot::Autosave, &data) {
+                    warn!("Save before quitting failed: {e:#}");
+                    toasts.write(ShowToast::new("Couldn't save - see the log"));
+                    return;
+                }
+                info!("💾 Saved in {} at ({}, {}) before quitting", data.scene, data.tile_x, data.tile_y);
+                unsaved.saved(&data);
+            }
+            exit.write(AppExit::Success);
+        }
+    }
+}
+
+fn spawn_quit_prompt(mut commands: Commands, prompt: Option<Res<QuitPrompt>>, game_assets: Option<Res<GameAssets>>) {
+    if prompt.is_none() {
+        return;
+    }
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    // Sized like the save menu's: 26px title, 20px rows, 16px hint at 1080p.
+    let text = |px: f32| TextFont { font: font.clone().into(), font_size: FontSize::Vh(px / 10.8), ..default() };
+
+    commands
+        .spawn((
+            QuitPromptRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(30.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.92)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new("Quit? You have unsaved progress."), text(26.0), TextColor(Color::WHITE)));
+                    for index in 0..QuitChoice::ALL.len() {
+                        panel.spawn((QuitPromptRow(index), Text::default(), text(20.0), TextColor(Color::WHITE)));
+                    }
+                    panel.spawn((
+                        InputPrompt::new("{advance}: choose   {cancel}: back", font.clone(), 16.0, Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                        Node { align_items: AlignItems::Center, ..default() },
+                    ));
+                });
+        });
+}
+
+/// "> " on the cursor's row.
+fn sync_quit_prompt(prompt: Option<Res<QuitPrompt>>, mut rows: Query<(&QuitPromptRow, &mut Text)>) {
+    let Some(prompt) = prompt.filter(|prompt| prompt.is_changed()) else { return };
+    for (row, mut text) in &mut rows {
+        let marker = if row.0 == prompt.cursor { "> " } else { "  " };
+        **text = format!("{marker}{}", QuitChoice::ALL[row.0].label());
+    }
+}
+
+fn close_quit_prompt(mut commands: Commands, roots: Query<Entity, With<QuitPromptRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+    commands.remove_resource::<QuitPrompt>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unattended_runs_quit_without_asking() {
+        assert_eq!(ConfirmQuit::for_run(false, None, None), ConfirmQuit(true));
+        assert_eq!(ConfirmQuit::for_run(true, None, None), ConfirmQuit(false));
+        assert_eq!(ConfirmQuit::for_run(false, Some(600), None), ConfirmQuit(false));
+        assert_eq!(ConfirmQuit::for_run(false, None, Some(30.0)), ConfirmQuit(false));
+    }
+}
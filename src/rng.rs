@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// The game's one source of randomness. Every consumer draws from a named
+/// stream (`rng.stream("wander")`) whose sequence depends only on the seed
+/// and the name, so a new consumer added next month doesn't shift what
+/// doggo does today, and a run replays exactly from its seed.
+///
+/// The seed comes from `--seed`, or from the clock when not given; either
+/// way it's logged at startup and put on the `game_session` span
+/// (`game.seed`), so any recorded session can be re-run with the same
+/// dice. Current streams: `wander` (npc.rs) and `ambient` (ambient.rs).
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // init, not insert: an entry point (or test) that seeded it keeps
+        // its seed.
+        app.init_resource::<GameRng>()
+            .add_systems(Startup, log_seed);
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<String, RngStream>,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, streams: HashMap::new() }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The stream called `name`, started on first use.
+    pub fn stream(&mut self, name: &str) -> &mut RngStream {
+        let seed = self.seed;
+        self.streams
+            .entry(name.to_string())
+            .or_insert_with(|| RngStream::new(seed ^ name_hash(name)))
+    }
+}
+
+impl Default for GameRng {
+    /// Seeded from the clock - a different game every launch.
+    fn default() -> Self {
+        let nanos = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .map(|since| since.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(nanos)
+    }
+}
+
+/// FNV-1a, for the same reason as `dialogue_id`: stable across Rust
+/// releases, so a seed means the same thing in every build.
+fn name_hash(name: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in name.as_bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// One named sequence: SplitMix64, which is fast, has no bad seeds, and
+/// is plenty for gameplay dice.
+#[derive(Debug, Clone)]
+pub struct RngStream {
+    state: u64,
+}
+
+impl RngStream {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// 0..n, for picking among `n` things. `n` must be nonzero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Uniform in [0, 1).
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn log_seed(rng: Res<GameRng>) {
+    info!("🎲 RNG seed: {} (replay with --seed {})", rng.seed(), rng.seed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_are_independent_of_each_other_and_of_draw_order() {
+        let draws = |rng: &mut GameRng, name: &str| -> Vec<u64> {
+            (0..4).map(|_| rng.stream(name).next_u64()).collect()
+        };
+
+        let mut alone = GameRng::new(42);
+        let wander = draws(&mut alone, "wander");
+
+        // Another consumer drawing first must not shift "wander".
+        let mut shared = GameRng::new(42);
+        let ambient = draws(&mut shared, "ambient");
+        assert_eq!(draws(&mut shared, "wander"), wander);
+        assert_ne!(ambient, wander);
+
+        assert_ne!(draws(&mut GameRng::new(43), "wander"), wander, "the seed matters");
+        let unit = GameRng::new(7).stream("x").unit();
+        assert!((0.0..1.0).contains(&unit));
+    }
+}
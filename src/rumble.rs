@@ -0,0 +1,123 @@
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+use std::time::Duration;
+use crate::settings::GameSettings;
+
+/// Controller rumble. Gameplay systems write a `RumbleEvent` - one of its
+/// presets, usually - and forget about it; this plugin turns it into
+/// Bevy's `GamepadRumbleRequest`s for every connected gamepad.
+///
+/// Rumbles coalesce rather than queue: a burst of events (walking along a
+/// row of NPCs) becomes one rumble, as strong as the strongest and lasting
+/// until the latest would have ended. Bevy adds up rumbles that overlap,
+/// so a longer or stronger one replaces the one playing instead.
+///
+/// No gamepad, or `GameSettings::rumble` off: the events are dropped.
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<RumbleEvent>()
+            .add_message::<GamepadRumbleRequest>()
+            .init_resource::<Rumbling>()
+            // After the frame's gameplay, so its events rumble this frame.
+            .add_systems(PostUpdate, play_rumble);
+    }
+}
+
+/// Rumble the controller: `intensity` from 0 (nothing) to 1 (both motors
+/// flat out).
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct RumbleEvent {
+    pub intensity: f32,
+    pub duration: Duration,
+}
+
+impl RumbleEvent {
+    /// A tap: an NPC came into talking range (npc.rs).
+    pub const LIGHT: Self = Self { intensity: 0.25, duration: Duration::from_millis(80) };
+    /// A pulse: a dialogue choice confirmed (dialogue.rs).
+    pub const MEDIUM: Self = Self { intensity: 0.5, duration: Duration::from_millis(150) };
+    /// A buzz, for something going wrong: hazard tiles, incident alarms.
+    pub const STRONG: Self = Self { intensity: 1.0, duration: Duration::from_millis(400) };
+
+    fn gamepad_intensity(&self) -> GamepadRumbleIntensity {
+        let intensity = self.intensity.clamp(0.0, 1.0);
+        GamepadRumbleIntensity { strong_motor: intensity, weak_motor: intensity }
+    }
+}
+
+/// The rumble playing, as `Time<Real>` elapsed when it ends - what new
+/// events are merged into.
+#[derive(Resource, Debug, Default)]
+struct Rumbling {
+    intensity: f32,
+    until: Duration,
+}
+
+impl Rumbling {
+    /// Merge `events` into what's playing at `now`: the rumble to play in
+    /// its place, or None when they ask for nothing it isn't already doing.
+    fn coalesce(&mut self, now: Duration, events: impl IntoIterator<Item = RumbleEvent>) -> Option<RumbleEvent> {
+        if now >= self.until {
+            self.intensity = 0.0;
+        }
+        let mut changed = false;
+        for event in events.into_iter().filter(|event| event.intensity > 0.0 && !event.duration.is_zero()) {
+            if event.intensity > self.intensity {
+                self.intensity = event.intensity;
+                changed = true;
+            }
+            if now + event.duration > self.until {
+                self.until = now + event.duration;
+                changed = true;
+            }
+        }
+        changed.then(|| RumbleEvent { intensity: self.intensity, duration: self.until - now })
+    }
+}
+
+fn play_rumble(
+    mut events: MessageReader<RumbleEvent>,
+    settings: Option<Res<GameSettings>>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    time: Res<Time<Real>>,
+    mut rumbling: ResMut<Rumbling>,
+    mut requests: MessageWriter<GamepadRumbleRequest>,
+) {
+    if gamepads.is_empty() || settings.is_some_and(|settings| !settings.rumble) {
+        events.clear();
+        return;
+    }
+    let Some(rumble) = rumbling.coalesce(time.elapsed(), events.read().copied()) else { return };
+    for gamepad in &gamepads {
+        requests.write(GamepadRumbleRequest::Stop { gamepad });
+        requests.write(GamepadRumbleRequest::Add {
+            gamepad,
+            intensity: rumble.gamepad_intensity(),
+            duration: rumble.duration,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_burst_of_rumbles_plays_as_one() {
+        let mut rumbling = Rumbling::default();
+        let at = Duration::from_millis;
+
+        let burst = [RumbleEvent::LIGHT, RumbleEvent::MEDIUM, RumbleEvent::LIGHT];
+        assert_eq!(rumbling.coalesce(at(0), burst), Some(RumbleEvent::MEDIUM));
+        assert_eq!(rumbling.coalesce(at(50), [RumbleEvent::LIGHT]), None, "already covered");
+        assert_eq!(
+            rumbling.coalesce(at(100), [RumbleEvent::LIGHT]),
+            Some(RumbleEvent { intensity: 0.5, duration: at(80) }),
+            "runs on at the stronger intensity"
+        );
+        assert_eq!(rumbling.coalesce(at(1000), [RumbleEvent::LIGHT]), Some(RumbleEvent::LIGHT), "over: starts afresh");
+        assert_eq!(rumbling.coalesce(at(2000), []), None);
+    }
+}
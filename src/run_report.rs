@@ -0,0 +1,307 @@
+use bevy::asset::UntypedAssetLoadFailedEvent;
+use bevy::prelude::*;
+use bevy::state::state::StateTransitionEvent;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::content_errors::ContentErrors;
+use crate::game_state::{GameState, Mode, Scene};
+
+/// A run's outcome as JSON, for CI smoke tests to check instead of
+/// grepping logs: how it exited, the states it got through, assets that
+/// failed to load, content problems, frame times, how telemetry export
+/// went, and any panic. `--run-report <path>` (main.rs) turns it on.
+///
+/// The plugin fills in a `RunReportHandle` as the game runs; main.rs
+/// writes it once `App::run` has returned - every exit system and
+/// summary done - and before the telemetry providers shut down. A panic
+/// writes what there is so far from the panic hook
+/// (`RunReportHandle::install_panic_hook`).
+///
+/// The field names are the schema: CI scripts read them, so renaming one
+/// means bumping `RUN_REPORT_SCHEMA`.
+pub struct RunReportPlugin;
+
+impl Plugin for RunReportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<UntypedAssetLoadFailedEvent>().add_systems(
+            Last,
+            (record_states, record_failed_assets, record_frame, record_content_errors)
+                .run_if(resource_exists::<RunReportHandle>),
+        );
+    }
+}
+
+/// Bumped whenever a field is renamed, removed, or changes meaning.
+pub const RUN_REPORT_SCHEMA: u32 = 1;
+
+/// The process exit code of a panicking Rust program.
+pub const PANIC_EXIT_CODE: i32 = 101;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunReport {
+    pub schema: u32,
+    /// None until the run has ended.
+    pub exit: Option<RunExit>,
+    /// Every state entered, in order.
+    pub states: Vec<StateReached>,
+    pub failed_assets: Vec<FailedAsset>,
+    /// `ContentErrors` as of the end of the run.
+    pub content_warnings: Vec<ContentWarning>,
+    pub frames: FrameStats,
+    pub telemetry: TelemetryHealth,
+    /// Panic messages, with where they happened.
+    pub panics: Vec<String>,
+}
+
+impl Default for RunReport {
+    fn default() -> Self {
+        Self {
+            schema: RUN_REPORT_SCHEMA,
+            exit: None,
+            states: Vec::new(),
+            failed_assets: Vec::new(),
+            content_warnings: Vec::new(),
+            frames: FrameStats::default(),
+            telemetry: TelemetryHealth::default(),
+            panics: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunExit {
+    /// "frame limit", "time limit", "exited" (the window, the quit
+    /// prompt), "error", or "panic".
+    pub reason: String,
+    pub code: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateReached {
+    /// Like "GameState::Playing" or "Scene::TownOfEndgame".
+    pub state: String,
+    /// Real time since startup.
+    pub at_secs: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedAsset {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentWarning {
+    pub path: String,
+    pub error: String,
+}
+
+/// Real frame times, every frame of the run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameStats {
+    pub count: u64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+}
+
+impl FrameStats {
+    pub fn record(&mut self, frame_ms: f64) {
+        self.count += 1;
+        self.average_ms += (frame_ms - self.average_ms) / self.count as f64;
+        self.max_ms = self.max_ms.max(frame_ms);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryHealth {
+    /// Whether there was a collector to export to.
+    pub exporting: bool,
+    /// Errors the OpenTelemetry SDK logged (`dashboard::ExportErrors`).
+    pub export_errors: u64,
+}
+
+impl RunReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a run report is plain data")
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+}
+
+/// The report being filled in, shared with main.rs and the panic hook.
+#[derive(Resource, Clone, Default)]
+pub struct RunReportHandle {
+    report: Arc<Mutex<RunReport>>,
+    /// Why the game is exiting, if something said before `finish`.
+    reason: Arc<Mutex<Option<String>>>,
+}
+
+impl RunReportHandle {
+    fn update(&self, change: impl FnOnce(&mut RunReport)) {
+        if let Ok(mut report) = self.report.lock() {
+            change(&mut report);
+        }
+    }
+
+    pub fn snapshot(&self) -> RunReport {
+        self.report.lock().map(|report| report.clone()).unwrap_or_default()
+    }
+
+    /// Say why the game is about to exit (`AppExit::Success` doesn't).
+    pub fn exit_reason(&self, reason: impl Into<String>) {
+        if let Ok(mut noted) = self.reason.lock() {
+            noted.get_or_insert_with(|| reason.into());
+        }
+    }
+
+    /// The run is over: how it exited, and how telemetry export went.
+    pub fn finish(&self, exit: &AppExit, exporting: bool, export_errors: u64) {
+        let noted = self.reason.lock().ok().and_then(|mut noted| noted.take());
+        self.update(|report| {
+            report.exit = Some(match exit {
+                AppExit::Success => RunExit { reason: noted.unwrap_or_else(|| "exited".into()), code: 0 },
+                AppExit::Error(code) => RunExit { reason: "error".into(), code: i32::from(code.get()) },
+            });
+            report.telemetry = TelemetryHealth { exporting, export_errors };
+        });
+    }
+
+    /// Write the report so far to `path` on any panic, the panic noted,
+    /// then carry on to the hook that was there before.
+    pub fn install_panic_hook(&self, path: PathBuf) {
+        let handle = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let note = |report: &mut RunReport| {
+                report.panics.push(info.to_string());
+                report.exit = Some(RunExit { reason: "panic".into(), code: PANIC_EXIT_CODE });
+            };
+            // The panic may have come from inside a lock: never wait. Kept
+            // in the handle when it can be, so a second panic's report has
+            // both.
+            let report = match handle.report.try_lock() {
+                Ok(mut report) => {
+                    note(&mut report);
+                    report.clone()
+                }
+                Err(_) => {
+                    let mut report = RunReport::default();
+                    note(&mut report);
+                    report
+                }
+            };
+            if let Err(e) = report.write(&path) {
+                eprintln!("⚠️  Couldn't write the run report to {}: {e}", path.display());
+            }
+            previous(info);
+        }));
+    }
+}
+
+fn record_states(
+    time: Res<Time<Real>>,
+    report: Res<RunReportHandle>,
+    mut games: MessageReader<StateTransitionEvent<GameState>>,
+    mut scenes: MessageReader<StateTransitionEvent<Scene>>,
+    mut modes: MessageReader<StateTransitionEvent<Mode>>,
+) {
+    let at_secs = time.elapsed_secs_f64();
+    let entered = games
+        .read()
+        .filter_map(|transition| transition.entered.map(|state| format!("GameState::{state:?}")))
+        .chain(scenes.read().filter_map(|transition| transition.entered.map(|state| format!("Scene::{state:?}"))))
+        .chain(modes.read().filter_map(|transition| transition.entered.map(|state| format!("Mode::{state:?}"))));
+    let reached: Vec<StateReached> = entered.map(|state| StateReached { state, at_secs }).collect();
+    if !reached.is_empty() {
+        report.update(|report| report.states.extend(reached));
+    }
+}
+
+fn record_failed_assets(report: Res<RunReportHandle>, mut failures: MessageReader<UntypedAssetLoadFailedEvent>) {
+    for failure in failures.read() {
+        let failed = FailedAsset { path: failure.path.to_string(), error: failure.error.to_string() };
+        report.update(|report| report.failed_assets.push(failed));
+    }
+}
+
+fn record_frame(time: Res<Time<Real>>, report: Res<RunReportHandle>) {
+    let frame_ms = time.delta_secs_f64() * 1000.0;
+    report.update(|report| report.frames.record(frame_ms));
+}
+
+fn record_content_errors(report: Res<RunReportHandle>, errors: Option<Res<ContentErrors>>) {
+    let Some(errors) = errors.filter(|errors| errors.is_changed()) else { return };
+    let warnings =
+        errors.errors.iter().map(|error| ContentWarning { path: error.path.clone(), error: error.error.clone() }).collect();
+    report.update(|report| report.content_warnings = warnings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RunReport {
+        let mut frames = FrameStats::default();
+        for frame_ms in [16.0, 17.0, 48.0] {
+            frames.record(frame_ms);
+        }
+        RunReport {
+            exit: Some(RunExit { reason: "frame limit".into(), code: 0 }),
+            states: vec![StateReached { state: "GameState::Playing".into(), at_secs: 0.5 }],
+            failed_assets: vec![FailedAsset { path: "textures/portraits/Gone.png".into(), error: "not found".into() }],
+            content_warnings: vec![ContentWarning { path: "maps/town.json".into(), error: "no lines".into() }],
+            frames,
+            telemetry: TelemetryHealth { exporting: true, export_errors: 2 },
+            panics: vec!["panicked at src/npc.rs:1:1:\noops".into()],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn frame_stats_keep_a_running_average_and_the_worst() {
+        let frames = sample().frames;
+        assert_eq!((frames.count, frames.average_ms, frames.max_ms), (3, 27.0, 48.0));
+    }
+
+    #[test]
+    fn a_report_round_trips_under_its_stable_field_names() {
+        let report = sample();
+        let json = report.to_json();
+        assert_eq!(serde_json::from_str::<RunReport>(&json).unwrap(), report);
+
+        // What CI scripts read. Changing any of these is a schema bump.
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let keys = |value: &serde_json::Value| -> Vec<String> {
+            let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(value["schema"], RUN_REPORT_SCHEMA);
+        assert_eq!(keys(&value), [
+            "content_warnings", "exit", "failed_assets", "frames", "panics", "schema", "states", "telemetry"
+        ]);
+        assert_eq!(keys(&value["exit"]), ["code", "reason"]);
+        assert_eq!(keys(&value["states"][0]), ["at_secs", "state"]);
+        assert_eq!(keys(&value["failed_assets"][0]), ["error", "path"]);
+        assert_eq!(keys(&value["content_warnings"][0]), ["error", "path"]);
+        assert_eq!(keys(&value["frames"]), ["average_ms", "count", "max_ms"]);
+        assert_eq!(keys(&value["telemetry"]), ["export_errors", "exporting"]);
+    }
+
+    #[test]
+    fn finishing_records_why_and_how_the_run_exited() {
+        let handle = RunReportHandle::default();
+        handle.exit_reason("frame limit");
+        handle.exit_reason("time limit");
+        handle.finish(&AppExit::Success, false, 0);
+        assert_eq!(handle.snapshot().exit, Some(RunExit { reason: "frame limit".into(), code: 0 }), "the first reason stands");
+
+        handle.finish(&AppExit::from_code(3), true, 1);
+        let report = handle.snapshot();
+        assert_eq!(report.exit, Some(RunExit { reason: "error".into(), code: 3 }));
+        assert_eq!(report.telemetry, TelemetryHealth { exporting: true, export_errors: 1 });
+    }
+}
@@ -0,0 +1,940 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result};
+use opentelemetry::{KeyValue, trace::Span as _};
+use crate::dialogue::SeenDialogues;
+use crate::flags::GameFlags;
+use crate::game_state::{GameState, Mode, Scene};
+use crate::group_conversation::GroupConversationLog;
+use crate::instrumentation::PlayerSessionTrace;
+use crate::content_pack::ContentPacks;
+use crate::map_data::{MapData, MapDirectory, scene_from_str, tile_to_world, world_to_tile};
+use crate::npc::TimesTalked;
+use crate::player::Player;
+use crate::tilemap::{CollisionMap, PendingArrival};
+use crate::toast::ShowToast;
+use crate::tutorial::TutorialProgress;
+
+/// Save files: three numbered slots (written from the slot picker,
+/// save_menu.rs) and a crash-safe autosave, all plain JSON in
+/// `SaveDirectory`. Native-only - there is no filesystem in the browser.
+///
+/// Each file starts with a one-line `SaveHeader` - what the picker shows -
+/// so listing the slots never parses (or trips over) a whole save.
+///
+/// Nothing is saved unless `SaveDirectory` exists; main.rs inserts it, the
+/// test harness doesn't, so test runs never touch a real save.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<AutosaveRequest>()
+            .init_resource::<AutosaveTimer>()
+            .init_resource::<Playtime>()
+            .init_resource::<UnsavedChanges>()
+            .add_message::<ShowToast>()
+            .init_resource::<GameFlags>()
+            .init_resource::<SeenDialogues>()
+            .init_resource::<TutorialProgress>()
+            .init_resource::<GroupConversationLog>()
+            .init_resource::<TimesTalked>()
+            .init_resource::<LegacyDialogueIds>()
+            .add_systems(Startup, collect_from_maps)
+            .add_systems(OnEnter(GameState::Playing), start_unsaved)
+            .add_systems(Update, (
+                tick_playtime.run_if(not(in_state(Mode::Menu))),
+                tick_autosave_timer,
+                autosave_on_scene_change,
+                apply_pending_continue,
+                track_unsaved_changes,
+                perform_autosave,
+            ).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// Bumped whenever a field changes meaning. Saves back to
+/// `OLDEST_SAVE_VERSION` are brought up to date as they load
+/// (`SaveData::migrate`); older ones are refused rather than misread.
+const SAVE_VERSION: u32 = 3;
+const OLDEST_SAVE_VERSION: u32 = 1;
+
+fn readable(version: u32) -> bool {
+    (OLDEST_SAVE_VERSION..=SAVE_VERSION).contains(&version)
+}
+
+/// Numbered slots the player can save into, besides the autosave.
+pub const SLOT_COUNT: u8 = 3;
+
+/// Everything a save restores: where the player is, which dialogues
+/// they've read (for "skip seen", dialogue.rs), which tutorial hints
+/// they've finished (tutorial.rs), the story flags set (flags.rs), and how
+/// often they've talked to each NPC (npc.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    /// Unix seconds at write time - how the picker finds the newest slot.
+    pub saved_at: u64,
+    /// `Scene` variant name, parsed back with `map_data::scene_from_str`.
+    pub scene: String,
+    pub tile_x: u32,
+    pub tile_y: u32,
+    /// `DialogueRequest::id`s read to the end: an NPC conversation's
+    /// qualified id (`NpcDialogue::id`), else `dialogue::dialogue_id`.
+    /// Defaults to empty for saves written before skip-seen existed.
+    #[serde(default)]
+    pub seen_dialogues: Vec<String>,
+    /// `tutorial::TutorialStep` ids completed. Absent (`None`) in saves
+    /// written before the tutorial existed: those players know the
+    /// controls, so loading one finishes the tutorial.
+    #[serde(default)]
+    pub tutorial_completed: Option<Vec<String>>,
+    /// Story flags set. Defaults to none for saves written before flags
+    /// were saved.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// Time spent playing, across sessions (see `Playtime`).
+    #[serde(default)]
+    pub playtime_secs: u64,
+    /// Share of the story's flags set, 0-100 (see `StoryFlags`).
+    #[serde(default)]
+    pub progress: u8,
+    /// Group conversations overheard and their cooldowns
+    /// (group_conversation.rs). Defaults to none heard.
+    #[serde(default)]
+    pub group_conversations: GroupConversationLog,
+    /// Conversations started per NPC id (`TimesTalked`), which dialogue
+    /// selection and `{npcs_met}` read. Added in version 3; older saves
+    /// load as nobody talked to yet.
+    #[serde(default)]
+    pub times_talked: HashMap<String, u32>,
+    /// Seconds before the save each NPC last said its dialogue in full, so
+    /// `repeat` once and cooldowns hold after loading. Older saves load as
+    /// nobody having spoken.
+    #[serde(default)]
+    pub said_secs_ago: HashMap<String, f32>,
+}
+
+impl SaveData {
+    /// This save as the current version would have written it. Version 1
+    /// knew NPC conversations by their content hash, which renaming the
+    /// speaker changes; the ones the game still has are re-keyed by id.
+    /// Versions before 3 didn't count conversations at all, and have no
+    /// `times_talked` to load.
+    pub fn migrate(mut self, legacy: &LegacyDialogueIds) -> Self {
+        if self.version < 2 {
+            for seen in &mut self.seen_dialogues {
+                if let Some(id) = legacy.0.get(seen) {
+                    seen.clone_from(id);
+                }
+            }
+        }
+        self.version = SAVE_VERSION;
+        self
+    }
+
+    pub fn header(&self) -> SaveHeader {
+        SaveHeader {
+            version: self.version,
+            saved_at: self.saved_at,
+            playtime_secs: self.playtime_secs,
+            scene: self.scene.clone(),
+            progress: self.progress,
+        }
+    }
+}
+
+/// A save's first line: what the slot picker lists it as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveHeader {
+    pub version: u32,
+    pub saved_at: u64,
+    pub playtime_secs: u64,
+    pub scene: String,
+    pub progress: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaveSlot {
+    /// 1 to `SLOT_COUNT`.
+    Numbered(u8),
+    Autosave,
+}
+
+impl SaveSlot {
+    /// The picker's order: the numbered slots, then the autosave.
+    pub fn all() -> impl Iterator<Item = SaveSlot> {
+        (1..=SLOT_COUNT).map(SaveSlot::Numbered).chain(std::iter::once(SaveSlot::Autosave))
+    }
+
+    fn file_name(self) -> String {
+        match self {
+            SaveSlot::Numbered(n) => format!("slot{n}.json"),
+            SaveSlot::Autosave => "autosave.json".into(),
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            SaveSlot::Numbered(n) => format!("slot {n}"),
+            SaveSlot::Autosave => "autosave".into(),
+        }
+    }
+}
+
+/// What's in a slot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotStatus {
+    Empty,
+    /// There, but unreadable - header or body - or from another save
+    /// version. Listed (as "damaged") rather than hidden, so the player can
+    /// see it and delete it.
+    Damaged,
+    Saved(SaveHeader),
+}
+
+/// Where save files live. `default_dir` is `$XDG_DATA_HOME/sregame` (or
+/// `~/.local/share/sregame`); `--save-dir` overrides it.
+#[derive(Resource, Clone, Debug)]
+pub struct SaveDirectory(pub PathBuf);
+
+impl SaveDirectory {
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("sregame")
+    }
+}
+
+/// Ask for an autosave now. Scene changes and the periodic timer write these
+/// internally; progress milestones (quest completion) should too.
+#[derive(Message)]
+pub struct AutosaveRequest {
+    pub reason: &'static str,
+}
+
+/// How often to checkpoint while playing, on top of every scene change.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Resource)]
+struct AutosaveTimer(Timer);
+
+impl Default for AutosaveTimer {
+    fn default() -> Self {
+        Self(Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+/// Time spent in `GameState::Playing` this game, carried across sessions
+/// by the save.
+#[derive(Resource, Default, Debug)]
+pub struct Playtime(pub Duration);
+
+/// Every flag some conversation in the game can set: a save's progress is
+/// the share of these it has. Collected from the maps at startup.
+#[derive(Resource, Default, Debug)]
+pub struct StoryFlags(BTreeSet<String>);
+
+impl StoryFlags {
+    /// Percent of the story's flags set; 0 for a story without any.
+    pub fn progress(&self, flags: &GameFlags) -> u8 {
+        if self.0.is_empty() {
+            return 0;
+        }
+        let set = self.0.iter().filter(|flag| flags.is_set(flag)).count();
+        (set * 100 / self.0.len()) as u8
+    }
+}
+
+impl FromIterator<String> for StoryFlags {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Whether the game has moved on since it was last saved or loaded: the
+/// scene, the player's tile, story flags or the conversations read differ
+/// from that save's. Quitting asks first while it has (quit.rs). A game
+/// that was never saved compares against where it started.
+#[derive(Resource, Default, Debug)]
+pub struct UnsavedChanges {
+    /// The last save written or loaded.
+    saved: Option<SaveData>,
+    dirty: bool,
+}
+
+impl UnsavedChanges {
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// `data` was just written or loaded: it's the game as saved.
+    pub fn saved(&mut self, data: &SaveData) {
+        self.saved = Some(data.clone());
+        self.dirty = false;
+    }
+
+    fn compare(&mut self, now: SaveData) {
+        match &self.saved {
+            Some(saved) => self.dirty = moved_on(saved, &now),
+            None => self.saved = Some(now),
+        }
+    }
+}
+
+/// Whether `now` holds anything `saved` would lose. Playtime and the
+/// tutorial don't count: nobody wants to be asked about those.
+fn moved_on(saved: &SaveData, now: &SaveData) -> bool {
+    saved.scene != now.scene
+        || (saved.tile_x, saved.tile_y) != (now.tile_x, now.tile_y)
+        || saved.flags != now.flags
+        || saved.seen_dialogues != now.seen_dialogues
+}
+
+/// Each NPC conversation's id (`NpcDialogue::id`) by the content hash
+/// version-1 saves knew it by, for `SaveData::migrate`.
+#[derive(Resource, Debug, Default)]
+pub struct LegacyDialogueIds(HashMap<String, String>);
+
+impl LegacyDialogueIds {
+    /// From every NPC's inline dialogue on `maps`, by map file name.
+    /// Dialogue files aren't read here: their conversations come up unread.
+    pub fn from_maps<'a>(maps: impl IntoIterator<Item = (&'a str, &'a MapData)>) -> Self {
+        let mut ids = HashMap::new();
+        for (name, map) in maps {
+            for dialogue in map.npcs.iter().chain(&map.spawnable).map(|npc| &npc.dialogue) {
+                let hash = crate::dialogue::content_hash(
+                    dialogue.lines.iter().map(|line| (dialogue.speaker_of(line), line.text.as_str())),
+                );
+                ids.insert(hash, crate::content::qualified_id(name, &dialogue.id()));
+            }
+        }
+        Self(ids)
+    }
+}
+
+/// A save to load - picked in the slot picker, or named on the command
+/// line - applied once the current scene is up.
+#[derive(Resource)]
+pub struct PendingContinue {
+    pub slot: SaveSlot,
+    pub data: SaveData,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn slot_path(dir: &Path, slot: SaveSlot) -> PathBuf {
+    dir.join(slot.file_name())
+}
+
+/// The previous autosave generation, kept so a corrupt or missing current
+/// file (a crash mid-rotation) still leaves something to continue from.
+fn previous_path(dir: &Path) -> PathBuf {
+    dir.join("autosave.prev.json")
+}
+
+/// The single manual save from before numbered slots. Read as slot 1
+/// until slot 1 is written.
+fn legacy_path(dir: &Path) -> PathBuf {
+    dir.join("save.json")
+}
+
+/// First half of an atomic write: the complete new contents land in a temp
+/// file beside the target and are synced to disk. Until `commit` renames it
+/// into place, the existing save is untouched.
+fn stage(dir: &Path, slot: SaveSlot, data: &SaveData) -> Result<PathBuf> {
+    use std::io::Write;
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create save dir {}", dir.display()))?;
+    let temp = dir.join(format!("{}.tmp", slot.file_name()));
+    let mut contents = serde_json::to_vec(&data.header()).context("Failed to serialize save header")?;
+    contents.push(b'\n');
+    contents.extend(serde_json::to_vec_pretty(data).context("Failed to serialize save")?);
+
+    let mut file = std::fs::File::create(&temp)
+        .with_context(|| format!("Failed to create {}", temp.display()))?;
+    file.write_all(&contents)?;
+    file.sync_all()?;
+    Ok(temp)
+}
+
+/// Second half: rotate the current autosave to `.prev`, then rename the
+/// staged file over the target. rename() replaces atomically, so readers
+/// see either the old save or the new one, never half of either.
+fn commit(dir: &Path, slot: SaveSlot, staged: &Path) -> Result<()> {
+    let target = slot_path(dir, slot);
+    if slot == SaveSlot::Autosave && target.exists() {
+        // Copy, not rename: the current file must stay in place until the
+        // new one replaces it.
+        std::fs::copy(&target, previous_path(dir)).context("Failed to rotate autosave")?;
+    }
+    std::fs::rename(staged, &target)
+        .with_context(|| format!("Failed to move save into place at {}", target.display()))
+}
+
+pub fn write_save(dir: &Path, slot: SaveSlot, data: &SaveData) -> Result<()> {
+    let staged = stage(dir, slot, data)?;
+    commit(dir, slot, &staged)
+}
+
+fn read_file(path: &Path) -> Option<SaveData> {
+    let json = std::fs::read_to_string(path).ok()?;
+    // Saves from before headers are the body alone.
+    let body = match json.split_once('\n') {
+        Some((first, rest)) if serde_json::from_str::<SaveHeader>(first).is_ok() => rest,
+        _ => &json,
+    };
+    match serde_json::from_str::<SaveData>(body) {
+        Ok(data) if readable(data.version) => Some(data),
+        Ok(data) => {
+            warn!(
+                "Ignoring {} - save version {} (expected {OLDEST_SAVE_VERSION} to {SAVE_VERSION})",
+                path.display(),
+                data.version
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Ignoring unreadable save {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// A save file as the picker lists it. The body is read too: a slot whose
+/// header is fine but whose save won't load is damaged, not one to offer.
+fn read_header(path: &Path) -> SlotStatus {
+    match std::fs::metadata(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => SlotStatus::Empty,
+        _ => read_file(path).map_or(SlotStatus::Damaged, |data| SlotStatus::Saved(data.header())),
+    }
+}
+
+/// Read a slot. The autosave falls back to its previous generation when the
+/// current file is missing or unreadable; slot 1 to the pre-slots manual
+/// save while it has never been written.
+pub fn read_save(dir: &Path, slot: SaveSlot) -> Option<SaveData> {
+    let path = slot_path(dir, slot);
+    match slot {
+        SaveSlot::Autosave => read_file(&path).or_else(|| read_file(&previous_path(dir))),
+        SaveSlot::Numbered(1) if !path.exists() => read_file(&legacy_path(dir)),
+        SaveSlot::Numbered(_) => read_file(&path),
+    }
+}
+
+/// A slot's header, with the same fallbacks as `read_save`.
+pub fn slot_status(dir: &Path, slot: SaveSlot) -> SlotStatus {
+    let path = slot_path(dir, slot);
+    match slot {
+        SaveSlot::Autosave => match read_header(&path) {
+            SlotStatus::Saved(header) => SlotStatus::Saved(header),
+            current => match read_header(&previous_path(dir)) {
+                SlotStatus::Saved(header) => SlotStatus::Saved(header),
+                _ => current,
+            },
+        },
+        SaveSlot::Numbered(1) if !path.exists() => read_header(&legacy_path(dir)),
+        SaveSlot::Numbered(_) => read_header(&path),
+    }
+}
+
+/// The most recently written slot, for the picker's cursor.
+pub fn newest_slot(dir: &Path) -> Option<SaveSlot> {
+    SaveSlot::all()
+        .filter_map(|slot| match slot_status(dir, slot) {
+            SlotStatus::Saved(header) => Some((header.saved_at, slot)),
+            _ => None,
+        })
+        .max_by_key(|&(saved_at, _)| saved_at)
+        .map(|(_, slot)| slot)
+}
+
+pub fn copy_slot(dir: &Path, from: SaveSlot, to: SaveSlot) -> Result<()> {
+    let data = read_save(dir, from).with_context(|| format!("Nothing to copy in {}", from.label()))?;
+    write_save(dir, to, &data)
+}
+
+/// Remove every file the slot reads from, so nothing falls back into view.
+pub fn delete_slot(dir: &Path, slot: SaveSlot) -> Result<()> {
+    let mut paths = vec![slot_path(dir, slot)];
+    match slot {
+        SaveSlot::Autosave => paths.push(previous_path(dir)),
+        SaveSlot::Numbered(1) => paths.push(legacy_path(dir)),
+        SaveSlot::Numbered(_) => {}
+    }
+    for path in paths {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Failed to delete {}", path.display()));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Reads every map once - the shipped ones, or `MapDirectory`'s, with any
+/// content packs over them - for the flags their conversations set, and
+/// what older saves called those conversations.
+fn collect_from_maps(
+    mut commands: Commands,
+    map_directory: Option<Res<MapDirectory>>,
+    content_packs: Option<Res<ContentPacks>>,
+) {
+    let dir = map_directory.as_ref().map(|dir| dir.0.as_path());
+    let names: Vec<String> = match dir {
+        Some(dir) => std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let stem = path.file_stem()?.to_str()?.to_string();
+                (path.extension()? == "json").then_some(stem)
+            })
+            .collect(),
+        None => crate::asset_manifest::map_names().map(str::to_string).collect(),
+    };
+    let load = |name: &str| match content_packs.as_ref().and_then(|packs| packs.load_map(name, dir)) {
+        Some((loaded, _)) => loaded.ok(),
+        None => match dir {
+            Some(dir) => MapData::load_from_dir(dir, name).ok(),
+            None => MapData::load(name).ok(),
+        },
+    };
+    let maps: Vec<(&str, MapData)> = names.iter().filter_map(|name| Some((name.as_str(), load(name)?))).collect();
+    let story: StoryFlags = maps.iter().flat_map(|(_, map)| map.flags_set()).map(str::to_string).collect();
+    debug!("🚩 {} story flags", story.0.len());
+    commands.insert_resource(story);
+    commands.insert_resource(LegacyDialogueIds::from_maps(maps.iter().map(|(name, map)| (*name, map))));
+}
+
+/// The live game, as a save would capture it.
+#[derive(SystemParam)]
+pub struct SaveSnapshot<'w, 's> {
+    scene: Res<'w, State<Scene>>,
+    collision_map: Option<Res<'w, CollisionMap>>,
+    seen: Res<'w, SeenDialogues>,
+    tutorial: Res<'w, TutorialProgress>,
+    flags: Res<'w, GameFlags>,
+    story: Option<Res<'w, StoryFlags>>,
+    playtime: Res<'w, Playtime>,
+    group_conversations: Res<'w, GroupConversationLog>,
+    times_talked: Res<'w, TimesTalked>,
+    time: Res<'w, Time>,
+    player: Query<'w, 's, &'static Transform, With<Player>>,
+}
+
+impl SaveSnapshot<'_, '_> {
+    /// The player's tile, `None` before the map and player exist.
+    fn tile(&self) -> Option<(u32, u32)> {
+        let map = self.collision_map.as_ref()?;
+        let transform = self.player.single().ok()?;
+        let logical = crate::player::logical_position(transform.translation.truncate());
+        let (x, y) = world_to_tile(logical, map.width, map.height);
+        Some((u32::try_from(x).ok()?, u32::try_from(y).ok()?))
+    }
+
+    /// `None` before the first map and player exist, or mid-transition.
+    pub fn capture(&self) -> Option<SaveData> {
+        let (tile_x, tile_y) = self.tile()?;
+        Some(SaveData {
+            version: SAVE_VERSION,
+            saved_at: unix_now(),
+            scene: format!("{:?}", self.scene.get()),
+            tile_x,
+            tile_y,
+            seen_dialogues: self.seen.ids().map(str::to_string).collect(),
+            tutorial_completed: Some(self.tutorial.ids().map(str::to_string).collect()),
+            flags: self.flags.iter().map(str::to_string).collect(),
+            playtime_secs: self.playtime.0.as_secs(),
+            progress: self.story.as_ref().map_or(0, |story| story.progress(&self.flags)),
+            group_conversations: self.group_conversations.clone(),
+            times_talked: self.times_talked.counts().clone(),
+            said_secs_ago: self.times_talked.said_ago(self.time.elapsed()),
+        })
+    }
+}
+
+/// Re-compares the game with the last save whenever the player changes
+/// tile or scene, or a flag or read conversation changes - not every frame,
+/// since a capture clones the lot.
+fn track_unsaved_changes(
+    mut unsaved: ResMut<UnsavedChanges>,
+    snapshot: SaveSnapshot,
+    mut last: Local<Option<(Scene, (u32, u32))>>,
+) {
+    let Some(tile) = snapshot.tile() else { return };
+    let here = (*snapshot.scene.get(), tile);
+    let moved = last.replace(here) != Some(here);
+    if !moved && !snapshot.flags.is_changed() && !snapshot.seen.is_changed() {
+        return;
+    }
+    if let Some(now) = snapshot.capture() {
+        unsaved.compare(now);
+    }
+}
+
+/// A new play session starts with no time played and nothing to lose: it
+/// compares against where it starts, as a fresh launch does.
+fn start_unsaved(mut playtime: ResMut<Playtime>, mut unsaved: ResMut<UnsavedChanges>) {
+    playtime.0 = Duration::ZERO;
+    *unsaved = UnsavedChanges::default();
+}
+
+fn tick_playtime(time: Res<Time>, mut playtime: ResMut<Playtime>) {
+    playtime.0 += time.delta();
+}
+
+fn tick_autosave_timer(
+    time: Res<Time>,
+    mut timer: ResMut<AutosaveTimer>,
+    mut requests: MessageWriter<AutosaveRequest>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        requests.write(AutosaveRequest { reason: "interval" });
+    }
+}
+
+/// Checkpoint after every scene change - but not the first scene entry of a
+/// run: a fresh launch would otherwise overwrite the crashed session's
+/// autosave with the starting town before the player could continue it.
+fn autosave_on_scene_change(
+    scene: Res<State<Scene>>,
+    mut last: Local<Option<Scene>>,
+    mut requests: MessageWriter<AutosaveRequest>,
+) {
+    let current = *scene.get();
+    if let Some(previous) = *last
+        && previous != current
+    {
+        requests.write(AutosaveRequest { reason: "scene_change" });
+    }
+    *last = Some(current);
+}
+
+fn perform_autosave(
+    mut requests: MessageReader<AutosaveRequest>,
+    save_dir: Option<Res<SaveDirectory>>,
+    snapshot: SaveSnapshot,
+    mut session_trace: Query<&mut PlayerSessionTrace, With<Player>>,
+    mut toasts: MessageWriter<ShowToast>,
+    mut timer: ResMut<AutosaveTimer>,
+    mut unsaved: ResMut<UnsavedChanges>,
+) {
+    // Several triggers in one frame still mean one write.
+    let Some(request) = requests.read().last() else { return };
+    let Some(dir) = save_dir else { return };
+    // Mid-transition (map not spawned yet): the next trigger will catch it.
+    let Some(data) = snapshot.capture() else { return };
+
+    match write_save(&dir.0, SaveSlot::Autosave, &data) {
+        Ok(()) => {
+            info!("💾 Autosaved ({}) in {} at ({}, {})", request.reason, data.scene, data.tile_x, data.tile_y);
+            toasts.write(ShowToast::new("Autosaved"));
+            unsaved.saved(&data);
+            if let Ok(mut trace) = session_trace.single_mut() {
+                trace.span.add_event(
+                    "save.autosave",
+                    vec![
+                        KeyValue::new("save.reason", request.reason),
+                        KeyValue::new("save.scene", data.scene.clone()),
+                    ],
+                );
+            }
+            // Any checkpoint restarts the interval - no autosave a few
+            // seconds after a scene-change one.
+            timer.0.reset();
+        }
+        Err(e) => warn!("Autosave failed: {e:#}"),
+    }
+}
+
+/// Put the player where a loaded save says, once the current scene has
+/// spawned: same scene = move in place, other scene = transfer there
+/// (spawn_map consumes the PendingArrival).
+fn apply_pending_continue(
+    mut commands: Commands,
+    pending: Option<Res<PendingContinue>>,
+    scene: Res<State<Scene>>,
+    collision_map: Option<Res<CollisionMap>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    mut next_scene: ResMut<NextState<Scene>>,
+    mut seen: ResMut<SeenDialogues>,
+    mut tutorial: ResMut<TutorialProgress>,
+    mut flags: ResMut<GameFlags>,
+    mut playtime: ResMut<Playtime>,
+    mut group_conversations: ResMut<GroupConversationLog>,
+    mut times_talked: ResMut<TimesTalked>,
+    time: Res<Time>,
+    mut unsaved: ResMut<UnsavedChanges>,
+    legacy: Res<LegacyDialogueIds>,
+    mut toasts: MessageWriter<ShowToast>,
+) {
+    let Some(pending) = pending else { return };
+    let Some(map) = collision_map else { return };
+    let Ok(mut transform) = player_query.single_mut() else { return };
+    commands.remove_resource::<PendingContinue>();
+    let data = pending.data.clone().migrate(&legacy);
+
+    let Some(target) = scene_from_str(&data.scene) else {
+        warn!("Save names unknown scene {:?} - starting fresh", data.scene);
+        return;
+    };
+    info!("💾 Continuing from {} ({} at {}, {})",
+        pending.slot.label(), data.scene, data.tile_x, data.tile_y);
+    *seen = data.seen_dialogues.iter().cloned().collect();
+    *tutorial = match &data.tutorial_completed {
+        Some(ids) => ids.iter().cloned().collect(),
+        None => TutorialProgress::finished(),
+    };
+    let loaded: GameFlags = data.flags.iter().cloned().collect();
+    flags.set_if_neq(loaded);
+    playtime.0 = Duration::from_secs(data.playtime_secs);
+    *group_conversations = data.group_conversations.clone();
+    times_talked.restore(data.times_talked.clone(), &data.said_secs_ago, time.elapsed());
+    unsaved.saved(&data);
+
+    if target == *scene.get() {
+        let position = tile_to_world(data.tile_x, data.tile_y, map.width, map.height);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    } else {
+        commands.insert_resource(PendingArrival {
+            spawn_x: data.tile_x,
+            spawn_y: data.tile_y,
+            facing: None,
+        });
+        next_scene.set(target);
+    }
+
+    toasts.write(ShowToast::new(format!("Continuing from your {}", pending.slot.label())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh, empty directory under the system temp dir.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sregame-save-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn save_at(saved_at: u64, scene: &str) -> SaveData {
+        SaveData {
+            version: SAVE_VERSION,
+            saved_at,
+            scene: scene.into(),
+            tile_x: 3,
+            tile_y: 4,
+            seen_dialogues: vec!["00c0ffee00c0ffee".into()],
+            tutorial_completed: Some(vec!["move".into()]),
+            flags: vec!["met_isabella".into()],
+            playtime_secs: 600,
+            progress: 50,
+            group_conversations: GroupConversationLog::default(),
+            times_talked: HashMap::from([("isabella".into(), 2)]),
+            said_secs_ago: HashMap::from([("isabella".into(), 42.5)]),
+        }
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let dir = scratch_dir("round-trip");
+        let data = save_at(100, "TeamDisco");
+        write_save(&dir, SaveSlot::Numbered(1), &data).unwrap();
+        assert_eq!(read_save(&dir, SaveSlot::Numbered(1)), Some(data));
+        assert_eq!(read_save(&dir, SaveSlot::Autosave), None, "slots are distinct files");
+    }
+
+    #[test]
+    fn saves_from_before_skip_seen_load_with_nothing_seen() {
+        let json = format!(
+            r#"{{"version":{SAVE_VERSION},"saved_at":1,"scene":"TownOfEndgame","tile_x":1,"tile_y":2}}"#
+        );
+        let data: SaveData = serde_json::from_str(&json).unwrap();
+        assert!(data.seen_dialogues.is_empty());
+        assert_eq!(data.tutorial_completed, None, "pre-tutorial saves skip the tutorial");
+    }
+
+    #[test]
+    fn a_kill_between_temp_write_and_rename_keeps_the_previous_autosave() {
+        let dir = scratch_dir("kill");
+        let old = save_at(100, "TownOfEndgame");
+        write_save(&dir, SaveSlot::Autosave, &old).unwrap();
+
+        // The process dies after staging the new autosave but before the
+        // rename: commit() never runs.
+        let _orphan = stage(&dir, SaveSlot::Autosave, &save_at(200, "TeamInferno")).unwrap();
+
+        assert_eq!(read_save(&dir, SaveSlot::Autosave), Some(old.clone()), "previous autosave must survive");
+
+        // The next successful autosave simply overwrites the orphaned temp.
+        let new = save_at(300, "TeamMarathon");
+        write_save(&dir, SaveSlot::Autosave, &new).unwrap();
+        assert_eq!(read_save(&dir, SaveSlot::Autosave), Some(new));
+        assert_eq!(read_file(&previous_path(&dir)), Some(old), "and rotates the old one to .prev");
+    }
+
+    #[test]
+    fn a_corrupt_autosave_falls_back_to_the_previous_generation() {
+        let dir = scratch_dir("corrupt");
+        write_save(&dir, SaveSlot::Autosave, &save_at(100, "TownOfEndgame")).unwrap();
+        write_save(&dir, SaveSlot::Autosave, &save_at(200, "TeamDisco")).unwrap();
+        std::fs::write(dir.join("autosave.json"), "{ truncated").unwrap();
+
+        assert_eq!(read_save(&dir, SaveSlot::Autosave).map(|s| s.saved_at), Some(100));
+    }
+
+    #[test]
+    fn the_newest_slot_is_the_most_recently_written() {
+        let dir = scratch_dir("newest");
+        assert_eq!(newest_slot(&dir), None);
+
+        write_save(&dir, SaveSlot::Numbered(2), &save_at(100, "TownOfEndgame")).unwrap();
+        assert_eq!(newest_slot(&dir), Some(SaveSlot::Numbered(2)));
+        write_save(&dir, SaveSlot::Autosave, &save_at(200, "TeamDisco")).unwrap();
+        assert_eq!(newest_slot(&dir), Some(SaveSlot::Autosave));
+        write_save(&dir, SaveSlot::Numbered(3), &save_at(300, "TeamInferno")).unwrap();
+        assert_eq!(newest_slot(&dir), Some(SaveSlot::Numbered(3)));
+    }
+
+    #[test]
+    fn a_slot_with_a_good_header_and_a_corrupt_body_is_damaged() {
+        let dir = scratch_dir("header");
+        let data = save_at(100, "TeamDisco");
+        write_save(&dir, SaveSlot::Numbered(2), &data).unwrap();
+        let path = dir.join("slot2.json");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let (header, _body) = contents.split_once('\n').unwrap();
+        std::fs::write(&path, format!("{header}\n{{ truncated")).unwrap();
+
+        assert_eq!(slot_status(&dir, SaveSlot::Numbered(2)), SlotStatus::Damaged);
+        assert_eq!(read_save(&dir, SaveSlot::Numbered(2)), None);
+        assert_eq!(newest_slot(&dir), None, "nothing there to continue");
+    }
+
+    #[test]
+    fn unreadable_slots_are_listed_as_damaged() {
+        let dir = scratch_dir("damaged");
+        std::fs::write(dir.join("slot3.json"), "not a save").unwrap();
+        assert_eq!(slot_status(&dir, SaveSlot::Numbered(3)), SlotStatus::Damaged);
+        assert_eq!(slot_status(&dir, SaveSlot::Numbered(2)), SlotStatus::Empty);
+        assert_eq!(newest_slot(&dir), None);
+
+        delete_slot(&dir, SaveSlot::Numbered(3)).unwrap();
+        assert_eq!(slot_status(&dir, SaveSlot::Numbered(3)), SlotStatus::Empty);
+    }
+
+    #[test]
+    fn the_old_manual_save_shows_up_as_slot_one() {
+        let dir = scratch_dir("legacy");
+        let data = save_at(100, "TeamMarathon");
+        std::fs::write(dir.join("save.json"), serde_json::to_vec_pretty(&data).unwrap()).unwrap();
+
+        assert_eq!(slot_status(&dir, SaveSlot::Numbered(1)), SlotStatus::Saved(data.header()));
+        assert_eq!(read_save(&dir, SaveSlot::Numbered(1)), Some(data));
+
+        delete_slot(&dir, SaveSlot::Numbered(1)).unwrap();
+        assert_eq!(slot_status(&dir, SaveSlot::Numbered(1)), SlotStatus::Empty);
+    }
+
+    #[test]
+    fn copying_a_slot_duplicates_its_save() {
+        let dir = scratch_dir("copy");
+        let data = save_at(100, "TeamDisco");
+        write_save(&dir, SaveSlot::Autosave, &data).unwrap();
+        copy_slot(&dir, SaveSlot::Autosave, SaveSlot::Numbered(3)).unwrap();
+        assert_eq!(read_save(&dir, SaveSlot::Numbered(3)), Some(data));
+        assert!(copy_slot(&dir, SaveSlot::Numbered(2), SaveSlot::Numbered(1)).is_err(), "nothing in slot 2");
+    }
+
+    #[test]
+    fn progress_is_the_share_of_story_flags_set() {
+        let story: StoryFlags = ["met_isabella", "mentored", "retro_done"].map(String::from).into_iter().collect();
+        let flags: GameFlags = ["met_isabella", "not_a_story_flag"].map(String::from).into_iter().collect();
+        assert_eq!(story.progress(&flags), 33);
+        assert_eq!(StoryFlags::default().progress(&flags), 0);
+    }
+
+    #[test]
+    fn moving_or_setting_flags_leaves_changes_unsaved_until_the_next_save() {
+        let mut unsaved = UnsavedChanges::default();
+        let start = save_at(100, "TownOfEndgame");
+        unsaved.compare(start.clone());
+        assert!(!unsaved.is_dirty(), "where a new game starts is nothing to lose");
+
+        let later = SaveData { saved_at: 200, playtime_secs: 900, ..start.clone() };
+        unsaved.compare(later);
+        assert!(!unsaved.is_dirty(), "time passing alone isn't progress");
+
+        let moved = SaveData { tile_x: 5, ..start.clone() };
+        unsaved.compare(moved.clone());
+        assert!(unsaved.is_dirty());
+        unsaved.compare(start.clone());
+        assert!(!unsaved.is_dirty(), "walking back to the saved tile loses nothing");
+
+        let flagged = SaveData { flags: vec!["met_isabella".into(), "mentored".into()], ..moved };
+        unsaved.compare(flagged.clone());
+        assert!(unsaved.is_dirty());
+        unsaved.saved(&flagged);
+        assert!(!unsaved.is_dirty());
+
+        unsaved.compare(SaveData { scene: "TeamDisco".into(), ..flagged });
+        assert!(unsaved.is_dirty(), "a new scene");
+    }
+
+    #[test]
+    fn saves_from_another_version_are_refused() {
+        let dir = scratch_dir("version");
+        let mut future = save_at(100, "TownOfEndgame");
+        future.version = SAVE_VERSION + 1;
+        write_save(&dir, SaveSlot::Numbered(1), &future).unwrap();
+        assert_eq!(read_save(&dir, SaveSlot::Numbered(1)), None);
+    }
+
+    #[test]
+    fn version_1_saves_know_npc_conversations_by_id_once_loaded() {
+        let town = |speaker: &str| {
+            crate::content::parse_map(&format!(
+                r#"{{ "name": "Tiny", "width": 1, "height": 1, "tiles": [1],
+                     "npcs": [{{ "name": "Isabella", "x": 0, "y": 0, "sprite": "People1", "facing": "down",
+                                "dialogue": {{ "id": "isabella", "speaker": "{speaker}", "portrait": "",
+                                              "lines": ["Welcome."] }} }}] }}"#
+            ))
+            .unwrap()
+        };
+        let legacy = LegacyDialogueIds::from_maps([("town_of_endgame", &town("Isabella"))]);
+        let dir = scratch_dir("migrate");
+        let mut old = save_at(100, "TownOfEndgame");
+        old.version = 1;
+        // Version 1 had no times_talked to write.
+        old.times_talked.clear();
+        old.seen_dialogues = vec![crate::dialogue::content_hash([("Isabella", "Welcome.")]), "00c0ffee00c0ffee".into()];
+        write_save(&dir, SaveSlot::Numbered(1), &old).unwrap();
+
+        let loaded = read_save(&dir, SaveSlot::Numbered(1)).expect("version 1 still loads").migrate(&legacy);
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert!(loaded.times_talked.is_empty(), "conversations weren't counted before version 3");
+        assert_eq!(loaded.seen_dialogues, ["town_of_endgame/isabella", "00c0ffee00c0ffee"], "other hashes stay");
+
+        // Keyed by id, it's still read after the speaker is renamed.
+        let renamed = town("Izzy");
+        let spoken = crate::npc::NpcDialogue::from_data(&renamed.npcs[0].dialogue, None, "data/maps/town_of_endgame.json".into());
+        assert_eq!(spoken.id, loaded.seen_dialogues[0]);
+    }
+}
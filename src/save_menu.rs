@@ -0,0 +1,537 @@
+use bevy::prelude::*;
+use crate::assets::GameAssets;
+use crate::game_state::{GameState, GameStatePlugin, Mode};
+use crate::glyphs::InputPrompt;
+use crate::input::GameInput;
+use crate::map_data::scene_from_str;
+use crate::save::{
+    PendingContinue, SaveDirectory, SaveHeader, SaveSlot, SaveSnapshot, SlotStatus, UnsavedChanges, copy_slot, delete_slot,
+    newest_slot, read_save, slot_status, write_save,
+};
+use crate::thumbnails::{MapThumbnail, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+use crate::toast::ShowToast;
+
+/// The save slot picker, over the paused game (`Mode::Menu`). F5 asks
+/// which slot to save into; F9 - or `--continue` at launch - lists every
+/// slot with its header (when, how long played, where, how far) to load
+/// one, with a thumbnail of the highlighted save's map (drawn by
+/// `MapThumbnailsPlugin` when it's there, a blank panel when it isn't).
+/// When there's any save to load, the list opens on a Continue entry: the
+/// newest of them, autosave or not. In either, C copies the highlighted save to another
+/// slot and Delete removes it; overwriting or deleting a save asks first.
+///
+/// There is no title screen or pause menu yet: these keys are where their
+/// Save and Continue entries will point.
+pub struct SaveMenuPlugin;
+
+impl Plugin for SaveMenuPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        app.add_message::<ShowToast>().init_resource::<UnsavedChanges>().add_systems(
+            Update,
+            (
+                open_save_menu.run_if(in_state(Mode::Exploring)),
+                (navigate_save_menu, sync_save_menu).chain().run_if(in_state(Mode::Menu)),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnEnter(Mode::Menu), spawn_save_menu)
+        .add_systems(OnExit(Mode::Menu), close_save_menu);
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<GameStatePlugin>(app, "SaveMenuPlugin");
+    }
+}
+
+/// Open the picker to load as soon as the first scene is up - `--continue`.
+#[derive(Resource)]
+pub struct OpenSaveMenuOnStart;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuPurpose {
+    Save,
+    Load,
+}
+
+/// Waiting on the player's yes or no.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirm {
+    Save(SaveSlot),
+    Copy { from: SaveSlot, to: SaveSlot },
+    Delete(SaveSlot),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuStep {
+    Choose,
+    /// Picking where the copy goes.
+    CopyTo(SaveSlot),
+    Confirm(Confirm),
+}
+
+/// One line of the picker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuEntry {
+    /// Loading the newest save, whichever slot it's in.
+    Continue(SaveSlot),
+    Slot(SaveSlot, SlotStatus),
+}
+
+#[derive(Resource, Debug)]
+pub struct SaveMenu {
+    purpose: MenuPurpose,
+    /// Each slot and its header, as of the last refresh.
+    rows: Vec<(SaveSlot, SlotStatus)>,
+    /// When loading and there's a save to load: the newest, listed first.
+    continue_from: Option<SaveSlot>,
+    /// Into `entries`.
+    cursor: usize,
+    step: MenuStep,
+}
+
+impl SaveMenu {
+    /// Saving offers the numbered slots; loading Continue - when there's
+    /// a save - then those and the autosave. Either starts at the top.
+    pub fn new(purpose: MenuPurpose, dir: &std::path::Path) -> Self {
+        let mut menu = Self { purpose, rows: Vec::new(), continue_from: None, cursor: 0, step: MenuStep::Choose };
+        menu.refresh(dir);
+        menu
+    }
+
+    fn refresh(&mut self, dir: &std::path::Path) {
+        self.rows = SaveSlot::all()
+            .filter(|&slot| self.purpose == MenuPurpose::Load || slot != SaveSlot::Autosave)
+            .map(|slot| (slot, slot_status(dir, slot)))
+            .collect();
+        self.continue_from = newest_slot(dir).filter(|_| self.purpose == MenuPurpose::Load);
+        self.cursor = self.cursor.min(self.entries().len() - 1);
+    }
+
+    /// Every line, top to bottom: Continue, if there is one, then the slots.
+    pub fn entries(&self) -> Vec<MenuEntry> {
+        let slots = self.rows.iter().map(|(slot, status)| MenuEntry::Slot(*slot, status.clone()));
+        self.continue_from.map(MenuEntry::Continue).into_iter().chain(slots).collect()
+    }
+
+    /// The slot Continue loads; None when it isn't offered.
+    pub fn continues(&self) -> Option<SaveSlot> {
+        self.continue_from
+    }
+
+    /// The header of the save an entry would load, if it has one.
+    fn header(&self, entry: &MenuEntry) -> Option<SaveHeader> {
+        let status = match entry {
+            MenuEntry::Continue(newest) => self.rows.iter().find(|(slot, _)| slot == newest).map(|(_, status)| status)?,
+            MenuEntry::Slot(_, status) => status,
+        };
+        match status {
+            SlotStatus::Saved(header) => Some(header.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn purpose(&self) -> MenuPurpose {
+        self.purpose
+    }
+
+    pub fn step(&self) -> MenuStep {
+        self.step
+    }
+
+    pub fn rows(&self) -> &[(SaveSlot, SlotStatus)] {
+        &self.rows
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn selected(&self) -> MenuEntry {
+        self.entries().swap_remove(self.cursor)
+    }
+
+    /// Up (-1) or down (+1), wrapping at either end.
+    fn move_cursor(&mut self, step: isize) {
+        self.cursor = (self.cursor as isize + step).rem_euclid(self.entries().len() as isize) as usize;
+    }
+
+    fn title(&self) -> String {
+        match self.step {
+            MenuStep::Choose if self.purpose == MenuPurpose::Save => "Save to which slot?".into(),
+            MenuStep::Choose => "Continue from which save?".into(),
+            MenuStep::CopyTo(from) => format!("Copy {} to which slot?", from.label()),
+            MenuStep::Confirm(Confirm::Delete(slot)) => format!("Delete {}?", slot.label()),
+            MenuStep::Confirm(Confirm::Save(slot) | Confirm::Copy { to: slot, .. }) => {
+                format!("Overwrite {}?", slot.label())
+            }
+        }
+    }
+
+    /// The footer, an `InputPrompt` template.
+    fn hint(&self) -> &'static str {
+        match self.step {
+            MenuStep::Choose if self.purpose == MenuPurpose::Save => "{advance}: save   C: copy   Del: delete   {cancel}: back",
+            MenuStep::Choose if matches!(self.selected(), MenuEntry::Continue(_)) => "{advance}: continue   {cancel}: back",
+            MenuStep::Choose => "{advance}: load   C: copy   Del: delete   {cancel}: back",
+            MenuStep::CopyTo(_) => "{advance}: copy here   {cancel}: back",
+            MenuStep::Confirm(_) => "Y / {advance}: yes   N / {cancel}: no",
+        }
+    }
+}
+
+/// One row of the picker: "Slot 2   TeamDisco   40%   1h 05m   2026-10-15 14:02".
+pub fn slot_line(slot: SaveSlot, status: &SlotStatus) -> String {
+    let label = slot.label();
+    let mut name = label[..1].to_uppercase();
+    name.push_str(&label[1..]);
+    status_line(&name, status)
+}
+
+/// The Continue entry: "Continue  (autosave) TeamDisco   40%   1h 05m   ...",
+/// the slot's own row under another name, saying which slot it is.
+pub fn continue_line(slot: SaveSlot, status: &SlotStatus) -> String {
+    format!("Continue  ({}) {}", slot.label(), status_line("", status).trim_start())
+}
+
+fn status_line(name: &str, status: &SlotStatus) -> String {
+    match status {
+        SlotStatus::Empty => format!("{name:<9} - empty -"),
+        SlotStatus::Damaged => format!("{name:<9} damaged"),
+        SlotStatus::Saved(header) => {
+            let minutes = header.playtime_secs / 60;
+            let played = match minutes / 60 {
+                0 => format!("{minutes}m"),
+                hours => format!("{hours}h {:02}m", minutes % 60),
+            };
+            let saved_at = chrono::DateTime::from_timestamp(header.saved_at as i64, 0)
+                .map(|utc| utc.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            format!("{name:<9} {:<18} {:>3}%   {played:>7}   {saved_at}", header.scene, header.progress)
+        }
+    }
+}
+
+#[derive(Component)]
+struct SaveMenuRoot;
+
+#[derive(Component)]
+struct SaveMenuTitle;
+
+/// The nth slot's row.
+#[derive(Component)]
+struct SaveMenuRow(usize);
+
+#[derive(Component)]
+struct SaveMenuHint;
+
+#[derive(Component)]
+struct SaveMenuThumbnail;
+
+fn open_save_menu(
+    mut commands: Commands,
+    keyboard: GameInput,
+    save_dir: Option<Res<SaveDirectory>>,
+    on_start: Option<Res<OpenSaveMenuOnStart>>,
+    collision_map: Option<Res<crate::tilemap::CollisionMap>>,
+    mut next_mode: ResMut<NextState<Mode>>,
+) {
+    let purpose = if keyboard.just_pressed(KeyCode::F5) {
+        MenuPurpose::Save
+    } else if keyboard.just_pressed(KeyCode::F9) || (on_start.is_some() && collision_map.is_some()) {
+        MenuPurpose::Load
+    } else {
+        return;
+    };
+    let Some(dir) = save_dir else { return };
+    commands.remove_resource::<OpenSaveMenuOnStart>();
+    commands.insert_resource(SaveMenu::new(purpose, &dir.0));
+    next_mode.set(Mode::Menu);
+}
+
+fn navigate_save_menu(
+    mut commands: Commands,
+    keyboard: GameInput,
+    menu: Option<ResMut<SaveMenu>>,
+    save_dir: Option<Res<SaveDirectory>>,
+    snapshot: SaveSnapshot,
+    mut unsaved: ResMut<UnsavedChanges>,
+    mut next_mode: ResMut<NextState<Mode>>,
+    mut toasts: MessageWriter<ShowToast>,
+) {
+    let (Some(mut menu), Some(dir)) = (menu, save_dir) else { return };
+    let dir = &dir.0;
+    let pick = keyboard.just_pressed(KeyCode::Space)
+        || keyboard.just_pressed(KeyCode::Enter)
+        || keyboard.just_pressed(KeyCode::KeyE);
+    let back = keyboard.just_pressed(KeyCode::Escape);
+
+    if let MenuStep::Confirm(confirm) = menu.step {
+        if pick || keyboard.just_pressed(KeyCode::KeyY) {
+            let close = perform(confirm, dir, &snapshot, &mut unsaved, &mut toasts);
+            menu.refresh(dir);
+            menu.step = MenuStep::Choose;
+            if close {
+                next_mode.set(Mode::Exploring);
+            }
+        } else if back || keyboard.just_pressed(KeyCode::KeyN) {
+            menu.step = MenuStep::Choose;
+        }
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyW) || keyboard.just_pressed(KeyCode::ArrowUp) {
+        menu.move_cursor(-1);
+    } else if keyboard.just_pressed(KeyCode::KeyS) || keyboard.just_pressed(KeyCode::ArrowDown) {
+        menu.move_cursor(1);
+    }
+    let (slot, status) = match menu.selected() {
+        MenuEntry::Slot(slot, status) => (slot, status),
+        // Continue only loads; the slot it names is listed below it for
+        // anything else.
+        MenuEntry::Continue(slot) => {
+            match menu.step {
+                MenuStep::Choose if pick => load(&mut commands, &mut menu, dir, slot, &mut next_mode, &mut toasts),
+                MenuStep::Choose if back => next_mode.set(Mode::Exploring),
+                MenuStep::CopyTo(_) if back => menu.step = MenuStep::Choose,
+                MenuStep::CopyTo(_) if pick => {
+                    toasts.write(ShowToast::new("Pick a slot to copy to"));
+                }
+                _ => {}
+            }
+            return;
+        }
+    };
+    let occupied = status != SlotStatus::Empty;
+
+    match menu.step {
+        MenuStep::CopyTo(_) if back => menu.step = MenuStep::Choose,
+        MenuStep::CopyTo(from) if pick => {
+            if slot == from || slot == SaveSlot::Autosave {
+                toasts.write(ShowToast::new(format!("Can't copy to the {}", slot.label())));
+            } else if occupied {
+                menu.step = MenuStep::Confirm(Confirm::Copy { from, to: slot });
+            } else {
+                perform(Confirm::Copy { from, to: slot }, dir, &snapshot, &mut unsaved, &mut toasts);
+                menu.refresh(dir);
+                menu.step = MenuStep::Choose;
+            }
+        }
+        MenuStep::Choose if back => next_mode.set(Mode::Exploring),
+        MenuStep::Choose if pick => match (menu.purpose, status) {
+            (MenuPurpose::Save, SlotStatus::Empty) => {
+                if perform(Confirm::Save(slot), dir, &snapshot, &mut unsaved, &mut toasts) {
+                    next_mode.set(Mode::Exploring);
+                }
+            }
+            (MenuPurpose::Save, _) => menu.step = MenuStep::Confirm(Confirm::Save(slot)),
+            (MenuPurpose::Load, SlotStatus::Empty) => {
+                toasts.write(ShowToast::new(format!("The {} is empty", slot.label())));
+            }
+            (MenuPurpose::Load, _) => load(&mut commands, &mut menu, dir, slot, &mut next_mode, &mut toasts),
+        },
+        MenuStep::Choose if keyboard.just_pressed(KeyCode::KeyC) => {
+            if matches!(status, SlotStatus::Saved(_)) {
+                menu.step = MenuStep::CopyTo(slot);
+            } else {
+                toasts.write(ShowToast::new(format!("Nothing to copy in the {}", slot.label())));
+            }
+        }
+        MenuStep::Choose if occupied && (keyboard.just_pressed(KeyCode::Delete) || keyboard.just_pressed(KeyCode::KeyX)) => {
+            menu.step = MenuStep::Confirm(Confirm::Delete(slot));
+        }
+        _ => {}
+    }
+}
+
+/// Load `slot` and close the picker; a save that turns out unreadable
+/// stays listed, now as damaged.
+fn load(
+    commands: &mut Commands,
+    menu: &mut SaveMenu,
+    dir: &std::path::Path,
+    slot: SaveSlot,
+    next_mode: &mut NextState<Mode>,
+    toasts: &mut MessageWriter<ShowToast>,
+) {
+    match read_save(dir, slot) {
+        Some(data) => {
+            commands.insert_resource(PendingContinue { slot, data });
+            next_mode.set(Mode::Exploring);
+        }
+        None => {
+            toasts.write(ShowToast::new(format!("The {} is damaged", slot.label())));
+            menu.refresh(dir);
+        }
+    }
+}
+
+/// Do what was confirmed. Whether the picker should close: a save closes
+/// it, a copy or delete leaves it open to show the result.
+fn perform(
+    confirm: Confirm,
+    dir: &std::path::Path,
+    snapshot: &SaveSnapshot,
+    unsaved: &mut UnsavedChanges,
+    toasts: &mut MessageWriter<ShowToast>,
+) -> bool {
+    let (result, done) = match confirm {
+        Confirm::Save(slot) => {
+            let Some(data) = snapshot.capture() else { return false };
+            info!("💾 Saving to {} in {} at ({}, {})", slot.label(), data.scene, data.tile_x, data.tile_y);
+            let result = write_save(dir, slot, &data);
+            if result.is_ok() {
+                unsaved.saved(&data);
+            }
+            (result, format!("Saved to {}", slot.label()))
+        }
+        Confirm::Copy { from, to } => (copy_slot(dir, from, to), format!("Copied {} to {}", from.label(), to.label())),
+        Confirm::Delete(slot) => (delete_slot(dir, slot), format!("Deleted {}", slot.label())),
+    };
+    match result {
+        Ok(()) => {
+            toasts.write(ShowToast::new(done));
+            matches!(confirm, Confirm::Save(_))
+        }
+        Err(e) => {
+            warn!("Save menu: {e:#}");
+            toasts.write(ShowToast::new("That didn't work - see the log"));
+            false
+        }
+    }
+}
+
+fn spawn_save_menu(mut commands: Commands, menu: Option<Res<SaveMenu>>, game_assets: Option<Res<GameAssets>>) {
+    let Some(menu) = menu else { return };
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    // At 1080p: 26px title, 20px rows, 16px hint - Vh like the toast.
+    let text = |px: f32| TextFont { font: font.clone().into(), font_size: FontSize::Vh(px / 10.8), ..default() };
+
+    commands
+        .spawn((
+            SaveMenuRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(20.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.92)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((SaveMenuTitle, Text::new(menu.title()), text(26.0), TextColor(Color::WHITE)));
+                    // Where the highlighted save was made; hidden on other rows.
+                    panel.spawn((
+                        SaveMenuThumbnail,
+                        ImageNode::default(),
+                        Node {
+                            display: Display::None,
+                            width: Val::Px(THUMBNAIL_WIDTH as f32 * 2.0),
+                            height: Val::Px(THUMBNAIL_HEIGHT as f32 * 2.0),
+                            ..default()
+                        },
+                    ));
+                    for index in 0..menu.entries().len() {
+                        panel.spawn((SaveMenuRow(index), Text::default(), text(20.0), TextColor(Color::WHITE)));
+                    }
+                    panel.spawn((
+                        SaveMenuHint,
+                        InputPrompt::new(menu.hint(), font.clone(), 16.0, Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                        Node { align_items: AlignItems::Center, ..default() },
+                    ));
+                });
+        });
+}
+
+/// Redraws the picker from `SaveMenu`: "> " on the cursor's row, empty
+/// slots dimmed, damaged ones in red, Continue in gold, and the map of the
+/// save under the cursor.
+fn sync_save_menu(
+    menu: Option<Res<SaveMenu>>,
+    mut title: Query<&mut Text, (With<SaveMenuTitle>, Without<SaveMenuRow>)>,
+    mut rows: Query<(&SaveMenuRow, &mut Text, &mut TextColor)>,
+    mut hint: Query<&mut InputPrompt, With<SaveMenuHint>>,
+    mut thumbnail: Query<(&mut ImageNode, &mut Node), With<SaveMenuThumbnail>>,
+    mut thumbnails: MapThumbnail,
+) {
+    let Some(menu) = menu.filter(|menu| menu.is_changed()) else { return };
+    if let Ok(mut text) = title.single_mut() {
+        **text = menu.title();
+    }
+    if let Ok(mut prompt) = hint.single_mut()
+        && prompt.template != menu.hint()
+    {
+        prompt.template = menu.hint().into();
+    }
+    let entries = menu.entries();
+    for (row, mut text, mut color) in &mut rows {
+        let Some(entry) = entries.get(row.0) else { continue };
+        let marker = if row.0 == menu.cursor { "> " } else { "  " };
+        let (line, color_now) = match entry {
+            MenuEntry::Continue(slot) => {
+                let status = menu.header(entry).map_or(SlotStatus::Damaged, SlotStatus::Saved);
+                (continue_line(*slot, &status), Color::srgb(1.0, 0.85, 0.4))
+            }
+            MenuEntry::Slot(slot, status) => (slot_line(*slot, status), match status {
+                SlotStatus::Empty => Color::srgba(1.0, 1.0, 1.0, 0.45),
+                SlotStatus::Damaged => Color::srgb(1.0, 0.45, 0.4),
+                SlotStatus::Saved(_) => Color::WHITE,
+            }),
+        };
+        **text = format!("{marker}{line}");
+        color.set_if_neq(TextColor(color_now));
+    }
+    if let Ok((mut image, mut node)) = thumbnail.single_mut() {
+        let scene = entries.get(menu.cursor).and_then(|entry| menu.header(entry)).and_then(|header| scene_from_str(&header.scene));
+        match scene {
+            Some(scene) => {
+                image.image = thumbnails.get_or_generate(scene);
+                node.display = Display::Flex;
+            }
+            None => node.display = Display::None,
+        }
+    }
+}
+
+fn close_save_menu(mut commands: Commands, roots: Query<Entity, With<SaveMenuRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn();
+    }
+    commands.remove_resource::<SaveMenu>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_show_what_each_slot_holds() {
+        let header = SaveHeader {
+            version: 1,
+            saved_at: 0,
+            playtime_secs: 3900,
+            scene: "TeamDisco".into(),
+            progress: 40,
+        };
+        let continues = continue_line(SaveSlot::Autosave, &SlotStatus::Saved(header.clone()));
+        assert!(continues.starts_with("Continue  (autosave) TeamDisco"), "{continues}");
+        let saved = slot_line(SaveSlot::Numbered(2), &SlotStatus::Saved(header));
+        assert!(saved.starts_with("Slot 2"), "{saved}");
+        assert!(saved.contains("TeamDisco") && saved.contains("40%") && saved.contains("1h 05m"), "{saved}");
+        assert!(slot_line(SaveSlot::Autosave, &SlotStatus::Damaged).starts_with("Autosave  damaged"));
+        assert!(slot_line(SaveSlot::Numbered(1), &SlotStatus::Empty).contains("empty"));
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::{KeyValue, trace::Span as _};
+use crate::game_state::{GameState, Scene};
+use crate::instrumentation::{MetricsBundle, PlayerSessionTrace, init_metrics};
+use crate::player::Player;
+
+/// How long players actually spend in each map, for level-design feedback
+/// ("70% of the session is TownOfEndgame"). Time is virtual time, so a
+/// paused clock adds nothing; it keeps counting through `Mode::Dialogue`,
+/// which is a sibling of `Scene` - a conversation belongs to the scene it
+/// happened in.
+///
+/// Exported as the `game.scene.active_seconds` counter (labelled
+/// `game.scene`), flushed every few seconds and on every scene change, and
+/// as per-scene totals on the session span when the game exits.
+pub struct SceneTimingsPlugin;
+
+impl Plugin for SceneTimingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SceneTimings>()
+            .insert_resource(SceneTimingsExportTimer(Timer::new(EXPORT_INTERVAL, TimerMode::Repeating)))
+            .add_systems(Update, (accumulate_scene_time, export_scene_time)
+                .chain()
+                .run_if(in_state(GameState::Playing)))
+            // Last, so the AppExit written anywhere during Update is visible.
+            .add_systems(Last, record_scene_totals_on_exit);
+        init_metrics::<SceneMetrics>(app);
+    }
+}
+
+/// `game.scene.active_seconds`, by scene.
+#[derive(Resource)]
+pub struct SceneMetrics {
+    pub active_seconds: Counter<f64>,
+}
+
+impl MetricsBundle for SceneMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            active_seconds: meter
+                .f64_counter("game.scene.active_seconds")
+                .with_description("Time spent in each scene, dialogue included (see scene_timings.rs)")
+                .with_unit("s")
+                .build(),
+        }
+    }
+}
+
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Resource, Default, Debug)]
+pub struct SceneTimings {
+    totals: HashMap<Scene, Duration>,
+    /// Per-scene time not yet added to the counter.
+    unexported: HashMap<Scene, Duration>,
+}
+
+impl SceneTimings {
+    pub fn add(&mut self, scene: Scene, delta: Duration) {
+        *self.totals.entry(scene).or_default() += delta;
+        *self.unexported.entry(scene).or_default() += delta;
+    }
+
+    pub fn total(&self, scene: Scene) -> Duration {
+        self.totals.get(&scene).copied().unwrap_or_default()
+    }
+
+    /// Every scene visited this session, longest first.
+    pub fn totals(&self) -> Vec<(Scene, Duration)> {
+        let mut totals: Vec<_> = self.totals.iter().map(|(s, d)| (*s, *d)).collect();
+        totals.sort_by_key(|&(_, total)| std::cmp::Reverse(total));
+        totals
+    }
+
+    fn flush(&mut self, metrics: Option<&SceneMetrics>) {
+        for (scene, delta) in self.unexported.drain() {
+            if let Some(metrics) = metrics {
+                metrics.active_seconds.add(
+                    delta.as_secs_f64(),
+                    &[KeyValue::new("game.scene", format!("{scene:?}"))],
+                );
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SceneTimingsExportTimer(Timer);
+
+fn accumulate_scene_time(
+    time: Res<Time>,
+    scene: Res<State<Scene>>,
+    mut timings: ResMut<SceneTimings>,
+) {
+    timings.add(*scene.get(), time.delta());
+}
+
+fn export_scene_time(
+    time: Res<Time>,
+    scene: Res<State<Scene>>,
+    mut timer: ResMut<SceneTimingsExportTimer>,
+    mut last_scene: Local<Option<Scene>>,
+    mut timings: ResMut<SceneTimings>,
+    metrics: Option<Res<SceneMetrics>>,
+) {
+    let scene_changed = last_scene.is_some_and(|last| last != *scene.get());
+    *last_scene = Some(*scene.get());
+    if timer.0.tick(time.delta()).just_finished() || scene_changed {
+        timings.flush(metrics.as_deref());
+    }
+}
+
+fn record_scene_totals_on_exit(
+    mut exits: MessageReader<AppExit>,
+    mut timings: ResMut<SceneTimings>,
+    metrics: Option<Res<SceneMetrics>>,
+    mut session: Query<&mut PlayerSessionTrace, With<Player>>,
+) {
+    if exits.read().count() == 0 {
+        return;
+    }
+
+    timings.flush(metrics.as_deref());
+    let totals = timings.totals();
+    for (scene, total) in &totals {
+        info!("⏱️ {scene:?}: {:.1}s", total.as_secs_f32());
+    }
+    if let Ok(mut trace) = session.single_mut() {
+        for (scene, total) in totals {
+            trace.span.set_attribute(KeyValue::new(
+                format!("scene.{scene:?}.active_seconds"),
+                total.as_secs_f64(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use crate::game_state::Mode;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn playing_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, SceneTimingsPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .init_state::<GameState>()
+            .add_sub_state::<Scene>()
+            .add_sub_state::<Mode>();
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        app.update();
+        app
+    }
+
+    #[test]
+    fn time_is_attributed_to_the_current_scene_including_dialogue() {
+        let mut app = playing_app();
+        for _ in 0..10 {
+            app.update();
+        }
+        let town_before_talking = app.world().resource::<SceneTimings>().total(Scene::TownOfEndgame);
+
+        app.world_mut().resource_mut::<NextState<Mode>>().set(Mode::Dialogue);
+        for _ in 0..10 {
+            app.update();
+        }
+        let town_after_talking = app.world().resource::<SceneTimings>().total(Scene::TownOfEndgame);
+        assert_eq!(town_after_talking - town_before_talking, FRAME * 10, "dialogue counts toward the town");
+
+        app.world_mut().resource_mut::<NextState<Scene>>().set(Scene::TeamDisco);
+        for _ in 0..5 {
+            app.update();
+        }
+        let timings = app.world().resource::<SceneTimings>();
+        assert_eq!(timings.total(Scene::TownOfEndgame), town_after_talking, "town stops counting on exit");
+        assert_eq!(timings.total(Scene::TeamDisco), FRAME * 5);
+        assert_eq!(timings.totals()[0].0, Scene::TownOfEndgame, "longest first");
+    }
+}
@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::Duration;
+use opentelemetry::{KeyValue, trace::Span as _};
+use crate::hooks::{DialogueEndOutcome, DialogueEnded, DialogueLineShown, DialogueStarted, NpcInteracted};
+use crate::instrumentation::PlayerSessionTrace;
+use crate::player::Player;
+use crate::splits::clock;
+
+/// Which NPCs players actually engaged with, for workshop facilitators:
+/// per NPC, how often they were talked to, how much of what they had to
+/// say was read, how many conversations were read to the end or walked
+/// away from (Escape), and the time spent in them. Fed from the hooks
+/// (hooks.rs), so a scripted scene with no NPC counts for no one.
+///
+/// At exit it's printed as a table, written to `--summary-json` (see
+/// `SummaryJson`), and added to the session span as one
+/// `npc.engagement` event per NPC - only the `SPAN_EVENT_LIMIT` NPCs
+/// talked to longest, to bound the payload; the JSON has them all. NPCs
+/// are keyed by `Npc::id`, which survives renames.
+pub struct SessionSummaryPlugin;
+
+impl Plugin for SessionSummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SessionSummary>()
+            .add_message::<DialogueStarted>()
+            .add_message::<DialogueLineShown>()
+            .add_message::<DialogueEnded>()
+            .add_message::<NpcInteracted>()
+            // Last, so the hooks and AppExit written anywhere during Update
+            // are visible.
+            .add_systems(Last, (record_npc_engagement, report_summary_on_exit).chain());
+    }
+}
+
+/// NPCs past this many get no span event (they're still in the JSON).
+pub const SPAN_EVENT_LIMIT: usize = 20;
+
+/// Where to write the summary as JSON at exit. main.rs inserts it from
+/// `--summary-json`.
+#[derive(Resource, Debug, Clone)]
+pub struct SummaryJson(pub PathBuf);
+
+/// One NPC's share of the session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NpcEngagement {
+    /// The name last seen, for people reading the table.
+    pub name: String,
+    pub interactions: u32,
+    /// Boxes shown, at most `lines_available` per conversation.
+    pub lines_read: usize,
+    /// Boxes in the conversations opened (see `DialogueStarted::lines`).
+    pub lines_available: usize,
+    /// Read to the end, or skipped through as already read.
+    pub completed: u32,
+    /// Closed with Escape.
+    pub abandoned: u32,
+    pub conversation_time: Duration,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct SessionSummary {
+    npcs: BTreeMap<String, NpcEngagement>,
+    /// NPC entity to id, from the latest `NpcInteracted`.
+    npc_ids: HashMap<Entity, String>,
+    /// By dialogue id, conversations with an NPC still open.
+    open: HashMap<String, OpenConversation>,
+}
+
+#[derive(Debug)]
+struct OpenConversation {
+    npc: String,
+    started: Duration,
+    lines_available: usize,
+    lines_read: usize,
+}
+
+impl SessionSummary {
+    pub fn npc(&self, id: &str) -> Option<&NpcEngagement> {
+        self.npcs.get(id)
+    }
+
+    /// Every NPC talked to, longest in conversation first (then by id).
+    pub fn npcs(&self) -> Vec<(&str, &NpcEngagement)> {
+        let mut npcs: Vec<_> = self.npcs.iter().map(|(id, npc)| (id.as_str(), npc)).collect();
+        npcs.sort_by_key(|&(_, npc)| std::cmp::Reverse(npc.conversation_time));
+        npcs
+    }
+
+    pub fn interacted(&mut self, event: &NpcInteracted) {
+        self.npc_ids.insert(event.entity, event.id.clone());
+        let npc = self.npcs.entry(event.id.clone()).or_default();
+        npc.name = event.name.clone();
+        npc.interactions += 1;
+    }
+
+    pub fn started(&mut self, event: &DialogueStarted, now: Duration) {
+        let Some(npc) = event.npc_entity.and_then(|entity| self.npc_ids.get(&entity)) else { return };
+        let npc = npc.clone();
+        self.npcs.entry(npc.clone()).or_default().lines_available += event.lines;
+        self.open.insert(
+            event.id.clone(),
+            OpenConversation { npc, started: now, lines_available: event.lines, lines_read: 0 },
+        );
+    }
+
+    pub fn line_shown(&mut self, event: &DialogueLineShown) {
+        let Some(open) = self.open.get_mut(&event.id) else { return };
+        if open.lines_read < open.lines_available {
+            open.lines_read += 1;
+            if let Some(npc) = self.npcs.get_mut(&open.npc) {
+                npc.lines_read += 1;
+            }
+        }
+    }
+
+    pub fn ended(&mut self, event: &DialogueEnded, now: Duration) {
+        let Some(open) = self.open.remove(&event.id) else { return };
+        let npc = self.npcs.entry(open.npc).or_default();
+        match event.outcome {
+            DialogueEndOutcome::Completed | DialogueEndOutcome::Skipped => npc.completed += 1,
+            DialogueEndOutcome::Forced => npc.abandoned += 1,
+        }
+        npc.conversation_time += now.saturating_sub(open.started);
+    }
+
+    pub fn table(&self) -> String {
+        let npcs = self.npcs();
+        let width = npcs.iter().map(|(id, _)| id.len()).max().unwrap_or(0).max("NPC".len());
+        let mut out = format!(
+            "{:<width$}  {:>6}  {:>9}  {:>4}  {:>9}  {:>9}\n",
+            "NPC", "Talks", "Lines", "Done", "Abandoned", "Time"
+        );
+        for (id, npc) in npcs {
+            let _ = writeln!(
+                out,
+                "{id:<width$}  {:>6}  {:>9}  {:>4}  {:>9}  {:>9}",
+                npc.interactions,
+                format!("{}/{}", npc.lines_read, npc.lines_available),
+                npc.completed,
+                npc.abandoned,
+                clock(npc.conversation_time),
+            );
+        }
+        out
+    }
+
+    /// Every NPC, by id.
+    pub fn to_json(&self) -> serde_json::Value {
+        let npcs: serde_json::Map<String, serde_json::Value> = self
+            .npcs
+            .iter()
+            .map(|(id, npc)| {
+                let npc = serde_json::json!({
+                    "name": npc.name,
+                    "interactions": npc.interactions,
+                    "lines_read": npc.lines_read,
+                    "lines_available": npc.lines_available,
+                    "dialogues_completed": npc.completed,
+                    "dialogues_abandoned": npc.abandoned,
+                    "conversation_seconds": npc.conversation_time.as_secs_f64(),
+                });
+                (id.clone(), npc)
+            })
+            .collect();
+        serde_json::json!({ "npcs": npcs })
+    }
+}
+
+fn record_npc_engagement(
+    time: Res<Time>,
+    mut summary: ResMut<SessionSummary>,
+    mut interactions: MessageReader<NpcInteracted>,
+    mut started: MessageReader<DialogueStarted>,
+    mut lines: MessageReader<DialogueLineShown>,
+    mut ended: MessageReader<DialogueEnded>,
+) {
+    let now = time.elapsed();
+    for event in interactions.read() {
+        summary.interacted(event);
+    }
+    for event in started.read() {
+        summary.started(event, now);
+    }
+    for event in lines.read() {
+        summary.line_shown(event);
+    }
+    for event in ended.read() {
+        summary.ended(event, now);
+    }
+}
+
+fn report_summary_on_exit(
+    mut exits: MessageReader<AppExit>,
+    summary: Res<SessionSummary>,
+    json: Option<Res<SummaryJson>>,
+    mut session: Query<&mut PlayerSessionTrace, With<Player>>,
+) {
+    if exits.read().count() == 0 {
+        return;
+    }
+
+    if !summary.npcs.is_empty() {
+        println!("\n{}", summary.table());
+    }
+    if let Some(SummaryJson(path)) = json.as_deref() {
+        let written = serde_json::to_vec_pretty(&summary.to_json())
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = written {
+            warn!("📋 Couldn't write the session summary to {}: {e}", path.display());
+        }
+    }
+    if let Ok(mut trace) = session.single_mut() {
+        for (id, npc) in summary.npcs().into_iter().take(SPAN_EVENT_LIMIT) {
+            trace.span.add_event(
+                "npc.engagement",
+                vec![
+                    KeyValue::new("npc.id", id.to_string()),
+                    KeyValue::new("npc.name", npc.name.clone()),
+                    KeyValue::new("npc.interactions", i64::from(npc.interactions)),
+                    KeyValue::new("npc.lines_read", npc.lines_read as i64),
+                    KeyValue::new("npc.lines_available", npc.lines_available as i64),
+                    KeyValue::new("npc.dialogues_completed", i64::from(npc.completed)),
+                    KeyValue::new("npc.dialogues_abandoned", i64::from(npc.abandoned)),
+                    KeyValue::new("npc.conversation_seconds", npc.conversation_time.as_secs_f64()),
+                ],
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversations_are_counted_for_the_npc_they_were_with() {
+        let mut summary = SessionSummary::default();
+        let isabella = Entity::from_raw_u32(1).unwrap();
+        let interacted = NpcInteracted { id: "isabella".into(), name: "Isabella".into(), entity: isabella };
+        let started = |id: &str, npc_entity| DialogueStarted { id: id.into(), speaker: "Isabella".into(), npc_entity, lines: 3 };
+        let line = |id: &str| DialogueLineShown { id: id.into(), index: 0, skipped: false };
+
+        summary.interacted(&interacted);
+        summary.started(&started("hello", Some(isabella)), Duration::from_secs(1));
+        for _ in 0..4 {
+            summary.line_shown(&line("hello"));
+        }
+        summary.ended(&DialogueEnded { id: "hello".into(), outcome: DialogueEndOutcome::Completed }, Duration::from_secs(5));
+        // A scripted scene belongs to no one.
+        summary.started(&started("cutscene", None), Duration::from_secs(6));
+        summary.line_shown(&line("cutscene"));
+        summary.ended(&DialogueEnded { id: "cutscene".into(), outcome: DialogueEndOutcome::Forced }, Duration::from_secs(7));
+
+        let npc = summary.npc("isabella").unwrap();
+        assert_eq!((npc.interactions, npc.lines_read, npc.lines_available), (1, 3, 3), "no more read than there was");
+        assert_eq!((npc.completed, npc.abandoned), (1, 0));
+        assert_eq!(npc.conversation_time, Duration::from_secs(4));
+        assert_eq!(summary.npcs().len(), 1);
+        assert!(summary.table().lines().any(|row| row.starts_with("isabella") && row.contains("3/3")), "{}", summary.table());
+        assert_eq!(summary.to_json()["npcs"]["isabella"]["dialogues_completed"], 1);
+    }
+}
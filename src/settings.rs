@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::{Context, Result};
+#[cfg(not(target_arch = "wasm32"))]
+use serde::{Deserialize, Serialize};
+use crate::input::InputMap;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::input::{Binding, button_from_name, key_from_name};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::save::SaveDirectory;
+
+/// Player-facing options. The defaults are the shipped experience, and
+/// main.rs maps command-line flags onto them (`--no-shadows`,
+/// `--no-tutorial`, `--ambient-chatter`, `--no-rumble`, `--dev-console`,
+/// `--click-to-walk`).
+/// Systems read `GameSettings` every frame or react to `resource_changed`,
+/// so a menu that edits it later needs no plumbing.
+///
+/// The one settings screen so far is Controls (controls_menu.rs): its
+/// bindings (`InputMap`) are kept in the settings file, `settings.json` in
+/// `SaveDirectory`, read at startup. Native only, like saves.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // init, not insert: an entry point that already inserted settings
+        // from its flags keeps them.
+        app.init_resource::<GameSettings>().init_resource::<InputMap>();
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Startup, load_controls);
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct GameSettings {
+    /// Oval drop shadows under the player and NPCs (shadow.rs).
+    pub shadows: bool,
+    /// First-run control hints (tutorial.rs).
+    pub tutorial: bool,
+    /// How often NPCs chatter on their own (ambient.rs): 1.0 is the
+    /// authored pace, 2.0 twice as often, 0 never.
+    pub ambient_chatter: f32,
+    /// Controller rumble (rumble.rs).
+    pub rumble: bool,
+    /// The developer console (console.rs). On in debug builds.
+    pub dev_console: bool,
+    /// Clicking an NPC out of reach walks the player over to talk to them
+    /// (click_to_talk.rs). Off, the click only names them.
+    pub click_to_walk: bool,
+    /// Losing window focus pauses the game clock until it comes back
+    /// (focus.rs). Off, the town carries on without the player.
+    pub pause_when_unfocused: bool,
+    /// Keep sampling frame times while the window is unfocused (focus.rs).
+    /// Off, idle frames stay out of the dashboard and stall reports.
+    pub frame_metrics_when_unfocused: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            shadows: true,
+            tutorial: true,
+            ambient_chatter: 1.0,
+            rumble: true,
+            dev_console: cfg!(debug_assertions),
+            click_to_walk: false,
+            pause_when_unfocused: true,
+            frame_metrics_when_unfocused: false,
+        }
+    }
+}
+
+/// The settings file: `{"controls": {"keys": {"interact": "KeyQ"},
+/// "buttons": {"skip": "North"}}}`, actions by `GameAction::name`, keys and
+/// buttons by their Bevy names. Actions it doesn't mention keep their
+/// defaults.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SettingsFile {
+    #[serde(default)]
+    controls: ControlsFile,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ControlsFile {
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    buttons: BTreeMap<String, String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn settings_path(dir: &Path) -> PathBuf {
+    dir.join("settings.json")
+}
+
+/// The bindings in `dir`'s settings file; `None` without one. Entries
+/// naming an unknown action, key or button - or one it can't be bound to -
+/// are skipped with a warning rather than failing the rest.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_controls(dir: &Path) -> Result<Option<InputMap>> {
+    let path = settings_path(dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: SettingsFile =
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut map = InputMap::default();
+    let keys = file.controls.keys.iter().map(|(action, key)| (action, key, key_from_name(key).map(Binding::Key)));
+    let buttons = file.controls.buttons.iter().map(|(action, button)| (action, button, button_from_name(button).map(Binding::Button)));
+    for (action_name, name, binding) in keys.chain(buttons) {
+        let action = crate::input::GameAction::from_name(action_name);
+        match (action, binding) {
+            (Some(action), Some(binding)) if InputMap::refusal(action, binding).is_none() => map.set(action, binding),
+            // Cancel's key is always Escape; saying so is fine.
+            (Some(action), Some(binding)) if map.binding(action, binding) == binding => {}
+            _ => warn!("{}: can't bind {action_name:?} to {name:?}, keeping the default", path.display()),
+        }
+    }
+    Ok(Some(map))
+}
+
+/// Write `map` to `dir`'s settings file, every action spelled out.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_controls(dir: &Path, map: &InputMap) -> Result<()> {
+    let controls = ControlsFile {
+        keys: crate::input::GameAction::ALL
+            .into_iter()
+            .map(|action| (action.name().to_string(), format!("{:?}", map.key(action))))
+            .collect(),
+        buttons: crate::input::GameAction::ALL
+            .into_iter()
+            .map(|action| (action.name().to_string(), format!("{:?}", map.button(action))))
+            .collect(),
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = settings_path(dir);
+    let json = serde_json::to_string_pretty(&SettingsFile { controls })?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_controls(save_dir: Option<Res<SaveDirectory>>, mut map: ResMut<InputMap>) {
+    let Some(dir) = save_dir else { return };
+    match read_controls(&dir.0) {
+        Ok(Some(loaded)) => {
+            if !loaded.is_default() {
+                info!("🎮 Controls from {}", settings_path(&dir.0).display());
+            }
+            *map = loaded;
+        }
+        Ok(None) => {}
+        Err(e) => warn!("{e:#} - using the default controls"),
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::input::GameAction;
+
+    #[test]
+    fn rebound_controls_survive_a_round_trip() {
+        let dir = std::env::temp_dir().join(format!("sregame-settings-{}", std::process::id()));
+        assert!(read_controls(&dir).unwrap().is_none());
+
+        let mut map = InputMap::default();
+        map.bind(GameAction::Interact, Binding::Key(KeyCode::KeyL));
+        map.bind(GameAction::Skip, Binding::Button(GamepadButton::RightTrigger));
+        write_controls(&dir, &map).unwrap();
+        assert_eq!(read_controls(&dir).unwrap(), Some(map));
+
+        // A hand edit gone wrong costs that one entry.
+        std::fs::write(
+            settings_path(&dir),
+            r#"{ "controls": { "keys": { "interact": "KeyQ", "skip": "Escape", "jump": "KeyJ" } } }"#,
+        )
+        .unwrap();
+        let read = read_controls(&dir).unwrap().unwrap();
+        assert_eq!(read.key(GameAction::Interact), KeyCode::KeyQ);
+        assert_eq!(read.key(GameAction::Skip), KeyCode::Tab);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crate::assets::GameAssets;
+use crate::character_sheet::FRAME_SIZE;
+use crate::depth::{SHADOW_Z_OFFSET, YSorted};
+use crate::game_state::GameState;
+use crate::npc::Npc;
+use crate::player::Player;
+use crate::settings::GameSettings;
+use crate::tilemap::IndoorMap;
+
+/// Oval drop shadows under the player and every NPC (not map objects -
+/// a sign is part of the scenery). Without them the characters float on
+/// the tiles - most visibly on the town's flat cobbles. A shadow is a child sprite, so it follows its character for
+/// free and despawns with it (NPCs with their map, see despawn_map).
+///
+/// Shadows sit just under the character band (`depth::SHADOW_Z_OFFSET`),
+/// hide when `GameSettings::shadows` is off, and hide on maps flagged
+/// `indoor` - interior lighting in the source art is flat and overhead,
+/// and a shadow there reads as a dirty floor tile.
+pub struct ShadowPlugin;
+
+impl Plugin for ShadowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameAssets>()
+            .init_resource::<GameSettings>()
+            .add_systems(
+                Update,
+                (attach_shadows, update_shadow_visibility)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Shadow;
+
+/// The standard 48px character's footprint: a little over half the frame
+/// wide, flattened to the top-down perspective of the tiles.
+const SHADOW_SIZE: Vec2 = Vec2::new(FRAME_SIZE as f32 * 0.6, FRAME_SIZE as f32 * 0.25);
+
+/// Opacity of the generated fallback's core.
+const SHADOW_ALPHA: f32 = 0.35;
+
+fn shadow_visibility(settings: &GameSettings, indoor: bool) -> Visibility {
+    if settings.shadows && !indoor {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn attach_shadows(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    settings: Res<GameSettings>,
+    indoor: Option<Res<IndoorMap>>,
+    characters: Query<(Entity, &YSorted), (Or<(Added<Player>, Added<Npc>)>, Without<crate::map_objects::MapObject>)>,
+) {
+    let visibility = shadow_visibility(&settings, indoor.is_some());
+    for (entity, sorted) in &characters {
+        commands.entity(entity).with_child((
+            Shadow,
+            Sprite {
+                image: game_assets.shadow.clone(),
+                custom_size: Some(SHADOW_SIZE),
+                ..default()
+            },
+            // Centered a touch above the feet so the oval sits under the
+            // sprite's soles rather than peeking out below them.
+            Transform::from_xyz(0.0, sorted.foot_offset + SHADOW_SIZE.y * 0.25, SHADOW_Z_OFFSET),
+            visibility,
+        ));
+    }
+}
+
+/// The player's shadow outlives every map, so visibility follows the
+/// current map and the setting rather than being decided once at spawn.
+fn update_shadow_visibility(
+    settings: Res<GameSettings>,
+    indoor: Option<Res<IndoorMap>>,
+    mut shadows: Query<&mut Visibility, With<Shadow>>,
+) {
+    let wanted = shadow_visibility(&settings, indoor.is_some());
+    for mut visibility in &mut shadows {
+        visibility.set_if_neq(wanted);
+    }
+}
+
+/// The shadow used when no `textures/shadow.png` shipped (see
+/// asset_manifest.rs): a translucent black ellipse with a soft rim, drawn
+/// at 32x16 and stretched to `SHADOW_SIZE` by the sprite.
+pub fn generated_shadow_image() -> Image {
+    const WIDTH: u32 = 32;
+    const HEIGHT: u32 = 16;
+    let mut data = Vec::with_capacity((WIDTH * HEIGHT * 4) as usize);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let dx = (x as f32 + 0.5) / WIDTH as f32 * 2.0 - 1.0;
+            let dy = (y as f32 + 0.5) / HEIGHT as f32 * 2.0 - 1.0;
+            let r = (dx * dx + dy * dy).sqrt();
+            // Full opacity inside r = 0.8, fading to nothing at the edge.
+            let alpha = SHADOW_ALPHA * ((1.0 - r) / 0.2).clamp(0.0, 1.0);
+            data.extend_from_slice(&[0, 0, 0, (alpha * 255.0).round() as u8]);
+        }
+    }
+    Image::new(
+        Extent3d { width: WIDTH, height: HEIGHT, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alpha_at(image: &Image, x: u32, y: u32) -> u8 {
+        let width = image.width();
+        image.data.as_ref().unwrap()[((y * width + x) * 4 + 3) as usize]
+    }
+
+    #[test]
+    fn generated_shadow_is_a_translucent_ellipse() {
+        let image = generated_shadow_image();
+        let center = alpha_at(&image, 16, 8);
+        assert_eq!(center, (SHADOW_ALPHA * 255.0).round() as u8, "solid translucent core");
+        assert_eq!(alpha_at(&image, 0, 0), 0, "corners are outside the ellipse");
+        assert_eq!(alpha_at(&image, 31, 15), 0);
+    }
+
+    #[test]
+    fn shadows_hide_indoors_and_when_disabled() {
+        let on = GameSettings { shadows: true, ..default() };
+        let off = GameSettings { shadows: false, ..default() };
+        assert_eq!(shadow_visibility(&on, false), Visibility::Inherited);
+        assert_eq!(shadow_visibility(&on, true), Visibility::Hidden);
+        assert_eq!(shadow_visibility(&off, false), Visibility::Hidden);
+    }
+}
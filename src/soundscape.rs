@@ -0,0 +1,461 @@
+use bevy::audio::{AudioSinkPlayback, Volume};
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+use crate::game_state::Mode;
+use crate::map_data::{AmbienceData, tile_to_world};
+use crate::player::Player;
+
+/// Layered ambience: each map lists looping sounds (`MapData::ambience`) -
+/// wind everywhere, a crowd only near the market stalls - and the
+/// `Soundscape` resource works out how loud each should be right now.
+/// Positional layers fade with the player's distance to their nearest tile,
+/// recomputed a few times a second rather than every frame. A map change
+/// crossfades the old map's layers out and the new one's in; dialogue
+/// ducks them under the conversation, and menus and the console silence
+/// them outright. At most `MAX_AUDIBLE_LAYERS` are heard at once - the
+/// loudest.
+///
+/// Each layer in the mix plays through a looping `AudioPlayer`
+/// (`AmbiencePlayer`), spawned silent as it starts fading in and despawned
+/// once it has faded out; its `AudioSink`'s volume follows
+/// `Soundscape::gain` every frame. Without an audio plugin (the headless
+/// test harness) the mix is still kept, with nothing to play it.
+pub struct SoundscapePlugin;
+
+impl Plugin for SoundscapePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Soundscape>().add_systems(
+            Update,
+            (
+                mix_soundscape,
+                play_soundscape.run_if(resource_exists::<Assets<AudioSource>>),
+                follow_soundscape::<AudioSink>,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// How often positional layers are re-measured against the player.
+pub const RECOMPUTE_SECONDS: f32 = 0.25;
+
+/// How long a layer takes to fade from silence to full volume, or back:
+/// a map change's crossfade, and coming out of dialogue or a menu.
+pub const CROSSFADE_SECONDS: f32 = 1.5;
+
+/// Layers heard at once; quieter ones beyond it are cut.
+pub const MAX_AUDIBLE_LAYERS: usize = 4;
+
+/// What dialogue leaves of each layer's volume.
+pub const DIALOGUE_DUCK: f32 = 0.35;
+
+/// The current map's ambience layers (the valid ones - see
+/// `AmbienceData::problem`). Inserted by tilemap.rs::spawn_map and removed
+/// with the map, like `CameraZones`.
+#[derive(Resource, Debug, Clone)]
+pub struct MapAmbience {
+    pub map_width: u32,
+    pub map_height: u32,
+    pub layers: Vec<AmbienceData>,
+}
+
+/// How much of a positional layer is heard `distance` tiles from its
+/// nearest tile: all of it on the tile, nothing from `radius` on, easing
+/// off in between (squared, so the edge of earshot fades in gently rather
+/// than starting at a step).
+pub fn attenuation(distance: f32, radius: f32) -> f32 {
+    if radius.is_nan() || radius <= 0.0 {
+        return 0.0;
+    }
+    let falloff = 1.0 - (distance / radius).clamp(0.0, 1.0);
+    falloff * falloff
+}
+
+/// Distance in tiles from `position` (tile coordinates, fractional) to the
+/// nearest of `tiles`; `None` when there are none.
+pub fn nearest_tile_distance(position: Vec2, tiles: &[(u32, u32)]) -> Option<f32> {
+    tiles
+        .iter()
+        .map(|&(x, y)| position.distance(Vec2::new(x as f32, y as f32)))
+        .min_by(f32::total_cmp)
+}
+
+/// How loud a layer should be with the player at `position` (tile
+/// coordinates): its volume, attenuated when it's positional. A positional
+/// layer with nobody to hear it is silent.
+pub fn layer_gain(layer: &AmbienceData, position: Option<Vec2>) -> f32 {
+    if !layer.is_positional() {
+        return layer.volume;
+    }
+    position
+        .and_then(|position| nearest_tile_distance(position, &layer.near_tiles))
+        .map_or(0.0, |distance| layer.volume * attenuation(distance, layer.radius()))
+}
+
+/// The share of the mix each mode leaves: all of it while exploring,
+/// `DIALOGUE_DUCK` under a conversation, and none in menus, the dashboard
+/// and the console - or outside play altogether.
+pub fn mode_gain(mode: Option<Mode>) -> f32 {
+    match mode {
+        Some(Mode::Exploring) => 1.0,
+        Some(Mode::Dialogue) => DIALOGUE_DUCK,
+        Some(Mode::Menu | Mode::Dashboard | Mode::Console) | None => 0.0,
+    }
+}
+
+/// One sound in the mix: where its gain is heading, and where it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundscapeLayer {
+    pub sound: String,
+    pub target: f32,
+    pub gain: f32,
+}
+
+/// The ambience mix: every layer still audible or on its way in, the old
+/// map's fading out alongside the new one's fading in.
+#[derive(Resource, Debug)]
+pub struct Soundscape {
+    layers: Vec<SoundscapeLayer>,
+    recompute: Timer,
+    /// The mode the mix was last measured in.
+    mode: Option<Mode>,
+}
+
+impl Default for Soundscape {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            recompute: Timer::from_seconds(RECOMPUTE_SECONDS, TimerMode::Repeating),
+            mode: None,
+        }
+    }
+}
+
+impl Soundscape {
+    pub fn layers(&self) -> &[SoundscapeLayer] {
+        &self.layers
+    }
+
+    /// How loud `sound` is playing right now, 0 if it isn't.
+    pub fn gain(&self, sound: &str) -> f32 {
+        self.layers.iter().find(|layer| layer.sound == sound).map_or(0.0, |layer| layer.gain)
+    }
+
+    /// The sounds being heard, loudest first.
+    pub fn audible(&self) -> Vec<&SoundscapeLayer> {
+        let mut audible: Vec<_> = self.layers.iter().filter(|layer| layer.gain > 0.0).collect();
+        audible.sort_by(|a, b| b.gain.total_cmp(&a.gain));
+        audible
+    }
+
+    /// Aim the mix at `targets` (sound, gain): a sound already playing
+    /// heads for its new gain from where it is, a new one starts from
+    /// silence, and one no longer listed fades out. Only the
+    /// `MAX_AUDIBLE_LAYERS` loudest targets are kept; a sound listed twice
+    /// plays at the louder of the two.
+    pub fn retarget(&mut self, targets: impl IntoIterator<Item = (String, f32)>) {
+        let mut wanted: Vec<(String, f32)> = Vec::new();
+        for (sound, gain) in targets {
+            match wanted.iter_mut().find(|(existing, _)| *existing == sound) {
+                Some((_, existing)) => *existing = existing.max(gain),
+                None => wanted.push((sound, gain)),
+            }
+        }
+        wanted.retain(|(_, gain)| *gain > 0.0);
+        wanted.sort_by(|a, b| b.1.total_cmp(&a.1));
+        wanted.truncate(MAX_AUDIBLE_LAYERS);
+
+        for layer in &mut self.layers {
+            layer.target = 0.0;
+        }
+        for (sound, target) in wanted {
+            match self.layers.iter_mut().find(|layer| layer.sound == sound) {
+                Some(layer) => layer.target = target,
+                None => self.layers.push(SoundscapeLayer { sound, target, gain: 0.0 }),
+            }
+        }
+    }
+
+    /// Move every layer `seconds` of a crossfade toward its target, forget
+    /// those that have faded out, and cut the quietest beyond
+    /// `MAX_AUDIBLE_LAYERS` - a layer on its way in waits for one on its
+    /// way out to make room.
+    pub fn fade(&mut self, seconds: f32) {
+        let step = seconds / CROSSFADE_SECONDS;
+        for layer in &mut self.layers {
+            layer.gain = if layer.gain < layer.target {
+                (layer.gain + step).min(layer.target)
+            } else {
+                (layer.gain - step).max(layer.target)
+            };
+        }
+        self.layers.retain(|layer| layer.gain > 0.0 || layer.target > 0.0);
+
+        let mut by_gain: Vec<usize> = (0..self.layers.len()).filter(|&i| self.layers[i].gain > 0.0).collect();
+        if by_gain.len() > MAX_AUDIBLE_LAYERS {
+            by_gain.sort_by(|&a, &b| self.layers[b].gain.total_cmp(&self.layers[a].gain));
+            for &index in &by_gain[MAX_AUDIBLE_LAYERS..] {
+                self.layers[index].gain = 0.0;
+            }
+        }
+    }
+
+    /// Cut every layer at once - a menu opening doesn't wait for a fade.
+    /// They come back in from silence when the mode allows.
+    pub fn silence(&mut self) {
+        for layer in &mut self.layers {
+            layer.gain = 0.0;
+        }
+    }
+}
+
+/// The looping player of one layer of the mix.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct AmbiencePlayer {
+    pub sound: String,
+}
+
+/// A playing sound the mix can turn up and down: Bevy's `AudioSink`,
+/// inserted by bevy_audio once the sound has loaded and started.
+pub trait AmbienceSink: Component<Mutability = Mutable> {
+    fn set_gain(&mut self, gain: f32);
+}
+
+impl AmbienceSink for AudioSink {
+    fn set_gain(&mut self, gain: f32) {
+        self.set_volume(Volume::Linear(gain));
+    }
+}
+
+/// Re-measure the mix every `RECOMPUTE_SECONDS`, or at once when the map
+/// or the mode changes, then fade toward it every frame.
+fn mix_soundscape(
+    mut soundscape: ResMut<Soundscape>,
+    ambience: Option<Res<MapAmbience>>,
+    mode: Option<Res<State<Mode>>>,
+    player_query: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    let mode = mode.map(|mode| *mode.get());
+    let mode_changed = soundscape.mode != mode;
+    let map_changed = ambience.as_ref().is_some_and(|ambience| ambience.is_changed());
+    let due = soundscape.recompute.tick(time.delta()).just_finished();
+    if due || map_changed || mode_changed {
+        let duck = mode_gain(mode);
+        let targets: Vec<(String, f32)> = match &ambience {
+            Some(ambience) => {
+                let position = player_query.single().ok().map(|transform| {
+                    // Tile coordinates with the fraction kept, so a layer
+                    // fades smoothly rather than a tile at a time.
+                    let origin = tile_to_world(0, 0, ambience.map_width, ambience.map_height);
+                    let offset = (transform.translation.truncate() - origin) / 48.0;
+                    Vec2::new(offset.x, -offset.y)
+                });
+                ambience
+                    .layers
+                    .iter()
+                    .map(|layer| (layer.sound.clone(), layer_gain(layer, position) * duck))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        soundscape.retarget(targets);
+        if duck == 0.0 {
+            soundscape.silence();
+        }
+        if map_changed || mode_changed {
+            debug!("🔊 Ambience heading for {:?} ({mode:?})", soundscape.layers());
+        }
+        soundscape.mode = mode;
+    }
+    soundscape.fade(time.delta_secs());
+}
+
+/// A player per layer in the mix: started for a layer on its way in,
+/// stopped once its layer has faded out and been forgotten.
+fn play_soundscape(
+    mut commands: Commands,
+    soundscape: Res<Soundscape>,
+    players: Query<(Entity, &AmbiencePlayer)>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, player) in &players {
+        if !soundscape.layers.iter().any(|layer| layer.sound == player.sound) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for layer in &soundscape.layers {
+        if players.iter().any(|(_, player)| player.sound == layer.sound) {
+            continue;
+        }
+        debug!("🔊 Playing {}", layer.sound);
+        commands.spawn((
+            AmbiencePlayer { sound: layer.sound.clone() },
+            AudioPlayer::new(asset_server.load(layer.sound.clone())),
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(layer.gain)),
+        ));
+    }
+}
+
+/// Every playing layer at the volume the mix has it at now.
+fn follow_soundscape<S: AmbienceSink>(soundscape: Res<Soundscape>, mut sinks: Query<(&AmbiencePlayer, &mut S)>) {
+    for (player, mut sink) in &mut sinks {
+        sink.set_gain(soundscape.gain(&player.sound));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::audio::PlaybackMode;
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use crate::game_state::{GameState, Scene};
+    use std::time::Duration;
+
+    fn layer(json: &str) -> AmbienceData {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn positional_layers_fade_out_to_nothing_at_their_radius() {
+        assert_eq!(attenuation(0.0, 5.0), 1.0);
+        assert_eq!(attenuation(2.5, 5.0), 0.25);
+        assert_eq!(attenuation(5.0, 5.0), 0.0);
+        assert_eq!(attenuation(50.0, 5.0), 0.0);
+        assert_eq!(attenuation(0.0, 0.0), 0.0, "no radius carries nowhere");
+        assert!(attenuation(1.0, 5.0) > attenuation(2.0, 5.0));
+
+        assert_eq!(nearest_tile_distance(Vec2::new(10.0, 7.0), &[(10, 4), (11, 4)]), Some(3.0));
+        assert_eq!(nearest_tile_distance(Vec2::new(12.0, 4.0), &[(10, 4), (11, 4)]), Some(1.0));
+        assert_eq!(nearest_tile_distance(Vec2::ZERO, &[]), None);
+
+        let crowd = layer(r#"{ "sound": "audio/crowd.ogg", "volume": 0.8, "near_tiles": [[10, 4], [11, 4]], "radius": 5 }"#);
+        assert_eq!(layer_gain(&crowd, Some(Vec2::new(11.0, 4.0))), 0.8);
+        assert!((layer_gain(&crowd, Some(Vec2::new(13.5, 4.0))) - 0.8 * 0.25).abs() < 1e-6);
+        assert_eq!(layer_gain(&crowd, Some(Vec2::new(20.0, 4.0))), 0.0);
+        assert_eq!(layer_gain(&crowd, None), 0.0);
+
+        let wind = layer(r#"{ "sound": "audio/wind.ogg", "volume": 0.4 }"#);
+        assert_eq!(layer_gain(&wind, None), 0.4, "a map-wide layer doesn't care where the player is");
+    }
+
+    #[test]
+    fn a_map_change_crossfades_and_carries_shared_sounds_on() {
+        let mut mix = Soundscape::default();
+        mix.retarget([("wind".to_string(), 1.0), ("crowd".to_string(), 0.5)]);
+        mix.fade(CROSSFADE_SECONDS);
+        assert_eq!((mix.gain("wind"), mix.gain("crowd")), (1.0, 0.5));
+
+        // Indoors: the wind carries on, quieter; the crowd gives way to a hum.
+        mix.retarget([("wind".to_string(), 0.5), ("hum".to_string(), 1.0)]);
+        mix.fade(CROSSFADE_SECONDS / 4.0);
+        assert_eq!((mix.gain("wind"), mix.gain("crowd"), mix.gain("hum")), (0.75, 0.25, 0.25));
+        mix.fade(CROSSFADE_SECONDS);
+        assert_eq!((mix.gain("wind"), mix.gain("crowd"), mix.gain("hum")), (0.5, 0.0, 1.0));
+        assert_eq!(mix.layers().len(), 2, "faded-out layers are forgotten");
+
+        let ducked = mode_gain(Some(Mode::Dialogue));
+        assert!(ducked > 0.0 && ducked < mode_gain(Some(Mode::Exploring)));
+        for stopped in [Some(Mode::Menu), Some(Mode::Dashboard), Some(Mode::Console), None] {
+            assert_eq!(mode_gain(stopped), 0.0);
+        }
+        mix.silence();
+        assert!(mix.audible().is_empty());
+    }
+
+    #[test]
+    fn only_the_loudest_layers_are_heard_at_once() {
+        let mut mix = Soundscape::default();
+        let many = (0..MAX_AUDIBLE_LAYERS + 2).map(|i| (format!("layer{i}"), (i + 1) as f32 / 10.0));
+        mix.retarget(many);
+        mix.fade(CROSSFADE_SECONDS);
+        let audible: Vec<&str> = mix.audible().iter().map(|layer| layer.sound.as_str()).collect();
+        assert_eq!(audible.len(), MAX_AUDIBLE_LAYERS);
+        assert_eq!(audible[0], format!("layer{}", MAX_AUDIBLE_LAYERS + 1));
+        assert_eq!(mix.gain("layer0"), 0.0);
+
+        // A new loud layer waits for an old one to fade out of its way.
+        mix.retarget((2..MAX_AUDIBLE_LAYERS + 1).map(|i| (format!("layer{i}"), 0.5)).chain([("siren".to_string(), 1.0)]));
+        mix.fade(CROSSFADE_SECONDS / 10.0);
+        assert!(mix.audible().len() <= MAX_AUDIBLE_LAYERS);
+        mix.fade(CROSSFADE_SECONDS * 2.0);
+        assert_eq!(mix.gain("siren"), 1.0);
+        assert_eq!(mix.audible().len(), MAX_AUDIBLE_LAYERS);
+    }
+
+    /// What bevy_audio's sink would be told, without an audio device.
+    #[derive(Component, Default)]
+    struct TestSink(f32);
+
+    impl AmbienceSink for TestSink {
+        fn set_gain(&mut self, gain: f32) {
+            self.0 = gain;
+        }
+    }
+
+    #[test]
+    fn layers_play_looped_and_their_sinks_follow_the_player_between_zones() {
+        const WIDTH: u32 = 12;
+        const HEIGHT: u32 = 8;
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, AssetPlugin::default()))
+            .init_asset::<AudioSource>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .init_state::<GameState>()
+            .add_sub_state::<Scene>()
+            .add_sub_state::<Mode>()
+            .init_resource::<Soundscape>()
+            .add_systems(Update, (mix_soundscape, play_soundscape, follow_soundscape::<TestSink>).chain())
+            .insert_resource(MapAmbience {
+                map_width: WIDTH,
+                map_height: HEIGHT,
+                layers: vec![
+                    layer(r#"{ "sound": "audio/wind.ogg", "volume": 0.4 }"#),
+                    layer(r#"{ "sound": "audio/crowd.ogg", "volume": 0.8, "near_tiles": [[10, 4]], "radius": 4 }"#),
+                ],
+            });
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        let at = |x, y| Transform::from_translation(tile_to_world(x, y, WIDTH, HEIGHT).extend(0.0));
+        let player = app.world_mut().spawn((Player, at(10, 4))).id();
+        app.update();
+        app.update();
+
+        let mut players = app.world_mut().query::<(Entity, &AmbiencePlayer, &PlaybackSettings)>();
+        let mut playing: Vec<(Entity, String)> = players
+            .iter(app.world())
+            .inspect(|(_, _, settings)| assert!(matches!(settings.mode, PlaybackMode::Loop)))
+            .map(|(entity, player, _)| (entity, player.sound.clone()))
+            .collect();
+        playing.sort_by(|a, b| a.1.cmp(&b.1));
+        let sounds: Vec<&str> = playing.iter().map(|(_, sound)| sound.as_str()).collect();
+        assert_eq!(sounds, ["audio/crowd.ogg", "audio/wind.ogg"]);
+        // bevy_audio inserts the sink once the sound starts.
+        for (entity, _) in &playing {
+            app.world_mut().entity_mut(*entity).insert(TestSink::default());
+        }
+        let crowd = playing[0].0;
+        let volume = |app: &App| app.world().get::<TestSink>(crowd).map(|sink| sink.0);
+
+        for _ in 0..20 {
+            app.update();
+        }
+        assert_eq!(volume(&app), Some(0.8), "faded all the way in beside the stalls");
+
+        // Three tiles off, near the edge of earshot.
+        *app.world_mut().get_mut::<Transform>(player).unwrap() = at(10, 7);
+        for _ in 0..20 {
+            app.update();
+        }
+        let edge = volume(&app).unwrap();
+        assert!((edge - 0.8 * attenuation(3.0, 4.0)).abs() < 1e-4, "{edge}");
+
+        // Out of earshot the crowd fades out and its player goes.
+        *app.world_mut().get_mut::<Transform>(player).unwrap() = at(0, 7);
+        for _ in 0..20 {
+            app.update();
+        }
+        assert!(app.world().get_entity(crowd).is_err(), "the crowd's player is stopped");
+        assert_eq!(app.world().resource::<Soundscape>().gain("audio/wind.ogg"), 0.4);
+    }
+}
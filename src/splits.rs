@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::Duration;
+use opentelemetry::{KeyValue, trace::Span as _};
+use crate::assets::GameAssets;
+use crate::game_events::GameEvents;
+use crate::game_state::{GameState, Mode};
+use crate::instrumentation::PlayerSessionTrace;
+use crate::player::Player;
+
+/// Playtest pacing: with `--timer`, session time in the top-right corner
+/// and a split at each milestone, printed as a table when the game exits
+/// and exported as a `game.split.reached` event on the session span.
+///
+/// Milestones are data (`assets/data/splits.json`, or `--splits <file>`).
+/// Each names a gameplay event (game_events.rs) and, optionally, field
+/// values it must have, and splits the first time one matches:
+///
+/// ```json
+/// { "name": "Team Marathon", "event": "map_changed", "to": "TeamMarathon" }
+/// ```
+///
+/// There are no quests yet; a quest's completion is the story flag it
+/// sets (`flag_set`).
+///
+/// The clock runs while `GameState::Playing`, through conversations, and
+/// stops while the game is paused (`Mode::Menu`).
+pub struct SplitsPlugin;
+
+impl Plugin for SplitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameEvents>().add_systems(
+            Update,
+            (tick_split_timer.run_if(not(in_state(Mode::Menu))), show_split_timer)
+                .chain()
+                .run_if(in_state(GameState::Playing).and_then(resource_exists::<SplitTimer>)),
+        )
+        // Last: game events are published in PostUpdate, and an AppExit
+        // written anywhere this frame is visible.
+        .add_systems(Last, (record_splits, print_splits_on_exit).chain().run_if(resource_exists::<SplitTimer>));
+    }
+}
+
+pub const SPLITS_PATH: &str = "assets/data/splits.json";
+pub const SPLITS_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/data/splits.json"));
+
+/// One milestone from the splits file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Milestone {
+    pub name: String,
+    /// A `GameEvent` tag: `interaction_started`, `map_changed`, ...
+    pub event: String,
+    /// The event's fields that must match, e.g. `"to": "TeamMarathon"`.
+    #[serde(flatten)]
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Milestone {
+    fn matches(&self, event: &serde_json::Value) -> bool {
+        event["event"] == self.event.as_str()
+            && self.fields.iter().all(|(field, value)| event[field.as_str()] == value.as_str())
+    }
+}
+
+pub fn parse_milestones(json: &str) -> serde_json::Result<Vec<Milestone>> {
+    serde_json::from_str(json)
+}
+
+/// Present when launched with `--timer`: the clock and the splits so far.
+#[derive(Resource, Debug)]
+pub struct SplitTimer {
+    milestones: Vec<Milestone>,
+    elapsed: Duration,
+    /// Time each milestone was reached, by index into `milestones`.
+    reached: Vec<Option<Duration>>,
+    /// `GameEvents` cursor.
+    cursor: u64,
+}
+
+impl SplitTimer {
+    pub fn new(milestones: Vec<Milestone>) -> Self {
+        Self { reached: vec![None; milestones.len()], milestones, elapsed: Duration::ZERO, cursor: 0 }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Every milestone and when it was reached, in file order.
+    pub fn splits(&self) -> impl Iterator<Item = (&str, Option<Duration>)> {
+        self.milestones.iter().map(|milestone| milestone.name.as_str()).zip(self.reached.iter().copied())
+    }
+
+    /// Split any milestone `event` is the first match for; returns the
+    /// names split.
+    fn reach(&mut self, event: &serde_json::Value) -> Vec<String> {
+        let mut split = Vec::new();
+        for (milestone, reached) in self.milestones.iter().zip(&mut self.reached) {
+            if reached.is_none() && milestone.matches(event) {
+                *reached = Some(self.elapsed);
+                split.push(milestone.name.clone());
+            }
+        }
+        split
+    }
+
+    /// The exit table: each milestone's time and the gap since the one
+    /// before it; "-" for those not reached.
+    pub fn table(&self) -> String {
+        let width = self.milestones.iter().map(|m| m.name.len()).max().unwrap_or(0).max("Split".len());
+        let mut out = format!("{:<width$}  {:>9}  {:>9}\n", "Split", "Time", "Delta");
+        let mut previous = Duration::ZERO;
+        for (name, reached) in self.splits() {
+            match reached {
+                Some(at) => {
+                    let _ = writeln!(out, "{name:<width$}  {:>9}  {:>9}", clock(at), format!("+{}", clock(at - previous)));
+                    previous = at;
+                }
+                None => {
+                    let _ = writeln!(out, "{name:<width$}  {:>9}  {:>9}", "-", "-");
+                }
+            }
+        }
+        let _ = writeln!(out, "{:<width$}  {:>9}", "Total", clock(self.elapsed));
+        out
+    }
+}
+
+/// "m:ss.t", or "h:mm:ss.t" from an hour on.
+pub fn clock(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    let (hours, minutes, seconds) = (tenths / 36_000, tenths / 600 % 60, tenths % 600);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{:02}.{}", seconds / 10, seconds % 10)
+    } else {
+        format!("{minutes}:{:02}.{}", seconds / 10, seconds % 10)
+    }
+}
+
+#[derive(Component)]
+struct SplitTimerText;
+
+fn tick_split_timer(time: Res<Time>, mut timer: ResMut<SplitTimer>) {
+    timer.elapsed += time.delta();
+}
+
+fn show_split_timer(
+    mut commands: Commands,
+    timer: Res<SplitTimer>,
+    mut text: Query<&mut Text, With<SplitTimerText>>,
+    game_assets: Option<Res<GameAssets>>,
+) {
+    let line = match timer.splits().filter_map(|(name, at)| at.map(|_| name)).last() {
+        Some(last) => format!("{}\n{last}", clock(timer.elapsed)),
+        None => clock(timer.elapsed),
+    };
+    if let Ok(mut text) = text.single_mut() {
+        if **text != line {
+            **text = line;
+        }
+        return;
+    }
+    let font = game_assets.map(|assets| assets.dialogue_font.clone()).unwrap_or_default();
+    commands.spawn((
+        SplitTimerText,
+        Text::new(line),
+        TextFont { font: font.into(), font_size: FontSize::Vh(24.0 / 10.8), ..default() },
+        TextColor(Color::WHITE),
+        TextLayout::justify(Justify::Right),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+    ));
+}
+
+fn record_splits(
+    events: Res<GameEvents>,
+    mut timer: ResMut<SplitTimer>,
+    mut session: Query<&mut PlayerSessionTrace, With<Player>>,
+) {
+    let page = events.since(timer.cursor);
+    timer.cursor = page.next;
+    for record in page.events {
+        let Ok(event) = serde_json::to_value(&record.event) else { continue };
+        for name in timer.reach(&event) {
+            let elapsed = timer.elapsed;
+            info!("🏁 Split: {name} at {}", clock(elapsed));
+            if let Ok(mut trace) = session.single_mut() {
+                trace.span.add_event(
+                    "game.split.reached",
+                    vec![
+                        KeyValue::new("split.name", name),
+                        KeyValue::new("split.elapsed_s", elapsed.as_secs_f64()),
+                    ],
+                );
+            }
+        }
+    }
+}
+
+fn print_splits_on_exit(mut exits: MessageReader<AppExit>, timer: Res<SplitTimer>) {
+    if exits.read().count() > 0 {
+        println!("\n{}", timer.table());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_shipped_milestones_parse() {
+        let milestones = parse_milestones(SPLITS_JSON).unwrap();
+        assert!(milestones.iter().any(|m| m.event == "map_changed" && m.fields["to"] == "TeamMarathon"));
+    }
+
+    #[test]
+    fn each_milestone_splits_once_on_its_first_match() {
+        let mut timer = SplitTimer::new(parse_milestones(SPLITS_JSON).unwrap());
+        timer.elapsed = Duration::from_millis(12_300);
+        let talk = serde_json::json!({"event": "interaction_started", "npc": "Isabella"});
+        assert_eq!(timer.reach(&talk), ["First conversation"]);
+        timer.elapsed = Duration::from_secs(70);
+        assert!(timer.reach(&talk).is_empty(), "already split");
+        assert!(timer.reach(&serde_json::json!({"event": "map_changed", "from": "", "to": "TeamDisco"})).is_empty());
+        assert_eq!(
+            timer.reach(&serde_json::json!({"event": "map_changed", "from": "TownOfEndgame", "to": "TeamMarathon"})),
+            ["Team Marathon"]
+        );
+
+        let table = timer.table();
+        assert!(table.contains("0:12.3") && table.contains("+0:57.7"), "{table}");
+        assert!(table.lines().any(|line| line.starts_with("Mentored") && line.contains('-')), "{table}");
+    }
+
+    #[test]
+    fn clock_reads_minutes_then_hours() {
+        assert_eq!(clock(Duration::from_millis(5_250)), "0:05.2");
+        assert_eq!(clock(Duration::from_secs(3_725)), "1:02:05.0");
+    }
+}
@@ -1,15 +1,229 @@
+use bevy::prelude::*;
+use bevy::render::renderer::RenderAdapterInfo;
+use opentelemetry::KeyValue;
+#[cfg(not(target_arch = "wasm32"))]
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+#[cfg(not(target_arch = "wasm32"))]
 use opentelemetry_otlp::{LogExporter, WithExportConfig};
+#[cfg(not(target_arch = "wasm32"))]
 use opentelemetry_sdk::logs::SdkLoggerProvider;
+#[cfg(not(target_arch = "wasm32"))]
 use opentelemetry_sdk::Resource;
+#[cfg(not(target_arch = "wasm32"))]
 use tracing_subscriber::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
 use tracing_subscriber::EnvFilter;
+#[cfg(not(target_arch = "wasm32"))]
 use anyhow::Context;
 
+// The exporters (tokio + OTLP/tonic) are native-only; the system profile
+// compiles everywhere, the browser's a reduced one (see `Host`).
+
+/// The machine the game is running on, gathered once at startup so a slow
+/// session can be matched to its hardware across the workshop's machines.
+/// Its `attributes` go on every provider's resource (`resource`), and on
+/// each session span as a `system.profile` event; `SystemProfilePlugin`
+/// logs it once.
+///
+/// The GPU is only known once the renderer is up, after the providers'
+/// resources are fixed, so `gpu.adapter.name` is on the log line and the
+/// span event but not the resources.
+///
+/// `--no-system-profile` leaves out what says more about the machine than
+/// the game needs: the CPU model and core count, the memory and the GPU.
+/// The OS, architecture, window backend and headless mode stay.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SystemProfile {
+    pub os: String,
+    pub arch: String,
+    pub cpu_model: Option<String>,
+    pub cpu_cores: Option<usize>,
+    pub memory_bytes: Option<u64>,
+    pub gpu_adapter: Option<String>,
+    pub window_backend: String,
+    pub headless: bool,
+    /// False for `--no-system-profile`.
+    pub detailed: bool,
+}
+
+impl SystemProfile {
+    /// Ask `platform` about the machine; only the OS, architecture and
+    /// window backend unless `detailed`.
+    pub fn gather(platform: &impl Platform, headless: bool, detailed: bool) -> Self {
+        Self {
+            os: platform.os(),
+            arch: platform.arch(),
+            cpu_model: platform.cpu_model().filter(|_| detailed),
+            cpu_cores: platform.cpu_cores().filter(|_| detailed),
+            memory_bytes: platform.memory_bytes().filter(|_| detailed),
+            gpu_adapter: None,
+            window_backend: if headless { "none".to_string() } else { platform.window_backend() },
+            headless,
+            detailed,
+        }
+    }
+
+    /// Note the renderer's GPU, unless the profile isn't `detailed`.
+    pub fn set_gpu_adapter(&mut self, name: &str) {
+        if self.detailed && !name.is_empty() {
+            self.gpu_adapter = Some(name.to_string());
+        }
+    }
+
+    /// As OpenTelemetry attributes, leaving out whatever isn't known.
+    pub fn attributes(&self) -> Vec<KeyValue> {
+        let mut attributes = vec![
+            KeyValue::new("os.type", self.os.clone()),
+            KeyValue::new("host.arch", self.arch.clone()),
+        ];
+        if let Some(model) = &self.cpu_model {
+            attributes.push(KeyValue::new("host.cpu.model.name", model.clone()));
+        }
+        if let Some(cores) = self.cpu_cores {
+            attributes.push(KeyValue::new("host.cpu.cores", cores as i64));
+        }
+        if let Some(bytes) = self.memory_bytes {
+            attributes.push(KeyValue::new("host.memory.bytes", bytes as i64));
+        }
+        if let Some(adapter) = &self.gpu_adapter {
+            attributes.push(KeyValue::new("gpu.adapter.name", adapter.clone()));
+        }
+        attributes.push(KeyValue::new("game.window.backend", self.window_backend.clone()));
+        attributes.push(KeyValue::new("game.headless", self.headless));
+        attributes
+    }
+}
+
+/// Where a `SystemProfile`'s facts come from: `Host`, or a test's fakes.
+/// Anything the platform can't tell is `None`.
+pub trait Platform {
+    fn os(&self) -> String;
+    fn arch(&self) -> String;
+    fn cpu_model(&self) -> Option<String>;
+    fn cpu_cores(&self) -> Option<usize>;
+    fn memory_bytes(&self) -> Option<u64>;
+    /// What the window is opened through, when there is one.
+    fn window_backend(&self) -> String;
+}
+
+/// The machine we're running on. The CPU model and memory come from
+/// /proc on Linux and are unknown elsewhere; in a browser only the
+/// architecture is (wasm32), the tab and its canvas being all we have.
+pub struct Host;
+
+impl Platform for Host {
+    fn os(&self) -> String {
+        if cfg!(target_arch = "wasm32") {
+            "browser".to_string()
+        } else {
+            std::env::consts::OS.to_string()
+        }
+    }
+
+    fn arch(&self) -> String {
+        std::env::consts::ARCH.to_string()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn cpu_model(&self) -> Option<String> {
+        cpuinfo_model(&std::fs::read_to_string("/proc/cpuinfo").ok()?)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn cpu_model(&self) -> Option<String> {
+        None
+    }
+
+    fn cpu_cores(&self) -> Option<usize> {
+        std::thread::available_parallelism().ok().map(usize::from)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn memory_bytes(&self) -> Option<u64> {
+        meminfo_total(&std::fs::read_to_string("/proc/meminfo").ok()?)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn memory_bytes(&self) -> Option<u64> {
+        None
+    }
+
+    fn window_backend(&self) -> String {
+        let backend = match std::env::consts::OS {
+            // winit takes Wayland when there's a compositor to talk to.
+            "linux" if std::env::var_os("WAYLAND_DISPLAY").is_some() => "wayland",
+            "linux" => "x11",
+            "windows" => "win32",
+            "macos" => "appkit",
+            _ if cfg!(target_arch = "wasm32") => "canvas",
+            other => other,
+        };
+        backend.to_string()
+    }
+}
+
+/// The first "model name" in /proc/cpuinfo.
+#[cfg(any(target_os = "linux", test))]
+fn cpuinfo_model(cpuinfo: &str) -> Option<String> {
+    cpuinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("model name")?.trim_start().strip_prefix(':'))
+        .map(|model| model.trim().to_string())
+}
+
+/// /proc/meminfo's MemTotal, which it gives in kB.
+#[cfg(any(target_os = "linux", test))]
+fn meminfo_total(meminfo: &str) -> Option<u64> {
+    let kilobytes = meminfo.lines().find_map(|line| line.strip_prefix("MemTotal:"))?;
+    let kilobytes: u64 = kilobytes.trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Notes the renderer's GPU in the `SystemProfile` main.rs inserts, and
+/// logs the profile once. Nothing without one.
+pub struct SystemProfilePlugin;
+
+impl Plugin for SystemProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, log_system_profile.run_if(resource_exists::<SystemProfile>));
+    }
+}
+
+fn log_system_profile(mut profile: ResMut<SystemProfile>, adapter: Option<Res<RenderAdapterInfo>>) {
+    if let Some(adapter) = adapter {
+        profile.set_gpu_adapter(&adapter.name);
+    }
+    info!(
+        os = %profile.os,
+        arch = %profile.arch,
+        cpu = profile.cpu_model.as_deref().unwrap_or("unknown"),
+        cores = profile.cpu_cores.unwrap_or(0),
+        memory_mb = profile.memory_bytes.unwrap_or(0) / (1024 * 1024),
+        gpu = profile.gpu_adapter.as_deref().unwrap_or("unknown"),
+        window_backend = %profile.window_backend,
+        headless = profile.headless,
+        "🖥️ System profile"
+    );
+}
+
+/// The resource every provider reports under: the service, and `profile`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn resource(profile: &SystemProfile) -> Resource {
+    Resource::builder_empty()
+        .with_service_name("sregame")
+        .with_attributes(profile.attributes())
+        .build()
+}
+
 /// Initialize OpenTelemetry with OTLP exporter
 /// Call this BEFORE creating the Bevy App
 /// Returns Some((logger_provider, tokio_runtime)) if endpoint provided, None otherwise
-pub fn init_telemetry(endpoint: Option<String>) -> anyhow::Result<Option<(SdkLoggerProvider, tokio::runtime::Runtime)>> {
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_telemetry(
+    endpoint: Option<String>,
+    profile: &SystemProfile,
+    export_errors: crate::dashboard::ExportErrors,
+) -> anyhow::Result<Option<(SdkLoggerProvider, tokio::runtime::Runtime)>> {
     let endpoint = match endpoint {
         Some(e) => e,
         None => return Ok(None),
@@ -28,11 +242,7 @@ pub fn init_telemetry(endpoint: Option<String>) -> anyhow::Result<Option<(SdkLog
 
     // Create logger provider with batch processor
     let logger_provider = SdkLoggerProvider::builder()
-        .with_resource(
-            Resource::builder_empty()
-                .with_service_name("sregame")
-                .build(),
-        )
+        .with_resource(resource(profile))
         .with_batch_exporter(exporter)
         .build();
 
@@ -53,19 +263,102 @@ pub fn init_telemetry(endpoint: Option<String>) -> anyhow::Result<Option<(SdkLog
         .with_thread_names(true)
         .with_filter(filter_fmt);
 
-    // Initialize tracing subscriber with both layers
+    // The SDK's own errors (failed exports) feed the dashboard's export
+    // health line - see dashboard.rs.
+    let filter_export_errors = EnvFilter::new("opentelemetry=error");
+
+    // Initialize tracing subscriber with all three layers
     tracing_subscriber::registry()
         .with(otel_layer.with_filter(filter_otel))
         .with(fmt_layer)
+        .with(ExportErrorCounter(export_errors).with_filter(filter_export_errors))
         .init();
 
     Ok(Some((logger_provider, runtime)))
 }
 
+/// Counts every event it sees into `dashboard::ExportErrors`; filtered
+/// down to the OpenTelemetry crates' errors in `init_telemetry`.
+#[cfg(not(target_arch = "wasm32"))]
+struct ExportErrorCounter(crate::dashboard::ExportErrors);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for ExportErrorCounter {
+    fn on_event(&self, _event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+        self.0.record();
+    }
+}
+
 /// Clean shutdown of telemetry
 /// Call this when the app exits
+#[cfg(not(target_arch = "wasm32"))]
 pub fn shutdown_telemetry(logger_provider: SdkLoggerProvider) -> anyhow::Result<()> {
     eprintln!("🔭 Shutting down OpenTelemetry");
     logger_provider.shutdown()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakePlatform;
+
+    impl Platform for FakePlatform {
+        fn os(&self) -> String {
+            "linux".to_string()
+        }
+        fn arch(&self) -> String {
+            "x86_64".to_string()
+        }
+        fn cpu_model(&self) -> Option<String> {
+            Some("Workshop CPU 9000".to_string())
+        }
+        fn cpu_cores(&self) -> Option<usize> {
+            Some(8)
+        }
+        fn memory_bytes(&self) -> Option<u64> {
+            Some(16 << 30)
+        }
+        fn window_backend(&self) -> String {
+            "wayland".to_string()
+        }
+    }
+
+    fn attribute(attributes: &[KeyValue], key: &str) -> Option<String> {
+        attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| kv.value.to_string())
+    }
+
+    #[test]
+    fn the_profile_names_the_whole_machine() {
+        let mut profile = SystemProfile::gather(&FakePlatform, false, true);
+        profile.set_gpu_adapter("Workshop GPU");
+        let attributes = profile.attributes();
+        assert_eq!(attribute(&attributes, "os.type").as_deref(), Some("linux"));
+        assert_eq!(attribute(&attributes, "host.arch").as_deref(), Some("x86_64"));
+        assert_eq!(attribute(&attributes, "host.cpu.model.name").as_deref(), Some("Workshop CPU 9000"));
+        assert_eq!(attribute(&attributes, "host.cpu.cores").as_deref(), Some("8"));
+        assert_eq!(attribute(&attributes, "host.memory.bytes").as_deref(), Some("17179869184"));
+        assert_eq!(attribute(&attributes, "gpu.adapter.name").as_deref(), Some("Workshop GPU"));
+        assert_eq!(attribute(&attributes, "game.window.backend").as_deref(), Some("wayland"));
+        assert_eq!(attribute(&attributes, "game.headless").as_deref(), Some("false"));
+    }
+
+    #[test]
+    fn opting_out_keeps_only_what_the_game_chose() {
+        let mut profile = SystemProfile::gather(&FakePlatform, true, false);
+        profile.set_gpu_adapter("Workshop GPU");
+        let keys: Vec<_> = profile.attributes().iter().map(|kv| kv.key.to_string()).collect();
+        assert_eq!(keys, ["os.type", "host.arch", "game.window.backend", "game.headless"]);
+        assert_eq!(attribute(&profile.attributes(), "game.window.backend").as_deref(), Some("none"), "headless has no window");
+    }
+
+    #[test]
+    fn proc_files_give_the_cpu_model_and_memory() {
+        let cpuinfo = "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Core(TM) i7-8650U CPU @ 1.90GHz\n";
+        assert_eq!(cpuinfo_model(cpuinfo).as_deref(), Some("Intel(R) Core(TM) i7-8650U CPU @ 1.90GHz"));
+        assert_eq!(cpuinfo_model("processor\t: 0\n"), None);
+        let meminfo = "MemTotal:       16303428 kB\nMemFree:         1234567 kB\n";
+        assert_eq!(meminfo_total(meminfo), Some(16303428 * 1024));
+    }
+}
@@ -0,0 +1,535 @@
+//! Headless simulation harness for integration tests (`--features testing`).
+//!
+//! `TestGame` builds the real game - `add_game`, every plugin - on top of a
+//! renderer-free plugin set, with a fixed 1/60s timestep so a test that
+//! steps 60 frames always simulates exactly one second. Content comes from a
+//! caller-provided fixtures directory (`<fixtures>/maps/<map_file>.json`,
+//! see `MapDirectory`), and telemetry goes to in-memory exporters that tests
+//! can drain and assert on.
+//!
+//! Asset loading is skipped, not simulated: the harness marks `GameAssets`
+//! loaded and jumps straight to `GameState::Playing`. Handles still exist
+//! (keyed off the manifest, pointing into the fixtures dir), they just never
+//! resolve to pixels - nothing here renders.
+//!
+//! Worlds too big to check in as fixtures (the benches' 200x200 map, a
+//! town of a thousand NPCs) are written on the fly by `GeneratedTown`.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey, NativeKeyCode};
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use opentelemetry::global::BoxedTracer;
+use opentelemetry::metrics::MeterProvider as _;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::metrics::data::ResourceMetrics;
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+
+use crate::assets::GameAssets;
+use crate::content_pack::ContentPacks;
+use crate::dialogue::{DialogueRoot, DialogueSegment, DialogueSession, DialogueTextNode, MenuRow, PortraitNode, SpeakerNameNode};
+use crate::entity_audit::Census;
+use crate::game_state::{GameState, Mode, Scene};
+use crate::input::{GameAction, InputMap};
+use crate::instrumentation::{GameMeter, GameTracer, TelemetryProviders};
+use crate::map_data::MapDirectory;
+use crate::npc::Npc;
+use crate::player::Player;
+
+/// One simulated frame.
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Where the state machine is right now. `scene`/`mode` are `None` outside
+/// `GameState::Playing` (they are sub-states of it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateSnapshot {
+    pub game: GameState,
+    pub scene: Option<Scene>,
+    pub mode: Option<Mode>,
+}
+
+pub struct TestGame {
+    app: App,
+    spans: InMemorySpanExporter,
+    metrics: InMemoryMetricExporter,
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl TestGame {
+    /// Boot the game against `fixtures` and run it until the first scene's
+    /// map is up and the player can move.
+    pub fn new(fixtures: impl AsRef<Path>) -> Self {
+        Self::with_plugins(fixtures, crate::add_game)
+    }
+
+    /// Same harness, but only the game plugins `add` installs instead of
+    /// the whole `add_game` - for checking that plugins stand on their own
+    /// and don't care what order they're added in.
+    pub fn with_plugins(fixtures: impl AsRef<Path>, add: impl FnOnce(&mut App)) -> Self {
+        Self::build(fixtures.as_ref(), Vec::new(), None, add)
+    }
+
+    /// The whole game against `fixtures` with content packs over them, as
+    /// `--content-pack` would stack them (see content_pack.rs).
+    pub fn with_content_packs(fixtures: impl AsRef<Path>, packs: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self::build(fixtures.as_ref(), packs.into_iter().collect(), None, crate::add_game)
+    }
+
+    /// The whole game against `fixtures`, its telemetry going to real
+    /// providers - `init_instrumentation`'s, exporting over OTLP - instead of
+    /// the in-memory ones, so `drain_spans`/`drain_metrics` come back empty.
+    /// For driving a collector with simulated play (examples/soak.rs).
+    pub fn with_telemetry(
+        fixtures: impl AsRef<Path>,
+        tracer: GameTracer,
+        meter: GameMeter,
+        providers: TelemetryProviders,
+    ) -> Self {
+        Self::build(fixtures.as_ref(), Vec::new(), Some((tracer, meter, providers)), crate::add_game)
+    }
+
+    fn build(
+        fixtures: &Path,
+        packs: Vec<PathBuf>,
+        telemetry: Option<(GameTracer, GameMeter, TelemetryProviders)>,
+        add: impl FnOnce(&mut App),
+    ) -> Self {
+
+        let spans = InMemorySpanExporter::default();
+        let metrics = InMemoryMetricExporter::default();
+        let (tracer, meter, providers) = telemetry.unwrap_or_else(|| {
+            let tracer_provider = SdkTracerProvider::builder()
+                .with_simple_exporter(spans.clone())
+                .build();
+            let meter_provider = SdkMeterProvider::builder()
+                .with_reader(PeriodicReader::builder(metrics.clone()).build())
+                .build();
+            (
+                GameTracer::new(BoxedTracer::new(Box::new(tracer_provider.tracer("sregame")))),
+                GameMeter::new(&meter_provider.meter("sregame")),
+                TelemetryProviders { tracer: tracer_provider, meter: meter_provider },
+            )
+        });
+        let (tracer_provider, meter_provider) = (providers.tracer.clone(), providers.meter.clone());
+
+        let mut app = App::new();
+        if !packs.is_empty() {
+            ContentPacks(packs).install(&mut app, fixtures);
+        }
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            TransformPlugin,
+            AssetPlugin {
+                file_path: fixtures.display().to_string(),
+                ..default()
+            },
+            ImagePlugin::default_nearest(),
+            bevy::image::TextureAtlasPlugin,
+        ))
+        // Font is normally registered by the text plugin, which wants a
+        // renderer; the dialogue UI only needs the handle type.
+        .init_asset::<Font>()
+        // Input is driven by press()/release(), not by OS events.
+        .init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+        .insert_resource(MapDirectory(fixtures.join("maps")))
+        .insert_resource(tracer)
+        .insert_resource(meter)
+        .insert_resource(providers);
+
+        add(&mut app);
+        // What App::run does before the first frame. Plugins check their
+        // hard requirements in finish (see `require_plugin` in lib.rs).
+        app.finish();
+        app.cleanup();
+
+        let mut game = Self {
+            app,
+            spans,
+            metrics,
+            tracer_provider,
+            meter_provider,
+        };
+
+        // Startup + OnEnter(Loading), then skip the asset wait.
+        game.step(1);
+        if let Some(mut assets) = game.app.world_mut().get_resource_mut::<GameAssets>() {
+            assets.loaded = true;
+        }
+        game.app
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        // Playing -> Scene/Mode sub-states -> map spawn commands applied.
+        game.step(2);
+        game
+    }
+
+    /// Advance the simulation `frames` fixed-length frames. A press is seen
+    /// as `just_pressed` by exactly the first of them; held keys stay held.
+    pub fn step(&mut self, frames: u32) {
+        for _ in 0..frames {
+            self.app.update();
+            self.app
+                .world_mut()
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .clear();
+        }
+    }
+
+    /// Press the key `action` is bound to (`InputMap`), the default one
+    /// unless it's been rebound.
+    pub fn press(&mut self, action: GameAction) {
+        let key = self.bound_key(action);
+        self.app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(key);
+    }
+
+    pub fn release(&mut self, action: GameAction) {
+        let key = self.bound_key(action);
+        self.app.world_mut().resource_mut::<ButtonInput<KeyCode>>().release(key);
+    }
+
+    /// Press and release `action` over two frames - one `just_pressed`, the
+    /// way a player taps a key.
+    pub fn tap(&mut self, action: GameAction) {
+        let key = self.bound_key(action);
+        self.tap_key(key);
+    }
+
+    /// `tap` for a raw key no `GameAction` names (F-keys, menu arrows).
+    pub fn tap_key(&mut self, key: KeyCode) {
+        self.app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(key);
+        self.step(1);
+        self.app.world_mut().resource_mut::<ButtonInput<KeyCode>>().release(key);
+        self.step(1);
+    }
+
+    /// Hold Interact long enough for whoever the player faces to answer.
+    pub fn interact(&mut self) {
+        self.press(GameAction::Interact);
+        self.step(3);
+        self.release(GameAction::Interact);
+    }
+
+    /// Tap Advance until the dialogue box closes. Returns the taps it took;
+    /// panics if it's still open after `max_pages`, so a conversation that
+    /// never ends fails the test instead of hanging it.
+    pub fn read_dialogue_to_end(&mut self, max_pages: u32) -> u32 {
+        self.tap_until_dialogue_closes(GameAction::Advance, max_pages)
+    }
+
+    /// `read_dialogue_to_end` closing with `action` (Cancel walks out early).
+    pub fn tap_until_dialogue_closes(&mut self, action: GameAction, max_taps: u32) -> u32 {
+        for taps in 0..=max_taps {
+            if self.current_state().mode != Some(Mode::Dialogue) {
+                return taps;
+            }
+            if taps < max_taps {
+                self.tap(action);
+            }
+        }
+        panic!("dialogue still open after {max_taps} taps of {action:?}: {:?}", self.active_dialogue());
+    }
+
+    /// Step until `done` holds, returning the frames it took; panics after
+    /// `max_frames` rather than spinning forever.
+    pub fn step_until(&mut self, max_frames: u32, mut done: impl FnMut(&mut Self) -> bool) -> u32 {
+        for frames in 0..=max_frames {
+            if done(self) {
+                return frames;
+            }
+            if frames < max_frames {
+                self.step(1);
+            }
+        }
+        panic!("condition still false after {max_frames} frames");
+    }
+
+    /// Type `text` as the OS reports it, a `KeyboardInput` per character,
+    /// for text entry (the developer console) rather than gameplay - which
+    /// reads `press`. Seen on the next step.
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            let key_code = if c == '`' { KeyCode::Backquote } else { KeyCode::Unidentified(NativeKeyCode::Unidentified) };
+            self.key_input(key_code, Key::Character(c.to_string().into()), Some(c.to_string()));
+        }
+    }
+
+    /// One key with no text (Enter, Tab, Escape) as `type_text` types.
+    pub fn type_key(&mut self, key_code: KeyCode) {
+        self.key_input(key_code, Key::Unidentified(NativeKey::Unidentified), None);
+    }
+
+    fn key_input(&mut self, key_code: KeyCode, logical_key: Key, text: Option<String>) {
+        self.app.world_mut().write_message(KeyboardInput {
+            key_code,
+            logical_key,
+            state: ButtonState::Pressed,
+            text: text.map(Into::into),
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    fn bound_key(&self, action: GameAction) -> KeyCode {
+        self.app.world().get_resource::<InputMap>().map_or(action.default_key(), |map| map.key(action))
+    }
+
+    /// World-space position of the player sprite, or `None` before it spawns.
+    pub fn player_pos(&mut self) -> Option<Vec2> {
+        let world = self.app.world_mut();
+        let mut players = world.query_filtered::<&Transform, With<Player>>();
+        players
+            .single(world)
+            .ok()
+            .map(|transform| transform.translation.truncate())
+    }
+
+    pub fn current_state(&self) -> StateSnapshot {
+        let world = self.app.world();
+        StateSnapshot {
+            game: *world.resource::<State<GameState>>().get(),
+            scene: world.get_resource::<State<Scene>>().map(|s| *s.get()),
+            mode: world.get_resource::<State<Mode>>().map(|s| *s.get()),
+        }
+    }
+
+    /// Switch scenes directly (no door, no fade) and run until the new map
+    /// is up.
+    pub fn enter_scene(&mut self, scene: Scene) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<Scene>>()
+            .set(scene);
+        self.step(2);
+    }
+
+    /// Entity counts by marker group right now - see `LeakCheck`.
+    pub fn census(&self) -> Census {
+        Census::take(self.app.world())
+    }
+
+    /// Names of every NPC on the map, sorted.
+    pub fn npc_names(&mut self) -> Vec<String> {
+        let world = self.app.world_mut();
+        let mut npcs = world.query::<&Npc>();
+        let mut names: Vec<String> = npcs.iter(world).map(|npc| npc.name.clone()).collect();
+        names.sort();
+        names
+    }
+
+    /// The dialogue box currently on screen, if any.
+    pub fn active_dialogue(&self) -> Option<DialogueSegment> {
+        self.app
+            .world()
+            .get_resource::<DialogueSession>()
+            .and_then(|session| session.queue.current_segment().cloned())
+    }
+
+    /// The box's text as typed so far, every markup span of it -
+    /// `active_dialogue` is the whole line as authored.
+    pub fn dialogue_text(&mut self) -> Option<String> {
+        let world = self.app.world_mut();
+        let mut texts = world.query_filtered::<(&Text, Option<&Children>), With<DialogueTextNode>>();
+        let mut spans = world.query::<&TextSpan>();
+        let world = &*world;
+        let (text, children) = texts.iter(world).next()?;
+        let typed = children.into_iter().flatten().filter_map(|&child| spans.get(world, child).ok());
+        Some(typed.fold(text.0.clone(), |shown, span| shown + &span.0))
+    }
+
+    /// The box's text size - smaller for a line with a word too long
+    /// for a row, under `LongWords::Shrink`.
+    pub fn dialogue_font_size(&mut self) -> Option<FontSize> {
+        let world = self.app.world_mut();
+        let mut fonts = world.query_filtered::<&TextFont, With<DialogueTextNode>>();
+        fonts.iter(world).next().map(|font| font.font_size)
+    }
+
+    /// The asset path of the portrait in the dialogue box, if one is
+    /// showing - after any fallback, unlike `active_dialogue`'s.
+    pub fn portrait_path(&mut self) -> Option<String> {
+        let world = self.app.world_mut();
+        let mut portraits = world.query_filtered::<&ImageNode, With<PortraitNode>>();
+        let image = portraits.iter(world).next()?;
+        image.image.path().map(ToString::to_string)
+    }
+
+    /// The dialogue box's background.
+    pub fn dialogue_panel_color(&mut self) -> Option<Color> {
+        let world = self.app.world_mut();
+        let mut panels = world.query_filtered::<&BackgroundColor, With<DialogueRoot>>();
+        panels.iter(world).next().map(|panel| panel.0)
+    }
+
+    /// The topic menu's rows on screen, top to bottom, as drawn.
+    pub fn topic_menu_rows(&mut self) -> Vec<String> {
+        let world = self.app.world_mut();
+        let mut rows = world.query::<(&MenuRow, &Text)>();
+        let mut rows: Vec<_> = rows
+            .iter(world)
+            .filter_map(|(row, text)| match row {
+                MenuRow::Topic(row) => Some((*row, text.0.clone())),
+                MenuRow::Choice(_) => None,
+            })
+            .collect();
+        rows.sort();
+        rows.into_iter().map(|(_, text)| text).filter(|text| !text.is_empty()).collect()
+    }
+
+    /// The name over the dialogue box, in the color it's showing.
+    pub fn speaker_name(&mut self) -> Option<(String, Color)> {
+        let world = self.app.world_mut();
+        let mut names = world.query_filtered::<(&Text, &TextColor), With<SpeakerNameNode>>();
+        let (text, color) = names.iter(world).next()?;
+        Some((text.0.clone(), color.0))
+    }
+
+    /// Every span that has ended since the last drain.
+    pub fn drain_spans(&mut self) -> Vec<SpanData> {
+        let _ = self.tracer_provider.force_flush();
+        let spans = self.spans.get_finished_spans().unwrap_or_default();
+        self.spans.reset();
+        spans
+    }
+
+    /// A metrics export since the last drain. Instruments are cumulative,
+    /// so the latest entry holds every total so far.
+    pub fn drain_metrics(&mut self) -> Vec<ResourceMetrics> {
+        let _ = self.meter_provider.force_flush();
+        let metrics = self.metrics.get_finished_metrics().unwrap_or_default();
+        self.metrics.reset();
+        metrics
+    }
+
+    /// Escape hatch for assertions the helpers don't cover.
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+/// Entity leak assertion around a stretch of gameplay: take a census at
+/// `start`, play, then `assert_no_growth` fails if any marker group ended up
+/// larger than it began (entity_audit.rs explains the grouping). Step far
+/// enough for timed entities (toasts, emotes) to expire before checking.
+pub struct LeakCheck {
+    before: Census,
+}
+
+impl LeakCheck {
+    pub fn start(game: &TestGame) -> Self {
+        Self { before: game.census() }
+    }
+
+    pub fn assert_no_growth(&self, game: &TestGame) {
+        let after = game.census();
+        let grown: Vec<String> = after
+            .delta(&self.before)
+            .into_iter()
+            .filter(|(_, change)| *change > 0)
+            .map(|(group, change)| format!("{group} {change:+}"))
+            .collect();
+        assert!(
+            grown.is_empty(),
+            "entities leaked ({} -> {} total): {}",
+            self.before.total,
+            after.total,
+            grown.join(", ")
+        );
+    }
+}
+
+/// An open, walled map written as a fixture at run time. Odd sizes put a
+/// tile center at the origin, where the player spawns (player.rs), with
+/// the greeter - if any - standing one tile north of it, in talk range.
+pub struct GeneratedTown {
+    width: u32,
+    height: u32,
+    npcs: usize,
+    greeting: Option<String>,
+}
+
+impl GeneratedTown {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height, npcs: 0, greeting: None }
+    }
+
+    /// Scatter `count` NPCs evenly over the open tiles, away from the
+    /// player's spawn.
+    pub fn npcs(mut self, count: usize) -> Self {
+        self.npcs = count;
+        self
+    }
+
+    /// A greeter next to the spawn whose dialogue is this one line.
+    pub fn greeter(mut self, line: impl Into<String>) -> Self {
+        self.greeting = Some(line.into());
+        self
+    }
+
+    /// Write the map as `<dir>/maps/<map_file>.json` (see `scene_config`
+    /// for which scene reads which file) and return `dir`, ready for
+    /// `TestGame::new`.
+    pub fn write(&self, dir: impl AsRef<Path>, map_file: &str) -> PathBuf {
+        let dir = dir.as_ref().to_path_buf();
+        let maps = dir.join("maps");
+        std::fs::create_dir_all(&maps).expect("create generated fixtures dir");
+        let json = serde_json::to_string(&self.map_json(map_file)).expect("serialize generated map");
+        std::fs::write(maps.join(format!("{map_file}.json")), json).expect("write generated map");
+        dir
+    }
+
+    fn map_json(&self, name: &str) -> serde_json::Value {
+        let (width, height) = (self.width, self.height);
+        let cells = (width * height) as usize;
+        let wall = |x: u32, y: u32| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+        let collision: Vec<bool> = (0..cells as u32).map(|i| wall(i % width, i / width)).collect();
+        let spawn = (width / 2, height / 2);
+
+        let npc = |name: String, x: u32, y: u32, line: &str| serde_json::json!({
+            "name": name,
+            "x": x,
+            "y": y,
+            "sprite": "Isabella",
+            "facing": "down",
+            "dialogue": { "speaker": name, "portrait": "", "lines": [line] },
+        });
+        let mut npcs = Vec::new();
+        if let Some(line) = &self.greeting {
+            npcs.push(npc("Greeter".into(), spawn.0, spawn.1 - 1, line));
+        }
+        let open: Vec<(u32, u32)> = (1..height - 1)
+            .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+            .filter(|&(x, y)| x.abs_diff(spawn.0) > 1 || y.abs_diff(spawn.1) > 1)
+            .collect();
+        if self.npcs > 0 && !open.is_empty() {
+            let stride = (open.len() / self.npcs).max(1);
+            for (i, &(x, y)) in open.iter().step_by(stride).take(self.npcs).enumerate() {
+                npcs.push(npc(format!("Npc{i}"), x, y, "Hello."));
+            }
+        }
+
+        serde_json::json!({
+            "name": name,
+            "width": width,
+            "height": height,
+            "tiles": vec![0; cells],
+            "collision": collision,
+            "npcs": npcs,
+        })
+    }
+}
+
+/// A fresh directory under the system temp dir for `GeneratedTown`s.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sregame-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
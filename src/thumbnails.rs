@@ -0,0 +1,501 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::assets::GameAssets;
+use crate::game_state::Scene;
+use crate::map_data::{MapData, MapDirectory, TileLayerData};
+use crate::tilemap::{TILE_PX, scene_config};
+
+/// A small picture of each map, for the save slot picker (save_menu.rs)
+/// and anything else that lists maps: every tile drawn as one pixel-block
+/// of its average color (`TilePalette`, sampled once per tileset), the
+/// layers stacked as the map draws them, letterboxed into `THUMBNAIL_WIDTH` x
+/// `THUMBNAIL_HEIGHT`.
+///
+/// `MapThumbnail::get_or_generate` hands back an image right away - a
+/// placeholder - and draws the map into it on the async compute pool. With
+/// a `ThumbnailCache` (main.rs inserts one; the test harness doesn't) the
+/// pixels are kept on disk, named by the map file's content hash, so a
+/// thumbnail is only drawn again once its map changes. The base map file
+/// is what's drawn; content packs' overrides aren't.
+pub struct MapThumbnailsPlugin;
+
+impl Plugin for MapThumbnailsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapThumbnails>()
+            .add_systems(Update, (start_thumbnails, collect_thumbnails).chain());
+    }
+}
+
+pub const THUMBNAIL_WIDTH: u32 = 128;
+pub const THUMBNAIL_HEIGHT: u32 = 96;
+
+/// Part of every content hash: bump it when drawing changes, and every
+/// cached thumbnail is drawn again.
+const THUMBNAIL_VERSION: &str = "thumbnail-v1";
+
+/// Shown until the map is drawn: the save picker's panel, a shade lighter.
+const PLACEHOLDER: [u8; 4] = [40, 40, 52, 255];
+
+/// Stands in for every tile of a map with no tileset to sample.
+const SILHOUETTE: [u8; 4] = [120, 120, 130, 255];
+
+/// Where drawn thumbnails are kept, one `<map_file>-<hash>.rgba` of raw
+/// pixels per map. Absent, nothing is kept.
+#[derive(Resource, Clone, Debug)]
+pub struct ThumbnailCache(pub PathBuf);
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        Self(PathBuf::from(".cache/thumbnails"))
+    }
+}
+
+/// The average color of each tile of a tileset, by atlas index (row-major,
+/// as `TileTextureIndex` counts them). Alpha is the tile's coverage; color
+/// is averaged over its opaque part.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TilePalette(Vec<[u8; 4]>);
+
+impl TilePalette {
+    /// Sample a tileset given as RGBA8 pixels.
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Self {
+        let (columns, rows) = (width / TILE_PX, height / TILE_PX);
+        let mut colors = Vec::with_capacity((columns * rows) as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                let mut sum = [0u64; 4];
+                for y in row * TILE_PX..(row + 1) * TILE_PX {
+                    for x in column * TILE_PX..(column + 1) * TILE_PX {
+                        let i = ((y * width + x) * 4) as usize;
+                        let Some(&[r, g, b, a]) = rgba.get(i..i + 4).and_then(|pixel| <&[u8; 4]>::try_from(pixel).ok())
+                        else {
+                            continue;
+                        };
+                        let a = u64::from(a);
+                        sum[0] += u64::from(r) * a;
+                        sum[1] += u64::from(g) * a;
+                        sum[2] += u64::from(b) * a;
+                        sum[3] += a;
+                    }
+                }
+                let color = match sum[3] {
+                    0 => [0, 0, 0, 0],
+                    coverage => [
+                        (sum[0] / coverage) as u8,
+                        (sum[1] / coverage) as u8,
+                        (sum[2] / coverage) as u8,
+                        (coverage / u64::from(TILE_PX * TILE_PX)) as u8,
+                    ],
+                };
+                colors.push(color);
+            }
+        }
+        Self(colors)
+    }
+
+    /// Sample a loaded tileset image; None unless it's 8-bit RGBA.
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let format = image.texture_descriptor.format;
+        if !matches!(format, TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm) {
+            warn!("🖼️ Can't sample a {format:?} tileset for thumbnails");
+            return None;
+        }
+        Some(Self::from_rgba(image.width(), image.height(), image.data.as_ref()?))
+    }
+
+    /// Tile `index`'s color. Index 0 is the blank tile; with nothing
+    /// sampled, every other tile is `SILHOUETTE`.
+    pub fn color(&self, index: u32) -> [u8; 4] {
+        match index {
+            0 => [0, 0, 0, 0],
+            _ if self.0.is_empty() => SILHOUETTE,
+            _ => self.0.get(index as usize).copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Draw `map` as `THUMBNAIL_WIDTH` x `THUMBNAIL_HEIGHT` RGBA8 pixels: the
+/// whole map at the largest scale that fits, centered, the rest
+/// transparent. Each pixel takes the tiles under its center, every layer
+/// composited bottom to top.
+pub fn rasterize(map: &MapData, palette: &TilePalette) -> Vec<u8> {
+    let mut pixels = vec![0; (THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4) as usize];
+    if map.width == 0 || map.height == 0 {
+        return pixels;
+    }
+    let scale = (THUMBNAIL_WIDTH as f32 / map.width as f32).min(THUMBNAIL_HEIGHT as f32 / map.height as f32);
+    let left = (THUMBNAIL_WIDTH as f32 - map.width as f32 * scale) / 2.0;
+    let top = (THUMBNAIL_HEIGHT as f32 - map.height as f32 * scale) / 2.0;
+    // Stacked as drawn: ground under overhead, then by `z`, then as listed.
+    let tile_layers = map.tile_layers();
+    let mut layers: Vec<&TileLayerData> = tile_layers.iter().collect();
+    layers.sort_by(|a, b| a.overhead.cmp(&b.overhead).then(a.z.total_cmp(&b.z)));
+    for py in 0..THUMBNAIL_HEIGHT {
+        for px in 0..THUMBNAIL_WIDTH {
+            let x = ((px as f32 + 0.5 - left) / scale).floor();
+            let y = ((py as f32 + 0.5 - top) / scale).floor();
+            if x < 0.0 || y < 0.0 || x >= map.width as f32 || y >= map.height as f32 {
+                continue;
+            }
+            let cell = (y as u32 * map.width + x as u32) as usize;
+            let color = layers.iter().fold([0; 4], |below, layer| {
+                over(palette.color(layer.tiles.get(cell).copied().unwrap_or(0)), below)
+            });
+            let i = ((py * THUMBNAIL_WIDTH + px) * 4) as usize;
+            pixels[i..i + 4].copy_from_slice(&color);
+        }
+    }
+    pixels
+}
+
+/// `top` alpha-composited over `bottom`.
+fn over(top: [u8; 4], bottom: [u8; 4]) -> [u8; 4] {
+    let (ta, ba) = (f32::from(top[3]) / 255.0, f32::from(bottom[3]) / 255.0);
+    let alpha = ta + ba * (1.0 - ta);
+    if alpha == 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |i: usize| {
+        ((f32::from(top[i]) * ta + f32::from(bottom[i]) * ba * (1.0 - ta)) / alpha).round() as u8
+    };
+    [channel(0), channel(1), channel(2), (alpha * 255.0).round() as u8]
+}
+
+/// What a map file's thumbnail is known by: FNV-1a over the file, and
+/// `THUMBNAIL_VERSION`.
+pub fn content_hash(map_json: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in THUMBNAIL_VERSION.as_bytes().iter().chain(b"\0").chain(map_json) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+pub fn cache_path(dir: &Path, map_file: &str, hash: &str) -> PathBuf {
+    dir.join(format!("{map_file}-{hash}.rgba"))
+}
+
+/// The kept pixels of `map_file` as of `hash`, if there are any (and
+/// they're the right size).
+pub fn read_cached(dir: &Path, map_file: &str, hash: &str) -> Option<Vec<u8>> {
+    std::fs::read(cache_path(dir, map_file, hash))
+        .ok()
+        .filter(|pixels| pixels.len() == (THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4) as usize)
+}
+
+/// Keep `pixels` as `map_file`'s thumbnail as of `hash`, and drop any
+/// from before its last change.
+pub fn write_cached(dir: &Path, map_file: &str, hash: &str, pixels: &[u8]) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = cache_path(dir, map_file, hash);
+    std::fs::write(&path, pixels)?;
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let stale = entry.path() != path
+            && entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix(map_file)?.strip_prefix('-')?.strip_suffix(".rgba"))
+                .is_some_and(|old_hash| old_hash.len() == hash.len() && !old_hash.contains('-'));
+        if stale {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+fn thumbnail_image(pixels: Vec<u8>) -> Image {
+    Image::new(
+        Extent3d { width: THUMBNAIL_WIDTH, height: THUMBNAIL_HEIGHT, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        pixels,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Every thumbnail asked for, and the work toward them.
+#[derive(Resource, Default)]
+pub struct MapThumbnails {
+    handles: HashMap<Scene, Handle<Image>>,
+    /// By tileset key, once sampled.
+    palettes: HashMap<&'static str, Arc<TilePalette>>,
+    queued: Vec<Queued>,
+    running: HashMap<Scene, Task<Generated>>,
+}
+
+struct Queued {
+    scene: Scene,
+    /// Held so a lazy tileset keeps loading.
+    tileset: Option<Handle<Image>>,
+    /// The disk cache has been checked, and missed: waiting on the tileset.
+    missed: bool,
+}
+
+enum Generated {
+    Drawn { pixels: Vec<u8>, sampled: Option<Arc<TilePalette>> },
+    /// Not cached, and the tileset isn't in yet.
+    NeedsTileset,
+    Failed(String),
+}
+
+/// What a job needs besides its scene, gathered on the main thread.
+struct Job {
+    scene: Scene,
+    map_directory: Option<PathBuf>,
+    cache: Option<PathBuf>,
+    palette: Option<Arc<TilePalette>>,
+    tileset: Option<Image>,
+}
+
+/// Thumbnails for menus to show. Without `MapThumbnailsPlugin` nothing is
+/// drawn, and every map is the one placeholder.
+#[derive(SystemParam)]
+pub struct MapThumbnail<'w, 's> {
+    thumbnails: Option<ResMut<'w, MapThumbnails>>,
+    images: ResMut<'w, Assets<Image>>,
+    placeholder: Local<'s, Option<Handle<Image>>>,
+}
+
+impl MapThumbnail<'_, '_> {
+    /// `scene`'s map as a thumbnail: drawn already, or a placeholder that
+    /// becomes it when it is. Asking again is free.
+    pub fn get_or_generate(&mut self, scene: Scene) -> Handle<Image> {
+        let Some(thumbnails) = self.thumbnails.as_deref_mut() else {
+            return self.placeholder.get_or_insert_with(|| self.images.add(placeholder_thumbnail())).clone();
+        };
+        if let Some(handle) = thumbnails.handles.get(&scene) {
+            return handle.clone();
+        }
+        let handle = self.images.add(placeholder_thumbnail());
+        thumbnails.handles.insert(scene, handle.clone());
+        thumbnails.queued.push(Queued { scene, tileset: None, missed: false });
+        handle
+    }
+}
+
+fn placeholder_thumbnail() -> Image {
+    thumbnail_image(PLACEHOLDER.repeat((THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT) as usize))
+}
+
+fn start_thumbnails(
+    mut thumbnails: ResMut<MapThumbnails>,
+    game_assets: Option<Res<GameAssets>>,
+    images: Res<Assets<Image>>,
+    asset_server: Option<Res<AssetServer>>,
+    map_directory: Option<Res<MapDirectory>>,
+    cache: Option<Res<ThumbnailCache>>,
+) {
+    if thumbnails.queued.is_empty() {
+        return;
+    }
+    let thumbnails = &mut *thumbnails;
+    for mut queued in std::mem::take(&mut thumbnails.queued) {
+        let key = scene_config(queued.scene).tileset_key;
+        let mut palette = thumbnails.palettes.get(key).cloned();
+        let mut tileset = None;
+        if palette.is_none() {
+            if queued.tileset.is_none() {
+                queued.tileset = game_assets.as_ref().and_then(|assets| assets.tileset(key, asset_server.as_deref()));
+            }
+            let failed = |handle: &Handle<Image>| {
+                asset_server.as_ref().is_some_and(|server| server.load_state(handle).is_failed())
+            };
+            match &queued.tileset {
+                // No tile art to sample: a silhouette of the map's shape.
+                None => palette = Some(Arc::default()),
+                Some(handle) if failed(handle) => palette = Some(Arc::default()),
+                Some(handle) => tileset = images.get(handle).cloned(),
+            }
+            // Already missed the cache: nothing to do till the tileset's in.
+            if queued.missed && palette.is_none() && tileset.is_none() {
+                thumbnails.queued.push(queued);
+                continue;
+            }
+        }
+        let job = Job {
+            scene: queued.scene,
+            map_directory: map_directory.as_ref().map(|dir| dir.0.clone()),
+            cache: cache.as_ref().map(|cache| cache.0.clone()),
+            palette,
+            tileset,
+        };
+        let task = AsyncComputeTaskPool::get().spawn(async move { generate(job) });
+        thumbnails.running.insert(queued.scene, task);
+    }
+}
+
+/// Off the main thread: the cached pixels if the map hasn't changed,
+/// otherwise draw it (and keep it).
+fn generate(job: Job) -> Generated {
+    let map_file = scene_config(job.scene).map_file;
+    let source = match &job.map_directory {
+        Some(dir) => std::fs::read(dir.join(format!("{map_file}.json"))).ok(),
+        None => crate::asset_manifest::map_json(map_file).map(|json| json.as_bytes().to_vec()),
+    };
+    let Some(source) = source else { return Generated::Failed(format!("no map file {map_file:?}")) };
+    let hash = content_hash(&source);
+    if let Some(pixels) = job.cache.as_deref().and_then(|dir| read_cached(dir, map_file, &hash)) {
+        return Generated::Drawn { pixels, sampled: None };
+    }
+
+    let (palette, sampled) = match (job.palette, job.tileset.as_ref().and_then(TilePalette::from_image)) {
+        (Some(palette), _) => (palette, None),
+        (None, Some(palette)) => {
+            let palette = Arc::new(palette);
+            (palette.clone(), Some(palette))
+        }
+        (None, None) if job.tileset.is_some() => (Arc::default(), None),
+        (None, None) => return Generated::NeedsTileset,
+    };
+    let map = match MapData::parse(&source) {
+        Ok(map) => map,
+        Err(e) => return Generated::Failed(e.to_string()),
+    };
+    let pixels = rasterize(&map, &palette);
+    if let Some(dir) = &job.cache
+        && let Err(e) = write_cached(dir, map_file, &hash, &pixels)
+    {
+        warn!("🖼️ Couldn't keep the {map_file} thumbnail in {}: {e}", dir.display());
+    }
+    Generated::Drawn { pixels, sampled }
+}
+
+fn collect_thumbnails(mut thumbnails: ResMut<MapThumbnails>, mut images: ResMut<Assets<Image>>) {
+    if thumbnails.running.is_empty() {
+        return;
+    }
+    let thumbnails = &mut *thumbnails;
+    let mut finished = Vec::new();
+    thumbnails.running.retain(|&scene, task| match check_ready(task) {
+        Some(generated) => {
+            finished.push((scene, generated));
+            false
+        }
+        None => true,
+    });
+    for (scene, generated) in finished {
+        match generated {
+            Generated::Drawn { pixels, sampled } => {
+                if let Some(palette) = sampled {
+                    thumbnails.palettes.insert(scene_config(scene).tileset_key, palette);
+                }
+                if let Some(handle) = thumbnails.handles.get(&scene)
+                    && let Err(e) = images.insert(handle, thumbnail_image(pixels))
+                {
+                    warn!("🖼️ Lost the {scene:?} thumbnail: {e}");
+                }
+            }
+            Generated::NeedsTileset => thumbnails.queued.push(Queued { scene, tileset: None, missed: true }),
+            Generated::Failed(e) => warn!("🖼️ No thumbnail for {scene:?}: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(width: u32, height: u32, tiles: Vec<u32>) -> MapData {
+        let json = serde_json::json!({ "name": "thumb", "width": width, "height": height, "tiles": tiles, "npcs": [] });
+        MapData::parse(json.to_string().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn without_the_plugin_every_map_is_one_placeholder() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<Assets<Image>>();
+        let (town, disco) = world
+            .run_system_once(|mut thumbnails: MapThumbnail| {
+                (thumbnails.get_or_generate(Scene::TownOfEndgame), thumbnails.get_or_generate(Scene::TeamDisco))
+            })
+            .unwrap();
+        assert_eq!(town, disco);
+        assert!(world.resource::<Assets<Image>>().contains(&town));
+    }
+
+    #[test]
+    fn a_tile_is_its_average_color() {
+        // Two tiles side by side: solid red, and half blue, half clear.
+        let (width, height) = (TILE_PX * 2, TILE_PX);
+        let mut rgba = Vec::new();
+        for _ in 0..height {
+            for x in 0..width {
+                rgba.extend_from_slice(match x {
+                    x if x < TILE_PX => &[255, 0, 0, 255],
+                    x if x < TILE_PX + TILE_PX / 2 => &[0, 0, 255, 255],
+                    _ => &[0, 0, 0, 0],
+                });
+            }
+        }
+        let palette = TilePalette::from_rgba(width, height, &rgba);
+        assert_eq!(palette.color(0), [0, 0, 0, 0], "index 0 is the blank tile");
+        assert_eq!(palette.color(1), [0, 0, 255, 127], "clear pixels thin it, not darken it");
+        assert_eq!(TilePalette::default().color(5), SILHOUETTE);
+    }
+
+    #[test]
+    fn a_map_is_letterboxed_into_the_thumbnail() {
+        let palette = TilePalette(vec![[0; 4], [255, 0, 0, 255], [0, 255, 0, 255]]);
+        // 2x1 tiles: 64px each, a 128x64 strip with 16px clear above and below.
+        let pixels = rasterize(&map(2, 1, vec![1, 2]), &palette);
+        let at = |x: u32, y: u32| {
+            let i = ((y * THUMBNAIL_WIDTH + x) * 4) as usize;
+            <[u8; 4]>::try_from(&pixels[i..i + 4]).unwrap()
+        };
+        assert_eq!(at(10, 48), [255, 0, 0, 255]);
+        assert_eq!(at(100, 48), [0, 255, 0, 255]);
+        assert_eq!(at(10, 5), [0, 0, 0, 0]);
+        assert_eq!(at(10, 90), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn overhead_layers_cover_the_ground_whatever_order_they_are_listed_in() {
+        let palette = TilePalette(vec![[0; 4], [255, 0, 0, 255], [0, 255, 0, 255]]);
+        let json = serde_json::json!({ "name": "thumb", "width": 2, "height": 1, "npcs": [], "layers": [
+            { "tiles": [2, 0], "overhead": true },
+            { "tiles": [1, 1] },
+        ] });
+        let pixels = rasterize(&MapData::parse(json.to_string().as_bytes()).unwrap(), &palette);
+        let at = |x: u32, y: u32| {
+            let i = ((y * THUMBNAIL_WIDTH + x) * 4) as usize;
+            <[u8; 4]>::try_from(&pixels[i..i + 4]).unwrap()
+        };
+        assert_eq!(at(10, 48), [0, 255, 0, 255], "the canopy");
+        assert_eq!(at(100, 48), [255, 0, 0, 255], "ground where the canopy is empty");
+    }
+
+    #[test]
+    fn an_edited_map_is_drawn_again_and_its_old_thumbnail_dropped() {
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/maps/town_of_endgame.json");
+        let original = std::fs::read(fixture).unwrap();
+        let mut edited: serde_json::Value = serde_json::from_slice(&original).unwrap();
+        edited["tiles"][0] = 1.into();
+        let edited = serde_json::to_vec(&edited).unwrap();
+        let (before, after) = (content_hash(&original), content_hash(&edited));
+        assert_eq!(before, content_hash(&original), "the same file, the same hash");
+        assert_ne!(before, after);
+
+        let dir = std::env::temp_dir().join(format!("sregame-thumbnails-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let pixels = vec![7; (THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4) as usize];
+        write_cached(&dir, "town_of_endgame", &before, &pixels).unwrap();
+        write_cached(&dir, "team_marathon", &before, &pixels).unwrap();
+        assert_eq!(read_cached(&dir, "town_of_endgame", &before), Some(pixels.clone()));
+        assert_eq!(read_cached(&dir, "town_of_endgame", &after), None, "edited: not cached");
+
+        write_cached(&dir, "town_of_endgame", &after, &pixels).unwrap();
+        assert!(!cache_path(&dir, "town_of_endgame", &before).exists(), "the old one is gone");
+        assert!(read_cached(&dir, "town_of_endgame", &after).is_some());
+        assert!(read_cached(&dir, "team_marathon", &before).is_some(), "other maps' are left alone");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::TileTextureIndex;
+use std::collections::BTreeMap;
+use crate::content_errors::{ContentErrors, ContentMetrics};
+use crate::game_state::GameState;
+use crate::map_data::AnimatedTileData;
+use crate::tilemap::TILE_PX;
+
+/// Water that ripples, torches that flicker: a map's `animated_tiles`
+/// (see `MapData::animated_tiles`) step their cells through their frames.
+///
+/// spawn_map marks the cells whose index animates with `AnimatedTile`, so
+/// only those are touched - not the whole tile storage - and inserts
+/// `MapTileAnimations`. Nothing moves until the tileset has loaded (they
+/// load lazily): then any animation with a frame past the tileset's last
+/// tile is dropped, recorded as a content error naming the tile, and its
+/// cells stay as placed. The marked cells go with the map's other
+/// entities, and despawn_map removes the resource.
+pub struct TileAnimationPlugin;
+
+impl Plugin for TileAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContentErrors>().add_systems(
+            Update,
+            (check_tile_animations, animate_tiles)
+                .chain()
+                .run_if(resource_exists::<MapTileAnimations>.and(in_state(GameState::Playing))),
+        );
+    }
+}
+
+/// A map cell that animates: `tile` is its index as placed, the key into
+/// `MapTileAnimations`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimatedTile {
+    pub tile: u32,
+}
+
+/// The current map's tile animations. Same lifecycle as `CollisionMap`:
+/// inserted by spawn_map, removed by despawn_map.
+#[derive(Resource, Debug)]
+pub struct MapTileAnimations {
+    /// The map file, for content errors.
+    pub path: String,
+    /// What the frames index into; None when the scene's tileset is
+    /// missing, and nothing is drawn to animate.
+    pub tileset: Option<Handle<Image>>,
+    pub animations: BTreeMap<u32, AnimatedTileData>,
+    /// Whether the frames have been checked against the tileset. Tiles
+    /// only animate after.
+    checked: bool,
+    /// Seconds since the map was entered: every animation runs off it, so
+    /// cells sharing an index stay in step.
+    elapsed: f32,
+}
+
+impl MapTileAnimations {
+    pub fn new(path: impl Into<String>, tileset: Option<Handle<Image>>, animations: BTreeMap<u32, AnimatedTileData>) -> Self {
+        Self { path: path.into(), tileset, animations, checked: false, elapsed: 0.0 }
+    }
+
+    /// The index `tile`'s cells show `elapsed` seconds in; None if it
+    /// doesn't animate.
+    pub fn frame(&self, tile: u32, elapsed: f32) -> Option<u32> {
+        let animation = self.animations.get(&tile)?;
+        let step = (elapsed / animation.frame_seconds) as usize;
+        animation.frames.get(step % animation.frames.len().max(1)).copied()
+    }
+}
+
+/// Tiles in a tileset image of `size` pixels.
+pub fn tileset_tiles(size: UVec2) -> u32 {
+    (size.x / TILE_PX) * (size.y / TILE_PX)
+}
+
+fn check_tile_animations(
+    mut animations: ResMut<MapTileAnimations>,
+    images: Option<Res<Assets<Image>>>,
+    mut content_errors: ResMut<ContentErrors>,
+    content_metrics: Option<Res<ContentMetrics>>,
+    time: Res<Time>,
+) {
+    if animations.checked {
+        return;
+    }
+    let tiles = match &animations.tileset {
+        None => None,
+        Some(tileset) => match images.as_ref().and_then(|images| images.get(tileset)) {
+            Some(image) => Some(tileset_tiles(image.size())),
+            // Not loaded yet.
+            None => return,
+        },
+    };
+    let animations = &mut *animations;
+    let path = animations.path.clone();
+    animations.animations.retain(|&tile, animation| {
+        let Some(problem) = animation.problem(tile, tiles) else { return true };
+        content_errors.record(&path, problem, time.elapsed(), content_metrics.as_deref());
+        false
+    });
+    animations.checked = true;
+}
+
+fn animate_tiles(
+    mut animations: ResMut<MapTileAnimations>,
+    mut tiles: Query<(&AnimatedTile, &mut TileTextureIndex)>,
+    time: Res<Time>,
+) {
+    if !animations.checked {
+        return;
+    }
+    animations.elapsed += time.delta_secs();
+    for (animated, mut index) in &mut tiles {
+        // Only written on a frame change, so a held frame doesn't mark the
+        // tile changed for the renderer every frame.
+        if let Some(frame) = animations.frame(animated.tile, animations.elapsed)
+            && index.0 != frame
+        {
+            index.0 = frame;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::RenderAssetUsages;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    fn torches() -> BTreeMap<u32, AnimatedTileData> {
+        BTreeMap::from([
+            (17, AnimatedTileData { frames: vec![17, 18, 19], frame_seconds: 0.25 }),
+            (30, AnimatedTileData { frames: vec![30, 400], frame_seconds: 0.5 }),
+        ])
+    }
+
+    #[test]
+    fn frames_loop_each_at_their_own_pace() {
+        let animations = MapTileAnimations::new("maps/town.json", None, torches());
+        let frames: Vec<Option<u32>> = [0.0, 0.3, 0.6, 0.8].iter().map(|&at| animations.frame(17, at)).collect();
+        assert_eq!(frames, [Some(17), Some(18), Some(19), Some(17)]);
+        assert_eq!(animations.frame(30, 0.6), Some(400));
+        assert_eq!(animations.frame(5, 0.6), None, "not animated");
+    }
+
+    #[test]
+    fn frames_past_the_tileset_drop_their_animation_and_name_the_tile() {
+        let mut world = World::new();
+        let mut images = Assets::<Image>::default();
+        // 4x5 tiles: indices 0-19.
+        let tileset = images.add(Image::new_fill(
+            Extent3d { width: 192, height: 240, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        ));
+        world.insert_resource(images);
+        world.init_resource::<ContentErrors>();
+        world.init_resource::<Time>();
+        world.insert_resource(MapTileAnimations::new("maps/town.json", Some(tileset), torches()));
+        let torch = world.spawn((AnimatedTile { tile: 17 }, TileTextureIndex(17))).id();
+        let broken = world.spawn((AnimatedTile { tile: 30 }, TileTextureIndex(30))).id();
+
+        world.run_system_once(check_tile_animations).unwrap();
+        let errors = &world.resource::<ContentErrors>().errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            (errors[0].path.as_str(), errors[0].error.as_str()),
+            ("maps/town.json", "animated tile 30 frame 400 is past the tileset's last tile, 19")
+        );
+
+        world.resource_mut::<Time>().advance_by(std::time::Duration::from_secs_f32(0.3));
+        world.run_system_once(animate_tiles).unwrap();
+        assert_eq!(world.get::<TileTextureIndex>(torch).unwrap().0, 18);
+        assert_eq!(world.get::<TileTextureIndex>(broken).unwrap().0, 30, "stays as placed");
+    }
+}
@@ -1,36 +1,56 @@
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 use crate::game_state::Scene;
-use crate::camera::{MainCamera, CameraFollow, CameraBounds};
+use crate::camera::{MainCamera, CameraFollow, CameraBounds, CameraZones};
 use crate::npc::{spawn_npc, Npc, NpcDialogue};
 use crate::transitions::Door;
-use crate::instrumentation::GameTracer;
+use crate::instrumentation::{GameTracer, PlayerSessionTrace, init_metrics};
+use crate::preload::PreparedScenes;
+use opentelemetry::KeyValue;
+use opentelemetry::trace::{Span as _, Status, Tracer as _};
+use crate::content_errors::{BrokenContent, ContentErrors, ContentMetrics};
+use crate::content_pack::ContentPacks;
 use crate::assets::GameAssets;
-use crate::map_data::{MapData, ExitData, tile_to_world, facing_from_string};
+use crate::map_data::{AnimatedTileData, DialogueBoxLayout, MapData, MapProblem, NpcData, ExitData, MAX_LAYER_Z, TileLayerData, tile_to_world, facing_from_string};
+use crate::dialogue::MapDialogueBox;
+use crate::flags::GameFlags;
+use crate::mood::Moods;
+use crate::group_conversation::{GroupConversation, MapGroupConversations};
 use crate::player::Player;
+use crate::soundscape::MapAmbience;
+use crate::tile_animation::{AnimatedTile, MapTileAnimations};
 
 pub struct TilemapPlugin;
 
 impl Plugin for TilemapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(bevy_ecs_tilemap::TilemapPlugin)
-            .add_systems(OnEnter(Scene::TownOfEndgame), spawn_map)
-            .add_systems(OnEnter(Scene::TeamMarathon), spawn_map)
-            .add_systems(OnEnter(Scene::TeamMarathonRetro), spawn_map)
-            .add_systems(OnEnter(Scene::TeamDisco), spawn_map)
-            .add_systems(OnEnter(Scene::TeamInferno), spawn_map)
-            .add_systems(OnEnter(Scene::MahoganyRow), spawn_map)
-            .add_systems(OnEnter(Scene::Intro), spawn_map)
-            .add_systems(OnEnter(Scene::End), spawn_map)
-            .add_systems(OnExit(Scene::TownOfEndgame), despawn_map)
-            .add_systems(OnExit(Scene::TeamMarathon), despawn_map)
-            .add_systems(OnExit(Scene::TeamMarathonRetro), despawn_map)
-            .add_systems(OnExit(Scene::TeamDisco), despawn_map)
-            .add_systems(OnExit(Scene::TeamInferno), despawn_map)
-            .add_systems(OnExit(Scene::MahoganyRow), despawn_map)
-            .add_systems(OnExit(Scene::Intro), despawn_map)
-            .add_systems(OnExit(Scene::End), despawn_map)
-            .add_systems(Update, pulse_interact_indicators);
+        // bevy_ecs_tilemap's plugin is rendering glue that reaches straight
+        // into the RenderApp; headless harnesses (testing::TestGame) run
+        // without a renderer and still need every map system below.
+        // An embedder may have added it already for maps of its own.
+        if app.get_sub_app(bevy::render::RenderApp).is_some()
+            && !app.is_plugin_added::<bevy_ecs_tilemap::TilemapPlugin>()
+        {
+            app.add_plugins(bevy_ecs_tilemap::TilemapPlugin);
+        }
+        app.add_message::<CollisionChangedEvent>()
+            .init_resource::<GameAssets>()
+            .init_resource::<ContentErrors>()
+            .init_resource::<GameFlags>()
+            .init_resource::<PreparedScenes>();
+        crate::map_data::init_dialogue_assets(app);
+        init_metrics::<ContentMetrics>(app);
+        // Every scene goes through the same two systems: spawn_map reads
+        // which map from the scene entered (see scene_config), and
+        // despawn_map takes down everything marked `Map`.
+        for scene in Scene::ALL {
+            app.add_systems(OnEnter(scene), spawn_map).add_systems(OnExit(scene), despawn_map);
+        }
+        app.add_systems(Update, pulse_interact_indicators);
+    }
+
+    fn finish(&self, app: &mut App) {
+        crate::require_plugin::<crate::game_state::GameStatePlugin>(app, "TilemapPlugin");
     }
 }
 
@@ -83,6 +103,15 @@ pub struct CollisionMap {
     /// Counter cells (RPGMaker tile flag 0x80): the action button reaches
     /// one tile across these. Filled from MapData::counters by spawn_map.
     pub counters: std::collections::HashSet<(i32, i32)>,
+    /// Telemetry terminal cells (see dashboard.rs). Filled from
+    /// MapData::terminals by spawn_map.
+    pub terminals: std::collections::HashSet<(i32, i32)>,
+    /// The dynamic layer over the static passability: which NPC stands on
+    /// (or is stepping onto) each cell, so two never share one. Kept by
+    /// npc_movement.rs, and gone with the map. The player doesn't collide
+    /// with these - NPC bodies are person-shaped, not tile-sized (see
+    /// npc_blocks_move in player.rs).
+    occupants: std::collections::HashMap<(i32, i32), Entity>,
 }
 
 impl CollisionMap {
@@ -92,6 +121,8 @@ impl CollisionMap {
             height,
             passability: vec![PASS_ALL; (width * height) as usize],
             counters: Default::default(),
+            terminals: Default::default(),
+            occupants: Default::default(),
         }
     }
 
@@ -101,7 +132,57 @@ impl CollisionMap {
             (width * height) as usize,
             "passability data doesn't match map dimensions"
         );
-        Self { width, height, passability, counters: Default::default() }
+        Self {
+            width,
+            height,
+            passability,
+            counters: Default::default(),
+            terminals: Default::default(),
+            occupants: Default::default(),
+        }
+    }
+
+    /// A map's static collision. CollisionMap stays in RPGMaker orientation
+    /// (y=0 = top row, same as the JSON), because every lookup goes through
+    /// world_to_tile, which returns RPGMaker-orientation coordinates.
+    /// Directional masks when the JSON has them; the coarse blocked/walkable
+    /// `collision` layer for older JSON; and for JSON with neither, just the
+    /// outer ring blocked, so the player can walk the map but not off it.
+    /// Props, counters and terminals are spawn_map's to add.
+    pub fn from_map_data(map: &MapData) -> Self {
+        let cell_count = (map.width * map.height) as usize;
+        if map.passability.len() == cell_count {
+            return Self::from_passability(map.width, map.height, map.passability.clone());
+        }
+        if !map.passability.is_empty() {
+            warn!(
+                "Map '{}' passability has {} cells, expected {} - falling back to collision",
+                map.name, map.passability.len(), cell_count
+            );
+        }
+        let mut fallback = Self::new(map.width, map.height);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let blocked = if map.collision.is_empty() {
+                    x == 0 || y == 0 || x == map.width - 1 || y == map.height - 1
+                } else {
+                    // A short layer fails closed: a missing cell is a wall.
+                    map.collision.get((y * map.width + x) as usize).copied().unwrap_or(true)
+                };
+                if blocked {
+                    fallback.set_tile(x, y, TileCollision::Blocked);
+                }
+            }
+        }
+        if map.collision.is_empty() {
+            warn!("Map '{}' has no collision data - blocking only its border", map.name);
+        }
+        fallback
+    }
+
+    /// Cells that can't be entered from any side.
+    pub fn blocked_tiles(&self) -> usize {
+        self.passability.iter().filter(|&&mask| mask == 0).count()
     }
 
     /// RPGMaker's Game_Map.isCounter.
@@ -109,6 +190,12 @@ impl CollisionMap {
         self.counters.contains(&(x, y))
     }
 
+    pub fn is_terminal(&self, x: i32, y: i32) -> bool {
+        self.terminals.contains(&(x, y))
+    }
+
+    /// The one way a tile's collision changes. At runtime, go through
+    /// `CollisionEdits` instead, so whoever depends on the map hears about it.
     pub fn set_tile(&mut self, x: u32, y: u32, collision: TileCollision) {
         if x < self.width && y < self.height {
             let index = (y * self.width + x) as usize;
@@ -134,6 +221,22 @@ impl CollisionMap {
         self.mask(x, y).is_some_and(|m| m != 0)
     }
 
+    /// The NPC on `(x, y)`, or stepping onto it.
+    pub fn occupant(&self, x: i32, y: i32) -> Option<Entity> {
+        self.occupants.get(&(x, y)).copied()
+    }
+
+    /// `entity` is on `tile` now, and off wherever it was.
+    pub fn occupy(&mut self, tile: (i32, i32), entity: Entity) {
+        self.vacate(entity);
+        self.occupants.insert(tile, entity);
+    }
+
+    /// `entity` is off the map (despawned).
+    pub fn vacate(&mut self, entity: Entity) {
+        self.occupants.retain(|_, occupant| *occupant != entity);
+    }
+
     /// Test-only direct mask access; production masks come from the baked
     /// map JSON via `from_passability`.
     #[cfg(test)]
@@ -164,6 +267,85 @@ impl CollisionMap {
         };
         from_mask & exit_bit != 0 && to_mask & entry_bit != 0
     }
+
+    /// Shortest tile-step route from `from` to `to`, both ends included,
+    /// by `can_step`; None when `to` can't be reached. A breadth-first
+    /// search - maps are a few thousand tiles at most.
+    pub fn find_path(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        self.find_path_to(from, |tile| tile == to, |_| false)
+    }
+
+    /// `find_path` to the nearest tile `arrived` accepts, never stepping
+    /// onto one `avoid` rules out - a route to beside someone, around
+    /// everyone else (examples/soak.rs).
+    pub fn find_path_to(
+        &self,
+        from: (i32, i32),
+        arrived: impl Fn((i32, i32)) -> bool,
+        avoid: impl Fn((i32, i32)) -> bool,
+    ) -> Option<Vec<(i32, i32)>> {
+        self.mask(from.0, from.1)?;
+        let mut came_from = std::collections::HashMap::from([(from, from)]);
+        let mut frontier = std::collections::VecDeque::from([from]);
+        while let Some(tile) = frontier.pop_front() {
+            if arrived(tile) {
+                let mut path = vec![tile];
+                let mut current = tile;
+                while current != from {
+                    current = came_from[&current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (dx, dy) in [(0, 1), (0, -1), (-1, 0), (1, 0)] {
+                let next = (tile.0 + dx, tile.1 + dy);
+                if !came_from.contains_key(&next) && !avoid(next) && self.can_step(tile, next) {
+                    came_from.insert(next, tile);
+                    frontier.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Tiles whose collision changed while the map was up - a door opening, a
+/// script walling something off. Written by `CollisionEdits`; anything
+/// that remembers a route across the map (a wanderer's step in progress,
+/// see npc.rs) rechecks it against these.
+#[derive(Message, Debug, Clone)]
+pub struct CollisionChangedEvent {
+    pub tiles: Vec<(u32, u32)>,
+}
+
+/// Runtime edits to the loaded `CollisionMap`: `CollisionMap::set_tile`,
+/// announced as one `CollisionChangedEvent` per call. Does nothing between
+/// maps.
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct CollisionEdits<'w> {
+    map: Option<ResMut<'w, CollisionMap>>,
+    changed: MessageWriter<'w, CollisionChangedEvent>,
+}
+
+impl CollisionEdits<'_> {
+    /// Sets each tile's collision. Tiles already as asked aren't reported,
+    /// and no event is written when none changed.
+    pub fn set_tiles(&mut self, tiles: impl IntoIterator<Item = ((u32, u32), TileCollision)>) {
+        let Some(map) = self.map.as_mut() else { return };
+        let mut changed = Vec::new();
+        for ((x, y), collision) in tiles {
+            let before = map.mask(x as i32, y as i32);
+            map.set_tile(x, y, collision);
+            if map.mask(x as i32, y as i32) != before {
+                changed.push((x, y));
+            }
+        }
+        if !changed.is_empty() {
+            debug!("🚧 Collision changed at {changed:?}");
+            self.changed.write(CollisionChangedEvent { tiles: changed });
+        }
+    }
 }
 
 /// Exit (portal) triggers for the currently loaded map. Same resource
@@ -172,6 +354,22 @@ impl CollisionMap {
 #[derive(Resource)]
 pub struct MapExits(pub Vec<ExitData>);
 
+/// Present while the loaded map is flagged `indoor` (see
+/// `MapData::indoor`). Same lifecycle as `MapExits`.
+#[derive(Resource)]
+pub struct IndoorMap;
+
+/// The loaded map's NPC entries, kept for runtime spawning (npc_spawning.rs):
+/// `placed` are re-checked against their `requires_flag` when flags change,
+/// `spawnable` are what a `spawn_npc` dialogue outcome may name. `path` is
+/// the map file, for content errors. Same lifecycle as `MapExits`.
+#[derive(Resource)]
+pub struct MapNpcs {
+    pub path: String,
+    pub placed: Vec<NpcData>,
+    pub spawnable: Vec<NpcData>,
+}
+
 /// Set by the transition system just before switching scenes; consumed by
 /// `spawn_map` on the following `OnEnter` to place the player at the correct
 /// tile in the newly-loaded map. Absent on the very first scene load, since
@@ -180,6 +378,9 @@ pub struct MapExits(pub Vec<ExitData>);
 pub struct PendingArrival {
     pub spawn_x: u32,
     pub spawn_y: u32,
+    /// Turn the player this way on arrival (`ExitData::target_facing`);
+    /// None leaves them facing the way they were.
+    pub facing: Option<crate::player::Facing>,
 }
 
 /// Per-scene map file + tileset lookup. Tileset keys are a contract with
@@ -204,24 +405,122 @@ pub fn scene_config(scene: Scene) -> SceneConfig {
     }
 }
 
+/// Read a scene's map JSON: from `MapDirectory` when one is given, else the
+/// embedded manifest, either way with any content packs over it (see
+/// content_pack.rs). Also returns the path content errors name. Shared by
+/// spawn_map and the background preloader (preload.rs).
+pub(crate) fn load_scene_map(
+    scene: Scene,
+    map_directory: Option<&std::path::Path>,
+    content_packs: Option<&ContentPacks>,
+) -> (anyhow::Result<MapData>, String) {
+    let config = scene_config(scene);
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(overlaid) = content_packs.and_then(|packs| packs.load_map(config.map_file, map_directory)) {
+        return overlaid;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = map_directory {
+        return (
+            MapData::load_from_dir(dir, config.map_file),
+            MapData::path_in_dir(dir, config.map_file).display().to_string(),
+        );
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = (map_directory, content_packs);
+    (MapData::load(config.map_file), format!("assets/data/maps/{}.json", config.map_file))
+}
+
 fn spawn_map(
     mut commands: Commands,
     scene: Res<State<Scene>>,
     game_assets: Res<GameAssets>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     mut camera_query: Query<&mut CameraFollow, With<MainCamera>>,
-    mut player_query: Query<&mut Transform, With<Player>>,
+    mut player_query: Query<(&mut Transform, Option<&PlayerSessionTrace>, Option<&mut crate::player::Facing>), With<Player>>,
     pending_arrival: Option<Res<PendingArrival>>,
     tracer: Option<Res<GameTracer>>,
+    #[cfg(not(target_arch = "wasm32"))] map_directory: Option<Res<crate::map_data::MapDirectory>>,
+    mut content_errors: ResMut<ContentErrors>,
+    time: Res<Time>,
+    content_metrics: Option<Res<ContentMetrics>>,
+    flags: Res<GameFlags>,
+    mut prepared_scenes: ResMut<PreparedScenes>,
+    (asset_server, images, moods): (Option<Res<AssetServer>>, Option<Res<Assets<Image>>>, Option<Res<Moods>>),
+    content_packs: Option<Res<ContentPacks>>,
 ) {
     let config = scene_config(*scene.get());
+    let started = web_time::Instant::now();
+
+    // Prepared in the background as the player approached (preload.rs);
+    // otherwise a cold read, here on the transition frame.
+    let (loaded, map_path, prepared_in) = match prepared_scenes.take(*scene.get()) {
+        Some(prepared) => {
+            info!("Entering {:?} from preloaded map data ({})", scene.get(), config.map_file);
+            (prepared.loaded, prepared.path, Some(prepared.prepared_in))
+        }
+        None => {
+            info!("Loading {:?} from map data ({})", scene.get(), config.map_file);
+            #[cfg(not(target_arch = "wasm32"))]
+            let map_directory = map_directory.as_ref().map(|dir| dir.0.as_path());
+            #[cfg(target_arch = "wasm32")]
+            let map_directory = None;
+            let (loaded, path) = load_scene_map(*scene.get(), map_directory, content_packs.as_deref());
+            (loaded, path, None)
+        }
+    };
+    let load_time = started.elapsed();
 
-    info!("Loading {:?} from map data ({})", scene.get(), config.map_file);
+    let mut transition_span = tracer.as_ref().map(|tracer| {
+        let context = player_query
+            .single()
+            .ok()
+            .and_then(|(_, session, _)| session)
+            .map(PlayerSessionTrace::as_context)
+            .unwrap_or_default();
+        let mut span = tracer.tracer().start_with_context("map.transition", &context);
+        span.set_attribute(KeyValue::new("map.scene", format!("{:?}", scene.get())));
+        span.set_attribute(KeyValue::new("map.preloaded", prepared_in.is_some()));
+        span.set_attribute(KeyValue::new("map.load_ms", load_time.as_secs_f64() * 1000.0));
+        if let Some(prepared_in) = prepared_in {
+            span.set_attribute(KeyValue::new("map.preload_ms", prepared_in.as_secs_f64() * 1000.0));
+        }
+        span
+    });
+
+    // A missing tileset is a visual gap, not a logical one: the map's
+    // collision, exits and NPCs must still come up so the transition system
+    // works even for scenes whose art hasn't been authored yet (several
+    // interior scenes don't have clean map JSON *or* art yet - see
+    // scene_config). Fall back to an empty texture handle and keep going.
+    let tileset = game_assets.tileset(config.tileset_key, asset_server.as_deref());
+    // Tilesets load lazily, so on a first visit its size may not be known
+    // yet and tiles go unchecked against it: tiles past its last are only
+    // warned about here, whenever they're seen (`validate` fails on them).
+    let tileset_tiles = tileset
+        .as_ref()
+        .and_then(|tileset| images.as_ref()?.get(tileset))
+        .map(|image| crate::tile_animation::tileset_tiles(image.size()));
+    let (hard, soft): (Vec<MapProblem>, Vec<MapProblem>) =
+        loaded.as_ref().map(|map| map.validate(tileset_tiles)).unwrap_or_default().into_iter().partition(MapProblem::is_hard);
 
-    let map = match MapData::load(config.map_file) {
-        Ok(m) => m,
-        Err(e) => {
-            error!("Failed to load map '{}': {:?}", config.map_file, e);
+    let map = match loaded {
+        Ok(map) if hard.is_empty() => map,
+        loaded => {
+            // Each hard problem is its own content error, so the author
+            // gets the whole list from one run.
+            let errors: Vec<String> = match loaded {
+                Err(e) => vec![format!("{e:#}")],
+                Ok(_) => hard.iter().map(MapProblem::to_string).collect(),
+            };
+            error!("Failed to load map '{}': {}", config.map_file, errors.join("; "));
+            if let Some(mut span) = transition_span.take() {
+                span.set_status(Status::error(errors.join("; ")));
+                span.end();
+            }
+            for error in &errors {
+                content_errors.record(&map_path, error, time.elapsed(), content_metrics.as_deref());
+            }
             // Don't leave a stale PendingArrival around for some later,
             // unrelated scene load to accidentally consume - a portal that
             // led nowhere shouldn't silently misplace the player next time
@@ -235,138 +534,42 @@ fn spawn_map(
     };
 
     info!("Loaded map: {} ({}x{})", map.name, map.width, map.height);
+    // Until they're authored, ids follow the names - and break what keys
+    // on them when a name changes.
+    for derived in map.derived_ids() {
+        warn!("{map_path}: {derived}");
+    }
+    // Not broken - the NPC just keeps quiet whenever none of its
+    // conditional dialogues hold.
+    for problem in map.npcs.iter().chain(&map.spawnable).filter_map(|npc| npc.default_dialogue_problem()) {
+        warn!("{map_path}: {problem}");
+    }
+    for problem in &soft {
+        warn!("{map_path}: {problem}");
+    }
+    for problem in moods.iter().flat_map(|moods| moods.unknown(map.moods())) {
+        warn!("{map_path}: {problem}");
+    }
 
-    // A missing tileset is a visual gap, not a logical one: the map's
-    // collision, exits and NPCs must still come up so the transition system
-    // works even for scenes whose art hasn't been authored yet (several
-    // interior scenes don't have clean map JSON *or* art yet - see
-    // scene_config). Fall back to an empty texture handle and keep going.
-    let texture_handle = match game_assets.tilesets.get(config.tileset_key).cloned() {
-        Some(handle) => handle,
-        None => {
-            warn!(
-                "Missing tileset '{}' for scene {:?} - rendering without tile art",
-                config.tileset_key, scene.get()
-            );
-            Handle::default()
-        }
-    };
-
-    const TILE_SIZE: TilemapTileSize = TilemapTileSize { x: 48.0, y: 48.0 };
-    const GRID_SIZE: TilemapGridSize = TilemapGridSize { x: 48.0, y: 48.0 };
-    // Ground and upper layers share one atlas (see tools/convert_maps.py),
-    // so both TilemapBundles below reference the same texture handle.
-    // Upper renders above the player/NPCs (z=1.0, see player.rs/npc.rs).
-    const GROUND_Z: f32 = 0.0;
-    const UPPER_Z: f32 = 2.0;
+    if tileset.is_none() {
+        warn!(
+            "Missing tileset '{}' for scene {:?} - rendering without tile art",
+            config.tileset_key, scene.get()
+        );
+    }
+    let texture_handle = tileset.clone().unwrap_or_default();
 
+    // Every layer shares one atlas (see tools/convert_maps.py), so each
+    // TilemapBundle references the same texture handle.
     let map_size = TilemapSize { x: map.width, y: map.height };
-
-    let ground_entity = commands.spawn_empty().id();
-    let mut ground_storage = TileStorage::empty(map_size);
-
-    let upper_entity = commands.spawn_empty().id();
-    let mut upper_storage = TileStorage::empty(map_size);
-
-    for y in 0..map.height {
-        for x in 0..map.width {
-            // Map JSON rows are RPGMaker-ordered (row 0 = top), while
-            // bevy_ecs_tilemap's TilePos y=0 is the BOTTOM row, so the row
-            // must be flipped here or the whole map renders vertically
-            // mirrored. Same convention boundary as map_data::tile_to_world.
-            let tile_pos = TilePos { x, y: map.height - 1 - y };
-            let index = (y * map.width + x) as usize;
-
-            let ground_index = map.tiles.get(index).copied().unwrap_or(0);
-            let ground_tile = commands
-                .spawn((
-                    TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(ground_entity),
-                        texture_index: TileTextureIndex(ground_index),
-                        ..default()
-                    },
-                    Map,
-                ))
-                .id();
-            ground_storage.set(&tile_pos, ground_tile);
-
-            let upper_index = map.upper_tiles.get(index).copied().unwrap_or(0);
-            let upper_tile = commands
-                .spawn((
-                    TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(upper_entity),
-                        texture_index: TileTextureIndex(upper_index),
-                        ..default()
-                    },
-                    Map,
-                ))
-                .id();
-            upper_storage.set(&tile_pos, upper_tile);
-        }
+    for (order, layer) in map.tile_layers().iter().enumerate() {
+        spawn_tile_layer(&mut commands, layer, order, map_size, texture_handle.clone(), &map.animated_tiles);
+    }
+    if !map.animated_tiles.is_empty() {
+        commands.insert_resource(MapTileAnimations::new(&map_path, tileset, map.animated_tiles.clone()));
     }
 
-    // TilemapAnchor::Center, NOT a hand-rolled -(W*48)/2 transform: the
-    // tilemap's native origin is the CENTER of the bottom-left tile (see
-    // bevy_ecs_tilemap::anchor), so the old manual offset rendered the whole
-    // map half a tile (24px) down-left of where tile_to_world - and
-    // therefore collision, NPCs, exits, and the player - believed tiles
-    // were. Felt like "collision is shifted" in playtesting. With Center,
-    // rendered tile centers coincide exactly with tile_to_world output.
-    commands.entity(ground_entity).insert((
-        TilemapBundle {
-            grid_size: GRID_SIZE,
-            size: map_size,
-            storage: ground_storage,
-            texture: TilemapTexture::Single(texture_handle.clone()),
-            tile_size: TILE_SIZE,
-            anchor: TilemapAnchor::Center,
-            transform: Transform::from_xyz(0.0, 0.0, GROUND_Z),
-            ..default()
-        },
-        Map,
-    ));
-
-    commands.entity(upper_entity).insert((
-        TilemapBundle {
-            grid_size: GRID_SIZE,
-            size: map_size,
-            storage: upper_storage,
-            texture: TilemapTexture::Single(texture_handle),
-            tile_size: TILE_SIZE,
-            anchor: TilemapAnchor::Center,
-            transform: Transform::from_xyz(0.0, 0.0, UPPER_Z),
-            ..default()
-        },
-        Map,
-    ));
-
-    // CollisionMap stays in RPGMaker orientation (y=0 = top row, same as the
-    // JSON), because every lookup goes through world_to_tile, which returns
-    // RPGMaker-orientation coordinates. Directional masks when the JSON has
-    // them; coarse blocked/walkable fallback for older JSON.
-    let cell_count = (map.width * map.height) as usize;
-    let mut collision_map = if map.passability.len() == cell_count {
-        CollisionMap::from_passability(map.width, map.height, map.passability.clone())
-    } else {
-        if !map.passability.is_empty() {
-            warn!(
-                "Map '{}' passability has {} cells, expected {} - falling back to collision",
-                map.name, map.passability.len(), cell_count
-            );
-        }
-        let mut fallback = CollisionMap::new(map.width, map.height);
-        for y in 0..map.height {
-            for x in 0..map.width {
-                let index = (y * map.width + x) as usize;
-                if map.collision.get(index).copied().unwrap_or(true) {
-                    fallback.set_tile(x, y, TileCollision::Blocked);
-                }
-            }
-        }
-        fallback
-    };
+    let mut collision_map = CollisionMap::from_map_data(&map);
     // Blocking props (The Boss's Truck): RPGMaker events with priority
     // "same as characters" and through=false are impassable, and the
     // tile-flag bake can't know about events.
@@ -378,6 +581,10 @@ fn spawn_map(
         .iter()
         .map(|&(x, y)| (x as i32, y as i32))
         .collect();
+    for &(x, y) in &map.terminals {
+        collision_map.set_tile(x, y, TileCollision::Blocked);
+        collision_map.terminals.insert((x as i32, y as i32));
+    }
     // NPCs deliberately do NOT bake into the tile map (they used to):
     // a full-tile block read as a boundary wider than the NPC's body yet
     // short enough for sprites to overlap vertically. They collide as
@@ -385,8 +592,78 @@ fn spawn_map(
     // in player.rs. (Verified against the original: every NPC event is
     // priority 1 / through=false; only doggo is through, and doggo is a
     // prop.)
+    info!("Map '{}' has {} blocked tiles", map.name, collision_map.blocked_tiles());
     commands.insert_resource(collision_map);
     commands.insert_resource(MapExits(map.exits.clone()));
+    let exit_problems = map.exits.iter().flat_map(|exit| exit.dialogue_problem().into_iter().chain(exit.facing_problem()));
+    for problem in exit_problems {
+        content_errors.record(&map_path, &problem, time.elapsed(), content_metrics.as_deref());
+    }
+    // Always inserted, zones or not: outside every zone (or on a map
+    // without any) it puts the camera back to the whole map at 1x.
+    let camera_zones = map
+        .camera_zones
+        .iter()
+        .filter(|zone| match zone.problem(map.width, map.height) {
+            Some(problem) => {
+                content_errors.record(&map_path, &problem, time.elapsed(), content_metrics.as_deref());
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect();
+    commands.insert_resource(CameraZones {
+        map_width: map.width,
+        map_height: map.height,
+        zones: camera_zones,
+    });
+    // Always inserted too: a map without ambience fades the last one's out.
+    let ambience = map
+        .ambience
+        .iter()
+        .filter(|layer| match layer.problem(map.width, map.height) {
+            Some(problem) => {
+                content_errors.record(&map_path, &problem, time.elapsed(), content_metrics.as_deref());
+                false
+            }
+            None => true,
+        })
+        .cloned()
+        .collect();
+    commands.insert_resource(MapAmbience {
+        map_width: map.width,
+        map_height: map.height,
+        layers: ambience,
+    });
+    if map.indoor {
+        commands.insert_resource(IndoorMap);
+    }
+    let group_conversations = map
+        .group_dialogues
+        .iter()
+        .filter_map(|group| match group.problem(&map) {
+            Some(problem) => {
+                content_errors.record(&map_path, &problem, time.elapsed(), content_metrics.as_deref());
+                None
+            }
+            None => Some(GroupConversation {
+                group: group.clone(),
+                lines: map.conversations[&group.dialogue_ref].clone(),
+            }),
+        })
+        .collect();
+    commands.insert_resource(MapGroupConversations(group_conversations));
+    // Always inserted, like the camera zones: the last map's placement
+    // mustn't carry over.
+    let box_layout = match map.box_layout.problem() {
+        Some(problem) => {
+            content_errors.record(&map_path, &problem, time.elapsed(), content_metrics.as_deref());
+            DialogueBoxLayout::default()
+        }
+        None => map.box_layout,
+    };
+    commands.insert_resource(MapDialogueBox(box_layout));
 
     if let Ok(mut camera_follow) = camera_query.single_mut() {
         let map_width_pixels = map.width as f32 * TILE_SIZE.x;
@@ -398,67 +675,61 @@ fn spawn_map(
         ));
     }
 
-    // Spawn NPCs from map data
+    // Spawn NPCs from map data. Flag-gated ones wait for their flag
+    // (npc_spawning.rs re-checks whenever flags change).
     info!("Spawning {} NPCs from map data", map.npcs.len());
     for npc_data in &map.npcs {
-        let world_pos = tile_to_world(npc_data.x, npc_data.y, map.width, map.height);
-
-        // Map sprite name to asset handle, looked up by filename stem from
-        // the data-driven GameAssets::npc_sprites map.
-        let Some(sprite_handle) = game_assets.npc_sprites.get(&npc_data.sprite).cloned() else {
-            warn!("Unknown NPC sprite: {} - skipping {}", npc_data.sprite, npc_data.name);
+        if !flags.allows(npc_data) {
+            info!("Holding back NPC {} until flag {:?} is set", npc_data.name, npc_data.requires_flag);
             continue;
-        };
-
-        let portrait_path = if !npc_data.dialogue.portrait.is_empty() {
-            format!("textures/portraits/{}.png", npc_data.dialogue.portrait)
-        } else {
-            String::new()
-        };
-
-        let npc_entity = spawn_npc(
+        }
+        let Some(npc_entity) = spawn_npc_from_data(
             &mut commands,
             &game_assets,
             &mut texture_atlas_layouts,
-            Vec3::new(world_pos.x, world_pos.y, 1.0),
-            sprite_handle,
-            Npc {
-                name: npc_data.name.clone(),
-                sprite_facing: facing_from_string(&npc_data.facing),
-                sprite_slot: npc_data.sprite_index,
-            },
-            npc_data.step_anime,
-            NpcDialogue {
-                speaker: npc_data.dialogue.speaker.clone(),
-                portrait_path,
-                portrait_face_index: npc_data.dialogue.face_index,
-                lines: npc_data.dialogue.lines.clone(),
-            },
+            npc_data,
+            (map.width, map.height),
+            &map_path,
+            &mut content_errors,
+            time.elapsed(),
+            content_metrics.as_deref(),
             tracer.as_deref(),
-        );
+            asset_server.as_deref(),
+        ) else {
+            continue;
+        };
         // Map marker so despawn_map removes NPCs on scene exit. Without it
         // NPCs leaked across transitions - live but offscreen in the next
         // map, complete with their Interactable zones (ghost dialogues).
         // Found via a mid-transfer BRP screenshot: a town NPC rendered in
         // the void outside the destination room.
         commands.entity(npc_entity).insert(Map);
-        // Solid body unless the original event is Through (doggo): the
-        // player's NPC collision (player.rs::npc_blocks_move) only sees
-        // NpcBody carriers.
-        if !npc_data.through {
-            commands.entity(npc_entity).insert(crate::npc::NpcBody);
-        }
-        if npc_data.wander {
-            commands.entity(npc_entity).insert(crate::npc::Wanderer::default());
+    }
+    // Signs and the like, read through the same interaction as NPCs. One
+    // with nothing to say is recorded and left out.
+    for object in &map.objects {
+        if let Some(problem) = object.problem() {
+            content_errors.record(&map_path, &problem, time.elapsed(), content_metrics.as_deref());
+            continue;
         }
-
-        info!("Spawned NPC: {} at tile ({}, {})", npc_data.name, npc_data.x, npc_data.y);
+        crate::map_objects::spawn_object_from_data(
+            &mut commands,
+            object,
+            (map.width, map.height),
+            &map_path,
+            asset_server.as_deref(),
+        );
     }
+    commands.insert_resource(MapNpcs {
+        path: map_path.clone(),
+        placed: map.npcs.clone(),
+        spawnable: map.spawnable.clone(),
+    });
 
     // Door sprites on exit trigger tiles (visual only - exit logic is in
     // MapExits; the open animation is driven by transitions.rs).
     for door in &map.doors {
-        let Some(handle) = game_assets.npc_sprites.get(&door.sprite).cloned() else {
+        let Some(handle) = game_assets.npc_sprite(&door.sprite, asset_server.as_deref()) else {
             warn!("Unknown door sprite: {} - skipping door at ({}, {})",
                 door.sprite, door.x, door.y);
             continue;
@@ -501,30 +772,36 @@ fn spawn_map(
     // no interaction. step_anime props bob in place via the shared
     // CharacterFrames + StepAnimation systems in npc.rs.
     for prop in &map.props {
-        let Some(handle) = game_assets.npc_sprites.get(&prop.sprite).cloned() else {
+        let Some(handle) = game_assets.npc_sprite(&prop.sprite, asset_server.as_deref()) else {
             warn!("Unknown prop sprite: {} - skipping {}", prop.sprite, prop.name);
             continue;
         };
 
-        let layout = texture_atlas_layouts.add(
-            crate::character_sheet::sheet_layout_with_frame(
-                UVec2::new(prop.frame_width, prop.frame_height),
-            ),
-        );
-        let facing_row = facing_from_string(&prop.facing) as u32;
-        let index = crate::character_sheet::atlas_index(
-            prop.sprite_index,
-            facing_row,
-            prop.pattern,
-        ) as usize;
+        let sheet = game_assets.sheet_options(&prop.sprite);
+        if prop.sprite_index >= sheet.grid.slots() {
+            warn!("Prop {} uses character {} of sprite {}, which has {} - skipping it",
+                prop.name, prop.sprite_index, prop.sprite, sheet.grid.slots());
+            continue;
+        }
+        let layout = texture_atlas_layouts.add(sheet.grid.layout(UVec2::new(prop.frame_width, prop.frame_height)));
+        let frames = crate::npc::CharacterFrames {
+            slot: prop.sprite_index,
+            facing_row: facing_from_string(&prop.facing) as u32,
+            sheet,
+        };
+        let frame = frames.frame(prop.pattern);
+        let index = frame.index as usize;
 
         let world_pos = tile_to_world(prop.x, prop.y, map.width, map.height);
         let y_offset = (prop.frame_height as f32 - TILE_SIZE.y) / 2.0;
 
         let mut prop_commands = commands.spawn((
-            Sprite::from_atlas_image(handle, TextureAtlas { layout, index }),
+            Sprite {
+                flip_x: frame.flip_x,
+                ..Sprite::from_atlas_image(handle, TextureAtlas { layout, index })
+            },
             Transform::from_xyz(world_pos.x, world_pos.y + y_offset, 0.95),
-            crate::npc::CharacterFrames { slot: prop.sprite_index, facing_row },
+            frames,
             // Feet at the tile the prop stands on, not its lifted center -
             // a 48x96 truck must y-sort by its ground line (see depth.rs).
             crate::depth::YSorted { foot_offset: -(prop.frame_height as f32) / 2.0 },
@@ -540,8 +817,9 @@ fn spawn_map(
     // Pulsing "interact here" highlights (see MapData::indicators): soft
     // warm overlays whose alpha breathes. Decoupled from exit triggers so
     // the glow can sit on the eye-catching graphic (the retro table's
-    // parchment) while the trigger stays on the walkable tiles.
-    for &(x, y) in &map.indicators {
+    // parchment) while the trigger stays on the walkable tiles. Terminals
+    // always get one.
+    for &(x, y) in map.indicators.iter().chain(&map.terminals) {
         let world_pos = tile_to_world(x, y, map.width, map.height);
         commands.spawn((
             Sprite {
@@ -559,18 +837,259 @@ fn spawn_map(
     }
 
     // If we arrived via a portal (see transitions.rs), place the player at
-    // the target spawn tile. If absent, this is either the very first scene
-    // load or a scene the player didn't reach via a portal - leave the
-    // player wherever it already is.
-    if let Some(arrival) = pending_arrival {
-        if let Ok(mut player_transform) = player_query.single_mut() {
-            let spawn_pos = tile_to_world(arrival.spawn_x, arrival.spawn_y, map.width, map.height);
+    // the target spawn tile; otherwise at the map's own `player_spawn`, if
+    // it has one. With neither, leave the player wherever it already is -
+    // the world origin in a new game.
+    let placement = match (&pending_arrival, &map.player_spawn) {
+        (Some(arrival), _) => Some((arrival.spawn_x, arrival.spawn_y, arrival.facing, "incoming spawn tile")),
+        (None, Some(spawn)) => {
+            let facing = spawn.facing.as_deref().and_then(crate::map_data::player_facing_from_string);
+            Some((spawn.x, spawn.y, facing, "map's player spawn"))
+        }
+        (None, None) => None,
+    };
+    if let Some((spawn_x, spawn_y, spawn_facing, what)) = placement {
+        if let Ok((mut player_transform, _, facing)) = player_query.single_mut() {
+            let spawn_pos = tile_to_world(spawn_x, spawn_y, map.width, map.height);
             player_transform.translation.x = spawn_pos.x;
             player_transform.translation.y = spawn_pos.y;
-            info!("Placed player at incoming spawn tile ({}, {})", arrival.spawn_x, arrival.spawn_y);
+            if let (Some(facing), Some(spawn_facing)) = (facing, spawn_facing) {
+                *facing.into_inner() = spawn_facing;
+            }
+            info!("Placed player at {what} ({spawn_x}, {spawn_y})");
+        }
+        // Straight there, not a pan across the map from wherever the
+        // camera was.
+        if let Ok(mut camera_follow) = camera_query.single_mut() {
+            camera_follow.snap = true;
         }
+        // A spawn tile that is itself a warp (the way back) mustn't send
+        // the player straight back: see transitions::ArrivedOn.
+        commands.insert_resource(crate::transitions::ArrivedOn { tile_x: spawn_x, tile_y: spawn_y });
+    }
+    if pending_arrival.is_some() {
         commands.remove_resource::<PendingArrival>();
     }
+
+    if let Some(mut span) = transition_span {
+        span.set_attribute(KeyValue::new("map.spawn_ms", (started.elapsed() - load_time).as_secs_f64() * 1000.0));
+        span.end();
+    }
+}
+
+/// One NPC from map data, with everything its entry asks for (body,
+/// wandering or patrolling, busy behavior, broken-content fallback). Shared by spawn_map
+/// and runtime spawns (npc_spawning.rs); the caller decides whether it is
+/// scene-scoped (`Map`). `source` names the file for content errors.
+/// `None` if the entry can't be spawned (unknown sprite - recorded).
+pub(crate) fn spawn_npc_from_data(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    npc_data: &NpcData,
+    map_size: (u32, u32),
+    source: &str,
+    content_errors: &mut ContentErrors,
+    at: std::time::Duration,
+    content_metrics: Option<&ContentMetrics>,
+    tracer: Option<&GameTracer>,
+    asset_server: Option<&AssetServer>,
+) -> Option<Entity> {
+    let world_pos = tile_to_world(npc_data.x, npc_data.y, map_size.0, map_size.1);
+
+    // Map sprite name to asset handle through the sprite registry
+    // (GameAssets::npc_sprite), which starts a lazy sheet loading.
+    let Some(sprite_handle) = game_assets.npc_sprite(&npc_data.sprite, asset_server) else {
+        let available = game_assets.npc_sprite_names().join(", ");
+        warn!("Unknown NPC sprite: {} - skipping {} (available: {available})", npc_data.sprite, npc_data.name);
+        content_errors.record(
+            source,
+            format!("NPC {:?} uses unknown sprite {:?} (available: {available})", npc_data.name, npc_data.sprite),
+            at,
+            content_metrics,
+        );
+        return None;
+    };
+    let sheet = game_assets.sheet_options(&npc_data.sprite);
+    if npc_data.sprite_index >= sheet.grid.slots() {
+        content_errors.record(
+            source,
+            format!(
+                "NPC {:?} uses character {} of sprite {:?}, which has {}",
+                npc_data.name,
+                npc_data.sprite_index,
+                npc_data.sprite,
+                sheet.grid.slots()
+            ),
+            at,
+            content_metrics,
+        );
+        return None;
+    }
+
+    let broken = npc_data.dialogue_problem().map(|error| {
+        content_errors.record(source, &error, at, content_metrics);
+        BrokenContent { path: source.to_string(), error }
+    });
+    let mut dialogue = NpcDialogue::from_data(&npc_data.dialogue, npc_data.requires_flag.clone(), source.to_string());
+    dialogue.alternatives = npc_data
+        .dialogues
+        .iter()
+        .map(|data| NpcDialogue::from_data(data, npc_data.requires_flag.clone(), source.to_string()))
+        .collect();
+    // Debug builds say what's wrong instead of nothing, with no topic menu
+    // or choices under it; release builds keep the authored lines (an
+    // empty conversation is then ignored by dialogue.rs, as before).
+    if let Some(broken) = &broken
+        && cfg!(debug_assertions)
+    {
+        dialogue.lines = vec![broken.fallback_line()];
+        dialogue.conditions.clear();
+        dialogue.alternatives.clear();
+        dialogue.topics.clear();
+        dialogue.branches.clear();
+    }
+    // A dialogue file takes over from the inline dialogue once it loads
+    // (see `NpcDialogue::spoken`).
+    if let Some(path) = &npc_data.dialogue_file {
+        match asset_server {
+            Some(asset_server) => dialogue.file = Some(asset_server.load(path.clone())),
+            None => warn!("No asset server to load {path} - {} keeps the inline dialogue", npc_data.name),
+        }
+    }
+
+    let npc_entity = spawn_npc(
+        commands,
+        game_assets,
+        texture_atlas_layouts,
+        Vec3::new(world_pos.x, world_pos.y, 1.0),
+        sprite_handle,
+        Npc {
+            id: npc_data.id(),
+            name: npc_data.name.clone(),
+            sprite_facing: facing_from_string(&npc_data.facing),
+            sprite_slot: npc_data.sprite_index,
+        },
+        sheet,
+        npc_data.step_anime,
+        dialogue,
+        tracer,
+    );
+    // Solid body unless the original event is Through (doggo): the
+    // player's NPC collision (player.rs::npc_blocks_move) only sees
+    // NpcBody carriers.
+    if !npc_data.through {
+        commands.entity(npc_entity).insert(crate::npc::NpcBody);
+    }
+    // A patrol off the map is recorded and not walked; the NPC stands at
+    // its spot.
+    for problem in npc_data.movement_problems(map_size.0, map_size.1) {
+        content_errors.record(source, &problem, at, content_metrics);
+    }
+    if let Some(movement) = crate::npc_movement::NpcMovement::from_data(npc_data, map_size) {
+        commands.entity(npc_entity).insert(movement);
+    }
+    if let Some(problem) = npc_data.interaction_problem() {
+        content_errors.record(source, &problem, at, content_metrics);
+    }
+    commands.entity(npc_entity).insert((npc_data.when_busy, crate::npc::Interactable::from_data(npc_data)));
+    commands
+        .entity(npc_entity)
+        .insert(crate::npc::NpcDialogueState::new(npc_data.repeat, npc_data.repeat_line()));
+    // An overlong line is dropped, not truncated mid-word; the rest of the
+    // NPC's chatter still plays.
+    for problem in npc_data.ambient_line_problems() {
+        content_errors.record(source, &problem, at, content_metrics);
+    }
+    let ambient_lines = npc_data.shown_ambient_lines();
+    if !ambient_lines.is_empty() {
+        commands.entity(npc_entity).insert(crate::ambient::AmbientChatter::new(ambient_lines));
+    }
+    if let Some(broken) = broken {
+        commands.entity(npc_entity).insert(broken);
+    }
+
+    info!("Spawned NPC: {} at tile ({}, {})", npc_data.name, npc_data.x, npc_data.y);
+    Some(npc_entity)
+}
+
+/// The game's tiles are 48px square: a tileset's, a map's grid, and the
+/// Tiled maps it imports (content/tiled.rs).
+pub const TILE_PX: u32 = 48;
+const TILE_SIZE: TilemapTileSize = TilemapTileSize { x: TILE_PX as f32, y: TILE_PX as f32 };
+const GRID_SIZE: TilemapGridSize = TilemapGridSize { x: TILE_PX as f32, y: TILE_PX as f32 };
+
+/// Ground layers draw under the y-sorted band (z=1.0, see depth.rs),
+/// overhead ones above it. A layer's own `z` orders it within its band;
+/// layers at the same `z` stack in listed order.
+const GROUND_Z: f32 = 0.0;
+const UPPER_Z: f32 = 2.0;
+const LAYER_ORDER_Z: f32 = 0.001;
+
+/// One `TileLayerData` as a tilemap. Empty cells (index 0) get no tile
+/// entity at all - most of an overhead layer is sky.
+fn spawn_tile_layer(
+    commands: &mut Commands,
+    layer: &TileLayerData,
+    order: usize,
+    map_size: TilemapSize,
+    texture: Handle<Image>,
+    animated_tiles: &std::collections::BTreeMap<u32, AnimatedTileData>,
+) {
+    let base = if layer.overhead { UPPER_Z } else { GROUND_Z };
+    let z = base + layer.z.clamp(0.0, MAX_LAYER_Z) + order as f32 * LAYER_ORDER_Z;
+    let layer_entity = commands.spawn_empty().id();
+    let mut storage = TileStorage::empty(map_size);
+
+    for y in 0..map_size.y {
+        for x in 0..map_size.x {
+            let index = layer.tiles.get((y * map_size.x + x) as usize).copied().unwrap_or(0);
+            if index == 0 {
+                continue;
+            }
+            // Map JSON rows are RPGMaker-ordered (row 0 = top), while
+            // bevy_ecs_tilemap's TilePos y=0 is the BOTTOM row, so the row
+            // must be flipped here or the whole map renders vertically
+            // mirrored. Same convention boundary as map_data::tile_to_world.
+            let tile_pos = TilePos { x, y: map_size.y - 1 - y };
+            let mut tile = commands.spawn((
+                TileBundle {
+                    position: tile_pos,
+                    tilemap_id: TilemapId(layer_entity),
+                    texture_index: TileTextureIndex(index),
+                    ..default()
+                },
+                Map,
+            ));
+            // Only these are ticked (tile_animation.rs).
+            if animated_tiles.contains_key(&index) {
+                tile.insert(AnimatedTile { tile: index });
+            }
+            let tile = tile.id();
+            storage.set(&tile_pos, tile);
+        }
+    }
+
+    // TilemapAnchor::Center, NOT a hand-rolled -(W*48)/2 transform: the
+    // tilemap's native origin is the CENTER of the bottom-left tile (see
+    // bevy_ecs_tilemap::anchor), so the old manual offset rendered the whole
+    // map half a tile (24px) down-left of where tile_to_world - and
+    // therefore collision, NPCs, exits, and the player - believed tiles
+    // were. Felt like "collision is shifted" in playtesting. With Center,
+    // rendered tile centers coincide exactly with tile_to_world output.
+    commands.entity(layer_entity).insert((
+        TilemapBundle {
+            grid_size: GRID_SIZE,
+            size: map_size,
+            storage,
+            texture: TilemapTexture::Single(texture),
+            tile_size: TILE_SIZE,
+            anchor: TilemapAnchor::Center,
+            transform: Transform::from_xyz(0.0, 0.0, z),
+            ..default()
+        },
+        Map,
+    ));
 }
 
 fn despawn_map(
@@ -582,9 +1101,17 @@ fn despawn_map(
     }
     commands.remove_resource::<CollisionMap>();
     commands.remove_resource::<MapExits>();
+    commands.remove_resource::<CameraZones>();
+    commands.remove_resource::<MapAmbience>();
+    commands.remove_resource::<MapTileAnimations>();
+    commands.remove_resource::<IndoorMap>();
+    commands.remove_resource::<MapNpcs>();
+    commands.remove_resource::<MapGroupConversations>();
+    commands.remove_resource::<MapDialogueBox>();
     // A door departure that caused this teardown holds player input frozen
     // until the scene actually swaps; release it here.
     commands.remove_resource::<crate::transitions::DepartingDoor>();
+    commands.remove_resource::<crate::transitions::ArrivedOn>();
     info!("Map despawned");
 }
 
@@ -609,6 +1136,34 @@ mod tests {
         assert!(max - min > 0.3, "pulse swing {} too subtle to notice", max - min);
     }
 
+    #[test]
+    fn collision_comes_from_the_map_or_blocks_only_its_border() {
+        let map = |layers: &str| {
+            crate::map_data::parse_map(&format!(
+                r#"{{ "name": "Tiny", "width": 3, "height": 3, "tiles": [1, 1, 1, 1, 1, 1, 1, 1, 1], {layers} "npcs": [] }}"#
+            ))
+            .unwrap()
+        };
+
+        let walled = CollisionMap::from_map_data(&map(""));
+        assert_eq!(walled.blocked_tiles(), 8, "the outer ring");
+        assert!(walled.is_walkable(1, 1) && !walled.is_walkable(0, 1));
+
+        let authored = CollisionMap::from_map_data(&map(
+            r#""collision": [false, false, false, false, true, false, false, false, false],"#,
+        ));
+        assert_eq!(authored.blocked_tiles(), 1);
+        assert!(!authored.is_walkable(1, 1) && authored.is_walkable(0, 0));
+
+        let short = CollisionMap::from_map_data(&map(r#""collision": [false, false],"#));
+        assert_eq!(short.blocked_tiles(), 7, "missing cells are walls");
+
+        let masks = CollisionMap::from_map_data(&map(
+            r#""passability": [15, 15, 15, 15, 15, 15, 15, 15, 0], "collision": [true, true, true, true, true, true, true, true, true],"#,
+        ));
+        assert_eq!(masks.blocked_tiles(), 1, "passability wins over collision");
+    }
+
     #[test]
     fn can_step_respects_one_way_edges() {
         // A "shop counter" cell: enterable/exitable down, left, right - but
@@ -650,4 +1205,53 @@ mod tests {
         map.set_tile(1, 0, TileCollision::Walkable);
         assert!(map.can_step((0, 0), (1, 0)));
     }
+
+    #[test]
+    fn an_occupant_moves_along_and_leaves() {
+        let mut map = CollisionMap::new(3, 1);
+        let npc = Entity::from_raw_u32(7).unwrap();
+        map.occupy((0, 0), npc);
+        map.occupy((1, 0), npc);
+        assert_eq!(map.occupant(0, 0), None, "a step leaves the old tile");
+        assert_eq!(map.occupant(1, 0), Some(npc));
+        assert!(map.is_walkable(1, 0), "terrain is unchanged underneath");
+        map.vacate(npc);
+        assert_eq!(map.occupant(1, 0), None);
+    }
+
+    #[test]
+    fn opening_a_door_reroutes_find_path() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        // Two rooms split by a wall at x=2 with a closed door at (2, 1).
+        let mut map = CollisionMap::new(5, 3);
+        for y in 0..3 {
+            map.set_tile(2, y, TileCollision::Blocked);
+        }
+        assert_eq!(map.find_path((0, 1), (4, 1)), None);
+        assert_eq!(map.find_path((0, 1), (0, 1)), Some(vec![(0, 1)]));
+        let beside_the_wall = |(x, _): (i32, i32)| x == 1;
+        assert_eq!(map.find_path_to((0, 0), beside_the_wall, |tile| tile == (1, 0)), Some(vec![(0, 0), (0, 1), (1, 1)]));
+
+        let mut world = World::new();
+        world.init_resource::<Messages<CollisionChangedEvent>>();
+        world.insert_resource(map);
+        let open_door = |mut edits: CollisionEdits| {
+            edits.set_tiles([((2, 1), TileCollision::Walkable), ((2, 0), TileCollision::Blocked)]);
+        };
+        world.run_system_once(open_door).unwrap();
+
+        let path = world.resource::<CollisionMap>().find_path((0, 1), (4, 1));
+        assert_eq!(path, Some(vec![(0, 1), (1, 1), (2, 1), (3, 1), (4, 1)]));
+        let changed: Vec<Vec<(u32, u32)>> = world
+            .resource::<Messages<CollisionChangedEvent>>()
+            .iter_current_update_messages()
+            .map(|event| event.tiles.clone())
+            .collect();
+        assert_eq!(changed, [vec![(2, 1)]], "the wall already blocked (2, 0)");
+
+        world.run_system_once(open_door).unwrap();
+        let events = world.resource::<Messages<CollisionChangedEvent>>();
+        assert_eq!(events.iter_current_update_messages().count(), 1, "an open door opening again changes nothing");
+    }
 }
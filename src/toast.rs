@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use crate::assets::GameAssets;
+
+/// Short-lived on-screen notices ("They're busy", ...). Gameplay systems
+/// write a `ShowToast` message and forget about it; this plugin owns the UI
+/// node and its fade. One toast at a time: a newer notice replaces the one
+/// on screen rather than stacking, so a burst of them can't bury the view.
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ShowToast>()
+            .add_systems(Update, (spawn_toasts, fade_toasts).chain());
+    }
+}
+
+#[derive(Message)]
+pub struct ShowToast {
+    pub text: String,
+}
+
+impl ShowToast {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+}
+
+/// How long a toast stays up, including its fade-out tail.
+const TOAST_SECONDS: f32 = 2.5;
+const TOAST_FADE_SECONDS: f32 = 0.5;
+
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut messages: MessageReader<ShowToast>,
+    existing: Query<Entity, With<Toast>>,
+    game_assets: Option<Res<GameAssets>>,
+) {
+    // Only the newest message of the frame matters - see the plugin docs.
+    let Some(toast) = messages.read().last() else {
+        return;
+    };
+
+    for entity in &existing {
+        commands.entity(entity).despawn();
+    }
+
+    info!("🍞 Toast: {}", toast.text);
+
+    let font = game_assets
+        .map(|assets| assets.dialogue_font.clone())
+        .unwrap_or_default();
+
+    commands
+        .spawn((
+            Toast {
+                timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(8.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.85)),
+                ))
+                .with_children(|chip| {
+                    chip.spawn((
+                        Text::new(toast.text.clone()),
+                        TextFont {
+                            font: font.into(),
+                            // 32px at 1080p, same Vh scaling as the dialogue box.
+                            font_size: FontSize::Vh(32.0 / 10.8),
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                });
+        });
+}
+
+/// Opacity multiplier for a toast `elapsed` seconds into its life: fully
+/// opaque, then a linear fade over the last `TOAST_FADE_SECONDS`.
+fn toast_alpha(elapsed: f32) -> f32 {
+    let fade_start = TOAST_SECONDS - TOAST_FADE_SECONDS;
+    if elapsed <= fade_start {
+        1.0
+    } else {
+        (1.0 - (elapsed - fade_start) / TOAST_FADE_SECONDS).clamp(0.0, 1.0)
+    }
+}
+
+fn fade_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &Children)>,
+    mut chips: Query<(&mut BackgroundColor, &Children)>,
+    mut texts: Query<&mut TextColor>,
+) {
+    for (entity, mut toast, children) in &mut toasts {
+        toast.timer.tick(time.delta());
+        if toast.timer.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let alpha = toast_alpha(toast.timer.elapsed_secs());
+        for child in children.iter() {
+            let Ok((mut background, chip_children)) = chips.get_mut(child) else {
+                continue;
+            };
+            background.0.set_alpha(0.85 * alpha);
+            for text in chip_children.iter() {
+                if let Ok(mut color) = texts.get_mut(text) {
+                    color.0.set_alpha(alpha);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toast_holds_then_fades_to_nothing() {
+        assert_eq!(toast_alpha(0.0), 1.0);
+        assert_eq!(toast_alpha(TOAST_SECONDS - TOAST_FADE_SECONDS), 1.0);
+        let mid = toast_alpha(TOAST_SECONDS - TOAST_FADE_SECONDS / 2.0);
+        assert!((mid - 0.5).abs() < 1e-4, "halfway through the fade, got {mid}");
+        assert_eq!(toast_alpha(TOAST_SECONDS), 0.0);
+    }
+}
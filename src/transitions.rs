@@ -1,7 +1,12 @@
 use bevy::prelude::*;
+use bevy::state::state::StateTransitionEvent;
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Counter, Meter};
+use crate::dialogue::{DialogueRequest, DialogueRequestBuilder, DialogueRequestSet};
 use crate::game_state::{Mode, Scene};
-use crate::map_data::{scene_from_str, world_to_tile, ExitTrigger};
-use crate::player::Player;
+use crate::instrumentation::{MetricsBundle, init_metrics};
+use crate::map_data::{player_facing_from_string, scene_from_str, world_to_tile, ExitTrigger};
+use crate::player::{Facing, Player};
 use crate::tilemap::{CollisionMap, MapExits, PendingArrival};
 
 /// Watches the player's position against the current map's exit triggers and
@@ -10,6 +15,11 @@ pub struct TransitionsPlugin;
 
 impl Plugin for TransitionsPlugin {
     fn build(&self, app: &mut App) {
+        app.add_message::<crate::player::BumpedIntoTile>()
+            .add_message::<DialogueRequest>();
+        crate::input::init_game_input(app);
+        init_metrics::<TransitionMetrics>(app);
+
         // Gated on Mode::Exploring (rather than GameState::Playing) so a
         // portal can't fire while a dialogue box is showing - Mode only
         // exists at all while GameState::Playing, so this also implies that.
@@ -21,14 +31,67 @@ impl Plugin for TransitionsPlugin {
             // the post-move position and this frame's bump messages, not
             // last frame's (kaibo review 2026-07-12, both reviewers).
             .after(crate::player::PlayerMovementSet)
+            .in_set(DialogueRequestSet)
             .run_if(in_state(Mode::Exploring)))
             // Fires the deferred transfer once the scripted scene closes
             // (Mode returns to Exploring). Also runs at game start and
             // after every ordinary dialogue - gated on the resource.
-            .add_systems(OnEnter(Mode::Exploring), fire_transfer_after_dialogue);
+            .add_systems(OnEnter(Mode::Exploring), fire_transfer_after_dialogue)
+            // However the scene changed - exit, door, scripted scene, a
+            // loaded save.
+            .add_systems(Update, count_map_transitions.run_if(on_message::<StateTransitionEvent<Scene>>));
+    }
+}
+
+/// `game.map.transitions`, by the scene left (`map.from`) and the one
+/// entered (`map.to`).
+#[derive(Resource)]
+pub struct TransitionMetrics {
+    pub transitions: Counter<u64>,
+}
+
+impl MetricsBundle for TransitionMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            transitions: meter
+                .u64_counter("game.map.transitions")
+                .with_description("Moves from one map to another, by scene left and entered (see transitions.rs)")
+                .build(),
+        }
+    }
+}
+
+fn count_map_transitions(
+    mut transitions: MessageReader<StateTransitionEvent<Scene>>,
+    metrics: Option<Res<TransitionMetrics>>,
+) {
+    for transition in transitions.read() {
+        // The first scene of a game is entered from no scene at all.
+        let (Some(from), Some(to)) = (transition.exited, transition.entered) else { continue };
+        if from == to {
+            continue;
+        }
+        info!("Map transition {from:?} -> {to:?}");
+        if let Some(metrics) = &metrics {
+            metrics.transitions.add(1, &[
+                KeyValue::new("map.from", format!("{from:?}")),
+                KeyValue::new("map.to", format!("{to:?}")),
+            ]);
+        }
     }
 }
 
+/// The tile the player was just put on by a transfer (see tilemap.rs's
+/// spawn_map). Standing on it doesn't fire a touch exit there until the
+/// player has stepped off: a spawn tile that is the warp back would
+/// otherwise bounce them between the two maps. Bumping into an exit, or
+/// pressing E on an action exit, still fires.
+#[derive(Resource, Debug, PartialEq, Eq)]
+pub struct ArrivedOn {
+    pub tile_x: u32,
+    pub tile_y: u32,
+}
+
 /// A transfer waiting for its scripted scene to finish (the exit had
 /// dialogue segments). Inserted by check_map_exits when the exit fires;
 /// consumed when Mode re-enters Exploring, i.e. when the dialogue closes -
@@ -39,6 +102,7 @@ pub struct PendingTransferAfterDialogue {
     pub(crate) target_scene: Scene,
     pub(crate) spawn_x: u32,
     pub(crate) spawn_y: u32,
+    pub(crate) facing: Option<Facing>,
     /// True for consent prompts (the End fairies): Escape drops this
     /// resource (see game_state::handle_escape_key) so the transfer never
     /// fires. False for scripted scenes like the retrospective, where
@@ -58,6 +122,7 @@ fn fire_transfer_after_dialogue(
     commands.insert_resource(PendingArrival {
         spawn_x: pending.spawn_x,
         spawn_y: pending.spawn_y,
+        facing: pending.facing,
     });
     next_scene.set(pending.target_scene);
     commands.remove_resource::<PendingTransferAfterDialogue>();
@@ -86,6 +151,7 @@ pub struct DepartingDoor {
     target_scene: Scene,
     spawn_x: u32,
     spawn_y: u32,
+    facing: Option<Facing>,
     /// 0 = closed (resting); 1..=3 = opening rows; past 3 = transfer.
     stage: u8,
     timer: Timer,
@@ -116,14 +182,14 @@ fn animate_door_departure(
     dep.stage += 1;
 
     if dep.stage <= 3 {
-        if let Ok((door, mut sprite)) = doors.get_mut(dep.door) {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                atlas.index = crate::character_sheet::atlas_index(
-                    door.sprite_slot,
-                    u32::from(dep.stage),
-                    door.pattern,
-                ) as usize;
-            }
+        if let Ok((door, mut sprite)) = doors.get_mut(dep.door)
+            && let Some(atlas) = &mut sprite.texture_atlas
+        {
+            atlas.index = crate::character_sheet::atlas_index(
+                door.sprite_slot,
+                u32::from(dep.stage),
+                door.pattern,
+            ) as usize;
         }
         let next_wait = if dep.stage == 3 { DOOR_OPEN_HOLD_SECONDS } else { DOOR_STAGE_SECONDS };
         dep.timer = Timer::from_seconds(next_wait, TimerMode::Once);
@@ -132,6 +198,7 @@ fn animate_door_departure(
         commands.insert_resource(PendingArrival {
             spawn_x: dep.spawn_x,
             spawn_y: dep.spawn_y,
+            facing: dep.facing,
         });
         next_scene.set(dep.target_scene);
         // Deliberately NOT removed here: the state transition applies at
@@ -150,13 +217,13 @@ fn dialogue_segments(
         .iter()
         .map(|seg| crate::dialogue::DialogueSegment {
             speaker: seg.speaker.clone(),
-            portrait_path: if seg.portrait.is_empty() {
-                String::new()
-            } else {
-                format!("textures/portraits/{}.png", seg.portrait)
-            },
+            portrait_path: crate::map_data::portrait_asset_path(&seg.portrait),
             portrait_face_index: seg.face_index,
             text: seg.text.clone(),
+            mood: seg.mood.clone(),
+            box_layout: seg.box_layout,
+            actions: Vec::new(),
+            speaker_id: None,
         })
         .collect()
 }
@@ -168,14 +235,16 @@ fn check_map_exits(
     collision_map: Option<Res<CollisionMap>>,
     doors: Query<(Entity, &Door)>,
     departing: Option<Res<DepartingDoor>>,
+    arrived_on: Option<Res<ArrivedOn>>,
     mut bumps: MessageReader<crate::player::BumpedIntoTile>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut dialogue_events: MessageWriter<crate::dialogue::StartDialogueEvent>,
+    keyboard: crate::input::GameInput,
+    mut dialogue_events: MessageWriter<DialogueRequest>,
     mut next_scene: ResMut<NextState<Scene>>,
 ) {
-    // A departure is already in flight - don't re-trigger. Still drain the
-    // bump messages so stale bumps can't fire an exit later.
-    if departing.is_some() {
+    // A departure is already in flight, or a transfer already set for the
+    // end of this frame - don't re-trigger. Still drain the bump messages
+    // so stale bumps can't fire an exit later.
+    if departing.is_some() || !matches!(*next_scene, NextState::Unchanged) {
         bumps.clear();
         return;
     }
@@ -206,6 +275,15 @@ fn check_map_exits(
     let (face_dx, face_dy) = facing.tile_delta();
     let (faced_x, faced_y) = (tile_x + face_dx, tile_y + face_dy);
 
+    // Not the tile a transfer just put the player on, until they leave it
+    // (see ArrivedOn).
+    let just_arrived = arrived_on
+        .as_ref()
+        .is_some_and(|arrived| (arrived.tile_x as i32, arrived.tile_y as i32) == (tile_x, tile_y));
+    if arrived_on.is_some() && !just_arrived {
+        commands.remove_resource::<ArrivedOn>();
+    }
+
     // A touch exit fires when the player stands on its tile (walkable exit
     // mats: the interior "To Town" tiles) OR bumps into it (RPGMaker
     // Player-Touch on impassable door tiles - the town doors are
@@ -213,7 +291,10 @@ fn check_map_exits(
     // exit only fires when the player stands on it and presses E - the
     // inn's "retro dialog" event is one, and treating it as touch warped
     // players to the End scene for walking near the table.
-    let mut touched_tiles: Vec<(i32, i32)> = vec![(tile_x, tile_y)];
+    let mut touched_tiles: Vec<(i32, i32)> = Vec::new();
+    if !just_arrived {
+        touched_tiles.push((tile_x, tile_y));
+    }
     for bump in bumps.read() {
         touched_tiles.push((bump.tile_x, bump.tile_y));
     }
@@ -242,9 +323,7 @@ fn check_map_exits(
                 "Player triggered scene at tile ({}, {}) - no transfer",
                 exit.trigger_x, exit.trigger_y
             );
-            dialogue_events.write(crate::dialogue::StartDialogueEvent {
-                segments: dialogue_segments(&exit.dialogue),
-            });
+            dialogue_events.write(DialogueRequestBuilder::segments(dialogue_segments(&exit.dialogue)).build());
             break;
         }
 
@@ -261,6 +340,8 @@ fn check_map_exits(
             exit.trigger_x, exit.trigger_y, target_scene, exit.target_spawn_x, exit.target_spawn_y
         );
 
+        let facing = exit.target_facing.as_deref().and_then(player_facing_from_string);
+
         // Precedence: a scripted scene plays first (transfer fires when it
         // closes); a door on the tile animates open first; bare exits
         // transfer immediately.
@@ -269,13 +350,12 @@ fn check_map_exits(
         });
 
         if !exit.dialogue.is_empty() {
-            dialogue_events.write(crate::dialogue::StartDialogueEvent {
-                segments: dialogue_segments(&exit.dialogue),
-            });
+            dialogue_events.write(DialogueRequestBuilder::segments(dialogue_segments(&exit.dialogue)).build());
             commands.insert_resource(PendingTransferAfterDialogue {
                 target_scene,
                 spawn_x: exit.target_spawn_x,
                 spawn_y: exit.target_spawn_y,
+                facing,
                 cancel_on_escape: exit.cancel_on_escape,
             });
         } else if let Some((door_entity, _)) = door_here {
@@ -284,6 +364,7 @@ fn check_map_exits(
                 target_scene,
                 spawn_x: exit.target_spawn_x,
                 spawn_y: exit.target_spawn_y,
+                facing,
                 stage: 0,
                 timer: Timer::from_seconds(DOOR_STAGE_SECONDS, TimerMode::Once),
             });
@@ -291,6 +372,7 @@ fn check_map_exits(
             commands.insert_resource(PendingArrival {
                 spawn_x: exit.target_spawn_x,
                 spawn_y: exit.target_spawn_y,
+                facing,
             });
             next_scene.set(target_scene);
         }
@@ -311,10 +393,10 @@ mod tests {
     // tile or target without the test being updated to match.
     fn town_of_endgame_exits() -> Vec<ExitData> {
         vec![
-            ExitData { trigger_x: 8, trigger_y: 29, target_scene: "TeamMarathonRetro".into(), target_spawn_x: 12, target_spawn_y: 15, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false },
-            ExitData { trigger_x: 23, trigger_y: 20, target_scene: "TeamDisco".into(), target_spawn_x: 7, target_spawn_y: 13, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false },
-            ExitData { trigger_x: 6, trigger_y: 18, target_scene: "TeamInferno".into(), target_spawn_x: 11, target_spawn_y: 18, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false },
-            ExitData { trigger_x: 29, trigger_y: 13, target_scene: "MahoganyRow".into(), target_spawn_x: 16, target_spawn_y: 10, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false },
+            ExitData { trigger_x: 8, trigger_y: 29, target_scene: "TeamMarathonRetro".into(), target_spawn_x: 12, target_spawn_y: 15, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false, target_facing: None },
+            ExitData { trigger_x: 23, trigger_y: 20, target_scene: "TeamDisco".into(), target_spawn_x: 7, target_spawn_y: 13, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false, target_facing: None },
+            ExitData { trigger_x: 6, trigger_y: 18, target_scene: "TeamInferno".into(), target_spawn_x: 11, target_spawn_y: 18, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false, target_facing: None },
+            ExitData { trigger_x: 29, trigger_y: 13, target_scene: "MahoganyRow".into(), target_spawn_x: 16, target_spawn_y: 10, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false, target_facing: None },
         ]
     }
 
@@ -326,8 +408,11 @@ mod tests {
         let mut world = World::new();
         world.init_resource::<NextState<Scene>>();
         world.init_resource::<Messages<crate::player::BumpedIntoTile>>();
-        world.init_resource::<Messages<crate::dialogue::StartDialogueEvent>>();
+        world.init_resource::<Messages<DialogueRequest>>();
         world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<crate::input::InputLatch>();
+        world.init_resource::<crate::input::KeyPressTimes>();
+        world.init_resource::<crate::input::InputMap>();
         world.insert_resource(MapExits(exits));
         world.insert_resource(CollisionMap::new(width, height));
 
@@ -373,7 +458,7 @@ mod tests {
     // mine where walking near the table teleported the player to End.
     fn retro_action_exit() -> Vec<ExitData> {
         vec![
-            ExitData { trigger_x: 12, trigger_y: 12, target_scene: "End".into(), target_spawn_x: 8, target_spawn_y: 5, trigger: ExitTrigger::Action, dialogue: vec![], cancel_on_escape: false },
+            ExitData { trigger_x: 12, trigger_y: 12, target_scene: "End".into(), target_spawn_x: 8, target_spawn_y: 5, trigger: ExitTrigger::Action, dialogue: vec![], cancel_on_escape: false, target_facing: None },
         ]
     }
 
@@ -421,6 +506,8 @@ mod tests {
             portrait: "Nature".into(),
             face_index: 4,
             text: "Thanks for helping us with this incident Amy.".into(),
+            mood: None,
+            box_layout: Default::default(),
         }];
         let mut world = setup_world((12, 12), exits, 24, 21);
         world
@@ -430,7 +517,7 @@ mod tests {
         world.run_system_once(check_map_exits).unwrap();
 
         let sent = world
-            .resource::<Messages<crate::dialogue::StartDialogueEvent>>()
+            .resource::<Messages<DialogueRequest>>()
             .iter_current_update_messages()
             .count();
         assert_eq!(sent, 1, "the scene should play");
@@ -521,7 +608,7 @@ mod tests {
     #[test]
     fn exit_with_dialogue_plays_the_scene_before_transferring() {
         // The retro dialog: E on the tile must start the scripted scene
-        // (StartDialogueEvent) and defer the transfer, not jump straight
+        // (DialogueRequest) and defer the transfer, not jump straight
         // to End - and once the scene closes, the deferred transfer fires.
         let mut exits = retro_action_exit();
         exits[0].dialogue = vec![crate::map_data::DialogueSegmentData {
@@ -529,6 +616,8 @@ mod tests {
             portrait: "Nature".into(),
             face_index: 4,
             text: "Thanks for helping us with this incident Amy.".into(),
+            mood: None,
+            box_layout: Default::default(),
         }];
         let mut world = setup_world((12, 12), exits, 24, 21);
         world
@@ -544,7 +633,7 @@ mod tests {
         assert!(world.get_resource::<PendingArrival>().is_none());
         assert!(world.get_resource::<PendingTransferAfterDialogue>().is_some());
         let sent = world
-            .resource::<Messages<crate::dialogue::StartDialogueEvent>>()
+            .resource::<Messages<DialogueRequest>>()
             .iter_current_update_messages()
             .count();
         assert_eq!(sent, 1, "the scripted scene should have been started");
@@ -592,6 +681,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn arriving_on_a_warp_does_not_send_the_player_straight_back() {
+        let mut world = setup_world((8, 29), town_of_endgame_exits(), TOWN_WIDTH, TOWN_HEIGHT);
+        world.insert_resource(ArrivedOn { tile_x: 8, tile_y: 29 });
+        let step_to = |world: &mut World, (x, y): (u32, u32)| {
+            let position = tile_to_world(x, y, TOWN_WIDTH, TOWN_HEIGHT);
+            let mut transforms = world.query_filtered::<&mut Transform, With<Player>>();
+            transforms.single_mut(world).unwrap().translation = position.extend(1.0);
+            world.run_system_once(check_map_exits).unwrap();
+        };
+
+        step_to(&mut world, (8, 29));
+        assert!(matches!(world.resource::<NextState<Scene>>(), NextState::Unchanged), "standing where they arrived");
+
+        step_to(&mut world, (8, 30));
+        assert!(world.get_resource::<ArrivedOn>().is_none(), "stepped off");
+        step_to(&mut world, (8, 29));
+        assert!(matches!(world.resource::<NextState<Scene>>(), NextState::Pending(Scene::TeamMarathonRetro)));
+    }
+
+    #[test]
+    fn a_transfer_already_set_this_frame_is_not_triggered_again() {
+        let mut world = setup_world((8, 29), town_of_endgame_exits(), TOWN_WIDTH, TOWN_HEIGHT);
+        world.resource_mut::<NextState<Scene>>().set(Scene::TeamDisco);
+
+        world.run_system_once(check_map_exits).unwrap();
+
+        assert!(matches!(world.resource::<NextState<Scene>>(), NextState::Pending(Scene::TeamDisco)));
+        assert!(world.get_resource::<PendingArrival>().is_none());
+    }
+
+    #[test]
+    fn an_exit_turns_the_player_the_way_it_says_on_arrival() {
+        let mut exits = town_of_endgame_exits();
+        exits[0].target_facing = Some("left".into());
+        let mut world = setup_world((8, 29), exits, TOWN_WIDTH, TOWN_HEIGHT);
+
+        world.run_system_once(check_map_exits).unwrap();
+
+        assert_eq!(world.resource::<PendingArrival>().facing, Some(Facing::Left));
+    }
+
     #[test]
     fn door_on_exit_tile_opens_before_the_transition_fires() {
         // With a Door entity on the trigger tile, touching the exit must
@@ -643,7 +774,7 @@ mod tests {
     // door out of the game's opening scene, back to Town of Endgame.
     fn intro_exits() -> Vec<ExitData> {
         vec![
-            ExitData { trigger_x: 8, trigger_y: 1, target_scene: "TownOfEndgame".into(), target_spawn_x: 16, target_spawn_y: 23, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false },
+            ExitData { trigger_x: 8, trigger_y: 1, target_scene: "TownOfEndgame".into(), target_spawn_x: 16, target_spawn_y: 23, trigger: ExitTrigger::Touch, dialogue: vec![], cancel_on_escape: false, target_facing: None },
         ]
     }
 
@@ -662,8 +793,11 @@ mod tests {
         let mut world = World::new();
         world.init_resource::<NextState<Scene>>();
         world.init_resource::<Messages<crate::player::BumpedIntoTile>>();
-        world.init_resource::<Messages<crate::dialogue::StartDialogueEvent>>();
+        world.init_resource::<Messages<DialogueRequest>>();
         world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<crate::input::InputLatch>();
+        world.init_resource::<crate::input::KeyPressTimes>();
+        world.init_resource::<crate::input::InputMap>();
         world.insert_resource(MapExits(intro_exits()));
         world.insert_resource(CollisionMap::new(WIDTH, HEIGHT));
         let world_pos = tile_to_world(8, 1, WIDTH, HEIGHT);
@@ -738,6 +872,7 @@ mod tests {
                 trigger: ExitTrigger::Touch,
                 dialogue: vec![],
                 cancel_on_escape: false,
+                target_facing: None,
             }];
             let mut world = setup_world(trigger_tile, exits, width, height);
             world.run_system_once(check_map_exits).unwrap();
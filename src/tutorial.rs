@@ -0,0 +1,251 @@
+use bevy::prelude::*;
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::KeyValue;
+use std::collections::BTreeSet;
+use crate::assets::GameAssets;
+use crate::dialogue::AdvanceInput;
+use crate::game_state::{GameState, Mode};
+use crate::glyphs::{InputGlyphs, InputPrompt};
+use crate::input::{ActiveDevice, GameAction, GameInput, InputMap};
+use crate::instrumentation::{MetricsBundle, init_metrics};
+use crate::npc::{InRange, Npc};
+use crate::settings::GameSettings;
+
+/// First-run hints for the controls, one at a time and in order: "Move with
+/// WASD" until the player walks, "Press E to talk" the first time an NPC is
+/// in reach, "Space to continue" on the first dialogue box - with the
+/// gamepad's buttons instead while one is in use (glyphs.rs). Each stays up
+/// until its action is done - unlike a toast, which times out whether or
+/// not it was read - and never comes back: completed steps are saved
+/// (save.rs), so a continued game shows only what's left.
+///
+/// `GameSettings::tutorial` (`--no-tutorial`) turns the layer off. Every
+/// completed step bumps `game.tutorial.step_completed`, labelled by step,
+/// so a drop-off between steps shows where new players get stuck.
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        crate::input::init_game_input(app);
+        app.init_resource::<TutorialProgress>()
+            .init_resource::<ActiveDevice>()
+            .init_resource::<GameSettings>()
+            .add_systems(OnEnter(GameState::Playing), restart_tutorial)
+            .add_systems(
+                Update,
+                (complete_tutorial_step, show_next_tutorial_step)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+        init_metrics::<TutorialMetrics>(app);
+    }
+}
+
+/// `game.tutorial.step_completed`, by step.
+#[derive(Resource)]
+pub struct TutorialMetrics {
+    pub steps: Counter<u64>,
+}
+
+impl MetricsBundle for TutorialMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            steps: meter
+                .u64_counter("game.tutorial.step_completed")
+                .with_description("First-run tutorial steps completed (see tutorial.rs)")
+                .build(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TutorialStep {
+    Move,
+    Talk,
+    Advance,
+}
+
+impl TutorialStep {
+    pub const ALL: [TutorialStep; 3] = [TutorialStep::Move, TutorialStep::Talk, TutorialStep::Advance];
+
+    /// Stable name for saves and the metric label.
+    pub fn id(self) -> &'static str {
+        match self {
+            TutorialStep::Move => "move",
+            TutorialStep::Talk => "talk",
+            TutorialStep::Advance => "advance",
+        }
+    }
+
+    /// An `InputPrompt` template.
+    fn hint(self) -> &'static str {
+        match self {
+            TutorialStep::Move => "Move with {move}",
+            TutorialStep::Talk => "Press {interact} to talk",
+            TutorialStep::Advance => "{advance} to continue",
+        }
+    }
+}
+
+/// Which steps are done. Fresh games start empty.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TutorialProgress {
+    completed: BTreeSet<String>,
+}
+
+impl TutorialProgress {
+    /// Every step done - for saves from before the tutorial existed, whose
+    /// players already know the controls.
+    pub fn finished() -> Self {
+        TutorialStep::ALL.iter().map(|step| step.id().to_string()).collect()
+    }
+
+    pub fn is_done(&self, step: TutorialStep) -> bool {
+        self.completed.contains(step.id())
+    }
+
+    pub fn complete(&mut self, step: TutorialStep) {
+        self.completed.insert(step.id().to_string());
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.completed.iter().map(String::as_str)
+    }
+
+    /// The first step not yet done.
+    pub fn next(&self) -> Option<TutorialStep> {
+        TutorialStep::ALL.into_iter().find(|&step| !self.is_done(step))
+    }
+}
+
+impl FromIterator<String> for TutorialProgress {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self { completed: iter.into_iter().collect() }
+    }
+}
+
+/// The hint on screen, for which step.
+#[derive(Component)]
+pub struct TutorialHint(pub TutorialStep);
+
+/// A new play session is a new player's: the hints start over.
+fn restart_tutorial(mut progress: ResMut<TutorialProgress>) {
+    progress.set_if_neq(TutorialProgress::default());
+}
+
+fn complete_tutorial_step(
+    mut commands: Commands,
+    hints: Query<(Entity, &TutorialHint)>,
+    mut progress: ResMut<TutorialProgress>,
+    settings: Res<GameSettings>,
+    keyboard: GameInput,
+    advance: AdvanceInput,
+    mode: Option<Res<State<Mode>>>,
+    metrics: Option<Res<TutorialMetrics>>,
+) {
+    let Ok((entity, &TutorialHint(step))) = hints.single() else { return };
+    // Turned off, or done elsewhere (a continued save restored progress):
+    // take the hint down without counting anything.
+    if !settings.tutorial || progress.is_done(step) {
+        commands.entity(entity).despawn();
+        return;
+    }
+
+    let mode = mode.map(|mode| *mode.get());
+    let done = match step {
+        TutorialStep::Move => {
+            mode == Some(Mode::Exploring) && GameAction::MOVEMENT.into_iter().any(|action| keyboard.action_pressed(action))
+        }
+        TutorialStep::Talk => mode == Some(Mode::Dialogue),
+        // Closing the box also proves the player found a way forward.
+        TutorialStep::Advance => {
+            mode != Some(Mode::Dialogue) || advance.requested()
+        }
+    };
+    if !done {
+        return;
+    }
+
+    info!("🎓 Tutorial step done: {}", step.id());
+    progress.complete(step);
+    commands.entity(entity).despawn();
+    if let Some(metrics) = metrics {
+        metrics.steps.add(1, &[KeyValue::new("tutorial.step", step.id())]);
+    }
+}
+
+fn show_next_tutorial_step(
+    mut commands: Commands,
+    hints: Query<(), With<TutorialHint>>,
+    progress: Res<TutorialProgress>,
+    settings: Res<GameSettings>,
+    mode: Option<Res<State<Mode>>>,
+    npcs_in_range: Query<(), (With<Npc>, With<InRange>)>,
+    game_assets: Option<Res<GameAssets>>,
+    device: Res<ActiveDevice>,
+    map: Res<InputMap>,
+) {
+    if !settings.tutorial || !hints.is_empty() {
+        return;
+    }
+    let Some(step) = progress.next() else { return };
+    let mode = mode.map(|mode| *mode.get());
+    let ready = match step {
+        TutorialStep::Move => mode == Some(Mode::Exploring),
+        TutorialStep::Talk => mode == Some(Mode::Exploring) && !npcs_in_range.is_empty(),
+        TutorialStep::Advance => mode == Some(Mode::Dialogue),
+    };
+    if !ready {
+        return;
+    }
+
+    let font = game_assets
+        .map(|assets| assets.dialogue_font.clone())
+        .unwrap_or_default();
+    // Same chip as a toast (toast.rs), a little lower so a toast arriving
+    // meanwhile doesn't cover it.
+    commands
+        .spawn((
+            TutorialHint(step),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(16.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.85)),
+                ))
+                .with_child((
+                    InputPrompt::new(step.hint(), font, 32.0, Color::srgb(1.0, 0.85, 0.3)),
+                    Node { align_items: AlignItems::Center, ..default() },
+                ));
+        });
+    info!("🎓 Tutorial hint: {}", InputGlyphs::plain(step.hint(), *device, &map));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_run_in_order_and_finished_means_all_done() {
+        let mut progress = TutorialProgress::default();
+        assert_eq!(progress.next(), Some(TutorialStep::Move));
+        progress.complete(TutorialStep::Move);
+        assert_eq!(progress.next(), Some(TutorialStep::Talk));
+        progress.complete(TutorialStep::Talk);
+        progress.complete(TutorialStep::Advance);
+        assert_eq!(progress.next(), None);
+        assert_eq!(progress, TutorialProgress::finished());
+    }
+}
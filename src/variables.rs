@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use crate::dashboard::LiveMetrics;
+use crate::dialogue::{DialogueRequest, DialogueRequestSet};
+use crate::game_state::Mode;
+use crate::instrumentation::PlayerSessionTrace;
+use crate::map_data::world_to_tile;
+use crate::npc::TimesTalked;
+use crate::player::{Player, logical_position};
+use crate::tilemap::CollisionMap;
+
+/// Live game numbers dialogue can quote: a line reading "You've talked to
+/// {npcs_met} engineers so far" shows the count as of when the conversation
+/// opens (dialogue.rs fills them in before the first box types, so what's
+/// typed, counted and logged is the final text).
+///
+/// | variable          | value                                          |
+/// |-------------------|------------------------------------------------|
+/// | `npcs_met`        | NPCs talked to this session (`TimesTalked`)     |
+/// | `lines_read`      | dialogue lines read this run (`LiveMetrics`)    |
+/// | `session_minutes` | whole minutes since the session started         |
+/// | `player_tile`     | the player's tile, `(x, y)`                     |
+///
+/// A name nobody sets stays in the line as written, and markup's reader
+/// warns about it like any other tag it doesn't know. Other plugins can
+/// publish their own with `GameVariables::set`.
+pub struct GameVariablesPlugin;
+
+impl Plugin for GameVariablesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameVariables>().add_systems(
+            Update,
+            refresh_game_variables
+                .in_set(GameVariablesSet)
+                .after(DialogueRequestSet)
+                .run_if(in_state(Mode::Exploring).and(on_message::<DialogueRequest>)),
+        );
+    }
+}
+
+/// Refreshes `GameVariables` once a conversation has been asked for and
+/// before it opens (dialogue.rs's `handle_dialogue_events` runs after it).
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameVariablesSet;
+
+/// Values by variable name, as they read in a line.
+#[derive(Resource, Debug, Default)]
+pub struct GameVariables(BTreeMap<String, String>);
+
+impl GameVariables {
+    pub fn set(&mut self, name: &str, value: impl ToString) {
+        self.0.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// `text` with each `{name}` of a set variable replaced by its value.
+    /// Anything else in braces - markup, a name nobody sets - is left as
+    /// written.
+    pub fn substitute(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(open) = rest.find('{') {
+            let Some(close) = rest[open..].find('}').map(|close| open + close) else { break };
+            out.push_str(&rest[..open]);
+            match self.get(&rest[open + 1..close]) {
+                Some(value) => out.push_str(value),
+                None => out.push_str(&rest[open..=close]),
+            }
+            rest = &rest[close + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn refresh_game_variables(
+    mut variables: ResMut<GameVariables>,
+    time: Res<Time>,
+    times_talked: Option<Res<TimesTalked>>,
+    live: Option<Res<LiveMetrics>>,
+    player: Query<(&Transform, Option<&PlayerSessionTrace>), With<Player>>,
+    collision_map: Option<Res<CollisionMap>>,
+) {
+    if let Some(times_talked) = times_talked {
+        variables.set("npcs_met", times_talked.npcs());
+    }
+    if let Some(live) = live {
+        variables.set("lines_read", live.dialogue_lines_read);
+    }
+    let player = player.single().ok();
+    let session = player
+        .and_then(|(_, trace)| trace)
+        .map_or_else(|| time.elapsed(), |trace| trace.session_start.elapsed());
+    variables.set("session_minutes", session.as_secs() / 60);
+    if let (Some((transform, _)), Some(map)) = (player, collision_map) {
+        let (x, y) = world_to_tile(logical_position(transform.translation.truncate()), map.width, map.height);
+        variables.set("player_tile", format!("({x}, {y})"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_variables_are_filled_in_and_everything_else_is_left_alone() {
+        let mut variables = GameVariables::default();
+        variables.set("npcs_met", 3);
+        variables.set("player_tile", "(4, 2)");
+        assert_eq!(
+            variables.substitute("Met {npcs_met}, at {player_tile}. {red}{nobody}{/red} {unclosed"),
+            "Met 3, at (4, 2). {red}{nobody}{/red} {unclosed"
+        );
+        assert_eq!(variables.substitute("No braces."), "No braces.");
+    }
+}
@@ -0,0 +1,83 @@
+//! Plugins have to stand on their own: an embedder composes them in
+//! whatever order it likes, and may leave some out. These boot the fixture
+//! town (see test_game.rs) with the game's plugins reversed, or only a
+//! few of them, through `TestGame::with_plugins`.
+
+use bevy::prelude::*;
+use sregame::dialogue::{DialogueMetrics, DialoguePlugin, DialogueRequest};
+use sregame::game_state::{GameState, GameStatePlugin, Mode, Scene};
+use sregame::input::GameAction;
+use sregame::kiosk::KioskMetrics;
+use sregame::npc::{InteractionMetrics, NpcPlugin};
+use sregame::player::PlayerPlugin;
+use sregame::testing::TestGame;
+use sregame::tilemap::TilemapPlugin;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// `add_game`'s plugins, last to first.
+fn add_game_reversed(app: &mut App) {
+    for add in sregame::GAME_PLUGINS.iter().rev() {
+        add(app);
+    }
+}
+
+#[test]
+fn the_game_plays_with_its_plugins_added_in_reverse() {
+    let mut game = TestGame::with_plugins(FIXTURES, add_game_reversed);
+    let state = game.current_state();
+    assert_eq!(state.game, GameState::Playing);
+    assert_eq!(state.scene, Some(Scene::TownOfEndgame));
+    assert_eq!(game.npc_names(), vec!["Isabella".to_string()]);
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert!(game.active_dialogue().is_some());
+}
+
+#[test]
+fn dialogue_works_without_npcs() {
+    let mut game = TestGame::with_plugins(FIXTURES, |app| {
+        app.add_plugins((DialoguePlugin, GameStatePlugin));
+    });
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    game.app_mut()
+        .world_mut()
+        .write_message(DialogueRequest::from(("Narrator", vec!["Hello.".to_string()])));
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Hello.".to_string()));
+}
+
+#[test]
+fn only_the_plugins_added_create_their_metrics() {
+    let mut game = TestGame::with_plugins(FIXTURES, |app| {
+        app.add_plugins((DialoguePlugin, GameStatePlugin));
+    });
+    let world = game.app_mut().world();
+    assert!(world.contains_resource::<DialogueMetrics>());
+    assert!(!world.contains_resource::<InteractionMetrics>());
+    assert!(!world.contains_resource::<KioskMetrics>());
+}
+
+#[test]
+fn npcs_can_be_talked_to_with_no_one_to_answer() {
+    let mut game = TestGame::with_plugins(FIXTURES, |app| {
+        app.add_plugins((NpcPlugin, PlayerPlugin, TilemapPlugin, GameStatePlugin));
+    });
+    assert_eq!(game.npc_names(), vec!["Isabella".to_string()]);
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+}
+
+#[test]
+#[should_panic(expected = "DialoguePlugin needs sregame::game_state::GameStatePlugin")]
+fn a_missing_hard_requirement_is_named() {
+    TestGame::with_plugins(FIXTURES, |app| {
+        app.add_plugins(DialoguePlugin);
+    });
+}
@@ -0,0 +1,2933 @@
+//! Behavior tests through the public harness (see src/testing.rs). The
+//! fixture town is 7x5: a walled border, the player spawning on the center
+//! tile (3, 2), a wall block at (4, 2) right beside them, and Isabella one
+//! tile north at (3, 1) - inside talk range.
+
+use sregame::content_errors::ContentErrors;
+use sregame::flags::GameFlags;
+use sregame::game_state::{Mode, Scene};
+use sregame::input::{GameAction, InputLatch};
+use sregame::npc_spawning::DespawnNpcEvent;
+use sregame::testing::{LeakCheck, TestGame};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// The fixture town as tests/fixtures/maps has it.
+fn base_game() -> TestGame {
+    TestGame::new(FIXTURES)
+}
+
+/// The fixture town with tests/fixtures/`name` over it as a content pack:
+/// its `data/maps/town_of_endgame.patch.json` adds or adjusts only what the
+/// test needs (see content_pack.rs), and any dialogue files beside it are
+/// served over the base's. A fixture whose cast can't be patched by name -
+/// `invalid_map`'s two Isabellas, `renamed_speaker`'s Izzy and
+/// `masked_speaker`'s Mysterious Monster - replaces the map whole.
+fn fixture_game(name: &str) -> TestGame {
+    TestGame::with_content_packs(FIXTURES, [fixture_pack(name)])
+}
+
+fn fixture_pack(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(FIXTURES).join(name)
+}
+
+fn metric_names(game: &mut TestGame) -> Vec<String> {
+    game.drain_metrics()
+        .iter()
+        .flat_map(|resource| resource.scope_metrics())
+        .flat_map(|scope| scope.metrics())
+        .map(|metric| metric.name().to_string())
+        .collect()
+}
+
+#[test]
+fn walking_into_a_wall_stops_at_its_edge() {
+    let mut game = base_game();
+    let start = game.player_pos().expect("player spawned");
+
+    game.press(GameAction::MoveRight);
+    game.step(60);
+    game.release(GameAction::MoveRight);
+
+    let end = game.player_pos().unwrap();
+    // Collider half-width is 14px and the wall tile starts 24px right of
+    // the spawn center: the player gets ~10px before the box touches it.
+    assert!(end.x > start.x, "player should move toward the wall");
+    assert!(end.x <= start.x + 10.5, "walked into the wall: x = {}", end.x);
+    assert_eq!(end.y, start.y, "a horizontal bump must not drift vertically");
+}
+
+#[test]
+fn talking_to_an_npc_opens_their_dialogue_and_records_the_interaction() {
+    let mut game = base_game();
+
+    game.press(GameAction::Interact);
+    game.step(3);
+
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    let segment = game.active_dialogue().expect("dialogue box up");
+    assert_eq!(segment.speaker, "Isabella");
+    assert_eq!(segment.text, "Welcome to the fixture.");
+
+    let spans = game.drain_spans();
+    assert!(
+        spans.iter().any(|span| span.name == "npc.interaction"),
+        "expected an npc.interaction span, got {:?}",
+        spans.iter().map(|span| &span.name).collect::<Vec<_>>()
+    );
+
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.interactions.total"), "metrics: {names:?}");
+}
+
+#[test]
+fn escape_closes_dialogue_and_ends_its_span_as_forced() {
+    let mut game = base_game();
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    game.drain_spans();
+
+    game.press(GameAction::Cancel);
+    game.step(2);
+
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert!(game.active_dialogue().is_none());
+
+    let spans = game.drain_spans();
+    let session = spans
+        .iter()
+        .find(|span| span.name == "dialogue.session")
+        .expect("dialogue.session span ends on escape");
+    assert!(
+        session.events.iter().any(|event| event.name == "dialogue.forced_exit"),
+        "escape should mark the session as force-closed"
+    );
+}
+
+#[test]
+fn broken_npc_dialogue_is_recorded_and_explained_in_debug_builds() {
+    let mut game = fixture_game("broken_content");
+
+    let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(errors[0].path.ends_with("town_of_endgame.patch.json"), "path: {}", errors[0].path);
+    assert!(errors[0].error.contains("no dialogue lines"), "error: {}", errors[0].error);
+
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.content.errors"), "metrics: {names:?}");
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    if cfg!(debug_assertions) {
+        let segment = game.active_dialogue().expect("fallback line instead of silence");
+        assert!(segment.text.starts_with("… (broken content:"), "text: {}", segment.text);
+        assert!(segment.text.contains("town_of_endgame.patch.json"), "text: {}", segment.text);
+    } else {
+        assert!(game.active_dialogue().is_none());
+    }
+}
+
+#[test]
+fn holding_interact_through_dialogue_start_does_not_advance_it() {
+    let mut game = base_game();
+
+    // Press E and keep it held well past the box opening.
+    game.press(GameAction::Interact);
+    game.step(30);
+
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert_eq!(game.active_dialogue().unwrap().text, "Welcome to the fixture.");
+    let latch = game.app_mut().world().resource::<InputLatch>();
+    assert!(latch.is_latched(GameAction::Interact.default_key()), "E held into dialogue is latched");
+
+    // Once released, the dialogue keys behave normally again.
+    game.release(GameAction::Interact);
+    game.step(1);
+    game.press(GameAction::Advance);
+    game.step(1);
+    game.release(GameAction::Advance);
+    game.press(GameAction::Advance);
+    game.step(1);
+    assert_eq!(game.active_dialogue().unwrap().text, "Mind the wall.");
+}
+
+#[test]
+fn dialogue_open_close_cycles_do_not_leak_entities() {
+    let mut game = base_game();
+    let leaks = LeakCheck::start(&game);
+
+    for _ in 0..5 {
+        game.interact();
+        assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+        game.press(GameAction::Cancel);
+        game.step(2);
+        game.release(GameAction::Cancel);
+        assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    }
+
+    leaks.assert_no_growth(&game);
+}
+
+#[test]
+fn map_transitions_do_not_leak_entities() {
+    let mut game = base_game();
+    let leaks = LeakCheck::start(&game);
+
+    for _ in 0..3 {
+        game.enter_scene(Scene::TeamMarathon);
+        assert_eq!(game.current_state().scene, Some(Scene::TeamMarathon));
+        game.enter_scene(Scene::TownOfEndgame);
+    }
+
+    leaks.assert_no_growth(&game);
+}
+
+#[test]
+fn characters_cast_shadows_outdoors_only() {
+    use bevy::prelude::{Visibility, With};
+    use sregame::shadow::Shadow;
+
+    fn shadow_visibilities(game: &mut TestGame) -> Vec<Visibility> {
+        let world = game.app_mut().world_mut();
+        let mut shadows = world.query_filtered::<&Visibility, With<Shadow>>();
+        shadows.iter(world).copied().collect()
+    }
+
+    let mut game = base_game();
+    // Amy and Isabella.
+    assert_eq!(shadow_visibilities(&mut game), vec![Visibility::Inherited; 2]);
+
+    // The marathon room fixture is flagged indoor; its NPC gets a shadow
+    // too, and every shadow - Amy's included - hides.
+    game.enter_scene(Scene::TeamMarathon);
+    game.step(1);
+    assert_eq!(shadow_visibilities(&mut game), vec![Visibility::Hidden; 2]);
+
+    game.enter_scene(Scene::TownOfEndgame);
+    game.step(1);
+    assert_eq!(shadow_visibilities(&mut game), vec![Visibility::Inherited; 2]);
+}
+
+#[test]
+fn a_dialogue_read_once_can_be_skipped_by_holding_tab() {
+    let mut game = base_game();
+    let talk = |game: &mut TestGame| {
+        game.interact();
+        game.step(1);
+        assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    };
+
+    // First time through: no skipping, read it all.
+    talk(&mut game);
+    game.press(GameAction::Skip);
+    game.step(60);
+    game.release(GameAction::Skip);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue), "unread dialogue can't be skipped");
+    game.read_dialogue_to_end(20);
+
+    // Second time: a tap isn't enough, a hold ends it.
+    talk(&mut game);
+    game.drain_spans();
+    game.press(GameAction::Skip);
+    game.step(5);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    game.step(40);
+    game.release(GameAction::Skip);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    let spans = game.drain_spans();
+    let session = spans
+        .iter()
+        .find(|span| span.name == "dialogue.session")
+        .expect("dialogue.session ends on skip");
+    assert!(
+        session.attributes.iter().any(|kv| kv.key.as_str() == "dialogue.skipped_seen"),
+        "attributes: {:?}",
+        session.attributes
+    );
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.dialogue.skipped_seen"), "metrics: {names:?}");
+}
+
+#[test]
+fn dialogue_outcomes_spawn_npcs_and_despawn_cleans_them_up() {
+    use bevy::prelude::With;
+    use sregame::shadow::Shadow;
+
+    fn shadow_count(game: &mut TestGame) -> usize {
+        let world = game.app_mut().world_mut();
+        world.query_filtered::<(), With<Shadow>>().iter(world).count()
+    }
+
+    let mut game = fixture_game("dynamic_npcs");
+    assert_eq!(game.npc_names(), ["Isabella"], "the Greeter waits for its flag");
+
+    game.interact();
+    game.read_dialogue_to_end(20);
+    game.step(2);
+    assert_eq!(game.npc_names(), ["Greeter", "Isabella", "Vendor"]);
+    assert!(game.app_mut().world().resource::<GameFlags>().is_set("met_isabella"));
+
+    let shadows_before = shadow_count(&mut game);
+    game.app_mut()
+        .world_mut()
+        .write_message(DespawnNpcEvent { id: "vendor".into() });
+    game.step(2);
+    assert_eq!(game.npc_names(), ["Greeter", "Isabella"]);
+    assert_eq!(shadow_count(&mut game), shadows_before - 1, "the Vendor's shadow went with it");
+
+    let spans = game.drain_spans();
+    assert!(spans.iter().any(|span| span.name == "npc.despawned"));
+
+    // Runtime NPCs are scene-scoped: leaving the map takes them along
+    // (entity_audit panics on any leftover `Map` entity).
+    game.enter_scene(Scene::TeamMarathon);
+}
+
+#[test]
+fn a_slow_frame_is_reported_as_a_stall() {
+    use bevy::prelude::{Local, Update};
+    use bevy::time::TimeUpdateStrategy;
+    use sregame::frame_watchdog::FrameWatchdog;
+
+    let mut game = base_game();
+    // The harness's fixed frame length would hide the sleep from
+    // Time<Real>; let the clock run for real.
+    game.app_mut().insert_resource(TimeUpdateStrategy::Automatic);
+    game.step(3);
+    game.app_mut().insert_resource(FrameWatchdog::default());
+    game.drain_spans();
+
+    game.app_mut().add_systems(Update, |mut slept: Local<bool>| {
+        if !*slept {
+            *slept = true;
+            std::thread::sleep(std::time::Duration::from_millis(200));
+        }
+    });
+    game.step(2);
+
+    let spans = game.drain_spans();
+    let stall = spans
+        .iter()
+        .find(|span| span.name == "game.frame.stall")
+        .expect("stall span recorded");
+    let duration = stall.end_time.duration_since(stall.start_time).unwrap();
+    assert!(duration.as_millis() >= 200, "span covers the slow frame: {duration:?}");
+    for key in ["game.scene", "game.mode", "entity.count", "frame.duration_ms"] {
+        assert!(
+            stall.attributes.iter().any(|kv| kv.key.as_str() == key),
+            "{key} missing: {:?}",
+            stall.attributes
+        );
+    }
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.frame.stalls"), "metrics: {names:?}");
+}
+
+#[test]
+fn text_that_outgrows_the_dialogue_box_is_reported() {
+    use bevy::math::Vec2;
+    use bevy::prelude::{Node, Val, With};
+    use bevy::ui::ComputedNode;
+    use sregame::dialogue::{DialogueRoot, DialogueTextColumn};
+
+    /// The harness has no UI layout pass; stand in for one that laid the
+    /// text column out at `content_size`.
+    fn lay_out(game: &mut TestGame, content_size: Vec2) {
+        let world = game.app_mut().world_mut();
+        let mut columns = world.query_filtered::<&mut ComputedNode, With<DialogueTextColumn>>();
+        *columns.single_mut(world).unwrap() = ComputedNode {
+            size: Vec2::new(40.0, 10.0),
+            content_size,
+            ..ComputedNode::DEFAULT
+        };
+        game.step(2);
+    }
+    fn border(game: &mut TestGame) -> Val {
+        let world = game.app_mut().world_mut();
+        world.query_filtered::<&Node, With<DialogueRoot>>().single(world).unwrap().border.top
+    }
+
+    let mut game = base_game();
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+
+    lay_out(&mut game, Vec2::new(40.0, 120.0));
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.ui.text_overflow"), "metrics: {names:?}");
+    assert_eq!(border(&mut game), Val::Px(4.0), "outlined while it overflows");
+
+    lay_out(&mut game, Vec2::new(40.0, 10.0));
+    assert_eq!(border(&mut game), Val::ZERO, "and not once it fits");
+}
+
+#[test]
+fn tutorial_hints_walk_a_new_player_through_the_controls() {
+    use sregame::tutorial::{TutorialHint, TutorialProgress, TutorialStep};
+
+    fn hint(game: &mut TestGame) -> Option<TutorialStep> {
+        let world = game.app_mut().world_mut();
+        let mut hints = world.query::<&TutorialHint>();
+        hints.iter(world).next().map(|hint| hint.0)
+    }
+
+    let mut game = base_game();
+    game.step(1);
+    assert_eq!(hint(&mut game), Some(TutorialStep::Move));
+
+    // Isabella is already in reach, but the talk hint waits its turn.
+    game.press(GameAction::MoveLeft);
+    game.step(2);
+    game.release(GameAction::MoveLeft);
+    game.step(1);
+    assert_eq!(hint(&mut game), Some(TutorialStep::Talk));
+
+    game.interact();
+    game.step(1);
+    assert_eq!(hint(&mut game), Some(TutorialStep::Advance));
+
+    game.tap(GameAction::Advance);
+    assert_eq!(hint(&mut game), None);
+    assert_eq!(*game.app_mut().world().resource::<TutorialProgress>(), TutorialProgress::finished());
+
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.tutorial.step_completed"), "metrics: {names:?}");
+}
+
+#[test]
+fn a_gamepad_plays_the_tutorial_and_its_prompts_name_its_buttons() {
+    use bevy::prelude::*;
+    use sregame::glyphs::InputPrompt;
+    use sregame::input::{ActiveDevice, GamepadStyle};
+    use sregame::tutorial::TutorialHint;
+
+    /// The hint as read off the screen: text runs and chip labels in order.
+    fn hint_text(game: &mut TestGame) -> String {
+        fn collect(world: &World, entity: Entity, out: &mut String) {
+            if let Some(text) = world.get::<Text>(entity) {
+                out.push_str(&text.0);
+            }
+            for &child in world.get::<Children>(entity).into_iter().flatten() {
+                collect(world, child, out);
+            }
+        }
+        let world = game.app_mut().world_mut();
+        let mut hints = world.query_filtered::<Entity, With<TutorialHint>>();
+        let mut out = String::new();
+        if let Some(hint) = hints.iter(world).next() {
+            collect(world, hint, &mut out);
+        }
+        out
+    }
+
+    /// Press (or release) a gamepad button for one frame's worth of input.
+    fn button(game: &mut TestGame, pad: Entity, button: GamepadButton, down: bool) {
+        let mut entity = game.app_mut().world_mut().entity_mut(pad);
+        let mut gamepad = entity.get_mut::<Gamepad>().unwrap();
+        if down {
+            gamepad.digital_mut().press(button);
+        } else {
+            gamepad.digital_mut().release(button);
+        }
+        game.step(1);
+        let mut entity = game.app_mut().world_mut().entity_mut(pad);
+        entity.get_mut::<Gamepad>().unwrap().digital_mut().clear();
+    }
+
+    let mut game = base_game();
+    game.step(2);
+    assert_eq!(hint_text(&mut game), "Move with WASD");
+
+    let pad = game.app_mut().world_mut().spawn(Gamepad::default()).id();
+    button(&mut game, pad, GamepadButton::DPadLeft, true);
+    game.step(1);
+    button(&mut game, pad, GamepadButton::DPadLeft, false);
+    game.step(1);
+    assert_eq!(*game.app_mut().world().resource::<ActiveDevice>(), ActiveDevice::Gamepad(GamepadStyle::Xbox));
+    assert_eq!(hint_text(&mut game), "Press A to talk");
+
+    button(&mut game, pad, GamepadButton::South, true);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert!(game.app_mut().world_mut().query::<&InputPrompt>().iter(game.app_mut().world()).count() >= 2);
+    assert_eq!(hint_text(&mut game), "A to continue");
+
+    // Touching the keyboard swaps every prompt back, mid-conversation.
+    button(&mut game, pad, GamepadButton::South, false);
+    game.tap(GameAction::MoveUp);
+    assert_eq!(*game.app_mut().world().resource::<ActiveDevice>(), ActiveDevice::Keyboard);
+    assert_eq!(hint_text(&mut game), "Space to continue");
+}
+
+#[test]
+fn interact_rebound_on_the_controls_screen_talks_on_the_new_key() {
+    use bevy::prelude::KeyCode;
+    use sregame::controls_menu::{ControlsMenu, ControlsStep};
+    use sregame::input::InputMap;
+
+    let mut game = base_game();
+    game.tap_key(KeyCode::F1);
+    assert_eq!(game.current_state().mode, Some(Mode::Menu));
+
+    // Down to Interact, pick it, and press Q.
+    for _ in 0..4 {
+        game.tap(GameAction::MoveDown);
+    }
+    game.tap(GameAction::Advance);
+    let step = game.app_mut().world().resource::<ControlsMenu>().step();
+    assert_eq!(step, ControlsStep::Listen(GameAction::Interact));
+    game.tap_key(KeyCode::KeyQ);
+    assert_eq!(game.app_mut().world().resource::<InputMap>().key(GameAction::Interact), KeyCode::KeyQ);
+
+    game.tap(GameAction::Cancel);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    // E is nothing now; Q talks.
+    game.tap_key(KeyCode::KeyE);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    game.tap_key(KeyCode::KeyQ);
+    game.step(1);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+}
+
+#[test]
+fn nearby_npcs_chatter_in_bubbles_that_give_way_to_dialogue() {
+    use bevy::prelude::{Text2d, With};
+    use sregame::ambient::{CHATTER_COOLDOWN, ChatterBubble};
+
+    fn bubbles(game: &mut TestGame) -> Vec<String> {
+        let world = game.app_mut().world_mut();
+        let mut bubbles = world.query_filtered::<&Text2d, With<ChatterBubble>>();
+        bubbles.iter(world).map(|text| text.0.clone()).collect()
+    }
+
+    let mut game = fixture_game("ambient_chatter");
+    let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(errors[0].error.contains("far too long"), "error: {}", errors[0].error);
+
+    // The longest possible first cooldown is 1.5x the mean.
+    let frames = (CHATTER_COOLDOWN.as_secs_f32() * 1.5 * 60.0) as u32 + 2;
+    for _ in 0..frames {
+        if !bubbles(&mut game).is_empty() {
+            break;
+        }
+        game.step(1);
+    }
+    assert_eq!(bubbles(&mut game), ["Lovely day for a deploy."], "the overlong line is never shown");
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert!(bubbles(&mut game).is_empty(), "dialogue clears the chatter");
+
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.npc.ambient_lines"), "metrics: {names:?}");
+}
+
+#[test]
+fn npcs_start_when_the_player_runs_past_but_not_when_they_walk() {
+    use bevy::prelude::{Text2d, With};
+    use sregame::npc::{CharacterFrames, Emote, Npc, NpcFacing};
+
+    /// Isabella's facing row, and whether she has a "!" up.
+    fn isabella(game: &mut TestGame) -> (u32, bool) {
+        let world = game.app_mut().world_mut();
+        let facing = world.query_filtered::<&CharacterFrames, With<Npc>>().single(world).unwrap().facing_row;
+        let startled = world.query_filtered::<&Text2d, With<Emote>>().iter(world).any(|text| text.0 == "!");
+        (facing, startled)
+    }
+
+    // Straight up at her from the spawn point, a tile below.
+    let mut game = base_game();
+    game.press(GameAction::MoveUp);
+    game.step(20);
+    assert_eq!(isabella(&mut game), (NpcFacing::Down as u32, false), "walking by isn't worth a look");
+    let names = metric_names(&mut game);
+    assert!(!names.iter().any(|name| name == "game.npc.reactions"), "metrics: {names:?}");
+
+    let mut game = base_game();
+    game.press(GameAction::Sprint);
+    game.press(GameAction::MoveUp);
+    game.step(5);
+    assert_eq!(isabella(&mut game), (NpcFacing::Up as u32, true), "she turns the way the player ran");
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.npc.reactions"), "metrics: {names:?}");
+}
+
+#[test]
+fn escaping_a_conversation_on_its_first_frame_turns_the_npc_back_as_it_was() {
+    use bevy::prelude::{Sprite, Transform, With, Without};
+    use sregame::npc::{CharacterFrames, Npc, NpcFacing};
+    use sregame::player::{Facing, Player};
+
+    /// Isabella's facing row and atlas frame.
+    fn isabella(game: &mut TestGame) -> (u32, usize) {
+        let world = game.app_mut().world_mut();
+        let (frames, sprite) = world.query_filtered::<(&CharacterFrames, &Sprite), With<Npc>>().single(world).unwrap();
+        (frames.facing_row, sprite.texture_atlas.as_ref().unwrap().index)
+    }
+
+    // Beside her, not below where she's already looking.
+    let mut game = base_game();
+    let world = game.app_mut().world_mut();
+    let at = world.query_filtered::<&Transform, With<Npc>>().single(world).unwrap().translation;
+    let (mut transform, mut facing) =
+        world.query_filtered::<(&mut Transform, &mut Facing), (With<Player>, Without<Npc>)>().single_mut(world).unwrap();
+    (transform.translation.x, transform.translation.y) = (at.x + 48.0, at.y);
+    *facing = Facing::Left;
+    game.step(1);
+    let before = isabella(&mut game);
+    assert_eq!(before.0, NpcFacing::Down as u32);
+
+    game.press(GameAction::Interact);
+    for _ in 0..5 {
+        game.step(1);
+        if game.current_state().mode == Some(Mode::Dialogue) {
+            break;
+        }
+    }
+    game.release(GameAction::Interact);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert_eq!(isabella(&mut game).0, NpcFacing::Right as u32, "she looks at the player");
+
+    game.press(GameAction::Cancel);
+    game.step(1);
+    assert_eq!(isabella(&mut game), before, "turned back the frame it ended");
+    game.release(GameAction::Cancel);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert_eq!(isabella(&mut game), before);
+}
+
+#[test]
+fn the_same_seed_and_inputs_replay_the_same_wander_path() {
+    use bevy::prelude::{Transform, With};
+    use sregame::npc_movement::NpcMovement;
+    use sregame::rng::GameRng;
+
+    fn wander_path(seed: u64) -> Vec<(i32, i32)> {
+        let mut game = fixture_game("wander");
+        game.app_mut().insert_resource(GameRng::new(seed));
+        let mut path = Vec::new();
+        for frame in 0..900 {
+            // The same recorded input every run: a short walk left.
+            match frame {
+                10 => game.press(GameAction::MoveLeft),
+                30 => game.release(GameAction::MoveLeft),
+                _ => {}
+            }
+            game.step(1);
+            let world = game.app_mut().world_mut();
+            let mut doggo = world.query_filtered::<&Transform, With<NpcMovement>>();
+            let position = doggo.single(world).expect("doggo").translation;
+            path.push((position.x.round() as i32, position.y.round() as i32));
+        }
+        path
+    }
+
+    let path = wander_path(42);
+    assert!(path.iter().any(|&step| step != path[0]), "doggo should have wandered");
+    assert_eq!(wander_path(42), path);
+}
+
+#[test]
+fn patrols_walk_their_path_and_one_off_the_map_is_reported() {
+    use bevy::prelude::Vec2;
+    use sregame::npc::Npc;
+
+    fn position(game: &mut TestGame, id: &str) -> Vec2 {
+        let world = game.app_mut().world_mut();
+        let mut npcs = world.query::<(&Npc, &bevy::prelude::Transform)>();
+        let (_, transform) = npcs.iter(world).find(|(npc, _)| npc.id == id).expect(id);
+        transform.translation.truncate()
+    }
+
+    let mut game = fixture_game("patrol");
+    let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert_eq!(errors[0].error, "NPC \"Lost\" path point #2 (9, 3) is off the 7x5 map");
+
+    let (casey, lost) = (position(&mut game, "casey"), position(&mut game, "lost"));
+    let mut farthest = casey.x;
+    // Casey waits out a pause at each end: there and back inside five
+    // seconds, still waiting at the start.
+    for _ in 0..300 {
+        game.step(1);
+        farthest = farthest.max(position(&mut game, "casey").x);
+        assert_eq!(position(&mut game, "lost"), lost, "a broken patrol isn't walked");
+    }
+    assert_eq!(farthest, casey.x + 48.0, "one tile east and no farther");
+    assert_eq!(position(&mut game, "casey"), casey);
+}
+
+#[test]
+fn interaction_spans_say_which_dialogue_was_selected_and_why() {
+    use opentelemetry::Value;
+
+    fn talk_to_isabella(game: &mut TestGame) -> Vec<(String, Value)> {
+        game.drain_spans();
+        game.interact();
+        game.read_dialogue_to_end(20);
+        game.step(2);
+        let spans = game.drain_spans();
+        let span = spans.iter().find(|span| span.name == "npc.interaction").expect("interaction span");
+        span.attributes
+            .iter()
+            .map(|kv| (kv.key.to_string(), kv.value.clone()))
+            .collect()
+    }
+    fn attribute(attributes: &[(String, Value)], key: &str) -> Option<Value> {
+        attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    let mut game = fixture_game("dynamic_npcs");
+    let first = talk_to_isabella(&mut game);
+    assert_eq!(attribute(&first, "dialogue.variant"), Some("default".into()));
+    assert_eq!(attribute(&first, "npc.times_talked"), Some(Value::I64(0)));
+    assert_eq!(attribute(&first, "flag.met_isabella"), Some(Value::Bool(false)));
+    let source = attribute(&first, "dialogue.source").expect("source").to_string();
+    assert!(source.ends_with("town_of_endgame.patch.json"), "source: {source}");
+    assert!(
+        first.iter().all(|(key, _)| !key.starts_with("flag.") || key == "flag.met_isabella"),
+        "only flags Isabella's content touches: {first:?}"
+    );
+
+    // The first conversation set her flag; the second sees it.
+    let second = talk_to_isabella(&mut game);
+    assert_eq!(attribute(&second, "npc.times_talked"), Some(Value::I64(1)));
+    assert_eq!(attribute(&second, "flag.met_isabella"), Some(Value::Bool(true)));
+}
+
+#[test]
+fn clicking_the_dialogue_box_advances_it_like_space() {
+    use bevy::prelude::{Interaction, With};
+    use sregame::dialogue::DialogueRoot;
+
+    fn click(game: &mut TestGame) {
+        for interaction in [Interaction::Pressed, Interaction::Hovered] {
+            let world = game.app_mut().world_mut();
+            let mut boxes = world.query_filtered::<&mut Interaction, With<DialogueRoot>>();
+            *boxes.single_mut(world).expect("dialogue box") = interaction;
+            game.step(1);
+        }
+    }
+
+    let mut game = base_game();
+    game.interact();
+    assert_eq!(game.active_dialogue().unwrap().text, "Welcome to the fixture.");
+
+    // The first click finishes the typewriter, the second moves on.
+    click(&mut game);
+    assert_eq!(game.active_dialogue().unwrap().text, "Welcome to the fixture.");
+    click(&mut game);
+    assert_eq!(game.active_dialogue().unwrap().text, "Mind the wall.");
+
+    click(&mut game);
+    click(&mut game);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring), "the last click closes it");
+}
+
+#[test]
+fn a_second_conversation_links_back_to_the_first() {
+    fn talk(game: &mut TestGame, close_with: GameAction) {
+        game.interact();
+        game.tap_until_dialogue_closes(close_with, 20);
+        game.step(2);
+    }
+    fn sessions(game: &mut TestGame) -> Vec<opentelemetry_sdk::trace::SpanData> {
+        game.drain_spans().into_iter().filter(|span| span.name == "dialogue.session").collect()
+    }
+
+    let mut game = base_game();
+    talk(&mut game, GameAction::Cancel);
+    let first = sessions(&mut game).pop().expect("first session");
+    assert!(first.links.links.is_empty(), "nothing to link the first talk to");
+
+    talk(&mut game, GameAction::Advance);
+    let second = sessions(&mut game).pop().expect("second session");
+    let link = second.links.links.first().expect("link to the first session");
+    assert_eq!(link.span_context.span_id(), first.span_context.span_id());
+    let outcome = link.attributes.iter().find(|kv| kv.key.as_str() == "previous.outcome");
+    assert_eq!(outcome.map(|kv| kv.value.to_string()), Some("forced".to_string()));
+    assert!(link.attributes.iter().any(|kv| kv.key.as_str() == "link.gap_secs"));
+}
+
+#[test]
+fn a_conversation_publishes_its_gameplay_events_in_order() {
+    use sregame::game_events::{GameEvent, GameEvents};
+
+    let mut game = fixture_game("dynamic_npcs");
+    game.interact();
+    game.read_dialogue_to_end(20);
+    game.step(2);
+
+    let page = game.app_mut().world().resource::<GameEvents>().since(0);
+    assert_eq!(page.missed, 0);
+    let events: Vec<GameEvent> = page.events.into_iter().map(|record| record.event).collect();
+    assert_eq!(
+        events.first(),
+        Some(&GameEvent::MapChanged { from: String::new(), to: "TownOfEndgame".into() })
+    );
+    let position = |wanted: &dyn Fn(&GameEvent) -> bool| {
+        events.iter().position(wanted).unwrap_or_else(|| panic!("missing event in {events:?}"))
+    };
+    let started = position(&|e| matches!(e, GameEvent::InteractionStarted { npc } if npc == "Isabella"));
+    let first_line = position(&|e| matches!(e, GameEvent::LineRead { line: 0, .. }));
+    let flag = position(&|e| matches!(e, GameEvent::FlagSet { flag } if flag == "met_isabella"));
+    assert!(started < first_line && first_line < flag, "{events:?}");
+}
+
+#[test]
+fn a_200_by_200_map_spawns_within_a_generous_budget() {
+    use sregame::testing::{GeneratedTown, scratch_dir};
+
+    let dir = scratch_dir("spawn-budget");
+    GeneratedTown::new(7, 5).write(&dir, "town_of_endgame");
+    GeneratedTown::new(200, 200).write(&dir, "team_marathon");
+    let mut game = TestGame::new(&dir);
+
+    // Orders of magnitude above a normal run (see benches/hot_systems.rs
+    // for the real number), so only a blowup trips it, not a slow CI box.
+    let started = std::time::Instant::now();
+    game.enter_scene(Scene::TeamMarathon);
+    let elapsed = started.elapsed();
+    let _ = std::fs::remove_dir_all(dir);
+
+    assert_eq!(game.current_state().scene, Some(Scene::TeamMarathon));
+    assert!(game.census().total > 200 * 200, "the tiles should have spawned");
+    assert!(elapsed < std::time::Duration::from_secs(10), "spawning 200x200 took {elapsed:?}");
+}
+
+#[test]
+fn a_mentor_offers_topics_until_goodbye() {
+    use sregame::dialogue::{DialogueSession, SeenDialogues};
+
+    /// The open menu's entries (label, read) and cursor.
+    fn menu(game: &mut TestGame) -> Option<(Vec<(String, bool)>, usize)> {
+        let world = game.app_mut().world();
+        let menu = world.get_resource::<DialogueSession>()?.queue.topic_menu()?;
+        menu.is_open().then(|| (menu.entries(world.resource::<SeenDialogues>()), menu.cursor()))
+    }
+    fn read_until_menu(game: &mut TestGame) -> Vec<(String, bool)> {
+        for _ in 0..20 {
+            if let Some((entries, _)) = menu(game) {
+                return entries;
+            }
+            game.tap(GameAction::Advance);
+        }
+        panic!("the topic menu never opened");
+    }
+    let entry = |label: &str, read| (label.to_string(), read);
+
+    let mut game = fixture_game("topics");
+    game.interact();
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Ask me anything.".into()));
+
+    // The gated topic isn't offered; Goodbye always is.
+    let entries = read_until_menu(&mut game);
+    assert_eq!(entries, [entry("SLOs", false), entry("Error budgets", false), entry("Goodbye", false)]);
+
+    game.tap(GameAction::MoveDown);
+    game.tap(GameAction::Advance);
+    assert!(menu(&mut game).is_none(), "the menu gives way to the topic");
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Spend them on shipping.".into()));
+
+    let entries = read_until_menu(&mut game);
+    assert_eq!(entries[1], entry("Error budgets", true), "read topics are marked");
+    assert_eq!(menu(&mut game).map(|(_, cursor)| cursor), Some(1));
+    assert_eq!(game.topic_menu_rows(), ["  SLOs", "> Error budgets ✓", "  Goodbye"]);
+
+    game.tap(GameAction::MoveDown);
+    game.tap(GameAction::Advance);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert!(game.app_mut().world().resource::<GameFlags>().is_set("mentored"), "goodbye completes it");
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session");
+    let visited = session.attributes.iter().find(|kv| kv.key.as_str() == "dialogue.topics_visited");
+    assert_eq!(visited.map(|kv| kv.value.to_string()), Some("[\"error_budgets\"]".to_string()));
+    // The interaction lasts the conversation, each pick an event on it.
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction ends with the talk");
+    let picked: Vec<_> = interaction
+        .events
+        .iter()
+        .filter(|event| event.name == "dialogue.topic_selected")
+        .flat_map(|event| event.attributes.iter().filter(|kv| kv.key.as_str() == "topic.id").map(|kv| kv.value.to_string()))
+        .collect();
+    assert_eq!(picked, ["error_budgets"]);
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.dialogue.topic_selected"), "metrics: {names:?}");
+}
+
+#[test]
+fn topic_menu_rows_answer_the_mouse_like_choices_do() {
+    use bevy::prelude::{Entity, Interaction};
+    use sregame::dialogue::MenuRow;
+
+    fn point(game: &mut TestGame, row: MenuRow, interaction: Interaction) {
+        let world = game.app_mut().world_mut();
+        let mut rows = world.query::<(Entity, &MenuRow)>();
+        let entity = rows.iter(world).find(|(_, candidate)| **candidate == row).map(|(entity, _)| entity);
+        *world.get_mut::<Interaction>(entity.expect("the row is spawned")).unwrap() = interaction;
+        game.step(1);
+    }
+
+    let mut game = fixture_game("topics");
+    game.interact();
+    // Through the greeting to the menu.
+    for _ in 0..20 {
+        if !game.topic_menu_rows().is_empty() {
+            break;
+        }
+        game.tap(GameAction::Advance);
+    }
+    assert_eq!(game.topic_menu_rows(), ["> SLOs", "  Error budgets", "  Goodbye"]);
+
+    point(&mut game, MenuRow::Topic(2), Interaction::Hovered);
+    assert_eq!(game.topic_menu_rows(), ["  SLOs", "  Error budgets", "> Goodbye"]);
+
+    point(&mut game, MenuRow::Topic(1), Interaction::Pressed);
+    point(&mut game, MenuRow::Topic(1), Interaction::Hovered);
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Spend them on shipping.".into()));
+}
+
+#[test]
+fn public_hooks_follow_a_conversation_from_start_to_end() {
+    use bevy::prelude::{MessageReader, ResMut, Resource, Update};
+    use sregame::prelude::*;
+
+    #[derive(Resource, Default)]
+    struct Heard(Vec<String>);
+
+    fn listen(
+        mut talks: MessageReader<NpcInteracted>,
+        mut started: MessageReader<DialogueStarted>,
+        mut lines: MessageReader<DialogueLineShown>,
+        mut ended: MessageReader<DialogueEnded>,
+        mut heard: ResMut<Heard>,
+    ) {
+        heard.0.extend(talks.read().map(|talk| format!("interacted {}", talk.name)));
+        heard.0.extend(started.read().map(|start| format!("started {} npc={}", start.speaker, start.npc_entity.is_some())));
+        heard.0.extend(lines.read().map(|line| format!("line {} skipped={}", line.index, line.skipped)));
+        heard.0.extend(ended.read().map(|end| format!("ended {:?}", end.outcome)));
+    }
+
+    let mut game = base_game();
+    game.app_mut().init_resource::<Heard>().add_systems(Update, listen);
+
+    // Let the first line type out; hurry the second with Space.
+    game.interact();
+    game.step(90);
+    for _ in 0..3 {
+        game.tap(GameAction::Advance);
+    }
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    // Then close one with Escape.
+    game.interact();
+    game.press(GameAction::Cancel);
+    game.step(2);
+    game.release(GameAction::Cancel);
+
+    let heard = std::mem::take(&mut game.app_mut().world_mut().resource_mut::<Heard>().0);
+    assert_eq!(heard, [
+        "interacted Isabella",
+        "started Isabella npc=true",
+        "line 0 skipped=false",
+        "line 1 skipped=true",
+        "ended Completed",
+        "interacted Isabella",
+        "started Isabella npc=true",
+        "ended Forced",
+    ]);
+}
+
+#[test]
+fn input_latency_is_measured_for_talking_and_walking() {
+    use sregame::input_latency::{InputLatency, LatencyAction};
+
+    let mut game = base_game();
+    game.interact();
+    game.press(GameAction::Cancel);
+    game.step(2);
+    game.release(GameAction::Cancel);
+    game.step(1);
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session span");
+    assert!(
+        session.attributes.iter().any(|kv| kv.key.as_str() == "input.latency_ms"),
+        "attributes: {:?}",
+        session.attributes
+    );
+
+    // From standstill, away from the wall.
+    game.press(GameAction::MoveLeft);
+    game.step(3);
+    game.release(GameAction::MoveLeft);
+
+    let latency = game.app_mut().world().resource::<InputLatency>();
+    assert!(latency.p95(LatencyAction::Interact).is_some());
+    assert!(latency.p95(LatencyAction::Move).is_some());
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.input.latency"), "metrics: {names:?}");
+}
+
+#[test]
+fn walking_into_a_camera_zone_swaps_the_bounds_and_zoom() {
+    use bevy::prelude::*;
+    use sregame::camera::{CameraBounds, CameraFollow, MainCamera};
+
+    fn camera(game: &mut TestGame) -> (Option<CameraBounds>, f32, f32) {
+        let world = game.app_mut().world_mut();
+        let (follow, projection) = world
+            .query_filtered::<(&CameraFollow, &Projection), With<MainCamera>>()
+            .single(world)
+            .expect("main camera");
+        let Projection::Orthographic(ortho) = projection else { panic!("2D camera") };
+        (follow.bounds, follow.zoom, ortho.scale)
+    }
+
+    let mut game = fixture_game("camera_zones");
+    game.step(2);
+    let errors = game.app_mut().world().resource::<ContentErrors>();
+    assert_eq!(errors.errors.len(), 1, "{:?}", errors.errors);
+    assert!(errors.errors[0].error.contains("doesn't fit the 7x5 map"));
+
+    // The spawn tile (3, 2) is only in the map-wide zone.
+    let (bounds, zoom, _) = camera(&mut game);
+    assert_eq!(bounds, Some(CameraBounds::from_tiles([1, 0, 6, 5], 7, 5)));
+    assert_eq!(zoom, 1.0);
+
+    // One tile left is inside the room too; the room is smaller, so it wins.
+    game.press(GameAction::MoveLeft);
+    game.step(20);
+    game.release(GameAction::MoveLeft);
+    let (bounds, zoom, scale) = camera(&mut game);
+    assert_eq!(bounds, Some(CameraBounds::from_tiles([1, 1, 2, 3], 7, 5)));
+    assert_eq!(zoom, 2.0);
+    assert!(scale < 1.0 && scale > 0.5, "eases toward the zoom: scale {scale}");
+    game.step(120);
+    assert_eq!(camera(&mut game).2, 0.5);
+
+    // And back out.
+    game.press(GameAction::MoveRight);
+    game.step(20);
+    game.release(GameAction::MoveRight);
+    let (bounds, zoom, _) = camera(&mut game);
+    assert_eq!(bounds, Some(CameraBounds::from_tiles([1, 0, 6, 5], 7, 5)));
+    assert_eq!(zoom, 1.0);
+}
+
+#[test]
+fn an_empty_dialogue_is_reported_instead_of_opening_a_blank_box() {
+    use sregame::dialogue::DialogueRequest;
+
+    for lines in [vec![], vec![String::new()]] {
+        let mut game = base_game();
+        game.step(2);
+        let request: DialogueRequest = ("Isabella", lines).into();
+        let id = request.id.clone();
+        game.app_mut().world_mut().write_message(request);
+        game.step(3);
+
+        let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+        assert!(
+            errors.iter().any(|error| error.path == format!("dialogue {id}") && error.error.contains("every line is empty")),
+            "{errors:?}"
+        );
+        if cfg!(debug_assertions) {
+            // Debug builds explain instead, like a broken NPC.
+            let segment = game.active_dialogue().expect("fallback box");
+            assert!(segment.text.contains("broken content"), "{}", segment.text);
+        } else {
+            assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+        }
+    }
+}
+
+#[test]
+fn a_preloaded_portal_spawns_its_map_on_the_frame_the_scene_changes() {
+    use sregame::preload::PreparedScenes;
+    use sregame::tilemap::CollisionMap;
+
+    fn transition_preloaded(game: &mut TestGame) -> Vec<bool> {
+        game.drain_spans()
+            .iter()
+            .filter(|span| span.name == "map.transition")
+            .map(|span| {
+                span.attributes
+                    .iter()
+                    .find(|kv| kv.key.as_str() == "map.preloaded")
+                    .map(|kv| kv.value == opentelemetry::Value::Bool(true))
+                    .expect("map.preloaded attribute")
+            })
+            .collect()
+    }
+
+    let mut game = fixture_game("portal");
+    assert_eq!(transition_preloaded(&mut game), [false], "the first map is a cold load");
+
+    // The spawn point is two tiles from the portal: close enough to start
+    // reading the marathon room straight away.
+    let mut frames = 0;
+    while !game.app_mut().world().resource::<PreparedScenes>().is_ready(Scene::TeamMarathon) {
+        assert!(frames < 120, "the marathon room never finished preloading");
+        game.step(1);
+        frames += 1;
+    }
+
+    game.press(GameAction::MoveLeft);
+    let mut changed_at = None;
+    for frame in 0..120 {
+        game.step(1);
+        if game.current_state().scene == Some(Scene::TeamMarathon) {
+            changed_at = Some(frame);
+            break;
+        }
+    }
+    game.release(GameAction::MoveLeft);
+    assert!(changed_at.is_some(), "the portal never fired");
+
+    // Same frame as the scene change, or the next at worst.
+    let room_up = |game: &mut TestGame| game.app_mut().world().get_resource::<CollisionMap>().is_some_and(|map| map.width == 5);
+    if !room_up(&mut game) {
+        game.step(1);
+    }
+    assert!(room_up(&mut game), "the marathon room should be spawned");
+    assert_eq!(transition_preloaded(&mut game), [true]);
+}
+
+#[test]
+fn a_warp_turns_the_player_counts_the_move_and_the_way_back_waits_for_a_step_off() {
+    use bevy::prelude::{Transform, With};
+    use sregame::map_data::tile_to_world;
+    use sregame::player::{Facing, Player};
+
+    fn facing(game: &mut TestGame) -> Facing {
+        let world = game.app_mut().world_mut();
+        *world.query_filtered::<&Facing, With<Player>>().single(world).unwrap()
+    }
+
+    // Onto the portal without walking, so no held key turns the player
+    // on arrival.
+    let mut game = fixture_game("portal");
+    let world = game.app_mut().world_mut();
+    let portal = tile_to_world(1, 2, 7, 5);
+    let mut transform = world.query_filtered::<&mut Transform, With<Player>>().single_mut(world).unwrap();
+    (transform.translation.x, transform.translation.y) = (portal.x, portal.y);
+    game.step(3);
+    assert_eq!(game.current_state().scene, Some(Scene::TeamMarathon), "the portal never fired");
+    assert_eq!(facing(&mut game), Facing::Up);
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.map.transitions"), "metrics: {names:?}");
+
+    // Arrived on the warp back to town: standing there doesn't take it.
+    game.step(10);
+    assert_eq!(game.current_state().scene, Some(Scene::TeamMarathon));
+
+    // Off it and back on: now it does.
+    game.press(GameAction::MoveRight);
+    game.step(40);
+    game.release(GameAction::MoveRight);
+    game.press(GameAction::MoveLeft);
+    for _ in 0..120 {
+        game.step(1);
+        if game.current_state().scene == Some(Scene::TownOfEndgame) {
+            break;
+        }
+    }
+    game.release(GameAction::MoveLeft);
+    assert_eq!(game.current_state().scene, Some(Scene::TownOfEndgame), "the warp back never fired");
+}
+
+#[test]
+fn a_map_with_a_player_spawn_starts_the_player_there() {
+    use bevy::prelude::{Transform, With};
+    use sregame::camera::{CameraFollow, MainCamera};
+    use sregame::map_data::tile_to_world;
+    use sregame::player::{Facing, Player};
+
+    let mut game = fixture_game("player_spawn");
+    game.step(2);
+    let world = game.app_mut().world_mut();
+    let (transform, facing) = world.query_filtered::<(&Transform, &Facing), With<Player>>().single(world).unwrap();
+    assert_eq!(transform.translation.truncate(), tile_to_world(5, 3, 7, 5));
+    assert_eq!(*facing, Facing::Left);
+    let follow = world.query_filtered::<&CameraFollow, With<MainCamera>>().single(world).unwrap();
+    assert!(!follow.snap, "the camera has already jumped there");
+}
+
+#[test]
+fn a_map_with_hard_problems_is_refused_and_each_one_recorded() {
+    use sregame::tilemap::CollisionMap;
+
+    let mut game = fixture_game("invalid_map");
+    game.step(2);
+    let errors: Vec<&str> =
+        game.app_mut().world().resource::<ContentErrors>().errors.iter().map(|error| error.error.as_str()).collect();
+    assert_eq!(
+        errors,
+        ["tiles has 33 cells, not the 7x5 map's 35", "NPC \"Casey\" at (9, 1) is off the 7x5 map"],
+        "the repeated name is only a warning"
+    );
+    assert!(game.app_mut().world().get_resource::<CollisionMap>().is_none(), "nothing of the map came up");
+    assert!(game.npc_names().is_empty());
+}
+
+#[test]
+fn saves_go_to_a_chosen_slot_and_the_picker_copies_deletes_and_loads_them() {
+    use bevy::prelude::KeyCode;
+    use sregame::npc::TimesTalked;
+    use sregame::save::{SaveDirectory, SaveSlot, SlotStatus, slot_status};
+    use sregame::save_menu::{MenuStep, SaveMenu};
+
+    fn menu(game: &mut TestGame) -> &SaveMenu {
+        game.app_mut().world().resource::<SaveMenu>()
+    }
+
+    let dir = sregame::testing::scratch_dir("save-slots");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("slot3.json"), "garbage").unwrap();
+    let mut game = base_game();
+    game.app_mut().insert_resource(SaveDirectory(dir.clone()));
+    let spawn = game.player_pos().unwrap();
+
+    // Nothing to continue yet: a damaged slot isn't a save.
+    game.tap_key(KeyCode::F9);
+    assert_eq!(menu(&mut game).continues(), None);
+    game.tap_key(KeyCode::Escape);
+
+    // F5 asks for a slot; slot 1 is empty, so it saves straight away -
+    // conversations counted along with everything else.
+    let talked = std::collections::HashMap::from([("isabella".to_string(), 2)]);
+    game.app_mut().world_mut().resource_mut::<TimesTalked>().restore(talked);
+    game.tap_key(KeyCode::F5);
+    assert_eq!(game.current_state().mode, Some(Mode::Menu));
+    let rows: Vec<SaveSlot> = menu(&mut game).rows().iter().map(|(slot, _)| *slot).collect();
+    assert_eq!(rows, [SaveSlot::Numbered(1), SaveSlot::Numbered(2), SaveSlot::Numbered(3)]);
+    game.tap_key(KeyCode::Space);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert!(matches!(slot_status(&dir, SaveSlot::Numbered(1)), SlotStatus::Saved(_)));
+
+    // Copy slot 1 into slot 2, then delete slot 1 (which asks first).
+    game.tap_key(KeyCode::F9);
+    assert_eq!(menu(&mut game).continues(), Some(SaveSlot::Numbered(1)));
+    assert_eq!(menu(&mut game).cursor(), 0, "starts on Continue");
+    assert_eq!(menu(&mut game).rows()[2].1, SlotStatus::Damaged, "damaged slots are listed, not hidden");
+    game.tap_key(KeyCode::ArrowDown);
+    game.tap_key(KeyCode::KeyC);
+    game.tap_key(KeyCode::ArrowDown);
+    game.tap_key(KeyCode::Space);
+    assert!(matches!(slot_status(&dir, SaveSlot::Numbered(2)), SlotStatus::Saved(_)));
+    game.tap_key(KeyCode::ArrowUp);
+    game.tap_key(KeyCode::Delete);
+    assert!(matches!(menu(&mut game).step(), MenuStep::Confirm(_)));
+    assert!(matches!(slot_status(&dir, SaveSlot::Numbered(1)), SlotStatus::Saved(_)), "not before Y");
+    game.tap_key(KeyCode::KeyY);
+    assert_eq!(slot_status(&dir, SaveSlot::Numbered(1)), SlotStatus::Empty);
+    game.tap_key(KeyCode::Escape);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    // Walk off and forget the conversations, then continue from the
+    // copy: back on the spawn tile, with the counts as saved.
+    game.press(GameAction::MoveLeft);
+    game.step(30);
+    game.release(GameAction::MoveLeft);
+    game.step(30);
+    assert_ne!(game.player_pos(), Some(spawn));
+    *game.app_mut().world_mut().resource_mut::<TimesTalked>() = TimesTalked::default();
+    game.tap_key(KeyCode::F9);
+    assert_eq!(menu(&mut game).continues(), Some(SaveSlot::Numbered(2)), "slot 2 is the only save left");
+    game.tap_key(KeyCode::Space);
+    game.step(1);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert_eq!(game.player_pos(), Some(spawn));
+    assert_eq!(game.app_mut().world().resource::<TimesTalked>().get("isabella"), 2);
+}
+
+#[test]
+fn the_split_timer_runs_through_dialogue_and_stops_while_paused() {
+    use bevy::prelude::{ButtonInput, KeyCode};
+    use sregame::splits::{SPLITS_JSON, SplitTimer, parse_milestones};
+
+    fn elapsed(game: &mut TestGame) -> std::time::Duration {
+        game.app_mut().world().resource::<SplitTimer>().elapsed()
+    }
+
+    let mut game = base_game();
+    game.app_mut().insert_resource(SplitTimer::new(parse_milestones(SPLITS_JSON).unwrap()));
+    let dir = sregame::testing::scratch_dir("split-timer");
+    game.app_mut().insert_resource(sregame::save::SaveDirectory(dir));
+    game.step(10);
+
+    game.interact();
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    let first = game.app_mut().world().resource::<SplitTimer>().splits().next().map(|(name, at)| (name.to_string(), at));
+    let (name, at) = first.unwrap();
+    assert_eq!(name, "First conversation");
+    assert!(at.is_some(), "talking to Isabella is the first split");
+
+    let talking = elapsed(&mut game);
+    game.step(30);
+    assert!(elapsed(&mut game) > talking, "the clock keeps running through dialogue");
+    game.press(GameAction::Cancel);
+    game.step(2);
+    game.release(GameAction::Cancel);
+    game.step(1);
+
+    game.app_mut().world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::F9);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Menu));
+    let paused = elapsed(&mut game);
+    game.step(60);
+    assert_eq!(elapsed(&mut game), paused, "paused time doesn't count");
+    assert!(game.app_mut().world().resource::<SplitTimer>().table().contains("First conversation"));
+}
+
+#[test]
+fn a_group_conversation_alternates_bubbles_and_waits_out_a_real_one() {
+    use bevy::prelude::{Text2d, With};
+    use opentelemetry::{Array, Value};
+    use sregame::ambient::ChatterBubble;
+    use sregame::dialogue::DialogueRequest;
+    use sregame::group_conversation::{ActiveGroupConversation, GROUP_LINE_SECONDS, GroupConversationLog};
+    use sregame::npc::PendingInteraction;
+
+    fn bubbles(game: &mut TestGame) -> Vec<String> {
+        let world = game.app_mut().world_mut();
+        let mut bubbles = world.query_filtered::<&Text2d, With<ChatterBubble>>();
+        bubbles.iter(world).map(|text| text.0.clone()).collect()
+    }
+    fn talking(game: &mut TestGame) -> bool {
+        game.app_mut().world().contains_resource::<ActiveGroupConversation>()
+    }
+    let line_frames = (GROUP_LINE_SECONDS * 60.0) as u32 + 2;
+
+    let mut game = fixture_game("group_conversation");
+    game.step(2);
+    assert_eq!(bubbles(&mut game), ["Morning, Casey."]);
+    game.step(line_frames);
+    assert_eq!(bubbles(&mut game), ["Morning. Pager's quiet."]);
+
+    // Talking to someone else pauses them; the line comes back afterwards.
+    game.app_mut().world_mut().write_message(DialogueRequest::from(("Narrator", vec!["Meanwhile.".to_string()])));
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert!(bubbles(&mut game).is_empty());
+    assert!(game.app_mut().world().resource::<ActiveGroupConversation>().is_paused());
+    game.read_dialogue_to_end(20);
+    game.step(2);
+    assert_eq!(bubbles(&mut game), ["Morning. Pager's quiet."]);
+
+    // Talking to Isabella waits for Casey's line, then breaks them up.
+    game.drain_spans();
+    game.interact();
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring), "a participant is busy");
+    assert!(game.app_mut().world().contains_resource::<PendingInteraction>());
+    assert!(!game.app_mut().world().resource::<ActiveGroupConversation>().is_paused());
+    for _ in 0..line_frames * 3 {
+        if game.current_state().mode == Some(Mode::Dialogue) {
+            break;
+        }
+        game.step(1);
+    }
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue), "the queued conversation starts");
+    assert!(!talking(&mut game), "the group broke up");
+    assert_eq!(game.active_dialogue().expect("Isabella's box").text, "Welcome to the fixture.");
+
+    let spans = game.drain_spans();
+    let span = spans.iter().find(|span| span.name == "npc.group_conversation").expect("group span");
+    let attribute = |key: &str| span.attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| kv.value.clone());
+    assert_eq!(
+        attribute("group.participants"),
+        Some(Value::Array(Array::String(vec!["Isabella".into(), "Casey".into()])))
+    );
+    assert_eq!(attribute("group.outcome"), Some("interrupted".into()));
+    assert_eq!(attribute("group.lines_shown"), Some(Value::I64(2)));
+    assert_eq!(attribute("group.pauses"), Some(Value::I64(1)));
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction span");
+    let Some(Value::I64(waited)) = span_attribute(interaction, "interaction.wait_ms") else {
+        panic!("no wait on {interaction:?}");
+    };
+    assert!(waited > 0 && waited <= (GROUP_LINE_SECONDS * 1000.0) as i64 + 100, "{waited}ms");
+
+    // `once`: standing right there doesn't start it again.
+    game.step(120);
+    assert!(!talking(&mut game));
+    assert_eq!(game.app_mut().world().resource::<GroupConversationLog>().get("fixture_standup").plays, 1);
+}
+
+fn span_attribute(span: &opentelemetry_sdk::trace::SpanData, key: &str) -> Option<opentelemetry::Value> {
+    span.attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| kv.value.clone())
+}
+
+#[test]
+fn chaos_frame_spikes_are_tagged_stalls() {
+    use bevy::time::TimeUpdateStrategy;
+    use sregame::chaos::{Chaos, ChaosParams, ChaosScenario};
+    use sregame::frame_watchdog::FrameWatchdog;
+
+    let mut game = base_game();
+    // Real time, as in a_slow_frame_is_reported_as_a_stall.
+    game.app_mut().insert_resource(TimeUpdateStrategy::Automatic);
+    game.step(3);
+    game.app_mut().insert_resource(FrameWatchdog::default());
+    let params = ChaosParams { amplitude: 200.0, period_secs: 0.0 };
+    game.app_mut().insert_resource(Chaos::new(ChaosScenario::FrameSpikes, params));
+    game.drain_spans();
+    game.step(2);
+
+    let spans = game.drain_spans();
+    let stall = spans.iter().find(|span| span.name == "game.frame.stall").expect("stall span recorded");
+    assert_eq!(span_attribute(stall, "chaos.scenario"), Some("frame_spikes".into()));
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.chaos.injections"), "metrics: {names:?}");
+}
+
+#[test]
+fn chaos_dialogue_latency_slows_and_tags_the_dialogue_span() {
+    use sregame::chaos::{Chaos, ChaosParams, ChaosScenario};
+
+    let mut game = base_game();
+    let params = ChaosParams { amplitude: 50.0, period_secs: 0.0 };
+    game.app_mut().insert_resource(Chaos::new(ChaosScenario::DialogueLatency, params));
+    game.drain_spans();
+
+    game.interact();
+    game.read_dialogue_to_end(20);
+    game.step(2);
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue span");
+    assert_eq!(span_attribute(session, "chaos.scenario"), Some("dialogue_latency".into()));
+    let duration = session.end_time.duration_since(session.start_time).unwrap();
+    assert!(duration.as_millis() >= 50, "the delay is inside the span: {duration:?}");
+}
+
+#[test]
+fn chaos_error_bursts_log_under_a_tagged_span() {
+    use opentelemetry::Value;
+    use sregame::chaos::{Chaos, ChaosParams, ChaosScenario};
+
+    let mut game = base_game();
+    let params = ChaosParams { amplitude: 3.0, period_secs: 0.5 };
+    game.app_mut().insert_resource(Chaos::new(ChaosScenario::ErrorBurst, params));
+    game.drain_spans();
+    game.step(40);
+
+    let spans = game.drain_spans();
+    let burst = spans.iter().find(|span| span.name == "chaos.error_burst").expect("burst span");
+    assert_eq!(span_attribute(burst, "chaos.scenario"), Some("error_burst".into()));
+    assert_eq!(span_attribute(burst, "chaos.errors"), Some(Value::I64(3)));
+}
+
+#[test]
+fn a_dialogue_choice_jumps_to_its_line_and_is_traced() {
+    use sregame::dialogue::DialogueSession;
+
+    /// The current box's choice labels and the highlighted one.
+    fn choices(game: &mut TestGame) -> (Vec<String>, usize) {
+        let queue = &game.app_mut().world().resource::<DialogueSession>().queue;
+        (queue.choices().iter().map(|choice| choice.label.clone()).collect(), queue.choice_cursor())
+    }
+
+    let mut game = fixture_game("branching");
+    game.interact();
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Which team are you here for?".into()));
+
+    game.tap(GameAction::Advance);
+    assert_eq!(choices(&mut game), (vec!["Disco".to_string(), "Marathon".to_string()], 0));
+    game.tap(GameAction::MoveDown);
+    assert_eq!(choices(&mut game).1, 1);
+    game.tap(GameAction::Advance);
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Marathon it is. Pace yourself.".into()));
+
+    game.tap(GameAction::Advance);
+    game.tap(GameAction::Advance);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session");
+    let choice = session.events.iter().find(|event| event.name == "dialogue.choice_selected").expect("choice event");
+    let label = choice.attributes.iter().find(|kv| kv.key.as_str() == "choice.label");
+    assert_eq!(label.map(|kv| kv.value.to_string()), Some("Marathon".to_string()));
+}
+
+#[test]
+fn hovering_a_choice_highlights_it_and_clicking_takes_its_branch() {
+    use bevy::prelude::{Entity, Interaction};
+    use sregame::dialogue::{DialogueSession, MenuRow};
+
+    /// What the mouse does to `row`, for a frame.
+    fn point(game: &mut TestGame, row: MenuRow, interaction: Interaction) {
+        let world = game.app_mut().world_mut();
+        let mut rows = world.query::<(Entity, &MenuRow)>();
+        let entity = rows.iter(world).find(|(_, candidate)| **candidate == row).map(|(entity, _)| entity);
+        *world.get_mut::<Interaction>(entity.expect("the row is spawned")).unwrap() = interaction;
+        game.step(1);
+    }
+    fn cursor(game: &mut TestGame) -> usize {
+        game.app_mut().world().resource::<DialogueSession>().queue.choice_cursor()
+    }
+
+    let mut game = fixture_game("branching");
+    game.interact();
+    game.tap(GameAction::Advance);
+    assert_eq!(cursor(&mut game), 0);
+
+    point(&mut game, MenuRow::Choice(1), Interaction::Hovered);
+    assert_eq!(cursor(&mut game), 1, "the highlight follows the mouse");
+    game.tap(GameAction::MoveUp);
+    assert_eq!(cursor(&mut game), 0, "and the keys still move it after");
+
+    point(&mut game, MenuRow::Choice(1), Interaction::Pressed);
+    point(&mut game, MenuRow::Choice(1), Interaction::Hovered);
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Marathon it is. Pace yourself.".into()));
+}
+
+#[test]
+fn an_npc_whose_sprite_the_registry_lacks_is_skipped_and_told_the_names_it_has() {
+    let mut game = fixture_game("unknown_sprite");
+    assert_eq!(game.npc_names(), vec!["Isabella".to_string()]);
+
+    let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(
+        errors[0].error.starts_with("NPC \"Stranger\" uses unknown sprite \"Nobody\" (available: Actor1, Actor2, "),
+        "error: {}",
+        errors[0].error
+    );
+    assert!(errors[0].error.contains(", Isabella, "), "error: {}", errors[0].error);
+}
+
+#[test]
+fn a_map_saved_on_windows_loads_and_reads_cleanly() {
+    let mut game = fixture_game("windows_authored");
+    let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(game.npc_names(), vec!["Isabella".to_string()]);
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(
+        game.active_dialogue().map(|segment| segment.text),
+        Some("Welcome to the fixture.\nIt was saved on Windows.".to_string())
+    );
+}
+
+#[test]
+fn an_npc_speaks_from_its_dialogue_file_once_loaded() {
+    use bevy::prelude::Assets;
+    use sregame::map_data::DialogueData;
+
+    let mut game = fixture_game("dialogue_file");
+    // The file loads on the IO task pool, in real time.
+    for _ in 0..200 {
+        if !game.app_mut().world().resource::<Assets<DialogueData>>().is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        game.step(1);
+    }
+    assert_eq!(game.app_mut().world().resource::<Assets<DialogueData>>().len(), 1);
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(
+        game.active_dialogue().map(|segment| segment.text),
+        Some("This line came from a dialogue file.".to_string())
+    );
+    let spans = game.drain_spans();
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction");
+    assert_eq!(
+        span_attribute(interaction, "dialogue.source").map(|value| value.to_string()),
+        Some("data/dialogue/isabella_intro.dialogue.json".to_string())
+    );
+}
+
+#[test]
+fn a_line_can_change_the_speakers_face_and_a_missing_one_falls_back() {
+    const OWN: &str = "textures/portraits/Isabella.png";
+    const SULKING: &str = "textures/portraits/Isabella_sulking.png";
+
+    let mut game = fixture_game("line_portraits");
+    game.interact();
+    assert_eq!(game.portrait_path().as_deref(), Some(OWN));
+
+    let mut taps = 0;
+    while game.active_dialogue().is_some_and(|segment| segment.text == "Welcome to the fixture.") {
+        assert!(taps < 20, "the line never advanced");
+        game.tap(GameAction::Advance);
+        taps += 1;
+    }
+    assert_eq!(game.active_dialogue().map(|segment| segment.portrait_path).as_deref(), Some(SULKING));
+
+    // The file isn't there; finding out takes the IO task pool, in real
+    // time.
+    for _ in 0..200 {
+        let reported = game.app_mut().world().resource::<ContentErrors>().errors.iter().any(|e| e.path == SULKING);
+        if reported && game.portrait_path().as_deref() == Some(OWN) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        game.step(1);
+    }
+    assert_eq!(game.portrait_path().as_deref(), Some(OWN), "her own face, not a broken one");
+    let errors = &game.app_mut().world().resource::<ContentErrors>().errors;
+    assert!(errors.iter().any(|e| e.path == SULKING && e.error.contains("portrait")), "{errors:?}");
+}
+
+#[test]
+fn the_dialogue_box_sits_where_the_map_and_conversation_put_it() {
+    use bevy::prelude::With;
+    use bevy::ui::{Node, Val};
+    use sregame::dialogue::DialogueRoot;
+
+    fn open_box(mut game: TestGame) -> (TestGame, [Val; 4]) {
+        game.press(GameAction::Interact);
+        game.step(3);
+        assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+        let world = game.app_mut().world_mut();
+        let mut boxes = world.query_filtered::<&Node, With<DialogueRoot>>();
+        let node = boxes.single(world).unwrap();
+        let placement = [node.top, node.left, node.width, node.height];
+        (game, placement)
+    }
+
+    let (_, bottom) = open_box(base_game());
+    assert_eq!(bottom, [Val::Percent(100.0 - 33.3), Val::Percent(0.0), Val::Percent(100.0), Val::Percent(33.3)]);
+
+    // The map's top and height, the conversation's width - centered, and
+    // still with room for her portrait.
+    let (mut game, top) = open_box(fixture_game("box_position"));
+    assert_eq!(top, [Val::Percent(0.0), Val::Percent(10.0), Val::Percent(80.0), Val::Percent(25.0)]);
+    assert_eq!(game.portrait_path().as_deref(), Some("textures/portraits/Isabella.png"));
+}
+
+#[test]
+fn a_conversation_can_pass_lines_between_the_npc_and_the_player() {
+    use bevy::color::{Color, ColorToComponents};
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use sregame::mood::{NEUTRAL_NAME_COLOR, PLAYER_NAME_COLOR};
+
+    fn advance_past(game: &mut TestGame, text: &str) {
+        let mut taps = 0;
+        while game.active_dialogue().is_some_and(|segment| segment.text == text) {
+            assert!(taps < 20, "the line never advanced");
+            game.tap(GameAction::Advance);
+            taps += 1;
+        }
+        // Long enough for the name's color to blend over.
+        game.step(20);
+    }
+    fn name_color_is(game: &mut TestGame, color: Color) -> bool {
+        let shown = game.speaker_name().unwrap().1.to_linear().to_f32_array();
+        let expected = color.to_linear().to_f32_array();
+        shown.iter().zip(expected).all(|(shown, expected)| (shown - expected).abs() < 1e-3)
+    }
+
+    let mut game = fixture_game("two_speakers");
+    game.interact();
+    assert_eq!(game.speaker_name().map(|name| name.0).as_deref(), Some("Isabella"));
+    assert!(name_color_is(&mut game, NEUTRAL_NAME_COLOR));
+
+    advance_past(&mut game, "Welcome to the fixture.");
+    assert_eq!(game.speaker_name().map(|name| name.0).as_deref(), Some("Amy"));
+    assert!(name_color_is(&mut game, PLAYER_NAME_COLOR), "the player's own color");
+
+    advance_past(&mut game, "Thanks. Is it always this small?");
+    assert_eq!(game.speaker_name().map(|name| name.0).as_deref(), Some("Isabella"));
+    assert!(name_color_is(&mut game, NEUTRAL_NAME_COLOR));
+
+    // Each line counts for whoever said it.
+    let mut speakers: Vec<String> = game
+        .drain_metrics()
+        .iter()
+        .flat_map(|resource| resource.scope_metrics())
+        .flat_map(|scope| scope.metrics())
+        .filter(|metric| metric.name() == "game.dialogue_lines_read")
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .flat_map(|point| point.attributes())
+                .filter(|attribute| attribute.key.as_str() == "speaker.id")
+                .map(|attribute| attribute.value.to_string())
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    speakers.sort();
+    speakers.dedup();
+    assert_eq!(speakers, ["amy", "isabella"]);
+}
+
+#[test]
+fn dialogue_lines_quote_live_game_variables() {
+    let mut game = fixture_game("variables");
+    game.interact();
+    // Filled in before typing: the box, its markup and the log all see
+    // the final text.
+    let first = "You've talked to 1 of us. You're at {b}(3, 2){/b}.";
+    assert_eq!(game.active_dialogue().map(|segment| segment.text).as_deref(), Some(first));
+    game.step(180);
+    assert_eq!(game.dialogue_text().as_deref(), Some("You've talked to 1 of us. You're at (3, 2)."));
+
+    let mut taps = 0;
+    while game.active_dialogue().is_some_and(|segment| segment.text == first) {
+        assert!(taps < 20, "the line never advanced");
+        game.tap(GameAction::Advance);
+        taps += 1;
+    }
+    assert_eq!(
+        game.active_dialogue().map(|segment| segment.text).as_deref(),
+        Some("{mystery_guest} says hi."),
+        "a variable nobody sets is shown as written"
+    );
+}
+
+#[test]
+fn a_content_pack_overrides_a_dialogue_and_patches_an_npc_into_the_map() {
+    use bevy::prelude::Assets;
+    use sregame::map_data::DialogueData;
+
+    // workshop_pack stacks on dialogue_file, the way a workshop's pack
+    // would on a town that already points Isabella at a dialogue file.
+    let mut game = TestGame::with_content_packs(FIXTURES, [fixture_pack("dialogue_file"), fixture_pack("workshop_pack")]);
+    assert_eq!(game.npc_names(), vec!["Isabella".to_string(), "Morgan".to_string()]);
+
+    for _ in 0..200 {
+        if !game.app_mut().world().resource::<Assets<DialogueData>>().is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        game.step(1);
+    }
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(
+        game.active_dialogue().map(|segment| segment.text),
+        Some("Welcome to the workshop edition.".to_string())
+    );
+}
+
+#[test]
+fn instant_text_skips_the_typewriter_but_still_counts_reading() {
+    use sregame::dialogue::DialogueSettings;
+
+    let mut game = base_game();
+    game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, ..Default::default() });
+    game.interact();
+
+    // The line is already whole, so Advance goes straight on.
+    game.tap(GameAction::Advance);
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Mind the wall.".to_string()));
+    game.tap(GameAction::Advance);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session");
+    let both_lines = "Welcome to the fixture.Mind the wall.".len() as i64;
+    assert_eq!(span_attribute(session, "dialogue.chars_read"), Some(both_lines.into()));
+    assert!(metric_names(&mut game).contains(&"game.dialogue.reading_speed".to_string()));
+}
+
+#[test]
+fn a_terminal_opens_the_dashboard_over_isabella_and_escape_closes_it() {
+    use sregame::dashboard::LiveMetrics;
+
+    let mut game = fixture_game("terminal");
+    // Isabella is in range too, but the terminal being faced wins.
+    game.interact();
+    assert_eq!(game.current_state().mode, Some(Mode::Dashboard));
+    assert!(game.active_dialogue().is_none());
+
+    game.step(2);
+    let live = game.app_mut().world().resource::<LiveMetrics>();
+    assert!(live.fps().is_some() && live.p95_frame_time().is_some());
+
+    game.press(GameAction::Cancel);
+    game.step(3);
+    game.release(GameAction::Cancel);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+}
+
+#[test]
+fn the_dashboard_counts_lines_as_they_are_read() {
+    use sregame::dashboard::LiveMetrics;
+    use sregame::dialogue::DialogueSettings;
+
+    let lines_read = |game: &mut TestGame| game.app_mut().world().resource::<LiveMetrics>().dialogue_lines_read;
+
+    let mut game = base_game();
+    game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, ..Default::default() });
+    assert_eq!(lines_read(&mut game), 0);
+    game.interact();
+    game.step(1);
+    assert_eq!(lines_read(&mut game), 1);
+
+    game.tap(GameAction::Advance);
+    assert_eq!(lines_read(&mut game), 2);
+    assert!(metric_names(&mut game).contains(&"game.dialogue_lines_read".to_string()));
+}
+
+#[test]
+fn the_typewriter_is_frame_exact_from_the_frame_the_box_opens() {
+    let mut game = base_game();
+    game.press(GameAction::Interact);
+    game.step_until(30, |game| game.current_state().mode == Some(Mode::Dialogue));
+    game.release(GameAction::Interact);
+    // The opening frame's time passed before the box was up.
+    assert_eq!(game.dialogue_text().as_deref(), Some(""));
+
+    // A character per 30ms, 60 frames a second.
+    let mut frames = 0;
+    for (after, typed) in [(1, ""), (2, "W"), (5, "We"), (10, "Welco"), (20, "Welcome to ")] {
+        game.step(after - frames);
+        frames = after;
+        assert_eq!(game.dialogue_text().as_deref(), Some(typed), "{after} frames in");
+    }
+
+    // Completing a line counts the rest as read, once.
+    let tap = |game: &mut TestGame| {
+        game.press(GameAction::Advance);
+        game.step(1);
+        game.release(GameAction::Advance);
+    };
+    tap(&mut game);
+    assert_eq!(game.dialogue_text().as_deref(), Some("Welcome to the fixture."));
+    game.step(5);
+    tap(&mut game);
+    game.step(5);
+    assert_eq!(game.dialogue_text().as_deref(), Some("Mi"));
+    tap(&mut game);
+    tap(&mut game);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session");
+    let both_lines = "Welcome to the fixture.Mind the wall.".len() as i64;
+    assert_eq!(span_attribute(session, "dialogue.chars_read"), Some(both_lines.into()));
+}
+
+#[test]
+fn marked_up_lines_show_their_words_in_their_colors() {
+    use bevy::prelude::{TextColor, TextSpan};
+    use sregame::dialogue::{DialogueRequest, DialogueSettings, MARKUP_COLORS};
+
+    let mut game = base_game();
+    game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, ..Default::default() });
+    game.step(2);
+    let line = "The {red}error budget{/red} is {pause:0.5}gone.".to_string();
+    game.app_mut().world_mut().write_message(DialogueRequest::from(("Narrator", vec![line])));
+    game.step(3);
+
+    assert_eq!(game.dialogue_text().as_deref(), Some("The error budget is gone."));
+    let world = game.app_mut().world_mut();
+    let mut spans = world.query::<(&TextSpan, &TextColor)>();
+    let red: Vec<String> = spans
+        .iter(world)
+        .filter(|(_, color)| color.0 == MARKUP_COLORS[0].1)
+        .map(|(span, _)| span.0.clone())
+        .collect();
+    assert_eq!(red, ["error budget"]);
+}
+
+#[test]
+fn reading_speed_counts_the_time_a_line_sits_whole_not_the_typing() {
+    let tap = |game: &mut TestGame| {
+        game.press(GameAction::Advance);
+        game.step(1);
+        game.release(GameAction::Advance);
+    };
+    let mut game = base_game();
+    game.press(GameAction::Interact);
+    game.step_until(30, |game| game.current_state().mode == Some(Mode::Dialogue));
+    game.release(GameAction::Interact);
+
+    // "Welcome to the fixture." is whole 42 frames in, a character per
+    // 30ms (see the typewriter test), then sits for 90 more frames until
+    // the press.
+    game.step(42);
+    assert_eq!(game.dialogue_text().as_deref(), Some("Welcome to the fixture."));
+    game.step(89);
+    tap(&mut game);
+    // The second line is cut short 5 frames in, then read for 30.
+    game.step(5);
+    tap(&mut game);
+    game.step(29);
+    tap(&mut game);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session");
+    let dwells: Vec<f64> = session
+        .events
+        .iter()
+        .filter(|event| event.name == "dialogue.line_dwell")
+        .filter_map(|event| event.attributes.iter().find(|kv| kv.key.as_str() == "line.dwell_secs"))
+        .filter_map(|kv| match kv.value {
+            opentelemetry::Value::F64(secs) => Some(secs),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(dwells.len(), 2, "{dwells:?}");
+    assert!((dwells[0] - 1.5).abs() < 0.001, "{dwells:?}");
+    assert!((dwells[1] - 0.5).abs() < 0.001, "{dwells:?}");
+
+    let chars = "Welcome to the fixture.Mind the wall.".len() as f64;
+    let Some(opentelemetry::Value::F64(speed)) = span_attribute(session, "dialogue.dwell_reading_speed") else {
+        panic!("no dialogue.dwell_reading_speed");
+    };
+    assert!((speed - chars / 2.0).abs() < 0.01, "{speed}");
+
+    let names = metric_names(&mut game);
+    assert!(names.contains(&"game.dialogue.line_dwell".to_string()));
+    assert!(names.contains(&"game.dialogue.dwell_reading_speed".to_string()));
+    assert!(names.contains(&"game.dialogue.reading_speed".to_string()), "kept for continuity");
+}
+
+#[test]
+fn the_dialogue_log_shows_lines_read_and_the_typewriter_waits_under_it() {
+    use sregame::dialogue_history::{DialogueHistory, HistoryLog};
+
+    let tap = |game: &mut TestGame, action: GameAction| {
+        game.press(action);
+        game.step(1);
+        game.release(action);
+    };
+    let log_open = |game: &mut TestGame| game.app_mut().world().contains_resource::<HistoryLog>();
+
+    let mut game = base_game();
+    game.interact();
+    // Reveal the first line whole, then type two characters of the second.
+    game.tap(GameAction::Advance);
+    game.step(5);
+    game.tap(GameAction::Advance);
+    game.step(5);
+    assert_eq!(game.dialogue_text().as_deref(), Some("Mi"));
+
+    let lines: Vec<(String, String)> = game
+        .app_mut()
+        .world()
+        .resource::<DialogueHistory>()
+        .entries()
+        .map(|entry| (entry.speaker.clone(), entry.line.clone()))
+        .collect();
+    assert_eq!(lines, vec![("Isabella".to_string(), "Welcome to the fixture.".to_string())]);
+
+    game.tap(GameAction::History);
+    assert!(log_open(&mut game));
+    game.step(30);
+    assert_eq!(game.dialogue_text().as_deref(), Some("Mi"), "the typewriter waits while the log is open");
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+
+    // A tap of Tab closes it too; the line carries on from where it was.
+    game.tap(GameAction::Skip);
+    game.step(1);
+    assert!(!log_open(&mut game));
+    game.step(10);
+    let text = game.dialogue_text().unwrap_or_default();
+    assert!(text.starts_with("Mind") && text.len() < "Mind the wall.".len(), "{text:?}");
+}
+
+#[test]
+fn a_long_line_pages_through_the_box_a_word_boundary_at_a_time() {
+    use sregame::dialogue::{BOX_ROWS, BOX_ROW_CHARS, DialogueSettings};
+
+    let mut game = fixture_game("long_line");
+    game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, ..Default::default() });
+    game.press(GameAction::Interact);
+    game.step_until(30, |game| game.current_state().mode == Some(Mode::Dialogue));
+    game.release(GameAction::Interact);
+    let line = game.active_dialogue().expect("box up").text;
+    assert_eq!(line.chars().count(), 600);
+
+    // Space turns the page until the line is done, then goes on.
+    let mut pages = Vec::new();
+    while game.active_dialogue().is_some_and(|segment| segment.text == line) {
+        assert!(pages.len() < 100, "the line never finished paging");
+        game.step(1);
+        pages.push(game.dialogue_text().expect("box up"));
+        game.press(GameAction::Advance);
+        game.step(1);
+        game.release(GameAction::Advance);
+    }
+    assert!(pages.len() > 1, "{pages:?}");
+    for page in &pages {
+        assert!(page.lines().count() <= BOX_ROWS, "{page:?}");
+        assert!(page.lines().all(|row| row.chars().count() <= BOX_ROW_CHARS), "{page:?}");
+    }
+    // Every word whole, in order, nothing lost but the breaks.
+    assert_eq!(pages.join(" ").replace('\n', " "), line);
+    assert_eq!(game.active_dialogue().map(|segment| segment.text), Some("Mind the wall.".to_string()));
+}
+
+#[test]
+fn quitting_asks_first_only_when_there_is_unsaved_progress() {
+    use sregame::quit::{ConfirmQuit, QuitPrompt, QuitRequested};
+    use sregame::save::UnsavedChanges;
+
+    fn dirty(game: &mut TestGame) -> bool {
+        game.app_mut().world().resource::<UnsavedChanges>().is_dirty()
+    }
+    fn request_quit(game: &mut TestGame) {
+        game.app_mut().world_mut().write_message(QuitRequested);
+        game.step(2);
+    }
+
+    // Nothing done yet: the close button just quits.
+    let mut game = base_game();
+    game.step(2);
+    assert!(!dirty(&mut game), "where the game starts is nothing to lose");
+    request_quit(&mut game);
+    assert!(game.app_mut().should_exit().is_some());
+
+    let mut game = base_game();
+    game.press(GameAction::MoveLeft);
+    game.step(60);
+    game.release(GameAction::MoveLeft);
+    game.step(2);
+    assert!(dirty(&mut game), "the player has walked off the start tile");
+
+    request_quit(&mut game);
+    assert_eq!(game.current_state().mode, Some(Mode::Menu));
+    assert!(game.app_mut().world().contains_resource::<QuitPrompt>());
+    assert!(game.app_mut().should_exit().is_none(), "it asks first");
+
+    game.press(GameAction::Cancel);
+    game.step(2);
+    game.release(GameAction::Cancel);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert!(!game.app_mut().world().contains_resource::<QuitPrompt>());
+    game.step(2);
+    assert!(game.app_mut().should_exit().is_none(), "cancel stays");
+
+    // --frames (like --seconds and --headless): nobody to ask.
+    game.app_mut().world_mut().insert_resource(ConfirmQuit::for_run(false, Some(600), None));
+    request_quit(&mut game);
+    assert!(game.app_mut().should_exit().is_some());
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+
+    // Closing the window a second time while it asks: save and quit.
+    let mut game = base_game();
+    game.press(GameAction::MoveLeft);
+    game.step(60);
+    game.release(GameAction::MoveLeft);
+    game.step(2);
+    request_quit(&mut game);
+    assert_eq!(game.current_state().mode, Some(Mode::Menu));
+    assert!(game.app_mut().should_exit().is_none());
+    request_quit(&mut game);
+    assert!(game.app_mut().should_exit().is_some(), "asking again is the answer");
+}
+
+#[test]
+fn a_line_acts_once_as_it_is_read_past() {
+    use sregame::dialogue::DialogueSettings;
+
+    fn met(game: &mut TestGame) -> bool {
+        game.app_mut().world().resource::<GameFlags>().is_set("met_isabella")
+    }
+    fn text(game: &TestGame) -> Option<String> {
+        game.active_dialogue().map(|segment| segment.text)
+    }
+
+    let mut game = fixture_game("line_actions");
+    game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, ..Default::default() });
+    game.interact();
+    assert_eq!(text(&game).as_deref(), Some("Welcome."));
+    assert!(!met(&mut game), "not until it's read past");
+
+    game.tap(GameAction::Advance);
+    assert_eq!(text(&game).as_deref(), Some("Hear that again?"));
+    assert!(met(&mut game));
+
+    // "Yes" goes back round: the line is read past twice, acts once.
+    game.tap(GameAction::Advance);
+    assert_eq!(text(&game).as_deref(), Some("Welcome."));
+    game.tap(GameAction::Advance);
+    game.tap(GameAction::MoveDown);
+    game.tap(GameAction::Advance);
+    assert_eq!(text(&game).as_deref(), Some("Then off to the Marathon."));
+    assert_eq!(game.current_state().scene, Some(Scene::TownOfEndgame));
+
+    // Space mashed through the last line: one scene change, once the box
+    // has closed.
+    for _ in 0..4 {
+        game.tap(GameAction::Advance);
+    }
+    game.step(10);
+    let state = game.current_state();
+    assert_eq!((state.mode, state.scene), (Some(Mode::Exploring), Some(Scene::TeamMarathon)));
+
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "dialogue.session").expect("dialogue.session");
+    let actions: Vec<(String, String)> = session
+        .events
+        .iter()
+        .filter(|event| event.name == "dialogue.line_action")
+        .map(|event| {
+            let attribute = |key: &str| {
+                event.attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| kv.value.to_string()).unwrap_or_default()
+            };
+            (attribute("action.type"), attribute("action.value"))
+        })
+        .collect();
+    assert_eq!(
+        actions,
+        [("set_flag".to_string(), "met_isabella".to_string()), ("change_scene".to_string(), "TeamMarathon".to_string())]
+    );
+}
+
+#[test]
+fn renaming_a_character_keeps_what_telemetry_and_saves_key_on() {
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use sregame::dialogue::SeenDialogues;
+
+    /// Talk the town's NPC through to the end: every key that names them -
+    /// in the interaction and dialogue spans, the lines-read metric and
+    /// the read conversations a save keeps - and the name they go by.
+    fn keys_after_talking(mut game: TestGame) -> (Vec<String>, String) {
+        game.interact();
+        game.read_dialogue_to_end(20);
+        game.step(2);
+
+        let spans = game.drain_spans();
+        let attribute = |span_name: &str, key: &str| {
+            spans
+                .iter()
+                .find(|span| span.name == span_name)
+                .and_then(|span| span.attributes.iter().find(|kv| kv.key.as_str() == key))
+                .map(|kv| kv.value.to_string())
+                .unwrap_or_default()
+        };
+        let mut keys = vec![
+            attribute("npc.interaction", "npc.id"),
+            attribute("dialogue.session", "dialogue.id"),
+            attribute("dialogue.session", "dialogue.speaker_id"),
+        ];
+        let mut speakers: Vec<String> = game
+            .drain_metrics()
+            .iter()
+            .flat_map(|resource| resource.scope_metrics())
+            .flat_map(|scope| scope.metrics())
+            .filter(|metric| metric.name() == "game.dialogue_lines_read")
+            .flat_map(|metric| match metric.data() {
+                AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                    .data_points()
+                    .flat_map(|point| point.attributes())
+                    .filter(|attribute| attribute.key.as_str() == "speaker.id")
+                    .map(|attribute| attribute.value.to_string())
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+        speakers.dedup();
+        keys.extend(speakers);
+        keys.extend(game.app_mut().world().resource::<SeenDialogues>().ids().map(str::to_string));
+        (keys, attribute("npc.interaction", "npc.name"))
+    }
+
+    let (keys, name) = keys_after_talking(base_game());
+    assert_eq!(keys, ["isabella", "town_of_endgame/isabella", "isabella", "isabella", "town_of_endgame/isabella"]);
+    assert_eq!(name, "Isabella");
+
+    let (renamed_keys, renamed_name) = keys_after_talking(fixture_game("renamed_speaker"));
+    assert_eq!(renamed_keys, keys, "dashboards and saves still find her");
+    assert_eq!(renamed_name, "Izzy", "the name comes along for reading");
+}
+
+#[test]
+fn npcs_say_the_first_dialogue_whose_flags_hold() {
+    /// Talk to Isabella: her first line, and the selection's variant.
+    fn talk(game: &mut TestGame) -> (String, String) {
+        game.interact();
+        let first = game.active_dialogue().expect("dialogue box up").text;
+        game.read_dialogue_to_end(20);
+        game.step(2);
+        let variant = game
+            .drain_spans()
+            .iter()
+            .rev()
+            .find(|span| span.name == "npc.interaction")
+            .and_then(|span| span.attributes.iter().find(|kv| kv.key.as_str() == "dialogue.variant"))
+            .map(|kv| kv.value.to_string())
+            .unwrap_or_default();
+        (first, variant)
+    }
+
+    let mut game = fixture_game("conditional_dialogue");
+    assert_eq!(talk(&mut game), ("Welcome to the fixture.".to_string(), "default".to_string()));
+    assert_eq!(
+        talk(&mut game),
+        ("Back again? It's still paging.".to_string(), "town_of_endgame/isabella_again".to_string())
+    );
+
+    game.app_mut().world_mut().resource_mut::<GameFlags>().set("quest_done");
+    assert_eq!(
+        talk(&mut game),
+        ("Thanks for fixing the pager.".to_string(), "town_of_endgame/isabella_thanks".to_string()),
+        "the first that holds wins"
+    );
+}
+
+#[test]
+fn rumble_reaches_connected_gamepads_unless_turned_off() {
+    use bevy::input::gamepad::{Gamepad, GamepadRumbleRequest};
+    use bevy::prelude::*;
+    use sregame::rumble::RumbleEvent;
+    use sregame::settings::GameSettings;
+
+    /// Rumble durations asked of the gamepads after writing `events`.
+    fn rumbles(game: &mut TestGame, events: &[RumbleEvent]) -> Vec<u128> {
+        game.app_mut().world_mut().write_message_batch(events.iter().copied());
+        game.step(1);
+        game.app_mut()
+            .world_mut()
+            .resource_mut::<Messages<GamepadRumbleRequest>>()
+            .drain()
+            .filter_map(|request| match request {
+                GamepadRumbleRequest::Add { duration, .. } => Some(duration.as_millis()),
+                GamepadRumbleRequest::Stop { .. } => None,
+            })
+            .collect()
+    }
+
+    let mut game = base_game();
+    assert!(rumbles(&mut game, &[RumbleEvent::STRONG]).is_empty(), "no gamepad, nothing to do");
+
+    game.app_mut().world_mut().spawn(Gamepad::default());
+    game.app_mut().world_mut().resource_mut::<GameSettings>().rumble = false;
+    assert!(rumbles(&mut game, &[RumbleEvent::STRONG]).is_empty(), "turned off");
+
+    game.app_mut().world_mut().resource_mut::<GameSettings>().rumble = true;
+    let burst = [RumbleEvent::LIGHT, RumbleEvent::STRONG, RumbleEvent::LIGHT];
+    assert_eq!(rumbles(&mut game, &burst), [400], "one, not three");
+}
+
+#[test]
+fn npcs_that_speak_once_say_their_repeat_line_after() {
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use sregame::npc::{NpcDialogueState, REPEAT_LINE_VARIANT};
+
+    /// Talk to Isabella: every line she says, and the selection's variant.
+    fn talk(game: &mut TestGame) -> (Vec<String>, String) {
+        game.interact();
+        let mut lines = Vec::new();
+        while game.current_state().mode == Some(Mode::Dialogue) {
+            assert!(lines.len() < 20, "the conversation never ended: {lines:?}");
+            if let Some(segment) = game.active_dialogue()
+                && lines.last() != Some(&segment.text)
+            {
+                lines.push(segment.text);
+            }
+            game.tap(GameAction::Advance);
+        }
+        game.step(2);
+        let variant = game
+            .drain_spans()
+            .iter()
+            .rev()
+            .find(|span| span.name == "npc.interaction")
+            .and_then(|span| span.attributes.iter().find(|kv| kv.key.as_str() == "dialogue.variant"))
+            .map(|kv| kv.value.to_string())
+            .unwrap_or_default();
+        (lines, variant)
+    }
+
+    let mut game = fixture_game("repeat_once");
+    let (lines, variant) = talk(&mut game);
+    assert_eq!(lines, ["Welcome to the fixture.", "Mind the wall."]);
+    assert_eq!(variant, "default");
+    for _ in 0..2 {
+        let (lines, variant) = talk(&mut game);
+        assert_eq!(lines, ["We already covered the postmortem."]);
+        assert_eq!(variant, REPEAT_LINE_VARIANT);
+    }
+
+    let world = game.app_mut().world_mut();
+    let mut states = world.query::<&NpcDialogueState>();
+    let state = states.single(world).expect("Isabella's state");
+    assert_eq!(state.times_talked, 3);
+    assert!(state.last_talked.is_some());
+
+    let mut attributes: Vec<String> = game
+        .drain_metrics()
+        .iter()
+        .flat_map(|resource| resource.scope_metrics())
+        .flat_map(|scope| scope.metrics())
+        .filter(|metric| metric.name() == "game.interactions.total")
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .flat_map(|point| point.attributes())
+                .filter(|attribute| matches!(attribute.key.as_str(), "repeat" | "npc.name"))
+                .map(|attribute| format!("{}={}", attribute.key, attribute.value))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    attributes.sort();
+    attributes.dedup();
+    assert_eq!(
+        attributes,
+        ["npc.name=Isabella", "repeat=false", "repeat=true"],
+        "first-time and repeat interactions counted apart, named"
+    );
+}
+
+#[test]
+fn the_console_teleports_the_player_to_a_tile() {
+    use bevy::prelude::KeyCode;
+    use sregame::console::DevConsole;
+    use sregame::map_data::tile_to_world;
+
+    let mut game = base_game();
+    game.type_text("`");
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Console));
+
+    game.type_text("tp 1 1");
+    game.type_key(KeyCode::Enter);
+    game.step(1);
+    assert_eq!(game.player_pos(), Some(tile_to_world(1, 1, 7, 5)));
+
+    game.type_text("tp 9 9");
+    game.type_key(KeyCode::Enter);
+    game.step(1);
+    let world = game.app_mut().world_mut();
+    let output: Vec<String> = world.resource::<DevConsole>().output().map(String::from).collect();
+    assert_eq!(output, ["> tp 1 1", "teleported to (1, 1)", "> tp 9 9", "error: (9, 9) is off the 7x5 map"]);
+    assert_eq!(game.player_pos(), Some(tile_to_world(1, 1, 7, 5)), "a bad tp stays put");
+}
+
+#[test]
+fn the_console_sets_flags_with_a_tab_completed_command() {
+    use bevy::prelude::KeyCode;
+
+    let mut game = base_game();
+    game.type_text("`");
+    game.step(2);
+    game.type_text("fl");
+    game.type_key(KeyCode::Tab);
+    game.type_text("set met_isabella");
+    game.type_key(KeyCode::Enter);
+    game.step(2);
+    assert!(game.app_mut().world().resource::<GameFlags>().is_set("met_isabella"));
+
+    game.type_key(KeyCode::Escape);
+    game.step(2);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+}
+
+#[test]
+fn the_console_gives_achievements() {
+    use bevy::prelude::KeyCode;
+    use sregame::achievements::Achievements;
+    use sregame::console::DevConsole;
+
+    let mut game = base_game();
+    game.type_text("`");
+    game.step(2);
+    game.type_text("give achievement first_steps");
+    game.type_key(KeyCode::Enter);
+    game.step(2);
+    let world = game.app_mut().world_mut();
+    assert!(world.resource::<Achievements>().has("first_steps"));
+    assert_eq!(world.resource::<DevConsole>().output().last(), Some("unlocking first_steps"));
+}
+
+#[test]
+fn the_talk_prompt_fades_in_over_the_npc_in_range_and_out_for_the_conversation() {
+    use bevy::prelude::{Text2d, TextColor};
+    use sregame::interaction_prompt::InteractionPrompt;
+
+    fn prompt(game: &mut TestGame) -> Option<(String, f32)> {
+        let world = game.app_mut().world_mut();
+        let mut prompts = world.query::<(&InteractionPrompt, &Text2d, &TextColor)>();
+        prompts.iter(world).next().map(|(prompt, text, color)| {
+            assert_eq!(color.0.alpha(), prompt.opacity);
+            (text.0.clone(), prompt.opacity)
+        })
+    }
+
+    let mut game = base_game();
+    game.step(2);
+    let (text, opacity) = prompt(&mut game).expect("Isabella's in range from the start");
+    assert_eq!(text, "Press E to talk");
+    assert!(opacity < 1.0, "fading in, not popped: {opacity}");
+    game.step(15);
+    assert_eq!(prompt(&mut game).map(|(_, opacity)| opacity), Some(1.0));
+
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    assert!(prompt(&mut game).is_some_and(|(_, opacity)| opacity < 1.0), "fading out under the box");
+    game.step(15);
+    assert_eq!(prompt(&mut game), None);
+}
+
+#[test]
+fn the_mouse_keeps_the_kiosk_from_resetting() {
+    use bevy::input::mouse::AccumulatedMouseMotion;
+    use bevy::prelude::{ButtonInput, MouseButton, Vec2};
+    use std::time::Duration;
+    use sregame::kiosk::Kiosk;
+
+    fn resetting(game: &mut TestGame) -> bool {
+        game.app_mut().world().resource::<Kiosk>().is_resetting()
+    }
+
+    let mut game = base_game();
+    game.app_mut().world_mut().insert_resource(Kiosk::new(Duration::from_secs(1)));
+    game.app_mut().world_mut().resource_mut::<AccumulatedMouseMotion>().delta = Vec2::new(3.0, 0.0);
+    game.step(90);
+    assert!(!resetting(&mut game), "someone is moving the mouse");
+
+    game.app_mut().world_mut().resource_mut::<AccumulatedMouseMotion>().delta = Vec2::ZERO;
+    game.app_mut().world_mut().resource_mut::<ButtonInput<MouseButton>>().press(MouseButton::Left);
+    game.step(90);
+    assert!(!resetting(&mut game), "or holding a button down");
+
+    game.app_mut().world_mut().resource_mut::<ButtonInput<MouseButton>>().release(MouseButton::Left);
+    game.step(90);
+    assert!(resetting(&mut game), "idle for the limit");
+}
+
+#[test]
+fn kiosk_resets_twice_to_a_fresh_game_without_leaking() {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+    use sregame::dialogue::SeenDialogues;
+    use sregame::kiosk::Kiosk;
+    use sregame::npc::TimesTalked;
+
+    /// Idle until the next reset has faded back in.
+    fn wait_for_reset(game: &mut TestGame) {
+        let resets = |game: &mut TestGame| {
+            let kiosk = game.app_mut().world().resource::<Kiosk>();
+            (kiosk.resets(), kiosk.is_resetting())
+        };
+        let (before, _) = resets(game);
+        for _ in 0..60 * 10 {
+            game.step(1);
+            if resets(game) == (before + 1, false) {
+                return;
+            }
+        }
+        panic!("no kiosk reset within 10 seconds");
+    }
+
+    /// Resources by type, counted: one that piles up shows as growth.
+    fn resources(game: &mut TestGame) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for (info, _) in game.app_mut().world().iter_resources() {
+            *counts.entry(info.name().to_string()).or_default() += 1;
+        }
+        counts
+    }
+
+    fn play_a_little(game: &mut TestGame) {
+        game.app_mut().world_mut().resource_mut::<GameFlags>().set("met_isabella");
+        // Walk off and leave Isabella mid-conversation.
+        game.interact();
+        assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+        assert_eq!(game.app_mut().world().resource::<TimesTalked>().npcs(), 1);
+    }
+
+    fn assert_fresh(game: &mut TestGame) {
+        let world = game.app_mut().world();
+        assert_eq!(*world.resource::<GameFlags>(), GameFlags::default());
+        assert_eq!(world.resource::<TimesTalked>().npcs(), 0);
+        assert_eq!(*world.resource::<SeenDialogues>(), SeenDialogues::default());
+        let state = game.current_state();
+        assert_eq!((state.scene, state.mode), (Some(Scene::TownOfEndgame), Some(Mode::Exploring)));
+        assert_eq!(game.player_pos(), Some(bevy::math::Vec2::ZERO));
+    }
+
+    let mut game = base_game();
+    game.app_mut().world_mut().insert_resource(Kiosk::new(Duration::from_secs(3)));
+    game.step(2);
+    let leaks = LeakCheck::start(&game);
+    game.drain_spans();
+
+    play_a_little(&mut game);
+    wait_for_reset(&mut game);
+    assert_fresh(&mut game);
+    leaks.assert_no_growth(&game);
+    let between = resources(&mut game);
+
+    play_a_little(&mut game);
+    wait_for_reset(&mut game);
+    assert_fresh(&mut game);
+    leaks.assert_no_growth(&game);
+    assert_eq!(resources(&mut game), between, "resources differ from the last session's");
+
+    let spans = game.drain_spans();
+    let sessions: Vec<_> = spans.iter().filter(|span| span.name == "game_session").collect();
+    assert_eq!(sessions.len(), 2, "each reset ends its session");
+    assert_ne!(sessions[0].span_context.trace_id(), sessions[1].span_context.trace_id());
+    for session in sessions {
+        assert!(
+            session.attributes.iter().any(|kv| kv.key.as_str() == "session.end_reason" && kv.value.as_str() == "kiosk.idle"),
+            "{:?}",
+            session.attributes
+        );
+    }
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.kiosk.resets"), "metrics: {names:?}");
+}
+
+/// Isabella's position, where a click on her lands.
+fn isabella_position(game: &mut TestGame) -> bevy::math::Vec2 {
+    use bevy::prelude::*;
+    let world = game.app_mut().world_mut();
+    let mut npcs = world.query::<(&Transform, &sregame::npc::Npc)>();
+    let (transform, _) = npcs.iter(world).find(|(_, npc)| npc.name == "Isabella").expect("Isabella");
+    transform.translation.truncate()
+}
+
+/// The player on tile (1, 3), two tiles from Isabella each way: out of
+/// talk range, with a way round the wall block to her.
+fn step_back_from_isabella(game: &mut TestGame) {
+    use bevy::prelude::*;
+    let world = game.app_mut().world_mut();
+    let mut player = world.query_filtered::<&mut Transform, With<sregame::player::Player>>();
+    let mut transform = player.single_mut(world).expect("player");
+    transform.translation.x = -96.0;
+    transform.translation.y = -32.0;
+    game.step(2);
+}
+
+#[test]
+fn clicking_an_npc_in_range_talks_to_them_with_the_mouse() {
+    use sregame::click_to_talk::WorldClick;
+
+    let mut game = base_game();
+    let position = isabella_position(&mut game);
+    game.app_mut().world_mut().write_message(WorldClick { position });
+    game.step(3);
+
+    assert_eq!(game.active_dialogue().map(|segment| segment.speaker), Some("Isabella".to_string()));
+    let spans = game.drain_spans();
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction");
+    assert_eq!(span_attribute(interaction, "input.source").map(|value| value.to_string()), Some("mouse".to_string()));
+    assert_eq!(
+        span_attribute(interaction, "interaction.selected_by").map(|value| value.to_string()),
+        Some("click".to_string())
+    );
+}
+
+#[test]
+fn clicking_an_npc_out_of_range_walks_over_only_when_set_to() {
+    use sregame::click_to_talk::WorldClick;
+    use sregame::settings::GameSettings;
+
+    let mut game = base_game();
+    let position = isabella_position(&mut game);
+    step_back_from_isabella(&mut game);
+    let start = game.player_pos().expect("player");
+
+    // Off by default: the click names her and that's all.
+    game.app_mut().world_mut().write_message(WorldClick { position });
+    game.step(60);
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert_eq!(game.player_pos(), Some(start));
+
+    game.app_mut().world_mut().resource_mut::<GameSettings>().click_to_walk = true;
+    game.app_mut().world_mut().write_message(WorldClick { position });
+    for _ in 0..180 {
+        game.step(1);
+        if game.current_state().mode == Some(Mode::Dialogue) {
+            break;
+        }
+    }
+    assert_eq!(game.active_dialogue().map(|segment| segment.speaker), Some("Isabella".to_string()));
+    let spans = game.drain_spans();
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction");
+    assert_eq!(span_attribute(interaction, "input.source").map(|value| value.to_string()), Some("mouse".to_string()));
+    assert!(game.app_mut().world().get_resource::<sregame::click_to_talk::ClickWalk>().is_none());
+}
+
+#[test]
+fn a_map_thumbnail_is_kept_until_its_map_changes() {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::{Assets, Image};
+    use sregame::map_data::MapDirectory;
+    use sregame::thumbnails::{MapThumbnail, ThumbnailCache};
+
+    /// Ask for the fixture town's thumbnail and wait for it to be drawn;
+    /// the names of the files in the cache after.
+    fn thumbnail(game: &mut TestGame, cache: &std::path::Path) -> Vec<String> {
+        let world = game.app_mut().world_mut();
+        let handle = world.run_system_once(|mut thumbnails: MapThumbnail| thumbnails.get_or_generate(Scene::TownOfEndgame)).unwrap();
+        let placeholder = world.resource::<Assets<Image>>().get(&handle).and_then(|image| image.data.clone());
+        for _ in 0..300 {
+            game.step(1);
+            let drawn = game.app_mut().world().resource::<Assets<Image>>().get(&handle).and_then(|image| image.data.clone());
+            if drawn != placeholder {
+                let mut files: Vec<String> = std::fs::read_dir(cache)
+                    .unwrap()
+                    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                    .collect();
+                files.sort();
+                return files;
+            }
+        }
+        panic!("the thumbnail was never drawn");
+    }
+
+    let dir = sregame::testing::scratch_dir("thumbnails");
+    let (maps, cache) = (dir.join("maps"), dir.join("cache"));
+    std::fs::create_dir_all(&maps).unwrap();
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/maps/town_of_endgame.json");
+    std::fs::copy(fixture, maps.join("town_of_endgame.json")).unwrap();
+
+    let mut game = base_game();
+    game.app_mut().insert_resource(MapDirectory(maps.clone())).insert_resource(ThumbnailCache(cache.clone()));
+    let first = thumbnail(&mut game, &cache);
+    assert_eq!(first.len(), 1, "{first:?}");
+    assert!(first[0].starts_with("town_of_endgame-") && first[0].ends_with(".rgba"), "{first:?}");
+
+    // Unchanged, a fresh game finds it where it was.
+    let mut game = base_game();
+    game.app_mut().insert_resource(MapDirectory(maps.clone())).insert_resource(ThumbnailCache(cache.clone()));
+    assert_eq!(thumbnail(&mut game, &cache), first);
+
+    // One tile different: drawn again, under a new hash, and the old one dropped.
+    let mut map: serde_json::Value = serde_json::from_slice(&std::fs::read(fixture).unwrap()).unwrap();
+    map["tiles"][0] = 1.into();
+    std::fs::write(maps.join("town_of_endgame.json"), serde_json::to_vec(&map).unwrap()).unwrap();
+    let mut game = base_game();
+    game.app_mut().insert_resource(MapDirectory(maps)).insert_resource(ThumbnailCache(cache.clone()));
+    let edited = thumbnail(&mut game, &cache);
+    assert_eq!(edited.len(), 1, "{edited:?}");
+    assert_ne!(edited, first);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn the_session_summary_counts_each_npcs_conversations() {
+    use sregame::session_summary::SessionSummary;
+
+    let mut game = base_game();
+    for _ in 0..2 {
+        game.interact();
+        game.read_dialogue_to_end(20);
+        game.step(2);
+    }
+    // The third time, walked away from before the first box is out.
+    game.interact();
+    game.press(GameAction::Cancel);
+    game.step(2);
+    game.release(GameAction::Cancel);
+    game.step(1);
+
+    let summary = game.app_mut().world().resource::<SessionSummary>();
+    let isabella = summary.npc("isabella").expect("Isabella's row");
+    assert_eq!(isabella.name, "Isabella");
+    assert_eq!(isabella.interactions, 3);
+    assert_eq!((isabella.lines_read, isabella.lines_available), (4, 6));
+    assert_eq!((isabella.completed, isabella.abandoned), (2, 1));
+    assert!(isabella.conversation_time > std::time::Duration::ZERO);
+    let json = summary.to_json();
+    assert_eq!(json["npcs"]["isabella"]["dialogues_abandoned"], 1, "{json}");
+}
+
+#[test]
+fn a_long_url_fits_the_dialogue_box() {
+    use bevy::text::FontSize;
+    use sregame::dialogue::{BOX_ROW_CHARS, DialogueRequest, DialogueSettings, LongWords};
+
+    let url = format!("https://sre.example.com/{}", "a".repeat(96));
+    assert_eq!(url.len(), 120);
+    for long_words in [LongWords::Break, LongWords::Shrink] {
+        let mut game = base_game();
+        game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, long_words, ..Default::default() });
+        game.step(2);
+        game.app_mut().world_mut().write_message(DialogueRequest::from(("Narrator", vec![format!("Start at {url}")])));
+        game.step(3);
+        let shown = game.dialogue_text().expect("the box is up");
+        let Some(FontSize::Vh(vh)) = game.dialogue_font_size() else { panic!("text sized to the window") };
+
+        // The harness has no UI layout pass, so measure in characters: the
+        // column holds `BOX_ROW_CHARS` of the box's usual 46px-at-1080p
+        // text, and more of smaller text. Sizes follow the window height,
+        // so this holds at any window size.
+        let scale = vh / (46.0 / 10.8);
+        let widest = shown.split('\n').map(|row| row.chars().count()).max().unwrap_or(0);
+        assert!(widest < url.len(), "{long_words:?}: the URL is broken up: {shown:?}");
+        assert!(widest as f32 * scale <= BOX_ROW_CHARS as f32 + 1e-3, "{long_words:?} at {scale}: {shown:?}");
+    }
+}
+
+#[test]
+fn reading_a_conversation_to_the_end_meets_the_npc_and_escape_does_not() {
+    use sregame::npc::NpcsMet;
+
+    let mut game = base_game();
+    game.interact();
+    game.press(GameAction::Cancel);
+    game.step(2);
+    game.release(GameAction::Cancel);
+    game.step(1);
+    assert_eq!(game.app_mut().world().resource::<NpcsMet>().count(), 0, "walked away");
+
+    game.drain_spans();
+    for _ in 0..2 {
+        game.interact();
+        game.read_dialogue_to_end(20);
+        game.step(2);
+    }
+
+    let met = game.app_mut().world().resource::<NpcsMet>();
+    assert!(met.contains("Isabella"));
+    assert_eq!(met.count(), 1);
+    let names = metric_names(&mut game);
+    assert!(names.iter().any(|name| name == "game.npcs_met"), "metrics: {names:?}");
+    let first_time: Vec<_> = game
+        .drain_spans()
+        .iter()
+        .filter(|span| span.name == "dialogue.session")
+        .map(|span| span_attribute(span, "dialogue.first_time"))
+        .collect();
+    assert_eq!(first_time, [Some(true.into()), Some(false.into())]);
+}
+
+#[test]
+fn saving_the_map_tunes_npcs_in_place_or_respawns_them() {
+    use bevy::prelude::{Entity, Transform};
+    use sregame::ambient::AmbientChatter;
+    use sregame::npc::{InRange, Npc, NpcFacing};
+    use sregame::testing::scratch_dir;
+
+    let dir = scratch_dir("live-tune");
+    let map_path = dir.join("maps/town_of_endgame.json");
+    std::fs::create_dir_all(dir.join("maps")).unwrap();
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/maps/town_of_endgame.json");
+    let mut map: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(fixture).unwrap()).unwrap();
+    std::fs::write(&map_path, map.to_string()).unwrap();
+    let mut game = TestGame::new(&dir);
+
+    fn isabella(game: &mut TestGame) -> (Entity, bool, bool, Transform) {
+        let world = game.app_mut().world_mut();
+        let mut npcs = world.query::<(Entity, &Npc, &Transform, Option<&AmbientChatter>)>();
+        let (entity, npc, transform, chatter) = npcs.iter(world).find(|(_, npc, ..)| npc.name == "Isabella").unwrap();
+        (entity, matches!(npc.sprite_facing, NpcFacing::Left), chatter.is_some(), *transform)
+    }
+    let (spawned, ..) = isabella(&mut game);
+
+    map["npcs"][0]["facing"] = "left".into();
+    map["npcs"][0]["ambient_lines"] = serde_json::json!(["Coffee?"]);
+    std::fs::write(&map_path, map.to_string()).unwrap();
+    game.step(40);
+    let (tuned, facing_left, chatters, at) = isabella(&mut game);
+    assert_eq!(tuned, spawned, "facing and ambient lines don't respawn");
+    assert!(facing_left && chatters);
+
+    fn in_range(game: &mut TestGame, entity: Entity) -> bool {
+        game.app_mut().world().get::<InRange>(entity).is_some()
+    }
+    assert!(in_range(&mut game, spawned), "the player starts within the default radius");
+    map["npcs"][0]["interaction_radius"] = 1.0.into();
+    std::fs::write(&map_path, map.to_string()).unwrap();
+    game.step(40);
+    let (narrowed, ..) = isabella(&mut game);
+    assert_eq!(narrowed, spawned, "a radius doesn't respawn");
+    assert!(!in_range(&mut game, spawned), "out of a one-pixel radius");
+
+    map["npcs"][0]["x"] = 2.into();
+    std::fs::write(&map_path, map.to_string()).unwrap();
+    game.step(40);
+    let (moved, facing_left, chatters, moved_to) = isabella(&mut game);
+    let _ = std::fs::remove_dir_all(dir);
+    assert_ne!(moved, spawned, "a move respawns");
+    assert!(facing_left && chatters, "from the new entry");
+    assert!(moved_to.translation.x < at.translation.x, "{moved_to:?}");
+}
+
+#[test]
+fn a_runbook_reads_like_a_faceless_npc() {
+    use sregame::map_objects::OBJECT_PANEL_COLOR;
+    use sregame::npc::{NpcsMet, TimesTalked};
+    use sregame::session_summary::SessionSummary;
+
+    let mut game = fixture_game("objects");
+    game.interact();
+
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+    let segment = game.active_dialogue().expect("dialogue box up");
+    assert_eq!(segment.speaker, "Runbook");
+    assert_eq!(segment.text, "Step one: don't panic.");
+    assert_eq!(game.portrait_path(), None);
+    assert_eq!(game.dialogue_panel_color(), Some(OBJECT_PANEL_COLOR));
+    let spans = game.drain_spans();
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction span");
+    assert_eq!(span_attribute(interaction, "interaction.kind"), Some("object".into()));
+
+    game.read_dialogue_to_end(20);
+    game.step(2);
+    let world = game.app_mut().world();
+    assert_eq!(world.resource::<NpcsMet>().count(), 0, "nobody to meet");
+    assert_eq!(world.resource::<TimesTalked>().npcs(), 0, "nobody talked to");
+    assert!(world.resource::<SessionSummary>().npcs().is_empty(), "no NpcInteracted: nobody to summarize");
+
+    // People keep the usual box.
+    let mut game = base_game();
+    game.press(GameAction::Interact);
+    game.step(3);
+    assert_ne!(game.dialogue_panel_color(), Some(OBJECT_PANEL_COLOR));
+    let spans = game.drain_spans();
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("npc.interaction span");
+    assert_eq!(span_attribute(interaction, "interaction.kind"), Some("npc".into()));
+}
+
+#[test]
+fn a_conversation_asked_for_mid_talk_is_turned_away_or_queued_after_it() {
+    use sregame::dialogue::{DialogueRequest, DialogueSettings, QueuedDialogues, WhileTalking};
+
+    fn ask(game: &mut TestGame, speaker: &str) {
+        game.app_mut().world_mut().write_message(DialogueRequest::from((speaker, vec![format!("{speaker} here.")])));
+    }
+    /// Every conversation opened from here, read to the end, by speaker.
+    fn read_everything(game: &mut TestGame) -> Vec<String> {
+        for _ in 0..40 {
+            game.tap(GameAction::Advance);
+        }
+        let spans = game.drain_spans();
+        let sessions: Vec<_> = spans.iter().filter(|span| span.name == "dialogue.session").collect();
+        for session in &sessions {
+            assert_eq!(span_attribute(session, "dialogue.outcome"), Some("completed".into()), "ended once, read through");
+        }
+        sessions.iter().filter_map(|span| span_attribute(span, "dialogue.speaker")).map(|speaker| speaker.to_string()).collect()
+    }
+
+    for (while_talking, read) in [
+        (WhileTalking::Ignore, vec!["Alice"]),
+        (WhileTalking::QueueAfter, vec!["Alice", "Bob", "Carol"]),
+    ] {
+        let mut game = base_game();
+        game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, while_talking, ..Default::default() });
+        game.step(2);
+        game.drain_spans();
+
+        // Two on one frame, and a third mid-conversation.
+        ask(&mut game, "Alice");
+        ask(&mut game, "Bob");
+        game.step(2);
+        ask(&mut game, "Carol");
+        game.step(2);
+        assert_eq!(game.active_dialogue().expect("box up").speaker, "Alice");
+        let queued = game.app_mut().world().resource::<QueuedDialogues>().len();
+        assert_eq!(queued, read.len() - 1, "{while_talking:?}");
+
+        assert_eq!(read_everything(&mut game), read, "{while_talking:?}");
+        assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+        assert!(game.app_mut().world().resource::<QueuedDialogues>().is_empty());
+    }
+}
+
+#[test]
+fn escape_on_the_frame_the_last_line_is_read_past_is_too_late() {
+    use bevy::prelude::Messages;
+    use sregame::dialogue::DialogueSettings;
+    use sregame::hooks::{DialogueEndOutcome, DialogueEnded};
+    use sregame::npc::NpcsMet;
+
+    let mut game = base_game();
+    game.app_mut().world_mut().insert_resource(DialogueSettings { chars_per_second: 0.0, ..Default::default() });
+    game.interact();
+    game.tap(GameAction::Advance);
+    assert_eq!(game.active_dialogue().expect("on the last line").text, "Mind the wall.");
+    game.drain_spans();
+
+    game.press(GameAction::Advance);
+    game.press(GameAction::Cancel);
+    game.step(1);
+    let ended: Vec<_> = game.app_mut().world().resource::<Messages<DialogueEnded>>().iter_current_update_messages().cloned().collect();
+    assert_eq!(ended.len(), 1, "{ended:?}");
+    assert_eq!(ended[0].outcome, DialogueEndOutcome::Completed);
+    game.release(GameAction::Advance);
+    game.release(GameAction::Cancel);
+    game.step(2);
+
+    assert_eq!(game.current_state().mode, Some(Mode::Exploring));
+    assert!(game.app_mut().world().resource::<NpcsMet>().contains("Isabella"));
+    let spans = game.drain_spans();
+    let sessions: Vec<_> = spans.iter().filter(|span| span.name == "dialogue.session").collect();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(span_attribute(sessions[0], "dialogue.outcome"), Some("completed".into()));
+    assert!(!sessions[0].events.iter().any(|event| event.name == "dialogue.forced_exit"));
+}
+
+#[test]
+fn the_npc_talked_to_is_known_by_name_whatever_its_lines_are_signed() {
+    use sregame::npc::{CurrentInteractionTarget, Npc};
+
+    let mut game = fixture_game("masked_speaker");
+    game.interact();
+    assert_eq!(game.current_state().mode, Some(Mode::Dialogue));
+
+    let world = game.app_mut().world_mut();
+    let monster = world.query_filtered::<bevy::prelude::Entity, bevy::prelude::With<Npc>>().single(world).unwrap();
+    let target = world.get_resource::<CurrentInteractionTarget>().cloned().expect("a target");
+    assert_eq!((target.npc, target.id.as_str(), target.name.as_str()), (monster, "monster", "Mysterious Monster"));
+
+    game.read_dialogue_to_end(20);
+    game.step(2);
+    assert!(game.app_mut().world().get_resource::<CurrentInteractionTarget>().is_none(), "gone with the conversation");
+    let spans = game.drain_spans();
+    let interaction = spans.iter().find(|span| span.name == "npc.interaction").expect("an npc.interaction span");
+    assert_eq!(span_attribute(interaction, "npc.name").map(|name| name.to_string()).as_deref(), Some("Mysterious Monster"));
+}
+
+#[test]
+fn time_away_from_the_window_is_paused_and_marked_on_the_session() {
+    use bevy::prelude::{Entity, Messages, Time, Virtual};
+    use bevy::window::WindowFocused;
+    use sregame::focus::WindowFocus;
+    use sregame::instrumentation::PlayerSessionTrace;
+
+    fn focus(game: &mut TestGame, focused: bool) {
+        game.app_mut().world_mut().resource_mut::<Messages<WindowFocused>>().write(WindowFocused { window: Entity::PLACEHOLDER, focused });
+        game.step(1);
+    }
+
+    let mut game = base_game();
+    game.drain_spans();
+    focus(&mut game, false);
+    assert!(game.app_mut().world().resource::<Time<Virtual>>().is_paused(), "the game clock stops");
+    assert!(!game.app_mut().world().resource::<WindowFocus>().is_focused());
+    game.step(30);
+    focus(&mut game, true);
+    assert!(!game.app_mut().world().resource::<Time<Virtual>>().is_paused());
+
+    let world = game.app_mut().world_mut();
+    let now = world.resource::<Time<bevy::prelude::Real>>().elapsed();
+    let away = world.resource::<WindowFocus>().unfocused_time(now);
+    assert_eq!(world.resource::<WindowFocus>().gaps(), 1);
+    assert!(away >= std::time::Duration::from_millis(400), "31 frames away: {away:?}");
+
+    world.query::<&mut PlayerSessionTrace>().single_mut(world).unwrap().span.end();
+    let spans = game.drain_spans();
+    let session = spans.iter().find(|span| span.name == "game_session").expect("the session span");
+    let events: Vec<&str> = session.events.iter().map(|event| event.name.as_ref()).filter(|name| name.starts_with("game.focus")).collect();
+    assert_eq!(events, ["game.focus_lost", "game.focus_gained"]);
+    let gained = session.events.iter().find(|event| event.name == "game.focus_gained").unwrap();
+    let gap_ms = gained.attributes.iter().find(|kv| kv.key.as_str() == "focus.gap_ms").map(|kv| kv.value.to_string());
+    assert_eq!(gap_ms, Some(away.as_millis().to_string()));
+    assert_eq!(
+        span_attribute(session, "session.unfocused_ms").map(|value| value.to_string()),
+        Some(away.as_millis().to_string())
+    );
+    assert_eq!(span_attribute(session, "session.unfocused_count").map(|value| value.to_string()), Some("1".to_string()));
+}
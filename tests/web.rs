@@ -0,0 +1,37 @@
+//! The headless logic run for real on wasm32, under wasm-bindgen-test-runner
+//! (scripts/check-wasm.sh): what the browser build shares with native has
+//! to behave the same there, not just compile. Native runs skip this file;
+//! the unit tests cover the same code there.
+#![cfg(target_arch = "wasm32")]
+
+use bevy::math::Vec2;
+use sregame::asset_manifest::map_names;
+use sregame::dialogue::paginate;
+use sregame::map_data::{MapData, tile_to_world, world_to_tile};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn every_embedded_map_parses() {
+    let mut names = map_names().peekable();
+    assert!(names.peek().is_some(), "the manifest embeds the maps");
+    for name in names {
+        let map = MapData::load(name).unwrap_or_else(|e| panic!("{name}: {e:#}"));
+        assert!(map.width > 0 && map.height > 0, "{name}");
+    }
+}
+
+#[wasm_bindgen_test]
+fn tiles_and_world_positions_round_trip() {
+    for (x, y) in [(0, 0), (3, 1), (38, 38)] {
+        let world = tile_to_world(x, y, 39, 39);
+        assert_eq!(world_to_tile(world, 39, 39), (x as i32, y as i32));
+    }
+    assert!(world_to_tile(Vec2::new(-10_000.0, 0.0), 39, 39).0 < 0, "off the map stays off it");
+}
+
+#[wasm_bindgen_test]
+fn long_lines_page_the_same_as_on_native() {
+    let text = "The pager went off at three in the morning, and nobody knew who owned the service.";
+    let pages: Vec<String> = paginate(text, 24, 2).into_iter().map(|page| page.text).collect();
+    assert_eq!(pages, ["The pager went off at\nthree in the morning,", "and nobody knew who\nowned the service."]);
+}
@@ -14,6 +14,7 @@ build time; the Rust/Bevy side only ever consumes plain atlas indices.
 """
 
 import json
+import re
 from collections import Counter
 from pathlib import Path
 
@@ -84,6 +85,12 @@ def convert_direction(rpgmaker_dir):
     }.get(rpgmaker_dir, "down")
 
 
+def content_id(name):
+    """`name` as the game makes a content id (src/content/mod.rs's `slug`):
+    lowercase ASCII letters and digits, anything else one `_`."""
+    return re.sub(r'[^a-z0-9]+', '_', name.lower()).strip('_')
+
+
 def clean_dialogue_text(text):
     """Clean up RPGMaker dialogue formatting"""
     text = text.replace('<WordWrap>', '')
@@ -271,6 +278,8 @@ EVENT_OVERRIDES = {
         "synthetic_speaker": "doggo",
         "wander": True,
         "through": True,
+        # Speech bubbles while the player is near (src/ambient.rs).
+        "ambient_lines": ["wan!", "*sniff sniff*", "*tail wag*"],
     },
 }
 
@@ -309,6 +318,9 @@ def extract_npcs(rpg_data, source_filename=""):
             speaker = override['synthetic_speaker']
 
         npcs.append({
+            # Stable ids, so renaming a character later doesn't reset what
+            # saves and telemetry know about them.
+            "id": content_id(event['name']),
             "name": event['name'],
             "x": event['x'],
             "y": event['y'],
@@ -329,12 +341,17 @@ def extract_npcs(rpg_data, source_filename=""):
             "wander": override.get('wander', False),
             "through": override.get('through', page['through']),
             "dialogue": {
+                "id": content_id(speaker),
                 "speaker": speaker,
                 "portrait": portrait,
                 "face_index": face_index,
                 "lines": lines
             }
         })
+        # Ambient chatter has no source-data equivalent; only overrides
+        # grant it, and NPCs without it keep the key out of the JSON.
+        if 'ambient_lines' in override:
+            npcs[-1]["ambient_lines"] = override['ambient_lines']
     return npcs
 
 
@@ -890,6 +907,14 @@ def load_tilesets_json():
         return json.load(f)
 
 
+def is_indoor(tileset_entry):
+    """Interior maps get no drop shadows in the game (see src/shadow.rs).
+    Every exterior map uses the Outside_B sheet - the same test
+    repair_buried_signs uses to spot the town - so anything else is
+    indoors."""
+    return tileset_entry['tilesetNames'][SET_NUMBER_B] != OUTSIDE_B_SHEET_NAME
+
+
 def convert_map(rpgmaker_map_path, output_path, tileset_entry, compositor):
     """Convert a single RPGMaker map to clean format, baking its tiles into
     the given (possibly shared) compositor. Does NOT write the atlas image -
@@ -921,6 +946,7 @@ def convert_map(rpgmaker_map_path, output_path, tileset_entry, compositor):
         "name": rpg_data['displayName'],
         "width": width,
         "height": height,
+        "indoor": is_indoor(tileset_entry),
         "tiles": tiles,
         "upper_tiles": upper_tiles,
         "collision": collision,